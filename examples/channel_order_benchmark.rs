@@ -0,0 +1,97 @@
+//! # Processing-Order Benchmark
+//!
+//! Time how long the engine takes per buffer at a 2-second delay while
+//! a parameter is being automated, walking each block channel by
+//! channel (the default) and sample by sample, and print the
+//! difference:
+//!
+//! ```text
+//! cargo run --release --example channel_order_benchmark
+//! ```
+//!
+//! Automation keeps the engine on its per-sample path, which is the one
+//! the processing order applies to (see "Processing Order" in the
+//! engine's docs). At 2 seconds each delay line is 384 KB, so a stereo
+//! pair outgrows most L2 caches, and how the block walks between them
+//! starts to show. The feedback cutoff is swept up and down one new
+//! value per buffer, as in `process_benchmark`, and the two orders'
+//! outputs are checked against each other as they go.
+//!
+//! The two engines take turns, buffer by buffer, so whatever else the
+//! machine is doing lands on both alike. Run it in release mode: a
+//! debug build times the bounds checks, not the memory traffic.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use loveless_delay_v1::dsp::noise::NoiseGenerator;
+use loveless_delay_v1::{DelayParams, LovelessDelayEngine, ProcessingOrder, MAX_DELAY_MS};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const BUFFER_SIZE: usize = 512;
+
+/// A minute of audio.
+const BUFFERS: usize = 60 * SAMPLE_RATE as usize / BUFFER_SIZE;
+
+fn main() {
+    let mut noise = NoiseGenerator::new(1);
+    let input: Vec<f32> = (0..BUFFER_SIZE * 2).map(|_| 0.5 * noise.next()).collect();
+    let (input_left, input_right) = input.split_at(BUFFER_SIZE);
+
+    let mut sample_major = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    sample_major.set_processing_order(ProcessingOrder::SampleMajor);
+    let mut channel_major = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+
+    let mut outputs = [0, 1].map(|_| [0, 1].map(|_| vec![0.0; BUFFER_SIZE]));
+    let mut totals = [Duration::ZERO; 2];
+    for buffer in 0..BUFFERS {
+        // A sweep from 500 Hz to 8 kHz and back every two seconds.
+        let phase = (buffer * BUFFER_SIZE) as f32 / (2.0 * SAMPLE_RATE);
+        let sweep = 0.5 - 0.5 * (std::f32::consts::TAU * phase).cos();
+        let cutoff = 500.0 * 16.0_f32.powf(sweep);
+        let params = DelayParams {
+            delay_ms: MAX_DELAY_MS,
+            feedback: 0.7,
+            filter_cutoff: cutoff,
+            filter_cutoff_right: cutoff,
+            ..DelayParams::default()
+        };
+
+        for ((engine, [left, right]), total) in [&mut sample_major, &mut channel_major]
+            .into_iter()
+            .zip(&mut outputs)
+            .zip(&mut totals)
+        {
+            engine.set_params(&params);
+            left.clone_from_slice(input_left);
+            right.clone_from_slice(input_right);
+            let start = Instant::now();
+            engine.process(&mut [left, right]);
+            *total += start.elapsed();
+            black_box((&left, &right));
+        }
+        assert!(
+            outputs[0] == outputs[1],
+            "the two orders parted ways in buffer {buffer}"
+        );
+    }
+
+    let [sample_major, channel_major] = totals.map(|total| total / BUFFERS as u32);
+    let budget = Duration::from_secs_f64(BUFFER_SIZE as f64 / f64::from(SAMPLE_RATE));
+    println!(
+        "{BUFFER_SIZE}-sample stereo buffers at {SAMPLE_RATE} Hz, {MAX_DELAY_MS} ms delay, \
+         cutoff automated"
+    );
+    println!("(real time allows {:.1} µs per buffer)", micros(budget));
+    println!();
+    println!("  sample by sample:    {:8.2} µs", micros(sample_major));
+    println!("  channel by channel:  {:8.2} µs", micros(channel_major));
+    println!();
+    let saved = 1.0 - channel_major.as_secs_f64() / sample_major.as_secs_f64();
+    println!("  per-buffer time cut by {:.0}%", 100.0 * saved);
+    println!("  (outputs identical in all {BUFFERS} buffers)");
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
@@ -0,0 +1,99 @@
+//! # Delay Line Benchmark
+//!
+//! Time the per-sample feedback step on a bare [`DelayLine`] — read,
+//! filter, write, advance — with the plain write and with
+//! `write_clamped()`, and a whole-sample delay read with `read()` and
+//! with `read_int()`, and print what each costs:
+//!
+//! ```text
+//! cargo run --release --example delay_line_benchmark
+//! ```
+//!
+//! Timings vary from run to run and machine to machine, so this prints
+//! them rather than checking them. Run it in release mode: a debug build
+//! times the bounds checks, not the write paths.
+
+use std::hint::black_box;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use loveless_delay_v1::dsp::delay_line::DelayLine;
+use loveless_delay_v1::dsp::filter::OnePoleFilter;
+
+const SAMPLE_RATE: f32 = 44100.0;
+
+/// Samples per run: a little under four minutes of audio.
+const SAMPLES: usize = 10_000_000;
+
+fn main() {
+    let mut line: DelayLine = DelayLine::new(NonZeroUsize::new(SAMPLE_RATE as usize).unwrap());
+    let mut filter: OnePoleFilter = OnePoleFilter::new();
+    filter.set_cutoff(8000.0, SAMPLE_RATE);
+
+    let mut run = |clamped: bool| {
+        let start = Instant::now();
+        for i in 0..SAMPLES {
+            let input = black_box(i as f32 * 1e-7);
+            let delayed = line.read(black_box(22_050.5));
+            let sample = input + filter.process(delayed) * 0.5;
+            if clamped {
+                line.write_clamped(sample, 4.0);
+            } else {
+                line.write(sample);
+            }
+            line.advance();
+        }
+        start.elapsed()
+    };
+
+    // Warm the caches before measuring.
+    run(false);
+    let raw = run(false);
+    let clamped = run(true);
+
+    println!("{SAMPLES} feedback steps on a 1-second line at {SAMPLE_RATE} Hz");
+    println!();
+    println!("  write:          {:8.2} ns per sample", nanos(raw));
+    println!("  write_clamped:  {:8.2} ns per sample", nanos(clamped));
+    println!();
+    let ratio = clamped.as_secs_f64() / raw.as_secs_f64();
+    println!("  the clamp costs {:.1}%", 100.0 * (ratio - 1.0));
+    println!();
+
+    // A full line, read at a whole number of samples: the case
+    // `read_int()` is for.
+    let mut line: DelayLine = DelayLine::new(NonZeroUsize::new(96_000).unwrap());
+    for i in 0..96_000 {
+        line.write(i as f32 * 1e-5);
+        line.advance();
+    }
+    let mut run = |int: bool| {
+        let start = Instant::now();
+        let mut acc = 0.0;
+        for _ in 0..SAMPLES {
+            acc += if int {
+                line.read_int(black_box(22_050))
+            } else {
+                line.read(black_box(22_050.0))
+            };
+            line.advance();
+        }
+        black_box(acc);
+        start.elapsed()
+    };
+    run(false);
+    let interp = run(false);
+    let int = run(true);
+
+    println!("{SAMPLES} reads of a 22050-sample delay");
+    println!();
+    println!("  read:           {:8.2} ns per sample", nanos(interp));
+    println!("  read_int:       {:8.2} ns per sample", nanos(int));
+    println!();
+    let saved = 1.0 - int.as_secs_f64() / interp.as_secs_f64();
+    println!("  read_int saves {:.0}%", 100.0 * saved);
+}
+
+fn nanos(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e9 / SAMPLES as f64
+}
@@ -0,0 +1,83 @@
+//! # Impulse Response
+//!
+//! Feed a single click (an *impulse*) through the delay and print every
+//! echo that comes out. The impulse response is the fingerprint of any
+//! linear effect: it shows exactly when each repeat arrives and how loud
+//! it is.
+//!
+//! ```text
+//! cargo run --example impulse_response -- [delay_ms] [feedback] [filter_hz]
+//! cargo run --example impulse_response -- 250 0.6 2000
+//! ```
+//!
+//! With feedback `f`, the Nth echo should be about `f^(N-1)` times as
+//! loud as the first — a straight line on the dB scale. A low filter
+//! cutoff makes each repeat lose a little more of the click's sharp
+//! peak, so the printed levels fall faster than `f` alone predicts.
+
+use loveless_delay_v1::{DelayParams, LovelessDelayEngine};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// How many seconds of response to render.
+const SECONDS: usize = 10;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut arg = |default: f32| {
+        args.next()
+            .map(|a| a.parse().expect("arguments must be numbers"))
+            .unwrap_or(default)
+    };
+
+    // Fully wet, so the output *is* the echo pattern with no dry click.
+    let params = DelayParams {
+        delay_ms: arg(250.0),
+        feedback: arg(0.6),
+        filter_cutoff: arg(20000.0),
+        mix: 1.0,
+        ..DelayParams::default()
+    };
+
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+    engine.set_params(&params);
+
+    let mut samples = vec![0.0; SAMPLE_RATE as usize * SECONDS];
+    samples[0] = 1.0;
+    engine.process(&mut [&mut samples]);
+
+    println!(
+        "Impulse response: {} ms delay, {:.0}% feedback, {} Hz filter",
+        params.delay_ms,
+        params.feedback * 100.0,
+        params.filter_cutoff
+    );
+    println!();
+    println!(
+        "{:>5}  {:>9}  {:>9}  level",
+        "echo", "time (ms)", "peak (dB)"
+    );
+
+    // Each echo is smeared over a few samples by the filter, so look for
+    // the loudest sample in a window around each expected arrival.
+    let delay_samples = (params.delay_ms * SAMPLE_RATE / 1000.0) as usize;
+    for (echo, window) in samples.chunks(delay_samples).enumerate().skip(1) {
+        let (offset, peak) = window
+            .iter()
+            .map(|s| s.abs())
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or_default();
+
+        let peak_db = 20.0 * peak.log10();
+        if peak_db < -60.0 {
+            println!("(remaining echoes are below -60 dB)");
+            break;
+        }
+
+        let time_ms = (echo * delay_samples + offset) as f32 * 1000.0 / SAMPLE_RATE;
+        // One '#' per 2 dB above -60 dB.
+        let bar = "#".repeat(((peak_db + 60.0) / 2.0) as usize);
+        println!("{echo:>5}  {time_ms:>9.1}  {peak_db:>9.1}  {bar}");
+    }
+}
@@ -0,0 +1,153 @@
+//! # Null-Test Render
+//!
+//! Render the standard test program through the headless engine and
+//! save every sample, so two builds can be compared sample for sample:
+//!
+//! ```text
+//! cargo run --release --example null_render -- <output dir>
+//! ```
+//!
+//! This is the render half of `xtask nulltest`, which runs it once on
+//! the working tree and once on a baseline git ref, then subtracts the
+//! two (see `xtask/src/nulltest.rs`). If a refactor leaves the sound
+//! alone, the difference is silence: the two *null*.
+//!
+//! ## The Program
+//!
+//! Three test signals, each through three factory presets, with a
+//! fresh engine every time:
+//!
+//! - **impulse** — one full-scale sample, then the echoes.
+//! - **noise** — half a second of white noise, then its tail.
+//! - **sweep** — an exponential sine sweep from 20 Hz to 20 kHz.
+//!
+//! ```text
+//! Quarter Echo   plain echoes through the default filter
+//! Dub Tape       a dark filter and drive in the loop
+//! Ping-Pong      stereo motion, the two sides coupled
+//! ```
+//!
+//! Each render is stereo, written as interleaved little-endian `f32`
+//! to `<preset>-<signal>.f32` in the output directory.
+//!
+//! ## Rendering an Older Build
+//!
+//! The xtask copies this file into the baseline's checkout, so both
+//! sides render exactly the same program. That's why it sticks to the
+//! engine calls and factory presets that have been there longest — a
+//! baseline that can build it can be null-tested.
+
+use std::fs;
+use std::path::Path;
+
+use loveless_delay_v1::presets::factory_presets;
+use loveless_delay_v1::LovelessDelayEngine;
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Hosts hand plugins audio in small blocks; we do the same.
+const BLOCK_SIZE: usize = 512;
+
+/// The presets the program runs through.
+const PRESETS: [&str; 3] = ["Quarter Echo", "Dub Tape", "Ping-Pong"];
+
+/// How long each render runs, in seconds: long enough for the echoes
+/// of the slowest preset to pile up and start dying away.
+const SECONDS: f32 = 4.0;
+
+fn main() {
+    let Some(out_dir) = std::env::args().nth(1) else {
+        eprintln!("usage: null_render <output dir>");
+        std::process::exit(2);
+    };
+    let out_dir = Path::new(&out_dir);
+    fs::create_dir_all(out_dir).expect("can't create the output directory");
+
+    let presets = factory_presets();
+    for name in PRESETS {
+        let preset = presets
+            .iter()
+            .find(|preset| preset.name == name)
+            .unwrap_or_else(|| panic!("no factory preset called {name:?}"));
+        for (signal, input) in test_signals() {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&preset.params);
+
+            let (mut left, mut right) = (input.clone(), input);
+            for (left, right) in left
+                .chunks_mut(BLOCK_SIZE)
+                .zip(right.chunks_mut(BLOCK_SIZE))
+            {
+                engine.process(&mut [left, right]);
+            }
+
+            let bytes: Vec<u8> = left
+                .iter()
+                .zip(&right)
+                .flat_map(|(l, r)| [*l, *r])
+                .flat_map(f32::to_le_bytes)
+                .collect();
+            let file = format!("{}-{signal}.f32", slug(name));
+            fs::write(out_dir.join(&file), bytes).expect("can't write a render");
+            println!("rendered {file}");
+        }
+    }
+}
+
+/// The test signals, by name, each [`SECONDS`] long.
+fn test_signals() -> [(&'static str, Vec<f32>); 3] {
+    let len = (SAMPLE_RATE * SECONDS) as usize;
+
+    let mut impulse = vec![0.0; len];
+    impulse[0] = 1.0;
+
+    // A fixed-seed xorshift, so every build hears the same noise.
+    let mut state: u32 = 0x9E37_79B9;
+    let burst = SAMPLE_RATE as usize / 2;
+    let noise = (0..len)
+        .map(|n| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let white = state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            if n < burst {
+                0.5 * white
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    // Exponential sweep: equal time per octave, phase integrated in
+    // f64 so it's the same however long the sweep runs.
+    let (start_hz, end_hz) = (20.0f64, 20_000.0f64);
+    let sweep_len = 2.0 * f64::from(SAMPLE_RATE);
+    let rate = (end_hz / start_hz).ln();
+    let sweep = (0..len)
+        .map(|n| {
+            let t = n as f64 / sweep_len;
+            if t >= 1.0 {
+                return 0.0;
+            }
+            let phase = std::f64::consts::TAU * start_hz * sweep_len / f64::from(SAMPLE_RATE)
+                * ((t * rate).exp() - 1.0)
+                / rate;
+            (0.5 * phase.sin()) as f32
+        })
+        .collect();
+
+    [("impulse", impulse), ("noise", noise), ("sweep", sweep)]
+}
+
+/// A preset name as a file name: "Quarter Echo" → "quarter-echo".
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,89 @@
+//! # Control-Rate Benchmark
+//!
+//! Time how long the engine takes per buffer while a parameter is being
+//! automated, with filter updates on control ticks (the default) and on
+//! every sample, and print the difference:
+//!
+//! ```text
+//! cargo run --release --example process_benchmark
+//! ```
+//!
+//! Automation keeps the engine on its per-sample path, which is where
+//! the control tick earns its keep (see "Control Rate" in the engine's
+//! docs). The settings are the defaults, with the feedback cutoff swept
+//! up and down the way a drawn-in automation lane would, one new value
+//! per buffer.
+//!
+//! The two engines take turns, buffer by buffer, so whatever else the
+//! machine is doing — and however its clock speed wanders — lands on
+//! both alike. Run it in release mode: a debug build times the bounds
+//! checks and the allocation checks, not the DSP.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use loveless_delay_v1::dsp::noise::NoiseGenerator;
+use loveless_delay_v1::{DelayParams, LovelessDelayEngine};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const BUFFER_SIZE: usize = 512;
+
+/// A minute of audio.
+const BUFFERS: usize = 60 * SAMPLE_RATE as usize / BUFFER_SIZE;
+
+fn main() {
+    let mut noise = NoiseGenerator::new(1);
+    let input: Vec<f32> = (0..BUFFER_SIZE * 2).map(|_| 0.5 * noise.next()).collect();
+    let (input_left, input_right) = input.split_at(BUFFER_SIZE);
+
+    let mut per_sample = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    per_sample.set_control_interval(1);
+    let mut control_rate = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    let interval = control_rate.control_interval();
+
+    let mut left = vec![0.0; BUFFER_SIZE];
+    let mut right = vec![0.0; BUFFER_SIZE];
+    let mut totals = [Duration::ZERO; 2];
+    for buffer in 0..BUFFERS {
+        // A sweep from 500 Hz to 8 kHz and back every two seconds.
+        let phase = (buffer * BUFFER_SIZE) as f32 / (2.0 * SAMPLE_RATE);
+        let sweep = 0.5 - 0.5 * (std::f32::consts::TAU * phase).cos();
+        let cutoff = 500.0 * 16.0_f32.powf(sweep);
+        let params = DelayParams {
+            filter_cutoff: cutoff,
+            filter_cutoff_right: cutoff,
+            ..DelayParams::default()
+        };
+
+        for (engine, total) in [&mut per_sample, &mut control_rate]
+            .into_iter()
+            .zip(&mut totals)
+        {
+            engine.set_params(&params);
+            left.copy_from_slice(input_left);
+            right.copy_from_slice(input_right);
+            let start = Instant::now();
+            engine.process(&mut [&mut left, &mut right]);
+            *total += start.elapsed();
+            black_box((&left, &right));
+        }
+    }
+
+    let [per_sample, control_rate] = totals.map(|total| total / BUFFERS as u32);
+    let budget = Duration::from_secs_f64(BUFFER_SIZE as f64 / f64::from(SAMPLE_RATE));
+    println!("{BUFFER_SIZE}-sample stereo buffers at {SAMPLE_RATE} Hz, cutoff automated");
+    println!("(real time allows {:.1} µs per buffer)", micros(budget));
+    println!();
+    println!("  updates every sample:      {:8.2} µs", micros(per_sample));
+    println!(
+        "  updates every {interval:2} samples:  {:8.2} µs",
+        micros(control_rate)
+    );
+    println!();
+    let saved = 1.0 - control_rate.as_secs_f64() / per_sample.as_secs_f64();
+    println!("  per-buffer time cut by {:.0}%", 100.0 * saved);
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
@@ -0,0 +1,125 @@
+//! # Render a Delay Offline
+//!
+//! Use the headless engine the way a batch effects pipeline would:
+//! generate some audio, push it through [`LovelessDelayEngine`] in
+//! host-sized blocks, and inspect the result — no plugin host involved.
+//!
+//! ```text
+//! cargo run --features testing --example render_delay
+//! cargo run --features testing --example render_delay -- --ring-out
+//! cargo run --release --features testing --example render_delay -- --self-test
+//! ```
+//!
+//! The input is a short 440 Hz tone burst. The output is shown as a
+//! level meter over time: the burst itself, then its echoes dying away.
+//! Halfway through, the delay time is changed, the way automation would,
+//! to show that parameter changes glide rather than click.
+//!
+//! The render stops where the input does, three seconds in, echoes or
+//! not. With `--ring-out` it carries on through silence until the tail
+//! the plugin reports is over, or for 30 seconds at most (see
+//! `loveless_delay_v1::ring_out`).
+//!
+//! With `--self-test` it renders nothing, and instead runs the runtime
+//! checks of the compiled DSP (impulse timing, the filter's corner, the
+//! tail's decay, denormals and allocations), prints a pass/fail report
+//! and exits non-zero if any failed: a quick way to see that a change
+//! to the engine still behaves. See `loveless_delay_v1::testing::self_test`.
+
+use loveless_delay_v1::ring_out::ring_out;
+use loveless_delay_v1::testing::self_test;
+use loveless_delay_v1::{DelayParams, LovelessDelayEngine};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// Hosts hand plugins audio in small blocks; we do the same.
+const BLOCK_SIZE: usize = 512;
+
+/// Width of one meter row, in milliseconds.
+const METER_MS: usize = 50;
+
+// Counts allocations for the self-test. A debug build has nih-plug's
+// allocator instead (it checks `process()` itself), and there can only
+// be one.
+#[cfg(not(debug_assertions))]
+#[global_allocator]
+static ALLOCATOR: self_test::CountingAllocator = self_test::CountingAllocator;
+
+fn main() {
+    if std::env::args().skip(1).any(|arg| arg == "--self-test") {
+        let results = self_test::run();
+        print!("{}", self_test::report(&results));
+        std::process::exit(if self_test::passed(&results) { 0 } else { 1 });
+    }
+
+    let ringing_out = std::env::args().skip(1).any(|arg| arg == "--ring-out");
+    let total = SAMPLE_RATE as usize * 3;
+    let burst = SAMPLE_RATE as usize / 4;
+
+    // A 250ms, 440 Hz sine burst with a short fade in and out so the
+    // tone itself doesn't click, followed by silence.
+    let mut left: Vec<f32> = (0..total)
+        .map(|n| {
+            if n >= burst {
+                return 0.0;
+            }
+            let fade = (n.min(burst - n) as f32 / 240.0).min(1.0);
+            let phase = 2.0 * std::f32::consts::PI * 440.0 * n as f32 / SAMPLE_RATE;
+            0.5 * fade * phase.sin()
+        })
+        .collect();
+    let mut right = left.clone();
+
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    let mut params = DelayParams {
+        delay_ms: 400.0,
+        feedback: 0.5,
+        mix: 0.5,
+        filter_cutoff: 3000.0,
+        filter_cutoff_right: 3000.0,
+        limiter: true,
+        ..DelayParams::default()
+    };
+    engine.set_params(&params);
+
+    let mut block_start = 0;
+    for (left_block, right_block) in left
+        .chunks_mut(BLOCK_SIZE)
+        .zip(right.chunks_mut(BLOCK_SIZE))
+    {
+        // Halfway through, shorten the delay. The engine glides to the
+        // new time instead of jumping.
+        if block_start >= total / 2 && params.delay_ms != 300.0 {
+            params.delay_ms = 300.0;
+            engine.set_params(&params);
+        }
+
+        engine.process(&mut [left_block, right_block]);
+        block_start += BLOCK_SIZE;
+    }
+
+    if ringing_out {
+        let tail = ring_out(&mut engine, BLOCK_SIZE);
+        left.extend_from_slice(&tail[0]);
+        right.extend_from_slice(&tail[1]);
+    }
+
+    println!(
+        "Rendered {} samples per channel at {SAMPLE_RATE} Hz",
+        left.len()
+    );
+    println!(
+        "Tail still expected after the render: {:?}",
+        engine.tail_samples()
+    );
+    println!();
+    println!("{:>8}  {:>8}  level", "time", "peak dB");
+
+    let meter_len = SAMPLE_RATE as usize * METER_MS / 1000;
+    for (row, window) in left.chunks(meter_len).enumerate() {
+        let peak = window.iter().fold(0.0_f32, |p, s| p.max(s.abs()));
+        let peak_db = 20.0 * peak.max(1e-6).log10();
+        let bar = "#".repeat(((peak_db + 60.0).max(0.0) / 2.0) as usize);
+        println!("{:>6}ms  {peak_db:>8.1}  {bar}", row * METER_MS);
+    }
+}
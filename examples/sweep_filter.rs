@@ -0,0 +1,55 @@
+//! # Feedback Filter Sweep
+//!
+//! Measure the frequency response of the feedback lowpass filter and
+//! print it as CSV, ready for a spreadsheet or plotting tool:
+//!
+//! ```text
+//! cargo run --features testing --example sweep_filter -- [cutoff_hz] > response.csv
+//! ```
+//!
+//! Each row is one test frequency. The `repeat_N_db` columns show the
+//! response after the signal has gone around the feedback loop N times —
+//! i.e., through the filter N times. That's why the repeats of an analog
+//! style delay get darker and darker: a gentle 6 dB/octave slope becomes
+//! 12, 18, 24 dB/octave as the filters stack up.
+//!
+//! The measurement is done the same way you'd measure hardware: play a
+//! sine at each frequency, let the filter settle, and compare the output
+//! level to the input level. It's the measurement the self-test's filter
+//! check makes (see `loveless_delay_v1::testing::self_test`).
+
+use loveless_delay_v1::testing::self_test::filter_gain_db;
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// How many trips around the feedback loop to show.
+const REPEATS: i32 = 4;
+
+fn main() {
+    let cutoff: f32 = std::env::args()
+        .nth(1)
+        .map(|a| a.parse().expect("cutoff must be a number"))
+        .unwrap_or(2000.0);
+
+    print!("frequency_hz");
+    for n in 1..=REPEATS {
+        print!(",repeat_{n}_db");
+    }
+    println!();
+
+    // Log-spaced test frequencies, 1/6 octave apart, 20 Hz to 20 kHz.
+    let mut freq = 20.0_f32;
+    while freq <= 20000.0 {
+        let gain_db = filter_gain_db(cutoff, freq, SAMPLE_RATE);
+
+        // Passing through N identical filters multiplies their gains,
+        // which in decibels means adding them.
+        print!("{freq:.1}");
+        for n in 1..=REPEATS {
+            print!(",{:.2}", gain_db * n as f32);
+        }
+        println!();
+
+        freq *= 2.0_f32.powf(1.0 / 6.0);
+    }
+}
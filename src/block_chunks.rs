@@ -0,0 +1,177 @@
+//! # Blocks Longer Than the Host Promised
+//!
+//! `initialize()` hears the longest block the host will send
+//! (`BufferConfig::max_buffer_size`), and anything sized per block is
+//! sized from it. Hosts don't always keep to it: some change their
+//! buffer size between `process()` calls without initializing again,
+//! and the next block is longer than anything prepared for.
+//!
+//! So the plugin's limit is the declared maximum plus
+//! [`BLOCK_LEN_MARGIN`] ([`max_block_len()`]), and `process()` hands
+//! the engine its audio through [`for_each_chunk()`], which splits a
+//! block over the limit into chunks within it:
+//!
+//! ```text
+//! declared 512, limit 576     block of 1500
+//!                             ├── 576 ──┼── 576 ──┼─ 348 ─┤
+//! ```
+//!
+//! The engine sounds the same however a stream is chopped up (see
+//! `tests/buffer_size.rs`), so the chunks play exactly what the whole
+//! block would have. Splitting takes no allocation: the chunks are
+//! reborrowed slices in arrays on the stack.
+//!
+//! Nothing in the engine keeps per-block scratch yet — every path works
+//! sample by sample, or in fixed stretches of its own — so for now the
+//! limit guards the stages to come (an oversampler's buffers, say): a
+//! stage that needs scratch per block sizes it to [`max_block_len()`].
+
+/// How far past the declared maximum a block can run before it's split,
+/// in samples: room for hosts that round their buffer size up.
+pub const BLOCK_LEN_MARGIN: usize = 64;
+
+/// The most channels a set can have and still be split. Every layout
+/// the plugin offers has two; a set with more is passed on whole, as
+/// the engine can take any length, rather than allocate.
+pub const MAX_CHUNK_CHANNELS: usize = 8;
+
+/// The longest block the plugin processes in one go, for a host that
+/// declared `max_buffer_size`.
+pub fn max_block_len(max_buffer_size: u32) -> usize {
+    max_buffer_size as usize + BLOCK_LEN_MARGIN
+}
+
+/// Call `process` on `main` and the `ports` that go with it (sets of
+/// channels each) in chunks of at most `max_len` samples, in order.
+/// Each chunk covers the same samples of every set; a port shorter
+/// than `main` gets what it has of them.
+pub fn for_each_chunk<const N: usize>(
+    main: &mut [&mut [f32]],
+    mut ports: [&mut [&mut [f32]]; N],
+    max_len: usize,
+    mut process: impl FnMut(&mut [&mut [f32]], [&mut [&mut [f32]]; N]),
+) {
+    if main.len() > MAX_CHUNK_CHANNELS || ports.iter().any(|port| port.len() > MAX_CHUNK_CHANNELS) {
+        process(main, ports);
+        return;
+    }
+    let len = main.first().map_or(0, |samples| samples.len());
+    let main_channels = main.len();
+    let port_channels = ports.each_ref().map(|port| port.len());
+    let max_len = max_len.max(1);
+
+    let mut start = 0;
+    while start < len {
+        let end = (start + max_len).min(len);
+        let mut main_chunk: [&mut [f32]; MAX_CHUNK_CHANNELS] = Default::default();
+        chunk_into(&mut main_chunk, main, start, end);
+        let mut port_chunks: [[&mut [f32]; MAX_CHUNK_CHANNELS]; N] =
+            std::array::from_fn(|_| Default::default());
+        for (chunk, port) in port_chunks.iter_mut().zip(ports.iter_mut()) {
+            chunk_into(chunk, port, start, end);
+        }
+        let mut channels = port_channels.iter();
+        process(
+            &mut main_chunk[..main_channels],
+            port_chunks.each_mut().map(|chunk| {
+                let count = channels.next().copied().unwrap_or(0);
+                &mut chunk[..count]
+            }),
+        );
+        start = end;
+    }
+}
+
+/// Fill `chunk` with samples `start..end` of each of `channels`, cut
+/// short where a channel is.
+fn chunk_into<'a>(
+    chunk: &mut [&'a mut [f32]],
+    channels: &'a mut [&mut [f32]],
+    start: usize,
+    end: usize,
+) {
+    for (slot, samples) in chunk.iter_mut().zip(channels.iter_mut()) {
+        let end = end.min(samples.len());
+        *slot = &mut samples[start.min(end)..end];
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::noise::NoiseGenerator;
+    use crate::engine::{DelayParams, LovelessDelayEngine};
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// The chunks cover the block in order, none longer than the limit,
+    /// and a shorter set is cut to what it has.
+    #[test]
+    fn test_chunks_cover_the_block() {
+        let ramp = |len: usize| (0..len).map(|n| n as f32).collect::<Vec<f32>>();
+        let [mut left, mut right, mut short] = [ramp(1000), ramp(1000), ramp(500)];
+        let mut main = [&mut left[..], &mut right[..]];
+        let mut aux = [&mut short[..]];
+
+        let mut seen = Vec::new();
+        let mut lens = Vec::new();
+        for_each_chunk(&mut main, [&mut aux[..]], 256, |main, [aux]| {
+            assert_eq!(main.len(), 2);
+            assert_eq!(main[0], main[1]);
+            seen.extend_from_slice(main[0]);
+            lens.push((main[0].len(), aux[0].len()));
+        });
+        assert_eq!(seen, ramp(1000));
+        assert_eq!(lens, [(256, 256), (256, 244), (256, 0), (232, 0)]);
+    }
+
+    /// A block far longer than the host declared comes out complete, and
+    /// exactly as it would have in one go.
+    #[test]
+    fn test_longer_block_than_declared_plays_whole() {
+        const DECLARED: u32 = 256;
+        const LEN: usize = 64 * DECLARED as usize + 100;
+        let params = DelayParams {
+            delay_ms: 100.0,
+            feedback: 0.6,
+            ..DelayParams::default()
+        };
+        let mut noise = NoiseGenerator::new(7);
+        let input: Vec<f32> = (0..LEN)
+            .map(|n| if n < LEN / 4 { 0.5 * noise.next() } else { 0.0 })
+            .collect();
+
+        let mut whole = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        whole.set_params(&params);
+        let [mut whole_left, mut whole_right] = [input.clone(), input.clone()];
+        whole.process(&mut [&mut whole_left, &mut whole_right]);
+
+        let mut chunked = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        chunked.set_params(&params);
+        let [mut left, mut right] = [input.clone(), input];
+        let mut main = [&mut left[..], &mut right[..]];
+        let mut chunks = 0;
+        for_each_chunk(&mut main, [], max_block_len(DECLARED), |main, []| {
+            assert!(main[0].len() <= max_block_len(DECLARED));
+            chunked.process(main);
+            chunks += 1;
+        });
+
+        assert_eq!(chunks, LEN.div_ceil(max_block_len(DECLARED)));
+        for (chunked, whole) in [(&left, &whole_left), (&right, &whole_right)] {
+            assert_eq!(chunked.len(), LEN);
+            for (n, (a, b)) in chunked.iter().zip(whole).enumerate() {
+                assert!((a - b).abs() < 1e-6, "sample {n}: {a} vs {b}");
+            }
+        }
+        // The echoes are there to the end, not just the input.
+        let echo = left[LEN / 4 + 4800..]
+            .iter()
+            .fold(0.0_f32, |peak, s| peak.max(s.abs()));
+        assert!(echo > 0.05, "no echoes: {echo}");
+    }
+}
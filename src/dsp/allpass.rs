@@ -0,0 +1,206 @@
+//! # Schroeder Allpass
+//!
+//! An *allpass* filter passes every frequency at exactly the same level —
+//! its magnitude response is flat — but delays different frequencies by
+//! different amounts. On its own it sounds like nothing happened. Chained
+//! together, allpasses smear a sharp transient into a dense wash of
+//! closely spaced echoes without coloring its tone, which is why they're
+//! the core of diffusers and reverbs (Schroeder's 1962 reverberator is
+//! built from them) and of many chorus designs.
+//!
+//! ## The Structure
+//!
+//! Manfred Schroeder's allpass wraps a delay of `D` samples in both a
+//! feedback and a feed-forward path with the same gain `g`:
+//!
+//! ```text
+//!                 ┌──────────── × (−g) ─────────────┐
+//!                 │                                 ▼
+//! x[n] ──► (+) ───┴─ v[n] ──► [ delay D ] ──┬──► (+) ──► y[n]
+//!           ▲                               │
+//!           └──────────── × g ◄─────────────┘
+//!
+//! v[n] = x[n] + g · v[n−D]
+//! y[n] = v[n−D] − g · v[n]
+//! ```
+//!
+//! The transfer function is `H(z) = (−g + z^−D) / (1 − g·z^−D)`. The
+//! numerator's coefficients are the denominator's in reverse order, which
+//! makes `|H| = 1` at every frequency.
+//!
+//! ## The Impulse Response
+//!
+//! Feed in a single 1.0 and the output is a decaying train of echoes `D`
+//! samples apart:
+//!
+//! ```text
+//! y[0]  = −g
+//! y[D]  = 1 − g²
+//! y[kD] = g^(k−1) · (1 − g²)     for k ≥ 1
+//! ```
+//!
+//! The gain must stay below 1 in magnitude for the echoes to die away.
+//!
+//! Like the [`DelayLine`] it's built on, the allpass is generic over the
+//! [`Sample`] type and defaults to `f32`.
+
+use std::num::NonZeroUsize;
+
+use super::{delay_line::DelayLine, sample::Sample};
+
+/// Largest gain magnitude allowed. At 1.0 the echoes would never decay.
+const MAX_GAIN: f32 = 0.99;
+
+/// A Schroeder allpass filter around a fixed delay.
+pub struct Allpass<T: Sample = f32> {
+    /// Holds `v[n]`, the signal after the feedback sum.
+    delay_line: DelayLine<T>,
+
+    /// The delay length `D` in samples.
+    delay_samples: usize,
+
+    /// The feedback (and, negated, feed-forward) gain `g`.
+    gain: T,
+}
+
+impl<T: Sample> Allpass<T> {
+    /// Create an allpass with a delay of `delay_samples` and gain `gain`
+    /// (clamped to ±0.99).
+    ///
+    /// This allocates the delay buffer, so call it from a setup path,
+    /// never from the audio thread.
+    pub fn new(delay_samples: NonZeroUsize, gain: T) -> Self {
+        let delay_samples = delay_samples.get();
+        // One extra slot: the delay line is read *before* this sample is
+        // written, so reaching back `D` samples needs `D + 1` of storage.
+        let length = NonZeroUsize::new(delay_samples + 1).unwrap_or(NonZeroUsize::MIN);
+
+        let mut allpass = Self {
+            delay_line: DelayLine::new(length),
+            delay_samples,
+            gain: T::ZERO,
+        };
+        allpass.set_gain(gain);
+        allpass
+    }
+
+    /// How much memory the delay buffer takes, in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.delay_line.memory_bytes()
+    }
+
+    /// How many bytes [`new()`](Self::new) allocates for a delay of
+    /// `delay_samples`.
+    pub fn memory_bytes_for(delay_samples: NonZeroUsize) -> usize {
+        delay_samples
+            .get()
+            .saturating_add(1)
+            .saturating_mul(std::mem::size_of::<T>())
+    }
+
+    /// Set the gain `g`, clamped to ±0.99.
+    pub fn set_gain(&mut self, gain: T) {
+        let max = T::from(MAX_GAIN);
+        self.gain = gain.clamp(-max, max);
+    }
+
+    /// Process one sample.
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        let delayed = self.delay_line.read_int(self.delay_samples);
+        let v = input + self.gain * delayed;
+        self.delay_line.write(v);
+        self.delay_line.advance();
+        delayed - self.gain * v
+    }
+
+    /// Clear the delay line to silence.
+    pub fn reset(&mut self) {
+        self.delay_line.clear();
+    }
+
+    /// Take on `other`'s state, in place (see
+    /// [`DelayLine::copy_from()`]).
+    pub fn copy_from(&mut self, other: &Self) {
+        self.delay_line.copy_from(&other.delay_line);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn allpass(delay: usize, gain: f32) -> Allpass {
+        Allpass::new(NonZeroUsize::new(delay).unwrap(), gain)
+    }
+
+    /// Sine probes at several frequencies come out at the level they
+    /// went in.
+    #[test]
+    fn test_unity_magnitude() {
+        for freq in [100.0, 1000.0, 5000.0, 12000.0] {
+            let mut filter = allpass(37, 0.6);
+            let step = std::f32::consts::TAU * freq / SAMPLE_RATE;
+
+            // Let the echoes of the sine's onset die away, then compare
+            // levels over a whole number of cycles (4800 samples).
+            let mut input_energy = 0.0;
+            let mut output_energy = 0.0;
+            for n in 0..9600 {
+                let x = (n as f32 * step).sin();
+                let y = filter.process(x);
+                if n >= 4800 {
+                    input_energy += x * x;
+                    output_energy += y * y;
+                }
+            }
+            let ratio = (output_energy / input_energy).sqrt();
+            assert!((ratio - 1.0).abs() < 1e-3, "{freq} Hz: gain {ratio}");
+        }
+    }
+
+    /// The impulse response matches the closed form: −g, then echoes of
+    /// g^(k−1)·(1 − g²) every D samples, and silence in between.
+    #[test]
+    fn test_impulse_response() {
+        let (delay, g) = (5, 0.5);
+        let mut filter = allpass(delay, g);
+        let response: Vec<f32> = (0..delay * 5)
+            .map(|n| filter.process(if n == 0 { 1.0 } else { 0.0 }))
+            .collect();
+
+        for (n, &y) in response.iter().enumerate() {
+            let expected = match (n, n % delay) {
+                (0, _) => -g,
+                (_, 0) => g.powi((n / delay) as i32 - 1) * (1.0 - g * g),
+                _ => 0.0,
+            };
+            assert!(
+                (y - expected).abs() < 1e-6,
+                "y[{n}] = {y}, expected {expected}"
+            );
+        }
+    }
+
+    /// After a reset the filter behaves like a fresh one.
+    #[test]
+    fn test_reset() {
+        let mut filter = allpass(11, 0.7);
+        for n in 0..100 {
+            filter.process((n as f32 * 0.3).sin());
+        }
+        filter.reset();
+
+        let mut fresh = allpass(11, 0.7);
+        for n in 0..50 {
+            let x = if n == 0 { 1.0 } else { 0.0 };
+            assert_eq!(filter.process(x), fresh.process(x));
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! # Bloom: Repeats That Swell
+//!
+//! Left alone, each repeat is quieter than the one before. Ambient
+//! players often want the opposite at first: a note played into the
+//! delay comes back faint, then grows over the next repeats before the
+//! feedback takes it away again. [`BloomEnvelope`] rides the gain of the
+//! wet signal to get that swell:
+//!
+//! ```text
+//! input   █
+//! plain   ·   █   ▇   ▆   ▅   ▄      each repeat a little quieter
+//! bloom   ·   ▂   ▅   ▆   ▅   ▄      the first ones held down, rising
+//!             ↑       ↑
+//!          −bloom    0 dB
+//! ```
+//!
+//! The envelope sits on the wet output, after the feedback tap, never
+//! inside the loop. The repeats circulate exactly as they would without
+//! it, so however far it swells, it can't change the loop's stability.
+//!
+//! ## The Swell
+//!
+//! The envelope starts over each time fresh input excites the loop. It
+//! holds the gain down by the bloom amount until the first repeat is
+//! heard — one delay period, plus the pre-delay — and then rises,
+//! evenly in decibels, to unity at the third:
+//!
+//! ```text
+//! gain (dB)   0 ┤                ╭──────
+//!               │             ╭──╯
+//!               │          ╭──╯
+//!      −bloom   ┤──────────╯
+//!               └──────────┬─────┬─────┬──► time since excitation
+//!                       1st    2nd   3rd repeat
+//! ```
+//!
+//! So the third repeat comes out exactly `bloom` dB louder, next to the
+//! first, than it would without bloom. The times follow the delay as it
+//! moves, so the swell lines up with the repeats at any setting.
+//!
+//! ## Excitation
+//!
+//! A peak [`EnvelopeFollower`] listens to what goes into the loop. When
+//! it rises above [`EXCITE_DB`] the swell starts over, and it can't start
+//! over again until the level has fallen [`REARM_DB`] below that, so a
+//! held note restarts it once, not on every cycle. Starting over pulls
+//! the gain down over [`RESTART_MS`] rather than at once, so the repeats
+//! already playing duck out of the way instead of clicking. The gain
+//! never rises faster than that either: a crossfade to a shorter delay
+//! puts the swell further along in an instant, and the gain catches up
+//! with it over the same few milliseconds.
+//!
+//! At a bloom of 0 dB the gain is exactly 1.0: the wet signal passes
+//! through bit for bit.
+
+use super::envelope::{DetectorMode, EnvelopeFollower};
+
+/// The most a bloom can hold the first repeat down by, in dB.
+pub const MAX_BLOOM_DB: f32 = 24.0;
+
+/// The input level, in dBFS, that starts the swell over.
+pub const EXCITE_DB: f32 = -40.0;
+
+/// How far below [`EXCITE_DB`] the input has to fall before it can start
+/// the swell over again, in dB.
+pub const REARM_DB: f32 = 6.0;
+
+/// How long starting over takes to pull the gain down, in milliseconds.
+pub const RESTART_MS: f32 = 20.0;
+
+/// The detector's release, in milliseconds: long enough to ride over the
+/// dips between a low note's peaks.
+const DETECTOR_RELEASE_MS: f32 = 50.0;
+
+/// The gain on the wet signal for a swell of `bloom_db`, `since`
+/// samples after the loop was excited, with the first repeat heard
+/// `first` samples after it and the third `first + 2 · period`.
+fn swell_gain(since: f32, bloom_db: f32, first: f32, period: f32) -> f32 {
+    if bloom_db <= 0.0 {
+        return 1.0;
+    }
+    let rise = 2.0 * period;
+    let left = if rise > 0.0 {
+        (1.0 - (since - first) / rise).clamp(0.0, 1.0)
+    } else if since >= first {
+        0.0
+    } else {
+        1.0
+    };
+    if left == 0.0 {
+        1.0
+    } else {
+        db_to_gain(-bloom_db * left)
+    }
+}
+
+/// Rides the wet signal's gain so repeats swell after fresh input. See
+/// [the module docs](self).
+#[derive(Clone)]
+pub struct BloomEnvelope {
+    detector: EnvelopeFollower,
+
+    /// The detected levels that start the swell over and re-arm it.
+    excite: f32,
+    rearm: f32,
+    armed: bool,
+
+    /// Samples since the loop was last excited.
+    since: usize,
+
+    /// The gain on this sample, and the most it moves by in a sample.
+    gain: f32,
+    max_step: f32,
+}
+
+impl BloomEnvelope {
+    /// An envelope that's fully bloomed, as if the last excitation were
+    /// long past: unity gain until the input starts a swell.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut detector = EnvelopeFollower::new(sample_rate);
+        detector.set_mode(DetectorMode::Peak);
+        detector.set_attack_ms(0.0);
+        detector.set_release_ms(DETECTOR_RELEASE_MS);
+        Self {
+            detector,
+            excite: db_to_gain(EXCITE_DB),
+            rearm: db_to_gain(EXCITE_DB - REARM_DB),
+            armed: true,
+            since: usize::MAX,
+            gain: 1.0,
+            max_step: 1.0 / (RESTART_MS / 1000.0 * sample_rate).max(1.0),
+        }
+    }
+
+    /// Measure one sample of the loop's `input` (the loudest channel's,
+    /// say) and return the wet gain for a bloom of `bloom_db`, with the
+    /// delay at `delay_samps` and the pre-delay at `pre_delay_samps`.
+    #[inline]
+    pub fn process(
+        &mut self,
+        input: f32,
+        bloom_db: f32,
+        delay_samps: f32,
+        pre_delay_samps: f32,
+    ) -> f32 {
+        let level = self.detector.process(input);
+        if self.armed && level > self.excite {
+            self.armed = false;
+            self.since = 0;
+        } else {
+            self.armed |= level < self.rearm;
+            self.since = self.since.saturating_add(1);
+        }
+
+        let target = swell_gain(
+            self.since as f32,
+            bloom_db,
+            delay_samps + pre_delay_samps,
+            delay_samps,
+        );
+        self.gain = if target >= self.gain {
+            (self.gain + self.max_step).min(target)
+        } else {
+            (self.gain - self.max_step).max(target)
+        };
+        self.gain
+    }
+
+    /// The gain on the last sample.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Forget the input, and go back to fully bloomed.
+    pub fn reset(&mut self) {
+        self.detector.reset();
+        self.armed = true;
+        self.since = usize::MAX;
+        self.gain = 1.0;
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    const PERIOD: usize = 4800;
+
+    fn gain_db(gain: f32) -> f32 {
+        20.0 * gain.log10()
+    }
+
+    /// After an impulse, the gain is held down by the bloom amount at
+    /// the first repeat, halfway up at the second, and back to unity at
+    /// the third, rising all the way.
+    #[test]
+    fn test_swells_from_first_to_third_repeat() {
+        let mut envelope = BloomEnvelope::new(SAMPLE_RATE);
+        let gains: Vec<f32> = (0..4 * PERIOD)
+            .map(|n| {
+                let input = if n == 0 { 1.0 } else { 0.0 };
+                envelope.process(input, 12.0, PERIOD as f32, 0.0)
+            })
+            .collect();
+        assert!((gain_db(gains[PERIOD]) + 12.0).abs() < 1e-3);
+        assert!((gain_db(gains[2 * PERIOD]) + 6.0).abs() < 1e-3);
+        assert_eq!(gains[3 * PERIOD], 1.0);
+        for pair in gains[PERIOD..].windows(2) {
+            assert!(pair[1] >= pair[0]);
+        }
+    }
+
+    /// Fresh input starts the swell over, pulling the gain down no faster
+    /// than the restart allows. A held note starts it only once.
+    #[test]
+    fn test_fresh_input_restarts() {
+        let mut envelope = BloomEnvelope::new(SAMPLE_RATE);
+        let fall_step = 1.0 / (RESTART_MS / 1000.0 * SAMPLE_RATE);
+        let mut last = 1.0;
+        for n in 0..12 * PERIOD {
+            // A burst every four periods, held for a quarter of one.
+            let input = if n % (4 * PERIOD) < PERIOD / 4 {
+                (n as f32 * 0.05).sin() * 0.5
+            } else {
+                0.0
+            };
+            let gain = envelope.process(input, 12.0, PERIOD as f32, 0.0);
+            assert!(
+                last - gain <= fall_step + 1e-6,
+                "sample {n}: {last} → {gain}"
+            );
+            // The burst starts the swell on its second sample, and never
+            // again while it plays.
+            match n % (4 * PERIOD) {
+                n if n == PERIOD => assert!((gain_db(gain) + 12.0).abs() < 1e-3),
+                n if n == 3 * PERIOD + 1 => assert_eq!(gain, 1.0),
+                _ => {}
+            }
+            last = gain;
+        }
+    }
+
+    /// A crossfade to a third of the delay, mid-swell, puts the third
+    /// repeat behind it: the gain heads for unity, but no faster than a
+    /// restart pulls it down.
+    #[test]
+    fn test_delay_jump_lifts_the_gain_gradually() {
+        let mut envelope = BloomEnvelope::new(SAMPLE_RATE);
+        let max_step = 1.0 / (RESTART_MS / 1000.0 * SAMPLE_RATE);
+        envelope.process(1.0, 24.0, PERIOD as f32, 0.0);
+        let mut last = 0.0;
+        for _ in 1..PERIOD {
+            last = envelope.process(0.0, 24.0, PERIOD as f32, 0.0);
+        }
+        assert!(gain_db(last) < -23.0, "{last}");
+
+        let period = (PERIOD / 3) as f32;
+        for n in 0..PERIOD {
+            let gain = envelope.process(0.0, 24.0, period, 0.0);
+            assert!(
+                gain - last <= max_step + 1e-6,
+                "sample {n}: {last} → {gain}"
+            );
+            last = gain;
+        }
+        assert_eq!(last, 1.0);
+    }
+
+    /// At 0 dB the gain is exactly 1.0, whatever the input does.
+    #[test]
+    fn test_zero_bloom_is_unity() {
+        let mut envelope = BloomEnvelope::new(SAMPLE_RATE);
+        for n in 0..4 * PERIOD {
+            let input = (n as f32 * 0.013).sin();
+            assert_eq!(envelope.process(input, 0.0, PERIOD as f32, 100.0), 1.0);
+        }
+    }
+}
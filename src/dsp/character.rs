@@ -0,0 +1,99 @@
+//! # The Character Macro
+//!
+//! One knob from "pristine digital" (0.0) to "worn tape" (1.0). Old tape
+//! echoes get darker and grittier as they wear, so the macro pushes
+//! several settings in that direction at once:
+//!
+//! | Character | Feedback cutoff  | Drive added |
+//! | --------- | ---------------- | ----------- |
+//! | 0%        | as set           | none        |
+//! | 50%       | 1.5 octaves down | +0.15       |
+//! | 100%      | 3 octaves down   | +0.6        |
+//!
+//! ## Offsets, Not Knob Moves
+//!
+//! The macro never moves the other knobs. It produces *offsets* that are
+//! combined with their values just before they reach the DSP, so the
+//! individual controls stay free for fine tuning — and setting the macro
+//! back to 0 gives back exactly the sound the knobs describe.
+//!
+//! ## The Curves
+//!
+//! - **Cutoff** moves linearly in *octaves* (`cutoff * 2^-(3 * amount)`).
+//!   We hear pitch logarithmically, so this sounds like an even darkening
+//!   across the knob's travel.
+//! - **Drive** grows with the *square* of the amount: it barely creeps
+//!   in over the first half and arrives in earnest near the worn end,
+//!   the way tape saturation does as the oxide wears.
+
+/// Octaves the feedback cutoff drops at full character.
+const MAX_CUTOFF_DROP_OCTAVES: f32 = 3.0;
+
+/// Drive added at full character.
+const MAX_DRIVE_ADDED: f32 = 0.6;
+
+/// The offsets one character amount applies to the other settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharacterOffsets {
+    /// How many octaves to move the feedback cutoff (0 or negative).
+    pub cutoff_octaves: f32,
+
+    /// How much drive to add (0.0–[`MAX_DRIVE_ADDED`]).
+    pub drive: f32,
+}
+
+impl CharacterOffsets {
+    /// The offsets for a character `amount` from 0.0 to 1.0 (clamped).
+    pub fn at(amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+        Self {
+            cutoff_octaves: -MAX_CUTOFF_DROP_OCTAVES * amount,
+            drive: MAX_DRIVE_ADDED * amount * amount,
+        }
+    }
+
+    /// Apply the cutoff offset to a feedback cutoff in Hz.
+    #[inline]
+    pub fn cutoff(&self, cutoff_hz: f32) -> f32 {
+        cutoff_hz * self.cutoff_octaves.exp2()
+    }
+
+    /// Apply the drive offset to a drive amount, staying within 0.0–1.0.
+    #[inline]
+    pub fn drive(&self, drive: f32) -> f32 {
+        (drive + self.drive).min(1.0)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The curves at 0%, 50% and 100%.
+    #[test]
+    fn test_mapping_curves() {
+        let pristine = CharacterOffsets::at(0.0);
+        assert_eq!(pristine.cutoff(8000.0), 8000.0);
+        assert_eq!(pristine.drive(0.25), 0.25);
+
+        let half = CharacterOffsets::at(0.5);
+        assert!((half.cutoff(8000.0) - 2828.4).abs() < 0.1);
+        assert!((half.drive(0.0) - 0.15).abs() < 1e-6);
+
+        let worn = CharacterOffsets::at(1.0);
+        assert_eq!(worn.cutoff(8000.0), 1000.0);
+        assert!((worn.drive(0.0) - 0.6).abs() < 1e-6);
+    }
+
+    /// Offsets stack on top of the knobs without leaving their ranges.
+    #[test]
+    fn test_offsets_stay_in_range() {
+        let worn = CharacterOffsets::at(2.0);
+        assert_eq!(worn, CharacterOffsets::at(1.0));
+        assert_eq!(worn.drive(0.9), 1.0);
+    }
+}
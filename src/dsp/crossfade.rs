@@ -0,0 +1,309 @@
+//! # Crossfades for Click-Free Switches
+//!
+//! Flipping a switch that changes the signal path — limiter in or out,
+//! feedback linked or not — swaps one waveform for another between two
+//! samples. Unless the two happen to agree at that instant, the output
+//! jumps, and a jump is a click.
+//!
+//! The cure is to run *both* paths for a few milliseconds and fade from
+//! one to the other:
+//!
+//! ```text
+//! output = old_path × fade_out  +  new_path × fade_in
+//! ```
+//!
+//! ## Fade Laws
+//!
+//! How the two gains move decides how loud the middle of the fade is.
+//!
+//! - **Equal power** (`cos`/`sin` of a quarter turn): `out² + in² = 1`
+//!   all the way through. Two *unrelated* signals add up by power, so
+//!   this keeps their sum at a steady loudness. It's the right law for
+//!   switching between paths that sound different.
+//! - **Linear** (`1 − p` / `p`): `out + in = 1`. Two *near-identical*
+//!   signals add up by amplitude, so an equal-power fade would bulge by
+//!   up to 3 dB in the middle. Use this when both paths carry mostly the
+//!   same audio — a limiter that's barely working, feedback that only
+//!   differs in its low band.
+//!
+//! ## Crossfade and SwitchedPath
+//!
+//! [`Crossfade`] is the bare fade: start it, then take one gain pair per
+//! sample until it's done. [`SwitchedPath`] wraps one for an on/off
+//! switch: it knows which way it's fading, and flipping it back halfway
+//! turns the fade around from where it is instead of jumping back to the
+//! start.
+
+/// How a [`Crossfade`] moves its two gains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FadeLaw {
+    /// `out² + in² = 1`: steady loudness between unrelated signals.
+    #[default]
+    EqualPower,
+    /// `out + in = 1`: steady level between near-identical signals.
+    Linear,
+}
+
+/// A fade from one signal path to another over a fixed number of
+/// samples.
+#[derive(Debug, Clone)]
+pub struct Crossfade {
+    law: FadeLaw,
+
+    /// How far through the fade we are, 0.0 (all old path) to 1.0 (all
+    /// new path).
+    position: f32,
+
+    /// How far `position` moves each sample.
+    step: f32,
+
+    /// Whether a fade is in progress.
+    active: bool,
+}
+
+impl Crossfade {
+    /// Create an idle crossfade (resting on the new path) with `law`.
+    pub fn new(law: FadeLaw) -> Self {
+        Self {
+            law,
+            position: 1.0,
+            step: 0.0,
+            active: false,
+        }
+    }
+
+    /// Start a fade lasting `duration_samples`. Starting while a fade is
+    /// already running restarts it from the beginning. A duration of 0
+    /// switches straight over.
+    pub fn start(&mut self, duration_samples: usize) {
+        self.start_from(0.0, duration_samples);
+    }
+
+    /// Start a fade at `position` (0.0–1.0) instead of the beginning,
+    /// still moving at the rate a full `duration_samples` fade would.
+    pub fn start_from(&mut self, position: f32, duration_samples: usize) {
+        if duration_samples == 0 {
+            self.position = 1.0;
+            self.active = false;
+            return;
+        }
+        self.position = position.clamp(0.0, 1.0);
+        self.step = 1.0 / duration_samples as f32;
+        self.active = self.position < 1.0;
+    }
+
+    /// Whether a fade is in progress.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// How far through the fade we are (1.0 once it's finished).
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// The `(fade_out, fade_in)` gains for this sample, then advance.
+    /// Once the fade is over this keeps returning `(0.0, 1.0)`.
+    #[inline]
+    pub fn next_gains(&mut self) -> (f32, f32) {
+        let gains = gains_at(self.law, self.position);
+        if self.active {
+            self.position += self.step;
+            // Snap the last step so rounding can't leave the fade a hair
+            // short of the end.
+            if self.position >= 1.0 - self.step * 0.5 {
+                self.position = 1.0;
+                self.active = false;
+            }
+        }
+        gains
+    }
+}
+
+/// The `(fade_out, fade_in)` gains at `position` under `law`.
+pub fn gains_at(law: FadeLaw, position: f32) -> (f32, f32) {
+    match law {
+        // sin((1 − p)·π/2) is cos(p·π/2), written so both gains hit
+        // exactly 0.0 and 1.0 at the ends.
+        FadeLaw::EqualPower => (
+            ((1.0 - position) * std::f32::consts::FRAC_PI_2).sin(),
+            (position * std::f32::consts::FRAC_PI_2).sin(),
+        ),
+        FadeLaw::Linear => (1.0 - position, position),
+    }
+}
+
+/// An on/off switch between two signal paths that crossfades instead
+/// of jumping.
+///
+/// Each sample, [`next_gains()`](Self::next_gains) gives the gains for
+/// the "off" and "on" paths. Outside a fade one of them is 1.0 and the
+/// other 0.0, so the caller can skip computing the silent path whenever
+/// [`is_active()`](Self::is_active) is false.
+#[derive(Debug, Clone)]
+pub struct SwitchedPath {
+    fade: Crossfade,
+
+    /// Where the switch is heading (or resting).
+    on: bool,
+}
+
+impl SwitchedPath {
+    /// Create a switch resting at `on`, fading with `law`.
+    pub fn new(on: bool, law: FadeLaw) -> Self {
+        Self {
+            fade: Crossfade::new(law),
+            on,
+        }
+    }
+
+    /// Flip the switch to `on`, fading over `duration_samples`. Setting
+    /// the state it's already in does nothing. Flipping it back mid-fade
+    /// turns the fade around from where it is.
+    pub fn set(&mut self, on: bool, duration_samples: usize) {
+        if on == self.on {
+            return;
+        }
+        self.on = on;
+        let position = if self.fade.is_active() {
+            1.0 - self.fade.position()
+        } else {
+            0.0
+        };
+        self.fade.start_from(position, duration_samples);
+    }
+
+    /// Jump straight to `on`, cancelling any fade.
+    pub fn jump(&mut self, on: bool) {
+        self.on = on;
+        self.fade.start(0);
+    }
+
+    /// Whether the switch is on (or fading towards on).
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Whether a fade is in progress, so both paths are needed.
+    pub fn is_active(&self) -> bool {
+        self.fade.is_active()
+    }
+
+    /// Whether the "on" path is needed at all: switched on, or still
+    /// fading out.
+    pub fn on_path_needed(&self) -> bool {
+        self.on || self.is_active()
+    }
+
+    /// The `(off_gain, on_gain)` pair for this sample, then advance.
+    #[inline]
+    pub fn next_gains(&mut self) -> (f32, f32) {
+        let (fade_out, fade_in) = self.fade.next_gains();
+        if self.on {
+            (fade_out, fade_in)
+        } else {
+            (fade_in, fade_out)
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Equal-power gains keep `out² + in² = 1`; linear gains keep
+    /// `out + in = 1`, all the way through.
+    #[test]
+    fn test_gain_sums() {
+        let mut equal_power = Crossfade::new(FadeLaw::EqualPower);
+        let mut linear = Crossfade::new(FadeLaw::Linear);
+        equal_power.start(480);
+        linear.start(480);
+        for _ in 0..600 {
+            let (out, fade_in) = equal_power.next_gains();
+            assert!((out * out + fade_in * fade_in - 1.0).abs() < 1e-6);
+            let (out, fade_in) = linear.next_gains();
+            assert!((out + fade_in - 1.0).abs() < 1e-6);
+        }
+    }
+
+    /// A fade of N samples starts on the old path, takes exactly N
+    /// samples, and ends on the new one.
+    #[test]
+    fn test_duration() {
+        for duration in [1, 7, 480, 4410] {
+            let mut fade = Crossfade::new(FadeLaw::EqualPower);
+            fade.start(duration);
+            assert_eq!(fade.next_gains(), (1.0, 0.0));
+            for _ in 1..duration {
+                assert!(fade.is_active(), "{duration}: ended early");
+                fade.next_gains();
+            }
+            assert!(!fade.is_active(), "{duration}: ran long");
+            assert_eq!(fade.next_gains(), (0.0, 1.0));
+        }
+    }
+
+    /// Starting a fade that's already running restarts it in full.
+    #[test]
+    fn test_restart_while_active() {
+        let mut fade = Crossfade::new(FadeLaw::Linear);
+        fade.start(100);
+        for _ in 0..60 {
+            fade.next_gains();
+        }
+        fade.start(100);
+        assert_eq!(fade.next_gains(), (1.0, 0.0));
+        for _ in 1..100 {
+            assert!(fade.is_active());
+            fade.next_gains();
+        }
+        assert!(!fade.is_active());
+    }
+
+    /// Flipping a switch back mid-fade turns around without a jump in
+    /// either gain, and the way back takes as long as the way there did.
+    #[test]
+    fn test_switch_reverses_smoothly() {
+        let mut switch = SwitchedPath::new(false, FadeLaw::Linear);
+        assert_eq!(switch.next_gains(), (1.0, 0.0));
+
+        switch.set(true, 100);
+        let mut last = (1.0, 0.0);
+        for _ in 0..30 {
+            last = switch.next_gains();
+        }
+
+        switch.set(false, 100);
+        let turned = switch.next_gains();
+        assert!((turned.0 - last.0).abs() <= 0.011, "{last:?} → {turned:?}");
+        assert!((turned.1 - last.1).abs() <= 0.011, "{last:?} → {turned:?}");
+
+        let mut steps = 1;
+        while switch.is_active() {
+            switch.next_gains();
+            steps += 1;
+        }
+        assert!((29..=31).contains(&steps), "{steps} steps back");
+        assert_eq!(switch.next_gains(), (1.0, 0.0));
+    }
+
+    /// Setting the state a switch is already in doesn't start a fade;
+    /// `jump()` switches with none.
+    #[test]
+    fn test_switch_idle_and_jump() {
+        let mut switch = SwitchedPath::new(true, FadeLaw::EqualPower);
+        switch.set(true, 100);
+        assert!(!switch.is_active());
+        assert_eq!(switch.next_gains(), (0.0, 1.0));
+
+        switch.set(false, 100);
+        switch.jump(true);
+        assert!(!switch.is_active());
+        assert_eq!(switch.next_gains(), (0.0, 1.0));
+    }
+}
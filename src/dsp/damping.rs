@@ -0,0 +1,143 @@
+//! # Level-Dependent Damping
+//!
+//! The lowpass in the feedback loop darkens every repeat by the same
+//! amount, so the top end fades at a steady rate: each pass loses the
+//! same slice of treble as the one before. A worn tape echo loses it
+//! faster towards the end. Quiet signal sits closer to the tape's noise
+//! and to the limits of the heads, so the late, faint repeats dull
+//! quicker than the loud early ones.
+//!
+//! [`LoopDamping`] imitates that by lowering the loop filter's cutoff as
+//! the loop gets quieter. An envelope follower listens to the signal
+//! coming out of the delay line, and the cutoff drops by up to
+//! [`MAX_DAMPING_OCTAVES`] as that level falls from full scale to
+//! [`DAMPING_RANGE_DB`] below it:
+//!
+//! ```text
+//! quietness = clamp(−level_dB / DAMPING_RANGE_DB, 0, 1)
+//! cutoff    = knob_cutoff · 2^(−MAX_DAMPING_OCTAVES · amount · quietness)
+//!
+//! amount 1.0, knob at 8 kHz:
+//!   loop at   0 dB → 8 kHz
+//!   loop at −24 dB → 2 kHz
+//!   loop at −48 dB → 500 Hz
+//! ```
+//!
+//! Working in decibels and octaves keeps the effect even: every 6 dB the
+//! loop loses costs the same musical interval of brightness.
+//!
+//! Because the level falls with every pass, each repeat is filtered a
+//! little darker than the last, and the darkening compounds: the tail
+//! loses its treble faster than a static filter could make it.
+
+use super::envelope::EnvelopeFollower;
+
+/// How far the cutoff falls at full damping on a signal
+/// [`DAMPING_RANGE_DB`] down, in octaves.
+pub const MAX_DAMPING_OCTAVES: f32 = 4.0;
+
+/// The loop level (in dB below full scale) at which the damping reaches
+/// its full depth.
+pub const DAMPING_RANGE_DB: f32 = 48.0;
+
+/// How fast the loop level follower responds, in milliseconds. A quick
+/// attack catches the start of each repeat; the slower release rides
+/// over the gaps inside a phrase rather than darkening every pause.
+const FOLLOWER_ATTACK_MS: f32 = 1.0;
+const FOLLOWER_RELEASE_MS: f32 = 150.0;
+
+/// Lowers a feedback cutoff as the loop level falls.
+#[derive(Clone)]
+pub struct LoopDamping {
+    follower: EnvelopeFollower,
+}
+
+impl LoopDamping {
+    /// Create a damping stage with its level follower at silence.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut follower = EnvelopeFollower::new(sample_rate);
+        follower.set_attack_ms(FOLLOWER_ATTACK_MS);
+        follower.set_release_ms(FOLLOWER_RELEASE_MS);
+        Self { follower }
+    }
+
+    /// Feed one sample of the loop signal to the level follower.
+    #[inline]
+    pub fn follow(&mut self, loop_sample: f32) {
+        self.follower.process(loop_sample);
+    }
+
+    /// `cutoff_hz` lowered for the current loop level, by `amount`
+    /// (0.0–1.0) of the full damping. At 0.0 it's returned unchanged.
+    #[inline]
+    pub fn cutoff(&self, cutoff_hz: f32, amount: f32) -> f32 {
+        if amount <= 0.0 {
+            return cutoff_hz;
+        }
+        let level_db = 20.0 * self.follower.envelope().max(1e-9).log10();
+        let quietness = (-level_db / DAMPING_RANGE_DB).clamp(0.0, 1.0);
+        cutoff_hz * (-MAX_DAMPING_OCTAVES * amount.min(1.0) * quietness).exp2()
+    }
+
+    /// The loop level the follower has measured, as a linear amplitude.
+    #[inline]
+    pub fn level(&self) -> f32 {
+        self.follower.envelope()
+    }
+
+    /// Forget the loop level, back to silence.
+    pub fn reset(&mut self) {
+        self.follower.reset();
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Hold the loop at `level` long enough for the follower to settle.
+    fn settled_at(level: f32) -> LoopDamping {
+        let mut damping = LoopDamping::new(SAMPLE_RATE);
+        for _ in 0..SAMPLE_RATE as usize {
+            damping.follow(level);
+        }
+        damping
+    }
+
+    /// The cutoff falls an even number of octaves per decibel, down to
+    /// the full depth at the bottom of the range and no further.
+    #[test]
+    fn test_cutoff_follows_level() {
+        let cases = [
+            (1.0, 8000.0),
+            (0.063, 2000.0),
+            (0.004, 500.0),
+            (1e-6, 500.0),
+        ];
+        for (level, want) in cases {
+            let got = settled_at(level).cutoff(8000.0, 1.0);
+            assert!(
+                (got / want).log2().abs() < 0.02,
+                "level {level}: {got} Hz, expected {want}"
+            );
+        }
+
+        // Half the amount, half the octaves.
+        let got = settled_at(0.004).cutoff(8000.0, 0.5);
+        assert!((got / 2000.0).log2().abs() < 0.02, "{got}");
+    }
+
+    /// At zero amount the cutoff passes through exactly, at any level.
+    #[test]
+    fn test_zero_amount_is_transparent() {
+        for level in [1.0, 0.01, 0.0] {
+            assert_eq!(settled_at(level).cutoff(8000.0, 0.0), 8000.0);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! # Stereo Decorrelation
+//!
+//! Feed a stereo delay the same signal on both sides — a mono synth on a
+//! stereo track, a dual-mono bus — and the repeats come out identical on
+//! both sides too, sitting in a narrow point in the middle. Making the
+//! two sides *different* without making them sound different is the job
+//! of a decorrelator.
+//!
+//! ## Why Not Just Delay One Side?
+//!
+//! Delaying one side by a few milliseconds does widen the image, but
+//! it's heard as a doubling (or, folded to mono, as the hollow sound of
+//! a comb filter). An [`Allpass`] smears the signal across many tiny
+//! echoes instead of one, so no single delay stands out.
+//!
+//! ## Complementary Sides
+//!
+//! A short chain of allpasses turns the signal `x` into a scrambled copy
+//! `d` — the same spectrum, but unrelated phase. The two sides then take
+//! it with opposite signs, the way a mid/side pair does:
+//!
+//! ```text
+//! left  = (x + a·d) / √(1 + a²)
+//! right = (x − a·d) / √(1 + a²)          a = amount, 0.0–1.0
+//! ```
+//!
+//! Each side alone is colored a little (`x ± d` has peaks and dips), but
+//! the dips on one side are the peaks on the other, so the pair sounds
+//! even, and folded to mono the `d` terms cancel exactly. Because `d` is
+//! unrelated to `x`, the correlation between the sides is
+//! `(1 − a²) / (1 + a²)`: 1.0 (identical) at 0%, 0.0 (fully
+//! decorrelated) at 100%. The `√(1 + a²)` keeps the loudness steady as
+//! the amount goes up.
+//!
+//! At an amount of 0.0 each side is exactly `x`, so switching it off
+//! changes nothing.
+
+use std::num::NonZeroUsize;
+
+use super::allpass::Allpass;
+
+/// The allpass delays, in milliseconds. Irregularly spaced, so their
+/// echoes don't line up on each other, and 13 ms in total — too short
+/// to hear as a delay.
+const STAGE_DELAYS_MS: [f32; 3] = [2.9, 4.3, 6.1];
+
+/// The allpass gain. High enough to smear each echo into the next,
+/// low enough that the smear dies away within a few tens of
+/// milliseconds.
+const STAGE_GAIN: f32 = 0.6;
+
+/// One side of a stereo decorrelator.
+pub struct Decorrelator {
+    stages: [Allpass; 3],
+
+    /// +1.0 or −1.0: which way round this side takes the scrambled copy.
+    sign: f32,
+}
+
+impl Decorrelator {
+    /// Create one side of a decorrelator. The two sides of a pair are
+    /// the same except for `inverted`, which the right side sets.
+    ///
+    /// This allocates the allpass buffers, so call it from a setup path,
+    /// never from the audio thread.
+    pub fn new(sample_rate: f32, inverted: bool) -> Self {
+        let stages = stage_delays(sample_rate).map(|samples| Allpass::new(samples, STAGE_GAIN));
+        Self {
+            stages,
+            sign: if inverted { -1.0 } else { 1.0 },
+        }
+    }
+
+    /// Decorrelate one sample by `amount` (0.0–1.0). The allpasses run
+    /// at every amount, so turning it up starts from a settled chain.
+    #[inline]
+    pub fn process(&mut self, x: f32, amount: f32) -> f32 {
+        let scrambled = self
+            .stages
+            .iter_mut()
+            .fold(x, |signal, stage| stage.process(signal));
+        let amount = amount.clamp(0.0, 1.0);
+        (x + self.sign * amount * scrambled) / (1.0 + amount * amount).sqrt()
+    }
+
+    /// How much memory the allpass buffers take, in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.stages.iter().map(Allpass::memory_bytes).sum()
+    }
+
+    /// How many bytes of allpass buffers [`new()`](Self::new) allocates
+    /// at `sample_rate`.
+    pub fn memory_bytes_for(sample_rate: f32) -> usize {
+        stage_delays(sample_rate)
+            .iter()
+            .map(|samples| Allpass::<f32>::memory_bytes_for(*samples))
+            .fold(0, usize::saturating_add)
+    }
+
+    /// Clear the allpasses to silence.
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.reset();
+        }
+    }
+
+    /// Take on what `other`'s allpasses hold, in place, keeping this
+    /// side's own sign.
+    pub fn copy_from(&mut self, other: &Self) {
+        for (stage, theirs) in self.stages.iter_mut().zip(&other.stages) {
+            stage.copy_from(theirs);
+        }
+    }
+}
+
+/// Each allpass stage's delay at `sample_rate`, in samples.
+fn stage_delays(sample_rate: f32) -> [NonZeroUsize; 3] {
+    STAGE_DELAYS_MS.map(|ms| {
+        let samples = (ms / 1000.0 * sample_rate) as usize;
+        NonZeroUsize::new(samples).unwrap_or(NonZeroUsize::MIN)
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::noise::NoiseGenerator;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Run `input` through both sides of a pair at `amount`.
+    fn pair(input: &[f32], amount: f32) -> (Vec<f32>, Vec<f32>) {
+        let mut left = Decorrelator::new(SAMPLE_RATE, false);
+        let mut right = Decorrelator::new(SAMPLE_RATE, true);
+        input
+            .iter()
+            .map(|&x| (left.process(x, amount), right.process(x, amount)))
+            .unzip()
+    }
+
+    /// At 0% both sides are the input, bit for bit.
+    #[test]
+    fn test_zero_amount_nulls() {
+        let input: Vec<f32> = (0..4800).map(|n| (n as f32 * 0.01).sin()).collect();
+        let (left, right) = pair(&input, 0.0);
+        assert_eq!(left, input);
+        assert_eq!(right, input);
+    }
+
+    /// The sides always fold back to the (rescaled) input, and at 100%
+    /// they're uncorrelated.
+    #[test]
+    fn test_sides_are_complementary() {
+        let mut noise = NoiseGenerator::new(7);
+        let input: Vec<f32> = (0..48000).map(|_| noise.next()).collect();
+        let (left, right) = pair(&input, 1.0);
+
+        for ((l, r), x) in left.iter().zip(&right).zip(&input) {
+            assert!((l + r - x * std::f32::consts::SQRT_2).abs() < 1e-5);
+        }
+
+        let dot: f32 = left.iter().zip(&right).map(|(l, r)| l * r).sum();
+        let power: f32 = left.iter().map(|l| l * l).sum();
+        assert!((dot / power).abs() < 0.05, "correlation {}", dot / power);
+    }
+}
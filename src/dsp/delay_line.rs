@@ -41,19 +41,48 @@
 //! - `sample_a` is at position 441 (weight 0.7)
 //! - `sample_b` is at position 442 (weight 0.3)
 //! - `result = sample_a * 0.7 + sample_b * 0.3`
+//!
+//! ## One Writer, Many Readers
+//!
+//! Multi-tap echoes, reverse playback, and crossfades between delay times
+//! all want *several* read heads over the same stored audio. The
+//! `DelayLine` itself owns the one and only write head (`write()` and
+//! `advance()` take `&mut self`), while [`DelayReader`] is a tiny handle
+//! that remembers its own delay offset and reads through a shared
+//! `&DelayLine`. Rust's borrow rules then enforce the ownership story for
+//! us: any number of readers can look at the buffer, but only while
+//! nobody is writing to it.
+//!
+//! ```text
+//!                   write head
+//!                       │
+//!   ┌───────────────────▼─────────┐
+//!   │ . . . . . . . . . . . . . . │  one buffer per channel
+//!   └──▲──────────▲─────────▲─────┘
+//!      │          │         │
+//!   reader C   reader B  reader A   (each with its own delay)
+//! ```
+//!
+//! ## Sample Precision
+//!
+//! `DelayLine` is generic over the [`Sample`] type and defaults to `f32`,
+//! so `DelayLine` on its own means the plugin's usual 32-bit line while
+//! `DelayLine<f64>` gives a high-precision one for offline work.
 
 use std::num::NonZeroUsize;
 
+use super::sample::Sample;
+
 /// A ring buffer that functions as an audio delay line.
 ///
 /// The buffer is pre-allocated to the maximum possible delay length
 /// during `initialize()`, so no memory allocation ever happens during
 /// audio processing. This is critical for real-time audio: memory
 /// allocation can block (waiting for a lock), causing audio dropouts.
-pub struct DelayLine {
+pub struct DelayLine<T: Sample = f32> {
     /// The circular buffer storing audio samples. All values start at
     /// 0.0 (silence).
-    buffer: Vec<f32>,
+    buffer: Vec<T>,
 
     /// Current write position — where the next incoming sample will be
     /// stored. Advances by 1 each sample, wrapping to 0 at `buffer_len`.
@@ -62,9 +91,19 @@ pub struct DelayLine {
     /// Cached buffer length, stored to avoid repeated `.len()` calls
     /// and to make the modular arithmetic clearer in the code.
     buffer_len: usize,
+
+    /// Optional safety limit for writes. When `Some(limit)`, every
+    /// `write()` goes through [`write_clamped()`](Self::write_clamped).
+    /// `None` (the default) stores samples untouched.
+    clip_level: Option<T>,
+
+    /// How many samples have been written (and advanced past) since the
+    /// line was created or cleared, saturating at `buffer_len`. Lets us
+    /// tell real content apart from the initial silence.
+    written: usize,
 }
 
-impl DelayLine {
+impl<T: Sample> DelayLine<T> {
     /// Create a new delay line with the given maximum size in samples.
     ///
     /// # Arguments
@@ -86,20 +125,74 @@ impl DelayLine {
     pub fn new(max_length: NonZeroUsize) -> Self {
         let len = max_length.get();
         Self {
-            buffer: vec![0.0; len],
+            buffer: vec![T::ZERO; len],
             write_pos: 0,
             buffer_len: len,
+            clip_level: None,
+            written: 0,
         }
     }
 
+    /// Enable (`Some(limit)`) or disable (`None`) write protection.
+    ///
+    /// A feedback loop is recursive: whatever lands in the buffer comes
+    /// back out and gets written in again. If a single NaN or infinity
+    /// sneaks in (a blown-up filter, a division by zero upstream), it
+    /// circulates forever and the delay is "poisoned" until the next
+    /// `clear()`. With a clip level set, the buffer can never hold
+    /// anything worse than `±limit`.
+    pub fn set_clip_level(&mut self, limit: Option<T>) {
+        self.clip_level = limit;
+    }
+
     /// Write a sample into the delay line at the current write position.
     ///
     /// **Important:** This does NOT advance the write position. Call
     /// [`advance()`](Self::advance) after both `read()` and `write()` are
     /// complete for the current sample. This separation lets us read the
     /// old value before overwriting it.
-    pub fn write(&mut self, sample: f32) {
-        self.buffer[self.write_pos] = sample;
+    pub fn write(&mut self, sample: T) {
+        match self.clip_level {
+            None => self.buffer[self.write_pos] = sample,
+            Some(limit) => self.write_clamped(sample, limit),
+        }
+    }
+
+    /// Write a sample, clamped to `±limit`, replacing NaN and ±infinity
+    /// with silence.
+    ///
+    /// Like [`write()`](Self::write), this does NOT advance the write
+    /// position.
+    ///
+    /// # Why not just `clamp()`?
+    ///
+    /// `clamp()` passes NaN straight through (`NaN.clamp(-1.0, 1.0)` is
+    /// still NaN), so clamping alone can't protect the buffer. Infinity
+    /// *would* clamp to the limit, but a non-finite value means something
+    /// upstream has already gone wrong, so silence is the safer answer.
+    ///
+    /// The first comparison handles the overwhelmingly common case — a
+    /// normal sample inside the limit — with a single, perfectly
+    /// predictable branch. (Any comparison with NaN is `false`, so NaN
+    /// falls through to the slow path.)
+    pub fn write_clamped(&mut self, sample: T, limit: T) {
+        self.buffer[self.write_pos] = clamp_sample(sample, limit);
+    }
+
+    /// Replace the sample `delay_samples` behind the write head, with
+    /// the same clip level as [`write()`](Self::write).
+    ///
+    /// Delays use the convention of [`read_int()`](Self::read_int), and
+    /// the delay is clamped to the buffer the same way, so reading a
+    /// sample and writing it back lands on the same slot. The write
+    /// position doesn't move. Overdubbing a frozen loop uses it: the
+    /// heads stand still while playback goes round the region.
+    pub fn write_behind(&mut self, delay_samples: usize, sample: T) {
+        let index = self.index_for_delay(delay_samples.min(self.buffer_len - 1));
+        self.buffer[index] = match self.clip_level {
+            None => sample,
+            Some(limit) => clamp_sample(sample, limit),
+        };
     }
 
     /// Read a delayed sample from the buffer using linear interpolation.
@@ -125,17 +218,31 @@ impl DelayLine {
     /// (5 + 100 - 10) % 100 = 95
     /// ```
     /// Position 95 is indeed 10 steps behind position 5 on a ring of 100.
-    pub fn read(&self, delay_samples: f32) -> f32 {
+    ///
+    /// # Capacity
+    ///
+    /// The longest delay a line can serve is `buffer_len - 1` samples:
+    /// the interpolation reads one slot further back than the delay.
+    /// Asking for more is a sizing bug in the caller, so debug builds
+    /// assert on it. Release builds clamp to the longest delay instead,
+    /// which is wrong but safe.
+    pub fn read(&self, delay_samples: T) -> T {
+        debug_assert!(
+            delay_samples <= T::from_usize(self.buffer_len - 1),
+            "read past the end of a {}-sample line",
+            self.buffer_len
+        );
+
         // Clamp to valid range: at least 0 samples, at most the full buffer.
-        let delay_clamped = delay_samples.clamp(0.0, (self.buffer_len - 1) as f32);
+        let delay_clamped = delay_samples.clamp(T::ZERO, T::from_usize(self.buffer_len - 1));
 
         // Split into integer and fractional parts.
         //
         // For delay_samples = 441.3:
         //   delay_int  = 441   (which buffer slots to look at)
         //   delay_frac = 0.3   (how much to blend between them)
-        let delay_int = delay_clamped as usize;
-        let delay_frac = delay_clamped - delay_int as f32;
+        let delay_int = delay_clamped.to_usize();
+        let delay_frac = delay_clamped - T::from_usize(delay_int);
 
         // Calculate two adjacent read positions in the ring buffer.
         // index_a is the "earlier" sample (closer in time to now).
@@ -155,7 +262,20 @@ impl DelayLine {
         //
         // This ensures smooth, artifact-free output when the delay time
         // is changed continuously (e.g., by automating the knob).
-        sample_a * (1.0 - delay_frac) + sample_b * delay_frac
+        sample_a * (T::ONE - delay_frac) + sample_b * delay_frac
+    }
+
+    /// Read a sample exactly `delay_samples` behind the write head, with
+    /// no interpolation.
+    ///
+    /// When the delay is a whole number of samples, the fractional part
+    /// in [`read()`](Self::read) is 0 and the blend reduces to
+    /// `sample_a * 1.0 + sample_b * 0.0` — two reads and two multiplies
+    /// to produce `sample_a`. This fast path skips straight to it. The
+    /// delay is clamped to the buffer just like `read()`.
+    pub fn read_int(&self, delay_samples: usize) -> T {
+        let delay = delay_samples.min(self.buffer_len - 1);
+        self.buffer[self.index_for_delay(delay)]
     }
 
     /// Advance the write position by one sample.
@@ -165,6 +285,7 @@ impl DelayLine {
     /// the end of the buffer, creating the circular behavior.
     pub fn advance(&mut self) {
         self.write_pos = (self.write_pos + 1) % self.buffer_len;
+        self.written = (self.written + 1).min(self.buffer_len);
     }
 
     /// Clear the entire buffer to silence and reset the write position.
@@ -172,8 +293,248 @@ impl DelayLine {
     /// Called during plugin `reset()` (when the user stops playback)
     /// to prevent stale audio from bleeding into the next play session.
     pub fn clear(&mut self) {
-        self.buffer.fill(0.0);
+        self.buffer.fill(T::ZERO);
         self.write_pos = 0;
+        self.written = 0;
+    }
+
+    /// Make this line a copy of `other`: the same samples, the same
+    /// write position. Copies in place, without allocating, so it's safe
+    /// on the audio thread. `other` should be the same length; if it
+    /// isn't, only the shorter length is copied.
+    pub fn copy_from(&mut self, other: &Self) {
+        let len = self.buffer_len.min(other.buffer_len);
+        self.buffer[..len].copy_from_slice(&other.buffer[..len]);
+        self.write_pos = other.write_pos % self.buffer_len;
+        self.written = other.written.min(self.buffer_len);
+    }
+
+    /// Like [`copy_from()`](Self::copy_from), but only the newest
+    /// `samples` samples (delays 1 to `samples`, as in
+    /// [`read()`](Self::read)), so the work is proportional to them
+    /// rather than the whole buffer. Older samples are left as they were
+    /// and no longer count as written, so only copy as far back as
+    /// anything will read.
+    pub fn copy_recent_from(&mut self, other: &Self, samples: usize) {
+        let len = self.buffer_len.min(other.buffer_len);
+        if samples >= len {
+            self.copy_from(other);
+            return;
+        }
+        self.write_pos = other.write_pos % self.buffer_len;
+        for delay in 1..=samples {
+            let index = self.index_for_delay(delay);
+            self.buffer[index] = other.buffer[other.index_for_delay(delay)];
+        }
+        self.written = other.written.min(samples);
+    }
+
+    /// Silence only the samples between `start_delay_samples` (inclusive)
+    /// and `end_delay_samples` (exclusive) behind the write head.
+    ///
+    /// Delays use the same convention as [`read()`](Self::read): 1 is
+    /// the most recently written sample (after `advance()`), 2 the one
+    /// before it, and so on. The range is clamped to the buffer length.
+    ///
+    /// Unlike [`clear()`](Self::clear), the work is proportional to the
+    /// size of the range, not the whole buffer, and the write position
+    /// doesn't move — so it's cheap enough to call from `process()`.
+    pub fn clear_range(&mut self, start_delay_samples: usize, end_delay_samples: usize) {
+        let end = end_delay_samples.min(self.buffer_len);
+        for delay in start_delay_samples..end {
+            let index = self.index_for_delay(delay);
+            self.buffer[index] = T::ZERO;
+        }
+    }
+
+    /// Apply a linear fade-out to the most recent `samples` samples, in
+    /// place.
+    ///
+    /// The newest sample (delay 1) is scaled to silence and the gain
+    /// rises linearly back toward unity for older samples:
+    ///
+    /// ```text
+    /// gain(delay) = (delay - 1) / samples     for delay in 1..=samples
+    /// ```
+    ///
+    /// Played back, the audio therefore ends with a smooth ramp to zero
+    /// instead of a hard cut — useful when disabling a channel or leaving
+    /// a frozen loop. Work is proportional to `samples`.
+    pub fn fade_out(&mut self, samples: usize) {
+        let samples = samples.min(self.buffer_len);
+        let step = T::ONE / T::from_usize(samples.max(1));
+        for delay in 1..=samples {
+            let index = self.index_for_delay(delay);
+            self.buffer[index] *= T::from_usize(delay - 1) * step;
+        }
+    }
+
+    /// Crossfade the end of a loop region into the audio that came just
+    /// before its start, so the region loops without a click.
+    ///
+    /// The region is the newest `loop_len` samples (delays `loop_len`
+    /// down to 1). Played round and round, it jumps from its newest
+    /// sample straight back to its oldest, and unless the audio happens
+    /// to line up there, the jump clicks. So the last stretch of the
+    /// region fades over to the audio that *preceded* its start: by the
+    /// time the loop wraps, it's playing whatever originally ran into
+    /// the start, and the wrap continues it seamlessly.
+    ///
+    /// ```text
+    ///          ┌──────────── region ────────────┐
+    /// ... p p p│ s s s s s s s s s s s e e e e e│  ← write head
+    ///    └──┬──┘                      └───┬───┘
+    ///    before                   e faded over to p
+    /// ```
+    ///
+    /// `gains` gives one `(keep, take)` pair per sample of the fade,
+    /// from its first sample to the one just before the wrap: `keep`
+    /// scales the region's own sample, `take` the one from before it.
+    /// The fade is cut short if the region or the line can't hold it.
+    /// Work is proportional to the fade, not the region.
+    pub fn blend_seam(&mut self, loop_len: usize, gains: impl ExactSizeIterator<Item = (T, T)>) {
+        let fade = gains
+            .len()
+            .min(loop_len)
+            .min(self.buffer_len.saturating_sub(loop_len));
+        for (k, (keep, take)) in gains.take(fade).enumerate() {
+            let end = self.index_for_delay(fade - k);
+            let before = self.index_for_delay(loop_len + fade - k);
+            self.buffer[end] = self.buffer[end] * keep + self.buffer[before] * take;
+        }
+    }
+
+    /// The longest delay the line can hold, in samples: its buffer
+    /// length.
+    pub fn capacity(&self) -> usize {
+        self.buffer_len
+    }
+
+    /// How much memory the buffer takes, in bytes: what it was actually
+    /// allocated, not just what it's using.
+    pub fn memory_bytes(&self) -> usize {
+        self.buffer.capacity() * std::mem::size_of::<T>()
+    }
+
+    /// How many samples of real content the line holds: the number of
+    /// samples written since creation or the last `clear()`, up to the
+    /// buffer length.
+    pub fn len_written(&self) -> usize {
+        self.written
+    }
+
+    /// Iterate over the stored content in chronological order, oldest
+    /// sample first and the most recently written sample last.
+    ///
+    /// Only [`len_written()`](Self::len_written) samples are yielded, so
+    /// a fresh line doesn't pretend its initial silence is audio.
+    ///
+    /// # Handling the wrap
+    ///
+    /// Once the line has wrapped, the oldest sample sits *at* the write
+    /// head and the chronological order is split in two:
+    ///
+    /// ```text
+    /// buffer:  [ 5  6  7 | 2  3  4 ]      write_pos = 3
+    ///            newer     older
+    /// ordered: 2 3 4 5 6 7
+    /// ```
+    pub fn iter_ordered(&self) -> impl Iterator<Item = T> + '_ {
+        let (older, newer) = self.ordered_slices();
+        older.iter().chain(newer.iter()).copied()
+    }
+
+    /// Copy the most recent content into `dest` in chronological order
+    /// (oldest first), returning the number of samples copied.
+    ///
+    /// If `dest` is shorter than [`len_written()`](Self::len_written),
+    /// only the newest `dest.len()` samples are copied. Any part of
+    /// `dest` beyond the returned count is left untouched. Doesn't
+    /// allocate, so it's safe to call from `process()`.
+    pub fn copy_ordered_into(&self, dest: &mut [T]) -> usize {
+        let count = dest.len().min(self.written);
+        let (older, newer) = self.ordered_slices();
+
+        // Skip the oldest samples that don't fit.
+        let skip = self.written - count;
+        let newer = &newer[skip.saturating_sub(older.len())..];
+        let older = &older[skip.min(older.len())..];
+
+        dest[..older.len()].copy_from_slice(older);
+        dest[older.len()..count].copy_from_slice(newer);
+        count
+    }
+
+    /// The written content as two slices (older part, newer part) that
+    /// concatenate into chronological order.
+    fn ordered_slices(&self) -> (&[T], &[T]) {
+        if self.written == 0 {
+            return (&[], &[]);
+        }
+        let start = self.index_for_delay(self.written);
+        if start < self.write_pos {
+            (&self.buffer[start..self.write_pos], &[])
+        } else {
+            (&self.buffer[start..], &self.buffer[..self.write_pos])
+        }
+    }
+
+    /// Physical buffer index of the sample `delay` steps behind the
+    /// write head (wrapping around the start of the buffer).
+    fn index_for_delay(&self, delay: usize) -> usize {
+        (self.write_pos + self.buffer_len - delay) % self.buffer_len
+    }
+}
+
+/// `sample` clamped to `±limit`, with NaN and ±infinity replaced by
+/// silence. See [`DelayLine::write_clamped()`].
+#[inline]
+fn clamp_sample<T: Sample>(sample: T, limit: T) -> T {
+    if sample.abs() <= limit {
+        sample
+    } else if sample.is_finite() {
+        if sample > T::ZERO {
+            limit
+        } else {
+            -limit
+        }
+    } else {
+        T::ZERO
+    }
+}
+
+/// A read head over a [`DelayLine`], with its own delay offset.
+///
+/// Readers don't own any audio — they're just "how far back to look" —
+/// so they're `Copy` and cost nothing to create. Keep as many as you
+/// need per channel and read them all after `write()`, before
+/// `advance()`, just like a plain [`DelayLine::read()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DelayReader<T: Sample = f32> {
+    /// How many samples behind the write head this reader looks. Can be
+    /// fractional; the line interpolates between neighbors.
+    delay_samples: T,
+}
+
+impl<T: Sample> DelayReader<T> {
+    /// Create a reader that looks `delay_samples` behind the write head.
+    pub fn new(delay_samples: T) -> Self {
+        Self { delay_samples }
+    }
+
+    /// Move this read head to a new delay offset.
+    pub fn set_delay(&mut self, delay_samples: T) {
+        self.delay_samples = delay_samples;
+    }
+
+    /// The current delay offset in samples.
+    pub fn delay(&self) -> T {
+        self.delay_samples
+    }
+
+    /// Read this head's (interpolated) sample from `line`.
+    pub fn read(&self, line: &DelayLine<T>) -> T {
+        line.read(self.delay_samples)
     }
 }
 
@@ -194,7 +555,7 @@ mod tests {
     /// Verify basic write-then-read at an exact sample position.
     #[test]
     fn test_write_and_read_exact() {
-        let mut dl = DelayLine::new(nz(100));
+        let mut dl: DelayLine = DelayLine::new(nz(100));
 
         // Write 0.75 at position 0, then advance to position 1.
         dl.write(0.75);
@@ -208,7 +569,7 @@ mod tests {
     /// Verify linear interpolation between two samples.
     #[test]
     fn test_interpolation() {
-        let mut dl = DelayLine::new(nz(100));
+        let mut dl: DelayLine = DelayLine::new(nz(100));
 
         // Write two known values: 0.0 at pos 0, then 1.0 at pos 1.
         dl.write(0.0);
@@ -227,7 +588,7 @@ mod tests {
     /// Verify the buffer wraps correctly past its boundaries.
     #[test]
     fn test_wrapping() {
-        let mut dl = DelayLine::new(nz(4));
+        let mut dl: DelayLine = DelayLine::new(nz(4));
 
         // Write values 0 through 5 into a buffer of size 4.
         // The buffer will contain the last 4 values written.
@@ -248,10 +609,53 @@ mod tests {
         assert!((result - 5.0).abs() < 1e-6, "Expected 5.0, got {result}");
     }
 
+    /// The seam blend fades the region's last samples over to the ones
+    /// before its start, leaving the rest of the region alone.
+    #[test]
+    fn test_blend_seam() {
+        let mut dl: DelayLine = DelayLine::new(nz(16));
+        for i in 0..12 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+
+        // Region: the newest 6 samples (6..=11); before it, 2..=5.
+        let gains = [(1.0, 0.0), (0.5, 0.5), (0.0, 1.0)];
+        dl.blend_seam(6, gains.into_iter());
+
+        let region: Vec<f32> = (1..=6).rev().map(|d| dl.read_int(d)).collect();
+        // 9 kept, 10 halfway to 4, 11 replaced by 5 — which runs
+        // straight on into the region's first sample, 6.
+        assert_eq!(region, vec![6.0, 7.0, 8.0, 9.0, 7.0, 5.0]);
+    }
+
+    /// Writing behind the head replaces the slot a read at the same
+    /// delay sees, clip level and all, and leaves the head where it is.
+    #[test]
+    fn test_write_behind() {
+        let mut dl: DelayLine = DelayLine::new(nz(8));
+        for i in 0..10 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+        dl.write_behind(3, 0.25);
+        assert_eq!(dl.read_int(3), 0.25);
+        assert_eq!([dl.read_int(2), dl.read_int(4)], [8.0, 6.0]);
+
+        dl.set_clip_level(Some(1.0));
+        dl.write_behind(5, f32::NAN);
+        dl.write_behind(1, -3.0);
+        assert_eq!([dl.read_int(5), dl.read_int(1)], [0.0, -1.0]);
+
+        dl.write(10.0);
+        dl.advance();
+        assert_eq!(dl.read_int(4), 0.25, "the head didn't move");
+    }
+
     /// Verify that clearing resets everything to silence.
     #[test]
     fn test_clear() {
-        let mut dl = DelayLine::new(nz(10));
+        let mut dl: DelayLine = DelayLine::new(nz(10));
 
         dl.write(0.5);
         dl.advance();
@@ -265,10 +669,53 @@ mod tests {
         );
     }
 
+    /// A copy reads back exactly what the original does, and the two go
+    /// their own ways after.
+    #[test]
+    fn test_copy_from() {
+        let mut original: DelayLine = DelayLine::new(nz(8));
+        for i in 1..=10 {
+            original.write(i as f32);
+            original.advance();
+        }
+        let mut copy: DelayLine = DelayLine::new(nz(8));
+        copy.copy_from(&original);
+        for delay in 1..8 {
+            assert_eq!(copy.read_int(delay), original.read_int(delay));
+        }
+
+        copy.write(-1.0);
+        copy.advance();
+        original.write(1.0);
+        original.advance();
+        assert_eq!(copy.read_int(1), -1.0);
+        assert_eq!(copy.read_int(2), original.read_int(2));
+    }
+
+    /// Copying only the newest samples reads back the same over that
+    /// span, across the wrap, and leaves what's older alone.
+    #[test]
+    fn test_copy_recent_from() {
+        let mut original: DelayLine = DelayLine::new(nz(8));
+        for i in 1..=10 {
+            original.write(i as f32);
+            original.advance();
+        }
+        let mut copy: DelayLine = DelayLine::new(nz(8));
+        copy.copy_recent_from(&original, 4);
+        for delay in 1..=4 {
+            assert_eq!(copy.read_int(delay), original.read_int(delay));
+        }
+        for delay in 5..8 {
+            assert_eq!(copy.read_int(delay), 0.0);
+        }
+        assert_eq!(copy.len_written(), 4);
+    }
+
     /// A buffer initialized to silence should output silence at any delay.
     #[test]
     fn test_silence_in_silence_out() {
-        let dl = DelayLine::new(nz(100));
+        let dl: DelayLine = DelayLine::new(nz(100));
 
         for delay in [1.0, 10.0, 50.0, 99.0] {
             let result = dl.read(delay);
@@ -283,7 +730,7 @@ mod tests {
     /// produces the correct sequence (FIFO behavior).
     #[test]
     fn test_fifo_sequence() {
-        let mut dl = DelayLine::new(nz(10));
+        let mut dl: DelayLine = DelayLine::new(nz(10));
 
         // Write a recognizable sequence: 1, 2, 3, 4, 5
         for i in 1..=5 {
@@ -301,4 +748,298 @@ mod tests {
         assert!((dl.read(4.0) - 2.0).abs() < 1e-6);
         assert!((dl.read(5.0) - 1.0).abs() < 1e-6);
     }
+
+    /// The generic `f32` path must be bit-for-bit identical to the
+    /// original hand-written `f32` read. This reference reproduces the
+    /// pre-generic math exactly.
+    #[test]
+    fn test_f32_matches_reference_bit_for_bit() {
+        fn reference_read(buffer: &[f32], write_pos: usize, delay_samples: f32) -> f32 {
+            let len = buffer.len();
+            let delay_clamped = delay_samples.clamp(0.0, (len - 1) as f32);
+            let delay_int = delay_clamped as usize;
+            let delay_frac = delay_clamped - delay_int as f32;
+            let index_a = (write_pos + len - delay_int) % len;
+            let index_b = (write_pos + len - delay_int - 1) % len;
+            buffer[index_a] * (1.0 - delay_frac) + buffer[index_b] * delay_frac
+        }
+
+        let mut dl: DelayLine = DelayLine::new(nz(64));
+        let mut shadow = [0.0_f32; 64];
+        let mut pos = 0;
+
+        for i in 0..200 {
+            let x = (i as f32 * 0.37).sin();
+            dl.write(x);
+            shadow[pos] = x;
+            dl.advance();
+            pos = (pos + 1) % 64;
+
+            for delay in [0.0, 1.0, 2.5, 17.3, 40.999, 63.0] {
+                let got = dl.read(delay);
+                let expected = reference_read(&shadow, pos, delay);
+                assert_eq!(got.to_bits(), expected.to_bits(), "delay {delay}");
+            }
+        }
+    }
+
+    /// Reading further back than the line can hold is a sizing bug, and
+    /// debug builds catch it.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "read past the end of a 64-sample line")]
+    fn test_read_past_capacity_asserts() {
+        let dl: DelayLine = DelayLine::new(nz(64));
+        dl.read(63.5);
+    }
+
+    /// An `f64` line stores and interpolates the same signal, just with
+    /// more precision.
+    #[test]
+    fn test_f64_line_matches_f32_line() {
+        let mut dl32: DelayLine = DelayLine::new(nz(100));
+        let mut dl64: DelayLine<f64> = DelayLine::new(nz(100));
+
+        for i in 0..150 {
+            let x = (i as f32 * 0.1).sin();
+            dl32.write(x);
+            dl64.write(f64::from(x));
+            dl32.advance();
+            dl64.advance();
+        }
+
+        for delay in [1.0_f32, 7.25, 50.5, 99.0] {
+            let a = dl32.read(delay);
+            let b = dl64.read(f64::from(delay));
+            assert!((f64::from(a) - b).abs() < 1e-6, "delay {delay}: {a} vs {b}");
+        }
+    }
+
+    /// Three readers at different delays over one line must produce the
+    /// same output as calling `read()` three times with those delays.
+    #[test]
+    fn test_multiple_readers_match_direct_reads() {
+        let mut dl: DelayLine = DelayLine::new(nz(64));
+        let mut readers = [
+            DelayReader::new(3.0),
+            DelayReader::new(10.5),
+            DelayReader::new(41.25),
+        ];
+
+        for i in 0..300 {
+            dl.write((i as f32 * 0.13).sin());
+
+            for reader in &readers {
+                let expected = dl.read(reader.delay());
+                assert_eq!(reader.read(&dl).to_bits(), expected.to_bits());
+            }
+
+            dl.advance();
+
+            // Move one head halfway through to check that readers are
+            // independent of each other.
+            if i == 150 {
+                readers[1].set_delay(20.0);
+            }
+        }
+
+        assert!((readers[0].delay() - 3.0).abs() < 1e-6);
+        assert!((readers[1].delay() - 20.0).abs() < 1e-6);
+        assert!((readers[2].delay() - 41.25).abs() < 1e-6);
+    }
+
+    /// Non-finite writes must never reach the buffer through the clamped
+    /// path, so reads stay finite no matter what was written.
+    #[test]
+    fn test_write_clamped_rejects_non_finite() {
+        let mut dl: DelayLine = DelayLine::new(nz(8));
+
+        for bad in [f32::INFINITY, f32::NEG_INFINITY, f32::NAN, 10.0, -10.0] {
+            dl.write_clamped(bad, 2.0);
+            dl.advance();
+        }
+
+        for delay in [0.5, 1.0, 2.5, 3.0, 4.0, 5.0, 7.0] {
+            let result = dl.read(delay);
+            assert!(result.is_finite(), "delay {delay} returned {result}");
+            assert!(
+                result.abs() <= 2.0,
+                "delay {delay} exceeded limit: {result}"
+            );
+        }
+
+        // Finite values beyond the limit are clamped, not silenced.
+        assert!((dl.read(2.0) - 2.0).abs() < 1e-6);
+        assert!((dl.read(1.0) + 2.0).abs() < 1e-6);
+    }
+
+    /// With a clip level configured, plain `write()` is protected too;
+    /// without one, it stores samples untouched.
+    #[test]
+    fn test_clip_level_flag_routes_write() {
+        let mut dl: DelayLine = DelayLine::new(nz(8));
+
+        dl.write(5.0);
+        dl.advance();
+        assert!(
+            (dl.read(1.0) - 5.0).abs() < 1e-6,
+            "unprotected write clamped"
+        );
+
+        dl.set_clip_level(Some(1.0));
+        dl.write(f32::NAN);
+        dl.advance();
+        dl.write(5.0);
+        dl.advance();
+        assert!(dl.read(2.0).abs() < 1e-6, "NaN should become silence");
+        assert!((dl.read(1.0) - 1.0).abs() < 1e-6, "5.0 should clamp to 1.0");
+    }
+
+    /// `clear_range()` must zero exactly the requested delays, including
+    /// when the region wraps past the start of the buffer.
+    #[test]
+    fn test_clear_range_across_wrap() {
+        let mut dl: DelayLine = DelayLine::new(nz(8));
+
+        // Fill with 1..=10 so write_pos ends at 10 % 8 = 2.
+        // Buffer: [9, 10, 3, 4, 5, 6, 7, 8]
+        for i in 1..=10 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+
+        // Delays 1..4 are physical indices 1, 0, 7 (wrapping).
+        dl.clear_range(1, 4);
+        assert_eq!(dl.buffer, [0.0, 0.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0]);
+        assert_eq!(dl.write_pos, 2, "clear_range must not move the write head");
+    }
+
+    /// Ranges past the end of the buffer are clamped instead of wrapping
+    /// around a second time.
+    #[test]
+    fn test_clear_range_clamps_to_buffer() {
+        let mut dl: DelayLine = DelayLine::new(nz(4));
+        for i in 1..=4 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+
+        // A ring of 4 holds delays 0..=3, so the range stops at delay 3
+        // (index 1) rather than wrapping on to index 0 (delay 4 == 0).
+        dl.clear_range(3, 100);
+        assert_eq!(dl.buffer, [1.0, 0.0, 3.0, 4.0]);
+    }
+
+    /// `fade_out()` ramps the newest samples to silence across the wrap
+    /// boundary and leaves older samples alone.
+    #[test]
+    fn test_fade_out_across_wrap() {
+        let mut dl: DelayLine = DelayLine::new(nz(8));
+
+        // Write 1.0 ten times so write_pos = 2.
+        for _ in 0..10 {
+            dl.write(1.0);
+            dl.advance();
+        }
+
+        dl.fade_out(4);
+
+        // Delay 1 → index 1 (gain 0), delay 2 → index 0 (gain 0.25),
+        // delay 3 → index 7 (gain 0.5), delay 4 → index 6 (gain 0.75).
+        assert_eq!(dl.buffer, [0.25, 0.0, 1.0, 1.0, 1.0, 1.0, 0.75, 0.5]);
+    }
+
+    /// Before the first wrap, only the samples actually written are
+    /// reported, oldest first.
+    #[test]
+    fn test_iter_ordered_before_wrap() {
+        let mut dl: DelayLine = DelayLine::new(nz(8));
+        assert_eq!(dl.len_written(), 0);
+        assert_eq!(dl.iter_ordered().count(), 0, "fresh line has no content");
+
+        for i in 1..=3 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+
+        assert_eq!(dl.len_written(), 3);
+        assert_eq!(dl.iter_ordered().collect::<Vec<_>>(), [1.0, 2.0, 3.0]);
+    }
+
+    /// Right after wrapping, the oldest sample is at the write head and
+    /// the order must stitch the two halves of the ring together.
+    #[test]
+    fn test_iter_ordered_after_wrap() {
+        let mut dl: DelayLine = DelayLine::new(nz(6));
+        for i in 1..=7 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+
+        // Buffer: [7, 2, 3, 4, 5, 6], write_pos = 1.
+        assert_eq!(dl.len_written(), 6);
+        assert_eq!(
+            dl.iter_ordered().collect::<Vec<_>>(),
+            [2.0, 3.0, 4.0, 5.0, 6.0, 7.0]
+        );
+
+        // Exactly one full lap: write_pos back at 0, no split needed.
+        let mut dl: DelayLine = DelayLine::new(nz(4));
+        for i in 1..=4 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+        assert_eq!(dl.iter_ordered().collect::<Vec<_>>(), [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    /// `copy_ordered_into()` agrees with `iter_ordered()` and keeps only
+    /// the newest samples when the destination is short.
+    #[test]
+    fn test_copy_ordered_into() {
+        let mut dl: DelayLine = DelayLine::new(nz(6));
+        for i in 1..=8 {
+            dl.write(i as f32);
+            dl.advance();
+        }
+
+        let mut full = [0.0; 6];
+        assert_eq!(dl.copy_ordered_into(&mut full), 6);
+        assert_eq!(full.to_vec(), dl.iter_ordered().collect::<Vec<_>>());
+
+        // Newest four samples only; 5 and 6 straddle the wrap split.
+        let mut short = [0.0; 4];
+        assert_eq!(dl.copy_ordered_into(&mut short), 4);
+        assert_eq!(short, [5.0, 6.0, 7.0, 8.0]);
+
+        // A long destination is only partly filled.
+        let mut long = [-1.0; 8];
+        assert_eq!(dl.copy_ordered_into(&mut long), 6);
+        assert_eq!(long, [3.0, 4.0, 5.0, 6.0, 7.0, 8.0, -1.0, -1.0]);
+
+        dl.clear();
+        assert_eq!(dl.len_written(), 0);
+        assert_eq!(dl.copy_ordered_into(&mut full), 0);
+    }
+
+    /// At whole-sample delays the fast path must agree with the
+    /// interpolated read (to within one ulp), including clamped delays.
+    #[test]
+    fn test_read_int_matches_interpolated_read() {
+        let mut dl: DelayLine = DelayLine::new(nz(32));
+
+        for i in 0..100 {
+            dl.write((i as f32 * 0.29).sin());
+            dl.advance();
+
+            for delay in [0_usize, 1, 2, 15, 31] {
+                let fast = dl.read_int(delay);
+                let interp = dl.read(delay as f32);
+                let ulp = f32::EPSILON * interp.abs().max(f32::MIN_POSITIVE);
+                assert!(
+                    (fast - interp).abs() <= ulp,
+                    "delay {delay}: read_int {fast} vs read {interp}"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,224 @@
+//! # Envelope Follower
+//!
+//! An envelope follower turns audio into a slowly moving *level*: how
+//! loud the signal is right now, ignoring the individual wiggles of its
+//! waveform. Ducking ("turn the echoes down while the dry signal plays"),
+//! feedback that responds to playing dynamics, and gates all start from
+//! this one measurement.
+//!
+//! ## Detection: Peak or RMS
+//!
+//! - **Peak** follows the rectified signal, `|x|`. It reacts to every
+//!   transient, which is what a gate or a fast ducker wants.
+//! - **RMS** (root-mean-square) follows the signal's *average power*:
+//!   square the signal, average it with a one-pole lowpass, take the
+//!   square root. It tracks perceived loudness rather than peaks — a
+//!   full-scale sine reads `1/√2 ≈ 0.707`, not 1.0.
+//!
+//! ## Attack and Release
+//!
+//! The detected level then passes through one-pole smoothing with two
+//! time constants: the *attack* while the level is rising, the *release*
+//! while it's falling.
+//!
+//! ```text
+//! envelope = level + (envelope - level) * coeff
+//! coeff    = e^(-1 / (time_constant * sample_rate))
+//! ```
+//!
+//! This is the same smoothing the limiter's release uses. After one time
+//! constant the envelope has covered `1 - 1/e ≈ 63%` of the distance to
+//! a new level, and after five it's within 1%.
+
+use super::filter::OnePoleFilter;
+
+/// How long the RMS detector averages over (its time constant), in
+/// milliseconds. Long enough to smooth out the cycles of a 20 Hz tone;
+/// the attack and release then shape how the level moves.
+const RMS_WINDOW_MS: f32 = 20.0;
+
+/// How an [`EnvelopeFollower`] measures the input level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DetectorMode {
+    /// Follow the rectified signal: reacts to every transient.
+    #[default]
+    Peak,
+    /// Follow the signal's average power: tracks loudness.
+    Rms,
+}
+
+/// A peak or RMS level detector with separate attack and release.
+#[derive(Clone)]
+pub struct EnvelopeFollower {
+    sample_rate: f32,
+    mode: DetectorMode,
+
+    /// Smoothing coefficients for rising and falling levels.
+    attack_coeff: f32,
+    release_coeff: f32,
+
+    /// Averages the squared signal in RMS mode.
+    mean_square: OnePoleFilter,
+
+    /// The current envelope value.
+    envelope: f32,
+}
+
+impl EnvelopeFollower {
+    /// Create a peak follower with 10 ms attack and 100 ms release.
+    pub fn new(sample_rate: f32) -> Self {
+        // A one-pole's time constant is 1 / (2π · cutoff).
+        let mut mean_square = OnePoleFilter::new();
+        mean_square.set_cutoff(
+            1000.0 / (std::f32::consts::TAU * RMS_WINDOW_MS),
+            sample_rate,
+        );
+
+        let mut follower = Self {
+            sample_rate,
+            mode: DetectorMode::Peak,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            mean_square,
+            envelope: 0.0,
+        };
+        follower.set_attack_ms(10.0);
+        follower.set_release_ms(100.0);
+        follower
+    }
+
+    /// Choose peak or RMS detection.
+    pub fn set_mode(&mut self, mode: DetectorMode) {
+        self.mode = mode;
+    }
+
+    /// Set the attack time constant in milliseconds.
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_coeff = time_constant_coeff(attack_ms, self.sample_rate);
+    }
+
+    /// Set the release time constant in milliseconds.
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_coeff = time_constant_coeff(release_ms, self.sample_rate);
+    }
+
+    /// Feed one sample in and return the envelope.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let level = match self.mode {
+            DetectorMode::Peak => input.abs(),
+            DetectorMode::Rms => self.mean_square.process(input * input).max(0.0).sqrt(),
+        };
+
+        let coeff = if level > self.envelope {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.envelope = level + (self.envelope - level) * coeff;
+        self.envelope
+    }
+
+    /// The current envelope, without feeding in a sample.
+    pub fn envelope(&self) -> f32 {
+        self.envelope
+    }
+
+    /// Clear the envelope and the RMS average.
+    pub fn reset(&mut self) {
+        self.mean_square.reset();
+        self.envelope = 0.0;
+    }
+}
+
+/// The one-pole coefficient for a time constant of `time_ms`. Zero (or
+/// less) gives a coefficient of 0: the envelope follows instantly.
+fn time_constant_coeff(time_ms: f32, sample_rate: f32) -> f32 {
+    if time_ms <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (time_ms / 1000.0 * sample_rate)).exp()
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Samples in one 10 ms time constant.
+    const TEN_MS: usize = 480;
+
+    /// A step up reaches 63% (1 - 1/e) after one attack time constant.
+    #[test]
+    fn test_attack_time_constant() {
+        let mut follower = EnvelopeFollower::new(SAMPLE_RATE);
+        follower.set_attack_ms(10.0);
+
+        let mut envelope = 0.0;
+        for _ in 0..TEN_MS {
+            envelope = follower.process(1.0);
+        }
+        let expected = 1.0 - (-1.0_f32).exp();
+        assert!((envelope - expected).abs() < 1e-3, "{envelope}");
+    }
+
+    /// With equal attack and release, a step down mirrors a step up:
+    /// 37% (1/e) remains after one release time constant.
+    #[test]
+    fn test_release_mirrors_attack() {
+        let mut follower = EnvelopeFollower::new(SAMPLE_RATE);
+        follower.set_attack_ms(10.0);
+        follower.set_release_ms(10.0);
+
+        let rise: Vec<f32> = (0..TEN_MS * 10).map(|_| follower.process(1.0)).collect();
+        let fall: Vec<f32> = (0..TEN_MS * 10).map(|_| follower.process(0.0)).collect();
+        assert!((fall[TEN_MS - 1] - (-1.0_f32).exp()).abs() < 1e-3);
+        for (up, down) in rise.iter().zip(&fall) {
+            assert!((up + down - 1.0).abs() < 1e-3, "{up} + {down}");
+        }
+    }
+
+    /// RMS mode reads a full-scale sine as 1/√2; peak mode reads its
+    /// peaks.
+    #[test]
+    fn test_rms_of_sine() {
+        let sine = |n: usize| (std::f32::consts::TAU * 1000.0 * n as f32 / SAMPLE_RATE).sin();
+
+        let mut rms = EnvelopeFollower::new(SAMPLE_RATE);
+        rms.set_mode(DetectorMode::Rms);
+        let mut peak = EnvelopeFollower::new(SAMPLE_RATE);
+        peak.set_attack_ms(0.0);
+
+        let (mut rms_level, mut peak_level) = (0.0, 0.0_f32);
+        for n in 0..SAMPLE_RATE as usize {
+            rms_level = rms.process(sine(n));
+            peak_level = peak_level.max(peak.process(sine(n)));
+        }
+        assert!(
+            (rms_level - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01,
+            "{rms_level}"
+        );
+        assert!((peak_level - 1.0).abs() < 1e-3, "{peak_level}");
+    }
+
+    /// Reset clears everything back to silence.
+    #[test]
+    fn test_reset() {
+        let mut follower = EnvelopeFollower::new(SAMPLE_RATE);
+        follower.set_mode(DetectorMode::Rms);
+        for _ in 0..1000 {
+            follower.process(0.8);
+        }
+        follower.reset();
+        assert_eq!(follower.envelope(), 0.0);
+
+        let mut fresh = EnvelopeFollower::new(SAMPLE_RATE);
+        fresh.set_mode(DetectorMode::Rms);
+        assert_eq!(follower.process(0.5), fresh.process(0.5));
+    }
+}
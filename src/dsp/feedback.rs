@@ -0,0 +1,341 @@
+//! # The Feedback Chain
+//!
+//! Everything that happens to a repeat on its way back into the delay
+//! line — darkening, saturation, and whatever comes next — is a *stage*
+//! in the feedback chain. Each stage takes one sample and returns one
+//! sample, and each has state to clear when playback stops. That shared
+//! shape is the [`FeedbackStage`] trait.
+//!
+//! ## Why the Order Matters
+//!
+//! Filtering and saturation don't commute. Run a repeat through the
+//! lowpass *first* and the saturator then adds fresh harmonics on top,
+//! so the repeats stay gritty and bright-edged. Saturate first and the
+//! lowpass files those harmonics back off, for a smoother, warmer
+//! distortion. [`ChainOrder`] picks between the two.
+//!
+//! ## Shelving the Repeats
+//!
+//! Alongside the lowpass, two first-order shelves shape each pass: a
+//! low shelf at [`LOW_SHELF_HZ`] and a high shelf at [`HIGH_SHELF_HZ`],
+//! each ±[`MAX_SHELF_DB`]. Cutting the lows thins the repeats out with
+//! every trip; boosting them makes them boomier. They run just before
+//! the lowpass and move with it when the order changes, so "the filter"
+//! is the whole tone stage: shelves, then lowpass.
+//!
+//! A boost in the loop is a boost on *every* pass, so a shelf raises
+//! the loop gain by its own gain. Each shelf reports that through
+//! [`FeedbackStage::max_gain()`], and the loop-gain ceiling eases the
+//! feedback back to match (see [`loop_gain`](super::loop_gain)). A
+//! +12 dB shelf at 95% feedback would otherwise put the boosted band
+//! round the loop at nearly four times unity.
+//!
+//! ## Enum Dispatch, Not Trait Objects
+//!
+//! The stages are plain struct fields, and [`FeedbackChain::process()`]
+//! runs them with a `match` on the order — no `Box<dyn FeedbackStage>`,
+//! no `Vec` to reorder. That keeps the chain allocation-free (it's built
+//! once, outside the audio thread) and lets the compiler inline every
+//! stage into the per-sample loop.
+
+use super::{
+    filter::{CrossfadingLowpass, HighShelf, LowShelf, OnePoleFilter},
+    saturator::Saturator,
+};
+
+/// Corner of the feedback low shelf, in Hz.
+pub const LOW_SHELF_HZ: f32 = 250.0;
+
+/// Corner of the feedback high shelf, in Hz.
+pub const HIGH_SHELF_HZ: f32 = 4000.0;
+
+/// The most either feedback shelf boosts or cuts, in dB.
+pub const MAX_SHELF_DB: f32 = 12.0;
+
+/// One processing step inside the feedback loop.
+pub trait FeedbackStage {
+    /// Process one sample.
+    fn process(&mut self, input: f32) -> f32;
+
+    /// Clear any internal state (called when playback stops).
+    fn reset(&mut self) {}
+
+    /// The most this stage can amplify any signal, at any frequency and
+    /// level. The loop-gain ceiling multiplies these together (see
+    /// [`loop_gain`](super::loop_gain)), so a stage that can boost must
+    /// say so here.
+    fn max_gain(&self) -> f32 {
+        1.0
+    }
+}
+
+impl FeedbackStage for OnePoleFilter {
+    fn process(&mut self, input: f32) -> f32 {
+        OnePoleFilter::process(self, input)
+    }
+
+    fn reset(&mut self) {
+        OnePoleFilter::reset(self);
+    }
+
+    /// A one-pole lowpass peaks at DC, where it passes the signal at
+    /// exactly unity gain.
+    fn max_gain(&self) -> f32 {
+        1.0
+    }
+}
+
+impl FeedbackStage for CrossfadingLowpass {
+    fn process(&mut self, input: f32) -> f32 {
+        CrossfadingLowpass::process(self, input)
+    }
+
+    fn reset(&mut self) {
+        CrossfadingLowpass::reset(self);
+    }
+
+    /// Both halves of a cutoff crossfade are lowpasses at unity gain at
+    /// DC, and a linear fade between them can't exceed either.
+    fn max_gain(&self) -> f32 {
+        1.0
+    }
+}
+
+impl FeedbackStage for LowShelf {
+    fn process(&mut self, input: f32) -> f32 {
+        LowShelf::process(self, input)
+    }
+
+    fn reset(&mut self) {
+        LowShelf::reset(self);
+    }
+
+    /// A boost lifts the lows by the full gain; a cut amplifies
+    /// nothing.
+    fn max_gain(&self) -> f32 {
+        LowShelf::max_gain(self)
+    }
+}
+
+impl FeedbackStage for HighShelf {
+    fn process(&mut self, input: f32) -> f32 {
+        HighShelf::process(self, input)
+    }
+
+    fn reset(&mut self) {
+        HighShelf::reset(self);
+    }
+
+    /// A boost lifts the highs by at most the full gain; a cut
+    /// amplifies nothing.
+    fn max_gain(&self) -> f32 {
+        HighShelf::max_gain(self)
+    }
+}
+
+impl FeedbackStage for Saturator {
+    fn process(&mut self, input: f32) -> f32 {
+        Saturator::process(self, input)
+    }
+
+    /// The curve never makes a signal louder; only the output trim can.
+    fn max_gain(&self) -> f32 {
+        Saturator::max_gain(self)
+    }
+}
+
+/// The order the feedback stages run in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChainOrder {
+    /// Lowpass, then saturation: the saturator adds harmonics after the
+    /// filter, so driven repeats keep some bite.
+    #[default]
+    FilterThenDrive,
+
+    /// Saturation, then lowpass: the filter smooths away the harmonics
+    /// the saturator creates, for a warmer, darker drive.
+    DriveThenFilter,
+}
+
+/// One channel's feedback stages, run in a selectable order.
+#[derive(Clone)]
+pub struct FeedbackChain {
+    /// The shelf that thins out or fattens the lows of each repeat.
+    pub low_shelf: LowShelf,
+
+    /// The shelf that dulls or brightens the highs of each repeat.
+    pub high_shelf: HighShelf,
+
+    /// The lowpass that darkens each repeat. A preset jump in its cutoff
+    /// crossfades rather than stepping the loop.
+    pub filter: CrossfadingLowpass,
+
+    /// The soft clipper that squashes loud repeats.
+    pub saturator: Saturator,
+
+    /// Which stage runs first.
+    order: ChainOrder,
+}
+
+impl FeedbackChain {
+    /// Create a chain with flat shelves, a transparent filter and no
+    /// drive, for audio at `sample_rate`.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            low_shelf: LowShelf::new(LOW_SHELF_HZ, sample_rate),
+            high_shelf: HighShelf::new(HIGH_SHELF_HZ, sample_rate),
+            filter: CrossfadingLowpass::new(),
+            saturator: Saturator::default(),
+            order: ChainOrder::default(),
+        }
+    }
+
+    /// Choose the order the stages run in.
+    pub fn set_order(&mut self, order: ChainOrder) {
+        self.order = order;
+    }
+
+    /// Set the shelves' linear gains, `[low, high]` (1.0 = flat).
+    pub fn set_shelf_gains(&mut self, [low, high]: [f32; 2]) {
+        self.low_shelf.set_gain(low);
+        self.high_shelf.set_gain(high);
+    }
+
+    /// Run one sample through every stage, in order.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        match self.order {
+            ChainOrder::FilterThenDrive => {
+                let filtered = self.tone(input);
+                FeedbackStage::process(&mut self.saturator, filtered)
+            }
+            ChainOrder::DriveThenFilter => {
+                let driven = FeedbackStage::process(&mut self.saturator, input);
+                self.tone(driven)
+            }
+        }
+    }
+
+    /// The tone stage: both shelves, then the lowpass.
+    #[inline]
+    fn tone(&mut self, input: f32) -> f32 {
+        let shelved = FeedbackStage::process(&mut self.low_shelf, input);
+        let shelved = FeedbackStage::process(&mut self.high_shelf, shelved);
+        FeedbackStage::process(&mut self.filter, shelved)
+    }
+
+    /// The peak gain of the whole chain: the product of its stages'.
+    ///
+    /// With both shelves boosting this counts both boosts at once,
+    /// though they lift opposite ends of the spectrum. It's an upper
+    /// bound, and a bound is what the ceiling needs.
+    pub fn max_gain(&self) -> f32 {
+        FeedbackStage::max_gain(&self.low_shelf)
+            * FeedbackStage::max_gain(&self.high_shelf)
+            * FeedbackStage::max_gain(&self.filter)
+            * FeedbackStage::max_gain(&self.saturator)
+    }
+
+    /// Reset every stage.
+    pub fn reset(&mut self) {
+        FeedbackStage::reset(&mut self.low_shelf);
+        FeedbackStage::reset(&mut self.high_shelf);
+        FeedbackStage::reset(&mut self.filter);
+        FeedbackStage::reset(&mut self.saturator);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Run a loud 500 Hz sine through a chain and return the energy in
+    /// the sample-to-sample differences — a simple measure of how much
+    /// high-frequency (harmonic) content the output has.
+    fn brightness(order: ChainOrder) -> f32 {
+        let mut chain = FeedbackChain::new(SAMPLE_RATE);
+        chain.set_order(order);
+        chain.filter.set_cutoff(1000.0, SAMPLE_RATE);
+        chain.saturator.set_drive(1.0);
+
+        let step = 2.0 * std::f32::consts::PI * 500.0 / SAMPLE_RATE;
+        let output: Vec<f32> = (0..4800)
+            .map(|n| chain.process(0.9 * (n as f32 * step).sin()))
+            .collect();
+
+        // Normalize by level so only the *shape* is compared.
+        let energy: f32 = output[480..].iter().map(|s| s * s).sum();
+        let diff: f32 = output[480..]
+            .windows(2)
+            .map(|w| (w[1] - w[0]).powi(2))
+            .sum();
+        diff / energy
+    }
+
+    /// Filtering after the drive removes the harmonics it adds, so
+    /// drive-then-filter must come out darker than filter-then-drive.
+    #[test]
+    fn test_order_changes_harmonic_content() {
+        let filter_first = brightness(ChainOrder::FilterThenDrive);
+        let drive_first = brightness(ChainOrder::DriveThenFilter);
+        assert!(
+            filter_first > drive_first * 1.2,
+            "filter→drive should be brighter: {filter_first} vs {drive_first}"
+        );
+    }
+
+    /// With no drive the order can't matter: the chain is just the
+    /// filter.
+    #[test]
+    fn test_zero_drive_is_just_the_filter() {
+        let mut a = FeedbackChain::new(SAMPLE_RATE);
+        let mut b = FeedbackChain::new(SAMPLE_RATE);
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
+        a.set_order(ChainOrder::FilterThenDrive);
+        b.set_order(ChainOrder::DriveThenFilter);
+        for f in [&mut a.filter, &mut b.filter] {
+            f.set_cutoff(2000.0, SAMPLE_RATE);
+        }
+        filter.set_cutoff(2000.0, SAMPLE_RATE);
+
+        for n in 0..1000 {
+            let x = (n as f32 * 0.21).sin();
+            let expected = filter.process(x);
+            assert_eq!(a.process(x), expected);
+            assert_eq!(b.process(x), expected);
+        }
+    }
+
+    /// Reset clears the filter's memory.
+    #[test]
+    fn test_reset() {
+        let mut chain = FeedbackChain::new(SAMPLE_RATE);
+        chain.filter.set_cutoff(500.0, SAMPLE_RATE);
+        chain.process(1.0);
+        chain.reset();
+        let mut fresh = FeedbackChain::new(SAMPLE_RATE);
+        fresh.filter.set_cutoff(500.0, SAMPLE_RATE);
+        assert_eq!(chain.process(0.5), fresh.process(0.5));
+    }
+
+    /// A boosting shelf raises the chain's peak gain by its own gain, so
+    /// the loop-gain ceiling sees it; a cut leaves it at unity.
+    #[test]
+    fn test_shelf_boost_counts_toward_max_gain() {
+        let mut chain = FeedbackChain::new(SAMPLE_RATE);
+        assert_eq!(chain.max_gain(), 1.0);
+        chain.low_shelf.set_gain(4.0);
+        assert_eq!(chain.max_gain(), 4.0);
+        chain.high_shelf.set_gain(2.0);
+        assert_eq!(chain.max_gain(), 8.0);
+        chain.low_shelf.set_gain(0.25);
+        chain.high_shelf.set_gain(0.25);
+        assert_eq!(chain.max_gain(), 1.0);
+    }
+}
@@ -0,0 +1,234 @@
+//! # Feedback Compensation: Keeping the Repeats Under a Dark Filter
+//!
+//! Every pass round the loop goes through the feedback lowpass, and the
+//! lower its cutoff, the more of each repeat it takes away. Turn the
+//! tone knob right down and the repeats fade out well before the
+//! Feedback knob says they should: the two knobs interact, and a dark,
+//! long delay takes both turned further than seems right.
+//!
+//! Feedback compensation undoes the interaction: it boosts the feedback
+//! by just enough that the repeats take as long to die away as they
+//! would without the filter, wherever the cutoff sits. The tone knob
+//! then sets only the tone, and the Feedback knob only the length.
+//!
+//! ## How Much Does the Filter Take?
+//!
+//! It depends on what's going round. A filter at 1 kHz barely touches a
+//! bass line and wipes out a hi-hat. So the model assumes a
+//! *representative spectrum* — pink noise, equal energy in every octave
+//! from [`BAND_LOW_HZ`] to [`BAND_HIGH_HZ`], the way most music roughly
+//! averages out — and asks what fraction of its energy is left after `n`
+//! passes through a one-pole lowpass at cutoff `c`:
+//!
+//! ```text
+//!         ∫ |H(f)|²ⁿ · (1/f) df                |H(f)|² = 1 / (1 + f²/c²)
+//! Eₙ  =  ───────────────────────
+//!             ∫ (1/f) df                       (both from 20 Hz to 20 kHz)
+//! ```
+//!
+//! (The filter's response is taken as its analog prototype's, which the
+//! digital filter matches closely below a quarter of the sample rate.)
+//! With `u = f²/c²` the integral comes apart into partial fractions,
+//! and has a closed form:
+//!
+//! ```text
+//! ∫ du / (u · (1 + u)ⁿ)  =  ln(u / (1 + u))  +  Σ_{k=1}^{n−1} 1 / (k · (1 + u)ᵏ)
+//! ```
+//!
+//! so no numerical integration is needed. A pink signal through a
+//! 200 Hz filter keeps a third of its energy after one pass (−4.8 dB),
+//! but only a tenth after 19: each pass takes less than the last, as
+//! the highs are already gone.
+//!
+//! ## The Boost
+//!
+//! Without the filter, the loop falls 60 dB in `N` passes, where
+//! `feedbackᴺ = 10⁻³`. With it, the same `N` passes leave an extra `Eₙ`
+//! of energy behind. Spreading the make-up evenly over those passes
+//! gives the boost:
+//!
+//! ```text
+//! N     = ⌈ln(10⁻³) / ln(feedback)⌉
+//! boost = E_N^(−1/(2N))          (energy, so the square root)
+//!
+//! feedback 0.9, filter 200 Hz:   N = 66,  boost ≈ 1.027
+//! ```
+//!
+//! So after `N` passes the compensated loop is exactly where the
+//! unfiltered one would be. The boost is small — a few percent — but
+//! compounded over dozens of passes it's the difference between a tail
+//! that lasts and one that's gone by the second bar.
+//!
+//! ## Staying Stable
+//!
+//! The boost is sized for the *average* loss, and the filter passes the
+//! lowest frequencies almost untouched, so they get the boost without
+//! the loss. At high feedback that could take the loop past unity for a
+//! bass note. The engine runs the boosted feedback through the same
+//! loop-gain ceiling as any other (see
+//! [`limit_feedback()`](super::loop_gain::limit_feedback)), so the loop
+//! gain at any frequency stays under
+//! [`LOOP_GAIN_CEILING`](super::loop_gain::LOOP_GAIN_CEILING).
+//!
+//! ## Warning Instead
+//!
+//! The same model counts how many repeats stay within 60 dB of the
+//! first (see [`audible_repeats()`]), so an editor can point out when
+//! the cutoff is eating the repeats the Feedback knob asked for, and
+//! suggest compensation.
+
+/// The bottom of the representative spectrum, in Hz.
+pub const BAND_LOW_HZ: f64 = 20.0;
+
+/// The top of the representative spectrum, in Hz.
+pub const BAND_HIGH_HZ: f64 = 20_000.0;
+
+/// How far the repeats fall before they count as gone: 60 dB, as an
+/// amplitude ratio.
+const AUDIBLE_RANGE: f64 = 1e-3;
+
+/// The most passes the model follows. Only a loop within a hair of
+/// unity takes longer to fall 60 dB, and that loop is held by the
+/// loop-gain ceiling anyway.
+pub const MAX_MODEL_PASSES: u32 = 256;
+
+/// The fraction of a pink signal's energy left after each pass through
+/// a one-pole lowpass at `cutoff_hz`: `E₁, E₂, …`, falling towards 0.
+/// See [the module docs](self).
+pub fn pass_energies(cutoff_hz: f32) -> impl Iterator<Item = f32> {
+    let cutoff = f64::from(cutoff_hz.max(1.0));
+    let [u_low, u_high] = [BAND_LOW_HZ, BAND_HIGH_HZ].map(|f| (f / cutoff).powi(2));
+    let [r_low, r_high] = [u_low, u_high].map(|u| 1.0 / (1.0 + u));
+    let scale = 0.5 / (BAND_HIGH_HZ / BAND_LOW_HZ).ln();
+
+    // The closed form at the top of the band minus at the bottom. The
+    // `ln` terms don't change from pass to pass; each pass adds one
+    // more term of the sum.
+    let mut integral = (u_high * r_high).ln() - (u_low * r_low).ln();
+    let [mut pow_low, mut pow_high] = [1.0, 1.0];
+    (1..).map(move |k| {
+        let energy = (scale * integral) as f32;
+        pow_low *= r_low;
+        pow_high *= r_high;
+        integral += (pow_high - pow_low) / f64::from(k);
+        energy
+    })
+}
+
+/// How many passes it takes `feedback` alone to fall 60 dB, up to
+/// [`MAX_MODEL_PASSES`]. `None` when there's no feedback to fall.
+fn passes_to_fall(feedback: f32) -> Option<u32> {
+    if feedback <= 0.0 {
+        return None;
+    }
+    if feedback >= 1.0 {
+        return Some(MAX_MODEL_PASSES);
+    }
+    let passes = AUDIBLE_RANGE.ln() / f64::from(feedback).ln();
+    Some((passes.ceil() as u32).clamp(1, MAX_MODEL_PASSES))
+}
+
+/// The gain to multiply `feedback` by so repeats under a filter at
+/// `cutoff_hz` take as long to fall 60 dB as unfiltered ones. At least
+/// 1.0; the caller keeps the result under the loop-gain ceiling. See
+/// [the module docs](self#the-boost).
+pub fn compensation_gain(feedback: f32, cutoff_hz: f32) -> f32 {
+    let Some(passes) = passes_to_fall(feedback) else {
+        return 1.0;
+    };
+    let energy = pass_energies(cutoff_hz)
+        .nth(passes as usize - 1)
+        .unwrap_or(1.0);
+    if energy <= 0.0 {
+        return 1.0;
+    }
+    energy.powf(-0.5 / passes as f32).max(1.0)
+}
+
+/// How many repeats after the first stay within 60 dB of it, with
+/// `feedback` under a filter at `cutoff_hz` — the repeat count a
+/// listener hears. `None` if the loop doesn't fall that far within
+/// [`MAX_MODEL_PASSES`].
+pub fn audible_repeats(feedback: f32, cutoff_hz: f32) -> Option<u32> {
+    if feedback <= 0.0 {
+        return Some(0);
+    }
+    let floor = (AUDIBLE_RANGE * AUDIBLE_RANGE) as f32;
+    let mut loop_energy = 1.0;
+    pass_energies(cutoff_hz)
+        .take(MAX_MODEL_PASSES as usize)
+        .position(|energy| {
+            loop_energy *= feedback * feedback;
+            loop_energy * energy <= floor
+        })
+        .map(|passes| passes as u32)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The closed form agrees with integrating the model numerically,
+    /// one pass or many.
+    #[test]
+    fn test_pass_energies_match_integral() {
+        let numeric = |cutoff: f64, passes: i32| {
+            const STEPS: usize = 20_000;
+            let (low, high) = (BAND_LOW_HZ.ln(), BAND_HIGH_HZ.ln());
+            let sum: f64 = (0..STEPS)
+                .map(|i| {
+                    let f = (low + (high - low) * (i as f64 + 0.5) / STEPS as f64).exp();
+                    (1.0 + (f / cutoff).powi(2)).powi(-passes)
+                })
+                .sum();
+            sum / STEPS as f64
+        };
+        for cutoff in [200.0, 1000.0, 5000.0, 20000.0] {
+            let energies: Vec<f32> = pass_energies(cutoff).take(20).collect();
+            for passes in [1, 3, 20] {
+                let want = numeric(f64::from(cutoff), passes) as f32;
+                let got = energies[passes as usize - 1];
+                assert!(
+                    (got - want).abs() < 1e-4,
+                    "{cutoff} Hz, {passes}: {got} vs {want}"
+                );
+            }
+        }
+        // One pass at 200 Hz keeps a third of a pink signal (−4.8 dB).
+        let first = pass_energies(200.0).next().unwrap();
+        assert!((first - 0.334).abs() < 1e-3, "{first}");
+    }
+
+    /// The boost is 1.0 with no filter to speak of and grows as the
+    /// cutoff falls, a few percent at most at high feedback.
+    #[test]
+    fn test_compensation_gain() {
+        assert!((compensation_gain(0.9, 200.0) - 1.027).abs() < 1e-3);
+        let mut previous = 1.0;
+        for cutoff in [20000.0, 8000.0, 2000.0, 500.0, 200.0] {
+            let gain = compensation_gain(0.7, cutoff);
+            assert!(gain >= previous, "{cutoff} Hz: {gain}");
+            previous = gain;
+        }
+        assert!(compensation_gain(0.7, 20000.0) < 1.01);
+        assert_eq!(compensation_gain(0.0, 200.0), 1.0);
+    }
+
+    /// Darker filters hear fewer repeats, and the boost gives them back.
+    #[test]
+    fn test_audible_repeats() {
+        let open = audible_repeats(0.9, 20000.0).unwrap();
+        let dark = audible_repeats(0.9, 200.0).unwrap();
+        assert!((62..=66).contains(&open), "{open}");
+        assert!(dark < open * 9 / 10, "{dark} vs {open}");
+        let boosted = 0.9 * compensation_gain(0.9, 200.0);
+        let restored = audible_repeats(boosted, 200.0).unwrap();
+        assert!(restored.abs_diff(open) <= 2, "{restored} vs {open}");
+        assert_eq!(audible_repeats(0.0, 200.0), Some(0));
+        assert_eq!(audible_repeats(1.0, 20000.0), None);
+    }
+}
@@ -44,8 +44,100 @@
 //! component limitations. This gives the repeats a progressively darker,
 //! warmer tone that sounds natural and musical. Our digital one-pole filter
 //! approximates this behavior with just one multiply and one add per sample.
+//!
+//! ## The Highpass Twin
+//!
+//! Whatever a lowpass removes is, by definition, the high-frequency part
+//! of the signal. So subtracting the lowpass output from the input gives
+//! a highpass with the same cutoff:
+//!
+//! ```text
+//! highpass[n] = x[n] - lowpass[n]
+//! ```
+//!
+//! [`OnePoleHighpass`] is built exactly that way. It's what you reach for
+//! to keep low end out of something — for example, so a level detector
+//! listening to a mix isn't dominated by the kick drum and bass.
+//!
+//! ## Shelves
+//!
+//! A lowpass can only take highs away. A *shelf* turns one end of the
+//! spectrum up or down by a set gain and leaves the other end alone, so
+//! it can thin out the lows or lift the highs as well. A first-order
+//! shelf is the same low/high split as the highpass twin, with one part
+//! scaled before the two are added back up:
+//!
+//! ```text
+//! low shelf:   y = x + (g - 1) * low(x)              g at DC, 1 at Nyquist
+//! high shelf:  y = x + (g - 1) * (x - low(x))        1 at DC, g at Nyquist
+//! ```
+//!
+//! The split can't be [`OnePoleFilter`], though. Its response never
+//! quite reaches zero: at Nyquist it's still `(1 - a) / (1 + a)`, which
+//! for a 4 kHz corner at 48 kHz is a quarter. A high shelf built on it
+//! would run out of room at the top — asked for +12 dB, it manages 10;
+//! asked for −12, barely 7. So the shelves split with a one-pole
+//! designed by the *bilinear transform*, which squeezes the whole
+//! analog frequency axis into the digital one and puts the filter's
+//! zero exactly at Nyquist:
+//!
+//! ```text
+//! K = tan(π · corner / sample_rate)        g = K / (1 + K)
+//!
+//! v    = (x - s) · g
+//! low  = v + s
+//! s    = low + v                           (s: the filter's memory)
+//! ```
+//!
+//! Both plateaus then land on the dot. The corner is the split's
+//! cutoff, and the shelf is part of the way up (or down) its slope
+//! there. Boosts and cuts aren't quite mirror images: a +12 dB low
+//! shelf is already about 9 dB up at its corner, a −12 dB one only
+//! about 3 dB down. That's the price of the simplest shelf there is,
+//! and it's a question of where the slope sits, not how far it goes.
+//!
+//! At `g = 1` (0 dB) there's nothing to add, and [`LowShelf`] and
+//! [`HighShelf`] hand the input back untouched — not "close to", but the
+//! same bits — so a shelf left at 0 dB can't change the sound.
+//!
+//! The split's response runs round a circle from 1 (at DC) to 0 (at
+//! Nyquist) without ever leaving the disc between them, so neither
+//! shelf overshoots its plateau: the most either can amplify anything
+//! is `max(g, 1)`, which is what their `max_gain()` reports.
+//!
+//! ## Jumping the Cutoff
+//!
+//! Changing the coefficient in place is fine for small moves, but a big
+//! jump — a preset switch from 200 Hz to 20 kHz, say — leaves the
+//! filter's memory describing the *old* response. Heavily smoothed,
+//! `y[n-1]` sits far from the input, and with the new, small `a` the
+//! very next output lands close to the input:
+//!
+//! ```text
+//! step = (a_old - a_new) * (y[n-1] - x[n])     up to ~1.0 in one sample
+//! ```
+//!
+//! [`CrossfadingLowpass`] catches those jumps. When a cutoff change moves
+//! the coefficient by more than [`COEFFICIENT_JUMP`], it keeps a copy of
+//! the old filter running and fades from its output to the new one's
+//! over [`JUMP_FADE_MS`]. Both halves are lowpasses of the same input,
+//! so a linear fade between them never rises above either. Ordinary
+//! smoothed moves are far below the threshold and change the coefficient
+//! in place, bit for bit like [`OnePoleFilter`].
+//!
+//! ## Ramping the Coefficient
+//!
+//! Working out a coefficient costs an `exp()`. While a cutoff is being
+//! smoothed, the engine doesn't pay that on every sample: it works one
+//! out every control tick (see the engine's docs) and has the filter
+//! glide to it with [`ramp_to_cutoff()`](OnePoleFilter::ramp_to_cutoff),
+//! one add per sample. Over a millisecond the straight line between two
+//! coefficients is indistinguishable from the exact curve.
+//!
+//! Like [`DelayLine`](super::delay_line::DelayLine), the filters are
+//! generic over the [`Sample`] type and default to `f32`.
 
-use std::f32::consts::PI;
+use super::sample::Sample;
 
 /// A one-pole (6 dB/octave) lowpass filter.
 ///
@@ -54,20 +146,28 @@ use std::f32::consts::PI;
 /// frequencies at 6 dB per octave — a gentle slope that sounds natural
 /// for feedback darkening. (A "two-pole" filter, like a biquad, rolls
 /// off at 12 dB/octave for a steeper cut.)
-pub struct OnePoleFilter {
+#[derive(Clone)]
+pub struct OnePoleFilter<T: Sample = f32> {
     /// The filter coefficient, computed from the cutoff frequency.
     /// Higher values = more filtering (lower cutoff).
     /// Range: 0.0 (no filtering) to ~0.999 (extreme filtering).
-    coefficient: f32,
+    coefficient: T,
 
     /// The previous output sample — the filter's only state variable.
     /// This is what makes it an "IIR" filter: the output depends on
     /// previous *outputs*, not just previous inputs. An "FIR" filter
     /// only looks at previous inputs.
-    prev_output: f32,
+    prev_output: T,
+
+    /// A coefficient ramp in progress (see [`ramp_to_cutoff()`](Self::ramp_to_cutoff)):
+    /// the per-sample step, where it ends, and how many samples it has
+    /// left.
+    ramp_step: T,
+    ramp_target: T,
+    ramp_left: usize,
 }
 
-impl OnePoleFilter {
+impl<T: Sample> OnePoleFilter<T> {
     /// Create a new filter initialized to passthrough (no filtering).
     ///
     /// With `coefficient = 0.0`, the filter equation becomes:
@@ -75,8 +175,11 @@ impl OnePoleFilter {
     /// ...which is just the input, unchanged.
     pub fn new() -> Self {
         Self {
-            coefficient: 0.0,
-            prev_output: 0.0,
+            coefficient: T::ZERO,
+            prev_output: T::ZERO,
+            ramp_step: T::ZERO,
+            ramp_target: T::ZERO,
+            ramp_left: 0,
         }
     }
 
@@ -101,15 +204,50 @@ impl OnePoleFilter {
     /// - cutoff = 20000 Hz → coeff ≈ 0.07 (barely filtering)
     /// - cutoff = 1000 Hz  → coeff ≈ 0.87 (noticeable filtering)
     /// - cutoff = 100 Hz   → coeff ≈ 0.99 (heavy filtering)
-    pub fn set_cutoff(&mut self, cutoff_hz: f32, sample_rate: f32) {
+    pub fn set_cutoff(&mut self, cutoff_hz: T, sample_rate: T) {
         // Clamp cutoff to a safe range:
         // - Min 20 Hz: prevents coefficient from reaching ~1.0, which
         //   could cause numerical stagnation (the filter "gets stuck")
         // - Max 49% of sample rate: approaching the Nyquist frequency
         //   (sample_rate / 2) makes the math unstable. We stay below it.
-        let safe_cutoff = cutoff_hz.clamp(20.0, sample_rate * 0.49);
+        self.coefficient = cutoff_coefficient(cutoff_hz, sample_rate);
+        self.ramp_left = 0;
+    }
+
+    /// Glide to the coefficient for `cutoff_hz` in a straight line over
+    /// the next `samples` samples, landing on it exactly on the last of
+    /// them. A ramp already under way carries on from where it is.
+    ///
+    /// With `samples` at 0 or 1 it's the same as
+    /// [`set_cutoff()`](Self::set_cutoff).
+    pub fn ramp_to_cutoff(&mut self, cutoff_hz: T, sample_rate: T, samples: usize) {
+        self.ramp_to(cutoff_coefficient(cutoff_hz, sample_rate), samples);
+    }
 
-        self.coefficient = (-2.0 * PI * safe_cutoff / sample_rate).exp();
+    fn ramp_to(&mut self, coefficient: T, samples: usize) {
+        if samples <= 1 {
+            self.coefficient = coefficient;
+            self.ramp_left = 0;
+            return;
+        }
+        self.ramp_step = (coefficient - self.coefficient) / T::from_usize(samples);
+        self.ramp_target = coefficient;
+        self.ramp_left = samples;
+    }
+
+    /// Whether a [`ramp_to_cutoff()`](Self::ramp_to_cutoff) glide is
+    /// still under way.
+    pub fn is_ramping(&self) -> bool {
+        self.ramp_left > 0
+    }
+
+    /// The coefficient the filter is at, or ramping to.
+    fn target_coefficient(&self) -> T {
+        if self.ramp_left > 0 {
+            self.ramp_target
+        } else {
+            self.coefficient
+        }
     }
 
     /// Process one sample through the filter.
@@ -126,8 +264,16 @@ impl OnePoleFilter {
     ///
     /// When `a` is low (e.g., 0.05), the output is mostly the new input
     /// → minimal smoothing → high cutoff frequency.
-    pub fn process(&mut self, input: f32) -> f32 {
-        let output = (1.0 - self.coefficient) * input + self.coefficient * self.prev_output;
+    pub fn process(&mut self, input: T) -> T {
+        if self.ramp_left > 0 {
+            self.ramp_left -= 1;
+            self.coefficient = if self.ramp_left == 0 {
+                self.ramp_target
+            } else {
+                self.coefficient + self.ramp_step
+            };
+        }
+        let output = (T::ONE - self.coefficient) * input + self.coefficient * self.prev_output;
         self.prev_output = output;
         output
     }
@@ -139,7 +285,362 @@ impl OnePoleFilter {
     /// few samples of a new play might sound wrong because `prev_output`
     /// would still hold a value from the end of the last play.
     pub fn reset(&mut self) {
-        self.prev_output = 0.0;
+        self.prev_output = T::ZERO;
+    }
+}
+
+impl<T: Sample> Default for OnePoleFilter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The highest cutoff the filters here take, as a fraction of the
+/// sample rate: just short of Nyquist, where the math breaks down.
+/// Higher settings play as this.
+pub const MAX_CUTOFF_RATIO: f32 = 0.49;
+
+/// The highest cutoff the filters here take at `sample_rate`, in Hz:
+/// 3920 Hz at 8 kHz, well under a 20 kHz knob.
+pub fn max_cutoff_hz(sample_rate: f32) -> f32 {
+    sample_rate * MAX_CUTOFF_RATIO
+}
+
+/// The one-pole coefficient for `cutoff_hz`, clamped to a safe range
+/// (see [`OnePoleFilter::set_cutoff()`]).
+fn cutoff_coefficient<T: Sample>(cutoff_hz: T, sample_rate: T) -> T {
+    let safe_cutoff = cutoff_hz.clamp(T::from(20.0), sample_rate * T::from(MAX_CUTOFF_RATIO));
+    (T::from(-2.0) * T::PI * safe_cutoff / sample_rate).exp()
+}
+
+/// The largest coefficient change [`CrossfadingLowpass`] makes in place.
+/// A 50 ms logarithmic sweep across the whole range moves it by under
+/// 0.001 per sample, so only real jumps get a crossfade.
+pub const COEFFICIENT_JUMP: f32 = 0.05;
+
+/// How long [`CrossfadingLowpass`] fades from the old response to the
+/// new one after a jump, in milliseconds.
+pub const JUMP_FADE_MS: f32 = 5.0;
+
+/// A [`OnePoleFilter`] whose cutoff can jump without a step in its
+/// output: a jump crossfades from the old filter to the new one instead
+/// of swapping the coefficient under the old filter's memory. See the
+/// [module docs](self#jumping-the-cutoff).
+#[derive(Clone)]
+pub struct CrossfadingLowpass<T: Sample = f32> {
+    /// The filter at the latest cutoff.
+    current: OnePoleFilter<T>,
+
+    /// The filter at the cutoff before the jump, still running while
+    /// the fade is.
+    outgoing: OnePoleFilter<T>,
+
+    /// Samples left in the fade, out of `fade_len`.
+    fade_left: usize,
+    fade_len: usize,
+
+    /// The last sample out, fade and all. A jump mid-fade starts the
+    /// outgoing filter from here, so the output carries on unbroken.
+    last_output: T,
+
+    /// Whether a cutoff has been set yet. The first one goes straight
+    /// in: nothing has been filtered to fade from.
+    primed: bool,
+}
+
+impl<T: Sample> CrossfadingLowpass<T> {
+    /// Create a transparent filter, like [`OnePoleFilter::new()`].
+    pub fn new() -> Self {
+        Self {
+            current: OnePoleFilter::new(),
+            outgoing: OnePoleFilter::new(),
+            fade_left: 0,
+            fade_len: 0,
+            last_output: T::ZERO,
+            primed: false,
+        }
+    }
+
+    /// Set the cutoff frequency. A change that moves the coefficient by
+    /// more than [`COEFFICIENT_JUMP`] fades over to the new response
+    /// across [`JUMP_FADE_MS`].
+    pub fn set_cutoff(&mut self, cutoff_hz: T, sample_rate: T) {
+        self.ramp_to_cutoff(cutoff_hz, sample_rate, 1);
+    }
+
+    /// Set the cutoff at once, however far it moves, with no fade. For
+    /// a filter that's just been [`reset()`](Self::reset): with its
+    /// memory gone there's no old response worth fading out of.
+    pub fn jump_to_cutoff(&mut self, cutoff_hz: T, sample_rate: T) {
+        self.current.set_cutoff(cutoff_hz, sample_rate);
+        self.fade_left = 0;
+        self.primed = true;
+    }
+
+    /// Glide to the cutoff over `samples` samples, like
+    /// [`OnePoleFilter::ramp_to_cutoff()`]. A jump still crossfades
+    /// instead.
+    pub fn ramp_to_cutoff(&mut self, cutoff_hz: T, sample_rate: T, samples: usize) {
+        let coefficient = cutoff_coefficient(cutoff_hz, sample_rate);
+        let jump = (coefficient - self.current.target_coefficient()).abs();
+        if self.primed && jump > T::from(COEFFICIENT_JUMP) {
+            self.outgoing = OnePoleFilter {
+                prev_output: self.last_output,
+                ..OnePoleFilter::new()
+            };
+            self.outgoing.coefficient = self.current.coefficient;
+            self.fade_len = (T::from(JUMP_FADE_MS / 1000.0) * sample_rate)
+                .to_usize()
+                .max(1);
+            self.fade_left = self.fade_len;
+            self.current.ramp_to(coefficient, 1);
+        } else {
+            self.current.ramp_to(coefficient, samples);
+        }
+        self.primed = true;
+    }
+
+    /// Process one sample, fading from the old response while a jump
+    /// is settling.
+    pub fn process(&mut self, input: T) -> T {
+        let mut output = self.current.process(input);
+        if self.fade_left > 0 {
+            let old = self.outgoing.process(input);
+            self.fade_left -= 1;
+            let old_gain = T::from_usize(self.fade_left) / T::from_usize(self.fade_len);
+            output = output + (old - output) * old_gain;
+        }
+        self.last_output = output;
+        output
+    }
+
+    /// Whether a jump is still being faded over.
+    pub fn is_fading(&self) -> bool {
+        self.fade_left > 0
+    }
+
+    /// Whether a [`ramp_to_cutoff()`](Self::ramp_to_cutoff) glide is
+    /// still under way.
+    pub fn is_ramping(&self) -> bool {
+        self.current.is_ramping()
+    }
+
+    /// Reset the filter state to zero, dropping any fade. The cutoff is
+    /// kept.
+    pub fn reset(&mut self) {
+        self.current.reset();
+        self.outgoing.reset();
+        self.fade_left = 0;
+        self.last_output = T::ZERO;
+    }
+}
+
+impl<T: Sample> Default for CrossfadingLowpass<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A one-pole (6 dB/octave) highpass filter: the input minus a
+/// [`OnePoleFilter`] lowpass at the same cutoff.
+///
+/// A fresh [`OnePoleFilter`] is transparent, so "input minus lowpass"
+/// would be silence. [`new()`](Self::new) therefore starts the lowpass at
+/// the lowest cutoff (20 Hz), which makes the highpass as close to
+/// transparent as it can be.
+#[derive(Clone)]
+pub struct OnePoleHighpass<T: Sample = f32> {
+    lowpass: OnePoleFilter<T>,
+}
+
+impl<T: Sample> OnePoleHighpass<T> {
+    /// Create a highpass at 20 Hz for the given sample rate: as close to
+    /// "no filtering" as the lowpass clamp allows.
+    pub fn new(sample_rate: T) -> Self {
+        let mut lowpass = OnePoleFilter::new();
+        lowpass.set_cutoff(T::from(20.0), sample_rate);
+        Self { lowpass }
+    }
+
+    /// Set the cutoff frequency. Below it, content is attenuated at
+    /// 6 dB/octave.
+    pub fn set_cutoff(&mut self, cutoff_hz: T, sample_rate: T) {
+        self.lowpass.set_cutoff(cutoff_hz, sample_rate);
+    }
+
+    /// Glide to the cutoff over `samples` samples, like
+    /// [`OnePoleFilter::ramp_to_cutoff()`].
+    pub fn ramp_to_cutoff(&mut self, cutoff_hz: T, sample_rate: T, samples: usize) {
+        self.lowpass.ramp_to_cutoff(cutoff_hz, sample_rate, samples);
+    }
+
+    /// Whether a [`ramp_to_cutoff()`](Self::ramp_to_cutoff) glide is
+    /// still under way.
+    pub fn is_ramping(&self) -> bool {
+        self.lowpass.is_ramping()
+    }
+
+    /// Process one sample: keep whatever the lowpass would remove.
+    pub fn process(&mut self, input: T) -> T {
+        input - self.lowpass.process(input)
+    }
+
+    /// Reset the filter state to zero.
+    pub fn reset(&mut self) {
+        self.lowpass.reset();
+    }
+}
+
+/// The low/high split the shelves share: a one-pole lowpass designed
+/// with the bilinear transform, so its response falls to exactly zero
+/// at Nyquist. See the [module docs](self#shelves).
+///
+/// It runs in the "topology-preserving" form: one state variable that
+/// integrates by the trapezoidal rule, the digital twin of the
+/// capacitor in an RC filter.
+#[derive(Clone)]
+struct ShelfSplit<T: Sample> {
+    /// The integrator's gain per sample: `K / (1 + K)`, with
+    /// `K = tan(π · corner / sample_rate)`.
+    g: T,
+
+    /// The integrator's state.
+    state: T,
+}
+
+impl<T: Sample> ShelfSplit<T> {
+    fn new(corner_hz: T, sample_rate: T) -> Self {
+        let corner = corner_hz.clamp(T::from(20.0), sample_rate * T::from(MAX_CUTOFF_RATIO));
+        let k = (T::PI * corner / sample_rate).tan();
+        Self {
+            g: k / (T::ONE + k),
+            state: T::ZERO,
+        }
+    }
+
+    /// The low part of `input`. The high part is `input` minus this.
+    #[inline]
+    fn lowpass(&mut self, input: T) -> T {
+        let v = (input - self.state) * self.g;
+        let low = v + self.state;
+        self.state = low + v;
+        low
+    }
+
+    fn reset(&mut self) {
+        self.state = T::ZERO;
+    }
+}
+
+/// The most a shelf at `gain` can amplify anything: the gain if it
+/// boosts, unity if it cuts.
+fn shelf_max_gain<T: Sample>(gain: T) -> T {
+    if gain > T::ONE {
+        gain
+    } else {
+        T::ONE
+    }
+}
+
+/// A first-order low shelf: `gain` at DC, unity at Nyquist. See the
+/// [module docs](self#shelves).
+#[derive(Clone)]
+pub struct LowShelf<T: Sample = f32> {
+    split: ShelfSplit<T>,
+    gain: T,
+}
+
+impl<T: Sample> LowShelf<T> {
+    /// Create a flat (0 dB) shelf with its corner at `corner_hz`.
+    pub fn new(corner_hz: T, sample_rate: T) -> Self {
+        Self {
+            split: ShelfSplit::new(corner_hz, sample_rate),
+            gain: T::ONE,
+        }
+    }
+
+    /// Set the shelf's gain, as a linear factor (1.0 = 0 dB). It's a
+    /// multiply per sample, so it can change on every one.
+    pub fn set_gain(&mut self, gain: T) {
+        self.gain = gain;
+    }
+
+    /// The shelf's gain, as a linear factor.
+    pub fn gain(&self) -> T {
+        self.gain
+    }
+
+    /// The most the shelf can amplify anything: its gain if it boosts,
+    /// unity if it cuts.
+    pub fn max_gain(&self) -> T {
+        shelf_max_gain(self.gain)
+    }
+
+    /// Process one sample. At 0 dB the input comes back exactly; the
+    /// split keeps running, so leaving 0 dB starts from live state.
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        let low = self.split.lowpass(input);
+        if self.gain == T::ONE {
+            return input;
+        }
+        input + (self.gain - T::ONE) * low
+    }
+
+    /// Reset the filter state to zero.
+    pub fn reset(&mut self) {
+        self.split.reset();
+    }
+}
+
+/// A first-order high shelf: unity at DC, `gain` at Nyquist. See the
+/// [module docs](self#shelves).
+#[derive(Clone)]
+pub struct HighShelf<T: Sample = f32> {
+    split: ShelfSplit<T>,
+    gain: T,
+}
+
+impl<T: Sample> HighShelf<T> {
+    /// Create a flat (0 dB) shelf with its corner at `corner_hz`.
+    pub fn new(corner_hz: T, sample_rate: T) -> Self {
+        Self {
+            split: ShelfSplit::new(corner_hz, sample_rate),
+            gain: T::ONE,
+        }
+    }
+
+    /// Set the shelf's gain, as a linear factor (1.0 = 0 dB). It's a
+    /// multiply per sample, so it can change on every one.
+    pub fn set_gain(&mut self, gain: T) {
+        self.gain = gain;
+    }
+
+    /// The shelf's gain, as a linear factor.
+    pub fn gain(&self) -> T {
+        self.gain
+    }
+
+    /// The most the shelf can amplify anything: its gain if it boosts,
+    /// unity if it cuts.
+    pub fn max_gain(&self) -> T {
+        shelf_max_gain(self.gain)
+    }
+
+    /// Process one sample. At 0 dB the input comes back exactly; the
+    /// split keeps running, so leaving 0 dB starts from live state.
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        let low = self.split.lowpass(input);
+        if self.gain == T::ONE {
+            return input;
+        }
+        input + (self.gain - T::ONE) * (input - low)
+    }
+
+    /// Reset the filter state to zero.
+    pub fn reset(&mut self) {
+        self.split.reset();
     }
 }
 
@@ -156,7 +657,7 @@ mod tests {
     /// transparent when the filter cutoff is at maximum.
     #[test]
     fn test_passthrough_when_coefficient_zero() {
-        let mut filter = OnePoleFilter::new();
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
 
         assert!(
             (filter.process(1.0) - 1.0).abs() < 1e-6,
@@ -178,7 +679,7 @@ mod tests {
     /// between +1 and -1 every sample.
     #[test]
     fn test_filter_attenuates_high_freq() {
-        let mut filter = OnePoleFilter::new();
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
         filter.set_cutoff(100.0, 44100.0); // Very low cutoff
 
         // Feed in a signal that alternates +1, -1 every sample.
@@ -201,7 +702,7 @@ mod tests {
     /// cutoff frequencies. This catches math errors in set_cutoff().
     #[test]
     fn test_coefficient_range() {
-        let mut filter = OnePoleFilter::new();
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
 
         // High cutoff (near Nyquist): coefficient should be small (near 0)
         // because the filter is barely doing anything.
@@ -225,7 +726,7 @@ mod tests {
     /// Verify that reset() clears the filter's memory.
     #[test]
     fn test_reset_clears_state() {
-        let mut filter = OnePoleFilter::new();
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
         filter.set_cutoff(1000.0, 44100.0);
 
         // Process a sample so prev_output is non-zero.
@@ -248,7 +749,7 @@ mod tests {
     /// lowest possible frequency and should always pass.
     #[test]
     fn test_dc_passes_through() {
-        let mut filter = OnePoleFilter::new();
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
         filter.set_cutoff(100.0, 44100.0); // Very low cutoff
 
         // Feed constant 1.0 for many samples. The output should converge to 1.0.
@@ -262,4 +763,300 @@ mod tests {
             "DC signal should pass through lowpass, got {output}"
         );
     }
+
+    /// The generic `f32` path must be bit-for-bit identical to the
+    /// original hand-written `f32` filter.
+    #[test]
+    fn test_f32_matches_reference_bit_for_bit() {
+        let sample_rate = 44100.0_f32;
+        let mut filter: OnePoleFilter = OnePoleFilter::new();
+        let mut ref_prev = 0.0_f32;
+
+        for (i, cutoff) in [20.0, 200.0, 1000.0, 8000.0, 30000.0].iter().enumerate() {
+            filter.set_cutoff(*cutoff, sample_rate);
+
+            // The original coefficient math, verbatim.
+            let safe_cutoff = cutoff.clamp(20.0, sample_rate * 0.49);
+            let ref_coeff = (-2.0 * std::f32::consts::PI * safe_cutoff / sample_rate).exp();
+            assert_eq!(filter.coefficient.to_bits(), ref_coeff.to_bits());
+
+            for n in 0..100 {
+                let x = ((i * 100 + n) as f32 * 0.71).sin();
+                let expected = (1.0 - ref_coeff) * x + ref_coeff * ref_prev;
+                ref_prev = expected;
+                assert_eq!(filter.process(x).to_bits(), expected.to_bits());
+            }
+        }
+    }
+
+    /// The highpass blocks DC and passes Nyquist, the mirror image of
+    /// the lowpass.
+    #[test]
+    fn test_highpass_blocks_dc_passes_high_freq() {
+        let mut highpass: OnePoleHighpass = OnePoleHighpass::new(44100.0);
+        highpass.set_cutoff(150.0, 44100.0);
+
+        let mut dc_out = 1.0;
+        for _ in 0..10000 {
+            dc_out = highpass.process(1.0);
+        }
+        assert!(dc_out.abs() < 1e-3, "DC should be blocked, got {dc_out}");
+
+        highpass.reset();
+        let mut max_output = 0.0_f32;
+        for i in 0..1000 {
+            let input = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let output = highpass.process(input);
+            if i > 100 {
+                max_output = max_output.max(output.abs());
+            }
+        }
+        assert!(
+            max_output > 0.95,
+            "Nyquist should pass almost untouched, got {max_output}"
+        );
+    }
+
+    /// The worst preset jump, 20 Hz to 20 kHz and back, under a slow
+    /// sine. Swapping the coefficient in place steps the output by most
+    /// of the signal's swing in one sample; the crossfade keeps every
+    /// sample-to-sample move within the sine's own slope plus the fade's.
+    #[test]
+    fn test_cutoff_jump_stays_smooth() {
+        const SAMPLE_RATE: f32 = 48000.0;
+        let input = |n: usize| (std::f32::consts::TAU * 100.0 * n as f32 / SAMPLE_RATE).sin();
+        // A jump up a quarter of the way into a cycle, where the 20 Hz
+        // output lags the input the most, then a jump back down.
+        let largest_step = |filter: &mut dyn FnMut(usize, Option<f32>) -> f32| {
+            let mut largest: f32 = 0.0;
+            let mut prev = filter(0, Some(20.0));
+            for n in 1..9600 {
+                let cutoff = match n {
+                    4920 => Some(20000.0),
+                    7200 => Some(20.0),
+                    _ => None,
+                };
+                let output = filter(n, cutoff);
+                largest = largest.max((output - prev).abs());
+                prev = output;
+            }
+            largest
+        };
+
+        let mut plain: OnePoleFilter = OnePoleFilter::new();
+        let plain_step = largest_step(&mut |n, cutoff| {
+            if let Some(cutoff) = cutoff {
+                plain.set_cutoff(cutoff, SAMPLE_RATE);
+            }
+            plain.process(input(n))
+        });
+        let mut fading: CrossfadingLowpass = CrossfadingLowpass::new();
+        let fading_step = largest_step(&mut |n, cutoff| {
+            if let Some(cutoff) = cutoff {
+                fading.set_cutoff(cutoff, SAMPLE_RATE);
+                assert_eq!(fading.is_fading(), n > 0);
+            }
+            fading.process(input(n))
+        });
+
+        // The sine's own steepest step is 2π · 100 / 48000 ≈ 0.013.
+        assert!(
+            plain_step > 0.5,
+            "the plain filter should step: {plain_step}"
+        );
+        assert!(fading_step < 0.02, "largest step {fading_step}");
+    }
+
+    /// A reset filter jumps straight to its new cutoff: no fade, and
+    /// from then on it's a plain filter at that cutoff.
+    #[test]
+    fn test_jump_after_reset_skips_the_fade() {
+        let mut plain: OnePoleFilter = OnePoleFilter::new();
+        let mut fading: CrossfadingLowpass = CrossfadingLowpass::new();
+        fading.set_cutoff(20000.0, 48000.0);
+        for n in 0..100 {
+            fading.process((n as f32 * 0.37).sin());
+        }
+
+        fading.reset();
+        fading.jump_to_cutoff(200.0, 48000.0);
+        plain.set_cutoff(200.0, 48000.0);
+        assert!(!fading.is_fading());
+        for n in 0..100 {
+            let x = (n as f32 * 0.37).sin();
+            assert_eq!(fading.process(x).to_bits(), plain.process(x).to_bits());
+        }
+    }
+
+    /// Small moves go in place: the output matches a plain filter's bit
+    /// for bit.
+    #[test]
+    fn test_small_cutoff_moves_match_plain_filter() {
+        let mut plain: OnePoleFilter = OnePoleFilter::new();
+        let mut fading: CrossfadingLowpass = CrossfadingLowpass::new();
+        for n in 0..2000 {
+            let cutoff = 1000.0 * 1.001_f32.powi(n);
+            plain.set_cutoff(cutoff, 48000.0);
+            fading.set_cutoff(cutoff, 48000.0);
+            let x = (n as f32 * 0.37).sin();
+            assert_eq!(fading.process(x).to_bits(), plain.process(x).to_bits());
+        }
+        assert!(!fading.is_fading());
+    }
+
+    /// A ramp moves the coefficient in equal steps and lands exactly on
+    /// the target's, where `set_cutoff()` would have put it.
+    #[test]
+    fn test_ramp_lands_on_the_cutoff() {
+        let mut ramped: OnePoleFilter = OnePoleFilter::new();
+        let mut direct: OnePoleFilter = OnePoleFilter::new();
+        ramped.set_cutoff(500.0, 48000.0);
+        direct.set_cutoff(2000.0, 48000.0);
+        let start = ramped.coefficient;
+
+        ramped.ramp_to_cutoff(2000.0, 48000.0, 32);
+        let mut coefficients = Vec::new();
+        for _ in 0..32 {
+            assert!(ramped.is_ramping());
+            ramped.process(0.0);
+            coefficients.push(ramped.coefficient);
+        }
+        assert!(!ramped.is_ramping());
+        assert_eq!(ramped.coefficient.to_bits(), direct.coefficient.to_bits());
+
+        let step = (direct.coefficient - start) / 32.0;
+        for (n, coefficient) in coefficients.iter().enumerate() {
+            let expected = start + step * (n + 1) as f32;
+            assert!((coefficient - expected).abs() < 1e-6, "sample {n}");
+        }
+    }
+
+    /// Lowpass + highpass at the same cutoff add back up to the input.
+    #[test]
+    fn test_highpass_complements_lowpass() {
+        let mut lowpass: OnePoleFilter = OnePoleFilter::new();
+        let mut highpass: OnePoleHighpass = OnePoleHighpass::new(48000.0);
+        lowpass.set_cutoff(1000.0, 48000.0);
+        highpass.set_cutoff(1000.0, 48000.0);
+
+        for n in 0..500 {
+            let x = (n as f32 * 0.37).sin();
+            let sum = lowpass.process(x) + highpass.process(x);
+            assert!((sum - x).abs() < 1e-6, "sample {n}: {sum} vs {x}");
+        }
+    }
+
+    /// The `f64` filter should settle on DC at least as accurately as the
+    /// `f32` one in the DC passthrough test above.
+    #[test]
+    fn test_f64_dc_accuracy_at_least_f32() {
+        let mut f32_filter: OnePoleFilter = OnePoleFilter::new();
+        let mut f64_filter: OnePoleFilter<f64> = OnePoleFilter::new();
+        f32_filter.set_cutoff(100.0, 44100.0);
+        f64_filter.set_cutoff(100.0, 44100.0);
+
+        let mut out32 = 0.0_f32;
+        let mut out64 = 0.0_f64;
+        for _ in 0..10000 {
+            out32 = f32_filter.process(1.0);
+            out64 = f64_filter.process(1.0);
+        }
+
+        let err32 = (f64::from(out32) - 1.0).abs();
+        let err64 = (out64 - 1.0).abs();
+        assert!(err64 < 1e-4, "f64 DC error too large: {err64}");
+        assert!(
+            err64 <= err32,
+            "f64 should be at least as accurate: f64 err {err64}, f32 err {err32}"
+        );
+    }
+
+    /// The steady-state gain of `process` on a sine at `freq_hz`: the
+    /// output's RMS over the second of two seconds, against the
+    /// input's. (A peak would miss the top of a sine only a few samples
+    /// long.)
+    fn sine_gain(mut process: impl FnMut(f32) -> f32, freq_hz: f32) -> f32 {
+        let step = std::f64::consts::TAU * f64::from(freq_hz) / 48000.0;
+        let (mut input_energy, mut output_energy) = (0.0, 0.0);
+        for n in 0..96000 {
+            let x = (n as f64 * step).sin() as f32;
+            let y = process(x);
+            if n >= 48000 {
+                input_energy += x * x;
+                output_energy += y * y;
+            }
+        }
+        (output_energy / input_energy).sqrt()
+    }
+
+    fn db(gain: f32) -> f32 {
+        20.0 * gain.log10()
+    }
+
+    /// A ±12 dB low shelf at 250 Hz: the full gain well below the
+    /// corner, part of it at the corner, none well above it.
+    #[test]
+    fn test_low_shelf_response() {
+        for shelf_db in [12.0, -12.0] {
+            let gain = 10.0_f32.powf(shelf_db / 20.0);
+            let response = |freq| {
+                let mut shelf: LowShelf = LowShelf::new(250.0, 48000.0);
+                shelf.set_gain(gain);
+                db(sine_gain(|x| shelf.process(x), freq))
+            };
+            let (low, corner, high) = (response(20.0), response(250.0), response(10000.0));
+            assert!(
+                (low - shelf_db).abs() < 0.6,
+                "{shelf_db} dB at 20 Hz: {low}"
+            );
+            assert!(
+                corner.abs() > 2.0 && corner.abs() < 10.0 && corner * shelf_db > 0.0,
+                "{shelf_db} dB at the corner: {corner}"
+            );
+            assert!(high.abs() < 0.3, "{shelf_db} dB at 10 kHz: {high}");
+        }
+    }
+
+    /// A ±12 dB high shelf at 4 kHz: nothing well below the corner,
+    /// most of the gain well above it, never more than all of it.
+    #[test]
+    fn test_high_shelf_response() {
+        for shelf_db in [12.0, -12.0] {
+            let gain = 10.0_f32.powf(shelf_db / 20.0);
+            let response = |freq| {
+                let mut shelf: HighShelf = HighShelf::new(4000.0, 48000.0);
+                shelf.set_gain(gain);
+                db(sine_gain(|x| shelf.process(x), freq))
+            };
+            let (low, corner, high) = (response(100.0), response(4000.0), response(18000.0));
+            assert!(low.abs() < 0.3, "{shelf_db} dB at 100 Hz: {low}");
+            assert!(
+                corner.abs() > 2.0 && corner.abs() < 10.0 && corner * shelf_db > 0.0,
+                "{shelf_db} dB at the corner: {corner}"
+            );
+            assert!(
+                high * shelf_db > 0.0 && high.abs() > 10.0 && high.abs() <= 12.0,
+                "{shelf_db} dB at 18 kHz: {high}"
+            );
+        }
+    }
+
+    /// At 0 dB both shelves hand back exactly what went in, and their
+    /// peak gain is unity.
+    #[test]
+    fn test_flat_shelves_bypass_exactly() {
+        let mut low: LowShelf = LowShelf::new(250.0, 48000.0);
+        let mut high: HighShelf = HighShelf::new(4000.0, 48000.0);
+        for n in 0..1000 {
+            let x = (n as f32 * 0.37).sin() * 0.8 + 1e-7 * n as f32;
+            assert_eq!(low.process(x).to_bits(), x.to_bits());
+            assert_eq!(high.process(x).to_bits(), x.to_bits());
+        }
+        assert_eq!(low.max_gain(), 1.0);
+        assert_eq!(high.max_gain(), 1.0);
+        low.set_gain(0.5);
+        assert_eq!(low.max_gain(), 1.0);
+        high.set_gain(4.0);
+        assert_eq!(high.max_gain(), 4.0);
+    }
 }
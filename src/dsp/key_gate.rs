@@ -0,0 +1,264 @@
+//! # Key Gate: Echoes Only When Keyed
+//!
+//! A delay normally repeats everything that goes into it. A *key gate*
+//! lets a second signal — the key, usually a sidechain — decide which
+//! moments get in. Key the delay from the snare track and only the
+//! snare hits spray into the echoes, even though the delay sits on a
+//! whole drum bus:
+//!
+//! ```text
+//! input  ▁▃▅▇▅▃▁▃▅▇▅▃▁▃▅▇▅▃▁▃▅▇▅▃▁      everything that plays
+//! key    ▁▁▁▁▁▁▁▁▁▁▁█▁▁▁▁▁▁▁▁▁▁▁█▁      the snare
+//! gate   ▁▁▁▁▁▁▁▁▁▁▁██▆▄▂▁▁▁▁▁▁▁██▆▄▂  opens on the key, then releases
+//! echoes                  only what passed the gate
+//! ```
+//!
+//! The gate sits on the *input* side of the loop only. The repeats
+//! already in the line keep circulating, and the dry signal is never
+//! touched, so the only thing that changes is what gets sampled into
+//! the delay.
+//!
+//! ## Detection
+//!
+//! A peak [`EnvelopeFollower`] measures the key. Its short release rides
+//! over the dips between a waveform's peaks, so a steady tone keeps the
+//! gate open instead of chattering once per cycle. When the level is
+//! above the threshold the gate opens over [`OPEN_MS`], quick enough to
+//! catch a transient; when it falls below, the gate closes over the
+//! release time, letting the tail of the keyed sound in too.
+//!
+//! Ahead of the follower, a fixed [`DETECTOR_HIGHPASS_HZ`] highpass
+//! keeps low end out of the key. A snare mic hears the kick and the bass
+//! too, and they carry most of its energy; unfiltered, they'd open the
+//! gate on every beat.
+//!
+//! ## Listening
+//!
+//! [`listen()`](KeyGate::listen) plays the key as the detector hears
+//! it, through a second copy of the highpass, so the engine can put it
+//! on the output in place of the delay (Key Listen). The copy is fed
+//! the same key as the detector's own, so what's heard is what the
+//! threshold is measured against.
+
+use super::envelope::{DetectorMode, EnvelopeFollower};
+use super::filter::OnePoleHighpass;
+use super::smoother::Smoother;
+
+/// How fast the gate opens, in milliseconds. A millisecond is short
+/// enough to let a drum's attack through and long enough not to click.
+pub const OPEN_MS: f32 = 1.0;
+
+/// The key detector's release, in milliseconds. Long enough to bridge
+/// the gaps between the peaks of a 50 Hz tone.
+const DETECTOR_RELEASE_MS: f32 = 30.0;
+
+/// The cutoff of the highpass the key passes through before it's
+/// measured, in Hz: below a snare's body, above a kick's.
+pub const DETECTOR_HIGHPASS_HZ: f32 = 150.0;
+
+/// Opens the delay's input while a key signal is loud.
+pub struct KeyGate {
+    highpass: OnePoleHighpass,
+
+    /// The highpass again, for [`listen()`](Self::listen). The engine
+    /// listens after the block, while `highpass` runs inside it.
+    listen_highpass: OnePoleHighpass,
+    detector: EnvelopeFollower,
+    sample_rate: f32,
+
+    /// Whether the gate is in circuit. Out of it, the gate stands open.
+    enabled: bool,
+
+    /// The key level, as a linear gain, above which the gate opens.
+    threshold: f32,
+
+    /// The gate's gain, from 0.0 (closed) to 1.0 (open). Opens over
+    /// [`OPEN_MS`] and closes over `release_ms`.
+    gain: Smoother,
+    release_ms: f32,
+}
+
+impl KeyGate {
+    /// Create a gate that's switched off (standing open). Switched on,
+    /// it opens at −30 dBFS and closes over 100 ms.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut detector = EnvelopeFollower::new(sample_rate);
+        detector.set_mode(DetectorMode::Peak);
+        detector.set_attack_ms(0.0);
+        detector.set_release_ms(DETECTOR_RELEASE_MS);
+        let mut highpass = OnePoleHighpass::new(sample_rate);
+        highpass.set_cutoff(DETECTOR_HIGHPASS_HZ, sample_rate);
+        let release_ms = 100.0;
+        Self {
+            listen_highpass: highpass.clone(),
+            highpass,
+            detector,
+            sample_rate,
+            enabled: false,
+            threshold: db_to_gain(-30.0),
+            gain: Smoother::new(OPEN_MS, 1.0).with_fall_ms(release_ms),
+            release_ms,
+        }
+    }
+
+    /// Put the gate in or out of circuit. Either way the gain ramps
+    /// from where it is, so switching doesn't click.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Whether the gate is in circuit.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set the key level, in dBFS, above which the gate opens.
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold = db_to_gain(threshold_db);
+    }
+
+    /// Set how long the gate takes to close, in milliseconds. A close
+    /// already under way starts over from where it is, at the new pace.
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        if release_ms == self.release_ms {
+            return;
+        }
+        self.release_ms = release_ms;
+        let target = self.gain.target();
+        self.gain = Smoother::new(OPEN_MS, self.gain.current()).with_fall_ms(release_ms);
+        self.gain.set_target(self.sample_rate, target);
+    }
+
+    /// Measure one sample of the key (the loudest of its channels, say)
+    /// and return the gate's gain for it.
+    ///
+    /// `None` means there's no key to listen to. The gate then opens, as
+    /// it does when switched off: with nothing plugged into the
+    /// sidechain, the delay behaves as if there were no gate at all.
+    #[inline]
+    pub fn process(&mut self, key: Option<f32>) -> f32 {
+        let open = match key {
+            Some(key) if self.enabled => {
+                self.detector.process(self.highpass.process(key)) > self.threshold
+            }
+            _ => true,
+        };
+        let target = if open { 1.0 } else { 0.0 };
+        self.gain.set_target(self.sample_rate, target);
+        self.gain.next()
+    }
+
+    /// One sample of the key as the detector hears it: through the
+    /// [`DETECTOR_HIGHPASS_HZ`] highpass, whether the gate is on or not.
+    #[inline]
+    pub fn listen(&mut self, key: f32) -> f32 {
+        self.listen_highpass.process(key)
+    }
+
+    /// Whether the gate is switched off and standing fully open, so
+    /// [`process()`](Self::process) would return 1.0 whatever the key.
+    pub fn is_bypassed(&self) -> bool {
+        !self.enabled && !self.gain.is_smoothing() && self.gain.current() == 1.0
+    }
+
+    /// Forget the key. A gate in circuit closes at once, ready for the
+    /// next key; one out of circuit opens.
+    pub fn reset(&mut self) {
+        self.highpass.reset();
+        self.listen_highpass.reset();
+        self.detector.reset();
+        self.gain.reset(if self.enabled { 0.0 } else { 1.0 });
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// A loud key opens the gate within the open time and holds it open
+    /// through a tone's zero crossings; once the key stops, the gate
+    /// closes over the release time (plus the detector's fall). Without
+    /// a key it opens again.
+    #[test]
+    fn test_opens_on_key_and_releases() {
+        let mut gate = KeyGate::new(SAMPLE_RATE);
+        assert!(gate.is_bypassed());
+        gate.set_enabled(true);
+        gate.set_threshold_db(-20.0);
+        gate.set_release_ms(50.0);
+        gate.reset();
+
+        assert_eq!(gate.process(Some(0.05)), 0.0);
+        let ms = |ms: f32| (ms / 1000.0 * SAMPLE_RATE) as usize;
+        let tone = |n: usize| 0.5 * (std::f32::consts::TAU * 1000.0 * n as f32 / SAMPLE_RATE).sin();
+
+        let opening: Vec<f32> = (1..ms(100.0))
+            .map(|n| gate.process(Some(tone(n))))
+            .collect();
+        let open_at = opening.iter().position(|g| *g == 1.0).unwrap();
+        assert!(open_at < ms(OPEN_MS) + ms(1.0), "opened at {open_at}");
+        assert!(opening[open_at..].iter().all(|g| *g == 1.0));
+
+        let closing: Vec<f32> = (0..ms(200.0)).map(|_| gate.process(Some(0.0))).collect();
+        let closed_at = closing.iter().position(|g| *g == 0.0).unwrap();
+        assert!(
+            (ms(50.0)..ms(50.0 + DETECTOR_RELEASE_MS * 3.0)).contains(&closed_at),
+            "closed at {closed_at}"
+        );
+
+        // No key: the gate stands open.
+        let unkeyed: Vec<f32> = (0..ms(10.0)).map(|_| gate.process(None)).collect();
+        assert_eq!(unkeyed.last(), Some(&1.0));
+    }
+
+    /// Low end doesn't open the gate: a 40 Hz key loud enough to clear
+    /// the threshold twice over stays shut out by the detector's
+    /// highpass, while the same level at 1 kHz opens it.
+    #[test]
+    fn test_low_end_does_not_open() {
+        let keyed = |hz: f32| {
+            let mut gate = KeyGate::new(SAMPLE_RATE);
+            gate.set_enabled(true);
+            gate.set_threshold_db(-20.0);
+            gate.reset();
+            (0..SAMPLE_RATE as usize / 2)
+                .map(|n| {
+                    let key = 0.25 * (std::f32::consts::TAU * hz * n as f32 / SAMPLE_RATE).sin();
+                    gate.process(Some(key))
+                })
+                .fold(0.0_f32, f32::max)
+        };
+        assert_eq!(keyed(40.0), 0.0);
+        assert_eq!(keyed(1000.0), 1.0);
+    }
+
+    /// Listening plays the key through the detector's highpass: 1 kHz
+    /// passes almost untouched, 40 Hz is cut well down, and it does so
+    /// with the gate switched off too.
+    #[test]
+    fn test_listen_plays_the_highpassed_key() {
+        let peak = |hz: f32| {
+            let mut gate = KeyGate::new(SAMPLE_RATE);
+            let len = SAMPLE_RATE as usize / 2;
+            (0..len)
+                .map(|n| {
+                    let key = 0.5 * (std::f32::consts::TAU * hz * n as f32 / SAMPLE_RATE).sin();
+                    gate.listen(key)
+                })
+                .skip(len / 2)
+                .fold(0.0_f32, |peak, sample| peak.max(sample.abs()))
+        };
+        assert!(peak(1000.0) > 0.45, "1 kHz at {}", peak(1000.0));
+        assert!(peak(40.0) < 0.15, "40 Hz at {}", peak(40.0));
+    }
+}
@@ -0,0 +1,674 @@
+//! # Low-Frequency Oscillator
+//!
+//! An LFO is a slow, inaudible wave used to *move* something else: wobble
+//! the delay time for chorus or tape flutter, sweep a filter, pan the
+//! repeats. It runs well below the audio range — a fraction of a hertz up
+//! to a few tens of hertz — and its output is a control signal in −1..1,
+//! not sound.
+//!
+//! ## The Phase Accumulator
+//!
+//! Every waveform is a function of a single number, the *phase*: how far
+//! through the current cycle we are, from 0.0 (start) to 1.0 (back at the
+//! start). Each sample the phase advances by a fixed increment and wraps
+//! around at 1.0:
+//!
+//! ```text
+//! increment = rate_hz / sample_rate
+//!
+//! 2 Hz at 48 kHz: increment = 0.0000417, one cycle every 24000 samples
+//! ```
+//!
+//! The phase is kept in `f64`. An `f32` phase loses enough precision over
+//! millions of tiny increments that a slow LFO audibly drifts off tempo.
+//!
+//! ## The Waveforms
+//!
+//! ```text
+//! Sine      smooth, the natural choice for chorus and vibrato
+//! Triangle  linear ramps up and down; like sine, but with sharper turns
+//! Saw       rises steadily, then snaps back to the bottom
+//! Square    jumps between +1 and −1 every half cycle
+//! S&H       "sample and hold": a new random level every cycle
+//! ```
+//!
+//! Sine and triangle start at 0 and rise, so swapping between them keeps
+//! the LFO in step.
+//!
+//! ## Gliding Sample-and-Hold
+//!
+//! Raw sample-and-hold jumps from one level to the next in a single
+//! sample. On a filter that's a pleasant step; on a delay time it's a
+//! click, because the read head jumps through the buffer. So the held
+//! level reaches the output through a one-pole *slew*, which chases it
+//! the way a capacitor charges:
+//!
+//! ```text
+//! out += (held − out) × coeff          coeff = 1 − e^(−1/τ)
+//!
+//! held   ──┐     ┌────          out  ──╮    ╭───
+//!          └─────┘                     ╰────╯
+//! ```
+//!
+//! The *glide* sets how long the chase takes as a fraction of the cycle,
+//! so the character stays the same at any rate: at a glide of 0.5 the
+//! output is 98% of the way to each new level halfway through its cycle
+//! (four time constants), whether the cycle lasts a second or a tenth
+//! of one. The glide never goes below [`MIN_GLIDE`], so there's always
+//! some slew between steps. Every slewed value lies between levels that
+//! were held, so the output never leaves −1..1.
+//!
+//! The random levels come from a seeded generator: the same seed (see
+//! [`Lfo::with_seed()`]) always gives the same sequence, so a test or a
+//! bounce can count on it.
+//!
+//! ## Phase Offset
+//!
+//! Two LFOs at the same rate, one shifted by a quarter cycle, move the
+//! left and right channels at different moments — the classic way to
+//! widen a modulated delay. The offset is added to the phase when it's
+//! read, so changing it never disturbs the running accumulator.
+//!
+//! ## Tempo Sync and Retrigger
+//!
+//! A synced LFO takes its rate from a [`NoteDivision`] at the song's
+//! tempo: one cycle per beat, per dotted eighth, and so on. The right
+//! rate isn't enough to keep it on the grid, though: *where* in its
+//! cycle it is depends on when it started, so a free-running LFO
+//! wobbles differently on every bounce, and drifts against a looped
+//! bar whose length isn't a whole number of cycles. The
+//! [`LfoRetrigger`] mode picks what the phase follows:
+//!
+//! - **Free**: nothing. The LFO runs on from wherever it is; the owner
+//!   can still call [`Lfo::reset()`] when the transport starts.
+//! - **Transport**: the host's playhead. At the start of each block the
+//!   owner hands [`Lfo::sync_to_beats()`] the playhead position, and the
+//!   phase is worked out from it:
+//!
+//! ```text
+//! phase = (pos_beats / division_beats) mod 1
+//!
+//! dotted eighth (0.75 beats), playhead at beat 5.5:   5.5 / 0.75 = 7.333
+//!                                                     phase 0.333
+//! ```
+//!
+//! So the same beat always gets the same point of the wave, however the
+//! playhead got there: every bounce, every pass round a loop.
+//!
+//! A third mode, retriggering on each MIDI note-on, waits for the
+//! plugin to take MIDI input.
+//!
+//! ### Slewing the Correction
+//!
+//! While the host plays straight through, the worked-out phase is where
+//! the accumulator already is, and nothing changes. When the playhead
+//! *jumps* — a loop wrapping, the user clicking somewhere else — the
+//! two disagree, and setting the phase outright would jump the output
+//! too: a click on a delay time. Instead the difference is spread over
+//! [`PHASE_SLEW_MS`], added a little each sample on top of the normal
+//! increment. The LFO briefly runs fast (or slow) and catches up with
+//! the grid, the way a tape machine's servo locks to timecode:
+//!
+//! ```text
+//! correction = wrap(target − phase)          shortest way, −½..½ cycle
+//! per sample = correction / slew_samples     for slew_samples samples
+//! ```
+
+use super::{noise::Xorshift32, tempo::NoteDivision};
+
+/// The shortest glide between sample-and-hold levels, as a fraction of
+/// the cycle: short enough to still sound stepped, long enough that a
+/// new level is never a jump.
+pub const MIN_GLIDE: f32 = 0.05;
+
+/// How long a transport-locked LFO takes to catch up with a jump in the
+/// playhead (see [the module docs](self#slewing-the-correction)).
+pub const PHASE_SLEW_MS: f32 = 5.0;
+
+/// Phase differences smaller than this, in cycles, are rounding, not a
+/// jump: left alone.
+const PHASE_EPSILON: f64 = 1e-9;
+
+/// The generator seed a new LFO starts from.
+const DEFAULT_SEED: u32 = 0x9E37_79B9;
+
+/// The shapes an [`Lfo`] can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoWaveform {
+    /// Smooth sine wave.
+    #[default]
+    Sine,
+    /// Linear ramps up and down.
+    Triangle,
+    /// Rising ramp that snaps back down.
+    Saw,
+    /// +1 for the first half of each cycle, −1 for the second.
+    Square,
+    /// A new random level at the start of every cycle, held until the
+    /// next, with a glide between levels (see [`Lfo::set_glide()`]).
+    SampleAndHold,
+}
+
+/// What a synced [`Lfo`]'s phase follows. See
+/// [the module docs](self#tempo-sync-and-retrigger).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoRetrigger {
+    /// Run on from wherever the phase is.
+    #[default]
+    Free,
+    /// Lock the phase to the host's playhead.
+    Transport,
+}
+
+/// A phase-accumulator LFO with a selectable waveform and phase offset.
+#[derive(Debug, Clone)]
+pub struct Lfo {
+    /// Position in the current cycle, 0.0..1.0.
+    phase: f64,
+
+    /// How far the phase advances each sample (`rate / sample_rate`).
+    increment: f64,
+
+    /// Added to the phase when reading, in cycles (0.0..1.0).
+    phase_offset: f64,
+
+    waveform: LfoWaveform,
+
+    /// The level the sample-and-hold waveform is holding, and the
+    /// output gliding after it.
+    held: f32,
+    slewed: f32,
+
+    /// The glide, as a fraction of the cycle, and the slew coefficient
+    /// it works out to at the current rate.
+    glide: f32,
+    slew_coeff: f32,
+
+    /// Where the offset phase was on the previous sample, so
+    /// sample-and-hold can spot the start of a new cycle.
+    last_read_phase: f64,
+
+    /// The random number generator behind sample-and-hold.
+    rng: Xorshift32,
+
+    /// What the phase follows.
+    retrigger: LfoRetrigger,
+
+    /// A playhead jump being caught up with: added to the phase each
+    /// sample, for `correction_samples` more samples.
+    correction: f64,
+    correction_samples: usize,
+}
+
+impl Lfo {
+    /// Create a stopped sine LFO (rate 0) at phase 0.
+    pub fn new() -> Self {
+        let mut lfo = Self {
+            phase: 0.0,
+            increment: 0.0,
+            phase_offset: 0.0,
+            waveform: LfoWaveform::Sine,
+            held: 0.0,
+            slewed: 0.0,
+            glide: MIN_GLIDE,
+            slew_coeff: 0.0,
+            last_read_phase: 0.0,
+            rng: Xorshift32::new(DEFAULT_SEED),
+            retrigger: LfoRetrigger::Free,
+            correction: 0.0,
+            correction_samples: 0,
+        };
+        lfo.held = lfo.rng.next_bipolar();
+        lfo.slewed = lfo.held;
+        lfo
+    }
+
+    /// Draw the sample-and-hold levels from `seed` instead, starting
+    /// from its first level.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.rng = Xorshift32::new(seed);
+        self.held = self.rng.next_bipolar();
+        self.slewed = self.held;
+        self
+    }
+
+    /// Set a free-running rate in Hz.
+    pub fn set_rate_hz(&mut self, rate_hz: f32, sample_rate: f32) {
+        self.increment = f64::from(rate_hz.max(0.0)) / f64::from(sample_rate);
+        self.update_slew();
+    }
+
+    /// Set the rate to one cycle per `division` at `tempo_bpm`.
+    pub fn set_rate_sync(&mut self, division: NoteDivision, tempo_bpm: f64, sample_rate: f32) {
+        let cycle_seconds = division.to_ms(tempo_bpm) / 1000.0;
+        self.increment = 1.0 / (cycle_seconds * f64::from(sample_rate));
+        self.update_slew();
+    }
+
+    /// Set how long sample-and-hold glides to each new level, as a
+    /// fraction of the cycle (clamped to [`MIN_GLIDE`]..=1.0). See [the
+    /// module docs](self).
+    pub fn set_glide(&mut self, glide: f32) {
+        self.glide = glide.clamp(MIN_GLIDE, 1.0);
+        self.update_slew();
+    }
+
+    /// Work the slew coefficient out from the glide and the rate: four
+    /// time constants per glide.
+    fn update_slew(&mut self) {
+        let cycle_samples = 1.0 / self.increment;
+        let tau = f64::from(self.glide) * cycle_samples / 4.0;
+        // A stopped LFO (an infinite cycle) never moves on from its level.
+        self.slew_coeff = (1.0 - (-1.0 / tau).exp()) as f32;
+    }
+
+    /// Choose the waveform. The phase carries on where it was.
+    pub fn set_waveform(&mut self, waveform: LfoWaveform) {
+        self.waveform = waveform;
+    }
+
+    /// The waveform being played.
+    pub fn waveform(&self) -> LfoWaveform {
+        self.waveform
+    }
+
+    /// Shift the output by `offset` cycles (0.25 = a quarter cycle
+    /// ahead). Values outside 0.0..1.0 wrap.
+    pub fn set_phase_offset(&mut self, offset: f32) {
+        self.phase_offset = f64::from(offset).rem_euclid(1.0);
+    }
+
+    /// Choose what the phase follows. Switching to
+    /// [`LfoRetrigger::Free`] lets the phase run on from where it is.
+    pub fn set_retrigger(&mut self, retrigger: LfoRetrigger) {
+        self.retrigger = retrigger;
+    }
+
+    /// Lock the phase to the playhead at `pos_beats`, for a cycle of one
+    /// `division`: call it at the start of each block, before that
+    /// block's samples. A phase off the grid is slewed back onto it over
+    /// [`PHASE_SLEW_MS`] (see [the module docs](self#slewing-the-correction)).
+    /// Does nothing unless the retrigger mode is
+    /// [`LfoRetrigger::Transport`].
+    pub fn sync_to_beats(&mut self, pos_beats: f64, division: NoteDivision, sample_rate: f32) {
+        if self.retrigger != LfoRetrigger::Transport {
+            return;
+        }
+        let target = (pos_beats / division.beats()).rem_euclid(1.0);
+        // Where the phase will be once the correction under way is done.
+        let pending = self.correction * self.correction_samples as f64;
+        let heading = self.phase + pending;
+        let error = (target - heading + 0.5).rem_euclid(1.0) - 0.5;
+        if error.abs() < PHASE_EPSILON {
+            return;
+        }
+        let slew_samples = (PHASE_SLEW_MS / 1000.0 * sample_rate).max(1.0) as usize;
+        self.correction = (pending + error) / slew_samples as f64;
+        self.correction_samples = slew_samples;
+    }
+
+    /// Jump to `phase` (in cycles, wrapped into 0.0..1.0) — e.g. to 0.0
+    /// when the transport starts, so a synced LFO lines up with the
+    /// beat. Any correction under way is dropped.
+    pub fn reset(&mut self, phase: f32) {
+        self.correction = 0.0;
+        self.correction_samples = 0;
+        self.phase = f64::from(phase).rem_euclid(1.0);
+        self.last_read_phase = (self.phase + self.phase_offset).fract();
+        self.held = self.rng.next_bipolar();
+    }
+
+    /// The next output value, in −1.0..=1.0. Like the smoother's, an
+    /// LFO never runs out, so this isn't an `Iterator`.
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        let phase = (self.phase + self.phase_offset).fract();
+
+        // The read phase wrapping around means a new cycle has begun.
+        if phase < self.last_read_phase {
+            self.held = self.rng.next_bipolar();
+        }
+        self.last_read_phase = phase;
+        self.slewed += (self.held - self.slewed) * self.slew_coeff;
+
+        let output = match self.waveform {
+            LfoWaveform::Sine => (std::f64::consts::TAU * phase).sin() as f32,
+            LfoWaveform::Triangle => {
+                // 0 → 1 over the first quarter, down to −1 by three
+                // quarters, back to 0 at the end.
+                let value = if phase < 0.25 {
+                    4.0 * phase
+                } else if phase < 0.75 {
+                    2.0 - 4.0 * phase
+                } else {
+                    4.0 * phase - 4.0
+                };
+                value as f32
+            }
+            LfoWaveform::Saw => (2.0 * phase - 1.0) as f32,
+            LfoWaveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoWaveform::SampleAndHold => self.slewed,
+        };
+
+        let mut step = self.increment;
+        if self.correction_samples > 0 {
+            step += self.correction;
+            self.correction_samples -= 1;
+        }
+        self.phase = (self.phase + step).rem_euclid(1.0);
+        output
+    }
+}
+
+impl Default for Lfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockTransport;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Count the upward zero crossings in `samples` of an LFO's output.
+    fn rising_crossings(lfo: &mut Lfo, samples: usize) -> usize {
+        let mut previous = lfo.next();
+        let mut crossings = 0;
+        for _ in 1..samples {
+            let value = lfo.next();
+            if previous < 0.0 && value >= 0.0 {
+                crossings += 1;
+            }
+            previous = value;
+        }
+        crossings
+    }
+
+    /// A 3 Hz sine crosses zero upward 3 times a second, and stays on
+    /// rate over a long run.
+    #[test]
+    fn test_frequency_accuracy() {
+        let mut lfo = Lfo::new();
+        lfo.set_rate_hz(3.0, SAMPLE_RATE);
+        let seconds = 100;
+        let crossings = rising_crossings(&mut lfo, SAMPLE_RATE as usize * seconds);
+        assert!(
+            (crossings as i64 - 3 * seconds as i64).abs() <= 1,
+            "{crossings} crossings in {seconds}s"
+        );
+    }
+
+    /// A synced quarter note at 120 BPM is 2 Hz.
+    #[test]
+    fn test_tempo_sync_rate() {
+        let mut lfo = Lfo::new();
+        lfo.set_rate_sync(NoteDivision::Quarter, 120.0, SAMPLE_RATE);
+        let crossings = rising_crossings(&mut lfo, SAMPLE_RATE as usize * 10);
+        assert!((crossings as i64 - 20).abs() <= 1, "{crossings}");
+    }
+
+    /// Each waveform's value at the start and at each quarter cycle.
+    #[test]
+    fn test_waveform_shapes() {
+        let expected: [(LfoWaveform, [f32; 4]); 4] = [
+            (LfoWaveform::Sine, [0.0, 1.0, 0.0, -1.0]),
+            (LfoWaveform::Triangle, [0.0, 1.0, 0.0, -1.0]),
+            (LfoWaveform::Saw, [-1.0, -0.5, 0.0, 0.5]),
+            (LfoWaveform::Square, [1.0, 1.0, -1.0, -1.0]),
+        ];
+        for (waveform, values) in expected {
+            let mut lfo = Lfo::new();
+            lfo.set_waveform(waveform);
+            // Four samples per cycle: one per quarter.
+            lfo.set_rate_hz(SAMPLE_RATE / 4.0, SAMPLE_RATE);
+            for (quarter, want) in values.into_iter().enumerate() {
+                let got = lfo.next();
+                assert!(
+                    (got - want).abs() < 1e-6,
+                    "{waveform:?} at quarter {quarter}: {got}, expected {want}"
+                );
+            }
+        }
+    }
+
+    /// Sample-and-hold picks one level per cycle and changes it at the
+    /// next. Even at the shortest glide the output settles on the level
+    /// well within the cycle, and it stays within −1..1.
+    #[test]
+    fn test_sample_and_hold() {
+        let mut lfo = Lfo::new();
+        lfo.set_waveform(LfoWaveform::SampleAndHold);
+        // 128 samples per cycle, so the increment is exact in binary.
+        lfo.set_rate_hz(SAMPLE_RATE / 128.0, SAMPLE_RATE);
+
+        let cycles: Vec<Vec<(f32, f32)>> = (0..20)
+            .map(|_| (0..128).map(|_| (lfo.next(), lfo.held)).collect())
+            .collect();
+        for cycle in &cycles {
+            let level = cycle[0].1;
+            assert!(
+                cycle.iter().all(|&(_, held)| held == level),
+                "level moved mid-cycle"
+            );
+            assert!((-1.0..=1.0).contains(&level));
+            for &(output, _) in &cycle[64..] {
+                assert!(
+                    (output - level).abs() < 1e-5,
+                    "{output} not settled on {level}"
+                );
+            }
+        }
+        assert!(cycles.windows(2).any(|w| w[0][0].1 != w[1][0].1));
+    }
+
+    /// With a glide, the output never steps: no sample moves further
+    /// than the slew allows, and halfway through each cycle a glide of
+    /// 0.5 is 98% of the way to the new level. The output stays within
+    /// −1..1, so within ± the depth once the owner scales it.
+    #[test]
+    fn test_sample_and_hold_glides() {
+        const CYCLE: usize = 4096;
+        let mut lfo = Lfo::new().with_seed(1234);
+        lfo.set_waveform(LfoWaveform::SampleAndHold);
+        lfo.set_rate_hz(SAMPLE_RATE / CYCLE as f32, SAMPLE_RATE);
+        lfo.set_glide(0.5);
+
+        let max_step = 2.0 * lfo.slew_coeff;
+        assert!(max_step < 0.005, "{max_step}");
+        let mut previous = lfo.next();
+        let mut start = previous;
+        for n in 1..20 * CYCLE {
+            let value = lfo.next();
+            assert!((-1.0..=1.0).contains(&value));
+            assert!(
+                (value - previous).abs() <= max_step,
+                "sample {n} stepped by {}",
+                value - previous
+            );
+            if n % CYCLE == 0 {
+                start = previous;
+            } else if n % CYCLE == CYCLE / 2 {
+                let left = (value - lfo.held).abs();
+                assert!(
+                    left <= 0.02 * (start - lfo.held).abs() + 1e-6,
+                    "sample {n}: {left} left"
+                );
+            }
+            previous = value;
+        }
+    }
+
+    /// The levels change once per cycle, on time: at a rate that isn't a
+    /// whole number of samples, each hold lasts the cycle rounded one way
+    /// or the other.
+    #[test]
+    fn test_hold_period_matches_rate() {
+        let cycle = SAMPLE_RATE / 7.3;
+        let mut lfo = Lfo::new();
+        lfo.set_waveform(LfoWaveform::SampleAndHold);
+        lfo.set_rate_hz(7.3, SAMPLE_RATE);
+
+        let mut held = lfo.held;
+        let mut changes = Vec::new();
+        for n in 0..(20.0 * cycle) as usize {
+            lfo.next();
+            if lfo.held != held {
+                changes.push(n);
+                held = lfo.held;
+            }
+        }
+        assert_eq!(changes.len(), 19);
+        for pair in changes.windows(2) {
+            let hold = (pair[1] - pair[0]) as f32;
+            assert!(
+                (hold - cycle).abs() < 1.0,
+                "held {hold} samples, cycle {cycle}"
+            );
+        }
+    }
+
+    /// The same seed always gives the same levels; another seed doesn't.
+    #[test]
+    fn test_seeded_levels_repeat() {
+        let render = |seed| {
+            let mut lfo = Lfo::new().with_seed(seed);
+            lfo.set_waveform(LfoWaveform::SampleAndHold);
+            lfo.set_rate_hz(20.0, SAMPLE_RATE);
+            (0..48000).map(|_| lfo.next()).collect::<Vec<f32>>()
+        };
+        assert_eq!(render(7), render(7));
+        assert_ne!(render(7), render(8));
+    }
+
+    /// Two LFOs a quarter cycle apart: the offset one runs a quarter of
+    /// a period ahead of the other — what stereo spread depends on.
+    #[test]
+    fn test_phase_offset_between_instances() {
+        let period = 4800;
+        let mut left = Lfo::new();
+        let mut right = Lfo::new();
+        for lfo in [&mut left, &mut right] {
+            lfo.set_rate_hz(SAMPLE_RATE / period as f32, SAMPLE_RATE);
+        }
+        right.set_phase_offset(0.25);
+
+        let l: Vec<f32> = (0..2 * period).map(|_| left.next()).collect();
+        let r: Vec<f32> = (0..2 * period).map(|_| right.next()).collect();
+        for n in 0..period {
+            assert!(
+                (r[n] - l[n + period / 4]).abs() < 1e-4,
+                "sample {n}: {} vs {}",
+                r[n],
+                l[n + period / 4]
+            );
+        }
+    }
+
+    /// Resetting returns the LFO to the requested point in its cycle.
+    #[test]
+    fn test_reset_retriggers() {
+        let mut lfo = Lfo::new();
+        lfo.set_rate_hz(1.7, SAMPLE_RATE);
+        for _ in 0..12345 {
+            lfo.next();
+        }
+        lfo.reset(0.0);
+        assert_eq!(lfo.next(), 0.0);
+        lfo.reset(0.25);
+        assert!((lfo.next() - 1.0).abs() < 1e-6);
+    }
+
+    /// Play `passes` passes of a looped bar at 120 BPM through an LFO
+    /// cycling every dotted eighth — 5⅓ cycles to the bar, so a free
+    /// LFO starts each pass somewhere new — and return each pass.
+    fn render_looped_bar(retrigger: LfoRetrigger, passes: usize) -> Vec<Vec<f32>> {
+        const BLOCK: usize = 480;
+        // Four beats at 120 BPM.
+        const BAR: usize = 96_000;
+        let mut host = MockTransport::new(SAMPLE_RATE, 120.0);
+        host.set_loop(Some((0.0, 4.0)));
+        host.play();
+        let mut lfo = Lfo::new();
+        lfo.set_rate_sync(NoteDivision::DottedEighth, 120.0, SAMPLE_RATE);
+        lfo.set_retrigger(retrigger);
+
+        let mut out = Vec::new();
+        for _ in 0..passes * BAR / BLOCK {
+            let transport = host.next_block(BLOCK);
+            lfo.sync_to_beats(
+                transport.pos_beats.unwrap(),
+                NoteDivision::DottedEighth,
+                SAMPLE_RATE,
+            );
+            out.extend((0..BLOCK).map(|_| lfo.next()));
+        }
+        out.chunks(BAR).map(<[f32]>::to_vec).collect()
+    }
+
+    /// Locked to the transport, every pass round a looped bar wobbles
+    /// the same: the later passes match sample for sample, and match
+    /// the first once the catch-up at the loop's start is over. Free,
+    /// each pass starts a third of a cycle on from the last.
+    #[test]
+    fn test_transport_lock_repeats_every_pass() {
+        let slew = (PHASE_SLEW_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let passes = render_looped_bar(LfoRetrigger::Transport, 3);
+        let max_diff = |a: &[f32], b: &[f32]| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max)
+        };
+        assert!(max_diff(&passes[1], &passes[2]) < 1e-5);
+        assert!(max_diff(&passes[0][slew..], &passes[1][slew..]) < 1e-5);
+        // The first pass started on the grid, with nothing to catch up.
+        assert!(passes[0][0].abs() < 1e-6);
+
+        let free = render_looped_bar(LfoRetrigger::Free, 3);
+        assert_eq!(free[0], passes[0]);
+        assert!(max_diff(&free[1], &free[2]) > 0.5);
+    }
+
+    /// Catching up with a jump never steps the output: a sine moves no
+    /// further per sample than the fastest the catch-up runs it.
+    #[test]
+    fn test_transport_jump_slews() {
+        let mut lfo = Lfo::new();
+        lfo.set_rate_sync(NoteDivision::Quarter, 120.0, SAMPLE_RATE);
+        lfo.set_retrigger(LfoRetrigger::Transport);
+        for _ in 0..1000 {
+            lfo.next();
+        }
+        // The playhead jumps half a cycle away from where the LFO is.
+        let phase = lfo.phase;
+        lfo.sync_to_beats(phase + 0.5, NoteDivision::Quarter, SAMPLE_RATE);
+
+        let slew = (PHASE_SLEW_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let fastest = lfo.increment + 0.5 / slew as f64;
+        let max_step = (std::f64::consts::TAU * fastest) as f32 * 1.01;
+        let mut previous = lfo.next();
+        for n in 1..2 * slew {
+            let value = lfo.next();
+            assert!((value - previous).abs() <= max_step, "sample {n}");
+            previous = value;
+        }
+        // Caught up: on the grid, half a cycle on.
+        let expected = (phase + 0.5 + 2.0 * slew as f64 * lfo.increment).rem_euclid(1.0);
+        let error = (lfo.phase - expected + 0.5).rem_euclid(1.0) - 0.5;
+        assert!(error.abs() < 1e-9, "{error}");
+    }
+}
@@ -0,0 +1,238 @@
+//! # Soft-Knee Output Limiter
+//!
+//! A limiter is a compressor with an infinite ratio: once the signal
+//! reaches the threshold, it simply isn't allowed to get any louder. We
+//! use one on the output so that feedback experiments (high feedback,
+//! boosted repeats) can never send "overs" — samples beyond 0 dBFS — to
+//! the host.
+//!
+//! ## Gain Computer
+//!
+//! The limiter works in decibels. For an input level `x` (in dBFS), the
+//! *gain computer* decides what the output level `y` should be:
+//!
+//! ```text
+//!   y (dB)
+//!    │                 ______________  threshold T
+//!    │               /
+//!    │             /   ← soft knee (width W), curving smoothly
+//!    │           /       from "no change" to "flat"
+//!    │         /
+//!    │       /  ← below the knee, y = x (untouched)
+//!    └──────────────────────────── x (dB)
+//! ```
+//!
+//! - Below the knee (`x < T - W/2`): `y = x`
+//! - Inside the knee: `y = x - (x - T + W/2)² / (2W)`
+//! - Above the knee (`x > T + W/2`): `y = T`
+//!
+//! The quadratic knee meets both straight lines with matching slope, so
+//! there's no audible "corner" where limiting kicks in. At the top of the
+//! knee the curve reaches exactly `T`, so the output never exceeds the
+//! threshold. The gain to apply is `y - x` dB.
+//!
+//! ## Attack and Release
+//!
+//! Without lookahead (which would add latency), the only way to
+//! *guarantee* the ceiling is an instant attack: when a peak arrives, the
+//! gain drops to the required value on that very sample. The release is
+//! slow (~50 ms) so the gain recovers smoothly after the peak instead of
+//! tracking every waveform cycle, which would distort.
+//!
+//! The release uses the same one-pole smoothing as our lowpass filter:
+//!
+//! ```text
+//! gain = target + (gain - target) * release_coeff
+//! release_coeff = e^(-1 / (release_time * sample_rate))
+//! ```
+//!
+//! After one release time constant the gain has recovered ~63% of the
+//! way back to its target.
+
+/// Output ceiling in dBFS. Just under full scale so that inter-sample
+/// rounding in the host can't tip a peak over 0 dBFS.
+pub const THRESHOLD_DB: f32 = -0.1;
+
+/// Width of the soft knee in dB, centered below the threshold's top.
+pub const KNEE_DB: f32 = 2.0;
+
+/// Release time constant in milliseconds.
+pub const RELEASE_MS: f32 = 50.0;
+
+/// A soft-knee, zero-latency peak limiter for one channel.
+#[derive(Clone)]
+pub struct Limiter {
+    /// Current gain (linear, 0.0–1.0) applied to the signal.
+    gain: f32,
+
+    /// One-pole coefficient for the release, derived from `RELEASE_MS`.
+    release_coeff: f32,
+
+    /// Linear amplitude where the knee starts. Anything quieter passes
+    /// through untouched, which lets us skip the `log10()` entirely for
+    /// normal-level material.
+    knee_start: f32,
+}
+
+impl Limiter {
+    /// Create a limiter at unity gain for the given sample rate.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut limiter = Self {
+            gain: 1.0,
+            release_coeff: 0.0,
+            knee_start: db_to_gain(THRESHOLD_DB - KNEE_DB / 2.0),
+        };
+        limiter.set_sample_rate(sample_rate);
+        limiter
+    }
+
+    /// Recompute the release coefficient for a new sample rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let release_samples = RELEASE_MS / 1000.0 * sample_rate;
+        self.release_coeff = (-1.0 / release_samples).exp();
+    }
+
+    /// The static gain curve: the (linear) gain needed for an input
+    /// peak of amplitude `level`, before any release smoothing.
+    pub fn target_gain(&self, level: f32) -> f32 {
+        let level = level.abs();
+        if level < self.knee_start {
+            return 1.0;
+        }
+
+        let x = 20.0 * level.log10();
+        let over = x - THRESHOLD_DB + KNEE_DB / 2.0;
+        let y = if over < KNEE_DB {
+            // Inside the knee: ease from "no change" into "flat".
+            x - over * over / (2.0 * KNEE_DB)
+        } else {
+            // Above the knee: pinned to the threshold.
+            THRESHOLD_DB
+        };
+
+        db_to_gain(y - x)
+    }
+
+    /// Limit one sample.
+    pub fn process(&mut self, input: f32) -> f32 {
+        let target = self.target_gain(input);
+
+        self.gain = if target < self.gain {
+            // Instant attack: never let a peak through.
+            target
+        } else {
+            // Release: glide back up toward the target.
+            target + (self.gain - target) * self.release_coeff
+        };
+
+        input * self.gain
+    }
+
+    /// The gain currently being applied (1.0 = no limiting).
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Return to unity gain.
+    pub fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
+
+/// Convert decibels to a linear amplitude: `10^(dB / 20)`.
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// A sine 6 dB over full scale must never come out above 0 dBFS —
+    /// not even on the first sample of the first peak.
+    #[test]
+    fn test_six_db_over_never_exceeds_full_scale() {
+        let mut limiter = Limiter::new(SAMPLE_RATE);
+        let amplitude = db_to_gain(6.0);
+        let ceiling = db_to_gain(THRESHOLD_DB);
+
+        for i in 0..48000 {
+            let input = amplitude * (i as f32 * 0.05).sin();
+            let output = limiter.process(input);
+            assert!(
+                output.abs() <= ceiling + 1e-6,
+                "sample {i}: output {output} exceeds ceiling {ceiling}"
+            );
+        }
+    }
+
+    /// Material below the knee passes through bit-for-bit.
+    #[test]
+    fn test_below_knee_is_transparent() {
+        let mut limiter = Limiter::new(SAMPLE_RATE);
+        for i in 0..4800 {
+            let input = 0.5 * (i as f32 * 0.03).sin();
+            assert_eq!(limiter.process(input).to_bits(), input.to_bits());
+        }
+        assert_eq!(limiter.gain(), 1.0);
+    }
+
+    /// The knee meets the straight segments continuously.
+    #[test]
+    fn test_knee_is_continuous() {
+        let limiter = Limiter::new(SAMPLE_RATE);
+        let knee_bottom = db_to_gain(THRESHOLD_DB - KNEE_DB / 2.0);
+        let knee_top = db_to_gain(THRESHOLD_DB + KNEE_DB / 2.0);
+
+        let just_below = limiter.target_gain(knee_bottom * 0.9999);
+        let just_inside = limiter.target_gain(knee_bottom * 1.0001);
+        assert!((just_below - just_inside).abs() < 1e-3);
+
+        let inside = limiter.target_gain(knee_top * 0.9999);
+        let above = limiter.target_gain(knee_top * 1.0001);
+        assert!((inside - above).abs() < 1e-3);
+    }
+
+    /// After a loud burst, the gain recovers with the release time
+    /// constant: one time constant later, ~63% of the reduction is gone.
+    #[test]
+    fn test_release_time_constant() {
+        let mut limiter = Limiter::new(SAMPLE_RATE);
+
+        // Drive the gain down with a +6 dB DC burst.
+        for _ in 0..100 {
+            limiter.process(2.0);
+        }
+        let reduced = limiter.gain();
+        assert!(reduced < 0.5);
+
+        // Then let it release on silence for one time constant.
+        let release_samples = (RELEASE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        for _ in 0..release_samples {
+            limiter.process(0.0);
+        }
+
+        let recovered = (limiter.gain() - reduced) / (1.0 - reduced);
+        let expected = 1.0 - (-1.0_f32).exp(); // ≈ 0.632
+        assert!(
+            (recovered - expected).abs() < 0.02,
+            "recovered {recovered}, expected {expected}"
+        );
+    }
+
+    /// Reset returns to unity gain.
+    #[test]
+    fn test_reset() {
+        let mut limiter = Limiter::new(SAMPLE_RATE);
+        limiter.process(4.0);
+        assert!(limiter.gain() < 1.0);
+        limiter.reset();
+        assert_eq!(limiter.gain(), 1.0);
+    }
+}
@@ -0,0 +1,132 @@
+//! # The Loop-Gain Ceiling
+//!
+//! The Feedback knob is only *part* of the gain around the loop. Each
+//! repeat also passes through the feedback chain, and the gain of the
+//! whole trip is the product of everything on the way:
+//!
+//! ```text
+//! loop gain = feedback × (gain of every feedback stage)
+//! ```
+//!
+//! If that product reaches 1.0, each repeat comes back as loud as the one
+//! before it and the echoes never die; above 1.0 they grow without bound.
+//! Capping the knob at 0.95 only keeps the loop stable as long as the
+//! stages never amplify — a resonant filter or a boost stage would push
+//! "95%" straight past unity.
+//!
+//! ## Measuring the Loop
+//!
+//! Every [`FeedbackStage`](super::feedback::FeedbackStage) reports its
+//! *peak* gain — the most it can amplify any signal, at any frequency
+//! and level. Multiplying those gives an upper bound on the loop gain
+//! that's exact for the worst case, known before a single sample goes
+//! round, and costs a couple of multiplies instead of an RMS measurement
+//! that would need several repeats to settle.
+//!
+//! ## The Ceiling
+//!
+//! Once the measured loop gain passes a knee, the applied feedback is
+//! eased back so the loop gain bends smoothly toward the ceiling and
+//! never reaches it:
+//!
+//! ```text
+//! loop gain ≤ KNEE:   unchanged
+//! loop gain > KNEE:   KNEE + span × tanh((loop gain − KNEE) / span)
+//!                     where span = LOOP_GAIN_CEILING − KNEE
+//! ```
+//!
+//! The curve leaves the knee with slope 1, so there's no kink to hear
+//! as the knob moves through it. The knee sits at the Feedback knob's
+//! own maximum, so with stages that don't amplify (today's filter and
+//! saturator) the ceiling never engages and the knob does exactly what
+//! it says.
+
+/// The highest loop gain the ceiling allows (approached, never reached).
+pub const LOOP_GAIN_CEILING: f32 = 0.98;
+
+/// Loop gain above which the ceiling starts easing the feedback back.
+const KNEE: f32 = 0.95;
+
+/// The feedback to apply so the loop gain stays under
+/// [`LOOP_GAIN_CEILING`], given the knob's `feedback` and the feedback
+/// chain's peak `stage_gain`. Below the knee this is `feedback` itself,
+/// bit for bit.
+#[inline]
+pub fn limit_feedback(feedback: f32, stage_gain: f32) -> f32 {
+    let loop_gain = feedback * stage_gain;
+    if loop_gain <= KNEE {
+        return feedback;
+    }
+
+    let span = LOOP_GAIN_CEILING - KNEE;
+    let limited = KNEE + span * ((loop_gain - KNEE) / span).tanh();
+    limited / stage_gain
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::feedback::FeedbackStage;
+
+    /// A stage that amplifies everything by a fixed gain — the kind of
+    /// stage the ceiling exists to catch.
+    struct Boost(f32);
+
+    impl FeedbackStage for Boost {
+        fn process(&mut self, input: f32) -> f32 {
+            input * self.0
+        }
+
+        fn max_gain(&self) -> f32 {
+            self.0
+        }
+    }
+
+    /// Below the knee the feedback passes through untouched.
+    #[test]
+    fn test_below_knee_is_untouched() {
+        for feedback in [0.0, 0.3, 0.7, 0.95] {
+            assert_eq!(limit_feedback(feedback, 1.0).to_bits(), feedback.to_bits());
+        }
+        assert_eq!(limit_feedback(0.5, 1.5), 0.5);
+    }
+
+    /// However hard the loop is pushed, its gain stays under the
+    /// ceiling, and more feedback never gives less.
+    #[test]
+    fn test_never_reaches_ceiling() {
+        let mut previous = 0.0;
+        for i in 0..=200 {
+            let feedback = i as f32 / 100.0;
+            let loop_gain = limit_feedback(feedback, 1.3) * 1.3;
+            assert!(loop_gain < LOOP_GAIN_CEILING, "{feedback}: {loop_gain}");
+            assert!(
+                loop_gain >= previous,
+                "{feedback}: {loop_gain} < {previous}"
+            );
+            previous = loop_gain;
+        }
+    }
+
+    /// A loop whose stages add up to more than unity gain would grow
+    /// without bound at full feedback. With the ceiling, every trip
+    /// round comes back quieter than the last.
+    #[test]
+    fn test_ceiling_keeps_boosted_loop_decaying() {
+        let mut boost = Boost(1.25);
+        let feedback = 0.95;
+        assert!(feedback * boost.max_gain() > 1.0);
+
+        let applied = limit_feedback(feedback, boost.max_gain());
+        let mut level: f32 = 1.0;
+        for trip in 0..100 {
+            let next = boost.process(level) * applied;
+            assert!(next.abs() < level.abs(), "trip {trip}: {level} → {next}");
+            level = next;
+        }
+    }
+}
@@ -2,13 +2,143 @@
 //!
 //! This module contains the core building blocks for our delay effect:
 //!
+//! - **`bloom`**: A gain envelope on the wet output that holds the
+//!   first repeats down after fresh input and lets them swell back up,
+//!   outside the loop.
+//!
+//! - **`crossfade`**: Equal-power and linear crossfades, and the
+//!   `SwitchedPath` wrapper that lets an on/off switch fade between two
+//!   signal paths instead of clicking.
+//!
+//! - **`damping`**: Lowers the feedback filter's cutoff as the loop
+//!   level falls, so quiet late repeats darken faster, like worn tape.
+//!
+//! - **`decorrelator`**: Complementary allpass chains that widen
+//!   identical left and right signals without an audible doubling, and
+//!   fold back to mono exactly.
+//!
 //! - **`delay_line`**: A ring buffer that stores past audio samples and
 //!   retrieves them after a specified delay. This is the heart of any
-//!   time-based audio effect.
+//!   time-based audio effect. `DelayReader` adds extra read heads
+//!   (taps) over the same buffer.
+//!
+//! - **`envelope`**: An envelope follower (peak or RMS detection, with
+//!   attack and release) that measures how loud a signal is, for
+//!   ducking, gating and dynamic feedback.
+//!
+//! - **`key_gate`**: Opens the delay's input only while a sidechain key
+//!   is above a threshold, so only keyed moments reach the echoes.
+//!
+//! - **`allpass`**: A Schroeder allpass around its own delay line — flat
+//!   magnitude, smeared phase — the building block of diffusers and
+//!   reverbs.
 //!
 //! - **`filter`**: A one-pole lowpass filter that removes high-frequency
 //!   content from the feedback signal, simulating the natural darkening
-//!   of repeats heard in analog delay units.
+//!   of repeats heard in analog delay units. Its highpass twin keeps low
+//!   end out of a signal, e.g. ahead of a level detector, and its
+//!   crossfading twin lets the cutoff jump without a step in the output.
+//!
+//! - **`saturator`**: A stateless waveshaper (tanh, cubic or hard clip)
+//!   that squashes loud repeats like tape, without its curve ever adding
+//!   energy to the loop.
+//!
+//! - **`feedback`**: The `FeedbackStage` trait and the `FeedbackChain`
+//!   that runs the filter and saturator in a selectable order.
+//!
+//! - **`feedback_compensation`**: An analytic model of how much energy
+//!   the feedback lowpass takes from each pass, and the feedback boost
+//!   that makes up for it, so repeats last as long at any cutoff.
+//!
+//! - **`feedback_matrix`**: The 2×2 matrix that routes each side's
+//!   repeats into the two delay lines (straight, ping-pong, swirls and
+//!   everything between), normalized so the loop stays stable.
+//!
+//! - **`noise`**: Seeded white and pink noise, plus the small xorshift
+//!   random number generator behind it, so renders that include noise
+//!   are reproducible.
+//!
+//! - **`output_filter`**: A low-cut and high-cut on the wet signal,
+//!   outside the feedback loop, with exact bypasses at their "off" ends.
+//!
+//! - **`character`**: The curves behind the "character" macro, which
+//!   darkens and drives the repeats as offsets on top of their knobs.
+//!
+//! - **`phase_align`**: Measures whether a short delay's wet signal is
+//!   cancelling the dry one, and decides when to flip its polarity.
+//!
+//! - **`lfo`**: A low-frequency oscillator (sine, triangle, saw, square,
+//!   seeded sample-and-hold with a glide between levels) with phase
+//!   offset and tempo-synced rates, for modulating other parameters.
+//!
+//! - **`repeat_sequencer`**: A step sequencer clocked by the delay
+//!   period, so each repeat can get its own setting (per-repeat
+//!   stepping).
+//!
+//! - **`repeat_spread`**: Pans successive repeats further and further
+//!   out, alternating sides, with a parallel line that counts how many
+//!   times each sample has been round the loop.
+//!
+//! - **`stereo_motion`**: Ping-pong wiring and a per-period pan rotor
+//!   with constant-power panning, for repeats that move around the
+//!   stereo field.
+//!
+//! - **`limiter`**: A soft-knee peak limiter for the output stage, so
+//!   runaway feedback can't send overs to the host.
+//!
+//! - **`loop_gain`**: The loop-gain ceiling, which eases the applied
+//!   feedback back whenever the stages in the loop would push the total
+//!   gain toward unity.
+//!
+//! - **`silence`**: Measures how long a signal has stayed below a
+//!   threshold, so the plugin can tell the host exactly when its echo
+//!   tail has died away.
+//!
+//! - **`watchdog`**: Trips when the wet signal stays loud for too long,
+//!   so an unattended installation can pull its feedback down.
+//!
+//! - **`smoother`**: A linear ramp that glides parameter changes over a
+//!   few milliseconds so knob moves don't click.
+//!
+//! - **`tape_head`**: A read head driven by its speed, with inertia and
+//!   a servo back to the delay time, for tape stops and scrubbing.
+//!
+//! - **`tempo`**: Note divisions and beat-grid math for tempo sync.
+//!
+//! - **`time_change`**: The delay time's multiplier and fine offset, and
+//!   the ways it moves to a new value: a pitch-bending glide, a
+//!   crossfade between two read heads, or a tape head running there.
+//!
+//! - **`sample`**: The `Sample` trait that lets the primitives run at
+//!   either `f32` (the plugin's normal precision) or `f64`.
 
+pub mod allpass;
+pub mod bloom;
+pub mod character;
+pub mod crossfade;
+pub mod damping;
+pub mod decorrelator;
 pub mod delay_line;
+pub mod envelope;
+pub mod feedback;
+pub mod feedback_compensation;
+pub mod feedback_matrix;
 pub mod filter;
+pub mod key_gate;
+pub mod lfo;
+pub mod limiter;
+pub mod loop_gain;
+pub mod noise;
+pub mod output_filter;
+pub mod phase_align;
+pub mod repeat_sequencer;
+pub mod repeat_spread;
+pub mod sample;
+pub mod saturator;
+pub mod silence;
+pub mod smoother;
+pub mod stereo_motion;
+pub mod tape_head;
+pub mod tempo;
+pub mod time_change;
+pub mod watchdog;
@@ -0,0 +1,215 @@
+//! # Output Low-Cut and High-Cut
+//!
+//! The feedback filter shapes how the repeats *decay*: every trip around
+//! the loop takes a little more top end away. Sometimes you want the
+//! opposite job — carve out a place for the echoes in the mix (thin out
+//! their low end so they don't muddy the bass, or tame their top so they
+//! sit behind the vocal) without changing how they decay.
+//!
+//! That's what the output filter is for. It sits on the wet signal
+//! *after* the delay line, outside the feedback loop, so each repeat
+//! passes through it exactly once, on its way to the mix:
+//!
+//! ```text
+//! delay line ──► [Low-Cut] ──► [High-Cut] ──► × mix ──► output
+//!     ▲
+//!     └── feedback loop (unaffected)
+//! ```
+//!
+//! Both are one-pole (6 dB/octave) filters — gentle slopes that shape
+//! the tone rather than surgically removing bands.
+//!
+//! ## The "Off" Positions
+//!
+//! The low-cut's lowest setting ([`LOWCUT_OFF_HZ`]) and the high-cut's
+//! highest ([`HIGHCUT_OFF_HZ`]) don't just put the corner somewhere
+//! inaudible — they switch that filter out entirely, so the wet signal
+//! passes bit-for-bit unchanged. A one-pole filter at 20 kHz is already
+//! close to transparent, so gliding the high-cut into or out of its "off"
+//! position doesn't produce an audible step.
+//!
+//! A one-pole low-cut at 20 Hz isn't, for everything below it: repeats
+//! that drift slowly away from zero (an offset the saturator leaves, a
+//! tape head crawling through a swell) come out of it well short of
+//! where they went in. Switched out between two samples, the wet signal
+//! would step back up to them. So the low-cut crossfades in and out of
+//! the path over [`LOWCUT_FADE_MS`], and only the resting bypass is
+//! exact.
+
+use super::crossfade::{FadeLaw, SwitchedPath};
+use super::filter::{OnePoleFilter, OnePoleHighpass};
+
+/// Low-cut setting at (or below) which the low-cut is bypassed.
+pub const LOWCUT_OFF_HZ: f32 = 20.0;
+
+/// High-cut setting at (or above) which the high-cut is bypassed.
+pub const HIGHCUT_OFF_HZ: f32 = 20000.0;
+
+/// How long the low-cut takes to fade into or out of the path, in ms.
+pub const LOWCUT_FADE_MS: f32 = 10.0;
+
+/// A low-cut (highpass) and a high-cut (lowpass) in series, each with an
+/// exact bypass.
+#[derive(Clone)]
+pub struct OutputFilter {
+    lowcut: OnePoleHighpass,
+    highcut: OnePoleFilter,
+
+    /// Whether each filter is in the signal path, the low-cut fading
+    /// in and out over `fade_samples`.
+    lowcut_on: SwitchedPath,
+    highcut_on: bool,
+    fade_samples: usize,
+}
+
+impl OutputFilter {
+    /// Create an output filter with both cuts off.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            lowcut: OnePoleHighpass::new(sample_rate),
+            highcut: OnePoleFilter::new(),
+            // Linear: at 20 Hz the two paths carry the same audio.
+            lowcut_on: SwitchedPath::new(false, FadeLaw::Linear),
+            highcut_on: false,
+            fade_samples: (LOWCUT_FADE_MS / 1000.0 * sample_rate) as usize,
+        }
+    }
+
+    /// Set both corner frequencies. A low-cut at or below
+    /// [`LOWCUT_OFF_HZ`], or a high-cut at or above [`HIGHCUT_OFF_HZ`],
+    /// switches that filter off.
+    pub fn set_cutoffs(&mut self, lowcut_hz: f32, highcut_hz: f32, sample_rate: f32) {
+        self.ramp_cutoffs(lowcut_hz, highcut_hz, sample_rate, 1);
+    }
+
+    /// Like [`set_cutoffs()`](Self::set_cutoffs), but a filter that stays
+    /// on glides to its new corner over `samples` samples (see
+    /// [`OnePoleFilter::ramp_to_cutoff()`]).
+    pub fn ramp_cutoffs(
+        &mut self,
+        lowcut_hz: f32,
+        highcut_hz: f32,
+        sample_rate: f32,
+        samples: usize,
+    ) {
+        let lowcut_on = lowcut_hz > LOWCUT_OFF_HZ;
+        let highcut_on = highcut_hz < HIGHCUT_OFF_HZ;
+
+        // A filter that's switched off stops running, so clear its memory
+        // as it comes back: it then starts from silence instead of from
+        // whatever it last heard. The low-cut is still running while it
+        // fades out, and turns around from there.
+        if lowcut_on && !self.lowcut_on.on_path_needed() {
+            self.lowcut.reset();
+        }
+        if self.highcut_on && !highcut_on {
+            self.highcut.reset();
+        }
+        self.lowcut_on.set(lowcut_on, self.fade_samples);
+        self.highcut_on = highcut_on;
+
+        if lowcut_on {
+            self.lowcut.ramp_to_cutoff(lowcut_hz, sample_rate, samples);
+        }
+        if highcut_on {
+            self.highcut
+                .ramp_to_cutoff(highcut_hz, sample_rate, samples);
+        }
+    }
+
+    /// Whether a filter that's in the signal path is still gliding to
+    /// its corner, or the low-cut is still fading in or out.
+    pub fn is_ramping(&self) -> bool {
+        self.lowcut_on.is_active()
+            || (self.lowcut_on.is_on() && self.lowcut.is_ramping())
+            || (self.highcut_on && self.highcut.is_ramping())
+    }
+
+    /// Filter one wet sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let mut output = input;
+        if self.lowcut_on.is_active() {
+            let (off_gain, on_gain) = self.lowcut_on.next_gains();
+            output = off_gain * output + on_gain * self.lowcut.process(output);
+        } else if self.lowcut_on.is_on() {
+            output = self.lowcut.process(output);
+        }
+        if self.highcut_on {
+            output = self.highcut.process(output);
+        }
+        output
+    }
+
+    /// Reset both filters' state to zero, finishing any fade.
+    pub fn reset(&mut self) {
+        self.lowcut.reset();
+        self.highcut.reset();
+        self.lowcut_on.jump(self.lowcut_on.is_on());
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Both "off" positions pass the signal through bit-for-bit.
+    #[test]
+    fn test_off_positions_bypass_exactly() {
+        let mut filter = OutputFilter::new(SAMPLE_RATE);
+        filter.set_cutoffs(LOWCUT_OFF_HZ, HIGHCUT_OFF_HZ, SAMPLE_RATE);
+        for n in 0..1000 {
+            let x = (n as f32 * 0.37).sin() + 0.25;
+            assert_eq!(filter.process(x).to_bits(), x.to_bits());
+        }
+    }
+
+    /// Switching a cut off and back on starts it from silence.
+    #[test]
+    fn test_switching_off_clears_state() {
+        let mut filter = OutputFilter::new(SAMPLE_RATE);
+        filter.set_cutoffs(LOWCUT_OFF_HZ, 1000.0, SAMPLE_RATE);
+        for _ in 0..100 {
+            filter.process(1.0);
+        }
+        filter.set_cutoffs(LOWCUT_OFF_HZ, HIGHCUT_OFF_HZ, SAMPLE_RATE);
+        filter.set_cutoffs(LOWCUT_OFF_HZ, 1000.0, SAMPLE_RATE);
+
+        let mut fresh = OutputFilter::new(SAMPLE_RATE);
+        fresh.set_cutoffs(LOWCUT_OFF_HZ, 1000.0, SAMPLE_RATE);
+        assert_eq!(filter.process(0.5), fresh.process(0.5));
+    }
+
+    /// Switching the low-cut off under a steady offset, which it has
+    /// half blocked, fades the offset back in rather than stepping up to
+    /// it, then bypasses exactly.
+    #[test]
+    fn test_lowcut_fades_out_of_the_path() {
+        let mut filter = OutputFilter::new(SAMPLE_RATE);
+        filter.set_cutoffs(25.0, HIGHCUT_OFF_HZ, SAMPLE_RATE);
+        let mut last = 0.0;
+        for _ in 0..600 {
+            last = filter.process(0.5);
+        }
+        assert!(last < 0.3, "{last}");
+
+        filter.set_cutoffs(LOWCUT_OFF_HZ, HIGHCUT_OFF_HZ, SAMPLE_RATE);
+        let fade_len = (LOWCUT_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        for _ in 0..fade_len {
+            let out = filter.process(0.5);
+            assert!(
+                (out - last).abs() < 2.0 / fade_len as f32,
+                "{last} to {out}"
+            );
+            last = out;
+        }
+        assert!(!filter.is_ramping());
+        assert_eq!(filter.process(0.5), 0.5);
+    }
+}
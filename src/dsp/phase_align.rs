@@ -0,0 +1,304 @@
+//! # Phase Alignment
+//!
+//! At a few milliseconds, a delay stops sounding like an echo and
+//! starts sounding like a filter: the dry signal and its copy sum into
+//! a comb. Whether a given note comes out fuller or thinner depends on
+//! where its partials fall on the comb's teeth, and with the mix near
+//! 50% the worst case is a partial cancelled outright — a bass note
+//! at 1 ms that all but disappears. For Haas widening and doubling
+//! that's rarely what anyone wants.
+//!
+//! The mix's energy tells us which way it's going:
+//!
+//! ```text
+//! E[(a·dry + b·wet)²] = a²·E[dry²] + b²·E[wet²] + 2ab·E[dry·wet]
+//! ```
+//!
+//! The last term is the interference. Positive, the two reinforce;
+//! negative, the mix is quieter than its parts and something is being
+//! cancelled. Flipping the wet signal's polarity flips that term, so
+//! when it's negative, a flip turns the loss into a gain.
+//!
+//! ## Measuring
+//!
+//! A [`PhaseMeter`] sums `dry·wet`, `dry²` and `wet²` over the samples
+//! between two control ticks, with the wet signal as it's heard (after
+//! any flip). On each tick the [`PhaseAligner`] folds the sums into a
+//! decaying window of about [`WINDOW_MS`], and reads off the
+//! correlation: the interference term over the largest it could be,
+//! from −1 (the wet is the dry signal upside down) to +1.
+//!
+//! ## Hysteresis
+//!
+//! The aligner flips when the correlation stays below
+//! −[`FLIP_CORRELATION`] for [`SUSTAIN_MS`]. A dip that brief is no
+//! cancellation anyone hears — two unrelated tones a little apart in
+//! pitch swing the window's correlation briefly negative as they beat,
+//! and so does a window barely started. Because it measures what's heard, a flip
+//! turns the same correlation positive, so it doesn't undo itself: only
+//! material that cancels *the other way up* flips it back. It also
+//! waits [`HOLD_MS`] after a flip before flipping again, and ignores
+//! near-silence, where the correlation means nothing:
+//!
+//! ```text
+//! correlation   −0.6 ──► flip ──► +0.6   stays flipped
+//!               −0.1                     too weak to act on
+//! ```
+//!
+//! Only the heard wet signal is flipped; the feedback loop carries on
+//! as it was. Fading the flip in is the engine's job.
+
+/// The longest delay phase alignment acts on, in milliseconds. Beyond
+/// about 20 ms the copy is heard as a separate event, not as a change
+/// of tone.
+pub const MAX_DELAY_MS: f32 = 20.0;
+
+/// How long a stretch the correlation is measured over, in
+/// milliseconds: a few periods of the lowest notes that cancel.
+pub const WINDOW_MS: f32 = 30.0;
+
+/// How strongly the dry and wet signals have to oppose each other
+/// before the wet is flipped.
+pub const FLIP_CORRELATION: f32 = 0.25;
+
+/// How long the correlation has to stay below −[`FLIP_CORRELATION`]
+/// before the wet is flipped, in milliseconds.
+pub const SUSTAIN_MS: f32 = 10.0;
+
+/// How long after one flip the aligner waits before another, in
+/// milliseconds.
+pub const HOLD_MS: f32 = 250.0;
+
+/// The mean square, over the window, below which a signal counts as
+/// silence (−80 dBFS).
+const MIN_ENERGY: f32 = 1e-8;
+
+/// Sums of one channel's dry and heard wet signals, since the last
+/// control tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhaseMeter {
+    pub cross: f32,
+    pub dry: f32,
+    pub wet: f32,
+}
+
+impl PhaseMeter {
+    /// Count one sample.
+    #[inline]
+    pub fn add(&mut self, dry: f32, wet: f32) {
+        self.cross += dry * wet;
+        self.dry += dry * dry;
+        self.wet += wet * wet;
+    }
+
+    /// The sums scaled by `gain`, to average several channels' meters.
+    pub fn scaled(self, gain: f32) -> Self {
+        Self {
+            cross: self.cross * gain,
+            dry: self.dry * gain,
+            wet: self.wet * gain,
+        }
+    }
+}
+
+impl std::ops::Add for PhaseMeter {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            cross: self.cross + other.cross,
+            dry: self.dry + other.dry,
+            wet: self.wet + other.wet,
+        }
+    }
+}
+
+/// Decides, tick by tick, whether the wet signal should be flipped.
+/// See [the module docs](self).
+#[derive(Debug, Clone)]
+pub struct PhaseAligner {
+    /// The decaying sums over the window.
+    window: PhaseMeter,
+    window_samples: f32,
+
+    flipped: bool,
+
+    /// Samples the correlation has stayed below −[`FLIP_CORRELATION`]
+    /// for, and how many it must reach to flip.
+    opposed: usize,
+    sustain_samples: usize,
+
+    /// Samples since the last flip, and how many it must reach before
+    /// the next.
+    since_flip: usize,
+    hold_samples: usize,
+}
+
+impl PhaseAligner {
+    pub fn new(sample_rate: f32) -> Self {
+        let hold_samples = (HOLD_MS / 1000.0 * sample_rate) as usize;
+        Self {
+            window: PhaseMeter::default(),
+            window_samples: (WINDOW_MS / 1000.0 * sample_rate).max(1.0),
+            flipped: false,
+            opposed: 0,
+            sustain_samples: (SUSTAIN_MS / 1000.0 * sample_rate) as usize,
+            since_flip: hold_samples,
+            hold_samples,
+        }
+    }
+
+    /// Fold in the sums of the last `samples` samples, and return
+    /// whether the wet signal should be flipped from now on.
+    pub fn update(&mut self, meter: PhaseMeter, samples: usize) -> bool {
+        let decay = (-(samples as f32) / self.window_samples).exp();
+        self.window = self.window.scaled(decay) + meter;
+        self.since_flip = self.since_flip.saturating_add(samples);
+
+        let audible = |energy: f32| energy / self.window_samples > MIN_ENERGY;
+        if audible(self.window.dry)
+            && audible(self.window.wet)
+            && self.correlation() < -FLIP_CORRELATION
+        {
+            self.opposed = self.opposed.saturating_add(samples);
+        } else {
+            self.opposed = 0;
+        }
+        if self.since_flip >= self.hold_samples && self.opposed >= self.sustain_samples {
+            self.flipped = !self.flipped;
+            self.since_flip = 0;
+            self.opposed = 0;
+            // What the window heard, heard the other way up.
+            self.window.cross = -self.window.cross;
+        }
+        self.flipped
+    }
+
+    /// The dry and heard wet signals' correlation over the window, from
+    /// −1.0 to 1.0; 0.0 while either is silent.
+    pub fn correlation(&self) -> f32 {
+        let norm = (self.window.dry * self.window.wet).sqrt();
+        if norm > 0.0 {
+            (self.window.cross / norm).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether the wet signal is flipped.
+    pub fn flipped(&self) -> bool {
+        self.flipped
+    }
+
+    /// Forget what's been measured and go back to the wet signal the
+    /// right way up.
+    pub fn reset(&mut self) {
+        self.window = PhaseMeter::default();
+        self.flipped = false;
+        self.opposed = 0;
+        self.since_flip = self.hold_samples;
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    const TICK: usize = 32;
+
+    /// One tick's meter over a sine and a copy of it scaled by `wet`,
+    /// flipped if the aligner says so.
+    fn tick(aligner: &mut PhaseAligner, tick_idx: usize, wet: f32) -> bool {
+        let sign = if aligner.flipped() { -1.0 } else { 1.0 };
+        let mut meter = PhaseMeter::default();
+        for i in 0..TICK {
+            let dry = ((tick_idx * TICK + i) as f32 * 0.05).sin() * 0.5;
+            meter.add(dry, dry * wet * sign);
+        }
+        aligner.update(meter, TICK)
+    }
+
+    /// A wet signal that cancels the dry one is flipped once, quickly,
+    /// and stays flipped.
+    #[test]
+    fn test_flips_once_and_stays() {
+        let mut aligner = PhaseAligner::new(SAMPLE_RATE);
+        let mut flips = 0;
+        let mut was = false;
+        for tick_idx in 0..3000 {
+            let flipped = tick(&mut aligner, tick_idx, -0.8);
+            flips += usize::from(flipped != was);
+            was = flipped;
+            if tick_idx == 100 {
+                assert!(flipped, "not flipped within 100 ticks");
+            }
+        }
+        assert!(was);
+        assert_eq!(flips, 1);
+        assert!(aligner.correlation() > 0.9, "{}", aligner.correlation());
+    }
+
+    /// Reinforcing, weakly opposed and silent signals are left alone,
+    /// even where two unrelated tones beat, and the window's correlation
+    /// dips below the threshold for a moment.
+    #[test]
+    fn test_leaves_the_rest_alone() {
+        for wet in [0.8, 0.0] {
+            let mut aligner = PhaseAligner::new(SAMPLE_RATE);
+            for tick_idx in 0..3000 {
+                assert!(!tick(&mut aligner, tick_idx, wet), "wet {wet}");
+            }
+        }
+
+        // Weakly opposed: the wet is mostly a signal of its own.
+        let mut aligner = PhaseAligner::new(SAMPLE_RATE);
+        for tick_idx in 0..3000 {
+            let mut meter = PhaseMeter::default();
+            for i in 0..TICK {
+                let n = (tick_idx * TICK + i) as f32;
+                let dry = (n * 0.05).sin();
+                meter.add(dry, (n * 0.0731).sin() - 0.1 * dry);
+            }
+            assert!(!aligner.update(meter, TICK));
+        }
+    }
+
+    /// Material that cancels one way up, then the other, is flipped back
+    /// and forth, but never faster than the hold time.
+    #[test]
+    fn test_holds_between_flips() {
+        let mut aligner = PhaseAligner::new(SAMPLE_RATE);
+        let hold_ticks = (HOLD_MS / 1000.0 * SAMPLE_RATE) as usize / TICK;
+        let switch_ticks = 3 * (SUSTAIN_MS / 1000.0 * SAMPLE_RATE) as usize / TICK;
+        let mut last_flip = None;
+        let mut was = false;
+        for tick_idx in 0..20 * hold_ticks {
+            // The wet signal itself changes polarity each time it has
+            // opposed the dry long enough to flip, far sooner than the
+            // hold allows.
+            let wet = if (tick_idx / switch_ticks).is_multiple_of(2) {
+                -0.8
+            } else {
+                0.8
+            };
+            let flipped = tick(&mut aligner, tick_idx, wet);
+            if flipped != was {
+                if let Some(last) = last_flip {
+                    assert!(
+                        tick_idx - last >= hold_ticks,
+                        "flipped after {}",
+                        tick_idx - last
+                    );
+                }
+                last_flip = Some(tick_idx);
+            }
+            was = flipped;
+        }
+        assert!(last_flip.is_some());
+    }
+}
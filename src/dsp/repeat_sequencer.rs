@@ -0,0 +1,213 @@
+//! # Per-Repeat Step Sequencer
+//!
+//! A continuous modulator (an LFO, a knob sweep) changes a parameter
+//! *while* a repeat plays. Some hardware delays do something different:
+//! they change it *between* repeats. The first echo is dark, the second
+//! bright, the third somewhere in between — each repeat gets its own
+//! setting and holds it, like a step sequencer clocked by the echoes
+//! themselves.
+//!
+//! ## Clocked by the Delay Time
+//!
+//! Every repeat of a sound arrives exactly one delay period after the
+//! previous one. So a counter that restarts every `delay` samples ticks
+//! once per repeat, and every repeat of any note lands the same distance
+//! past a tick:
+//!
+//! ```text
+//! ticks:    |  step 0   |  step 1   |  step 2   |  step 3   |  step 0 ...
+//! note:       x
+//! repeats:                x           x           x           x
+//!                       (1st)       (2nd)       (3rd)       (4th)
+//! ```
+//!
+//! Each tick moves to the next step, so each repeat is heard with the
+//! next value in the pattern, and after the last step the pattern starts
+//! over.
+//!
+//! ## Tempo Sync
+//!
+//! While the delay is synced, a period is a fixed note length, so the
+//! steps can be pinned to the song instead of to when the plugin
+//! started: [`RepeatSequencer::sync()`] takes the host's position
+//! measured in periods and jumps straight to the matching step. The
+//! owner calls [`RepeatSequencer::reset()`] when the transport stops, so
+//! the next playback starts the pattern from the top.
+//!
+//! The sequencer only produces step *values* (0.0–1.0). What they mean —
+//! a cutoff, a pan position — is up to whoever reads them, and so is the
+//! short smoothing that keeps a step change from clicking.
+
+/// The most steps a pattern can have.
+pub const MAX_STEPS: usize = 8;
+
+/// What the per-repeat steps modulate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepTarget {
+    /// No stepping: every repeat sounds the same.
+    #[default]
+    Off,
+    /// Each repeat gets its own wet-path high-cut corner.
+    Cutoff,
+}
+
+/// Steps through a pattern of values, one step per delay period.
+#[derive(Debug, Clone)]
+pub struct RepeatSequencer {
+    /// The pattern. Only the first `length` values are used.
+    steps: [f32; MAX_STEPS],
+
+    /// How many steps the pattern has (1..=[`MAX_STEPS`]).
+    length: usize,
+
+    /// The step currently playing.
+    step: usize,
+
+    /// Samples since the last tick. `f64`, like the LFO's phase, so a
+    /// fractional period doesn't drift over a long run.
+    elapsed: f64,
+}
+
+impl RepeatSequencer {
+    /// Create a four-step sequencer with every step at 1.0, on step 0.
+    pub fn new() -> Self {
+        Self {
+            steps: [1.0; MAX_STEPS],
+            length: 4,
+            step: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Set the pattern (values clamped to 0.0–1.0). A new value for the
+    /// current step shows up in [`value()`](Self::value) right away.
+    pub fn set_steps(&mut self, steps: [f32; MAX_STEPS]) {
+        self.steps = steps.map(|s| s.clamp(0.0, 1.0));
+    }
+
+    /// Set how many steps the pattern uses, clamped to 1..=[`MAX_STEPS`].
+    /// Shortening it past the current step wraps back into range.
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length.clamp(1, MAX_STEPS);
+        self.step %= self.length;
+    }
+
+    /// The step currently playing.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// The current step's value.
+    pub fn value(&self) -> f32 {
+        self.steps[self.step]
+    }
+
+    /// Count one sample of a `period_samples`-long delay. Returns the
+    /// new step's value when this sample starts a new step.
+    #[inline]
+    pub fn advance(&mut self, period_samples: f32) -> Option<f32> {
+        self.elapsed += 1.0;
+        let period = f64::from(period_samples.max(1.0));
+        if self.elapsed < period {
+            return None;
+        }
+        self.elapsed -= period;
+        // A period that just shrank below `elapsed` ticks once now
+        // rather than several times in a row.
+        self.elapsed = self.elapsed.min(period - 1.0).max(0.0);
+        self.step = (self.step + 1) % self.length;
+        Some(self.value())
+    }
+
+    /// Jump to where the pattern would be `position` periods into the
+    /// song (e.g. the host's beat position divided by the synced
+    /// division's length in beats).
+    pub fn sync(&mut self, position: f64, period_samples: f32) {
+        let position = position.max(0.0);
+        self.step = (position.floor() as usize) % self.length;
+        self.elapsed = position.fract() * f64::from(period_samples.max(1.0));
+    }
+
+    /// Go back to the first step at the start of its period.
+    pub fn reset(&mut self) {
+        self.step = 0;
+        self.elapsed = 0.0;
+    }
+}
+
+impl Default for RepeatSequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequencer(length: usize) -> RepeatSequencer {
+        let mut sequencer = RepeatSequencer::new();
+        sequencer.set_steps([0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8]);
+        sequencer.set_length(length);
+        sequencer
+    }
+
+    /// One tick per period, through the pattern and back to the start.
+    #[test]
+    fn test_ticks_once_per_period() {
+        let mut sequencer = sequencer(4);
+        let mut ticks = Vec::new();
+        for n in 1..=500 {
+            if let Some(value) = sequencer.advance(100.0) {
+                ticks.push((n, value));
+            }
+        }
+        assert_eq!(
+            ticks,
+            [(100, 0.2), (200, 0.3), (300, 0.4), (400, 0.1), (500, 0.2)]
+        );
+    }
+
+    /// A fractional period keeps its average spacing over a long run.
+    #[test]
+    fn test_fractional_period() {
+        let mut sequencer = sequencer(8);
+        let ticks = (0..1_000_000)
+            .filter(|_| sequencer.advance(1234.5).is_some())
+            .count();
+        assert_eq!(ticks, (1_000_000.0 / 1234.5) as usize);
+    }
+
+    /// Syncing jumps to the step for the song position, and the next
+    /// tick lands where the position says the period ends.
+    #[test]
+    fn test_sync() {
+        let mut sequencer = sequencer(4);
+        sequencer.sync(6.25, 100.0);
+        assert_eq!(sequencer.step(), 2);
+        assert_eq!(sequencer.value(), 0.3);
+
+        let samples_to_tick = (1..=100)
+            .find(|_| sequencer.advance(100.0).is_some())
+            .unwrap();
+        assert_eq!(samples_to_tick, 75);
+        assert_eq!(sequencer.step(), 3);
+    }
+
+    /// Shortening the pattern keeps the current step in range; reset
+    /// goes back to the top.
+    #[test]
+    fn test_length_and_reset() {
+        let mut sequencer = sequencer(8);
+        sequencer.sync(6.0, 100.0);
+        sequencer.set_length(4);
+        assert_eq!(sequencer.step(), 2);
+        sequencer.reset();
+        assert_eq!(sequencer.step(), 0);
+        assert_eq!(sequencer.value(), 0.1);
+    }
+}
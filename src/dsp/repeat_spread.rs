@@ -0,0 +1,444 @@
+//! # Repeat Spread: Each Echo a Little Further Out
+//!
+//! Spread pans the repeats apart one by one: the first lands slightly
+//! left, the second slightly right, the third further left, and so on,
+//! each a step wider than the last until they reach the Spread setting:
+//!
+//! ```text
+//! spread 100%:   repeat    1      2      3      4      5      6  ...
+//!                pan     −0.25  +0.50  −0.75  +1.00  −1.00  +1.00
+//!
+//!       L ◄──────────────── centre ────────────────► R
+//!       5 ·  3 ·    1 ·       │       · 2      · 4 · 6
+//! ```
+//!
+//! It's the width a tape echo's repeats seem to gain as they smear and
+//! drift, made deliberate. Unlike ping-pong, nothing is rewired: each
+//! line keeps its own repeats, and each repeat is panned on its way out
+//! (see [`spread_pan()`]). At 0% nothing is panned at all.
+//!
+//! ## Which Repeat Is This?
+//!
+//! A delay line doesn't know. By the time a sample comes out, the input
+//! and every earlier repeat have been summed into it. So a second,
+//! parallel line runs alongside the audio — the [`RepeatTracker`] —
+//! holding for each sample how many times it has been round the loop.
+//! It's written with the audio and read with the audio, so each echo
+//! comes out with its own count:
+//!
+//! ```text
+//! audio line:    write  input + feedback    read ─► echo
+//! repeat line:   write  0 for the input,    read ─► g: the echo is
+//!                       g + 1 for feedback            repeat g + 1
+//! ```
+//!
+//! When input and feedback overlap, the count written is their average
+//! weighted by level: a loud new note over a faint tail counts as
+//! mostly new. It's an estimate — a sum of two repeats is neither — but
+//! the pan it gives lies between theirs, so overlaps sit between the
+//! positions the repeats would have on their own.
+//!
+//! The levels are envelopes (see [`EnvelopeFollower`]), not the samples
+//! themselves. Two waveforms swap places as the louder one at every
+//! zero crossing, so weighted sample by sample the count would flip
+//! between theirs hundreds of times a second — and the pan with it, a
+//! buzz on every overlap. The envelopes move with the notes instead.
+//!
+//! ## Starting from Nothing
+//!
+//! The tracker only runs while spread is up. When it starts again, what
+//! it holds belongs to audio long gone, so it starts empty, and anything
+//! it hasn't seen written reads as *unknown*: no pan. The echoes already
+//! in the lines play on from the centre as before, and everything new
+//! spreads out.
+//!
+//! Where the unknown meets the counted, the pan would jump — mid-note,
+//! if the two are playing over each other — so the pan glides instead,
+//! the way Alternate's swap does (see below): over [`REPEAT_GLIDE_MS`]
+//! from one side to the other, read that far ahead of the audio.
+//!
+//! ## Alternate
+//!
+//! Alternate reads the same count to bounce the image instead: odd
+//! repeats come out where their lines put them, even ones with the sides
+//! exchanged (see [`alternate_swap()`]). Only what's heard is swapped —
+//! each line keeps its own feedback, time and tone — so the decay is
+//! the one without it, and a mono source on both sides doesn't move at
+//! all. Unknown counts play straight.
+//!
+//! Swapping a side at a repeat boundary that lands mid-note would click,
+//! so the swap glides over [`REPEAT_GLIDE_MS`] instead
+//! ([`RepeatGlide`]). It can't wait for the boundary to start: the
+//! engine reads the count that far ahead of the audio, so the glide
+//! ends just as the repeat it's for comes out:
+//!
+//! ```text
+//! count read:    ·······1·······│·······2·······│      (ahead by the fade)
+//! swap:          0   ╱‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾╲  0
+//! audio:            repeat 1    │  repeat 2     │  repeat 3
+//! ```
+
+use std::f32::consts::PI;
+
+use super::delay_line::DelayLine;
+use super::envelope::EnvelopeFollower;
+
+/// The repeat at which the spread reaches its full width. Earlier
+/// repeats step out towards it, one quarter at a time.
+pub const FULL_SPREAD_REPEAT: f32 = 4.0;
+
+/// The highest count the tracker stores. Nothing is left to hear by
+/// then, and it keeps the count of a silent line from growing forever.
+pub const MAX_TRACKED_REPEAT: f32 = 64.0;
+
+/// How long Alternate's swap and Spread's pan take to glide from one
+/// side to the other, in ms.
+pub const REPEAT_GLIDE_MS: f32 = 10.0;
+
+/// Written for feedback the tracker never saw go in.
+const UNKNOWN: f32 = -1.0;
+
+/// How fast the levels that weight an overlap rise and fall, in ms:
+/// quick enough to keep up with a pluck, slow enough not to follow the
+/// waveform.
+const LEVEL_ATTACK_MS: f32 = 1.0;
+const LEVEL_RELEASE_MS: f32 = 5.0;
+
+/// Levels below this (−120 dB) are silence: what's left of the
+/// envelopes' release, too little to weight anything by.
+const SILENT_LEVEL: f32 = 1e-6;
+
+/// Where `repeat` (1 for the first echo) sits at `spread` (0.0–1.0):
+/// −1.0 hard left to 1.0 hard right. Odd repeats go left and even ones
+/// right, widening until [`FULL_SPREAD_REPEAT`]. A count between two
+/// repeats (an overlap, see [the module docs](self)) lands between
+/// their positions.
+#[inline]
+pub fn spread_pan(repeat: f32, spread: f32) -> f32 {
+    let width = spread * (repeat / FULL_SPREAD_REPEAT).clamp(0.0, 1.0);
+    // cos(π·n) is −1 for odd n and +1 for even n.
+    width * (PI * repeat).cos()
+}
+
+/// How far Alternate exchanges the sides for `repeat` (1 for the first
+/// echo): 0.0 for odd repeats, which play where their lines put them,
+/// and 1.0 for even ones. A count between two repeats lands between.
+#[inline]
+pub fn alternate_swap(repeat: f32) -> f32 {
+    0.5 + 0.5 * (PI * repeat).cos()
+}
+
+/// Alternate's swap or Spread's pan, gliding to each repeat's side over
+/// [`REPEAT_GLIDE_MS`]. See [the module docs](self#alternate).
+#[derive(Debug, Clone)]
+pub struct RepeatGlide {
+    /// Where the glide has got to: how far the sides are exchanged, or
+    /// the pan.
+    value: f32,
+
+    /// The most it moves in a sample, and how many samples it takes to
+    /// go from one side to the other.
+    step: f32,
+    lead: usize,
+}
+
+impl RepeatGlide {
+    /// A glide resting at 0.0 (straight, or centred) that crosses `span`
+    /// — from one side to the other — in its lead: 1.0 for the swap,
+    /// 2.0 for a pan.
+    pub fn new(sample_rate: f32, span: f32) -> Self {
+        let lead = ((REPEAT_GLIDE_MS / 1000.0 * sample_rate) as usize).max(1);
+        Self {
+            value: 0.0,
+            step: span / lead as f32,
+            lead,
+        }
+    }
+
+    /// How far ahead of the audio, in samples, to read the count the
+    /// glide heads for, so it gets there in time.
+    pub fn lead(&self) -> usize {
+        self.lead
+    }
+
+    /// Move a sample towards `target`, and return where the glide is.
+    #[inline]
+    pub fn next(&mut self, target: f32) -> f32 {
+        self.value += (target - self.value).clamp(-self.step, self.step);
+        self.value
+    }
+
+    /// Go back to 0.0 at once.
+    pub fn reset(&mut self) {
+        self.value = 0.0;
+    }
+}
+
+/// How many times each sample in the delay lines has been round the
+/// loop. See [the module docs](self).
+pub struct RepeatTracker {
+    repeats: DelayLine,
+
+    /// The levels of the input and the feedback going into the lines,
+    /// for weighting overlaps.
+    input_level: EnvelopeFollower,
+    feedback_level: EnvelopeFollower,
+
+    /// Whether the last sample was tracked. If not, the next write
+    /// starts again from an empty line.
+    tracking: bool,
+}
+
+impl RepeatTracker {
+    /// A tracker keeping its counts in `repeats`, a line as long as the
+    /// audio lines it follows.
+    ///
+    /// Whatever clip level `repeats` came with is replaced: an audio
+    /// line's would cap the counts at a few repeats, and every later
+    /// one would read as the last it let through.
+    pub fn new(mut repeats: DelayLine, sample_rate: f32) -> Self {
+        repeats.set_clip_level(Some(MAX_TRACKED_REPEAT));
+        let follower = || {
+            let mut follower = EnvelopeFollower::new(sample_rate);
+            follower.set_attack_ms(LEVEL_ATTACK_MS);
+            follower.set_release_ms(LEVEL_RELEASE_MS);
+            follower
+        };
+        Self {
+            repeats,
+            input_level: follower(),
+            feedback_level: follower(),
+            tracking: false,
+        }
+    }
+
+    /// How much memory the line of counts takes, in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.repeats.memory_bytes()
+    }
+
+    /// The count of the sample `delay_samps` back, or `None` if the
+    /// tracker didn't see it written.
+    #[inline]
+    pub fn read(&self, delay_samps: f32) -> Option<f32> {
+        if delay_samps.ceil() as usize > self.repeats.len_written() {
+            return None;
+        }
+        Some(self.repeats.read(delay_samps)).filter(|count| *count >= 0.0)
+    }
+
+    /// Like [`read()`](Self::read), a whole number of samples back.
+    #[inline]
+    pub fn read_int(&self, delay_samps: usize) -> Option<f32> {
+        if delay_samps > self.repeats.len_written() {
+            return None;
+        }
+        Some(self.repeats.read_int(delay_samps)).filter(|count| *count >= 0.0)
+    }
+
+    /// Record the count of the sample written into the lines now:
+    /// `input_level` of new input and `feedback_level` of feedback whose
+    /// count, on its way out, was `fed_back`. Then move on a sample.
+    ///
+    /// The levels are this sample's; the tracker follows their
+    /// envelopes (see [the module docs](self)).
+    #[inline]
+    pub fn write(&mut self, input_level: f32, feedback_level: f32, fed_back: Option<f32>) {
+        if !self.tracking {
+            self.repeats.clear();
+            self.input_level.reset();
+            self.feedback_level.reset();
+            self.tracking = true;
+        }
+        let input_level = self.input_level.process(input_level);
+        let feedback_level = self.feedback_level.process(feedback_level);
+        let count = match fed_back {
+            Some(count) => {
+                let count = (count + 1.0).min(MAX_TRACKED_REPEAT);
+                let level = input_level + feedback_level;
+                // Silence keeps counting, so a note that fades in over
+                // it isn't dragged back towards the first repeat.
+                if level > SILENT_LEVEL {
+                    count * feedback_level / level
+                } else {
+                    count
+                }
+            }
+            None if feedback_level > input_level.max(SILENT_LEVEL) => UNKNOWN,
+            None => 0.0,
+        };
+        self.repeats.write(count);
+        self.repeats.advance();
+    }
+
+    /// Stop tracking: the lines move on without the tracker, so what it
+    /// holds goes stale. The next [`write()`](Self::write) starts over.
+    #[inline]
+    pub fn pause(&mut self) {
+        self.tracking = false;
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroUsize;
+
+    /// The first four repeats step out a quarter at a time, alternating
+    /// left and right; later ones stay at the full width.
+    #[test]
+    fn test_spread_pan_alternates_and_widens() {
+        let pans: Vec<f32> = (1..=6).map(|n| spread_pan(n as f32, 1.0)).collect();
+        let want = [-0.25, 0.5, -0.75, 1.0, -1.0, 1.0];
+        for (pan, want) in pans.iter().zip(want) {
+            assert!((pan - want).abs() < 1e-6, "{pans:?}");
+        }
+        assert!((spread_pan(2.0, 0.5) - 0.25).abs() < 1e-6);
+        assert_eq!(spread_pan(3.0, 0.0), 0.0);
+        // Halfway between two repeats is halfway between their sides.
+        assert!(spread_pan(1.5, 1.0).abs() < 1e-6);
+    }
+
+    /// Odd repeats play straight and even ones swapped; the swap glides
+    /// between them in exactly its lead, never faster. A pan crosses
+    /// from hard left to hard right in the same time.
+    #[test]
+    fn test_repeat_glides_in_its_lead() {
+        let swaps: Vec<f32> = (1..=4).map(|n| alternate_swap(n as f32)).collect();
+        for (swap, want) in swaps.iter().zip([0.0, 1.0, 0.0, 1.0]) {
+            assert!((swap - want).abs() < 1e-6, "{swaps:?}");
+        }
+        assert!((alternate_swap(1.5) - 0.5).abs() < 1e-6);
+
+        let mut swap = RepeatGlide::new(48000.0, 1.0);
+        assert_eq!(swap.lead(), 480);
+        let mut last = 0.0;
+        for n in 0..swap.lead() {
+            let now = swap.next(1.0);
+            assert!(
+                now - last <= 1.0 / 480.0 + 1e-6,
+                "sample {n}: {last} → {now}"
+            );
+            last = now;
+        }
+        assert!((last - 1.0).abs() < 1e-4, "{last}");
+        assert_eq!(swap.next(1.0), 1.0);
+        swap.reset();
+        assert_eq!(swap.next(0.0), 0.0);
+
+        let mut pan = RepeatGlide::new(48000.0, 2.0);
+        for _ in 0..pan.lead() / 2 {
+            pan.next(-1.0);
+        }
+        let glided: Vec<f32> = (0..pan.lead()).map(|_| pan.next(1.0)).collect();
+        assert!((glided[pan.lead() - 1] - 1.0).abs() < 1e-4, "{glided:?}");
+        assert!((glided[pan.lead() - 2] - 1.0).abs() > 1e-3, "{glided:?}");
+    }
+
+    /// An impulse counts up by one each trip; an overlap is weighted by
+    /// level; nothing from before the tracker started is known.
+    #[test]
+    fn test_tracker_counts_trips() {
+        // At 1 kHz the levels rise within a sample or two and fall to
+        // silence well within a trip.
+        const TRIP: usize = 100;
+        let mut tracker =
+            RepeatTracker::new(DelayLine::new(NonZeroUsize::new(256).unwrap()), 1000.0);
+        assert_eq!(tracker.read(4.0), None);
+        let trip = |tracker: &mut RepeatTracker, input: f32, feedback: f32| {
+            for n in 0..TRIP {
+                let fed_back = tracker.read_int(TRIP);
+                let (input, feedback) = if n == 0 {
+                    (input, feedback)
+                } else {
+                    (0.0, 0.0)
+                };
+                tracker.write(input, feedback, fed_back);
+            }
+        };
+        let near = |count: Option<f32>, want: f32| count.is_some_and(|c| (c - want).abs() < 1e-4);
+
+        // Input, then its first and second repeats a trip apart.
+        trip(&mut tracker, 1.0, 0.0);
+        assert_eq!(tracker.read_int(TRIP), Some(0.0));
+        trip(&mut tracker, 0.0, 0.5);
+        assert!(
+            near(tracker.read_int(TRIP), 1.0),
+            "{:?}",
+            tracker.read_int(TRIP)
+        );
+
+        // Equal input and feedback: halfway between new and repeat 2.
+        for _ in 0..50 {
+            let fed_back = tracker.read_int(TRIP);
+            tracker.write(0.25, 0.25, fed_back);
+        }
+        assert!(near(tracker.read_int(1), 1.0), "{:?}", tracker.read_int(1));
+
+        // Feedback nobody tracked, louder than the input: unknown.
+        for _ in 0..50 {
+            tracker.write(0.1, 0.5, None);
+        }
+        assert_eq!(tracker.read_int(1), None);
+
+        // Paused, then started again: the old counts are gone.
+        tracker.pause();
+        tracker.write(1.0, 0.0, None);
+        assert_eq!(tracker.read_int(1), Some(0.0));
+        assert_eq!(tracker.read_int(2), None);
+    }
+
+    /// Counts go on past the few repeats an audio line's clip level
+    /// would allow, even on a line handed over with one.
+    #[test]
+    fn test_tracker_counts_past_the_audio_clip_level() {
+        const TRIP: usize = 100;
+        let mut line = DelayLine::new(NonZeroUsize::new(256).unwrap());
+        line.set_clip_level(Some(4.0));
+        let mut tracker = RepeatTracker::new(line, 1000.0);
+        for trip in 0..10 {
+            for n in 0..TRIP {
+                let fed_back = tracker.read_int(TRIP);
+                let level = if n == 0 { 0.5_f32.powi(trip) } else { 0.0 };
+                let (input, feedback) = if trip == 0 {
+                    (level, 0.0)
+                } else {
+                    (0.0, level)
+                };
+                tracker.write(input, feedback, fed_back);
+            }
+        }
+        let count = tracker.read_int(TRIP);
+        assert!(count.is_some_and(|c| (c - 9.0).abs() < 1e-4), "{count:?}");
+    }
+
+    /// The count follows the notes, not the waveforms: a sine over a
+    /// different sine's repeat gets a steady count between the two,
+    /// where weighting each sample would flip it from one to the other.
+    #[test]
+    fn test_overlap_count_is_steady() {
+        const SAMPLE_RATE: f32 = 48000.0;
+        let mut tracker = RepeatTracker::new(
+            DelayLine::new(NonZeroUsize::new(4800).unwrap()),
+            SAMPLE_RATE,
+        );
+        let sine =
+            |freq: f32, n: usize| (std::f32::consts::TAU * freq * n as f32 / SAMPLE_RATE).sin();
+        let mut counts = Vec::new();
+        for n in 0..4800 {
+            tracker.write(sine(220.0, n).abs(), sine(330.0, n).abs(), Some(1.0));
+            counts.push(tracker.read_int(1).unwrap());
+        }
+        // Past the attack, the count stays near the middle: repeat 2
+        // weighted equally with new input.
+        let settled = &counts[480..];
+        let (low, high) = settled
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), c| (lo.min(*c), hi.max(*c)));
+        assert!(low > 0.6 && high < 1.4, "{low}..{high}");
+    }
+}
@@ -0,0 +1,154 @@
+//! # Sample Types
+//!
+//! Audio samples are almost always 32-bit floats (`f32`) — that's what
+//! the host hands us and what the plugin processes. But some jobs want
+//! more precision: offline rendering, long feedback loops where rounding
+//! error accumulates, or measuring how *accurate* the `f32` path is.
+//!
+//! Rather than writing every DSP primitive twice, the primitives in this
+//! module are generic over a small [`Sample`] trait. It's essentially a
+//! tiny subset of `num_traits::Float` plus `From<f32>`: just the handful
+//! of operations our DSP math actually uses. Keeping it in-house means
+//! the `dsp` module stays free of external dependencies.
+//!
+//! ## Why `From<f32>`?
+//!
+//! DSP code is full of literal constants (`0.49`, `20.0`, `-2.0`). With
+//! `From<f32>` we can write `T::from(20.0)` once and have it work for
+//! both `f32` (a no-op) and `f64` (a lossless widening).
+
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// A floating-point sample type (`f32` or `f64`) usable by the DSP
+/// primitives.
+///
+/// Each method mirrors the inherent method of the same name on `f32` /
+/// `f64`, so the generic code performs *exactly* the same operations as
+/// hand-written `f32` code — the `f32` results are bit-for-bit identical.
+pub trait Sample:
+    Copy
+    + PartialOrd
+    + From<f32>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+    /// Archimedes' constant (π) at this type's precision.
+    const PI: Self;
+
+    /// Additive identity (silence).
+    const ZERO: Self;
+
+    /// Multiplicative identity (unity gain).
+    const ONE: Self;
+
+    /// `e^self`.
+    fn exp(self) -> Self;
+
+    /// The tangent of `self` (in radians).
+    fn tan(self) -> Self;
+
+    /// Absolute value.
+    fn abs(self) -> Self;
+
+    /// Restrict `self` to the range `[min, max]`.
+    fn clamp(self, min: Self, max: Self) -> Self;
+
+    /// Convert a buffer index or length to a sample value (`n as f32`).
+    fn from_usize(n: usize) -> Self;
+
+    /// Truncate toward zero to a buffer index (`self as usize`).
+    ///
+    /// Negative values saturate to 0, just like an `as` cast.
+    fn to_usize(self) -> usize;
+
+    /// `true` unless the value is NaN or ±infinity.
+    fn is_finite(self) -> bool;
+}
+
+impl Sample for f32 {
+    const PI: Self = std::f32::consts::PI;
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    #[inline]
+    fn exp(self) -> Self {
+        f32::exp(self)
+    }
+
+    #[inline]
+    fn tan(self) -> Self {
+        f32::tan(self)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+
+    #[inline]
+    fn clamp(self, min: Self, max: Self) -> Self {
+        f32::clamp(self, min, max)
+    }
+
+    #[inline]
+    fn from_usize(n: usize) -> Self {
+        n as f32
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    #[inline]
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+}
+
+impl Sample for f64 {
+    const PI: Self = std::f64::consts::PI;
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    #[inline]
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    #[inline]
+    fn tan(self) -> Self {
+        f64::tan(self)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    #[inline]
+    fn clamp(self, min: Self, max: Self) -> Self {
+        f64::clamp(self, min, max)
+    }
+
+    #[inline]
+    fn from_usize(n: usize) -> Self {
+        n as f64
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    #[inline]
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
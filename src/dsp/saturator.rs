@@ -0,0 +1,327 @@
+//! # Saturator
+//!
+//! Analog delays don't just darken their repeats, they *squash* them:
+//! tape compresses loud signals, and bucket-brigade chips clip softly.
+//! Each trip around the loop rounds off the peaks a little more, adding
+//! harmonics and keeping runaway feedback in check.
+//!
+//! ## The Curves
+//!
+//! A saturator is a *waveshaper*: a fixed curve every sample is pushed
+//! through. All three curves here are linear (slope 1) for small inputs
+//! and differ in how they bend as the input grows:
+//!
+//! ```text
+//! Tanh        tanh(u)                          smooth all the way; the
+//!                                              classic "tape" soft clip
+//! Cubic       u − u³/3   for |u| ≤ 1           soft, but flattens out
+//!             ±2/3       beyond                completely at its knee
+//! Hard clip   clamp(u, −1, 1)                  linear right up to the
+//!                                              ceiling, then flat: harsh
+//! ```
+//!
+//! All three are *odd* (`f(−u) = −f(u)`), so they treat the positive and
+//! negative halves of a wave the same and add only odd harmonics. The
+//! cubic curve reaches its ceiling with a slope of exactly zero, so there
+//! is no corner where it meets the flat part.
+//!
+//! ## Drive
+//!
+//! The `drive` amount (0.0–1.0) both raises the gain `g` into the curve
+//! and crossfades from the clean signal to the shaped one:
+//!
+//! ```text
+//! g      = 1 + 7 * drive                  (0 dB up to +18 dB into the curve)
+//! shaped = curve(g * x) / g
+//! y      = x + drive * (shaped - x)
+//! ```
+//!
+//! Dividing by `g` undoes the gain for small signals (where every curve
+//! is `≈ u`), so quiet repeats keep their level and only the loud ones
+//! get squashed. Every curve has `|curve(u)| ≤ |u|`, so the shaped
+//! signal is never louder than the input — the curve itself can't add
+//! energy to the feedback loop.
+//!
+//! At `drive = 0` (0 dB into the curve) the output is exactly the input,
+//! so "no drive" is a true bypass, and turning the knob up from zero
+//! never jumps.
+//!
+//! ## Output Trim
+//!
+//! A final gain in dB, applied after the curve, to level-match a driven
+//! signal or feed the next stage hotter. It's the one part that *can*
+//! make the signal louder, so it's what the saturator reports as its
+//! peak gain to the loop-gain ceiling. At 0 dB (the default) it's an
+//! exact multiply by 1.
+//!
+//! The saturator holds settings but no signal state, so it's trivially
+//! real-time safe and can be shared or copied freely.
+
+/// Gain into the curve at full drive (+18 dB).
+const MAX_GAIN: f32 = 8.0;
+
+/// The waveshaping curves a [`Saturator`] can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Curve {
+    /// Hyperbolic tangent: smooth, tape-like soft clipping.
+    #[default]
+    Tanh,
+    /// Cubic soft clip: `u − u³/3`, flat at ±2/3 beyond `|u| = 1`.
+    Cubic,
+    /// Hard clip at ±1.
+    HardClip,
+}
+
+impl Curve {
+    /// Shape one value through the curve.
+    #[inline]
+    pub fn shape(self, u: f32) -> f32 {
+        match self {
+            Self::Tanh => u.tanh(),
+            Self::Cubic => {
+                let u = u.clamp(-1.0, 1.0);
+                u - u * u * u / 3.0
+            }
+            Self::HardClip => u.clamp(-1.0, 1.0),
+        }
+    }
+}
+
+/// A soft (or hard) clipper with a drive amount that bypasses exactly
+/// at 0, and an output trim.
+#[derive(Debug, Clone, Copy)]
+pub struct Saturator {
+    /// Which waveshaping curve to use.
+    curve: Curve,
+
+    /// How hard to drive (0.0 = bypass, 1.0 = full).
+    drive: f32,
+
+    /// Gain into the curve, derived from `drive`.
+    gain: f32,
+
+    /// Linear output gain, from the trim in dB.
+    trim: f32,
+}
+
+impl Saturator {
+    /// Create a bypassed tanh saturator with no trim.
+    pub fn new() -> Self {
+        Self {
+            curve: Curve::Tanh,
+            drive: 0.0,
+            gain: 1.0,
+            trim: 1.0,
+        }
+    }
+
+    /// Choose the waveshaping curve.
+    pub fn set_curve(&mut self, curve: Curve) {
+        self.curve = curve;
+    }
+
+    /// Set the drive amount, clamped to 0.0–1.0.
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(0.0, 1.0);
+        self.gain = 1.0 + (MAX_GAIN - 1.0) * self.drive;
+    }
+
+    /// Set the output trim in dB.
+    pub fn set_trim_db(&mut self, trim_db: f32) {
+        self.trim = 10.0_f32.powf(trim_db / 20.0);
+    }
+
+    /// The most the saturator can amplify a signal: its output trim.
+    pub fn max_gain(&self) -> f32 {
+        self.trim
+    }
+
+    /// Shape one sample.
+    #[inline]
+    pub fn process(&self, input: f32) -> f32 {
+        if self.drive == 0.0 {
+            return input * self.trim;
+        }
+
+        let shaped = self.curve.shape(self.gain * input) / self.gain;
+        (input + self.drive * (shaped - input)) * self.trim
+    }
+}
+
+impl Default for Saturator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Zero drive is a bit-exact bypass, even for huge values.
+    #[test]
+    fn test_zero_drive_bypasses() {
+        let saturator = Saturator::new();
+        for x in [0.0, 0.3, -0.9, 4.0, -100.0] {
+            assert_eq!(saturator.process(x).to_bits(), f32::to_bits(x));
+        }
+    }
+
+    /// The output is never louder than the input, at any drive, and
+    /// keeps its sign.
+    #[test]
+    fn test_never_adds_energy() {
+        let mut saturator = Saturator::new();
+        for drive in [0.1, 0.5, 1.0] {
+            saturator.set_drive(drive);
+            for i in -400..=400 {
+                let x = i as f32 / 100.0;
+                let y = saturator.process(x);
+                assert!(y.abs() <= x.abs() + 1e-6, "drive {drive}: {x} → {y}");
+                assert!(y * x >= 0.0, "drive {drive}: sign flipped for {x}");
+            }
+        }
+    }
+
+    /// Quiet signals pass almost untouched; loud ones get squashed.
+    #[test]
+    fn test_squashes_only_loud_signals() {
+        let mut saturator = Saturator::new();
+        saturator.set_drive(1.0);
+
+        let quiet = saturator.process(0.01);
+        assert!((quiet - 0.01).abs() < 1e-4, "quiet: {quiet}");
+
+        let loud = saturator.process(1.0);
+        assert!(loud < 0.2, "loud should be squashed, got {loud}");
+    }
+
+    /// All three curves, for the shape tests.
+    const CURVES: [Curve; 3] = [Curve::Tanh, Curve::Cubic, Curve::HardClip];
+
+    /// Inputs from −4 to 4 in steps of 0.01.
+    fn sweep() -> impl Iterator<Item = f32> {
+        (-400..=400).map(|i| i as f32 / 100.0)
+    }
+
+    /// Every curve is odd: negative inputs mirror positive ones exactly.
+    #[test]
+    fn test_curves_are_odd() {
+        for curve in CURVES {
+            for u in sweep() {
+                assert_eq!(curve.shape(-u), -curve.shape(u), "{curve:?} at {u}");
+            }
+        }
+    }
+
+    /// A bigger input never gives a smaller output, for every curve at
+    /// every drive.
+    #[test]
+    fn test_monotonic() {
+        let mut saturator = Saturator::new();
+        for curve in CURVES {
+            saturator.set_curve(curve);
+            for drive in [0.0, 0.3, 1.0] {
+                saturator.set_drive(drive);
+                let outputs: Vec<f32> = sweep().map(|x| saturator.process(x)).collect();
+                // Allow for rounding in the crossfade once the curve has
+                // gone flat.
+                assert!(
+                    outputs.windows(2).all(|w| w[1] >= w[0] - 1e-6),
+                    "{curve:?} at drive {drive}"
+                );
+            }
+        }
+    }
+
+    /// The curves never exceed their ceilings, however hard they're hit.
+    #[test]
+    fn test_soft_curves_are_bounded() {
+        for u in sweep().map(|u| u * 100.0) {
+            assert!(Curve::Tanh.shape(u).abs() <= 1.0);
+            assert!(Curve::Cubic.shape(u).abs() <= 2.0 / 3.0);
+            assert!(Curve::HardClip.shape(u).abs() <= 1.0);
+        }
+    }
+
+    /// The cubic curve meets its flat part at the knee with no step and
+    /// no corner: the value arrives at 2/3 with a slope of zero.
+    #[test]
+    fn test_cubic_continuous_at_knee() {
+        let eps = 1e-3;
+        let below = Curve::Cubic.shape(1.0 - eps);
+        let above = Curve::Cubic.shape(1.0 + eps);
+        assert!((below - 2.0 / 3.0).abs() < 1e-5, "{below}");
+        assert!((above - 2.0 / 3.0).abs() < 1e-6, "{above}");
+
+        let slope_below = (Curve::Cubic.shape(1.0) - below) / eps;
+        assert!(slope_below.abs() < 1e-2, "slope {slope_below}");
+    }
+
+    /// The trim is applied after the curve and reported as the peak
+    /// gain; at 0 dB it's exact.
+    #[test]
+    fn test_output_trim() {
+        let mut saturator = Saturator::new();
+        saturator.set_trim_db(0.0);
+        assert_eq!(saturator.max_gain(), 1.0);
+        assert_eq!(saturator.process(0.3), 0.3);
+
+        saturator.set_trim_db(6.0);
+        assert!((saturator.max_gain() - 1.995).abs() < 1e-3);
+        assert!((saturator.process(0.3) - 0.3 * 1.995).abs() < 1e-3);
+    }
+
+    /// Harmonic distortion of a 0.5-amplitude sine through the tanh
+    /// curve at full drive: plenty of odd harmonics, no even ones — and
+    /// next to none with the drive barely up.
+    #[test]
+    fn test_thd_sanity() {
+        // 64 samples per cycle, 16 cycles: every harmonic lands exactly
+        // on a DFT bin.
+        const PERIOD: usize = 64;
+        const LEN: usize = PERIOD * 16;
+
+        let harmonic_amplitude = |output: &[f32], harmonic: usize| {
+            let bin = 16 * harmonic;
+            let (mut re, mut im) = (0.0_f64, 0.0_f64);
+            for (n, &y) in output.iter().enumerate() {
+                let phase = std::f64::consts::TAU * (bin * n) as f64 / LEN as f64;
+                re += f64::from(y) * phase.cos();
+                im -= f64::from(y) * phase.sin();
+            }
+            2.0 * (re * re + im * im).sqrt() / LEN as f64
+        };
+        let thd = |drive: f32| {
+            let mut saturator = Saturator::new();
+            saturator.set_drive(drive);
+            let output: Vec<f32> = (0..LEN)
+                .map(|n| {
+                    let phase = std::f32::consts::TAU * n as f32 / PERIOD as f32;
+                    saturator.process(0.5 * phase.sin())
+                })
+                .collect();
+            let fundamental = harmonic_amplitude(&output, 1);
+            let odd: f64 = [3, 5, 7, 9]
+                .iter()
+                .map(|&h| harmonic_amplitude(&output, h).powi(2))
+                .sum();
+            let even: f64 = [2, 4, 6, 8]
+                .iter()
+                .map(|&h| harmonic_amplitude(&output, h).powi(2))
+                .sum();
+            (odd.sqrt() / fundamental, even.sqrt() / fundamental)
+        };
+
+        let (odd, even) = thd(1.0);
+        assert!(odd > 0.1, "full drive THD {odd}");
+        assert!(even < 1e-4, "even harmonics {even}");
+
+        let (odd, _) = thd(0.02);
+        assert!(odd < 0.01, "light drive THD {odd}");
+    }
+}
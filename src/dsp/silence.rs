@@ -0,0 +1,159 @@
+//! # Silence Tracking
+//!
+//! A delay keeps making sound after its input stops — that's the whole
+//! point. The host needs to know when the echoes have *actually* died
+//! away so it can stop calling `process()` on a silent track.
+//!
+//! We could predict that from the feedback math alone, but as soon as
+//! anything non-linear or level-dependent lives in the loop (filters,
+//! gates, ducking), the prediction drifts from reality. So instead we
+//! *measure*: a [`SilenceTracker`] counts how many consecutive samples
+//! have stayed below a threshold across every channel it watches.
+//!
+//! ## Why "stereo-linked"?
+//!
+//! One tracker watches all channels at once. A sample only counts as
+//! silent if *every* channel is below the threshold at that moment, so a
+//! tail that has died on the left but still rings on the right keeps the
+//! plugin alive.
+//!
+//! ## Thresholds in dBFS
+//!
+//! Levels are compared as linear amplitudes. The conversion is:
+//!
+//! ```text
+//! amplitude = 10^(dB / 20)
+//!
+//! -60 dBFS  → 0.001
+//! -90 dBFS  → 0.0000316
+//! -120 dBFS → 0.000001
+//! ```
+
+/// Counts consecutive below-threshold samples across one or more
+/// channels.
+///
+/// Feed it one block at a time: for each channel, find the index of the
+/// last loud sample with [`last_loud_index()`](Self::last_loud_index) (or
+/// track it while processing), then call [`update()`](Self::update) with
+/// the latest of those indices. The result doesn't depend on how the
+/// stream is split into blocks.
+pub struct SilenceTracker {
+    /// Linear amplitude below which a sample counts as silent.
+    threshold: f32,
+
+    /// Consecutive silent samples (on all channels) up to now.
+    silent_samples: usize,
+}
+
+impl SilenceTracker {
+    /// Create a tracker with a threshold given in dBFS (e.g. `-90.0`).
+    ///
+    /// The tracker starts out assuming it has heard nothing silent yet,
+    /// which is the conservative choice: the plugin keeps processing
+    /// until silence has actually been observed.
+    pub fn new(threshold_db: f32) -> Self {
+        Self {
+            threshold: 10.0_f32.powf(threshold_db / 20.0),
+            silent_samples: 0,
+        }
+    }
+
+    /// The linear-amplitude threshold.
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// `true` if `sample` is at or above the threshold.
+    #[inline]
+    pub fn is_loud(&self, sample: f32) -> bool {
+        sample.abs() >= self.threshold
+    }
+
+    /// Index of the last sample in `samples` at or above the threshold,
+    /// or `None` if the whole slice is silent.
+    pub fn last_loud_index(&self, samples: &[f32]) -> Option<usize> {
+        samples.iter().rposition(|s| self.is_loud(*s))
+    }
+
+    /// Account for a block of `block_len` samples in which the last loud
+    /// sample (on any channel) was at `last_loud` — `None` if the block
+    /// was silent everywhere.
+    pub fn update(&mut self, last_loud: Option<usize>, block_len: usize) {
+        self.silent_samples = match last_loud {
+            Some(index) => block_len - (index + 1),
+            None => self.silent_samples.saturating_add(block_len),
+        };
+    }
+
+    /// How many consecutive samples have been silent on every channel.
+    pub fn silent_samples(&self) -> usize {
+        self.silent_samples
+    }
+
+    /// Forget everything observed so far.
+    pub fn reset(&mut self) {
+        self.silent_samples = 0;
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// -90 dBFS should convert to ~3.16e-5.
+    #[test]
+    fn test_threshold_conversion() {
+        let tracker = SilenceTracker::new(-90.0);
+        assert!((tracker.threshold() - 3.162e-5).abs() < 1e-7);
+        assert!(tracker.is_loud(1e-4));
+        assert!(!tracker.is_loud(-1e-5));
+    }
+
+    /// Silent samples accumulate across blocks and restart after the
+    /// last loud sample of a block.
+    #[test]
+    fn test_counts_across_blocks() {
+        let mut tracker = SilenceTracker::new(-90.0);
+        assert_eq!(tracker.silent_samples(), 0);
+
+        // Loud sample at index 3 of 10 → 6 silent samples after it.
+        tracker.update(Some(3), 10);
+        assert_eq!(tracker.silent_samples(), 6);
+
+        tracker.update(None, 10);
+        assert_eq!(tracker.silent_samples(), 16);
+
+        // Loud on the very last sample resets to zero.
+        tracker.update(Some(9), 10);
+        assert_eq!(tracker.silent_samples(), 0);
+
+        tracker.update(None, 5);
+        tracker.reset();
+        assert_eq!(tracker.silent_samples(), 0);
+    }
+
+    /// The count must not depend on how the stream is chopped into
+    /// blocks.
+    #[test]
+    fn test_block_size_independent() {
+        let signal: Vec<f32> = (0..1000)
+            .map(|i| if i % 97 == 0 && i < 600 { 0.5 } else { 0.0 })
+            .collect();
+
+        let mut results = Vec::new();
+        for block in [1, 7, 64, 1000] {
+            let mut tracker = SilenceTracker::new(-90.0);
+            for chunk in signal.chunks(block) {
+                tracker.update(tracker.last_loud_index(chunk), chunk.len());
+            }
+            results.push(tracker.silent_samples());
+        }
+
+        // Last loud sample is at 582 (= 97 * 6), so 417 silent samples.
+        assert!(results.iter().all(|&r| r == 417), "{results:?}");
+    }
+}
@@ -0,0 +1,342 @@
+//! # Parameter Smoother
+//!
+//! When a parameter jumps (a knob is turned, automation steps, a preset
+//! loads), applying the new value instantly puts a corner in the audio:
+//! a click, or a burst of "zipper noise" while the knob is moving. A
+//! smoother hides the jump by ramping from the old value to the new one
+//! over a few tens of milliseconds.
+//!
+//! ## Linear Ramps
+//!
+//! The simplest ramp is a straight line. When a new target arrives, we
+//! work out how many samples the ramp should last and how far to move on
+//! each one:
+//!
+//! ```text
+//! steps     = round(ramp_ms / 1000 * sample_rate)
+//! step_size = (target - current) / steps
+//! ```
+//!
+//! Every call to [`next()`](Smoother::next) moves one step, and the
+//! final step lands exactly on the target (no accumulated rounding
+//! error). This is the same shape as nih-plug's
+//! `SmoothingStyle::Linear`, but it lives here so the DSP engine can be
+//! driven without the plugin framework.
+//!
+//! ## Logarithmic Ramps
+//!
+//! A straight line is the wrong shape for a frequency. We hear pitch in
+//! ratios — every octave sounds the same size — so a linear sweep from
+//! 20 kHz down to 200 Hz spends most of its time in the top two octaves,
+//! where little changes, then crosses the whole audible middle in the
+//! last few milliseconds:
+//!
+//! ```text
+//! 20 kHz → 200 Hz over 50 ms     linear         logarithmic
+//!   after 25 ms                  10.1 kHz       2 kHz
+//!   octaves covered by then      1 of 6.6       3.3 of 6.6
+//! ```
+//!
+//! A logarithmic ramp moves by the same *ratio* every sample instead,
+//!
+//! ```text
+//! ratio = (target / current)^(1 / steps)
+//! ```
+//!
+//! so it covers an equal number of octaves in equal time. It needs
+//! positive values, which frequencies always are.
+//!
+//! ## Rising and Falling at Different Speeds
+//!
+//! Some moves are more urgent one way than the other. Turning feedback
+//! *down* in a hurry is how a player stops a runaway loop, and a 20 ms
+//! ramp lets several more milliseconds of it through. A smoother can
+//! have its own, shorter ramp time for falling targets
+//! ([`with_fall_ms()`](Smoother::with_fall_ms)), so cuts land fast while
+//! rises stay gentle.
+//!
+//! ## Shorter Ramps, With a Floor
+//!
+//! Automation a host has already smoothed doesn't need tens of
+//! milliseconds more on top; it only makes the knob feel late. A ramp
+//! scale ([`set_ramp_scale()`](Smoother::set_ramp_scale)) shortens the
+//! ramps, but never below [`MIN_RAMP_MS`]: a single jump, however
+//! large, still gets enough samples not to click.
+//!
+//! ```text
+//! ramp = max(ramp_ms × scale, min(ramp_ms, MIN_RAMP_MS))
+//! ```
+
+/// The shortest a scaled ramp gets, in milliseconds: the safety floor
+/// under [`Smoother::set_ramp_scale()`].
+pub const MIN_RAMP_MS: f32 = 2.0;
+
+/// The shape of a [`Smoother`]'s ramp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SmoothingStyle {
+    /// Equal steps: right for gains and times.
+    #[default]
+    Linear,
+    /// Equal ratios: right for frequencies.
+    Logarithmic,
+}
+
+/// Ramps from its current value to a target over a fixed time.
+pub struct Smoother {
+    style: SmoothingStyle,
+
+    /// How long a full ramp up takes, in milliseconds.
+    ramp_ms: f32,
+
+    /// How long a full ramp down takes, in milliseconds.
+    fall_ms: f32,
+
+    /// What both times are scaled by (see
+    /// [`set_ramp_scale()`](Self::set_ramp_scale)).
+    scale: f32,
+
+    /// The value most recently returned by `next()`.
+    current: f32,
+
+    /// Where the ramp is heading.
+    target: f32,
+
+    /// How far to move per sample while ramping: added for a linear
+    /// ramp, multiplied in for a logarithmic one.
+    step_size: f32,
+
+    /// Samples remaining in the current ramp (0 = idle).
+    steps_left: u32,
+}
+
+/// The smallest value a logarithmic ramp works with; anything at or
+/// below zero is treated as this.
+const LOG_FLOOR: f32 = 1e-6;
+
+impl Smoother {
+    /// Create an idle linear smoother at `value` whose ramps last
+    /// `ramp_ms`.
+    pub fn new(ramp_ms: f32, value: f32) -> Self {
+        Self {
+            style: SmoothingStyle::Linear,
+            ramp_ms,
+            fall_ms: ramp_ms,
+            scale: 1.0,
+            current: value,
+            target: value,
+            step_size: 0.0,
+            steps_left: 0,
+        }
+    }
+
+    /// Create an idle logarithmic smoother at `value` (for frequencies)
+    /// whose ramps last `ramp_ms`.
+    pub fn logarithmic(ramp_ms: f32, value: f32) -> Self {
+        Self {
+            style: SmoothingStyle::Logarithmic,
+            ..Self::new(ramp_ms, value)
+        }
+    }
+
+    /// Make ramps toward a lower value last `fall_ms` instead.
+    pub fn with_fall_ms(mut self, fall_ms: f32) -> Self {
+        self.fall_ms = fall_ms;
+        self
+    }
+
+    /// Scale the ramp times by `scale` from the next target on, but not
+    /// below [`MIN_RAMP_MS`] (or the unscaled time, if that's shorter).
+    /// A ramp under way finishes as it started.
+    pub fn set_ramp_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+
+    /// Jump straight to `value` without ramping.
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.steps_left = 0;
+    }
+
+    /// Start ramping from the current value toward `target`.
+    ///
+    /// Setting the target it's already heading for does nothing, so this
+    /// is safe to call at the start of every block with whatever the
+    /// latest parameter value is — an in-progress ramp isn't restarted.
+    pub fn set_target(&mut self, sample_rate: f32, target: f32) {
+        if target == self.target {
+            return;
+        }
+
+        let ramp_ms = if target < self.current {
+            self.fall_ms
+        } else {
+            self.ramp_ms
+        };
+        let ramp_ms = (ramp_ms * self.scale).max(ramp_ms.min(MIN_RAMP_MS));
+        self.target = target;
+        self.steps_left = (ramp_ms / 1000.0 * sample_rate).round() as u32;
+        if self.steps_left == 0 {
+            self.current = target;
+            return;
+        }
+        let steps = self.steps_left as f32;
+        self.step_size = match self.style {
+            SmoothingStyle::Linear => (target - self.current) / steps,
+            SmoothingStyle::Logarithmic => {
+                self.current = self.current.max(LOG_FLOOR);
+                (target.max(LOG_FLOOR) / self.current).powf(1.0 / steps)
+            }
+        };
+    }
+
+    /// Advance one sample and return the new value.
+    ///
+    /// An idle smoother just returns its target.
+    ///
+    /// Named like nih-plug's `Smoother::next()` on purpose; a smoother
+    /// never runs out, so it isn't an `Iterator`.
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.steps_left > 0 {
+            self.steps_left -= 1;
+            self.current = if self.steps_left == 0 {
+                self.target
+            } else {
+                match self.style {
+                    SmoothingStyle::Linear => self.current + self.step_size,
+                    SmoothingStyle::Logarithmic => self.current * self.step_size,
+                }
+            };
+        }
+        self.current
+    }
+
+    /// The value most recently returned by [`next()`](Self::next),
+    /// without advancing.
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// The value being ramped toward.
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// `true` while a ramp is in progress.
+    pub fn is_smoothing(&self) -> bool {
+        self.steps_left > 0
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1ms ramp at 48 kHz takes 48 even steps and ends exactly on the
+    /// target.
+    #[test]
+    fn test_ramp_is_linear_and_exact() {
+        let mut smoother = Smoother::new(1.0, 0.0);
+        smoother.set_target(48000.0, 1.0);
+        assert!(smoother.is_smoothing());
+
+        let values: Vec<f32> = (0..48).map(|_| smoother.next()).collect();
+        for (i, v) in values.iter().enumerate() {
+            let expected = (i + 1) as f32 / 48.0;
+            assert!((v - expected).abs() < 1e-5, "step {i}: {v} vs {expected}");
+        }
+        assert_eq!(values[47], 1.0);
+        assert!(!smoother.is_smoothing());
+        assert_eq!(smoother.next(), 1.0);
+    }
+
+    /// Re-sending the current target mid-ramp must not restart the ramp.
+    #[test]
+    fn test_same_target_does_not_restart() {
+        let mut reference = Smoother::new(10.0, 0.0);
+        let mut resent = Smoother::new(10.0, 0.0);
+        reference.set_target(48000.0, 1.0);
+        resent.set_target(48000.0, 1.0);
+
+        for i in 0..600 {
+            if i % 64 == 0 {
+                resent.set_target(48000.0, 1.0);
+            }
+            assert_eq!(reference.next(), resent.next());
+        }
+    }
+
+    /// A logarithmic ramp moves by the same ratio every sample, so it's
+    /// at the geometric mean halfway through, and still ends exactly on
+    /// the target.
+    #[test]
+    fn test_logarithmic_ramp_is_geometric() {
+        let mut smoother = Smoother::logarithmic(1.0, 20000.0);
+        smoother.set_target(48000.0, 200.0);
+
+        let mut values = vec![20000.0];
+        values.extend((0..48).map(|_| smoother.next()));
+        let first_ratio = values[1] / values[0];
+        for w in values.windows(2) {
+            assert!((w[1] / w[0] - first_ratio).abs() < 1e-4, "{w:?}");
+        }
+        assert!((values[24] - 2000.0).abs() < 1.0, "{}", values[24]);
+        assert_eq!(values[48], 200.0);
+    }
+
+    /// With a fall time set, ramps down take that long and ramps up
+    /// keep the rise time.
+    #[test]
+    fn test_fall_time() {
+        let mut smoother = Smoother::new(20.0, 1.0).with_fall_ms(5.0);
+        smoother.set_target(1000.0, 0.0);
+        let down = (1..).find(|_| smoother.next() == 0.0).unwrap();
+        assert_eq!(down, 5);
+
+        smoother.set_target(1000.0, 1.0);
+        let up = (1..).find(|_| smoother.next() == 1.0).unwrap();
+        assert_eq!(up, 20);
+    }
+
+    /// A scale shortens both ramp times, down to the floor and no
+    /// further; a ramp already shorter than the floor is left alone.
+    #[test]
+    fn test_ramp_scale_keeps_the_floor() {
+        let mut smoother = Smoother::new(20.0, 1.0).with_fall_ms(5.0);
+        smoother.set_ramp_scale(0.25);
+        smoother.set_target(1000.0, 0.0);
+        assert_eq!((1..).find(|_| smoother.next() == 0.0), Some(2));
+        smoother.set_target(1000.0, 1.0);
+        assert_eq!((1..).find(|_| smoother.next() == 1.0), Some(5));
+
+        smoother.set_ramp_scale(0.0);
+        smoother.set_target(1000.0, 0.0);
+        assert_eq!((1..).find(|_| smoother.next() == 0.0), Some(2));
+
+        let mut short = Smoother::new(1.0, 0.0);
+        short.set_ramp_scale(0.0);
+        short.set_target(1000.0, 1.0);
+        assert_eq!(short.next(), 1.0);
+    }
+
+    /// `current()` reads without stepping; `reset()` jumps.
+    #[test]
+    fn test_current_and_reset() {
+        let mut smoother = Smoother::new(10.0, 5.0);
+        smoother.set_target(1000.0, 15.0);
+        let first = smoother.next();
+        assert_eq!(smoother.current(), first);
+        assert_eq!(smoother.current(), first);
+
+        smoother.reset(2.0);
+        assert!(!smoother.is_smoothing());
+        assert_eq!(smoother.target(), 2.0);
+        assert_eq!(smoother.next(), 2.0);
+    }
+}
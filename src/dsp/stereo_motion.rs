@@ -0,0 +1,424 @@
+//! # Stereo Motion: Ping-Pong and Circular Repeats
+//!
+//! A plain stereo delay keeps each side to itself: whatever goes in on
+//! the left repeats on the left. *Stereo motion* moves the repeats
+//! around the stereo field instead, so each echo arrives from somewhere
+//! new.
+//!
+//! ## Ping-Pong
+//!
+//! The classic trick needs no panning at all, just different wiring.
+//! The input (summed to mono) enters only the left line, and each line's
+//! feedback is written into the *other* line:
+//!
+//! ```text
+//!  in ──► [left line] ──► L out        1st repeat: left
+//!              ▲    ╲                  2nd repeat: right
+//!              │     ╲  feedback       3rd repeat: left
+//!              │      ▼                ...
+//!         [right line] ──► R out
+//! ```
+//!
+//! Every pass through the loop crosses sides, so the repeats bounce
+//! left, right, left. *Reverse* ping-pong feeds the right line instead,
+//! so the first repeat comes from the right.
+//!
+//! ### A Centered First Repeat
+//!
+//! A [`StartSide`] picks which line the input enters, and so where the
+//! first repeat lands. *Center* puts the first repeat in the middle and
+//! only then starts bouncing. That can't be done by feeding both lines
+//! and crossing the feedback: the loop treats every pass the same way,
+//! so whatever starts in the middle stays in the middle. The first
+//! repeat has to be handled outside the loop instead.
+//!
+//! So a centered start runs the loop exactly like a left start, and
+//! moves only the first repeat. The first repeat is the input one
+//! period ago, with nothing else mixed in: its copy is read from a
+//! delay line that holds just the input, then taken out of the left
+//! line's output and put back in the middle:
+//!
+//! ```text
+//! heard left  = left line  − first + first·g
+//! heard right = right line          + first·g      g = cos(π/4) ≈ 0.707
+//! ```
+//!
+//! The loop itself is untouched, so the second repeat still crosses
+//! to the right and the third comes back left, just as with a left
+//! start.
+//!
+//! ## Circular
+//!
+//! Circular motion pans the repeats with a rotor: an angle that turns by
+//! a fixed amount — the *rotation* — every delay period. Each repeat
+//! arrives one period after the previous one, so each is heard one
+//! rotation further round. The stereo position is the sine of the angle,
+//! like looking at something circling you from straight ahead:
+//!
+//! ```text
+//! rotation 90°:   angle   0°    90°    180°   270°   360° ...
+//!                 pan   centre right  centre  left  centre
+//! ```
+//!
+//! Smaller rotations drift slowly across the field; 180° flips between
+//! the sides much like ping-pong, but only once the rotor has moved off
+//! centre.
+//!
+//! ## Constant-Power Panning
+//!
+//! Turning one side up and the other down linearly (`L = 1 − p`,
+//! `R = p`) leaves a sound in the middle 3 dB quieter than at the
+//! edges: two half-level copies add up to only half the power. The
+//! constant-power law moves the gains along a quarter circle instead,
+//!
+//! ```text
+//! L = cos(θ), R = sin(θ),  θ = (pan + 1) · π/4
+//! ```
+//!
+//! so `L² + R² = 1` everywhere, and an echo keeps its loudness as it
+//! travels. It's the same law an equal-power crossfade uses (see
+//! `crossfade`), applied to space instead of time.
+//!
+//! ## Panning a Stereo Pair
+//!
+//! A pan knob on a stereo signal can't place one sound, because there
+//! are two: the left channel and the right. [`pan_stereo_pair()`]
+//! moves the far one across instead. Turning left, the right channel
+//! travels along the same constant-power arc towards the left, while
+//! the left channel stays where it is:
+//!
+//! ```text
+//! pan  0.0:  L = l                  R = r          (untouched)
+//! pan −0.5:  L = l + r·cos(π/4)     R = r·sin(π/4) (right channel centred)
+//! pan −1.0:  L = l + r              R = 0          (both hard left)
+//! ```
+//!
+//! Turning right mirrors it. Nothing is lost or boosted on the way —
+//! the moving channel keeps its power, and the other is left alone —
+//! so it sounds like sliding the stereo image over, not like the
+//! balance knob on a hi-fi, which just turns one side down.
+//!
+//! ## Balancing the Input
+//!
+//! For the input going into the delay, a balance knob is what's wanted:
+//! more of one side, less of the other. [`balance_gains()`] keeps the
+//! total power the same, as the panning law does, but leaves both sides
+//! at unity in the middle, so a centred knob changes nothing at all:
+//!
+//! ```text
+//! L = √(1 − b), R = √(1 + b)       L² + R² = 2 everywhere
+//!
+//! b  0.0:  L = 1.0    R = 1.0      (untouched)
+//! b  0.5:  L = 0.71   R = 1.22     (−3.0 dB, +1.8 dB)
+//! b  1.0:  L = 0      R = 1.41     (right only, +3 dB)
+//! ```
+//!
+//! The side turned up gains at most 3 dB: the level the left side had,
+//! moved into the right.
+//!
+//! ## Narrowing the Repeats
+//!
+//! Any stereo pair can be taken apart into what the two sides share,
+//! the *mid*, and what sets them apart, the *side*:
+//!
+//! ```text
+//! M = (L + R) / 2        L = M + S
+//! S = (L − R) / 2        R = M − S
+//! ```
+//!
+//! Scale the side on every trip round the feedback loop and each repeat
+//! comes back a little narrower than the last, while the mid decays at
+//! the Feedback knob's rate as before. [`narrow_pair()`] does it: a
+//! side decay of 3 dB per pass leaves the fifth repeat 12 dB narrower
+//! (side against mid) than the first, which is how echoes in a real
+//! room tend to fold back into the middle as they fade.
+//!
+//! It can only take level away. The mid passes untouched and the side
+//! only shrinks, so as a matrix it has a gain of exactly 1 — the loop
+//! gain, and the loop-gain ceiling, don't change. Ping-pong crosses the
+//! sides after it, which turns the side upside down on every pass but
+//! doesn't change its size, so the repeats still close in towards the
+//! centre, alternating as they go.
+
+use std::f32::consts::FRAC_PI_4;
+
+/// How the repeats move around the stereo field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StereoMotion {
+    /// No motion: each side repeats its own input.
+    #[default]
+    Off,
+    /// The input enters the left line and the repeats alternate sides,
+    /// left first.
+    PingPong,
+    /// Like [`PingPong`](Self::PingPong), but right first.
+    ReversePingPong,
+    /// The repeats follow a rotor that turns once per delay period.
+    Circular,
+}
+
+/// Where ping-pong's first repeat is heard. Reverse ping-pong mirrors
+/// it: a left start there begins on the right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartSide {
+    #[default]
+    Left,
+    Right,
+    /// In the middle, then alternating from the other side of the
+    /// entry line (see [the module docs](self)).
+    Center,
+}
+
+impl StereoMotion {
+    /// How much of the mono input sum each line (`[left, right]`)
+    /// receives, or `None` when each line keeps its own side's input.
+    pub fn input_gains(self, start: StartSide) -> Option<[f32; 2]> {
+        match self {
+            StereoMotion::Off => None,
+            StereoMotion::PingPong | StereoMotion::ReversePingPong => {
+                Some(match self.entry_line(start) {
+                    0 => [1.0, 0.0],
+                    _ => [0.0, 1.0],
+                })
+            }
+            StereoMotion::Circular => Some([1.0, 1.0]),
+        }
+    }
+
+    /// For ping-pong with a centered start, the line (0 left, 1 right)
+    /// whose first repeat is moved to the middle.
+    pub fn centered_line(self, start: StartSide) -> Option<usize> {
+        (self.crosses_feedback() && start == StartSide::Center).then(|| self.entry_line(start))
+    }
+
+    /// The line the input enters for ping-pong. A centered start enters
+    /// where a left start would.
+    fn entry_line(self, start: StartSide) -> usize {
+        let mirrored = self == StereoMotion::ReversePingPong;
+        match (start, mirrored) {
+            (StartSide::Right, false) | (StartSide::Left | StartSide::Center, true) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Whether each line's feedback is written into the other line.
+    pub fn crosses_feedback(self) -> bool {
+        matches!(self, StereoMotion::PingPong | StereoMotion::ReversePingPong)
+    }
+}
+
+/// The `[left, right]` gains that place a sound at `pan` (−1.0 hard
+/// left, 0.0 centre, 1.0 hard right) with constant power.
+#[inline]
+pub fn constant_power_gains(pan: f32) -> [f32; 2] {
+    let theta = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    [theta.cos(), theta.sin()]
+}
+
+/// Pan a stereo pair by `pan` (−1.0 hard left, 0.0 centre, 1.0 hard
+/// right), moving the far channel across with constant power (see
+/// [the module docs](self)). At 0.0 the pair comes back bit for bit.
+#[inline]
+pub fn pan_stereo_pair(pan: f32, [left, right]: [f32; 2]) -> [f32; 2] {
+    // Both directions take their gains from the same half of the arc,
+    // so hard left and hard right are exact mirrors: `[1.0, 0.0]` at
+    // the end, with no rounding left over on the side that empties.
+    let pan = pan.clamp(-1.0, 1.0);
+    if pan == 0.0 {
+        return [left, right];
+    }
+    let [across, stays] = constant_power_gains(1.0 - 2.0 * pan.abs());
+    if pan < 0.0 {
+        [left + across * right, stays * right]
+    } else {
+        [stays * left, right + across * left]
+    }
+}
+
+/// The `[left, right]` gains that balance a stereo pair by `balance`
+/// (−1.0 left only, 0.0 even, 1.0 right only) at constant total power.
+/// Exactly `[1.0, 1.0]` at 0.0. See [the module docs](self#balancing-the-input).
+#[inline]
+pub fn balance_gains(balance: f32) -> [f32; 2] {
+    let balance = balance.clamp(-1.0, 1.0);
+    [(1.0 - balance).sqrt(), (1.0 + balance).sqrt()]
+}
+
+/// The most side decay there is, in dB per pass.
+pub const MAX_SIDE_DECAY_DB: f32 = 12.0;
+
+/// `pair` with its side (see [the module docs](self#narrowing-the-repeats))
+/// scaled by `side_gain` and its mid left alone. The round trip is only
+/// exact to rounding, so at a `side_gain` of 1.0 callers skip it.
+#[inline]
+pub fn narrow_pair(side_gain: f32, [left, right]: [f32; 2]) -> [f32; 2] {
+    let mid = 0.5 * (left + right);
+    let side = 0.5 * (left - right) * side_gain;
+    [mid + side, mid - side]
+}
+
+/// An angle that turns by a set rotation every delay period, for
+/// circular motion.
+#[derive(Debug, Clone)]
+pub struct PanRotor {
+    /// The angle, in turns (0.0..1.0). `f64`, like the LFO's phase, so
+    /// long runs don't drift off the delay grid.
+    phase: f64,
+
+    /// How far the angle turns per period, in turns.
+    rotation: f64,
+}
+
+impl PanRotor {
+    /// Create a rotor at 0° (centre) turning a quarter turn per period.
+    pub fn new() -> Self {
+        Self {
+            phase: 0.0,
+            rotation: 0.25,
+        }
+    }
+
+    /// Set how far the rotor turns each period, in degrees.
+    pub fn set_rotation_degrees(&mut self, degrees: f32) {
+        self.rotation = f64::from(degrees) / 360.0;
+    }
+
+    /// The pan position (−1.0..=1.0) for this sample, then turn by one
+    /// sample's worth of a `period_samples`-long period.
+    #[inline]
+    pub fn next_pan(&mut self, period_samples: f32) -> f32 {
+        let pan = (std::f64::consts::TAU * self.phase).sin() as f32;
+        self.phase = (self.phase + self.rotation / f64::from(period_samples.max(1.0))).fract();
+        pan
+    }
+
+    /// Go back to 0° (centre).
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+impl Default for PanRotor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The gains keep constant power across the field, and hit the
+    /// edges and the centre exactly where they should.
+    #[test]
+    fn test_constant_power() {
+        for n in -20..=20 {
+            let [l, r] = constant_power_gains(n as f32 / 20.0);
+            assert!((l * l + r * r - 1.0).abs() < 1e-6);
+        }
+        let [l, r] = constant_power_gains(-1.0);
+        assert!((l - 1.0).abs() < 1e-6 && r.abs() < 1e-6);
+        let [l, r] = constant_power_gains(1.0);
+        assert!(l.abs() < 1e-6 && (r - 1.0).abs() < 1e-6);
+        let [l, r] = constant_power_gains(0.0);
+        assert!((l - r).abs() < 1e-6);
+    }
+
+    /// Centre passes the pair through untouched, hard left puts both
+    /// channels on the left with nothing on the right (and hard right
+    /// mirrors it), and the moving channel keeps its power on the way.
+    #[test]
+    fn test_pan_stereo_pair() {
+        let pair = [0.3, -0.7];
+        assert_eq!(pan_stereo_pair(0.0, pair), pair);
+        assert_eq!(pan_stereo_pair(-1.0, pair), [0.3 - 0.7, 0.0]);
+        assert_eq!(pan_stereo_pair(1.0, pair), [0.0, -0.7 + 0.3]);
+
+        for n in 1..=20 {
+            let pan = -(n as f32) / 20.0;
+            let [l, r] = pan_stereo_pair(pan, [0.0, 1.0]);
+            assert!((l * l + r * r - 1.0).abs() < 1e-6, "pan {pan}");
+            assert_eq!(pan_stereo_pair(pan, [1.0, 0.0]), [1.0, 0.0]);
+            assert_eq!(pan_stereo_pair(-pan, [1.0, 0.0]), [r, l]);
+        }
+    }
+
+    /// Balance keeps the pair's total power across its range, is unity
+    /// on both sides in the middle, and empties one side at each end.
+    #[test]
+    fn test_balance_gains_keep_total_power() {
+        for n in -20..=20 {
+            let [l, r] = balance_gains(n as f32 / 20.0);
+            assert!((l * l + r * r - 2.0).abs() < 1e-6, "{n}");
+        }
+        assert_eq!(balance_gains(0.0), [1.0, 1.0]);
+        assert_eq!(balance_gains(-1.0), [2.0_f32.sqrt(), 0.0]);
+        assert_eq!(balance_gains(1.0), [0.0, 2.0_f32.sqrt()]);
+        let [l, r] = balance_gains(0.5);
+        assert!(l < 1.0 && r > 1.0);
+    }
+
+    /// Narrowing keeps the mid and scales only the side; a mono pair
+    /// comes through it unchanged and nothing comes out louder.
+    #[test]
+    fn test_narrow_pair_scales_only_the_side() {
+        let [l, r] = narrow_pair(0.5, [1.0, 0.2]);
+        assert!((0.5 * (l + r) - 0.6).abs() < 1e-6);
+        assert!((0.5 * (l - r) - 0.2).abs() < 1e-6);
+        assert_eq!(narrow_pair(0.25, [0.3, 0.3]), [0.3, 0.3]);
+        assert_eq!(narrow_pair(0.0, [1.0, -1.0]), [0.0, 0.0]);
+        for (left, right) in [(1.0, 0.0), (0.7, -0.4), (-0.2, 0.9)] {
+            let [l, r] = narrow_pair(0.3, [left, right]);
+            assert!(l * l + r * r <= left * left + right * right + 1e-6);
+        }
+    }
+
+    /// The start side picks the entry line, reverse mirrors it, and only
+    /// a centered ping-pong moves its first repeat.
+    #[test]
+    fn test_start_sides() {
+        use StartSide::*;
+        use StereoMotion::*;
+        let cases = [
+            (PingPong, Left, [1.0, 0.0], None),
+            (PingPong, Right, [0.0, 1.0], None),
+            (PingPong, Center, [1.0, 0.0], Some(0)),
+            (ReversePingPong, Left, [0.0, 1.0], None),
+            (ReversePingPong, Right, [1.0, 0.0], None),
+            (ReversePingPong, Center, [0.0, 1.0], Some(1)),
+            (Circular, Center, [1.0, 1.0], None),
+        ];
+        for (motion, start, gains, centered) in cases {
+            assert_eq!(
+                motion.input_gains(start),
+                Some(gains),
+                "{motion:?} {start:?}"
+            );
+            assert_eq!(
+                motion.centered_line(start),
+                centered,
+                "{motion:?} {start:?}"
+            );
+        }
+        assert_eq!(Off.input_gains(Center), None);
+    }
+
+    /// The rotor turns by the rotation once per period: at 90°, one
+    /// period in is hard right, two is centre, three is hard left.
+    #[test]
+    fn test_rotor_turns_once_per_period() {
+        let mut rotor = PanRotor::new();
+        rotor.set_rotation_degrees(90.0);
+        let pans: Vec<f32> = (0..=400).map(|_| rotor.next_pan(100.0)).collect();
+        for (n, want) in [(0, 0.0), (100, 1.0), (200, 0.0), (300, -1.0), (400, 0.0)] {
+            assert!((pans[n] - want).abs() < 1e-4, "sample {n}: {}", pans[n]);
+        }
+
+        rotor.reset();
+        assert_eq!(rotor.next_pan(100.0), 0.0);
+    }
+}
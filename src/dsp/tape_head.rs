@@ -0,0 +1,262 @@
+//! # The Tape Read Head
+//!
+//! Glide and Crossfade (see `time_change`) both put the read head where
+//! the delay time says. A tape echo works the other way round: the tape
+//! runs past the playback head at some speed, and the delay is wherever
+//! that leaves it. Slow the tape and the repeats sag in pitch as the
+//! delay stretches; stop it, and the pitch falls away to nothing — the
+//! tape stop. So [`TapeHead`] keeps the head's *speed*, relative to the
+//! tape, and lets the delay follow from it:
+//!
+//! ```text
+//! delay += (1 − speed) each moment     pitch of the repeats × speed
+//!
+//! speed  1.0   playing normally: the delay stands still
+//!        0.0   stopped: the delay grows a millisecond per millisecond
+//!       −1.0   backwards, at normal speed
+//! ```
+//!
+//! ## Scrub
+//!
+//! Scrub (±[`MAX_SCRUB`], shown as ±200%) sets the speed the head heads
+//! for, `1 − scrub`: +100% stops the tape, +200% runs it backwards, and
+//! −100% runs it at twice the speed, an octave up, eating into the
+//! delay. The head has inertia — the speed moves towards where Scrub
+//! sends it over about [`INERTIA_MS`], like a motor slowing a reel — so
+//! automating Scrub bends the pitch rather than stepping it.
+//!
+//! ## Re-locking
+//!
+//! With Scrub back at 0, the head servos back to the delay time: the
+//! further off it is, the faster (or slower) it runs, by up to
+//! [`MAX_BEND`] either way:
+//!
+//! ```text
+//! speed it heads for = 1 + clamp(delay − target, ±RELOCK_MS·MAX_BEND) / RELOCK_MS
+//! ```
+//!
+//! With [`RELOCK_MS`] four times [`INERTIA_MS`] the servo and the
+//! inertia together are critically damped, so the delay settles on its
+//! target as fast as it can without swinging past. A change of delay
+//! time in Tape mode is heard the same way: the head runs to the new
+//! time. Once it's within a hair of the target, and barely moving, the
+//! head locks: the delay is the target, exactly, until either moves.
+//!
+//! ## The Ends of the Tape
+//!
+//! The head can't run past the shortest or longest delay. At either end
+//! it rests against the stop, carried along at the tape's own speed,
+//! until Scrub or the servo takes it off again.
+
+/// The furthest Scrub goes either way: ±200%.
+pub const MAX_SCRUB: f32 = 2.0;
+
+/// How long the head's speed takes to follow where it's sent, in
+/// milliseconds (the time constant).
+pub const INERTIA_MS: f32 = 25.0;
+
+/// How far off, in milliseconds, a bend of the whole speed (×2) would
+/// be asked for while re-locking. Four times [`INERTIA_MS`], for a
+/// critically damped servo.
+pub const RELOCK_MS: f32 = 4.0 * INERTIA_MS;
+
+/// The most the servo bends the speed while re-locking: ×0.5 to ×1.5,
+/// under seven semitones either way.
+pub const MAX_BEND: f32 = 0.5;
+
+/// How close to the target, in milliseconds, and to normal speed the
+/// head must be to lock: a twentieth of a sample at 48 kHz, a hundredth
+/// of a semitone.
+const LOCK_MS: f64 = 0.001;
+const LOCK_SPEED: f64 = 1e-4;
+
+/// A read head driven by its speed. See [the module docs](self).
+///
+/// The head keeps its place in `f64`: near the end of a re-lock it
+/// moves by millionths of a millisecond a sample, less than an `f32`
+/// can add to a delay of a second or more.
+#[derive(Debug, Clone)]
+pub struct TapeHead {
+    /// Where the head is, as a delay in milliseconds.
+    delay_ms: f64,
+
+    /// The head's speed against the tape (1.0 is normal).
+    speed: f64,
+
+    /// The one-pole coefficient for [`INERTIA_MS`].
+    inertia: f64,
+
+    /// How many milliseconds one sample lasts.
+    ms_per_sample: f64,
+
+    /// The shortest and longest delay the head can reach.
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl TapeHead {
+    /// A head locked at `delay_ms`, that can travel `min_ms..=max_ms`.
+    pub fn new(sample_rate: f32, delay_ms: f32, min_ms: f32, max_ms: f32) -> Self {
+        let sample_rate = f64::from(sample_rate);
+        let (min_ms, max_ms) = (f64::from(min_ms), f64::from(max_ms));
+        Self {
+            delay_ms: f64::from(delay_ms).clamp(min_ms, max_ms),
+            speed: 1.0,
+            inertia: 1.0 - (-1000.0 / (f64::from(INERTIA_MS) * sample_rate)).exp(),
+            ms_per_sample: 1000.0 / sample_rate,
+            min_ms,
+            max_ms,
+        }
+    }
+
+    /// Move the head on by one sample, towards `target_ms` or as
+    /// `scrub` drives it, and return its delay in milliseconds.
+    #[inline]
+    pub fn next(&mut self, target_ms: f32, scrub: f32) -> f32 {
+        if self.is_locked(target_ms, scrub) {
+            return target_ms;
+        }
+        let target_ms = f64::from(target_ms);
+        let bend = f64::from(MAX_BEND);
+        let wanted = if scrub == 0.0 {
+            1.0 + ((self.delay_ms - target_ms) / f64::from(RELOCK_MS)).clamp(-bend, bend)
+        } else {
+            1.0 - f64::from(scrub)
+        };
+        self.speed += (wanted - self.speed) * self.inertia;
+        let delay_ms = self.delay_ms + (1.0 - self.speed) * self.ms_per_sample;
+        self.delay_ms = delay_ms.clamp(self.min_ms, self.max_ms);
+        if self.delay_ms != delay_ms {
+            // Against the stop, riding with the tape.
+            self.speed = 1.0;
+        }
+
+        if scrub == 0.0
+            && (self.delay_ms - target_ms).abs() < LOCK_MS
+            && (self.speed - 1.0).abs() < LOCK_SPEED
+        {
+            self.delay_ms = target_ms;
+            self.speed = 1.0;
+        }
+        self.delay_ms as f32
+    }
+
+    /// Whether the head sits exactly on `target_ms` at normal speed,
+    /// with nothing scrubbing it: stepping it would change nothing.
+    pub fn is_locked(&self, target_ms: f32, scrub: f32) -> bool {
+        scrub == 0.0 && self.speed == 1.0 && self.delay_ms == f64::from(target_ms)
+    }
+
+    /// Where the head is, as a delay in milliseconds.
+    pub fn delay_ms(&self) -> f32 {
+        self.delay_ms as f32
+    }
+
+    /// The head's speed against the tape: the pitch ratio of what it
+    /// reads.
+    pub fn speed(&self) -> f32 {
+        self.speed as f32
+    }
+
+    /// Lock the head at `delay_ms`, at normal speed.
+    pub fn reset(&mut self, delay_ms: f32) {
+        self.delay_ms = f64::from(delay_ms).clamp(self.min_ms, self.max_ms);
+        self.speed = 1.0;
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn samples(ms: f32) -> usize {
+        (ms / 1000.0 * SAMPLE_RATE) as usize
+    }
+
+    fn test_head() -> TapeHead {
+        TapeHead::new(SAMPLE_RATE, 500.0, 100.0, 2000.0)
+    }
+
+    /// Scrub swept from 0 to +100% and held stops the tape: the speed
+    /// (the pitch of what's read) only ever falls, to nothing. Back at
+    /// 0, the head re-locks to the target within two seconds, never
+    /// swinging past it.
+    #[test]
+    fn test_scrub_stops_the_tape_and_relocks() {
+        let mut head = test_head();
+        let ramp = samples(200.0);
+        let mut last_speed = head.speed();
+        for n in 0..ramp + samples(300.0) {
+            let scrub = (n as f32 / ramp as f32).min(1.0);
+            head.next(500.0, scrub);
+            assert!(head.speed() <= last_speed, "sample {n}: pitch rose");
+            last_speed = head.speed();
+        }
+        assert!(head.speed() < 0.01, "{}", head.speed());
+        let stretched = head.delay_ms();
+        assert!(stretched > 700.0, "{stretched}");
+
+        let mut relocked = None;
+        for n in 0..samples(3000.0) {
+            let delay_ms = head.next(500.0, 0.0);
+            assert!(delay_ms >= 500.0, "swung past to {delay_ms}");
+            if relocked.is_none() && head.is_locked(500.0, 0.0) {
+                relocked = Some(n);
+            }
+        }
+        let relocked = relocked.expect("never re-locked");
+        assert!(relocked < samples(2000.0), "re-locked after {relocked}");
+        assert_eq!(head.delay_ms(), 500.0);
+        assert_eq!(head.speed(), 1.0);
+    }
+
+    /// Scrub at +200% runs the tape backwards at normal speed, until the
+    /// head rests against the longest delay; −100% runs it at double
+    /// speed down to the shortest.
+    #[test]
+    fn test_scrub_runs_to_the_ends() {
+        let mut head = test_head();
+        for _ in 0..samples(200.0) {
+            head.next(500.0, 2.0);
+        }
+        assert!((head.speed() + 1.0).abs() < 1e-3, "{}", head.speed());
+        for _ in 0..samples(1000.0) {
+            assert!(head.next(500.0, 2.0) <= 2000.0);
+        }
+        assert_eq!(head.delay_ms(), 2000.0);
+
+        for _ in 0..samples(200.0) {
+            head.next(500.0, -1.0);
+        }
+        assert!((head.speed() - 2.0).abs() < 1e-3, "{}", head.speed());
+        for _ in 0..samples(2000.0) {
+            assert!(head.next(500.0, -1.0) >= 100.0);
+        }
+        assert_eq!(head.delay_ms(), 100.0);
+    }
+
+    /// Locked, the head reads the target exactly. A new target is run
+    /// to, bending the pitch by no more than [`MAX_BEND`], and locked
+    /// onto without overshoot.
+    #[test]
+    fn test_runs_to_a_new_target() {
+        let mut head = test_head();
+        assert_eq!(head.next(500.0, 0.0), 500.0);
+        assert!(head.is_locked(500.0, 0.0));
+
+        let mut last = head.delay_ms();
+        for _ in 0..samples(2000.0) {
+            let delay_ms = head.next(800.0, 0.0);
+            assert!((1.0 - MAX_BEND..=1.0).contains(&head.speed()));
+            assert!((last..=800.0).contains(&delay_ms), "{last} → {delay_ms}");
+            last = delay_ms;
+        }
+        assert!(head.is_locked(800.0, 0.0));
+    }
+}
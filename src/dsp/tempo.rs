@@ -0,0 +1,168 @@
+//! # Tempo and Note Divisions
+//!
+//! A *synced* delay sets its time from the song's tempo instead of a
+//! number of milliseconds: "one echo every dotted eighth" stays locked
+//! to the groove even when the tempo changes.
+//!
+//! ## Beats to Milliseconds
+//!
+//! Tempo is given in beats (quarter notes) per minute, so one beat lasts
+//! `60000 / bpm` milliseconds. A note division is just a number of beats:
+//!
+//! ```text
+//! delay_ms = beats * 60000 / bpm
+//!
+//! 120 BPM, quarter note   (1 beat):    500 ms
+//! 120 BPM, dotted eighth  (0.75 beat): 375 ms
+//! 120 BPM, eighth triplet (1/3 beat):  166.7 ms
+//! ```
+//!
+//! ## Finding the Next Beat
+//!
+//! Hosts report the playhead as a position in beats (e.g. `6.3` means
+//! 30% of the way through the seventh beat). The distance to the next
+//! whole beat, converted to samples, tells us exactly where in the
+//! audio the next beat boundary falls — even several buffers ahead.
+//!
+//! ## The Nearest Division
+//!
+//! Going the other way — which division is closest to a time in
+//! milliseconds — compares *ratios*, not differences, because that's
+//! how time intervals are heard: 300 ms is as far from 200 ms as 600 ms
+//! is from 400 ms. So the nearest division is the one with the smallest
+//! `|log(division_ms / delay_ms)|`.
+
+/// The note lengths a synced delay can lock to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteDivision {
+    /// 1/1: four beats.
+    Whole,
+    /// 1/2: two beats.
+    Half,
+    /// Dotted 1/4: a beat and a half.
+    DottedQuarter,
+    /// 1/4: one beat.
+    #[default]
+    Quarter,
+    /// 1/4 triplet: three in the space of two beats.
+    QuarterTriplet,
+    /// Dotted 1/8: three quarters of a beat — the classic
+    /// "galloping" delay.
+    DottedEighth,
+    /// 1/8: half a beat.
+    Eighth,
+    /// 1/8 triplet: three per beat.
+    EighthTriplet,
+    /// 1/16: a quarter of a beat.
+    Sixteenth,
+}
+
+impl NoteDivision {
+    /// Every division, longest first.
+    pub const ALL: [Self; 9] = [
+        Self::Whole,
+        Self::Half,
+        Self::DottedQuarter,
+        Self::Quarter,
+        Self::QuarterTriplet,
+        Self::DottedEighth,
+        Self::Eighth,
+        Self::EighthTriplet,
+        Self::Sixteenth,
+    ];
+
+    /// The division whose length at `tempo_bpm` is nearest `delay_ms`,
+    /// by ratio (see [the module docs](self#the-nearest-division)).
+    pub fn nearest(delay_ms: f32, tempo_bpm: f64) -> Self {
+        let distance =
+            |division: &Self| (division.to_ms(tempo_bpm) / f64::from(delay_ms)).ln().abs();
+        Self::ALL
+            .into_iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or_default()
+    }
+
+    /// The length of this division in beats (quarter notes).
+    pub fn beats(self) -> f64 {
+        match self {
+            Self::Whole => 4.0,
+            Self::Half => 2.0,
+            Self::DottedQuarter => 1.5,
+            Self::Quarter => 1.0,
+            Self::QuarterTriplet => 2.0 / 3.0,
+            Self::DottedEighth => 0.75,
+            Self::Eighth => 0.5,
+            Self::EighthTriplet => 1.0 / 3.0,
+            Self::Sixteenth => 0.25,
+        }
+    }
+
+    /// The length of this division in milliseconds at `tempo_bpm`.
+    pub fn to_ms(self, tempo_bpm: f64) -> f64 {
+        self.beats() * 60_000.0 / tempo_bpm
+    }
+}
+
+/// How many samples from `pos_beats` until the next whole beat, at
+/// `tempo_bpm` and `sample_rate`. A position already on a beat is 0
+/// samples away.
+pub fn samples_to_next_beat(pos_beats: f64, tempo_bpm: f64, sample_rate: f32) -> f64 {
+    // Hosts report positions as doubles computed from sample counts, so
+    // a "whole" beat may come through as 7.999999999. Treat anything
+    // within a millionth of a beat as on the beat.
+    const ON_BEAT_EPSILON: f64 = 1e-6;
+
+    let into_beat = pos_beats - pos_beats.floor();
+    let beats_left = if into_beat < ON_BEAT_EPSILON || 1.0 - into_beat < ON_BEAT_EPSILON {
+        0.0
+    } else {
+        1.0 - into_beat
+    };
+    beats_left * 60.0 / tempo_bpm * f64::from(sample_rate)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Division lengths at 120 BPM, where one beat is 500ms.
+    #[test]
+    fn test_division_lengths() {
+        assert_eq!(NoteDivision::Quarter.to_ms(120.0), 500.0);
+        assert_eq!(NoteDivision::DottedEighth.to_ms(120.0), 375.0);
+        assert_eq!(NoteDivision::Whole.to_ms(120.0), 2000.0);
+        assert!((NoteDivision::EighthTriplet.to_ms(120.0) - 166.667).abs() < 1e-3);
+    }
+
+    /// The nearest division goes by ratio: 430 ms at 120 BPM is nearer
+    /// a dotted eighth (375 ms, ×1.15) than a quarter (500 ms, ×1.16).
+    #[test]
+    fn test_nearest_division() {
+        assert_eq!(NoteDivision::nearest(500.0, 120.0), NoteDivision::Quarter);
+        assert_eq!(
+            NoteDivision::nearest(430.0, 120.0),
+            NoteDivision::DottedEighth
+        );
+        assert_eq!(NoteDivision::nearest(440.0, 120.0), NoteDivision::Quarter);
+        assert_eq!(NoteDivision::nearest(2000.0, 60.0), NoteDivision::Half);
+        assert_eq!(NoteDivision::nearest(100.0, 60.0), NoteDivision::Sixteenth);
+        for division in NoteDivision::ALL {
+            let ms = division.to_ms(97.0) as f32;
+            assert_eq!(NoteDivision::nearest(ms, 97.0), division);
+        }
+    }
+
+    /// The next beat boundary, in samples, from a few positions.
+    #[test]
+    fn test_samples_to_next_beat() {
+        // 120 BPM at 48 kHz: 24000 samples per beat.
+        assert_eq!(samples_to_next_beat(1.25, 120.0, 48000.0), 18000.0);
+        assert_eq!(samples_to_next_beat(3.0, 120.0, 48000.0), 0.0);
+        assert_eq!(samples_to_next_beat(3.999_999_9, 120.0, 48000.0), 0.0);
+        assert_eq!(samples_to_next_beat(-0.5, 120.0, 48000.0), 12000.0);
+    }
+}
@@ -0,0 +1,349 @@
+//! # Changing the Delay Time: Glide or Crossfade
+//!
+//! A delay time is one number, but players reach for it with two
+//! different gestures: picking a musical length (a quarter note, twice
+//! as long, a dotted feel) and nudging it by a few milliseconds so the
+//! echoes sit just ahead of or behind the beat. So the time is built
+//! from three parts:
+//!
+//! ```text
+//! effective = base × multiplier + fine
+//!
+//! base 500 ms, ×1.5, fine −8 ms  →  742 ms
+//! ```
+//!
+//! `base` is the Delay Time knob (or the synced division), the
+//! [`TimeMultiplier`] scales it in musical steps, and `fine` adds a
+//! small offset on top. The sum is clamped to the delay's range only
+//! once it's complete, so a fine nudge still works on a base that the
+//! multiplier has pushed past the end.
+//!
+//! ## Two Ways to Move
+//!
+//! Moving the delay time moves the read head through the buffer. How it
+//! gets from the old position to the new one decides what's heard in
+//! between, and there are two classic answers ([`TimeChange`]), and a
+//! third that models the tape itself.
+//!
+//! **Glide** ramps the read head over a few tens of milliseconds, like
+//! turning the speed knob on a tape echo. While it moves, the head runs
+//! through the recording faster or slower than it was written, so the
+//! repeats bend in pitch:
+//!
+//! ```text
+//! pitch ratio = 1 − (change in delay per sample)
+//!
+//! 500 →  510 ms over 50 ms:  0.8×, nearly 4 semitones flat
+//! 500 → 1000 ms over 50 ms:  −9×, backwards and far too fast
+//! ```
+//!
+//! Small moves make a gentle swoop, which is half the fun of a tape
+//! delay. Big jumps — like flipping ×1 to ×2 — make a loud chirp.
+//!
+//! **Crossfade** jumps instead, hiding the jump behind a second read
+//! head. For a moment the delay reads at both the old and the new time
+//! and fades from one to the other with an equal-power fade (see
+//! `crossfade`):
+//!
+//! ```text
+//! delayed = read(old) × fade_out  +  read(new) × fade_in
+//! ```
+//!
+//! Neither head ever moves, so nothing is repitched: the old repeats
+//! fade out while the new ones fade in, the way a digital delay changes
+//! time. The fade runs inside the loop, so what goes round again is the
+//! blend too.
+//!
+//! **Tape** turns it round: the head has a speed and inertia, and the
+//! delay is wherever it's run to (see `tape_head`). A new time is run
+//! to by a servo, bending the pitch up to seven semitones on the way,
+//! and Scrub slows, stops or reverses the head outright — a tape stop.
+//!
+//! ## Snapping the Knob
+//!
+//! A knob turned by hand, or automation drawn with a mouse, lands on
+//! 333.7 ms as easily as on 330. [`TimeSnap`] rounds the base time (the
+//! unsynced Delay Time knob) to a grid before anything else sees it:
+//! every millisecond, every 5 or 10, or the nearest note length at one
+//! of the [`COMMON_TEMPOS_BPM`]. Fine stays off the grid, so the echoes
+//! can still be nudged.
+//!
+//! ## Multiply and Divide
+//!
+//! A looper's Multiply and Divide buttons change the length while
+//! they're held and give it back on release. [`TimeTriggers`] does the
+//! same for the delay: Double Time doubles the base time, Half Time
+//! halves it, and a synced division moves one step longer or shorter
+//! instead, so the echoes stay on a note length. Both held at once, the
+//! one pressed last wins, and letting it go hands back to the other:
+//!
+//! ```text
+//! held:    Double ──► Double + Half ──► Double ──► none
+//! heard:     ×2          ×0.5             ×2        ×1
+//! ```
+//!
+//! The engine always crossfades into and out of a gesture: it's a jump
+//! by design, and a glide would bend every repeat by an octave. The
+//! gesture only scales the base, so automation of the Delay Time knob
+//! carries on underneath it and is where the time lands on release.
+
+use super::tempo::NoteDivision;
+
+/// How the delay moves to a new time. See [the module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeChange {
+    /// Ramp the read head, bending the pitch of the repeats.
+    #[default]
+    Glide,
+    /// Fade between read heads at the old and new times, without any
+    /// change in pitch.
+    Crossfade,
+    /// Run a read head with inertia to the new time, and let Scrub
+    /// drive it (see `tape_head`).
+    Tape,
+}
+
+/// The musical steps the delay's base time can be scaled by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeMultiplier {
+    /// ×0.5: half the time, twice the rate.
+    Half,
+    /// ×1: the base time as it is.
+    #[default]
+    Single,
+    /// ×1.5: the dotted version of the base time.
+    Dotted,
+    /// ×2: twice the time.
+    Double,
+}
+
+impl TimeMultiplier {
+    /// The factor the base time is multiplied by.
+    pub fn factor(self) -> f32 {
+        match self {
+            Self::Half => 0.5,
+            Self::Single => 1.0,
+            Self::Dotted => 1.5,
+            Self::Double => 2.0,
+        }
+    }
+}
+
+/// Which momentary time trigger is in charge (see [the module
+/// docs](self#multiply-and-divide)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeGesture {
+    /// Neither: the base time as it is.
+    #[default]
+    None,
+    /// Double Time: twice the base time, or the next longer division.
+    Double,
+    /// Half Time: half the base time, or the next shorter division.
+    Half,
+}
+
+impl TimeGesture {
+    /// The factor an unsynced base time is multiplied by.
+    pub fn factor(self) -> f32 {
+        match self {
+            Self::None => 1.0,
+            Self::Double => 2.0,
+            Self::Half => 0.5,
+        }
+    }
+
+    /// `division` one step along [`NoteDivision::ALL`]: longer for
+    /// [`Double`](Self::Double), shorter for [`Half`](Self::Half),
+    /// staying put at either end.
+    pub fn shift(self, division: NoteDivision) -> NoteDivision {
+        let all = NoteDivision::ALL;
+        let idx = all.iter().position(|&d| d == division).unwrap_or(0);
+        match self {
+            Self::None => division,
+            Self::Double => all[idx.saturating_sub(1)],
+            Self::Half => all[(idx + 1).min(all.len() - 1)],
+        }
+    }
+}
+
+/// The Double Time and Half Time switches, and which of them is in
+/// charge when both are held.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeTriggers {
+    double: bool,
+    half: bool,
+    active: TimeGesture,
+}
+
+impl TimeTriggers {
+    /// Take the switches' new positions, and return the gesture in
+    /// charge from now on. Pressed at the same moment, Double wins.
+    pub fn update(&mut self, double: bool, half: bool) -> TimeGesture {
+        let pressed_double = double && !self.double;
+        let pressed_half = half && !self.half;
+        self.active = match (double, half) {
+            (false, false) => TimeGesture::None,
+            (true, false) => TimeGesture::Double,
+            (false, true) => TimeGesture::Half,
+            (true, true) if pressed_double => TimeGesture::Double,
+            (true, true) if pressed_half => TimeGesture::Half,
+            (true, true) => self.active,
+        };
+        self.double = double;
+        self.half = half;
+        self.active
+    }
+
+    /// The gesture in charge.
+    pub fn active(&self) -> TimeGesture {
+        self.active
+    }
+}
+
+/// The tempos the musical snap grid is built from: a note length at any
+/// of them is a grid point.
+pub const COMMON_TEMPOS_BPM: [f64; 11] = [
+    60.0, 70.0, 80.0, 90.0, 100.0, 110.0, 120.0, 128.0, 140.0, 160.0, 174.0,
+];
+
+/// The grid the unsynced delay time snaps to. See [the module
+/// docs](self#snapping-the-knob).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeSnap {
+    /// No grid: the time as it's set.
+    #[default]
+    Off,
+    /// Whole milliseconds.
+    Ms1,
+    /// Every 5 ms.
+    Ms5,
+    /// Every 10 ms.
+    Ms10,
+    /// The nearest length of a [`NoteDivision`] at one of the
+    /// [`COMMON_TEMPOS_BPM`], within `min_ms..=max_ms`.
+    Musical,
+}
+
+impl TimeSnap {
+    /// `delay_ms` on this grid. The musical grid only offers points
+    /// within `min_ms..=max_ms`, the knob's range, so a time there stays
+    /// there; the millisecond grids keep any range whose ends are on the
+    /// grid.
+    pub fn apply(self, delay_ms: f32, min_ms: f32, max_ms: f32) -> f32 {
+        let step = match self {
+            Self::Off => return delay_ms,
+            Self::Ms1 => 1.0,
+            Self::Ms5 => 5.0,
+            Self::Ms10 => 10.0,
+            Self::Musical => return nearest_note_ms(delay_ms, min_ms, max_ms),
+        };
+        (delay_ms / step).round() * step
+    }
+}
+
+/// The musical grid point nearest `delay_ms` (see [`TimeSnap::Musical`]),
+/// or `delay_ms` itself if the range holds none.
+fn nearest_note_ms(delay_ms: f32, min_ms: f32, max_ms: f32) -> f32 {
+    COMMON_TEMPOS_BPM
+        .iter()
+        .flat_map(|&bpm| NoteDivision::ALL.map(|division| division.to_ms(bpm) as f32))
+        .filter(|ms| (min_ms..=max_ms).contains(ms))
+        .min_by(|a, b| (a - delay_ms).abs().total_cmp(&(b - delay_ms).abs()))
+        .unwrap_or(delay_ms)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// On a synced base, each step lands on another note division.
+    #[test]
+    fn test_multipliers_land_on_divisions() {
+        let quarter = NoteDivision::Quarter.beats() as f32;
+        let cases = [
+            (TimeMultiplier::Half, NoteDivision::Eighth),
+            (TimeMultiplier::Single, NoteDivision::Quarter),
+            (TimeMultiplier::Dotted, NoteDivision::DottedQuarter),
+            (TimeMultiplier::Double, NoteDivision::Half),
+        ];
+        for (multiplier, division) in cases {
+            assert_eq!(
+                quarter * multiplier.factor(),
+                division.beats() as f32,
+                "{multiplier:?}"
+            );
+        }
+    }
+
+    /// The last trigger pressed wins; letting it go hands back to the
+    /// one still held.
+    #[test]
+    fn test_last_trigger_pressed_wins() {
+        use TimeGesture::*;
+        let mut triggers = TimeTriggers::default();
+        let steps = [
+            ((true, false), Double),
+            ((true, true), Half),
+            ((true, true), Half),
+            ((true, false), Double),
+            ((false, false), None),
+            ((false, true), Half),
+            ((true, true), Double),
+            ((false, true), Half),
+            ((true, true), Double),
+            ((false, false), None),
+            ((true, true), Double),
+        ];
+        for (i, ((double, half), want)) in steps.into_iter().enumerate() {
+            assert_eq!(triggers.update(double, half), want, "step {i}");
+            assert_eq!(triggers.active(), want);
+        }
+    }
+
+    /// Synced, the gestures move one division along, and stop at the
+    /// ends.
+    #[test]
+    fn test_gestures_shift_divisions() {
+        use NoteDivision::*;
+        assert_eq!(TimeGesture::Double.shift(Quarter), DottedQuarter);
+        assert_eq!(TimeGesture::Half.shift(Quarter), QuarterTriplet);
+        assert_eq!(TimeGesture::None.shift(Quarter), Quarter);
+        assert_eq!(TimeGesture::Double.shift(Whole), Whole);
+        assert_eq!(TimeGesture::Half.shift(Sixteenth), Sixteenth);
+    }
+
+    /// The millisecond grids round to their nearest point: 333 ms on
+    /// the 10 ms grid is exactly 330.
+    #[test]
+    fn test_millisecond_grids_round() {
+        let cases = [
+            (TimeSnap::Off, 333.3),
+            (TimeSnap::Ms1, 333.0),
+            (TimeSnap::Ms5, 335.0),
+            (TimeSnap::Ms10, 330.0),
+        ];
+        for (snap, want) in cases {
+            assert_eq!(snap.apply(333.3, 100.0, 2000.0), want, "{snap:?}");
+        }
+        assert_eq!(TimeSnap::Ms10.apply(2000.0, 100.0, 2000.0), 2000.0);
+        assert_eq!(TimeSnap::Ms10.apply(100.0, 100.0, 2000.0), 100.0);
+    }
+
+    /// The musical grid lands on note lengths at common tempos — a
+    /// quarter at 120 BPM, a dotted eighth at 100 — and never leaves
+    /// the range.
+    #[test]
+    fn test_musical_grid_lands_on_notes() {
+        assert_eq!(TimeSnap::Musical.apply(497.0, 100.0, 2000.0), 500.0);
+        assert_eq!(TimeSnap::Musical.apply(452.0, 100.0, 2000.0), 450.0);
+        for delay_ms in [100.0, 101.0, 1990.0, 2000.0] {
+            let snapped = TimeSnap::Musical.apply(delay_ms, 100.0, 2000.0);
+            assert!((100.0..=2000.0).contains(&snapped), "{delay_ms}: {snapped}");
+        }
+        assert_eq!(TimeSnap::Musical.apply(50.0, 40.0, 60.0), 50.0);
+    }
+}
@@ -0,0 +1,221 @@
+//! # Feedback Watchdog
+//!
+//! At 95% feedback a loop rings for a very long time: each repeat loses
+//! less than half a decibel, so a loud phrase takes the best part of a
+//! minute to die away, and anything still playing into it keeps it
+//! topped up. In a studio someone turns the feedback down. In an
+//! unattended installation — a kiosk, a gallery piece — nobody is there
+//! to do it.
+//!
+//! The watchdog does it for them. If the wet signal stays loud for too
+//! long, it pulls the feedback down to [`SAFE_FEEDBACK`] until the loop
+//! has died away, then hands control back to the knob.
+//!
+//! ## Loud for Too Long
+//!
+//! An RMS [`EnvelopeFollower`] measures the wet level. Its slow release
+//! rides over the gaps between repeats, so a ringing tail reads as one
+//! long loud stretch rather than a string of short ones. A counter then
+//! tallies how many samples in a row the level has stayed above
+//! [`TRIP_DB`]; once that reaches the hold time, the watchdog *trips*.
+//!
+//! ## Hysteresis
+//!
+//! Releasing as soon as the level dips under the trip threshold would
+//! hand the loop straight back to 95% feedback while it's still fairly
+//! loud, and it would trip again a moment later. So the watchdog only
+//! releases once the level has fallen well below, to [`RELEASE_DB`]:
+//!
+//! ```text
+//! level ──────╮ loud for hold time
+//!  −20 dB ····│·················╲·······  trip: feedback → 0.5
+//!             │                  ╲
+//!  −40 dB ····│···················╲·····  release: back to the knob
+//! ```
+//!
+//! The watchdog only reports when it trips and releases. Moving the
+//! feedback is up to its owner, which ramps it like any other change.
+
+use super::envelope::{DetectorMode, EnvelopeFollower};
+
+/// The wet level, in dBFS, that counts as loud.
+pub const TRIP_DB: f32 = -20.0;
+
+/// The wet level, in dBFS, the loop has to fall below before the
+/// watchdog lets go.
+pub const RELEASE_DB: f32 = -40.0;
+
+/// The feedback the watchdog pulls the loop down to while tripped. At
+/// 0.5 each repeat is 6 dB quieter than the last, so a loud tail is gone
+/// in well under a second at typical delay times.
+pub const SAFE_FEEDBACK: f32 = 0.5;
+
+/// How fast the level follower responds, in milliseconds. The release
+/// is long enough to span the gaps between repeats.
+const FOLLOWER_ATTACK_MS: f32 = 10.0;
+const FOLLOWER_RELEASE_MS: f32 = 300.0;
+
+/// What changed on a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    /// The wet signal has been loud for the hold time: pull the feedback
+    /// down.
+    Tripped,
+    /// The loop has died away: hand the feedback back.
+    Released,
+}
+
+/// Watches the wet level and trips when it stays loud for too long.
+pub struct FeedbackWatchdog {
+    follower: EnvelopeFollower,
+    sample_rate: f32,
+
+    /// How many loud samples in a row trip the watchdog, or `None`
+    /// while it's switched off.
+    hold_samples: Option<usize>,
+
+    /// Loud samples in a row so far.
+    loud_for: usize,
+
+    tripped: bool,
+}
+
+impl FeedbackWatchdog {
+    /// Create a watchdog, switched off.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut follower = EnvelopeFollower::new(sample_rate);
+        follower.set_mode(DetectorMode::Rms);
+        follower.set_attack_ms(FOLLOWER_ATTACK_MS);
+        follower.set_release_ms(FOLLOWER_RELEASE_MS);
+        Self {
+            follower,
+            sample_rate,
+            hold_samples: None,
+            loud_for: 0,
+            tripped: false,
+        }
+    }
+
+    /// Switch the watchdog on with a hold time in seconds, or off with
+    /// `None`. Switching it off releases it at once.
+    pub fn set_hold_seconds(&mut self, seconds: Option<f32>) {
+        self.hold_samples = seconds.map(|s| (s.max(0.0) * self.sample_rate) as usize);
+        if self.hold_samples.is_none() {
+            self.reset();
+        }
+    }
+
+    /// Whether the watchdog is switched on.
+    pub fn is_enabled(&self) -> bool {
+        self.hold_samples.is_some()
+    }
+
+    /// Whether the watchdog is holding the feedback down.
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Measure one sample of the wet level (the loudest channel's, say),
+    /// and report whether the watchdog tripped or released on it.
+    #[inline]
+    pub fn process(&mut self, wet: f32) -> Option<WatchdogEvent> {
+        let hold_samples = self.hold_samples?;
+        let level = self.follower.process(wet);
+        let level_db = 20.0 * level.max(1e-9).log10();
+
+        if self.tripped {
+            if level_db < RELEASE_DB {
+                self.tripped = false;
+                self.loud_for = 0;
+                return Some(WatchdogEvent::Released);
+            }
+            return None;
+        }
+
+        if level_db <= TRIP_DB {
+            self.loud_for = 0;
+            return None;
+        }
+        self.loud_for += 1;
+        if self.loud_for >= hold_samples {
+            self.tripped = true;
+            return Some(WatchdogEvent::Tripped);
+        }
+        None
+    }
+
+    /// Forget the level and let go, keeping the hold time.
+    pub fn reset(&mut self) {
+        self.follower.reset();
+        self.loud_for = 0;
+        self.tripped = false;
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Feed `seconds` of a full-scale square wave (RMS 1.0) at `gain`,
+    /// collecting the events with the sample they happened on.
+    fn run(
+        watchdog: &mut FeedbackWatchdog,
+        seconds: f32,
+        gain: f32,
+    ) -> Vec<(usize, WatchdogEvent)> {
+        (0..(seconds * SAMPLE_RATE) as usize)
+            .filter_map(|n| {
+                let sample = if n % 100 < 50 { gain } else { -gain };
+                watchdog.process(sample).map(|event| (n, event))
+            })
+            .collect()
+    }
+
+    /// A loud signal trips the watchdog after the hold time (plus the
+    /// follower's attack); it releases only once the level falls below
+    /// the release threshold, not merely below the trip threshold.
+    #[test]
+    fn test_trips_after_hold_and_releases_with_hysteresis() {
+        let mut watchdog = FeedbackWatchdog::new(SAMPLE_RATE);
+        watchdog.set_hold_seconds(Some(1.0));
+
+        let events = run(&mut watchdog, 1.5, 0.5);
+        assert_eq!(events.len(), 1);
+        let (at, event) = events[0];
+        assert_eq!(event, WatchdogEvent::Tripped);
+        assert!((48_000..49_000).contains(&at), "tripped at {at}");
+
+        // −30 dB is below the trip threshold but above the release.
+        assert!(run(&mut watchdog, 2.0, 0.0316).is_empty());
+        assert!(watchdog.is_tripped());
+
+        let events = run(&mut watchdog, 2.0, 0.001);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].1, WatchdogEvent::Released);
+        assert!(!watchdog.is_tripped());
+    }
+
+    /// A quiet spell restarts the count; switched off, the watchdog
+    /// never trips and lets go of a trip in progress.
+    #[test]
+    fn test_quiet_spell_and_off() {
+        let mut watchdog = FeedbackWatchdog::new(SAMPLE_RATE);
+        watchdog.set_hold_seconds(Some(1.0));
+        // The follower's release counts as loud for a while too, so
+        // these stay well short of the hold time.
+        assert!(run(&mut watchdog, 0.4, 0.5).is_empty());
+        assert!(run(&mut watchdog, 2.0, 0.001).is_empty());
+        assert!(run(&mut watchdog, 0.4, 0.5).is_empty());
+
+        assert_eq!(run(&mut watchdog, 1.0, 0.5).len(), 1);
+        watchdog.set_hold_seconds(None);
+        assert!(!watchdog.is_tripped());
+        assert!(run(&mut watchdog, 5.0, 0.5).is_empty());
+    }
+}
@@ -0,0 +1,11064 @@
+//! # The Delay Engine
+//!
+//! Everything that makes sound lives here, in [`LovelessDelayEngine`]:
+//! the delay lines, feedback filters, output filters and limiters, parameter
+//! smoothing, and tail detection. It knows nothing about plugin hosts —
+//! you hand it plain `f32` slices and a [`DelayParams`] struct.
+//!
+//! The nih-plug `Plugin` in `lib.rs` is a thin adapter around it: each
+//! `process()` call copies the current parameter values into a
+//! `DelayParams`, passes the buffer's channel slices through, and turns
+//! the engine's tail estimate into a `ProcessStatus`.
+//!
+//! Keeping the DSP host-free means it can be:
+//!
+//! - **Reused** in ordinary Rust programs (batch rendering, offline
+//!   effects pipelines) — see the `examples/` directory.
+//! - **Tested** directly, without constructing framework types like
+//!   `Buffer` or `ProcessContext`.
+//!
+//! ```
+//! use loveless_delay_v1::{DelayParams, LovelessDelayEngine};
+//!
+//! let mut engine = LovelessDelayEngine::new(48000.0, 1);
+//! engine.set_params(&DelayParams {
+//!     delay_ms: 250.0,
+//!     feedback: 0.5,
+//!     ..DelayParams::default()
+//! });
+//!
+//! let mut samples = vec![0.0; 1024];
+//! samples[0] = 1.0;
+//! engine.process(&mut [&mut samples]);
+//! ```
+//!
+//! ## Control Rate
+//!
+//! While parameters are moving, the engine runs one sample at a time,
+//! and some of what it works out per sample is expensive: a filter
+//! coefficient costs an `exp()`, loop damping a `log10()` and an
+//! `exp2()`, per channel. None of it changes fast enough to need
+//! recomputing 48,000 times a second. So those updates run on a
+//! *control tick*, every [`control_interval()`](LovelessDelayEngine::control_interval)
+//! samples — at most a millisecond apart — and in between the filters
+//! glide linearly to the values worked out at the tick:
+//!
+//! ```text
+//! sample   0    1    2   …   31   32   33   …
+//! tick     ●                      ●                 exp(), log10(): new targets
+//! filter   ╱────────────────────╱─────────────      one add per sample
+//! ```
+//!
+//! The per-sample loop is left with the smoothers' steps, the delay
+//! lines' reads and writes, and multiplies. The tick clock counts every
+//! sample, whichever path processes it, so ticks land on the same
+//! samples however the host splits its buffers. A glide can run on into
+//! the next block, and the engine stays on the per-sample path until it
+//! lands, so the block-rate idle path picks up exactly where the
+//! per-sample path leaves off.
+//!
+//! When the filters are all that's moving — a cutoff sweep, a damping
+//! or compensation change, or just loop damping following the repeats
+//! down — nothing else needs stepping per sample. Channel-major blocks
+//! (see [Processing Order](#processing-order)) then read every other
+//! value once, as the idle path does, and step only what the ticks
+//! read.
+//!
+//! In *economy* (see [`set_economy()`](LovelessDelayEngine::set_economy)),
+//! ticks come [`ECONOMY_CONTROL_FACTOR`] times further apart: the glides
+//! are longer and coarser, and the `exp()`s that much rarer. It's the
+//! one saving the engine can make without changing what it plays — the
+//! reads are linear already, and nothing is oversampled.
+//!
+//! ## Processing Order
+//!
+//! The per-sample path could go one sample at a time across every
+//! channel, but that switches channels on every sample. Each switch
+//! swaps in another delay line and another set of filters and limiter
+//! state, and at a 2-second delay a stereo pair's lines alone are
+//! over 750 KB, more than most L2 caches hold. So while nothing couples the
+//! channels, the engine goes channel by channel instead, in stretches
+//! of [`SHARED_BLOCK_LEN`] samples, in two passes:
+//!
+//! ```text
+//! pass 1   shared     sample 0 1 2 … 63     smoothers, taps, key gate
+//! pass 2   left              0 1 2 … 63     one channel at a time,
+//!          right             0 1 2 … 63     reading pass 1's table
+//! ```
+//!
+//! The first pass steps the smoothers once per sample, as before, and
+//! keeps what every channel needs in a small table. The second runs
+//! each channel through its stretch without leaving it. Every channel
+//! does exactly the same arithmetic as before, in the same order, so the
+//! output is identical to the bit — only the memory traffic changes.
+//!
+//! Some blocks keep the sample-by-sample order: anything that couples
+//! the channels (mono-safe linking, stereo motion, the feedback matrix,
+//! spread, Alternate, the dry pan, the channel swap) needs both read
+//! before either is written; the watchdog listens to every channel's
+//! wet level before setting the next sample's feedback, and phase
+//! alignment to every channel's wet signal before its next control
+//! tick; a frozen loop takes a branch of its own; and a panic clear
+//! wipes every line a little on each sample.
+//! [`LovelessDelayEngine::set_processing_order()`] puts
+//! every block back in sample-by-sample order, to compare against.
+//!
+//! ## Shared Lines
+//!
+//! A mono source on a stereo track arrives as two identical channels,
+//! and two lines fed the same input under the same settings hold the
+//! same samples: the second line's work, and its memory traffic, are
+//! wasted. So a stereo engine runs such blocks on the first channel's
+//! state alone, and copies its output to the second:
+//!
+//! ```text
+//! inputs identical, sides treated alike   left state runs, right = left
+//! anything sets them apart                copy left state → right, split
+//! every line silent (asleep), reset()     shared again
+//! ```
+//!
+//! "Treated alike" is checked every block, conservatively: nothing
+//! couples the channels, the two feedback cutoffs match, decorrelation
+//! and the input balance are at zero, the key gate is out of circuit,
+//! and no freeze or panic clear is running. The inputs have to match to
+//! the bit — dual mono only counts while it's truly mono.
+//!
+//! The moment any of that stops holding, the first channel's state —
+//! line, pre-delay, filters, limiter — is copied over the second's, and
+//! both carry on from there on their own. The copy is exactly the state
+//! the second channel would have built up by itself, so the split can't
+//! click: the output is bit for bit what two separate lines would have
+//! played. Once split, the lines stay split until they've gone silent.
+//!
+//! There's no stereo read offset yet ([`MAX_STEREO_OFFSET_MS`] is 0).
+//! Sides read at different times could still share one line — two
+//! heads on the one buffer — but not one output, so when an offset
+//! lands it belongs in the checks above until it has a path of its own.
+//!
+//! ## Time Modulation
+//!
+//! An [`Lfo`] can move the delay time either side of its setting, for
+//! chorus-like drift or, with [`LfoWaveform::SampleAndHold`], the
+//! stepped warble of a worn tape machine. It rides on top of everything
+//! else that sets the time — the glide, the tape head, a crossfade's
+//! old head — so each read is the time it would have been, plus the
+//! LFO's value times the depth:
+//!
+//! ```text
+//! heard = delay + depth × lfo        lfo in −1..1, depth ≤ MAX_MOD_DEPTH_MS
+//! ```
+//!
+//! The lines are built [`MAX_TIME_MODULATION_MS`] longer, so the
+//! deepest swing at the longest time still has buffer to read. The
+//! depth glides like any other knob, and at zero the LFO isn't read
+//! at all: the time is exactly what it was. A change of waveform
+//! crossfades from the old shape to the new over [`MOD_SWITCH_FADE_MS`]
+//! rather than jumping the read head. While the engine sleeps the LFO
+//! keeps running, so it's where it would have been when the input
+//! comes back, whatever the block sizes.
+
+use std::num::NonZeroUsize;
+
+use crate::dsp::{
+    bloom::{BloomEnvelope, MAX_BLOOM_DB},
+    character::CharacterOffsets,
+    crossfade::{gains_at, Crossfade, FadeLaw, SwitchedPath},
+    damping::LoopDamping,
+    decorrelator::Decorrelator,
+    delay_line::DelayLine,
+    feedback::{ChainOrder, FeedbackChain, MAX_SHELF_DB},
+    feedback_compensation::compensation_gain,
+    feedback_matrix::FeedbackMatrix,
+    filter::{max_cutoff_hz, OnePoleFilter},
+    key_gate::KeyGate,
+    lfo::{Lfo, LfoWaveform},
+    limiter::Limiter,
+    loop_gain::{limit_feedback, LOOP_GAIN_CEILING},
+    output_filter::{OutputFilter, HIGHCUT_OFF_HZ, LOWCUT_OFF_HZ},
+    phase_align::{self, PhaseAligner, PhaseMeter},
+    repeat_sequencer::{RepeatSequencer, StepTarget, MAX_STEPS},
+    repeat_spread::{alternate_swap, spread_pan, RepeatGlide, RepeatTracker},
+    silence::SilenceTracker,
+    smoother::Smoother,
+    stereo_motion::{
+        balance_gains, constant_power_gains, narrow_pair, pan_stereo_pair, PanRotor, StartSide,
+        StereoMotion, MAX_SIDE_DECAY_DB,
+    },
+    tape_head::{self, TapeHead},
+    tempo::{samples_to_next_beat, NoteDivision},
+    time_change::{TimeChange, TimeMultiplier, TimeSnap, TimeTriggers},
+    watchdog::{FeedbackWatchdog, SAFE_FEEDBACK},
+};
+use crate::loop_meter::LoopReading;
+
+/// The most samples the channel-major pass works out shared values for
+/// before running the channels (see
+/// [Processing Order](self#processing-order)): small enough that the
+/// table stays in the L1 cache, big enough that each channel's state is
+/// loaded once per stretch, not once per sample.
+const SHARED_BLOCK_LEN: usize = 64;
+
+/// The most samples between control ticks (see
+/// [Control Rate](self#control-rate)). At 32 kHz and above a millisecond
+/// is longer than this, so the interval stops growing here.
+pub const MAX_CONTROL_INTERVAL: usize = 32;
+
+/// How many times further apart control ticks are in economy (see
+/// [Control Rate](self#control-rate)).
+pub const ECONOMY_CONTROL_FACTOR: usize = 4;
+
+/// Shortest delay time, in milliseconds.
+pub const MIN_DELAY_MS: f32 = 100.0;
+
+/// Longest delay time, in milliseconds.
+pub const MAX_DELAY_MS: f32 = 2000.0;
+
+/// Furthest the fine offset can move the delay time, either way, in
+/// milliseconds.
+pub const MAX_TIME_FINE_MS: f32 = 20.0;
+
+/// Longest pre-delay, in milliseconds.
+pub const MAX_PRE_DELAY_MS: f32 = 250.0;
+
+/// Deepest the time modulation reaches either side of the delay time,
+/// in milliseconds (see [Time Modulation](self#time-modulation)).
+pub const MAX_MOD_DEPTH_MS: f32 = 10.0;
+
+/// The range of [`DelayParams::mod_rate_hz`], in Hz.
+pub const MOD_RATE_RANGE_HZ: (f32, f32) = (0.05, 10.0);
+
+/// Furthest anything modulates the delay time past its setting, in
+/// milliseconds: the time modulation's deepest. A wow/flutter stage
+/// would add its depth here, and the lines grow to match.
+pub const MAX_TIME_MODULATION_MS: f32 = MAX_MOD_DEPTH_MS;
+
+/// Furthest one side's delay time can be offset from the other's, in
+/// milliseconds. Both sides read at the same time for now; a stereo
+/// offset control adds its range here.
+pub const MAX_STEREO_OFFSET_MS: f32 = 0.0;
+
+/// How much further back than the delay a frozen loop can reach, in
+/// milliseconds: the room a freeze leaves behind its loop, for the
+/// quantized seam's lead-in and for the release, when the write heads
+/// move on while the loop still plays. Built into the lines, so even a
+/// freeze at the longest time loops what it was playing.
+pub const FREEZE_ROOM_MS: f32 = SWITCH_FADE_MS;
+
+/// The longest delay any read head can ask the main lines for, in
+/// milliseconds, and so how long they're built — unless a memory budget
+/// shortens them (see [`LovelessDelayEngine::with_memory_budget()`]).
+///
+/// The fine offset isn't a term of its own: [`effective_delay_ms()`]
+/// clamps the coarse time plus the offset to [`MAX_DELAY_MS`], so it
+/// never reaches past the knob's range. Modulation rides on top of that
+/// clamped time (as a stereo offset would), and the freeze's room
+/// behind all of it.
+pub const MAX_READ_DELAY_MS: f32 =
+    MAX_DELAY_MS + MAX_TIME_MODULATION_MS + MAX_STEREO_OFFSET_MS + FREEZE_ROOM_MS;
+
+/// The sample rates the plugin runs at, in Hz: from telephone-band 8
+/// kHz to twice 384 kHz. The plugin turns down anything outside it (or
+/// not a number) in `initialize()`; the engine itself will build at
+/// any rate, but its buffers grow with it, and below about 41 Hz the
+/// filters have no range left to clamp their cutoffs to.
+pub const SAMPLE_RATE_RANGE: (f32, f32) = (8000.0, 768_000.0);
+
+/// How far [`LovelessDelayEngine::with_memory_budget()`] shortens the
+/// longest delay at a time while looking for one that fits, in
+/// milliseconds.
+pub const BUDGET_STEP_MS: f32 = 100.0;
+
+/// Highest feedback amount. At 1.0 the repeats would never decay, and
+/// above it they'd grow until they clipped; 0.95 gives very long tails
+/// while staying stable.
+pub const MAX_FEEDBACK: f32 = 0.95;
+
+/// How long a panic clear fades the wet output out before wiping the
+/// lines, in milliseconds. Quick enough to feel instant, slow enough
+/// not to click.
+pub const CLEAR_FADE_MS: f32 = 30.0;
+
+/// How long [`StopBehavior::Fade`] takes to fade the repeats out when
+/// the transport stops, in milliseconds.
+pub const STOP_FADE_MS: f32 = 500.0;
+
+/// How long the input has to stay silent before a host that reports no
+/// transport at all counts as stopped, in milliseconds (see
+/// [`LovelessDelayEngine::set_transport()`]). Longer than any rest in a
+/// song, so a break in the music isn't mistaken for one.
+pub const GAP_SILENCE_MS: f32 = 5000.0;
+
+/// How long the repeat hold takes to move the feedback to its level,
+/// and back to the knob on release, in milliseconds. Slow enough to
+/// swell rather than jump, quick enough to play in time.
+pub const HOLD_FADE_MS: f32 = 100.0;
+
+/// The range of [`DelayParams::overdub_decay_db`], in dB per pass. At
+/// the top, old layers all but stay; at the bottom, each is gone in a
+/// few passes. Never 0 dB: a layer that never fades leaves no room for
+/// new input (see
+/// [`overdub_gains()`]).
+pub const OVERDUB_DECAY_RANGE_DB: (f32, f32) = (-24.0, -0.5);
+
+/// The range of [`DelayParams::manual_bpm`], in beats per minute.
+pub const MANUAL_BPM_RANGE: (f32, f32) = (30.0, 300.0);
+
+/// Every setting the engine needs, as plain values.
+///
+/// Out-of-range values are clamped by
+/// [`LovelessDelayEngine::set_params()`]: the effective delay time
+/// (see [`time_multiplier`](Self::time_multiplier)) to
+/// [`MIN_DELAY_MS`]..=[`MAX_DELAY_MS`], feedback to
+/// 0.0..=[`MAX_FEEDBACK`], pre-delay to 0.0..=[`MAX_PRE_DELAY_MS`],
+/// scrub to ±[`tape_head::MAX_SCRUB`], and mix to 0.0..=1.0. The filter
+/// clamps its own cutoff to a safe range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DelayParams {
+    /// Time between the dry signal and the first echo, in milliseconds.
+    /// Ignored while [`sync`](Self::sync) is on.
+    pub delay_ms: f32,
+
+    /// Whether the delay time follows the tempo, as
+    /// [`division`](Self::division) notes, instead of `delay_ms`: the
+    /// host's tempo, or [`manual_bpm`](Self::manual_bpm) while the host
+    /// doesn't report one.
+    pub sync: bool,
+
+    /// The tempo sync follows while the host reports none (standalone,
+    /// or a host that keeps it to itself), in beats per minute, within
+    /// [`MANUAL_BPM_RANGE`]. See [`TempoSource`].
+    pub manual_bpm: f32,
+
+    /// The note length of one repeat while synced. Divisions longer than
+    /// [`MAX_DELAY_MS`] at slow tempos are clamped to it.
+    pub division: NoteDivision,
+
+    /// When sync is switched on or the division changes, hold the new
+    /// time back until the next beat boundary so the echoes lock to the
+    /// grid. Falls back to changing immediately when the host doesn't
+    /// report a beat position.
+    pub quantize_start: bool,
+
+    /// Scales the delay time — `delay_ms`, or the division while synced
+    /// — in musical steps. The time heard is
+    /// `base × multiplier + fine`, clamped to the delay's range only
+    /// once it's complete.
+    pub time_multiplier: TimeMultiplier,
+
+    /// Momentary Multiply: while held, the base time is doubled, or a
+    /// synced division moves one step longer (see
+    /// [`TimeGesture`](crate::dsp::time_change::TimeGesture)). The
+    /// change crossfades in and out whatever
+    /// [`time_change`](Self::time_change) says. If
+    /// [`half_time`](Self::half_time) is held too, the one pressed last
+    /// wins.
+    pub double_time: bool,
+
+    /// Momentary Divide: while held, the base time is halved, or a
+    /// synced division moves one step shorter. See
+    /// [`double_time`](Self::double_time).
+    pub half_time: bool,
+
+    /// An offset on top of the scaled delay time, in milliseconds
+    /// (±[`MAX_TIME_FINE_MS`]), for pushing the echoes a little ahead of
+    /// or behind the beat. It always glides, whatever
+    /// [`time_change`](Self::time_change) says.
+    pub time_fine_ms: f32,
+
+    /// How a change of delay time is heard: a glide that bends the
+    /// repeats' pitch, or a crossfade between the old and new times
+    /// (see [`TimeChange`]).
+    pub time_change: TimeChange,
+
+    /// The grid `delay_ms` snaps to before it's used (see [`TimeSnap`]).
+    /// Only the knob's time snaps: a synced division is on its own grid
+    /// already, and the fine offset stays continuous.
+    pub time_snap: TimeSnap,
+
+    /// How hard the tape is slowed (−[`tape_head::MAX_SCRUB`]..=
+    /// [`tape_head::MAX_SCRUB`]) while
+    /// [`time_change`](Self::time_change) is [`TimeChange::Tape`]: the
+    /// read head heads for speed `1 − scrub`, so 1.0 stops the tape,
+    /// 2.0 runs it backwards and −1.0 at double speed. At 0.0 the head
+    /// runs back to the delay time. Ignored in the other modes. See
+    /// [`TapeHead`].
+    pub scrub: f32,
+
+    /// The shape the time modulation moves the delay time in (see
+    /// [Time Modulation](self#time-modulation)).
+    /// [`LfoWaveform::SampleAndHold`] steps to a new random time each
+    /// cycle, gliding there by [`mod_glide`](Self::mod_glide).
+    pub mod_waveform: LfoWaveform,
+
+    /// How many cycles the time modulation runs a second, within
+    /// [`MOD_RATE_RANGE_HZ`].
+    pub mod_rate_hz: f32,
+
+    /// How far the time modulation moves the delay time either side of
+    /// its setting, in milliseconds (0.0 to [`MAX_MOD_DEPTH_MS`]). At
+    /// 0.0 the delay time is left exactly as it was.
+    pub mod_depth_ms: f32,
+
+    /// How long sample-and-hold takes to glide to each new level, as a
+    /// fraction of the cycle (see [`Lfo::set_glide()`]). The other
+    /// waveforms ignore it.
+    pub mod_glide: f32,
+
+    /// How much of each echo is fed back in (0.0 = one echo).
+    pub feedback: f32,
+
+    /// Hold the repeats: while on, the feedback moves to
+    /// [`hold_level`](Self::hold_level) instead of the knob's, over
+    /// [`HOLD_FADE_MS`] each way. Unlike [`freeze`](Self::freeze), the
+    /// loop stays live, so what's played meanwhile joins the repeats.
+    pub hold: bool,
+
+    /// The feedback while [`hold`](Self::hold) is on (0.0–1.0). It may
+    /// go past [`MAX_FEEDBACK`]: the loop-gain ceiling (see
+    /// [`limit_feedback()`]) still applies, so at 1.0 the loop sits
+    /// just under unity and the repeats hang on without growing.
+    pub hold_level: f32,
+
+    /// Extra time before the wet signal is heard, in milliseconds. It
+    /// shifts every echo later by the same amount, leaving the spacing
+    /// between repeats at `delay_ms`.
+    pub pre_delay_ms: f32,
+
+    /// Dry/wet balance (0.0 = dry only, 1.0 = wet only).
+    pub mix: f32,
+
+    /// Cutoff of the feedback lowpass filter, in Hz. This is the left
+    /// channel's filter; mono runs one filter between the two (see
+    /// [`folded_to_mono()`](Self::folded_to_mono)).
+    pub filter_cutoff: f32,
+
+    /// Cutoff of the right channel's feedback filter, in Hz. Channels
+    /// after the second use it too.
+    ///
+    /// The engine always treats the two sides independently; linking
+    /// them is a parameter-layer decision (the plugin simply copies the
+    /// left value here while its link switch is on).
+    pub filter_cutoff_right: f32,
+
+    /// Boost the feedback to make up for what the feedback filter takes
+    /// from each pass, so the repeats last as long at any cutoff. See
+    /// [`compensation_gain()`].
+    pub feedback_compensation: bool,
+
+    /// Where the feedback is taken from: the wet tap, or the mixed
+    /// output. See [`FeedbackSource`].
+    pub feedback_source: FeedbackSource,
+
+    /// How hard the feedback saturator squashes loud repeats
+    /// (0.0 = off, 1.0 = full).
+    pub drive: f32,
+
+    /// Whether the feedback filter runs before or after the saturator.
+    pub chain_order: ChainOrder,
+
+    /// Gain of the feedback low shelf at
+    /// [`LOW_SHELF_HZ`](crate::dsp::feedback::LOW_SHELF_HZ), in dB
+    /// (±[`MAX_SHELF_DB`]). Cuts thin the repeats out, boosts make them
+    /// boomier; 0 dB leaves them exactly as they were. A boost raises
+    /// the loop gain, and the loop-gain ceiling eases the feedback back
+    /// to match (see [`FeedbackChain`]).
+    pub fb_low_shelf_db: f32,
+
+    /// Gain of the feedback high shelf at
+    /// [`HIGH_SHELF_HZ`](crate::dsp::feedback::HIGH_SHELF_HZ), in dB
+    /// (±[`MAX_SHELF_DB`]), the same way.
+    pub fb_high_shelf_db: f32,
+
+    /// The character macro, from 0.0 (pristine) to 1.0 (worn tape). It
+    /// darkens the feedback filter and adds drive on top of
+    /// `filter_cutoff`, `filter_cutoff_right` and `drive` (see
+    /// [`CharacterOffsets`]) without changing those fields.
+    pub character: f32,
+
+    /// How much the feedback cutoffs drop as the loop gets quieter, from
+    /// 0.0 (a fixed filter) to 1.0 (see [`LoopDamping`]). Like
+    /// `character`, it acts on top of the cutoff fields.
+    pub damping: f32,
+
+    /// Corner of the wet signal's low-cut, in Hz. At or below
+    /// [`LOWCUT_OFF_HZ`](crate::dsp::output_filter::LOWCUT_OFF_HZ) the
+    /// low-cut is bypassed.
+    pub out_lowcut: f32,
+
+    /// Corner of the wet signal's high-cut, in Hz. At or above
+    /// [`HIGHCUT_OFF_HZ`](crate::dsp::output_filter::HIGHCUT_OFF_HZ) the
+    /// high-cut is bypassed.
+    pub out_highcut: f32,
+
+    /// Keep the low end mono-compatible: both sides' repeats share
+    /// the same content below ~200 Hz, so folding the mix to mono can't
+    /// cancel the bass.
+    pub mono_safe: bool,
+
+    /// Whether the soft-knee output limiter is engaged.
+    pub limiter: bool,
+
+    /// Route the feedback through external gear instead of the internal
+    /// loop (see [`LovelessDelayEngine::process_with_external_loop()`]).
+    /// Ignored by plain [`process()`](LovelessDelayEngine::process).
+    pub external_loop: bool,
+
+    /// What the per-repeat step pattern modulates, if anything. With
+    /// [`StepTarget::Cutoff`], each repeat is heard through its own
+    /// wet-path high-cut (the lower of the step's corner and
+    /// `out_highcut`), leaving the feedback loop alone.
+    pub step_target: StepTarget,
+
+    /// How many of [`steps`](Self::steps) the pattern uses (1–8).
+    pub step_length: usize,
+
+    /// The step pattern, one value (0.0–1.0) per repeat. For the cutoff
+    /// target, 0.0 is 200 Hz and 1.0 is fully open, on a log scale.
+    pub steps: [f32; MAX_STEPS],
+
+    /// How the repeats move around the stereo field. Needs two
+    /// channels, and isn't applied while the external loop is in use.
+    pub motion: StereoMotion,
+
+    /// How far circular motion turns per repeat, in degrees.
+    pub motion_rotation: f32,
+
+    /// Where ping-pong's first repeat is heard; reverse ping-pong
+    /// mirrors it.
+    pub start_side: StartSide,
+
+    /// How each side's repeats feed the two delay lines, on top of the
+    /// feedback amount (see [`FeedbackMatrix`]). The engine normalizes
+    /// it so the loop stays as stable as a straight delay's. Needs two
+    /// channels; ping-pong wires the loop its own way, and it isn't
+    /// applied while the external loop is in use.
+    pub feedback_matrix: FeedbackMatrix,
+
+    /// The panic switch. Turning it on fades the wet output and the loop
+    /// to silence over [`CLEAR_FADE_MS`], wipes the delay lines, and
+    /// carries on with an empty loop. Only the switch turning *on*
+    /// starts a clear, so to clear again it has to go off and back on;
+    /// leaving it on does nothing further.
+    pub clear: bool,
+
+    /// Mute the dry signal so only the repeats are heard, whatever the
+    /// mix — for dialing in the filter and drive. Switching fades the
+    /// dry level over a few milliseconds.
+    pub wet_solo: bool,
+
+    /// Switch on the feedback watchdog: if the repeats stay loud for
+    /// longer than [`watchdog_seconds`](Self::watchdog_seconds), the
+    /// feedback is ramped down until the loop has died away, then handed
+    /// back to the knob. For installations nobody is watching.
+    pub watchdog: bool,
+
+    /// How long the repeats may stay loud before the watchdog steps in,
+    /// in seconds.
+    pub watchdog_seconds: f32,
+
+    /// What happens to the repeats when the host's transport stops (see
+    /// [`Transport::playing`]).
+    pub stop_behavior: StopBehavior,
+
+    /// How far apart the two sides' repeats are pulled (0.0–1.0), so a
+    /// dual-mono input still gives a wide echo. At 0.0 the wet signal is
+    /// untouched. See [`Decorrelator`].
+    pub decorrelate: f32,
+
+    /// Flip the wet signal's polarity when, at delays under
+    /// [`phase_align::MAX_DELAY_MS`], it's cancelling the dry signal
+    /// rather than adding to it. See [`PhaseAligner`].
+    pub phase_align: bool,
+
+    /// Where the dry signal sits in the stereo field (−1.0 hard left,
+    /// 0.0 as it came in, 1.0 hard right), leaving the repeats alone.
+    /// See [`pan_stereo_pair()`].
+    pub dry_pan: f32,
+
+    /// Balances the input going into the delay lines between left and
+    /// right (−1.0 left only, 0.0 even, 1.0 right only) at constant
+    /// total power, leaving the dry signal alone. See [`balance_gains()`].
+    pub input_balance: f32,
+
+    /// Exchanges the left and right wet signals, so the repeats that
+    /// would land on the left land on the right. Switching crossfades.
+    pub swap_channels: bool,
+
+    /// Extra attenuation of the feedback's side (L − R) component on
+    /// every pass, in dB (0 to [`MAX_SIDE_DECAY_DB`]), so the tail
+    /// narrows towards mono as it decays. Stereo only; at 0 dB the
+    /// feedback is untouched. See [`narrow_pair()`].
+    pub side_decay_db: f32,
+
+    /// How far successive repeats are panned apart (0.0–1.0): the first
+    /// slightly left, the second slightly right, each further out until
+    /// the fourth reaches the full width, each pan gliding in ahead of
+    /// its repeat. At 0.0 every repeat stays where its line put it. See
+    /// [`spread_pan()`].
+    pub spread: f32,
+
+    /// Exchanges the left and right wet signals on every other repeat,
+    /// so the image bounces from side to side while each line keeps its
+    /// own feedback, time and tone. Only the output alternates; the loop
+    /// is untouched. Each swap glides in ahead of its repeat. See
+    /// [`alternate_swap()`].
+    pub alternate: bool,
+
+    /// How far the first repeat after fresh input is held down, in dB
+    /// (0 to [`MAX_BLOOM_DB`]), the wet gain rising back to unity by the
+    /// third so the repeats swell before they decay. It rides the wet
+    /// output only, never the loop. At 0 dB the wet signal is untouched.
+    /// See [`BloomEnvelope`].
+    pub bloom_db: f32,
+
+    /// Only let input into the delay lines while the sidechain key (see
+    /// [`process_with_key()`](LovelessDelayEngine::process_with_key)) is
+    /// above [`key_threshold_db`](Self::key_threshold_db). The dry
+    /// signal is never gated, and without a key the gate stands open.
+    pub key_gate: bool,
+
+    /// The key level that opens the gate, in dBFS.
+    pub key_threshold_db: f32,
+
+    /// How long the gate takes to close once the key falls below the
+    /// threshold, in milliseconds.
+    pub key_release_ms: f32,
+
+    /// Replace the output with the key as the gate's detector hears it,
+    /// through its highpass, to hear what opens the gate. It works with
+    /// the gate on or off, fades over 10 ms either way, and leaves the
+    /// output silent while there's no key.
+    pub duck_listen: bool,
+
+    /// Hold what's in the delay lines and play it round and round: no
+    /// input goes in, nothing decays. Turning it off fades back to the
+    /// live delay over a few milliseconds. See
+    /// [`capture_freeze()`](LovelessDelayEngine::capture_freeze).
+    pub freeze: bool,
+
+    /// Make the frozen loop a whole number of delay periods (of the
+    /// synced division, while synced) and smooth over its seam, so it
+    /// loops in time and without a click. Read when freeze turns on.
+    pub freeze_quantize: bool,
+
+    /// While frozen, let the input into the loop too, a simple looper:
+    /// each pass through the region rewrites it as the old loop, faded
+    /// by [`overdub_decay_db`](Self::overdub_decay_db), plus the input.
+    /// Overdubbed material stays in the loop region; releasing the
+    /// freeze lets it all go round the feedback loop like any other
+    /// repeats. See [`overdub_gains()`].
+    pub overdub: bool,
+
+    /// How much the loop fades on each pass while overdubbing, in dB,
+    /// within [`OVERDUB_DECAY_RANGE_DB`].
+    pub overdub_decay_db: f32,
+
+    /// A hidden gain on the finished output, in dB, ahead of the limiter.
+    /// The plugin uses it to loudness-match presets while they're being
+    /// auditioned (see [`presets`](crate::presets)); it has no knob.
+    pub output_trim_db: f32,
+}
+
+impl Default for DelayParams {
+    /// The same defaults as the plugin's parameters.
+    fn default() -> Self {
+        Self {
+            delay_ms: 500.0,
+            sync: false,
+            manual_bpm: 120.0,
+            division: NoteDivision::Quarter,
+            quantize_start: true,
+            time_multiplier: TimeMultiplier::Single,
+            double_time: false,
+            half_time: false,
+            time_fine_ms: 0.0,
+            time_change: TimeChange::Glide,
+            time_snap: TimeSnap::Off,
+            scrub: 0.0,
+            mod_waveform: LfoWaveform::Sine,
+            mod_rate_hz: 1.0,
+            mod_depth_ms: 0.0,
+            mod_glide: 0.5,
+            feedback: 0.40,
+            hold: false,
+            hold_level: 1.0,
+            pre_delay_ms: 0.0,
+            mix: 0.50,
+            filter_cutoff: 8000.0,
+            filter_cutoff_right: 8000.0,
+            feedback_compensation: false,
+            feedback_source: FeedbackSource::WetTap,
+            drive: 0.0,
+            chain_order: ChainOrder::FilterThenDrive,
+            fb_low_shelf_db: 0.0,
+            fb_high_shelf_db: 0.0,
+            character: 0.0,
+            damping: 0.0,
+            out_lowcut: LOWCUT_OFF_HZ,
+            out_highcut: HIGHCUT_OFF_HZ,
+            mono_safe: false,
+            limiter: false,
+            external_loop: false,
+            step_target: StepTarget::Off,
+            step_length: 4,
+            steps: [1.0, 0.6, 0.8, 0.4, 1.0, 0.6, 0.8, 0.4],
+            motion: StereoMotion::Off,
+            motion_rotation: 90.0,
+            start_side: StartSide::Left,
+            feedback_matrix: FeedbackMatrix::STRAIGHT,
+            clear: false,
+            wet_solo: false,
+            watchdog: false,
+            watchdog_seconds: 10.0,
+            stop_behavior: StopBehavior::RingOut,
+            decorrelate: 0.0,
+            phase_align: false,
+            dry_pan: 0.0,
+            input_balance: 0.0,
+            swap_channels: false,
+            side_decay_db: 0.0,
+            spread: 0.0,
+            alternate: false,
+            bloom_db: 0.0,
+            key_gate: false,
+            key_threshold_db: -30.0,
+            key_release_ms: 100.0,
+            duck_listen: false,
+            freeze: false,
+            freeze_quantize: false,
+            overdub: false,
+            overdub_decay_db: -6.0,
+            output_trim_db: 0.0,
+        }
+    }
+}
+
+impl DelayParams {
+    /// The settings a single-channel engine runs with: every stereo-only
+    /// setting folded into one combined voice.
+    ///
+    /// nih-plug's parameter flags are fixed at compile time, so the
+    /// stereo-only knobs can't grey themselves out on a mono track.
+    /// Instead each one gets a deterministic mono meaning here, and
+    /// [`LovelessDelayEngine::set_params()`] applies it whenever the
+    /// engine has one channel:
+    ///
+    /// - **Filter L / Filter R** become one filter at their geometric
+    ///   mean — halfway between them in octaves, so an unlinked pair
+    ///   sounds like the average of the two sides rather than just the
+    ///   left one. Linked, that's simply the shared cutoff.
+    /// - **Mono-Safe** is off: a single channel is already mono, and
+    ///   there's no second side to fold the low band into.
+    /// - **Motion** is off: there's no field for the repeats to move
+    ///   around, so every repeat plays straight back.
+    /// - **Feedback Matrix** is straight: there's no second line to
+    ///   cross into, so the feedback amount alone sets the decay.
+    /// - **Dry Pan** and **Input Balance** are centred, and **Swap** is
+    ///   off: there's no other side to move anything towards.
+    /// - **Side Decay** is 0 dB: a single channel has no side to decay.
+    /// - **Spread** is 0% and **Alternate** off: with nowhere to pan
+    ///   them, every repeat plays from the one speaker.
+    ///
+    /// Everything else is channel-independent and passes through
+    /// unchanged.
+    pub fn folded_to_mono(&self) -> Self {
+        let cutoff = (self.filter_cutoff.max(1.0) * self.filter_cutoff_right.max(1.0)).sqrt();
+        Self {
+            filter_cutoff: cutoff,
+            filter_cutoff_right: cutoff,
+            mono_safe: false,
+            motion: StereoMotion::Off,
+            feedback_matrix: FeedbackMatrix::STRAIGHT,
+            decorrelate: 0.0,
+            dry_pan: 0.0,
+            input_balance: 0.0,
+            swap_channels: false,
+            side_decay_db: 0.0,
+            spread: 0.0,
+            alternate: false,
+            ..*self
+        }
+    }
+}
+
+/// What the host's transport is doing, for tempo sync and
+/// [`StopBehavior`].
+///
+/// Either timing value may be unknown: not every host reports a tempo,
+/// and some don't report a musical position (or only while playing).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transport {
+    /// The tempo in beats (quarter notes) per minute.
+    pub tempo: Option<f64>,
+
+    /// The playhead position at the start of the next block, in beats.
+    pub pos_beats: Option<f64>,
+
+    /// Whether the transport is playing. The moment it goes from playing
+    /// to stopped is when [`DelayParams::stop_behavior`] acts.
+    pub playing: bool,
+}
+
+/// Where the tempo sync follows comes from, decided afresh each block
+/// in [`LovelessDelayEngine::set_params()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempoSource {
+    /// The host's transport reports a tempo.
+    Host,
+    /// It doesn't, so [`DelayParams::manual_bpm`] stands in.
+    Manual,
+}
+
+/// What happens to the repeats when the transport stops.
+///
+/// Hosts disagree here. Some reset the plugin on stop, cutting the tail
+/// dead; others just stop sending audio, and whatever was ringing picks
+/// up again on the next play. Watching the transport instead makes the
+/// choice the user's, the same in every host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StopBehavior {
+    /// Leave the repeats alone: they ring out, and anything left is
+    /// still there when playback starts again.
+    #[default]
+    RingOut,
+    /// Fade the repeats out over [`STOP_FADE_MS`], then empty the lines.
+    Fade,
+    /// Clear the repeats at once (over the panic clear's
+    /// [`CLEAR_FADE_MS`], so it doesn't click).
+    Clear,
+}
+
+/// What the feedback loop takes its signal from.
+///
+/// Some classic delays feed their *output* back, dry signal and all,
+/// rather than the echoes alone. The mix knob is then part of the loop:
+/// turning it towards dry shortens the tail, and the dry signal goes
+/// round again under the echoes, its own first repeat shaped by the
+/// feedback chain.
+///
+/// ```text
+/// WetTap:    line ◄── input + fb · chain(delayed)
+/// PostMix:   line ◄── input + fb · chain(delayed · mix + input · (1 − mix))
+/// ```
+///
+/// The post-mix signal is taken where the dry and wet meet, ahead of
+/// the pre-delay and the output stages, so switching adds no latency to
+/// the loop. With the linear mix, only `mix` of the delayed signal goes
+/// round, so the loop gain is the wet tap's times `mix`: the loop-gain
+/// ceiling is worked out on that (see
+/// [`limit_feedback()`](crate::dsp::loop_gain::limit_feedback)), and the
+/// dry part, which never recirculates, adds nothing to it. The external
+/// loop always sends the wet tap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeedbackSource {
+    /// The delayed signal alone, before the mix. The mix knob sets only
+    /// how loud the repeats are heard.
+    #[default]
+    WetTap,
+    /// The mixed output: the delayed signal at `mix` and the dry input
+    /// at `1 − mix`.
+    PostMix,
+}
+
+/// The smoothed values the audio last used, for the decisions made
+/// around it: the tail estimate, a readout, a meter.
+///
+/// Only the audio steps a smoother. A status check that called `next()`
+/// would move a ramp on by a sample nobody heard, so the sound would
+/// depend on how often the host asked for the tail. These are read
+/// without stepping (see [`LovelessDelayEngine::current_values()`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurrentValues {
+    /// The delay time heard, fine offset included, in milliseconds.
+    pub delay_ms: f32,
+
+    /// The pre-delay, in milliseconds.
+    pub pre_delay_ms: f32,
+
+    /// The feedback applied: the knob's (held down by the watchdog)
+    /// moved towards the hold level as far as the repeat hold has got,
+    /// before the loop-gain ceiling.
+    pub feedback: f32,
+
+    /// The wet/dry mix, before the engage fade.
+    pub mix: f32,
+
+    /// How much loop damping is applied, 0.0–1.0.
+    pub damping: f32,
+
+    /// The dry pan, −1.0 (left) to 1.0 (right).
+    pub dry_pan: f32,
+
+    /// How far Spread pans the repeats apart, 0.0–1.0.
+    pub spread: f32,
+
+    /// The gain side decay leaves the side with on each pass (1.0 at
+    /// 0 dB).
+    pub side_decay_gain: f32,
+
+    /// The output trim, as a gain.
+    pub output_trim_gain: f32,
+}
+
+/// How the smoothing path walks a block (see
+/// [Processing Order](self#processing-order)). Either way the output is
+/// the same, sample for sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProcessingOrder {
+    /// Each channel's stretch of the block in turn, whenever nothing
+    /// couples the channels.
+    #[default]
+    ChannelMajor,
+
+    /// Every channel on one sample before the next sample: the order
+    /// the smoothing path always used, kept to compare against.
+    SampleMajor,
+}
+
+/// How much of the engine's own smoothing parameter changes get, on
+/// top of whatever the host has done (see
+/// [`LovelessDelayEngine::set_automation_smoothing()`]).
+///
+/// Some hosts step automation a few times a second, and every step
+/// needs the full ramp. Others send hundreds of points a second along
+/// a curve that's smooth already, and the full ramp only makes the
+/// automation lag behind it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AutomationSmoothing {
+    /// The full ramps: 20 ms for gains, 50 ms for frequencies and tone.
+    #[default]
+    Full,
+
+    /// A quarter of the full ramps.
+    Reduced,
+
+    /// Only the [`MIN_RAMP_MS`](crate::dsp::smoother::MIN_RAMP_MS)
+    /// safety floor, so a jump still doesn't click.
+    Off,
+}
+
+impl AutomationSmoothing {
+    /// Every mode, in the order an editor lists them.
+    pub const ALL: [Self; 3] = [Self::Full, Self::Reduced, Self::Off];
+
+    /// What the full ramp times are scaled by, before the floor.
+    pub fn ramp_scale(self) -> f32 {
+        match self {
+            Self::Full => 1.0,
+            Self::Reduced => 0.25,
+            Self::Off => 0.0,
+        }
+    }
+}
+
+/// The repeats in flight in an engine, copied out on the audio thread
+/// so the rest of the tail can be rendered somewhere else (see
+/// [`LovelessDelayEngine::capture_tail()`]).
+///
+/// It holds the copy in an engine of its own, built to match, so
+/// capturing never allocates: lines and filters are copied into ones
+/// that are already the right size.
+pub struct TailSnapshot {
+    engine: LovelessDelayEngine,
+
+    /// How many of the newest samples in each line were copied: as far
+    /// back as anything was still reading (see
+    /// [`capture_tail()`](LovelessDelayEngine::capture_tail)). What's
+    /// older is left over from an earlier capture.
+    span: usize,
+
+    /// The settings and transport the repeats were playing under.
+    params: DelayParams,
+    transport: Transport,
+}
+
+impl TailSnapshot {
+    /// An empty snapshot for an engine of `channels` channels at
+    /// `sample_rate` with lines for `max_delay_ms`, for
+    /// [`capture_tail()`](LovelessDelayEngine::capture_tail) to fill.
+    ///
+    /// This allocates a second set of buffers, as much again as the
+    /// engine's own, so like [`LovelessDelayEngine::new()`] it belongs
+    /// off the audio thread.
+    pub fn new(sample_rate: f32, channels: usize, max_delay_ms: f32) -> Self {
+        Self {
+            engine: LovelessDelayEngine::with_max_delay(sample_rate, channels, max_delay_ms),
+            span: 0,
+            params: DelayParams::default(),
+            transport: Transport::default(),
+        }
+    }
+
+    /// How many bytes of buffers the snapshot holds: the same as the
+    /// engine it was made for.
+    pub fn memory_bytes(&self) -> usize {
+        self.engine.memory_bytes()
+    }
+
+    /// A new engine that carries on from the captured moment, ready to
+    /// be fed silence for the rest of the tail.
+    ///
+    /// It plays the captured settings, landed on at once, and rings out
+    /// whatever the transport does. It knows no playhead, so nothing
+    /// waits for a beat, but synced times follow the captured tempo.
+    /// What's ramping at the capture lands where it was headed, so a
+    /// capture mid-glide renders a slightly different tail.
+    ///
+    /// This allocates the new engine's buffers, so call it away from
+    /// the audio thread.
+    pub fn seeded_engine(&self) -> LovelessDelayEngine {
+        let source = &self.engine;
+        let mut engine = LovelessDelayEngine::with_max_delay(
+            source.sample_rate,
+            source.channels(),
+            source.max_delay_ms,
+        );
+        engine.set_transport(Transport {
+            pos_beats: None,
+            playing: false,
+            ..self.transport
+        });
+        engine.set_params(&DelayParams {
+            stop_behavior: StopBehavior::RingOut,
+            ..self.params
+        });
+        engine.copy_tail_from(source, self.span);
+        engine
+    }
+}
+
+/// A synced delay time waiting for the next beat boundary, and how the
+/// change will be heard when it gets there.
+#[derive(Debug, Clone, Copy)]
+struct PendingDelay {
+    delay_ms: f32,
+    samples_left: usize,
+    change: TimeChange,
+}
+
+/// A crossfaded change of delay time (see [`TimeChange::Crossfade`]):
+/// the time being faded away from, and how far the fade has got.
+#[derive(Debug, Clone)]
+struct TimeFade {
+    /// The coarse delay time before the change, in milliseconds. The
+    /// fine offset moves the old head along with the new one.
+    from_ms: f32,
+    fade: Crossfade,
+}
+
+/// Where the old head of a time crossfade reads on one sample, and how
+/// the fade splits the delayed signal between it and the new head.
+#[derive(Debug, Clone, Copy)]
+struct TimeTap {
+    delay_samps: f32,
+    old_gain: f32,
+    new_gain: f32,
+}
+
+impl TimeTap {
+    /// Blend `delayed`, read from `line` at the new time, with the old
+    /// head's read.
+    #[inline]
+    fn blend(self, line: &DelayLine, delayed: f32) -> f32 {
+        self.new_gain * delayed + self.old_gain * line.read(self.delay_samps)
+    }
+}
+
+/// A captured freeze loop (see [`DelayParams::freeze`]): a region of
+/// the delay lines, played round and round while their write heads stand
+/// still.
+#[derive(Debug, Clone, Copy)]
+struct FrozenLoop {
+    /// The region's length: the newest `len` samples at capture.
+    len: usize,
+
+    /// Where playback is in the region, from 0 (its oldest sample).
+    pos: usize,
+
+    /// How far the write heads have moved on since the release began.
+    /// The region is that much further behind them while it fades out.
+    moved: usize,
+
+    /// Whether the time crossfade running at capture is still running.
+    /// That one carries on in the loop; one that starts later only moves
+    /// the live read the release fades back to.
+    caught_fade: bool,
+}
+
+/// Where the frozen loop reads on one sample, and how the release fade
+/// splits the wet signal between it and the live read.
+#[derive(Debug, Clone, Copy)]
+struct FrozenTap {
+    delay: usize,
+    live_gain: f32,
+    frozen_gain: f32,
+    caught_fade: bool,
+}
+
+/// Everything the channels share on one sample of the smoothing path:
+/// the smoothers' values and the taps, worked out once by
+/// [`next_shared_values()`](LovelessDelayEngine::next_shared_values)
+/// and handed to every channel.
+#[derive(Debug, Clone, Copy)]
+struct SharedValues {
+    /// `Some(steps)` on a control tick (see [Control Rate](self#control-rate)).
+    control_steps: Option<usize>,
+    clear_gain: f32,
+    dry_gain: f32,
+    time_tap: Option<TimeTap>,
+    feedback: f32,
+    feedback_matrix: FeedbackMatrix,
+    mix: f32,
+    offsets: CharacterOffsets,
+    filter_cutoffs: [f32; 2],
+    drive: f32,
+    shelf_gains: [f32; 2],
+    compensation: f32,
+    post_mix: f32,
+    damping: f32,
+    decorrelate: f32,
+    wet_polarity: f32,
+    spread: f32,
+    overdub: Option<(f32, f32)>,
+    out_lowcut: f32,
+    out_highcut: f32,
+    delay_samps: f32,
+    pre_delay_samps: f32,
+    integer_delay: Option<usize>,
+    dry_pair: Option<[f32; 2]>,
+    key_gain: f32,
+    bloom_gain: f32,
+    balance: [f32; 2],
+    side_gain: f32,
+    frozen_tap: Option<FrozenTap>,
+}
+
+/// What a control tick hands a channel's filters on the control-rate
+/// path (see
+/// [`process_control_rate()`](LovelessDelayEngine::process_control_rate)).
+#[derive(Debug, Clone, Copy)]
+struct ControlTick {
+    /// How many samples the filters glide over, up to the next tick.
+    steps: usize,
+    filter_cutoffs: [f32; 2],
+    compensation: f32,
+    damping: f32,
+    out_cutoffs: [f32; 2],
+}
+
+/// Where a block was last loud on any channel, measured against the
+/// tail threshold and the much lower sleep threshold. Each processing
+/// path fills one in, and
+/// [`finish_block()`](LovelessDelayEngine::finish_block) hands the two
+/// indices to their [`SilenceTracker`]s.
+#[derive(Debug, Clone, Copy)]
+struct LastLoud {
+    tail_threshold: f32,
+    sleep_threshold: f32,
+
+    /// The last sample at or above `tail_threshold`.
+    tail: Option<usize>,
+
+    /// The last sample at or above `sleep_threshold`.
+    awake: Option<usize>,
+}
+
+impl LastLoud {
+    /// Nothing loud yet, against the thresholds of the two trackers.
+    fn new(tail: &SilenceTracker, idle: &SilenceTracker) -> Self {
+        Self {
+            tail_threshold: tail.threshold(),
+            sleep_threshold: idle.threshold(),
+            tail: None,
+            awake: None,
+        }
+    }
+
+    /// Account for sample `i`, whose loudest input or delayed signal was
+    /// `level` (a magnitude).
+    #[inline]
+    fn note(&mut self, i: usize, level: f32) {
+        if level >= self.tail_threshold {
+            self.tail = self.tail.max(Some(i));
+        }
+        if level >= self.sleep_threshold {
+            self.awake = self.awake.max(Some(i));
+        }
+    }
+}
+
+/// How far along a panic clear (see [`DelayParams::clear`]) is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TailClear {
+    Idle,
+    /// Fading the loop and the wet output to silence.
+    Fading,
+    /// Silencing the lines behind the write head, `chunk` samples of
+    /// each line per processed sample. `since` counts the samples
+    /// written since the fade ended (fresh input, which is kept; none
+    /// while frozen), and `wiped` how many samples behind them are
+    /// silent so far.
+    Wiping {
+        since: usize,
+        wiped: usize,
+        chunk: usize,
+    },
+}
+
+/// The complete delay effect, independent of any plugin framework.
+///
+/// Create one with [`new()`](Self::new), set its parameters with
+/// [`set_params()`](Self::set_params), and feed it audio with
+/// [`process()`](Self::process). All allocation happens in `new()`, so
+/// `set_params()` and `process()` are safe to call on a real-time audio
+/// thread.
+pub struct LovelessDelayEngine {
+    /// The sample rate in Hz (e.g., 44100.0 or 48000.0). Used to convert
+    /// delay time from milliseconds to samples:
+    /// `delay_samples = delay_ms * sample_rate / 1000`.
+    sample_rate: f32,
+
+    /// The longest delay time the lines were built for: [`MAX_DELAY_MS`],
+    /// unless a memory budget asked for less (see
+    /// [`with_memory_budget()`](Self::with_memory_budget)).
+    max_delay_ms: f32,
+
+    /// The delay line, feedback chain and output filter for each audio
+    /// channel.
+    ///
+    /// For stereo audio, this will contain 2 independent sets. Each
+    /// channel is processed separately so that stereo imaging is
+    /// preserved — if only the left channel has audio, only the left
+    /// delay line produces echoes.
+    channel_states: Vec<ChannelState>,
+
+    /// One output limiter per channel, used when
+    /// [`DelayParams::limiter`] is on. Each keeps its own gain so a peak
+    /// on one side doesn't duck the other.
+    limiters: Vec<Limiter>,
+
+    /// Watches the input and the delayed (wet) signal on every channel to
+    /// measure when the tail has really died away, so we can tell the
+    /// host to stop processing instead of relying only on an estimate.
+    silence: SilenceTracker,
+
+    /// Watches the same signals against [`SLEEP_SILENCE_DB`], and
+    /// whether the last block was skipped because of it (see
+    /// [`process()`](Self::process)).
+    idle: SilenceTracker,
+    asleep: bool,
+
+    /// Whether the first channel's state stands for the second's too
+    /// (see [Shared Lines](self#shared-lines)). While it does, the
+    /// second's own state is stale, until
+    /// [`split_lines()`](Self::split_lines) copies the first's over it.
+    lines_shared: bool,
+
+    /// Smoothers for the continuous parameters. When a value changes,
+    /// these ramp to it instead of jumping, which would click.
+    ///
+    /// The delay time is split in two: `delay_time` holds the coarse
+    /// time (the base time times the multiplier), which can crossfade
+    /// instead of gliding, and `time_fine` the fine offset, which always
+    /// glides. The delay heard is their sum (see
+    /// [`effective_delay_ms()`]).
+    delay_time: Smoother,
+    time_fine: Smoother,
+    pre_delay: Smoother,
+    feedback: Smoother,
+    mix: Smoother,
+
+    /// Feedback filter cutoffs: `[left, right]`.
+    filter_cutoffs: [Smoother; 2],
+
+    /// How much of the feedback compensation is applied, 0.0 (off) to
+    /// 1.0 (on), so switching it fades the boost in and out.
+    feedback_compensation: Smoother,
+
+    /// How far the feedback source has moved from the wet tap (0.0)
+    /// to the post-mix signal (1.0), so switching fades between them.
+    post_mix: Smoother,
+
+    /// The normalized feedback matrix's entries: `[ll, lr, rl, rr]`.
+    /// The norm is convex, so every step of a glide between two
+    /// normalized matrices is normalized too.
+    feedback_matrix: [Smoother; 4],
+
+    drive: Smoother,
+    /// The feedback shelves' linear gains: `[low, high]`.
+    shelf_gains: [Smoother; 2],
+    character: Smoother,
+    damping: Smoother,
+    decorrelate: Smoother,
+    dry_pan: Smoother,
+    input_balance: Smoother,
+    /// The feedback's side gain per pass, from the side decay.
+    side_decay: Smoother,
+    spread: Smoother,
+
+    /// The bloom amount in dB, and the envelope it shapes the wet
+    /// signal's gain with.
+    bloom: Smoother,
+    bloom_envelope: BloomEnvelope,
+
+    /// Lets input into the lines only while the sidechain key is loud.
+    key_gate: KeyGate,
+
+    /// Output filter corners (shared by all channels).
+    out_lowcut: Smoother,
+    out_highcut: Smoother,
+
+    /// The output trim, as a linear gain.
+    output_trim: Smoother,
+
+    /// Steps through the per-repeat pattern, one step per delay period.
+    sequencer: RepeatSequencer,
+    step_target: StepTarget,
+
+    /// The current step's high-cut corner ([`HIGHCUT_OFF_HZ`] while
+    /// stepping is off). Steps glide over a few milliseconds so they
+    /// don't click.
+    step_cutoff: Smoother,
+
+    /// Set by [`reset()`](Self::reset): line the step pattern back up
+    /// with the song at the next synced `set_params()`.
+    resync_steps: bool,
+
+    /// How the repeats move around the stereo field, and the rotor that
+    /// pans them for circular motion.
+    motion: StereoMotion,
+    pan_rotor: PanRotor,
+    start_side: StartSide,
+
+    /// The motion and start side being faded away from while
+    /// `motion_fade` runs. Changing either crossfades the pair's routing
+    /// — the input gains, the matrix, what each side hears — over
+    /// [`SWITCH_FADE_MS`], rather than rewiring it between two samples.
+    faded_motion: (StereoMotion, StartSide),
+    motion_fade: Crossfade,
+
+    /// The mono sum of the first two channels' input, as long as the
+    /// delay lines. A centered ping-pong start reads its first repeat
+    /// from here (see [`StartSide::Center`]). Recorded on every stereo
+    /// block, whatever the motion, so it's never stale when a centered
+    /// start is switched on.
+    mono_input: DelayLine,
+
+    /// How many times each sample in the first two lines has been round
+    /// the loop, so Spread can pan each repeat by its number and
+    /// Alternate pick its side. Runs only while either is on (see
+    /// [`RepeatTracker`]).
+    repeat_tracker: RepeatTracker,
+
+    /// Where Spread's pan has got to on the way to the next repeat's.
+    spread_glide: RepeatGlide,
+
+    /// Whether the wet sides alternate repeat by repeat, and how far
+    /// they're exchanged on the way to the next repeat's side.
+    alternate: SwitchedPath,
+    alternate_swap: RepeatGlide,
+
+    /// Fades the effect in after [`new()`](Self::new) and
+    /// [`reset()`](Self::reset), by scaling the mix up from 0 over
+    /// [`ENGAGE_FADE_MS`]. A host may activate the plugin on a block
+    /// that already holds program material; without the fade, the dry
+    /// signal would drop from full level to `1 − mix` in one sample.
+    engage: Smoother,
+
+    /// A panic clear in progress, the fade it runs the wet signal
+    /// through (and that fade's length), and the switch's last value, so
+    /// a clear starts only when it turns on.
+    tail_clear: TailClear,
+    clear_fade: Smoother,
+    clear_fade_ms: f32,
+    clear_held: bool,
+
+    /// Which feedback stage runs first. Kept here as well as in each
+    /// chain so channels added later pick it up.
+    chain_order: ChainOrder,
+
+    /// Whether mono-safe mode links the first two channels' low bands.
+    /// Switching crossfades between the unlinked and linked feedback.
+    mono_safe: SwitchedPath,
+
+    /// Whether the output limiter is engaged. Switching crossfades
+    /// between the unlimited and limited output.
+    limiter: SwitchedPath,
+
+    /// Whether the feedback runs through the external loop. Switching
+    /// crossfades between the internal and returned feedback.
+    external_loop: SwitchedPath,
+
+    /// Whether the dry signal is muted. Switching fades the dry level
+    /// rather than cutting it.
+    wet_solo: SwitchedPath,
+
+    /// Whether the key is played in place of the output (see
+    /// [`listen_to_key()`](Self::listen_to_key)). Switching crossfades
+    /// between them.
+    duck_listen: SwitchedPath,
+
+    /// Whether the wet sides are exchanged. Switching crossfades each
+    /// side from its own repeats to the other's.
+    swap_channels: SwitchedPath,
+
+    /// Pulls the feedback down when the repeats stay loud for too long,
+    /// and the knob's own feedback, which it hands back afterwards.
+    watchdog: FeedbackWatchdog,
+    feedback_setting: f32,
+
+    /// Phase alignment: whether it's in circuit (switched on, at a
+    /// short enough delay), what it's decided, and the wet signal's
+    /// polarity fading to match (1.0 or −1.0, over
+    /// [`POLARITY_FADE_MS`]).
+    aligning: bool,
+    phase_aligner: PhaseAligner,
+    wet_polarity: Smoother,
+
+    /// The repeat hold: how far the feedback has moved from the knob's
+    /// towards `hold_level` (0.0 the knob, 1.0 the hold level). It ramps
+    /// on its own, apart from the feedback smoother, so releasing the
+    /// hold glides back to wherever the knob is by then, automation and
+    /// all.
+    hold: Smoother,
+    hold_level: Smoother,
+
+    /// Whether the lines are frozen. Freezing is instant — the frozen
+    /// loop carries on exactly where the live read was — but releasing
+    /// crossfades back to the live delay.
+    freeze: SwitchedPath,
+
+    /// The loop being played while frozen, and while the release fades
+    /// out.
+    frozen: Option<FrozenLoop>,
+
+    /// How much overdubbing is applied, 0.0 (a plain freeze) to 1.0, so
+    /// switching it fades in and out. Along with the per-pass decay, as
+    /// a gain.
+    overdub: Smoother,
+    overdub_decay: Smoother,
+
+    /// How many samples late the external loop's return arrives. See
+    /// [`set_external_loop_latency()`](Self::set_external_loop_latency).
+    loop_latency: usize,
+
+    /// The loop gain on the last processed sample, highest across
+    /// channels. See [`loop_gain()`](Self::loop_gain).
+    loop_gain: f32,
+
+    /// How many samples late the wet path delivers its signal, summed
+    /// over every wet-path stage that adds latency. The dry signal is
+    /// held back by the same amount. See [`latency_samples()`](Self::latency_samples).
+    wet_latency: usize,
+
+    /// The latest transport state from [`set_transport()`](Self::set_transport).
+    transport: Transport,
+
+    /// What the transport stopping does, from the last `set_params()`.
+    stop_behavior: StopBehavior,
+
+    /// Where the playhead should be at the start of the next block if
+    /// the transport plays straight on, and how far one block moves it,
+    /// both in beats. Each block played with a known position and tempo
+    /// sets it; the next [`set_transport()`](Self::set_transport) takes
+    /// it to spot a jump.
+    expected_pos: Option<(f64, f64)>,
+
+    /// Whether the input has stayed silent since the transport stopped,
+    /// so the repeats still in the lines all date from before the stop.
+    quiet_since_stop: bool,
+
+    /// Watches the input alone against [`TAIL_SILENCE_DB`], to spot a
+    /// pause in a host that reports no transport.
+    input_silence: SilenceTracker,
+
+    /// The division the delay is currently locked to (`None` while not
+    /// synced), so `set_params()` can spot sync being switched on or the
+    /// division changing.
+    synced_division: Option<NoteDivision>,
+
+    /// The tempo sync follows, and where it came from, as of the last
+    /// `set_params()`.
+    sync_tempo: (f64, TempoSource),
+
+    /// The Double Time and Half Time switches, and which is in charge.
+    time_triggers: TimeTriggers,
+
+    /// A quantized delay change counting down to its beat boundary.
+    pending_delay: Option<PendingDelay>,
+
+    /// How a change of the coarse delay time is heard, the crossfade in
+    /// progress, and the latest change to arrive during it, which waits
+    /// for it to finish.
+    time_change: TimeChange,
+    time_fade: Option<TimeFade>,
+    queued_delay: Option<f32>,
+
+    /// The read head while [`TimeChange::Tape`] is the mode, and the
+    /// scrub driving it.
+    tape_head: Option<TapeHead>,
+    scrub: f32,
+
+    /// The time modulation (see [Time Modulation](self#time-modulation)):
+    /// the LFO, its depth in milliseconds, and how far it moved the delay
+    /// time on the last sample. While a change of waveform fades,
+    /// `faded_lfo` runs on in the old shape alongside.
+    time_lfo: Lfo,
+    faded_lfo: Lfo,
+    lfo_fade: Crossfade,
+    mod_depth: Smoother,
+    time_modulation_ms: f32,
+
+    /// Samples between control ticks in the per-sample paths, and how
+    /// far the clock is past the last one. The clock counts every sample
+    /// processed, whichever path takes it, so ticks fall on the same
+    /// samples however the host splits its buffers. See
+    /// [Control Rate](self#control-rate).
+    control_interval: usize,
+    control_clock: usize,
+
+    /// Whether the control ticks are spread out to save CPU (see
+    /// [`set_economy()`](Self::set_economy)).
+    economy: bool,
+
+    /// How the smoothing path walks a block. See
+    /// [Processing Order](self#processing-order).
+    processing_order: ProcessingOrder,
+
+    /// How much the parameter smoothers ramp (see
+    /// [`set_automation_smoothing()`](Self::set_automation_smoothing)).
+    automation_smoothing: AutomationSmoothing,
+
+    /// `false` until the first `set_params()`, which jumps straight to
+    /// its values instead of ramping from the defaults.
+    params_set: bool,
+}
+
+impl LovelessDelayEngine {
+    /// Create an engine for `channels` channels at `sample_rate` Hz,
+    /// with [`DelayParams::default()`] settings.
+    ///
+    /// This allocates the delay buffers, so call it from a setup path
+    /// (the plugin does so in `initialize()`), never from the audio
+    /// thread.
+    pub fn new(sample_rate: f32, channels: usize) -> Self {
+        Self::with_max_delay(sample_rate, channels, MAX_DELAY_MS)
+    }
+
+    /// Like [`new()`](Self::new), but taking no more than `budget_bytes`
+    /// of buffers (see [`memory_bytes()`](Self::memory_bytes)).
+    ///
+    /// Most of the memory is the delay lines, and they grow with the
+    /// longest delay time. So rather than fail, an engine short of memory
+    /// gives up delay time: the longest that fits, in
+    /// [`BUDGET_STEP_MS`] steps, down to [`MIN_DELAY_MS`]. Longer
+    /// settings are clamped to it. A budget too small for even that gets
+    /// an engine at [`MIN_DELAY_MS`] anyway — working, if over budget.
+    pub fn with_memory_budget(sample_rate: f32, channels: usize, budget_bytes: usize) -> Self {
+        let mut max_delay_ms = MAX_DELAY_MS;
+        while max_delay_ms > MIN_DELAY_MS
+            && Self::memory_bytes_for(sample_rate, channels, max_delay_ms) > budget_bytes
+        {
+            max_delay_ms = (max_delay_ms - BUDGET_STEP_MS).max(MIN_DELAY_MS);
+        }
+        Self::with_max_delay(sample_rate, channels, max_delay_ms)
+    }
+
+    /// How many bytes of buffers an engine for `channels` channels at
+    /// `sample_rate` takes, with lines long enough for `max_delay_ms`:
+    /// what [`memory_bytes()`](Self::memory_bytes) will say once it's
+    /// built, worked out before anything is allocated.
+    pub fn memory_bytes_for(sample_rate: f32, channels: usize, max_delay_ms: f32) -> usize {
+        let line =
+            |ms: f32| delay_line_len(ms, sample_rate).saturating_mul(std::mem::size_of::<f32>());
+        let main_line = line(read_delay_ms(max_delay_ms));
+        let per_channel = main_line
+            .saturating_add(line(MAX_PRE_DELAY_MS))
+            .saturating_add(Decorrelator::memory_bytes_for(sample_rate))
+            .saturating_add(FixedDelay::memory_bytes_for(WET_PATH_LATENCY));
+        // The mono input and the repeat tracker's counts are as long as
+        // the main lines, and there's one of each whatever the channels.
+        // Saturating, so an absurd sample rate reads as more than any
+        // budget rather than overflowing.
+        channels
+            .saturating_mul(per_channel)
+            .saturating_add(main_line.saturating_mul(2))
+    }
+
+    /// An engine whose lines hold up to `max_delay_ms`.
+    fn with_max_delay(sample_rate: f32, channels: usize, max_delay_ms: f32) -> Self {
+        let defaults = DelayParams::default();
+        let read_delay_ms = read_delay_ms(max_delay_ms);
+
+        let mut engine = Self {
+            sample_rate,
+            max_delay_ms,
+            channel_states: Vec::new(),
+            limiters: Vec::new(),
+            silence: SilenceTracker::new(TAIL_SILENCE_DB),
+            idle: SilenceTracker::new(SLEEP_SILENCE_DB),
+            asleep: false,
+            lines_shared: false,
+            // Delay times and cutoffs glide over 50ms: a delay jump moves
+            // the read position in the ring buffer, and a cutoff jump is
+            // a sudden change in tone — both want a gentle ramp.
+            // Feedback and mix are plain gains, so 20ms is enough.
+            //
+            // Cutoffs glide logarithmically, an even number of octaves
+            // per millisecond. Delay times stay linear: a linear ramp
+            // moves the read head at a constant speed, so the repeats
+            // bend by a steady pitch while it glides, like tape.
+            delay_time: Smoother::new(50.0, defaults.delay_ms),
+            time_fine: Smoother::new(50.0, defaults.time_fine_ms),
+            pre_delay: Smoother::new(50.0, defaults.pre_delay_ms),
+            // Feedback cuts land fast, so pulling the knob down stops a
+            // runaway loop almost at once.
+            feedback: Smoother::new(20.0, defaults.feedback).with_fall_ms(FEEDBACK_FALL_MS),
+            mix: Smoother::new(20.0, defaults.mix),
+            filter_cutoffs: [
+                Smoother::logarithmic(50.0, defaults.filter_cutoff),
+                Smoother::logarithmic(50.0, defaults.filter_cutoff_right),
+            ],
+            feedback_compensation: Smoother::new(20.0, 0.0),
+            post_mix: Smoother::new(20.0, post_mix_target(defaults.feedback_source)),
+            feedback_matrix: matrix_entries(defaults.feedback_matrix.normalized())
+                .map(|entry| Smoother::new(20.0, entry)),
+            drive: Smoother::new(20.0, defaults.drive),
+            shelf_gains: [defaults.fb_low_shelf_db, defaults.fb_high_shelf_db]
+                .map(|db| Smoother::logarithmic(20.0, shelf_gain(db))),
+            character: Smoother::new(50.0, defaults.character),
+            damping: Smoother::new(50.0, defaults.damping),
+            decorrelate: Smoother::new(20.0, defaults.decorrelate),
+            dry_pan: Smoother::new(20.0, defaults.dry_pan),
+            input_balance: Smoother::new(20.0, defaults.input_balance),
+            side_decay: Smoother::logarithmic(20.0, side_decay_gain(defaults.side_decay_db)),
+            spread: Smoother::new(20.0, defaults.spread),
+            bloom: Smoother::new(20.0, defaults.bloom_db),
+            bloom_envelope: BloomEnvelope::new(sample_rate),
+            key_gate: KeyGate::new(sample_rate),
+            out_lowcut: Smoother::logarithmic(50.0, defaults.out_lowcut),
+            out_highcut: Smoother::logarithmic(50.0, defaults.out_highcut),
+            output_trim: Smoother::new(50.0, db_to_gain(defaults.output_trim_db)),
+            sequencer: RepeatSequencer::new(),
+            step_target: defaults.step_target,
+            step_cutoff: Smoother::logarithmic(STEP_GLIDE_MS, HIGHCUT_OFF_HZ),
+            resync_steps: false,
+            motion: defaults.motion,
+            pan_rotor: PanRotor::new(),
+            start_side: defaults.start_side,
+            faded_motion: (defaults.motion, defaults.start_side),
+            motion_fade: Crossfade::new(FadeLaw::Linear),
+            mono_input: new_delay_line(read_delay_ms, sample_rate),
+            repeat_tracker: RepeatTracker::new(
+                new_delay_line(read_delay_ms, sample_rate),
+                sample_rate,
+            ),
+            spread_glide: RepeatGlide::new(sample_rate, 2.0),
+            // Linear, like the channel swap it repeats.
+            alternate: SwitchedPath::new(defaults.alternate, FadeLaw::Linear),
+            alternate_swap: RepeatGlide::new(sample_rate, 1.0),
+            engage: Smoother::new(ENGAGE_FADE_MS, 0.0),
+            tail_clear: TailClear::Idle,
+            clear_fade: Smoother::new(CLEAR_FADE_MS, 1.0),
+            clear_fade_ms: CLEAR_FADE_MS,
+            clear_held: false,
+            chain_order: defaults.chain_order,
+            // Both switches fade between near-identical signals, so a
+            // linear fade keeps the level steady (see `crossfade`).
+            mono_safe: SwitchedPath::new(defaults.mono_safe, FadeLaw::Linear),
+            limiter: SwitchedPath::new(defaults.limiter, FadeLaw::Linear),
+            // Linear too, so the fade can never lift the loop gain above
+            // either source's on its own.
+            external_loop: SwitchedPath::new(defaults.external_loop, FadeLaw::Linear),
+            wet_solo: SwitchedPath::new(defaults.wet_solo, FadeLaw::Linear),
+            duck_listen: SwitchedPath::new(defaults.duck_listen, FadeLaw::Linear),
+            // Linear: halfway, each side is the two sides' average,
+            // which is never louder than the louder of them.
+            swap_channels: SwitchedPath::new(defaults.swap_channels, FadeLaw::Linear),
+            // The release fades between unrelated signals — the loop and
+            // whatever the live read finds — so equal power keeps the
+            // level up through the middle.
+            freeze: SwitchedPath::new(false, FadeLaw::EqualPower),
+            frozen: None,
+            overdub: Smoother::new(20.0, if defaults.overdub { 1.0 } else { 0.0 }),
+            overdub_decay: Smoother::new(20.0, overdub_decay_gain(defaults.overdub_decay_db)),
+            watchdog: FeedbackWatchdog::new(sample_rate),
+            aligning: false,
+            phase_aligner: PhaseAligner::new(sample_rate),
+            wet_polarity: Smoother::new(POLARITY_FADE_MS, 1.0),
+            feedback_setting: defaults.feedback,
+            hold: Smoother::new(HOLD_FADE_MS, if defaults.hold { 1.0 } else { 0.0 }),
+            hold_level: Smoother::new(20.0, defaults.hold_level),
+            loop_latency: 0,
+            loop_gain: 0.0,
+            wet_latency: WET_PATH_LATENCY,
+            transport: Transport::default(),
+            stop_behavior: defaults.stop_behavior,
+            expected_pos: None,
+            quiet_since_stop: false,
+            input_silence: SilenceTracker::new(TAIL_SILENCE_DB),
+            synced_division: None,
+            sync_tempo: (f64::from(defaults.manual_bpm), TempoSource::Manual),
+            time_triggers: TimeTriggers::default(),
+            pending_delay: None,
+            time_change: defaults.time_change,
+            time_fade: None,
+            queued_delay: None,
+            tape_head: None,
+            scrub: 0.0,
+            time_lfo: Lfo::new(),
+            faded_lfo: Lfo::new(),
+            lfo_fade: Crossfade::new(FadeLaw::Linear),
+            mod_depth: Smoother::new(50.0, defaults.mod_depth_ms),
+            time_modulation_ms: 0.0,
+            control_interval: control_interval_for(sample_rate),
+            control_clock: 0,
+            economy: false,
+            processing_order: ProcessingOrder::default(),
+            automation_smoothing: AutomationSmoothing::default(),
+            params_set: false,
+        };
+        engine.set_channels(channels);
+        // Every line starts out silent, so the first two start out as one.
+        engine.lines_shared = true;
+        engine.engage.set_target(sample_rate, 1.0);
+        engine
+    }
+
+    /// The sample rate this engine was created for.
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// How many channels this engine processes.
+    pub fn channels(&self) -> usize {
+        self.channel_states.len()
+    }
+
+    /// The longest delay time this engine's lines hold, in milliseconds:
+    /// [`MAX_DELAY_MS`] unless a memory budget cut it short.
+    pub fn max_delay_ms(&self) -> f32 {
+        self.max_delay_ms
+    }
+
+    /// How many bytes of buffers the engine holds: every delay line,
+    /// allpass and compensation delay, as allocated. The rest of its
+    /// state is a few KB at most.
+    pub fn memory_bytes(&self) -> usize {
+        let per_channel: usize = self
+            .channel_states
+            .iter()
+            .map(|state| {
+                state.delay_line.memory_bytes()
+                    + state.pre_delay_line.memory_bytes()
+                    + state.decorrelator.memory_bytes()
+                    + state
+                        .dry_compensation
+                        .as_ref()
+                        .map_or(0, |compensation| compensation.line.memory_bytes())
+            })
+            .sum();
+        per_channel + self.mono_input.memory_bytes() + self.repeat_tracker.memory_bytes()
+    }
+
+    /// How many samples apart the control ticks are: a millisecond's
+    /// worth, rounded down, and no more than [`MAX_CONTROL_INTERVAL`] —
+    /// times [`ECONOMY_CONTROL_FACTOR`] in economy. See
+    /// [Control Rate](self#control-rate).
+    pub fn control_interval(&self) -> usize {
+        self.control_interval
+    }
+
+    /// Override the control interval. 1 puts every update back on every
+    /// sample, which is mainly useful for comparing against: the
+    /// `process_benchmark` example does. Values are kept within
+    /// 1..=[`MAX_CONTROL_INTERVAL`], and economy still spreads them out.
+    pub fn set_control_interval(&mut self, samples: usize) {
+        self.control_interval = samples.clamp(1, MAX_CONTROL_INTERVAL);
+        if self.economy {
+            self.control_interval *= ECONOMY_CONTROL_FACTOR;
+        }
+        self.control_clock = 0;
+    }
+
+    /// Whether the engine is in economy.
+    pub fn economy(&self) -> bool {
+        self.economy
+    }
+
+    /// Save CPU while the host's deadline is tight, or stop saving it:
+    /// in economy the control ticks come [`ECONOMY_CONTROL_FACTOR`]
+    /// times further apart (see [Control Rate](self#control-rate)). The
+    /// plugin switches it from its load monitor (see
+    /// [`load_monitor`](crate::load_monitor)).
+    ///
+    /// Switching is click-free either way: a glide under way carries on
+    /// from where it is to the next tick's value, over the new interval.
+    pub fn set_economy(&mut self, on: bool) {
+        if on == self.economy {
+            return;
+        }
+        let interval = if self.economy {
+            self.control_interval / ECONOMY_CONTROL_FACTOR
+        } else {
+            self.control_interval
+        };
+        self.economy = on;
+        self.set_control_interval(interval);
+    }
+
+    /// How the smoothing path walks a block: channel-major unless set
+    /// otherwise. See [Processing Order](self#processing-order).
+    pub fn processing_order(&self) -> ProcessingOrder {
+        self.processing_order
+    }
+
+    /// Choose how the smoothing path walks a block. The output is the
+    /// same either way, so this is for comparing speed: the
+    /// `channel_order_benchmark` example does.
+    pub fn set_processing_order(&mut self, order: ProcessingOrder) {
+        self.processing_order = order;
+    }
+
+    /// How much the parameter smoothers ramp.
+    pub fn automation_smoothing(&self) -> AutomationSmoothing {
+        self.automation_smoothing
+    }
+
+    /// Shorten the ramps parameter changes get, for hosts whose
+    /// automation arrives smooth already, or put them back. The plugin
+    /// sets it from a saved option every block.
+    ///
+    /// It covers the knobs a host automates as levels and tones: gains,
+    /// feedback, mix, filters, drive and the like. The times are left
+    /// alone — a read head moved in a couple of milliseconds chirps
+    /// however it's ramped, and how they move is the time change mode's
+    /// business — and so are the fades that switches run.
+    ///
+    /// Ramps under way finish as they started; the next change takes the
+    /// new length.
+    pub fn set_automation_smoothing(&mut self, smoothing: AutomationSmoothing) {
+        if smoothing == self.automation_smoothing {
+            return;
+        }
+        self.automation_smoothing = smoothing;
+        let scale = smoothing.ramp_scale();
+        for smoother in self.automation_smoothers() {
+            smoother.set_ramp_scale(scale);
+        }
+    }
+
+    /// The smoothers [`set_automation_smoothing()`](Self::set_automation_smoothing)
+    /// acts on.
+    fn automation_smoothers(&mut self) -> [&mut Smoother; 24] {
+        let [cutoff_left, cutoff_right] = &mut self.filter_cutoffs;
+        let [ll, lr, rl, rr] = &mut self.feedback_matrix;
+        let [low_shelf, high_shelf] = &mut self.shelf_gains;
+        [
+            &mut self.feedback,
+            &mut self.hold_level,
+            &mut self.mix,
+            cutoff_left,
+            cutoff_right,
+            ll,
+            lr,
+            rl,
+            rr,
+            &mut self.drive,
+            low_shelf,
+            high_shelf,
+            &mut self.character,
+            &mut self.damping,
+            &mut self.decorrelate,
+            &mut self.dry_pan,
+            &mut self.input_balance,
+            &mut self.side_decay,
+            &mut self.spread,
+            &mut self.bloom,
+            &mut self.overdub_decay,
+            &mut self.out_lowcut,
+            &mut self.out_highcut,
+            &mut self.output_trim,
+        ]
+    }
+
+    /// Change the channel count, keeping the state of the channels that
+    /// exist both before and after.
+    ///
+    /// Some hosts re-initialize a plugin with a different layout while it
+    /// is running (stereo → mono and back). Shrinking drops the extra
+    /// channels' buffers entirely; growing adds *fresh*, silent ones. So
+    /// going stereo → mono → stereo keeps the left channel's echoes, and
+    /// the old right channel's audio can never reappear.
+    ///
+    /// Growing allocates, so like [`new()`](Self::new) this belongs on a
+    /// setup path, never the audio thread.
+    pub fn set_channels(&mut self, channels: usize) {
+        // A fresh channel is silent whatever the first one holds.
+        self.split_lines();
+        self.channel_states.truncate(channels);
+        self.limiters.truncate(channels);
+
+        while self.channel_states.len() < channels {
+            let channel_idx = self.channel_states.len();
+            let mut feedback_chain = FeedbackChain::new(self.sample_rate);
+            feedback_chain.set_order(self.chain_order);
+            self.channel_states.push(ChannelState {
+                delay_line: new_delay_line(read_delay_ms(self.max_delay_ms), self.sample_rate),
+                pre_delay_line: new_delay_line(MAX_PRE_DELAY_MS, self.sample_rate),
+                feedback_chain,
+                crossover: new_crossover(self.sample_rate),
+                damping: LoopDamping::new(self.sample_rate),
+                output_filter: OutputFilter::new(self.sample_rate),
+                clear_gain: 1.0,
+                dry_gain: 1.0,
+                decorrelator: Decorrelator::new(self.sample_rate, side(channel_idx) == 1),
+                decorrelate: 0.0,
+                wet_polarity: 1.0,
+                phase_meter: self.aligning.then(PhaseMeter::default),
+                panned_dry: None,
+                key_gain: 1.0,
+                bloom_gain: 1.0,
+                balance: 1.0,
+                compensation: 1.0,
+                dry_feed: 0.0,
+                frozen_tap: None,
+                time_tap: None,
+                dry_compensation: FixedDelay::new(self.wet_latency),
+                chain_latency: FEEDBACK_PATH_LATENCY,
+                #[cfg(test)]
+                chain_stage: None,
+            });
+            self.limiters.push(Limiter::new(self.sample_rate));
+        }
+    }
+
+    /// [`set_channels()`](Self::set_channels), if the buffers for
+    /// `channels` channels fit in `budget_bytes`. Returns whether it
+    /// changed anything: growth past the budget is refused, leaving the
+    /// engine as it was, for the caller to build a smaller one (see
+    /// [`with_memory_budget()`](Self::with_memory_budget)). Shrinking
+    /// always fits.
+    pub fn set_channels_within(&mut self, channels: usize, budget_bytes: usize) -> bool {
+        let bytes = Self::memory_bytes_for(self.sample_rate, channels, self.max_delay_ms);
+        if channels > self.channels() && bytes > budget_bytes {
+            return false;
+        }
+        self.set_channels(channels);
+        true
+    }
+
+    /// Apply new parameter values.
+    ///
+    /// Continuous values glide to their new settings over a few
+    /// milliseconds, starting at the next processed sample. The very
+    /// first call after [`new()`](Self::new) takes effect immediately,
+    /// so an engine set up for an offline render doesn't start with a
+    /// ramp from the defaults.
+    ///
+    /// Calling this every block with unchanged values is cheap and
+    /// doesn't disturb ramps in progress.
+    ///
+    /// A one-channel engine runs the [mono
+    /// fold](DelayParams::folded_to_mono) of `params`.
+    pub fn set_params(&mut self, params: &DelayParams) {
+        let folded;
+        let params = if self.channel_states.len() == 1 {
+            folded = params.folded_to_mono();
+            &folded
+        } else {
+            params
+        };
+
+        self.scrub = params
+            .scrub
+            .clamp(-tape_head::MAX_SCRUB, tape_head::MAX_SCRUB);
+        self.set_tape_mode(params.time_change == TimeChange::Tape);
+        self.time_change = params.time_change;
+        self.set_delay_target(params);
+        self.set_time_lfo(params);
+        let time_fine = params
+            .time_fine_ms
+            .clamp(-MAX_TIME_FINE_MS, MAX_TIME_FINE_MS);
+
+        self.feedback_setting = params.feedback.clamp(0.0, MAX_FEEDBACK);
+        self.watchdog
+            .set_hold_seconds(params.watchdog.then_some(params.watchdog_seconds));
+        let feedback = self.watched_feedback();
+        let pre_delay_ms = params.pre_delay_ms.clamp(0.0, MAX_PRE_DELAY_MS);
+        let mix = params.mix.clamp(0.0, 1.0);
+        let drive = params.drive.clamp(0.0, 1.0);
+        let character = params.character.clamp(0.0, 1.0);
+        let damping = params.damping.clamp(0.0, 1.0);
+        let decorrelate = params.decorrelate.clamp(0.0, 1.0);
+        let hold = if params.hold { 1.0 } else { 0.0 };
+        let hold_level = params.hold_level.clamp(0.0, 1.0);
+        let dry_pan = params.dry_pan.clamp(-1.0, 1.0);
+        let input_balance = params.input_balance.clamp(-1.0, 1.0);
+        let compensation = if params.feedback_compensation {
+            1.0
+        } else {
+            0.0
+        };
+        let spread = params.spread.clamp(0.0, 1.0);
+        let bloom_db = params.bloom_db.clamp(0.0, MAX_BLOOM_DB);
+        let overdub = if params.overdub { 1.0 } else { 0.0 };
+        let mod_depth_ms = params.mod_depth_ms.clamp(0.0, MAX_MOD_DEPTH_MS);
+
+        self.key_gate.set_enabled(params.key_gate);
+        self.key_gate
+            .set_threshold_db(params.key_threshold_db.clamp(-60.0, 0.0));
+        self.key_gate
+            .set_release_ms(params.key_release_ms.clamp(10.0, 1000.0));
+        if !self.params_set {
+            self.key_gate.reset();
+            self.bloom_envelope.reset();
+        }
+
+        self.sequencer.set_steps(params.steps);
+        self.sequencer.set_length(params.step_length);
+        self.step_target = params.step_target;
+        let routing = (params.motion, params.start_side);
+        if self.params_set && routing != (self.motion, self.start_side) {
+            // Rewired between two samples, the pair would click: fade
+            // from the old routing to the new instead.
+            self.faded_motion = (self.motion, self.start_side);
+            self.motion_fade
+                .start((SWITCH_FADE_MS / 1000.0 * self.sample_rate) as usize);
+        }
+        (self.motion, self.start_side) = routing;
+        self.pan_rotor.set_rotation_degrees(params.motion_rotation);
+        if params.clear && !self.clear_held {
+            self.start_clear(CLEAR_FADE_MS);
+        }
+        self.stop_behavior = params.stop_behavior;
+        self.clear_held = params.clear;
+        let step_cutoff = match self.step_target {
+            StepTarget::Off => HIGHCUT_OFF_HZ,
+            StepTarget::Cutoff => step_to_cutoff(self.sequencer.value()),
+        };
+        // The filters play anything above the ceiling as the ceiling, so
+        // the smoothers stop there too: at 8 kHz a sweep down from a 20
+        // kHz setting would otherwise spend half its glide above 3.9 kHz,
+        // changing nothing, then rush through the rest.
+        let cutoff_ceiling = max_cutoff_hz(self.sample_rate);
+
+        let [cutoff_left, cutoff_right] = &mut self.filter_cutoffs;
+        let [ll, lr, rl, rr] = &mut self.feedback_matrix;
+        let [ll_gain, lr_gain, rl_gain, rr_gain] =
+            matrix_entries(params.feedback_matrix.normalized());
+        let [low_shelf, high_shelf] = &mut self.shelf_gains;
+        let targets = [
+            (&mut self.time_fine, time_fine),
+            (&mut self.mod_depth, mod_depth_ms),
+            (&mut self.pre_delay, pre_delay_ms),
+            (&mut self.feedback, feedback),
+            (&mut self.hold, hold),
+            (&mut self.hold_level, hold_level),
+            (&mut self.mix, mix),
+            (cutoff_left, params.filter_cutoff.min(cutoff_ceiling)),
+            (cutoff_right, params.filter_cutoff_right.min(cutoff_ceiling)),
+            (&mut self.feedback_compensation, compensation),
+            (&mut self.post_mix, post_mix_target(params.feedback_source)),
+            (ll, ll_gain),
+            (lr, lr_gain),
+            (rl, rl_gain),
+            (rr, rr_gain),
+            (&mut self.drive, drive),
+            (low_shelf, shelf_gain(params.fb_low_shelf_db)),
+            (high_shelf, shelf_gain(params.fb_high_shelf_db)),
+            (&mut self.character, character),
+            (&mut self.damping, damping),
+            (&mut self.decorrelate, decorrelate),
+            (&mut self.dry_pan, dry_pan),
+            (&mut self.input_balance, input_balance),
+            (&mut self.side_decay, side_decay_gain(params.side_decay_db)),
+            (&mut self.spread, spread),
+            (&mut self.bloom, bloom_db),
+            (&mut self.overdub, overdub),
+            (
+                &mut self.overdub_decay,
+                overdub_decay_gain(params.overdub_decay_db),
+            ),
+            (&mut self.out_lowcut, params.out_lowcut),
+            (&mut self.out_highcut, params.out_highcut),
+            (&mut self.step_cutoff, step_cutoff),
+            (&mut self.output_trim, db_to_gain(params.output_trim_db)),
+        ];
+        for (smoother, value) in targets {
+            if self.params_set {
+                smoother.set_target(self.sample_rate, value);
+            } else {
+                smoother.reset(value);
+            }
+        }
+
+        // Switching the order is instant. With drive at zero it makes no
+        // difference at all; with drive up it changes the tone of the
+        // repeats already in the loop, which is the point.
+        self.chain_order = params.chain_order;
+        for state in &mut self.channel_states {
+            state.feedback_chain.set_order(self.chain_order);
+        }
+
+        // Start the crossovers (and limiters) from silence when their
+        // path comes back on, rather than from whatever they last saw.
+        if params.mono_safe && !self.mono_safe.on_path_needed() {
+            for state in &mut self.channel_states {
+                state.crossover.reset();
+            }
+        }
+        if params.limiter && !self.limiter.on_path_needed() {
+            for limiter in &mut self.limiters {
+                limiter.reset();
+            }
+        }
+
+        let fade_samples = (SWITCH_FADE_MS / 1000.0 * self.sample_rate) as usize;
+        for (switch, on) in [
+            (&mut self.mono_safe, params.mono_safe),
+            (&mut self.limiter, params.limiter),
+            (&mut self.external_loop, params.external_loop),
+            (&mut self.wet_solo, params.wet_solo),
+            (&mut self.duck_listen, params.duck_listen),
+            (&mut self.swap_channels, params.swap_channels),
+            (&mut self.alternate, params.alternate),
+        ] {
+            if self.params_set {
+                switch.set(on, fade_samples);
+            } else {
+                switch.jump(on);
+            }
+        }
+        if params.freeze && !self.freeze.is_on() {
+            self.capture_freeze(params.freeze_quantize);
+        } else if !params.freeze && self.freeze.is_on() {
+            self.freeze.set(false, fade_samples);
+        }
+
+        // Judged by the delay the knobs are heading for, so a sweep out
+        // of range lets go once, not sample by sample.
+        let delay_ms = effective_delay_ms(
+            self.delay_time.target(),
+            self.time_fine.target(),
+            self.max_delay_ms,
+        );
+        self.set_aligning(params.phase_align && delay_ms < phase_align::MAX_DELAY_MS);
+        if !self.params_set {
+            if let Some(head) = &mut self.tape_head {
+                head.reset(delay_ms);
+            }
+        }
+
+        self.params_set = true;
+    }
+
+    /// Put the tape head (see [`TimeChange::Tape`]) in or out of charge
+    /// of the read position. Into Tape mode the head starts where the
+    /// read is heard, locked. Out of it, a head Scrub has run off its
+    /// time crossfades back there, as a sync switch does: a glide home
+    /// from a second or two away would sweep the repeats up by octaves
+    /// in a moment. A head within [`SYNC_GLIDE_MS`] — or one that's
+    /// already the far side of a crossfade — hands its place to the
+    /// coarse time, and the new mode moves on from there.
+    fn set_tape_mode(&mut self, on: bool) {
+        if on == self.tape_head.is_some() {
+            return;
+        }
+        if on {
+            // The modulation rides on top of the head, not in it.
+            self.tape_head = Some(TapeHead::new(
+                self.sample_rate,
+                self.current_delay_ms() - self.time_modulation_ms,
+                MIN_DELAY_MS,
+                self.max_delay_ms,
+            ));
+        } else if let Some(head) = self.tape_head.take() {
+            let heard_ms = head.delay_ms() - self.time_fine.current();
+            if self.time_fade.is_none()
+                && (heard_ms - self.delay_time.current()).abs() >= SYNC_GLIDE_MS
+            {
+                self.start_time_fade(heard_ms);
+            } else {
+                self.delay_time.reset(heard_ms);
+            }
+        }
+    }
+
+    /// Set the time modulation's LFO up from `params`. A change of
+    /// waveform that would be heard fades from the old shape to the new
+    /// (see [Time Modulation](self#time-modulation)).
+    fn set_time_lfo(&mut self, params: &DelayParams) {
+        if self.params_set && self.modulating() && params.mod_waveform != self.time_lfo.waveform() {
+            self.faded_lfo.clone_from(&self.time_lfo);
+            self.lfo_fade
+                .start((MOD_SWITCH_FADE_MS / 1000.0 * self.sample_rate) as usize);
+        }
+        self.time_lfo.set_waveform(params.mod_waveform);
+        let rate_hz = params
+            .mod_rate_hz
+            .clamp(MOD_RATE_RANGE_HZ.0, MOD_RATE_RANGE_HZ.1);
+        for lfo in [&mut self.time_lfo, &mut self.faded_lfo] {
+            lfo.set_rate_hz(rate_hz, self.sample_rate);
+            lfo.set_glide(params.mod_glide);
+        }
+    }
+
+    /// Put phase alignment in or out of circuit. Either way it starts
+    /// over, with the wet signal heading back the right way up.
+    fn set_aligning(&mut self, on: bool) {
+        if on == self.aligning {
+            return;
+        }
+        self.aligning = on;
+        self.phase_aligner.reset();
+        for state in &mut self.channel_states {
+            state.phase_meter = on.then(PhaseMeter::default);
+        }
+        if self.params_set {
+            self.wet_polarity.set_target(self.sample_rate, 1.0);
+        } else {
+            self.wet_polarity.reset(1.0);
+        }
+    }
+
+    /// Apply a whole restored state at once: a preset, or a project
+    /// reopened over a running engine.
+    ///
+    /// [`set_params()`](Self::set_params) would ramp every value from
+    /// where it was, and with everything moving together that's a sweep
+    /// through settings nobody chose — the delay time and the cutoff
+    /// gliding across each other on live input. A load is a jump, so it
+    /// jumps:
+    ///
+    /// - every smoother lands on its new value, as on the first
+    ///   `set_params()`, and every switch goes straight to its new
+    ///   position;
+    /// - any time crossfade, queued delay time, or quantized change
+    ///   still waiting for its beat is dropped;
+    /// - the filters forget what they heard and take their new corners
+    ///   at once, rather than gliding to them on the next control tick;
+    /// - the wet signal mutes and fades back in over
+    ///   [`ENGAGE_FADE_MS`], as after a [`reset()`](Self::reset), so the
+    ///   jump in the read heads isn't heard as a click.
+    ///
+    /// The delay lines keep what's in them: the old repeats carry on
+    /// under the new settings.
+    pub fn load_params(&mut self, params: &DelayParams) {
+        self.pending_delay = None;
+        self.time_fade = None;
+        self.queued_delay = None;
+        self.motion_fade.start(0);
+        self.restart_time_lfo();
+        self.params_set = false;
+        self.set_params(params);
+
+        let offsets = CharacterOffsets::at(self.character.current());
+        let filter_cutoffs = self.filter_cutoffs.each_ref().map(Smoother::current);
+        let damping = self.damping.current();
+        let out_lowcut = self.out_lowcut.current();
+        let out_highcut = self.out_highcut.current().min(self.step_cutoff.current());
+        for (channel_idx, state) in self.channel_states.iter_mut().enumerate() {
+            state.feedback_chain.reset();
+            state.crossover.reset();
+            state.damping.reset();
+            state.output_filter.reset();
+            state.decorrelator.reset();
+            let cutoff = state
+                .damping
+                .cutoff(offsets.cutoff(filter_cutoffs[side(channel_idx)]), damping);
+            state
+                .feedback_chain
+                .filter
+                .jump_to_cutoff(cutoff, self.sample_rate);
+            state
+                .output_filter
+                .set_cutoffs(out_lowcut, out_highcut, self.sample_rate);
+        }
+        for limiter in &mut self.limiters {
+            limiter.reset();
+        }
+        self.phase_aligner.reset();
+        self.wet_polarity.reset(1.0);
+        self.engage.reset(0.0);
+        self.engage.set_target(self.sample_rate, 1.0);
+    }
+
+    /// Tell the engine where the host's transport is. Call this before
+    /// [`set_params()`](Self::set_params) each block; tempo sync and the
+    /// quantized start of a synced change read it from there.
+    ///
+    /// When the transport has just stopped, this is also where
+    /// [`DelayParams::stop_behavior`] acts: a fade or a clear runs
+    /// through the panic clear's machinery (see
+    /// [`next_clear_gain()`](Self::next_clear_gain)), just with its own
+    /// fade time.
+    ///
+    /// # Gaps in Playback
+    ///
+    /// Not every host says when it stops. Some just stop calling
+    /// `process()`, and never call `reset()` either, so whatever was
+    /// ringing when playback paused comes back, stale, when it resumes —
+    /// ten seconds or ten minutes later. The engine can't see time it
+    /// wasn't given, but it can see the signs of a gap:
+    ///
+    /// ```text
+    /// playhead jumps ahead      more than a block past where the last
+    ///                           block should have left it
+    /// play after a quiet stop   the transport starts again, and the input
+    ///                           has been silent since it stopped
+    /// ```
+    ///
+    /// Either means the repeats in the lines belong to playback that's
+    /// over, and nobody has heard them since. With the stop behavior set
+    /// to fade or clear, they're cut at once rather than faded: there's
+    /// no tail anyone is listening to to let down gently, and a fade
+    /// would take the first moments of the new playing with it (see
+    /// [`next_clear_gain()`](Self::next_clear_gain)). Ring Out leaves
+    /// them, as it would across a stop. A loop jumping back is normal
+    /// playback, not a gap, and lines that have already gone silent
+    /// have nothing to clear.
+    ///
+    /// A host that reports no transport at all — not playing, and no
+    /// position — gives neither sign. For those, input that stays silent
+    /// for [`GAP_SILENCE_MS`] counts as a stop: the stop behavior's clear
+    /// runs (over [`CLEAR_FADE_MS`], since the tail may still be ringing)
+    /// from the top of the block in which the silence gets that long.
+    pub fn set_transport(&mut self, transport: Transport) {
+        let expected_pos = self.expected_pos.take();
+        if self.transport.playing && !transport.playing {
+            match self.stop_behavior {
+                StopBehavior::RingOut => {}
+                StopBehavior::Fade => self.start_clear(STOP_FADE_MS),
+                StopBehavior::Clear => self.start_clear(CLEAR_FADE_MS),
+            }
+            self.quiet_since_stop = true;
+        } else if !self.transport.playing && transport.playing {
+            if self.quiet_since_stop {
+                self.clear_after_gap(0.0);
+            }
+        } else if let (true, Some((expected, block_beats)), Some(pos)) =
+            (transport.playing, expected_pos, transport.pos_beats)
+        {
+            if pos - expected > block_beats {
+                self.clear_after_gap(0.0);
+            }
+        }
+        self.transport = transport;
+    }
+
+    /// The tempo sync follows, in beats per minute, and whether it's the
+    /// host's or [`DelayParams::manual_bpm`], as of the last
+    /// [`set_params()`](Self::set_params). Sync off, it's what sync
+    /// would follow. For an editor to show which one is in charge.
+    pub fn sync_tempo(&self) -> (f64, TempoSource) {
+        self.sync_tempo
+    }
+
+    /// Clear repeats left over from before a gap in playback over
+    /// `fade_ms`, if the stop behavior clears them at all. See
+    /// [`set_transport()`](Self::set_transport).
+    fn clear_after_gap(&mut self, fade_ms: f32) {
+        let stale = self.silence.silent_samples() < self.mono_input.capacity();
+        if stale && self.stop_behavior != StopBehavior::RingOut {
+            self.start_clear(fade_ms);
+        }
+    }
+
+    /// Look at a block's input before it's processed, for the pauses
+    /// [`set_transport()`](Self::set_transport) can't see from the
+    /// transport, and work out where the playhead should be when the
+    /// next block starts.
+    fn watch_for_gaps(&mut self, channels: &[&mut [f32]]) {
+        let num_samples = block_len(channels);
+        let inputs = || channels.iter().take(self.channel_states.len());
+        let first_loud = inputs()
+            .filter_map(|samples| samples.iter().position(|s| self.input_silence.is_loud(*s)))
+            .min();
+        let last_loud = inputs()
+            .filter_map(|samples| self.input_silence.last_loud_index(samples))
+            .max();
+        if last_loud.is_some() {
+            self.quiet_since_stop = false;
+        }
+
+        let reported = self.transport.playing || self.transport.pos_beats.is_some();
+        let gap_samples = (GAP_SILENCE_MS / 1000.0 * self.sample_rate) as usize;
+        let silent_before = self.input_silence.silent_samples();
+        let silent_through = silent_before.saturating_add(first_loud.unwrap_or(num_samples));
+        if !reported && silent_before < gap_samples && silent_through >= gap_samples {
+            self.clear_after_gap(CLEAR_FADE_MS);
+        }
+        self.input_silence.update(last_loud, num_samples);
+
+        self.expected_pos = match self.transport {
+            Transport {
+                tempo: Some(bpm),
+                pos_beats: Some(pos),
+                playing: true,
+            } if bpm > 0.0 => {
+                let block_beats = num_samples as f64 / f64::from(self.sample_rate) * bpm / 60.0;
+                Some((pos + block_beats, block_beats))
+            }
+            _ => None,
+        };
+    }
+
+    /// Point the coarse delay time at its new value: the knob's
+    /// milliseconds, or the division's length at the current tempo while
+    /// synced, times the multiplier.
+    ///
+    /// # Double and half time
+    ///
+    /// A held [`DelayParams::double_time`] or
+    /// [`DelayParams::half_time`] scales the knob's time, or shifts the
+    /// division, before the multiplier. Pressing or releasing one always
+    /// crossfades, and doesn't wait for the beat: it's played, so it
+    /// happens now, taking any change still waiting for the beat with
+    /// it. The gesture is worked out afresh from the knob every time, so
+    /// automation of the base time underneath it is never lost: on
+    /// release the time lands wherever the knob has got to.
+    ///
+    /// The fine offset is added later, per sample, and only the sum is
+    /// clamped to the delay's range. The coarse time is held within the
+    /// range widened by [`MAX_TIME_FINE_MS`] each way: any further out,
+    /// no offset could bring it back in.
+    ///
+    /// # Quantized start
+    ///
+    /// Switching sync on (or picking a new division) halfway through a
+    /// beat would start the new echo spacing at an arbitrary point, so
+    /// the echoes land off the grid until the ramp happens to line up.
+    /// With [`DelayParams::quantize_start`] on, the change instead waits
+    /// for the next beat boundary: we work out how many samples away it
+    /// is from the host's beat position and count them down, across as
+    /// many blocks as it takes. Without a beat position there's no grid
+    /// to wait for, so the change happens immediately.
+    ///
+    /// # Switching sync on and off
+    ///
+    /// Turning sync on or off swaps one source of delay time for
+    /// another, and the two rarely agree. A glide from one to the other
+    /// would bend every repeat in the line, so the switch crossfades
+    /// instead, whatever [`DelayParams::time_change`] says — from
+    /// wherever the time is, even halfway through a glide. (The plugin
+    /// can also make the two agree before the switch; see
+    /// [`sync_handover`](crate::sync_handover).) Moves under
+    /// [`SYNC_GLIDE_MS`] still glide.
+    ///
+    /// # Tempo
+    ///
+    /// Sync follows the host's tempo while it reports one, and
+    /// [`DelayParams::manual_bpm`] while it doesn't, decided afresh
+    /// every block (see [`sync_tempo()`](Self::sync_tempo)). A host that
+    /// starts or stops reporting mid-session swaps one tempo for the
+    /// other, and the two needn't agree, so that's handed over like a
+    /// sync switch: crossfaded, unless the time barely moves.
+    fn set_delay_target(&mut self, params: &DelayParams) {
+        let (bpm, source) = match self.transport.tempo.filter(|bpm| *bpm > 0.0) {
+            Some(bpm) => (bpm, TempoSource::Host),
+            None => {
+                let (min, max) = MANUAL_BPM_RANGE;
+                let bpm = params.manual_bpm.clamp(min, max);
+                (f64::from(bpm), TempoSource::Manual)
+            }
+        };
+        let source_switched = source != self.sync_tempo.1;
+        self.sync_tempo = (bpm, source);
+        let multiplier = params.time_multiplier.factor();
+        let was_gesture = self.time_triggers.active();
+        let gesture = self
+            .time_triggers
+            .update(params.double_time, params.half_time);
+        let gesture_changed = gesture != was_gesture;
+        let delay_ms = if params.sync {
+            gesture.shift(params.division).to_ms(bpm) as f32
+        } else {
+            params
+                .time_snap
+                .apply(params.delay_ms, MIN_DELAY_MS, self.max_delay_ms)
+                * gesture.factor()
+        } * multiplier;
+        let delay_ms = delay_ms.clamp(
+            MIN_DELAY_MS - MAX_TIME_FINE_MS,
+            self.max_delay_ms + MAX_TIME_FINE_MS,
+        );
+
+        let synced_division = params.sync.then_some(params.division);
+        let sync_changed = synced_division.is_some() && synced_division != self.synced_division;
+        let sync_switched = synced_division.is_some() != self.synced_division.is_some();
+        self.synced_division = synced_division;
+
+        // A synced delay's period is a fixed note length, so the step
+        // pattern can follow the song: pin it to the host's position
+        // whenever sync starts, the division changes, or playback
+        // restarts.
+        if let (Some(division), Some(pos)) = (synced_division, self.transport.pos_beats) {
+            if sync_changed || self.resync_steps {
+                let period = calculate_delay_samples(
+                    effective_delay_ms(
+                        delay_ms,
+                        params
+                            .time_fine_ms
+                            .clamp(-MAX_TIME_FINE_MS, MAX_TIME_FINE_MS),
+                        self.max_delay_ms,
+                    ),
+                    self.sample_rate,
+                );
+                let period_beats = gesture.shift(division).beats() * f64::from(multiplier);
+                self.sequencer.sync(pos / period_beats, period);
+                self.resync_steps = false;
+            }
+        }
+
+        if !self.params_set {
+            self.delay_time.reset(delay_ms);
+            return;
+        }
+
+        let handed_over = sync_switched || (source_switched && synced_division.is_some());
+        let change = if gesture_changed
+            || (handed_over && (delay_ms - self.delay_time.target()).abs() >= SYNC_GLIDE_MS)
+        {
+            TimeChange::Crossfade
+        } else {
+            self.time_change
+        };
+        if params.quantize_start && synced_division.is_some() && !gesture_changed {
+            if let (true, Some(pos)) = (sync_changed, self.transport.pos_beats) {
+                let samples_left = samples_to_next_beat(pos, bpm, self.sample_rate).round();
+                self.pending_delay = Some(PendingDelay {
+                    delay_ms,
+                    samples_left: samples_left as usize,
+                    change,
+                });
+                return;
+            }
+            // Still waiting for the beat: keep following tempo changes.
+            if let Some(pending) = &mut self.pending_delay {
+                pending.delay_ms = delay_ms;
+                return;
+            }
+        }
+
+        self.pending_delay = None;
+        self.retarget_delay(delay_ms, change);
+    }
+
+    /// Move the coarse delay time to `delay_ms`, gliding or crossfading
+    /// as `change` says (usually [`DelayParams::time_change`]).
+    ///
+    /// A crossfade jumps the smoother straight to the new time, and a
+    /// second head reads the old time while the fade runs (see
+    /// [`next_time_tap()`](Self::next_time_tap)). There's only the one
+    /// spare head, so a change that arrives mid-fade waits for the fade
+    /// to finish, and if several arrive, only the latest is kept. A
+    /// glide that arrives while one waits — the knob moving on after
+    /// Double Time let go, say — moves the waiting change rather than
+    /// dropping it: gliding from where the time was would sweep the
+    /// whole distance the change was to jump.
+    ///
+    /// In Tape mode the smoother jumps too, and the tape head runs after
+    /// it (see [`next_delay_ms()`](Self::next_delay_ms)). A crossfade
+    /// there — Double Time, say — fades out of wherever the tape head
+    /// had got to, and jumps the head along with the smoother.
+    fn retarget_delay(&mut self, delay_ms: f32, change: TimeChange) {
+        if change == TimeChange::Glide {
+            match &mut self.queued_delay {
+                Some(queued) => *queued = delay_ms,
+                None => self.delay_time.set_target(self.sample_rate, delay_ms),
+            }
+        } else if change == TimeChange::Tape {
+            self.queued_delay = None;
+            self.delay_time.reset(delay_ms);
+        } else if self.time_fade.is_some() {
+            self.queued_delay = Some(delay_ms);
+        } else if delay_ms != self.delay_time.target() {
+            let from_ms = match &self.tape_head {
+                Some(head) => head.delay_ms() - self.time_fine.current(),
+                None => self.delay_time.current(),
+            };
+            self.start_time_fade(from_ms);
+            self.delay_time.reset(delay_ms);
+            let heard_ms =
+                effective_delay_ms(delay_ms, self.time_fine.current(), self.max_delay_ms);
+            if let Some(head) = &mut self.tape_head {
+                head.reset(heard_ms);
+            }
+        }
+    }
+
+    /// Start the old head of a time crossfade reading at `from_ms` (a
+    /// coarse time), fading over to wherever the new head reads.
+    fn start_time_fade(&mut self, from_ms: f32) {
+        let mut fade = Crossfade::new(FadeLaw::EqualPower);
+        fade.start((TIME_FADE_MS / 1000.0 * self.sample_rate) as usize);
+        self.time_fade = Some(TimeFade { from_ms, fade });
+    }
+
+    /// Clear all audio state: empty the delay lines, reset the filters
+    /// and limiters, and finish any parameter ramps.
+    ///
+    /// The plugin calls this when playback stops, so that stale audio
+    /// doesn't bleed into the next playback. Without this, pressing
+    /// "play" after "stop" might produce a burst of old echoes.
+    pub fn reset(&mut self) {
+        // Every line is about to be silent.
+        self.lines_shared = true;
+        for state in &mut self.channel_states {
+            state.delay_line.clear();
+            state.pre_delay_line.clear();
+            state.feedback_chain.reset();
+            state.crossover.reset();
+            state.damping.reset();
+            state.output_filter.reset();
+            state.decorrelator.reset();
+            if let Some(compensation) = &mut state.dry_compensation {
+                compensation.line.clear();
+            }
+        }
+        // With playback stopped there's no beat to wait for, and no
+        // old time worth fading out of.
+        if let Some(pending) = self.pending_delay.take() {
+            self.delay_time
+                .set_target(self.sample_rate, pending.delay_ms);
+        }
+        self.time_fade = None;
+        if let Some(delay_ms) = self.queued_delay.take() {
+            self.delay_time.set_target(self.sample_rate, delay_ms);
+        }
+        // Nothing's left in the lines to hear the head run back.
+        let target_ms = self.target_delay_ms();
+        if let Some(head) = &mut self.tape_head {
+            head.reset(target_ms);
+        }
+        for l in &mut self.limiters {
+            l.reset();
+        }
+        self.sequencer.reset();
+        self.resync_steps = true;
+        self.pan_rotor.reset();
+        self.motion_fade.start(0);
+        self.restart_time_lfo();
+        self.mono_input.clear();
+        self.repeat_tracker.pause();
+        self.spread_glide.reset();
+        self.alternate_swap.reset();
+        self.key_gate.reset();
+        self.bloom_envelope.reset();
+        // Nothing is ringing any more, so the knob has the feedback back.
+        self.watchdog.reset();
+        self.phase_aligner.reset();
+        self.wet_polarity.reset(1.0);
+        self.feedback
+            .set_target(self.sample_rate, self.feedback_setting);
+        // The lines are empty already, so a clear has nothing left to do.
+        self.tail_clear = TailClear::Idle;
+        self.clear_fade.reset(1.0);
+        for state in &mut self.channel_states {
+            state.clear_gain = 1.0;
+        }
+        for switch in [
+            &mut self.mono_safe,
+            &mut self.limiter,
+            &mut self.external_loop,
+            &mut self.wet_solo,
+            &mut self.duck_listen,
+            &mut self.swap_channels,
+            &mut self.alternate,
+            &mut self.freeze,
+        ] {
+            switch.jump(switch.is_on());
+        }
+        if !self.freeze.is_on() {
+            self.frozen = None;
+        }
+        let [cutoff_left, cutoff_right] = &mut self.filter_cutoffs;
+        let [ll, lr, rl, rr] = &mut self.feedback_matrix;
+        let [low_shelf, high_shelf] = &mut self.shelf_gains;
+        for smoother in [
+            &mut self.delay_time,
+            &mut self.time_fine,
+            &mut self.mod_depth,
+            &mut self.pre_delay,
+            &mut self.feedback,
+            &mut self.hold,
+            &mut self.hold_level,
+            &mut self.mix,
+            cutoff_left,
+            cutoff_right,
+            &mut self.feedback_compensation,
+            &mut self.post_mix,
+            ll,
+            lr,
+            rl,
+            rr,
+            &mut self.drive,
+            low_shelf,
+            high_shelf,
+            &mut self.character,
+            &mut self.damping,
+            &mut self.decorrelate,
+            &mut self.dry_pan,
+            &mut self.input_balance,
+            &mut self.side_decay,
+            &mut self.spread,
+            &mut self.bloom,
+            &mut self.overdub,
+            &mut self.overdub_decay,
+            &mut self.out_lowcut,
+            &mut self.out_highcut,
+            &mut self.step_cutoff,
+            &mut self.output_trim,
+        ] {
+            smoother.reset(smoother.target());
+        }
+        // Playback may restart on a loud block: fade back in.
+        self.engage.reset(0.0);
+        self.engage.set_target(self.sample_rate, 1.0);
+        self.silence.reset();
+        self.idle.reset();
+        self.asleep = false;
+        self.expected_pos = None;
+        self.quiet_since_stop = false;
+        self.input_silence.reset();
+    }
+
+    /// Run the delay over one block of audio, in place.
+    ///
+    /// `channels` holds one slice per channel, all the same length.
+    /// Channels beyond the count given to [`new()`](Self::new) are left
+    /// untouched.
+    ///
+    /// # The Delay Algorithm
+    ///
+    /// For each sample, across all channels:
+    ///
+    /// 1. **Read** the delayed sample from the ring buffer
+    /// 2. **Shape** it through the feedback chain (lowpass darkens it,
+    ///    the saturator squashes loud repeats)
+    /// 3. **Scale** by feedback amount (controls decay rate)
+    /// 4. **Write** (input + scaled feedback) into the ring buffer
+    /// 5. **Mix** dry and wet (through the pre-delay and output filter)
+    ///    for the output
+    /// 6. **Advance** the ring buffer write position
+    ///
+    /// # Idle vs. smoothing blocks
+    ///
+    /// Most of the time nobody is touching the knobs, so every smoother
+    /// would just hand back the same value 256 times per buffer. We check
+    /// `is_smoothing()` once per block: if every parameter is idle
+    /// we take the streamlined [`process_static()`](Self::process_static)
+    /// path, otherwise the per-sample
+    /// [`process_smoothing()`](Self::process_smoothing) path.
+    ///
+    /// A ramp can only *start* at the beginning of a block, because
+    /// [`set_params()`](Self::set_params) is called between blocks. (The
+    /// plugin gets sample-accurate automation by having nih-plug split
+    /// the host's buffer at every parameter change.) It can finish
+    /// mid-block; the smoothing path simply keeps reading the (now
+    /// constant) values. The one exception is a quantized sync change
+    /// waiting for its beat: that block also takes the smoothing path,
+    /// which starts the delay ramp on the exact sample of the beat.
+    ///
+    /// # Output limiter
+    ///
+    /// The limiter runs last, as its own pass over the finished block.
+    /// Tail detection has already looked at the input and wet signals by
+    /// then, so limiting never changes when the tail is considered over.
+    ///
+    /// # Sleeping
+    ///
+    /// A session with dozens of instances has most of them idle most of
+    /// the time, each still paying for every sample. So once nothing is
+    /// moving and the engine has heard only silence (below
+    /// [`SLEEP_SILENCE_DB`], on the input and the delayed signal) for as
+    /// long as its delay lines — so every sample in them was written
+    /// during that silence — a silent block is skipped outright and left
+    /// as it is: the dry signal, untouched.
+    ///
+    /// Asleep, the lines hold nothing but silence, and silence read at
+    /// any position is the same silence. Not moving the write head
+    /// changes nothing anyone can hear, so the first loud block simply
+    /// runs as normal: no wake-up fade, and no shift in the echoes.
+    ///
+    /// # Odd block shapes
+    ///
+    /// Plugin validators (and now and then a host) call with blocks no
+    /// one would play through: no samples, a single sample, no
+    /// channels, or a different number of channels from the engine's.
+    ///
+    /// - An empty block returns straight away. Nothing is read, nothing
+    ///   counts down, and no smoother moves, so an empty call between
+    ///   two real ones leaves the stream exactly as if it never happened.
+    /// - A single sample is just a short block: the paths, the control
+    ///   tick, the transport and the tail all count in samples, so any
+    ///   block size produces the same stream.
+    /// - Channels past the engine's own are left as they came in; an
+    ///   engine given fewer channels than it has runs those it got.
+    pub fn process(&mut self, channels: &mut [&mut [f32]]) {
+        self.process_with_key(channels, None);
+    }
+
+    /// Run the delay over one block, with `key` as the sidechain for the
+    /// [key gate](DelayParams::key_gate): while the gate is on, input
+    /// only goes into the lines while the key (its loudest channel) is
+    /// above the threshold. The dry signal is never gated.
+    ///
+    /// `None`, or a key with no channels, leaves the gate standing open,
+    /// exactly like [`process()`](Self::process).
+    pub fn process_with_key(&mut self, channels: &mut [&mut [f32]], key: Option<&[&mut [f32]]>) {
+        if block_len(channels) == 0 {
+            return;
+        }
+        let key = key.filter(|key| !key.is_empty());
+        // One channel's work, played on both (see Shared Lines in the
+        // module docs). The right input is still there to record the
+        // mono sum from, as a pair would: with the gate and the balance
+        // out of circuit, it's the input's own sum.
+        if let (true, [left, right]) = (self.can_share_lines(channels, key), &mut *channels) {
+            self.process_block(std::slice::from_mut(left), key);
+            if !self.asleep {
+                for &input in right.iter() {
+                    self.mono_input.write(0.5 * (input + input));
+                    self.mono_input.advance();
+                }
+            }
+            right.copy_from_slice(left);
+        } else {
+            self.split_lines();
+            self.process_block(channels, key);
+        }
+        self.listen_to_key(channels, key);
+    }
+
+    /// Key Listen (see [`DelayParams::duck_listen`]): fade the finished
+    /// block out and the key, as the gate's detector hears it, in on
+    /// every channel in its place. Without a key, that's silence.
+    fn listen_to_key(&mut self, channels: &mut [&mut [f32]], key: Option<&[&mut [f32]]>) {
+        if !self.duck_listen.on_path_needed() {
+            return;
+        }
+        for i in 0..block_len(channels) {
+            let heard = loudest_key_sample(key, i).map_or(0.0, |key| self.key_gate.listen(key));
+            let (output_gain, heard_gain) = self.duck_listen.next_gains();
+            for samples in channels.iter_mut().take(self.channel_states.len()) {
+                samples[i] = output_gain * samples[i] + heard_gain * heard;
+            }
+        }
+    }
+
+    /// Run the delay over one block, on the channels it's given. See
+    /// [`process_with_key()`](Self::process_with_key).
+    fn process_block(&mut self, channels: &mut [&mut [f32]], key: Option<&[&mut [f32]]>) {
+        self.watch_for_gaps(channels);
+
+        // What only a control tick reads: the filters' cutoffs, and the
+        // compensation and damping worked out on them.
+        let gliding = self.filter_cutoffs.iter().any(Smoother::is_smoothing)
+            || self.feedback_compensation.is_smoothing()
+            || self.damping.is_smoothing()
+            || self.out_lowcut.is_smoothing()
+            || self.out_highcut.is_smoothing()
+            || self.filters_ramping();
+        // Everything else that changes from sample to sample whatever the
+        // signal is. While any of either is moving, the engine stays
+        // awake.
+        let stepping = self.delay_time.is_smoothing()
+            || self.time_fine.is_smoothing()
+            || self.mod_depth.is_smoothing()
+            || self.lfo_fade.is_active()
+            || self.time_fade.is_some()
+            || self.tape_moving()
+            || self.pre_delay.is_smoothing()
+            || self.feedback.is_smoothing()
+            || self.hold.is_smoothing()
+            || self.hold_level.is_smoothing()
+            || self.mix.is_smoothing()
+            || self.post_mix.is_smoothing()
+            || self.feedback_matrix.iter().any(Smoother::is_smoothing)
+            || self.drive.is_smoothing()
+            || self.shelf_gains.iter().any(Smoother::is_smoothing)
+            || self.character.is_smoothing()
+            || self.decorrelate.is_smoothing()
+            || self.wet_polarity.is_smoothing()
+            || self.dry_pan.is_smoothing()
+            || self.input_balance.is_smoothing()
+            || self.side_decay.is_smoothing()
+            || self.swap_channels.is_active()
+            || self.alternate.is_active()
+            || self.spread.is_smoothing()
+            || self.bloom.is_smoothing()
+            || self.overdub.is_smoothing()
+            || self.overdub_decay.is_smoothing()
+            || self.step_target != StepTarget::Off
+            || self.step_cutoff.is_smoothing()
+            || self.pending_delay.is_some()
+            || self.tail_clear != TailClear::Idle
+            || self.wet_solo.is_active()
+            || self.engage.is_smoothing()
+            || self.frozen.is_some()
+            || self.limiter.is_active()
+            || self.mono_safe.is_active()
+            || self.output_trim.is_smoothing();
+        let moving = gliding || stepping;
+        self.asleep = !moving && self.can_sleep(channels);
+        if self.asleep {
+            // Every line holds only silence, so the first two are one
+            // again, whatever set them apart before.
+            if channels.len() >= self.channel_states.len() {
+                self.lines_shared = true;
+            }
+            let num_samples = block_len(channels);
+            self.silence.update(None, num_samples);
+            self.idle.update(None, num_samples);
+            self.skip_control_ticks(num_samples);
+            self.skip_time_modulation(num_samples);
+            return;
+        }
+
+        // Whatever needs more than a control tick's attention: with none
+        // of it, only the filters (and what they hear) can move.
+        let per_sample = stepping
+            || self.modulating()
+            || self.gates_input(key)
+            || self.blooms()
+            || self.watchdog.is_enabled()
+            || self.aligning
+            || self.couples_channels(channels.len());
+        let ticking = gliding || self.damping.current() > 0.0;
+        let channel_major = self.runs_channel_major(channels.len());
+
+        let last_loud = if per_sample && channel_major {
+            self.process_channel_major(channels, key)
+        } else if per_sample {
+            self.process_smoothing(channels, key)
+        } else if ticking && channel_major {
+            self.process_control_rate(channels)
+        } else if ticking {
+            self.process_smoothing(channels, key)
+        } else {
+            self.process_static(channels)
+        };
+        self.finish_block(channels, last_loud);
+    }
+
+    /// Run the delay over one block, with the feedback patched through
+    /// external gear while [`DelayParams::external_loop`] is on.
+    ///
+    /// Hardware delays often have a send and return in the feedback path,
+    /// so another box (a filter, a spring reverb, a pitch shifter) can
+    /// color every repeat. Here `send` receives each channel's shaped
+    /// feedback signal, *before* the feedback amount is applied, and
+    /// `returned` is what came back. The feedback amount scales the
+    /// return on its way into the delay line.
+    ///
+    /// # Timing
+    ///
+    /// A host can only hand our send to another plugin and back at the
+    /// next buffer, so the return arrives
+    /// [`set_external_loop_latency()`](Self::set_external_loop_latency)
+    /// samples late. To keep the repeats on time, the loop's tap reads
+    /// the delay line that much *earlier* than the heard echo does:
+    ///
+    /// ```text
+    /// heard echo:  read at  delay
+    /// loop send:   read at  delay − latency  ──► gear ──► back `latency` later
+    /// ```
+    ///
+    /// so each trip round the loop still takes exactly `delay`. Our own
+    /// feedback chain's latency, if it has any, comes off on top (see
+    /// [`FEEDBACK_PATH_LATENCY`]). Delays shorter than the latency can't
+    /// be compensated: the loop tap stops at one sample, and the repeats
+    /// come out late.
+    ///
+    /// # Fallback
+    ///
+    /// With the loop switched off, or when the host hasn't given us a
+    /// send and return for every channel, this zeroes `send` and runs
+    /// the ordinary internal loop, exactly like
+    /// [`process_with_key()`](Self::process_with_key).
+    /// Mono-safe linking is skipped while the loop is external: the
+    /// feedback belongs to the external gear until it comes back.
+    pub fn process_with_external_loop(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        send: &mut [&mut [f32]],
+        returned: &[&mut [f32]],
+        key: Option<&[&mut [f32]]>,
+    ) {
+        let num_samples = block_len(channels);
+        if num_samples == 0 {
+            return;
+        }
+        let channel_count = channels.len().min(self.channel_states.len());
+        let connected = send.len() >= channel_count
+            && returned.len() >= channel_count
+            && send.iter().all(|s| s.len() >= num_samples)
+            && returned.iter().all(|r| r.len() >= num_samples);
+
+        if !connected || !self.external_loop.on_path_needed() {
+            for samples in send.iter_mut() {
+                samples.fill(0.0);
+            }
+            self.process_with_key(channels, key);
+            return;
+        }
+
+        let key = key.filter(|key| !key.is_empty());
+        self.split_lines();
+        self.watch_for_gaps(channels);
+        let last_loud = self.process_external(channels, send, returned, key);
+        self.finish_block(channels, last_loud);
+        self.listen_to_key(channels, key);
+    }
+
+    /// How many samples late the external loop's return arrives: the
+    /// host's buffer size, for a send and return patched through the
+    /// host. The plugin sets this from its buffer configuration.
+    pub fn set_external_loop_latency(&mut self, samples: usize) {
+        self.loop_latency = samples;
+    }
+
+    /// Whether a block can be skipped, once nothing is moving: the lines
+    /// hold only silence, and so does the block's input. See
+    /// [`process()`](Self::process).
+    ///
+    /// The limiters need no check of their own: they've been releasing
+    /// on silence for the whole length of a line, dozens of their
+    /// release times, so they're long back at unity gain.
+    fn can_sleep(&self, channels: &[&mut [f32]]) -> bool {
+        // The delay lines are the longest lines the engine keeps, and
+        // `mono_input` is as long as they are.
+        self.idle.silent_samples() >= self.mono_input.capacity()
+            && channels
+                .iter()
+                .take(self.channel_states.len())
+                .all(|samples| self.idle.last_loud_index(samples).is_none())
+    }
+
+    /// Whether the last block was skipped because the engine was idle
+    /// (see [`process()`](Self::process)).
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// Update tail detection, then apply the output trim and run the
+    /// output limiter over a finished block.
+    fn finish_block(&mut self, channels: &mut [&mut [f32]], last_loud: LastLoud) {
+        let num_samples = block_len(channels);
+        self.silence.update(last_loud.tail, num_samples);
+        self.idle.update(last_loud.awake, num_samples);
+
+        // The output trim: skipped entirely at unity, so a block without
+        // one stays bit for bit what the paths produced.
+        if self.output_trim.is_smoothing() || self.output_trim.current() != 1.0 {
+            for i in 0..num_samples {
+                let gain = self.output_trim.next();
+                for samples in channels.iter_mut().take(self.channel_states.len()) {
+                    samples[i] *= gain;
+                }
+            }
+        }
+
+        if self.limiter.is_active() {
+            // Switching the limiter in or out: run it, and fade between
+            // its output and the unlimited signal.
+            for i in 0..num_samples {
+                let (unlimited_gain, limited_gain) = self.limiter.next_gains();
+                for (samples, limiter) in channels.iter_mut().zip(&mut self.limiters) {
+                    let sample = samples[i];
+                    samples[i] = unlimited_gain * sample + limited_gain * limiter.process(sample);
+                }
+            }
+        } else if self.limiter.is_on() {
+            for (samples, limiter) in channels.iter_mut().zip(&mut self.limiters) {
+                for sample in samples.iter_mut() {
+                    *sample = limiter.process(*sample);
+                }
+            }
+        }
+    }
+
+    /// How many more samples of echo tail to expect, or `None` once the
+    /// tail has been measured to be over.
+    ///
+    /// The plugin reports this to the host so it keeps calling
+    /// `process()` after the input goes silent (e.g., when a region ends
+    /// or the track is muted). Without this, the delay echoes would be
+    /// cut off abruptly.
+    ///
+    /// # Estimate vs. measurement
+    ///
+    /// The *estimate* is a conservative upper bound from the feedback
+    /// math. The *measurement* comes from the [`SilenceTracker`]: once
+    /// both the input and the delayed signal have stayed below
+    /// `TAIL_SILENCE_DB` on every channel for a full delay period (plus
+    /// the pre-delay), the ring buffers hold nothing but silence, so the
+    /// tail is over and the host is free to stop processing us right
+    /// away.
+    pub fn tail_samples(&self) -> Option<u32> {
+        // The tail length depends on how many repeats it takes for the
+        // feedback loop to decay to -60 dB (inaudible). Each repeat is
+        // attenuated by the feedback factor, so after N repeats the level
+        // is feedback^N. Solving feedback^N = 0.001 (-60 dB):
+        //
+        //   N = log(0.001) / log(feedback)
+        //
+        // Multiply N by the delay time in samples to get the tail length.
+        //
+        // The pre-delay holds every echo back by the same amount, so it
+        // adds to the tail once — both to the estimate and to how long
+        // the signal must stay silent before the last echo is out.
+        let current = self.current_values();
+        // A repeat hold can ask for more than the knob allows; the
+        // loop-gain ceiling keeps what's applied below it.
+        let feedback = current.feedback.min(LOOP_GAIN_CEILING);
+        let delay_samps = calculate_delay_samples(current.delay_ms, self.sample_rate);
+        let pre_delay_samps = calculate_delay_samples(current.pre_delay_ms, self.sample_rate);
+
+        // Asleep, the lines hold nothing but silence (see `process()`).
+        if self.asleep || self.silence.silent_samples() as f32 >= delay_samps + pre_delay_samps {
+            return None;
+        }
+
+        let tail_samples = if feedback > 0.001 {
+            let repeats = -3.0 / feedback.log10(); // log10(0.001) = -3
+            (repeats * delay_samps + pre_delay_samps) as u32
+        } else {
+            // With no feedback, just one delay period for the single echo.
+            (delay_samps + pre_delay_samps) as u32
+        };
+
+        Some(tail_samples)
+    }
+
+    /// An empty [`TailSnapshot`] the size of this engine, for
+    /// [`capture_tail()`](Self::capture_tail) to fill.
+    ///
+    /// This allocates a second set of buffers (see
+    /// [`TailSnapshot::new()`]), and needs making again whenever the
+    /// engine is rebuilt or changes channels.
+    pub fn tail_snapshot(&self) -> TailSnapshot {
+        TailSnapshot::new(self.sample_rate, self.channels(), self.max_delay_ms)
+    }
+
+    /// Copy the repeats in flight into `snapshot`, along with the
+    /// `params` they're playing under (what this block's
+    /// [`set_params()`](Self::set_params) was given). Render the rest of
+    /// the tail from [`TailSnapshot::seeded_engine()`].
+    ///
+    /// Copies in place, without allocating: safe on the audio thread.
+    /// Only the live span of each line is copied — the newest samples,
+    /// back as far as the delay time, the pre-delay and any crossfade or
+    /// frozen loop still read (see [`live_span()`](Self::live_span)) —
+    /// so the copy grows with the settings, not the buffers. A frozen
+    /// loop can reach back the whole line.
+    ///
+    /// `snapshot` should come from this engine's
+    /// [`tail_snapshot()`](Self::tail_snapshot); one made for another
+    /// size copies only what fits.
+    pub fn capture_tail(&self, snapshot: &mut TailSnapshot, params: &DelayParams) {
+        let span = self.live_span();
+        snapshot.engine.copy_tail_from(self, span);
+        snapshot.span = span;
+        snapshot.params = *params;
+        snapshot.transport = self.transport;
+    }
+
+    /// The smoothed values as the audio last used them (see
+    /// [`CurrentValues`]). Reading them steps nothing, so it's safe to
+    /// call as often as you like between blocks.
+    pub fn current_values(&self) -> CurrentValues {
+        let knob = self.feedback.current();
+        CurrentValues {
+            delay_ms: self.current_delay_ms(),
+            pre_delay_ms: self.pre_delay.current(),
+            feedback: knob + (self.hold_level.current() - knob) * self.hold.current(),
+            mix: self.mix.current(),
+            damping: self.damping.current(),
+            dry_pan: self.dry_pan.current(),
+            spread: self.spread.current(),
+            side_decay_gain: self.side_decay.current(),
+            output_trim_gain: self.output_trim.current(),
+        }
+    }
+
+    /// How many samples of latency the engine adds, for the host to
+    /// compensate.
+    ///
+    /// When a wet-path stage delivers its signal late, mixing it with
+    /// the untouched dry signal would smear every transient into two: the
+    /// dry hit, then the wet copy of it a few samples later. So the dry
+    /// signal is held back by the same amount (see
+    /// [`ChannelState::dry_compensation`]), keeping the two sample-aligned
+    /// at the mix, and the total is reported here once.
+    pub fn latency_samples(&self) -> u32 {
+        self.wet_latency as u32
+    }
+
+    /// The gain around the feedback loop on the last processed sample:
+    /// the applied feedback times the feedback chain's peak gain, after
+    /// the loop-gain ceiling (see [`loop_gain`](crate::dsp::loop_gain)).
+    /// Post-mix, it's times the mix too (see [`FeedbackSource`]).
+    ///
+    /// With stages that don't amplify, fed from the wet tap, this equals
+    /// the Feedback setting.
+    /// It differs — staying below the ceiling — once the stages in the
+    /// loop would push the loop toward unity gain, so it's the number a
+    /// "how close to runaway" meter should show. While frozen it's 1.0:
+    /// the loop plays on without decaying.
+    pub fn loop_gain(&self) -> f32 {
+        self.loop_gain
+    }
+
+    /// How loud the feedback loop is and how many repeats it has left,
+    /// for a meter (see [`loop_meter`](crate::loop_meter)).
+    ///
+    /// The level is the loudest channel's loop damping follower, which
+    /// listens to the delayed signal whether or not damping is turned
+    /// up. Costs a `log10()` per call, so it's for once a block, not
+    /// once a sample.
+    pub fn loop_reading(&self) -> LoopReading {
+        let level = self
+            .channel_states
+            .iter()
+            .map(|state| state.damping.level())
+            .fold(0.0, f32::max);
+        LoopReading::measure(level, self.loop_gain)
+    }
+
+    /// The idle-parameter path: every value is constant for the block.
+    ///
+    /// Each value is read once, the filter coefficient (an `exp()` per
+    /// call) is computed once per channel instead of once per sample,
+    /// and because nothing changes over time we can run each channel's
+    /// whole block in one tight loop.
+    ///
+    /// Returns where the input or the delayed signal was last loud on
+    /// any channel (see [`LastLoud`]).
+    fn process_static(&mut self, channels: &mut [&mut [f32]]) -> LastLoud {
+        // An idle smoother's `next()` just returns its target without
+        // stepping, so this is a plain read.
+        let time_fine = self.time_fine.next();
+        let delay_ms = self.next_delay_ms(time_fine);
+        let pre_delay_ms = self.pre_delay.next();
+        let feedback = self.next_feedback();
+        let mix = self.next_mix();
+        let (offsets, filter_cutoffs, drive) = self.next_shaping_values();
+        let shelf_gains = self.shelf_gains.each_mut().map(Smoother::next);
+        let out_lowcut = self.out_lowcut.next();
+        let out_highcut = self.out_highcut.next();
+
+        let delay_samps = calculate_delay_samples(delay_ms, self.sample_rate);
+        let pre_delay_samps = calculate_delay_samples(pre_delay_ms, self.sample_rate);
+        // Stepping forces the smoothing path, so here the step cutoff is
+        // idle at "off" (or settled, if stepping was just switched off).
+        let out_highcut = out_highcut.min(self.step_cutoff.next());
+        // Likewise the wet solo switch is resting, on or off.
+        let (dry_gain, _) = self.wet_solo.next_gains();
+        let decorrelate = self.decorrelate.next();
+        // Phase alignment forces the smoothing path, and so does a
+        // polarity still fading.
+        let wet_polarity = self.wet_polarity.next();
+        let compensation = self.feedback_compensation.next();
+        let dry_feed = self.post_mix.next() * (1.0 - mix);
+        let balance = self.next_balance(channels.len());
+        let integer_delay = ((delay_samps - delay_samps.round()).abs() < INTEGER_DELAY_EPSILON)
+            .then_some(delay_samps.round() as usize);
+        // Nothing is gliding here (see `filters_ramping()`), so the
+        // filters are set once, below; the control clock just moves on.
+        self.skip_control_ticks(block_len(channels));
+
+        // Nothing reads the mono input here (a centered start takes the
+        // smoothing path), so the whole block goes in up front.
+        if let (true, [left, right, ..]) = (self.has_pair(channels.len()), &*channels) {
+            for (left, right) in left.iter().zip(right.iter()) {
+                self.mono_input
+                    .write(0.5 * (left * balance[0] + right * balance[1]));
+                self.mono_input.advance();
+            }
+        }
+        // Spread and Alternate force the smoothing path too: the lines
+        // move on without the repeat tracker, so its counts go stale.
+        self.repeat_tracker.pause();
+
+        let mut last_loud = LastLoud::new(&self.silence, &self.idle);
+        let mut loop_gain: f32 = 0.0;
+        for (channel_idx, samples) in channels.iter_mut().enumerate() {
+            let Some(state) = self.channel_states.get_mut(channel_idx) else {
+                continue;
+            };
+
+            let cutoff = offsets.cutoff(filter_cutoffs[side(channel_idx)]);
+            state
+                .feedback_chain
+                .filter
+                .set_cutoff(cutoff, self.sample_rate);
+            state.compensation = compensation_boost(compensation, feedback, cutoff);
+            state.feedback_chain.saturator.set_drive(drive);
+            state.feedback_chain.set_shelf_gains(shelf_gains);
+            state
+                .output_filter
+                .set_cutoffs(out_lowcut, out_highcut, self.sample_rate);
+            state.dry_gain = dry_gain;
+            state.decorrelate = decorrelate;
+            state.wet_polarity = wet_polarity;
+            // A freeze release that ended on the last block's final
+            // sample leaves its tap behind, and so does a time
+            // crossfade.
+            state.frozen_tap = None;
+            state.time_tap = None;
+            state.panned_dry = None;
+            // A gate in circuit forces the smoothing path, and so does a
+            // bloom.
+            state.key_gain = 1.0;
+            state.bloom_gain = 1.0;
+            state.balance = balance.get(channel_idx).copied().unwrap_or(1.0);
+            state.dry_feed = dry_feed;
+
+            let stage_gain = loop_stage_gain(state);
+            let feedback = limit_feedback(feedback * state.compensation, stage_gain);
+            loop_gain = loop_gain.max(feedback * stage_gain);
+
+            // Each sample is read from the delay line before it's
+            // written, so a delay shorter than the block is still exact:
+            // by the time sample `i` reads `delay_samps` back, every
+            // sample it can reach has been written. A vectorised rewrite
+            // of this loop must keep that true, by working in sub-blocks
+            // no longer than the delay.
+            for (i, sample) in samples.iter_mut().enumerate() {
+                let input = *sample;
+                let (output, delayed) = process_sample(
+                    state,
+                    input,
+                    delay_samps,
+                    integer_delay,
+                    pre_delay_samps,
+                    feedback,
+                    mix,
+                );
+                *sample = output;
+
+                last_loud.note(i, input.abs().max(delayed.abs()));
+            }
+        }
+
+        self.loop_gain = loop_gain;
+        last_loud
+    }
+
+    /// The control-rate path: nothing is moving but what only a control
+    /// tick reads (the feedback and output filters' cutoffs, damping and
+    /// compensation), so the per-sample loop has nothing to step but
+    /// those.
+    ///
+    /// Every other value is read once, as on the idle path. Each stretch
+    /// of [`SHARED_BLOCK_LEN`] samples is run in two passes, as on the
+    /// channel-major smoothing path: the first steps what the ticks read
+    /// and keeps each tick's values, the second runs each channel's
+    /// samples in one go, updating its filters on the ticks. The
+    /// channels do exactly what the per-sample paths would have, so the
+    /// output matches them to the bit.
+    ///
+    /// Returns where the block was last loud, like
+    /// [`process_static()`](Self::process_static).
+    fn process_control_rate(&mut self, channels: &mut [&mut [f32]]) -> LastLoud {
+        let num_samples = block_len(channels);
+        let time_fine = self.time_fine.next();
+        let delay_ms = self.next_delay_ms(time_fine);
+        let pre_delay_ms = self.pre_delay.next();
+        let feedback = self.next_feedback();
+        let mix = self.next_mix();
+        let offsets = CharacterOffsets::at(self.character.next());
+        let drive = offsets.drive(self.drive.next());
+        let shelf_gains = self.shelf_gains.each_mut().map(Smoother::next);
+        let delay_samps = calculate_delay_samples(delay_ms, self.sample_rate);
+        let pre_delay_samps = calculate_delay_samples(pre_delay_ms, self.sample_rate);
+        let (dry_gain, _) = self.wet_solo.next_gains();
+        let decorrelate = self.decorrelate.next();
+        let wet_polarity = self.wet_polarity.next();
+        let dry_feed = self.post_mix.next() * (1.0 - mix);
+        let balance = self.next_balance(channels.len());
+        let integer_delay = ((delay_samps - delay_samps.round()).abs() < INTEGER_DELAY_EPSILON)
+            .then_some(delay_samps.round() as usize);
+
+        // As on the idle path, nothing reads the mono input or the
+        // repeat counts here.
+        if let (true, [left, right, ..]) = (self.has_pair(channels.len()), &*channels) {
+            for (left, right) in left.iter().zip(right.iter()) {
+                self.mono_input
+                    .write(0.5 * (left * balance[0] + right * balance[1]));
+                self.mono_input.advance();
+            }
+        }
+        self.repeat_tracker.pause();
+
+        for (channel_idx, state) in self.channel_states.iter_mut().enumerate() {
+            state.feedback_chain.saturator.set_drive(drive);
+            state.feedback_chain.set_shelf_gains(shelf_gains);
+            state.dry_gain = dry_gain;
+            state.decorrelate = decorrelate;
+            state.wet_polarity = wet_polarity;
+            state.frozen_tap = None;
+            state.time_tap = None;
+            state.panned_dry = None;
+            state.key_gain = 1.0;
+            state.bloom_gain = 1.0;
+            state.balance = balance.get(channel_idx).copied().unwrap_or(1.0);
+            state.dry_feed = dry_feed;
+        }
+
+        let mut last_loud = LastLoud::new(&self.silence, &self.idle);
+        let mut ticks = [None; SHARED_BLOCK_LEN];
+        for start in (0..num_samples).step_by(SHARED_BLOCK_LEN) {
+            let len = SHARED_BLOCK_LEN.min(num_samples - start);
+
+            // Pass 1: step what the ticks read, sample by sample, and
+            // keep what each tick hands the filters.
+            for tick in &mut ticks[..len] {
+                let control_steps = self.next_control_tick();
+                let filter_cutoffs = self.filter_cutoffs.each_mut().map(Smoother::next);
+                let compensation = self.feedback_compensation.next();
+                let damping = self.damping.next();
+                let out_lowcut = self.out_lowcut.next();
+                let out_highcut = self.out_highcut.next().min(self.step_cutoff.next());
+                *tick = control_steps.map(|steps| ControlTick {
+                    steps,
+                    filter_cutoffs,
+                    compensation,
+                    damping,
+                    out_cutoffs: [out_lowcut, out_highcut],
+                });
+            }
+
+            // Pass 2: each channel's stretch in one go, its feedback
+            // worked out again only when a tick moves its compensation.
+            let mut loop_gain: f32 = 0.0;
+            for (channel_idx, samples) in channels.iter_mut().enumerate() {
+                let Some(state) = self.channel_states.get_mut(channel_idx) else {
+                    continue;
+                };
+                let stage_gain = loop_stage_gain(state);
+                let mut limited = limit_feedback(feedback * state.compensation, stage_gain);
+                let stretch = samples[start..start + len].iter_mut();
+                for (offset, (sample, tick)) in stretch.zip(&ticks[..len]).enumerate() {
+                    if let Some(tick) = tick {
+                        let cutoff = update_filters(
+                            state,
+                            offsets.cutoff(tick.filter_cutoffs[side(channel_idx)]),
+                            tick.damping,
+                            tick.out_cutoffs,
+                            self.sample_rate,
+                            tick.steps,
+                        );
+                        state.compensation =
+                            compensation_boost(tick.compensation, feedback, cutoff);
+                        limited = limit_feedback(feedback * state.compensation, stage_gain);
+                    }
+                    let input = *sample;
+                    let (output, delayed) = process_sample(
+                        state,
+                        input,
+                        delay_samps,
+                        integer_delay,
+                        pre_delay_samps,
+                        limited,
+                        mix,
+                    );
+                    *sample = output;
+
+                    last_loud.note(start + offset, input.abs().max(delayed.abs()));
+                }
+                loop_gain = loop_gain.max(limited * stage_gain);
+            }
+            self.loop_gain = loop_gain;
+        }
+
+        last_loud
+    }
+
+    /// The smoothing path: at least one parameter is ramping, so every
+    /// sample gets fresh values.
+    ///
+    /// Returns where the block was last loud, like
+    /// [`process_static()`](Self::process_static).
+    fn process_smoothing(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        key: Option<&[&mut [f32]]>,
+    ) -> LastLoud {
+        let num_samples = block_len(channels);
+        let moving = self.moves_channels(channels.len());
+        let motion = if moving {
+            (self.motion, self.start_side)
+        } else {
+            (StereoMotion::Off, self.start_side)
+        };
+        // While a change of motion fades, the old setting routes the
+        // pair alongside the new.
+        let faded_motion = (moving && self.motion_fade.is_active()).then_some(self.faded_motion);
+        let centered = |(motion, side): (StereoMotion, StartSide)| motion.centered_line(side);
+        let mut last_loud = LastLoud::new(&self.silence, &self.idle);
+
+        // Iterate over the block one sample at a time, across all
+        // channels. This is the "per-sample, per-channel" pattern — the
+        // clearest (though not the fastest) way to implement audio
+        // processing, and the one we need while values are changing.
+        for i in 0..num_samples {
+            let values = self.next_shared_values(channels, key, i);
+            let SharedValues {
+                control_steps,
+                clear_gain,
+                dry_gain,
+                time_tap,
+                feedback,
+                feedback_matrix,
+                mix,
+                decorrelate,
+                wet_polarity,
+                spread,
+                overdub,
+                out_lowcut,
+                out_highcut,
+                delay_samps,
+                pre_delay_samps,
+                integer_delay,
+                dry_pair,
+                key_gain,
+                bloom_gain,
+                balance,
+                side_gain,
+                frozen_tap,
+                ..
+            } = values;
+
+            // Frozen: the write heads stand still, and each channel plays
+            // its loop region round and round, untouched by feedback or
+            // the loop's filters — and by input, unless overdubbing,
+            // which rewrites each sample of the region as it's played.
+            // The mono input stands still too, so it lines up with the
+            // delay lines again after the release.
+            let spreading = spread > 0.0 && self.has_pair(channels.len());
+            let swapping = self.swaps_wet(channels.len());
+            let alternating = self.alternates_wet(channels.len());
+            if !spreading {
+                self.spread_glide.reset();
+            }
+            if !alternating {
+                self.alternate_swap.reset();
+            }
+            if let Some(tap) = frozen_tap.filter(|_| self.freeze.is_on()) {
+                // A time crossfade the freeze caught carries on in the
+                // loop, its old head as far from the loop's read as it
+                // was from the live one, so the loop starts on what was
+                // being heard. One started since leaves the loop alone.
+                let old_tap = time_tap.filter(|_| tap.caught_fade).map(|old| TimeTap {
+                    delay_samps: (tap.delay as f32 + old.delay_samps - delay_samps).max(1.0),
+                    ..old
+                });
+                let frozen_read = |line: &DelayLine| {
+                    let looped = line.read_int(tap.delay);
+                    old_tap.map_or(looped, |old| old.blend(line, looped))
+                };
+                // Stereo motion goes on shaping what each side hears:
+                // the rotor turns, and a centered start's first repeat
+                // comes from the mono input, which stands still with the
+                // lines. The repeat tracker stands still too, so each
+                // part of the loop keeps the place it had. The loop goes
+                // round too fast to read ahead in, so the pan and
+                // Alternate glide in a fade behind instead.
+                let wet_pair = (moving || spreading || swapping || alternating).then(|| {
+                    let pair = [0, 1].map(|side| {
+                        frozen_read(&self.channel_states[side].delay_line) * clear_gain
+                    });
+                    let (wet_gains, pair) = if moving {
+                        let (faded_gain, motion_gain) = match faded_motion {
+                            Some(_) => self.motion_fade.next_gains(),
+                            None => (0.0, 1.0),
+                        };
+                        let wet_gains = self.next_wet_gains(
+                            motion,
+                            faded_motion,
+                            (faded_gain, motion_gain),
+                            delay_samps,
+                        );
+                        let centered_lines = [
+                            (centered(motion), motion_gain),
+                            (faded_motion.and_then(centered), faded_gain),
+                        ];
+                        let first = if centered_lines.iter().any(|(line, _)| line.is_some()) {
+                            frozen_read(&self.mono_input)
+                        } else {
+                            0.0
+                        };
+                        let pair = center_first_repeat(pair, first * clear_gain, centered_lines);
+                        (wet_gains, pair)
+                    } else {
+                        ([1.0; 2], pair)
+                    };
+                    let counted = self.repeat_tracker.read_int(tap.delay);
+                    let pair = if spreading {
+                        let pan = counted.map_or(0.0, |count| spread_pan(count + 1.0, spread));
+                        pan_stereo_pair(self.spread_glide.next(pan), pair)
+                    } else {
+                        pair
+                    };
+                    let pair = if alternating {
+                        self.alternate_wet(counted, pair)
+                    } else {
+                        pair
+                    };
+                    let pair = [0, 1].map(|side| pair[side] * wet_gains[side]);
+                    if swapping {
+                        self.swap_wet(pair)
+                    } else {
+                        pair
+                    }
+                });
+                let mut wet_level: f32 = 0.0;
+                for (channel_idx, samples) in channels.iter_mut().enumerate() {
+                    let Some(state) = self.channel_states.get_mut(channel_idx) else {
+                        continue;
+                    };
+                    if let Some(steps) = control_steps {
+                        state.output_filter.ramp_cutoffs(
+                            out_lowcut,
+                            out_highcut,
+                            self.sample_rate,
+                            steps,
+                        );
+                    }
+                    state.clear_gain = clear_gain;
+                    state.dry_gain = dry_gain;
+                    state.decorrelate = decorrelate;
+                    state.wet_polarity = wet_polarity;
+                    state.panned_dry = dry_pair.and_then(|pair| pair.get(channel_idx).copied());
+                    state.bloom_gain = bloom_gain;
+                    state.balance = balance.get(channel_idx).copied().unwrap_or(1.0);
+
+                    let input = samples[i];
+                    let looped = state.delay_line.read_int(tap.delay);
+                    let delayed = match wet_pair {
+                        Some(pair) if channel_idx < 2 => pair[channel_idx],
+                        _ => frozen_read(&state.delay_line) * clear_gain,
+                    };
+                    if let Some((keep, inject)) = overdub {
+                        let input = input * state.balance * key_gain;
+                        let layered = looped * keep + input * inject;
+                        state.delay_line.write_behind(tap.delay, layered);
+                    }
+                    samples[i] = mix_wet(state, input, delayed, pre_delay_samps, mix);
+                    wet_level = wet_level.max(delayed.abs());
+
+                    last_loud.note(i, input.abs().max(delayed.abs()));
+                }
+                self.loop_gain = 1.0;
+                self.watch_wet(wet_level);
+                continue;
+            }
+
+            // Only a centered start hears the first repeat, so only it
+            // pays for reading one.
+            let first_repeat = if self.has_pair(channels.len()) {
+                let first =
+                    if centered(motion).is_some() || faded_motion.and_then(centered).is_some() {
+                        self.read_first_repeat(delay_samps, integer_delay, time_tap)
+                    } else {
+                        0.0
+                    };
+                self.record_mono_input(channels, i, key_gain, balance);
+                first
+            } else {
+                0.0
+            };
+
+            // Mono-safe mode, stereo motion, the feedback matrix, spread
+            // and side decay couple the first two channels: both have to
+            // be read and shaped before either is written back. Checked
+            // every sample, not once per block: a mono-safe fade-out that
+            // ends mid-block must stop linking right there, whatever size
+            // the host's blocks are. Ping-pong is the matrix that only
+            // crosses over.
+            let linked = self.links_channels(channels.len());
+            let routed_matrix = |(motion, _): (StereoMotion, StartSide)| {
+                if motion.crosses_feedback() {
+                    FeedbackMatrix::CROSSED
+                } else {
+                    feedback_matrix
+                }
+            };
+            // How much of the pair each motion setting routes: all of it
+            // the current one's, but for a fade.
+            let (faded_gain, motion_gain) = match faded_motion {
+                Some(_) => self.motion_fade.next_gains(),
+                None => (0.0, 1.0),
+            };
+            let matrix = match faded_motion {
+                Some(faded) => {
+                    let [from, to] = [routed_matrix(faded), routed_matrix(motion)]
+                        .map(|matrix| [matrix.ll, matrix.lr, matrix.rl, matrix.rr]);
+                    let [ll, lr, rl, rr] =
+                        [0, 1, 2, 3].map(|k| faded_gain * from[k] + motion_gain * to[k]);
+                    FeedbackMatrix { ll, lr, rl, rr }
+                }
+                None => routed_matrix(motion),
+            };
+            let mixed = matrix != FeedbackMatrix::STRAIGHT && self.has_pair(channels.len());
+            let narrowing = side_gain != 1.0 && self.has_pair(channels.len());
+            let coupled = linked
+                || motion.0 != StereoMotion::Off
+                || faded_motion.is_some()
+                || mixed
+                || spreading
+                || swapping
+                || alternating
+                || narrowing;
+            let tracking = spreading || alternating;
+            if !tracking {
+                self.repeat_tracker.pause();
+            }
+            let mut shaped_pair = [(0.0, 0.0); 2];
+            let mut applied_feedback = [feedback; 2];
+            let mut loop_gain: f32 = 0.0;
+            let mut wet_level: f32 = 0.0;
+
+            // Process each audio channel independently.
+            for (channel_idx, samples) in channels.iter_mut().enumerate() {
+                // Get this channel's delay line, feedback chain and
+                // output filter. The `let-else` pattern skips channels we
+                // don't have state for.
+                let Some(state) = self.channel_states.get_mut(channel_idx) else {
+                    continue;
+                };
+
+                // Hand the channel this sample's values. Its feedback
+                // comes back eased off if its stages would push the loop
+                // toward unity gain.
+                let (feedback, channel_loop_gain) =
+                    prepare_channel(state, &values, channel_idx, self.sample_rate);
+                loop_gain = loop_gain.max(channel_loop_gain);
+
+                if coupled && channel_idx < 2 {
+                    shaped_pair[channel_idx] =
+                        read_and_shape(state, samples[i], delay_samps, integer_delay);
+                    applied_feedback[channel_idx] = feedback;
+                    continue;
+                }
+
+                let input = samples[i];
+                let (output, delayed) = process_sample(
+                    state,
+                    input,
+                    delay_samps,
+                    integer_delay,
+                    pre_delay_samps,
+                    feedback,
+                    mix,
+                );
+                samples[i] = output;
+                wet_level = wet_level.max(delayed.abs());
+
+                last_loud.note(i, input.abs().max(delayed.abs()));
+            }
+
+            if coupled {
+                let shaped = [shaped_pair[0].1, shaped_pair[1].1];
+                let mut pair_feedback = shaped;
+                if linked {
+                    let (left, right) = self.channel_states.split_at_mut(1);
+                    pair_feedback = link_low_bands(&mut left[0], &mut right[0], shaped);
+
+                    // Switching mono-safe in or out fades between the
+                    // linked and unlinked feedback instead of jumping.
+                    if self.mono_safe.is_active() {
+                        let (unlinked_gain, linked_gain) = self.mono_safe.next_gains();
+                        for (linked, unlinked) in pair_feedback.iter_mut().zip(shaped) {
+                            *linked = unlinked_gain * unlinked + linked_gain * *linked;
+                        }
+                    }
+                }
+
+                // Stereo motion rewires the pair: where the input enters,
+                // and (circular) how loud each side hears its repeats. The
+                // matrix then decides whose repeats each line takes: a
+                // small matrix-vector multiply of the scaled repeats.
+                // The balance trims each side's input before any of that.
+                let inputs = [channels[0][i], channels[1][i]];
+                let balanced = [0, 1].map(|side| inputs[side] * balance[side]);
+                let routed_inputs =
+                    |(motion, side): (StereoMotion, StartSide)| match motion.input_gains(side) {
+                        Some(gains) => {
+                            let mono = 0.5 * (balanced[0] + balanced[1]);
+                            gains.map(|g| g * mono)
+                        }
+                        None => balanced,
+                    };
+                let mut line_inputs = routed_inputs(motion);
+                if let Some(faded) = faded_motion {
+                    let from = routed_inputs(faded);
+                    for (line, from) in line_inputs.iter_mut().zip(from) {
+                        *line = faded_gain * from + motion_gain * *line;
+                    }
+                }
+                // Side decay narrows the scaled repeats before the matrix
+                // routes them: the side shrinks, the mid is untouched.
+                let mut scaled = [0, 1].map(|side| applied_feedback[side] * pair_feedback[side]);
+                if narrowing {
+                    scaled = narrow_pair(side_gain, scaled);
+                }
+                let line_feedback = matrix.apply(scaled);
+                let wet_gains = self.next_wet_gains(
+                    motion,
+                    faded_motion,
+                    (faded_gain, motion_gain),
+                    delay_samps,
+                );
+
+                // A centered start moves the first repeat out of its
+                // line and into the middle; the loop carries on as if
+                // it had started on that side.
+                let mut heard = center_first_repeat(
+                    [shaped_pair[0].0, shaped_pair[1].0],
+                    first_repeat * clear_gain,
+                    [
+                        (centered(motion), motion_gain),
+                        (faded_motion.and_then(centered), faded_gain),
+                    ],
+                );
+
+                // Spread pans each repeat by its number and Alternate swaps
+                // every other one, both reading the count a glide ahead so
+                // the move is done by the time the repeat comes out; the
+                // tracker counts the trips of what goes back in (see
+                // `repeat_spread`).
+                if tracking {
+                    let fed_back = match integer_delay {
+                        Some(delay) => self.repeat_tracker.read_int(delay),
+                        None => self.repeat_tracker.read(delay_samps),
+                    };
+                    let lead = self.alternate_swap.lead();
+                    let coming = match integer_delay {
+                        Some(delay) => self
+                            .repeat_tracker
+                            .read_int(delay.saturating_sub(lead).max(1)),
+                        None => self
+                            .repeat_tracker
+                            .read((delay_samps - lead as f32).max(1.0)),
+                    };
+                    if spreading {
+                        let pan = coming.map_or(0.0, |count| spread_pan(count + 1.0, spread));
+                        heard = pan_stereo_pair(self.spread_glide.next(pan), heard);
+                    }
+                    if alternating {
+                        heard = self.alternate_wet(coming, heard);
+                    }
+                    let level = |pair: [f32; 2]| pair[0].abs() + pair[1].abs();
+                    self.repeat_tracker.write(
+                        level(line_inputs) * key_gain,
+                        level(line_feedback),
+                        fed_back,
+                    );
+                }
+
+                // Last, the swap exchanges what each side hears.
+                let mut wet = [0, 1].map(|side| heard[side] * wet_gains[side]);
+                if swapping {
+                    wet = self.swap_wet(wet);
+                }
+
+                for (channel_idx, state) in self.channel_states.iter_mut().take(2).enumerate() {
+                    // Already scaled by the feedback amount, side by side.
+                    write_feedback(
+                        state,
+                        line_inputs[channel_idx],
+                        line_feedback[channel_idx],
+                        1.0,
+                    );
+
+                    let input = inputs[channel_idx];
+                    let delayed = heard[channel_idx];
+                    channels[channel_idx][i] =
+                        mix_and_advance(state, input, wet[channel_idx], pre_delay_samps, mix);
+                    wet_level = wet_level.max(delayed.abs());
+
+                    last_loud.note(i, input.abs().max(delayed.abs()));
+                }
+            }
+
+            self.loop_gain = loop_gain;
+            self.watch_wet(wet_level);
+        }
+
+        last_loud
+    }
+
+    /// The smoothing path in channel-major order, for blocks whose
+    /// channels don't depend on each other: each stretch of
+    /// [`SHARED_BLOCK_LEN`] samples is run in two passes, the shared
+    /// values first, then each channel's samples in one go. See
+    /// [Processing Order](self#processing-order).
+    ///
+    /// Returns where the block was last loud, like
+    /// [`process_static()`](Self::process_static).
+    fn process_channel_major(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        key: Option<&[&mut [f32]]>,
+    ) -> LastLoud {
+        let num_samples = block_len(channels);
+        let has_pair = self.has_pair(channels.len());
+        // Spread couples the channels, so it's off here.
+        self.repeat_tracker.pause();
+
+        let mut last_loud = LastLoud::new(&self.silence, &self.idle);
+        let mut table = [None; SHARED_BLOCK_LEN];
+        for start in (0..num_samples).step_by(SHARED_BLOCK_LEN) {
+            let len = SHARED_BLOCK_LEN.min(num_samples - start);
+
+            // Pass 1: everything the channels share, sample by sample,
+            // reading the input before any channel overwrites it.
+            for (offset, entry) in table[..len].iter_mut().enumerate() {
+                let i = start + offset;
+                let values = self.next_shared_values(channels, key, i);
+                if has_pair {
+                    self.record_mono_input(channels, i, values.key_gain, values.balance);
+                }
+                *entry = Some(values);
+            }
+
+            // Pass 2: each channel's stretch in one go. The loop gain
+            // reported is the last sample's, as in sample-major order.
+            let mut loop_gain: f32 = 0.0;
+            for (channel_idx, samples) in channels.iter_mut().enumerate() {
+                let Some(state) = self.channel_states.get_mut(channel_idx) else {
+                    continue;
+                };
+                let stretch = samples[start..start + len].iter_mut();
+                for (offset, (sample, values)) in stretch.zip(table.iter().flatten()).enumerate() {
+                    let (feedback, channel_loop_gain) =
+                        prepare_channel(state, values, channel_idx, self.sample_rate);
+                    let input = *sample;
+                    let (output, delayed) = process_sample(
+                        state,
+                        input,
+                        values.delay_samps,
+                        values.integer_delay,
+                        values.pre_delay_samps,
+                        feedback,
+                        values.mix,
+                    );
+                    *sample = output;
+
+                    last_loud.note(start + offset, input.abs().max(delayed.abs()));
+                    if offset == len - 1 {
+                        loop_gain = loop_gain.max(channel_loop_gain);
+                    }
+                }
+            }
+            self.loop_gain = loop_gain;
+        }
+
+        last_loud
+    }
+
+    /// Step every smoother and tap the per-sample paths share, for
+    /// sample `i` of the block, and return their values for the
+    /// channels. Both processing orders call this once per sample, in
+    /// sample order, so they step everything identically (see
+    /// [Processing Order](self#processing-order)).
+    #[inline]
+    fn next_shared_values(
+        &mut self,
+        channels: &[&mut [f32]],
+        key: Option<&[&mut [f32]]>,
+        i: usize,
+    ) -> SharedValues {
+        // On a control tick, the filters are given new cutoffs to
+        // glide to until the next one (see the module docs).
+        let control_steps = self.next_control_tick();
+        if let (true, Some(steps)) = (self.aligning, control_steps) {
+            self.align_phase(steps);
+        }
+
+        // A quantized delay change starts its ramp on its beat.
+        self.advance_pending_delay();
+        // Frozen, the lines stand still (see `process_smoothing()`).
+        let clear_gain = self.next_clear_gain(!self.freeze.is_on());
+        let (dry_gain, _) = self.wet_solo.next_gains();
+
+        // ─── Read smoothed parameter values for this sample ───
+        //
+        // `.next()` returns the parameter's current value after
+        // applying the smoother. If the user just moved a knob from
+        // 500ms to 1000ms, the smoother gradually ramps from 500 to
+        // 1000 over the smoothing duration (e.g., 50ms), giving us
+        // intermediate values like 501, 502, 503... instead of an
+        // instant jump.
+        //
+        // The delay time is the coarse time plus the fine offset;
+        // while the coarse time crossfades, a second head reads the
+        // old one.
+        let time_fine = self.time_fine.next();
+        let delay_ms = self.next_delay_ms(time_fine);
+        let time_tap = self.next_time_tap(time_fine);
+        let pre_delay_ms = self.pre_delay.next();
+        let feedback = self.next_feedback();
+        let feedback_matrix = self.next_feedback_matrix();
+        let mix = self.next_mix();
+        let (offsets, filter_cutoffs, drive) = self.next_shaping_values();
+        let shelf_gains = self.shelf_gains.each_mut().map(Smoother::next);
+        let compensation = self.feedback_compensation.next();
+        let post_mix = self.post_mix.next();
+        let damping = self.damping.next();
+        let decorrelate = self.decorrelate.next();
+        let wet_polarity = self.wet_polarity.next();
+        let spread = self.spread.next();
+        let overdub = overdub_gains(self.overdub.next(), self.overdub_decay.next());
+        let out_lowcut = self.out_lowcut.next();
+        let out_highcut = self.out_highcut.next();
+
+        // Convert delay time from milliseconds to samples.
+        //
+        // This is one of the most fundamental DSP conversions:
+        //
+        //   delay_samples = delay_ms * sample_rate / 1000
+        //
+        // At 44100 Hz:
+        //   100ms  =  4410 samples
+        //   500ms  = 22050 samples
+        //   2000ms = 88200 samples
+        //
+        // The result is often fractional (e.g., 441.3 samples for
+        // 10.007ms), which is why our delay line supports fractional
+        // reads via linear interpolation.
+        let delay_samps = calculate_delay_samples(delay_ms, self.sample_rate);
+        let pre_delay_samps = calculate_delay_samples(pre_delay_ms, self.sample_rate);
+        let out_highcut = out_highcut.min(self.next_step_cutoff(delay_samps));
+        let dry_pair = self.next_dry_pair(channels, i);
+        let key_gain = self.next_key_gain(key, i);
+        let bloom_gain = self.next_bloom_gain(channels, i, key_gain, delay_samps, pre_delay_samps);
+        let balance = self.next_balance(channels.len());
+        let side_gain = self.side_decay.next();
+
+        // Fast path: once the delay time has settled (its smoother is
+        // idle) and lands on a whole number of samples, interpolation
+        // has nothing to blend. `read_int()` then skips the second
+        // read and the multiplies. Both paths agree to within 1 ulp.
+        let integer_delay = (!self.delay_time.is_smoothing()
+            && !self.time_fine.is_smoothing()
+            && (delay_samps - delay_samps.round()).abs() < INTEGER_DELAY_EPSILON)
+            .then_some(delay_samps.round() as usize);
+
+        let frozen_tap = self.next_frozen_tap();
+
+        SharedValues {
+            control_steps,
+            clear_gain,
+            dry_gain,
+            time_tap,
+            feedback,
+            feedback_matrix,
+            mix,
+            offsets,
+            filter_cutoffs,
+            drive,
+            shelf_gains,
+            compensation,
+            post_mix,
+            damping,
+            decorrelate,
+            wet_polarity,
+            spread,
+            overdub,
+            out_lowcut,
+            out_highcut,
+            delay_samps,
+            pre_delay_samps,
+            integer_delay,
+            dry_pair,
+            key_gain,
+            bloom_gain,
+            balance,
+            side_gain,
+            frozen_tap,
+        }
+    }
+
+    /// The external-loop path: like the smoothing path, one sample at a
+    /// time across all channels, but with the feedback leaving through
+    /// `send` and coming back through `returned`. See
+    /// [`process_with_external_loop()`](Self::process_with_external_loop).
+    fn process_external(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        send: &mut [&mut [f32]],
+        returned: &[&mut [f32]],
+        key: Option<&[&mut [f32]]>,
+    ) -> LastLoud {
+        let num_samples = block_len(channels);
+        let mut last_loud = LastLoud::new(&self.silence, &self.idle);
+
+        for i in 0..num_samples {
+            let control_steps = self.next_control_tick();
+            self.advance_pending_delay();
+            let clear_gain = self.next_clear_gain(true);
+            let (dry_gain, _) = self.wet_solo.next_gains();
+
+            let time_fine = self.time_fine.next();
+            let delay_ms = self.next_delay_ms(time_fine);
+            let time_tap = self.next_time_tap(time_fine);
+            let pre_delay_ms = self.pre_delay.next();
+            let feedback = self.next_feedback();
+            let mix = self.next_mix();
+            let (offsets, filter_cutoffs, drive) = self.next_shaping_values();
+            let shelf_gains = self.shelf_gains.each_mut().map(Smoother::next);
+            let compensation = self.feedback_compensation.next();
+            // The send is always the wet tap (see `FeedbackSource`).
+            self.post_mix.next();
+            let damping = self.damping.next();
+            let decorrelate = self.decorrelate.next();
+            let wet_polarity = self.wet_polarity.next();
+            let out_lowcut = self.out_lowcut.next();
+            let out_highcut = self.out_highcut.next();
+
+            let delay_samps = calculate_delay_samples(delay_ms, self.sample_rate);
+            let pre_delay_samps = calculate_delay_samples(pre_delay_ms, self.sample_rate);
+            let out_highcut = out_highcut.min(self.next_step_cutoff(delay_samps));
+            let loop_samps = (delay_samps - self.loop_latency as f32).max(1.0);
+            let dry_pair = self.next_dry_pair(channels, i);
+            let key_gain = self.next_key_gain(key, i);
+            let bloom_gain =
+                self.next_bloom_gain(channels, i, key_gain, delay_samps, pre_delay_samps);
+            let balance = self.next_balance(channels.len());
+            // Motion, spread, Alternate, side decay and the swap are set
+            // aside here (their fades and the side decay's glide still
+            // move on), but the mono input keeps recording so it's
+            // current when the loop comes back inside.
+            self.swap_channels.next_gains();
+            self.alternate.next_gains();
+            self.spread_glide.reset();
+            self.alternate_swap.reset();
+            self.side_decay.next();
+            if self.has_pair(channels.len()) {
+                self.record_mono_input(channels, i, key_gain, balance);
+            }
+            self.repeat_tracker.pause();
+            let (internal_gain, external_gain) = self.external_loop.next_gains();
+            let mut loop_gain: f32 = 0.0;
+            let mut wet_level: f32 = 0.0;
+
+            for (channel_idx, samples) in channels.iter_mut().enumerate() {
+                let Some(state) = self.channel_states.get_mut(channel_idx) else {
+                    continue;
+                };
+
+                if let Some(steps) = control_steps {
+                    let cutoff = update_filters(
+                        state,
+                        offsets.cutoff(filter_cutoffs[side(channel_idx)]),
+                        damping,
+                        [out_lowcut, out_highcut],
+                        self.sample_rate,
+                        steps,
+                    );
+                    state.compensation = compensation_boost(compensation, feedback, cutoff);
+                }
+                state.feedback_chain.saturator.set_drive(drive);
+                state.feedback_chain.set_shelf_gains(shelf_gains);
+                state.clear_gain = clear_gain;
+                state.dry_gain = dry_gain;
+                state.decorrelate = decorrelate;
+                state.wet_polarity = wet_polarity;
+                state.panned_dry = dry_pair.and_then(|pair| pair.get(channel_idx).copied());
+                state.key_gain = key_gain;
+                state.bloom_gain = bloom_gain;
+                state.balance = balance.get(channel_idx).copied().unwrap_or(1.0);
+                state.dry_feed = 0.0;
+                // Freeze belongs to the internal loop. A time crossfade
+                // moves the loop tap's old head back by the latency too.
+                state.frozen_tap = None;
+                state.time_tap = time_tap.map(|tap| TimeTap {
+                    delay_samps: (tap.delay_samps - self.loop_latency as f32).max(1.0),
+                    ..tap
+                });
+
+                let stage_gain = state.feedback_chain.max_gain();
+                let feedback = limit_feedback(feedback * state.compensation, stage_gain);
+                loop_gain = loop_gain.max(feedback * stage_gain);
+
+                // The heard echo comes from the full delay; the send
+                // from the earlier loop tap, shaped by our own chain.
+                let delayed = state.delay_line.read(delay_samps);
+                let delayed = match time_tap {
+                    Some(tap) => tap.blend(&state.delay_line, delayed),
+                    None => delayed,
+                } * clear_gain;
+                let (_, shaped) = read_and_shape(state, samples[i], loop_samps, None);
+                send[channel_idx][i] = shaped;
+
+                // Mid-switch, blend in the internal loop. Its shaping
+                // chain is busy with the loop tap, so for those few
+                // milliseconds the internal repeats go unshaped — far
+                // less noticeable than a jump in the loop.
+                let returned = returned[channel_idx][i] * clear_gain;
+                let feedback_source = internal_gain * delayed + external_gain * returned;
+
+                let input = samples[i];
+                samples[i] = write_and_mix(
+                    state,
+                    input,
+                    delayed,
+                    feedback_source,
+                    pre_delay_samps,
+                    feedback,
+                    mix,
+                );
+                wet_level = wet_level.max(delayed.abs());
+
+                last_loud.note(i, input.abs().max(delayed.abs()).max(returned.abs()));
+            }
+
+            self.loop_gain = loop_gain;
+            self.watch_wet(wet_level);
+        }
+
+        // Any send channels beyond ours carry nothing.
+        for samples in send.iter_mut().skip(self.channel_states.len()) {
+            samples.fill(0.0);
+        }
+
+        last_loud
+    }
+
+    /// Advance the control clock by a sample. On a tick, returns how
+    /// many samples the filters glide over: up to the next tick.
+    fn next_control_tick(&mut self) -> Option<usize> {
+        let tick = self.control_clock == 0;
+        self.control_clock += 1;
+        if self.control_clock == self.control_interval {
+            self.control_clock = 0;
+        }
+        tick.then_some(self.control_interval)
+    }
+
+    /// Advance the control clock over a block with no ticks in it (the
+    /// idle path sets its filters directly, and a sleeping engine none).
+    fn skip_control_ticks(&mut self, num_samples: usize) {
+        self.control_clock = (self.control_clock + num_samples) % self.control_interval;
+    }
+
+    /// Whether any channel's filters are still gliding to the values from
+    /// the last control tick.
+    fn filters_ramping(&self) -> bool {
+        // A second channel standing in for the first (see Shared Lines)
+        // is stale, and may have been left mid-glide.
+        let live = if self.lines_shared { 1 } else { usize::MAX };
+        self.channel_states.iter().take(live).any(|state| {
+            state.feedback_chain.filter.is_ramping() || state.output_filter.is_ramping()
+        })
+    }
+
+    /// The feedback to aim for: the knob's, unless the watchdog is
+    /// holding it down.
+    fn watched_feedback(&self) -> f32 {
+        if self.watchdog.is_tripped() {
+            self.feedback_setting.min(SAFE_FEEDBACK)
+        } else {
+            self.feedback_setting
+        }
+    }
+
+    /// The feedback for this sample: the knob's (smoothed, and held
+    /// down by the watchdog), moved towards the hold level as far as
+    /// the repeat hold has got.
+    #[inline]
+    fn next_feedback(&mut self) -> f32 {
+        let knob = self.feedback.next();
+        let hold = self.hold.next();
+        let hold_level = self.hold_level.next();
+        knob + (hold_level - knob) * hold
+    }
+
+    /// Show the watchdog this sample's wet level (the loudest channel's
+    /// repeats). When it trips or releases, the feedback ramps to its
+    /// new target like any knob move.
+    #[inline]
+    fn watch_wet(&mut self, wet_level: f32) {
+        if self.watchdog.process(wet_level).is_some() {
+            let feedback = self.watched_feedback();
+            self.feedback.set_target(self.sample_rate, feedback);
+        }
+    }
+
+    /// Hand the phase aligner what the channels have measured over the
+    /// last `samples` samples, and aim the wet polarity where it says.
+    ///
+    /// The channels' meters are averaged, not summed, so two lines
+    /// sharing one (see [Shared Lines](self#shared-lines)) decide
+    /// exactly as two separate ones would.
+    fn align_phase(&mut self, samples: usize) {
+        let live = if self.lines_shared {
+            1
+        } else {
+            self.channel_states.len().max(1)
+        };
+        let mut meter = PhaseMeter::default();
+        for state in self.channel_states.iter_mut().take(live) {
+            if let Some(channel) = &mut state.phase_meter {
+                meter = meter + *channel;
+                *channel = PhaseMeter::default();
+            }
+        }
+        let flipped = self
+            .phase_aligner
+            .update(meter.scaled(1.0 / live as f32), samples);
+        let polarity = if flipped { -1.0 } else { 1.0 };
+        self.wet_polarity.set_target(self.sample_rate, polarity);
+    }
+
+    /// Whether phase alignment has flipped the wet signal (see
+    /// [`DelayParams::phase_align`]). Always `false` while it's out of
+    /// circuit.
+    pub fn phase_flipped(&self) -> bool {
+        self.aligning && self.phase_aligner.flipped()
+    }
+
+    /// The mix for this sample, faded in after activation (see
+    /// [`engage`](Self::engage)).
+    #[inline]
+    fn next_mix(&mut self) -> f32 {
+        self.mix.next() * self.engage.next()
+    }
+
+    /// The first repeat of a centered ping-pong start: the first two
+    /// channels' mono input sum from `delay_samps` ago, read the way the
+    /// delay lines read (through both heads, mid time crossfade). Call it
+    /// before [`record_mono_input()`](Self::record_mono_input).
+    #[inline]
+    fn read_first_repeat(
+        &self,
+        delay_samps: f32,
+        integer_delay: Option<usize>,
+        time_tap: Option<TimeTap>,
+    ) -> f32 {
+        let first = match integer_delay {
+            Some(delay) => self.mono_input.read_int(delay),
+            None => self.mono_input.read(delay_samps),
+        };
+        match time_tap {
+            Some(tap) => tap.blend(&self.mono_input, first),
+            None => first,
+        }
+    }
+
+    /// How loud each side of the pair hears its repeats: circular motion
+    /// pans them with the rotor, turning it one sample on, and a change
+    /// of motion fades between the old setting's gains and the new.
+    #[inline]
+    fn next_wet_gains(
+        &mut self,
+        motion: (StereoMotion, StartSide),
+        faded_motion: Option<(StereoMotion, StartSide)>,
+        (faded_gain, motion_gain): (f32, f32),
+        delay_samps: f32,
+    ) -> [f32; 2] {
+        let circular = |(motion, _): (StereoMotion, StartSide)| motion == StereoMotion::Circular;
+        let circling = [Some(motion), faded_motion].map(|m| m.is_some_and(circular));
+        let rotor_gains = if circling.contains(&true) {
+            constant_power_gains(self.pan_rotor.next_pan(delay_samps))
+        } else {
+            [1.0; 2]
+        };
+        [0, 1].map(|side| {
+            let [to, from] = circling.map(|c| if c { rotor_gains[side] } else { 1.0 });
+            faded_gain * from + motion_gain * to
+        })
+    }
+
+    /// Record sample `i` of the first two channels' mono input sum,
+    /// through the key gate's `key_gain` and the input `balance` like
+    /// the lines' input.
+    #[inline]
+    fn record_mono_input(
+        &mut self,
+        channels: &[&mut [f32]],
+        i: usize,
+        key_gain: f32,
+        [left, right]: [f32; 2],
+    ) {
+        self.mono_input
+            .write(0.5 * (channels[0][i] * left + channels[1][i] * right) * key_gain);
+        self.mono_input.advance();
+    }
+
+    /// The input balance's `[left, right]` gains for this sample, moving
+    /// the smoother on. Unity without a pair to balance.
+    #[inline]
+    fn next_balance(&mut self, num_channels: usize) -> [f32; 2] {
+        let balance = self.input_balance.next();
+        if balance != 0.0 && self.has_pair(num_channels) {
+            balance_gains(balance)
+        } else {
+            [1.0; 2]
+        }
+    }
+
+    /// Exchange the wet `pair` as far as the channel swap has got,
+    /// moving its crossfade on.
+    #[inline]
+    fn swap_wet(&mut self, [left, right]: [f32; 2]) -> [f32; 2] {
+        let (straight, swapped) = self.swap_channels.next_gains();
+        [
+            straight * left + swapped * right,
+            straight * right + swapped * left,
+        ]
+    }
+
+    /// Exchange the wet `pair` as far as Alternate has got, gliding
+    /// towards the side of the repeat `counted` (the tracker's count, a
+    /// glide ahead of the audio; `None` plays straight), and moving the
+    /// on/off fade on.
+    #[inline]
+    fn alternate_wet(&mut self, counted: Option<f32>, [left, right]: [f32; 2]) -> [f32; 2] {
+        let (_, on) = self.alternate.next_gains();
+        let target = counted.map_or(0.0, |count| alternate_swap(count + 1.0));
+        let swapped = on * self.alternate_swap.next(target);
+        let straight = 1.0 - swapped;
+        [
+            straight * left + swapped * right,
+            straight * right + swapped * left,
+        ]
+    }
+
+    /// The first two channels' dry samples at index `i`, panned by the
+    /// dry pan, moving its smoother on by one. `None` at centre (where
+    /// each channel mixes its own input, bit for bit) or without a
+    /// pair. Call it before either channel's sample `i` is overwritten.
+    #[inline]
+    fn next_dry_pair(&mut self, channels: &[&mut [f32]], i: usize) -> Option<[f32; 2]> {
+        let pan = self.dry_pan.next();
+        (pan != 0.0 && self.has_pair(channels.len()))
+            .then(|| pan_stereo_pair(pan, [channels[0][i], channels[1][i]]))
+    }
+
+    /// The gain on what goes into the lines for sample `i`: the key
+    /// gate's, listening to the loudest channel of `key`, with fresh
+    /// input faded in behind a clear's wipe (see
+    /// [`next_clear_gain()`](Self::next_clear_gain)).
+    #[inline]
+    fn next_key_gain(&mut self, key: Option<&[&mut [f32]]>, i: usize) -> f32 {
+        self.key_gate.process(loudest_key_sample(key, i)) * self.wipe_fade_in()
+    }
+
+    /// How far fresh input has faded into the lines since a clear's
+    /// wipe began: up from 0.0 as the wipe works through the lines (over
+    /// [`CLEAR_WIPE_MS`]), and 1.0 outside a wipe.
+    fn wipe_fade_in(&self) -> f32 {
+        match self.tail_clear {
+            TailClear::Wiping { wiped, .. } => {
+                (wiped as f32 / self.line_capacity().max(1) as f32).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// The bloom's wet gain for sample `i`, listening to the loudest
+    /// channel's input as it goes into the lines (through the key gate's
+    /// `key_gain`), with the delay and pre-delay at `delay_samps` and
+    /// `pre_delay_samps`. Call it before any channel's sample `i` is
+    /// overwritten.
+    #[inline]
+    fn next_bloom_gain(
+        &mut self,
+        channels: &[&mut [f32]],
+        i: usize,
+        key_gain: f32,
+        delay_samps: f32,
+        pre_delay_samps: f32,
+    ) -> f32 {
+        let bloom_db = self.bloom.next();
+        let level = channels
+            .iter()
+            .take(self.channel_states.len())
+            .filter_map(|samples| samples.get(i))
+            .fold(0.0_f32, |level, sample| level.max(sample.abs()));
+        self.bloom_envelope
+            .process(level * key_gain, bloom_db, delay_samps, pre_delay_samps)
+    }
+
+    /// Whether the bloom is riding the wet gain, or still letting go of
+    /// it, so every sample needs its own gain.
+    fn blooms(&self) -> bool {
+        self.bloom.current() > 0.0 || self.bloom.is_smoothing() || self.bloom_envelope.gain() != 1.0
+    }
+
+    /// The per-repeat step's high-cut corner for this sample, advancing
+    /// the step sequencer by one sample of a `delay_samps` period.
+    #[inline]
+    fn next_step_cutoff(&mut self, delay_samps: f32) -> f32 {
+        if let Some(value) = self.sequencer.advance(delay_samps) {
+            if self.step_target == StepTarget::Cutoff {
+                self.step_cutoff
+                    .set_target(self.sample_rate, step_to_cutoff(value));
+            }
+        }
+        self.step_cutoff.next()
+    }
+
+    /// Capture the freeze loop from what's in the delay lines, and stop
+    /// their write heads.
+    ///
+    /// # The loop region
+    ///
+    /// The loop is a region of each line: its newest `len` samples. A
+    /// plain freeze takes everything the lines hold (less a little
+    /// room, see below). That rarely comes out a whole number of delay
+    /// periods, so the rhythm trips where the loop wraps, and the audio
+    /// at its two ends doesn't meet, so the wrap clicks.
+    ///
+    /// Quantized, the region is rounded down to a whole number of delay
+    /// periods — the nearest that fits — so the repeats keep their
+    /// spacing across the wrap. The seam is then smoothed once, here:
+    /// [`DelayLine::blend_seam()`] fades the region's last
+    /// [`SWITCH_FADE_MS`] over to the audio just before its start, with
+    /// an equal-power fade. The work is bounded by the fade length, and
+    /// the playback path pays nothing for it.
+    ///
+    /// The room left behind the region holds that lead-in, and covers
+    /// the release: for its fade the write heads move on again while the
+    /// loop still plays, and they mustn't reach the region. The lines
+    /// are built [`FREEZE_ROOM_MS`] longer than the longest delay for it.
+    ///
+    /// # Picking up where the delay was
+    ///
+    /// Playback starts at the sample the live read would have reached
+    /// next, `delay` samples behind the head. Nothing written from here
+    /// on would have been heard for a full period anyway, so the frozen
+    /// loop plays exactly what the delay would have — until it reaches
+    /// the region's end and wraps. So the region always reaches back
+    /// that far, silence and all if the lines haven't been filled yet:
+    /// starting anywhere newer would jump.
+    fn capture_freeze(&mut self, quantize: bool) {
+        let seam = ((FREEZE_ROOM_MS / 1000.0 * self.sample_rate) as usize).max(1);
+        let period = calculate_delay_samples(self.target_delay_ms(), self.sample_rate).round();
+        let period = (period as usize).max(1);
+        let heard = calculate_delay_samples(self.current_delay_ms(), self.sample_rate).round();
+        let heard = (heard as usize).max(1);
+
+        // A second channel standing in for the first (see Shared Lines)
+        // hasn't been written; the split copies the first over it before
+        // the loop plays.
+        let live = if self.lines_shared { 1 } else { usize::MAX };
+        let written = self
+            .channel_states
+            .iter()
+            .take(live)
+            .map(|state| state.delay_line.len_written())
+            .min()
+            .unwrap_or(0);
+        let available = written.saturating_sub(seam);
+        let len = if quantize {
+            available / period * period
+        } else {
+            available
+        };
+        // Too little recorded to reach back to what's being heard: loop
+        // from there anyway, the silence before the recording included —
+        // whole periods of it, quantized.
+        let len = if len < heard {
+            let reach = if quantize {
+                heard.div_ceil(period) * period
+            } else {
+                heard
+            };
+            reach.min(self.line_capacity().saturating_sub(seam)).max(1)
+        } else {
+            len
+        };
+
+        if quantize {
+            for state in &mut self.channel_states {
+                let gains =
+                    (0..seam).map(|k| gains_at(FadeLaw::EqualPower, k as f32 / seam as f32));
+                state.delay_line.blend_seam(len, gains);
+            }
+        }
+
+        self.frozen = Some(FrozenLoop {
+            len,
+            pos: len.saturating_sub(heard),
+            moved: 0,
+            caught_fade: self.time_fade.is_some(),
+        });
+        self.freeze.jump(true);
+    }
+
+    /// The frozen loop's read for this sample, moving playback on by
+    /// one. `None` when nothing is frozen or fading out.
+    #[inline]
+    fn next_frozen_tap(&mut self) -> Option<FrozenTap> {
+        let frozen = self.frozen.as_mut()?;
+        let (live_gain, frozen_gain) = self.freeze.next_gains();
+        let tap = FrozenTap {
+            delay: frozen.len - frozen.pos + frozen.moved,
+            live_gain,
+            frozen_gain,
+            caught_fade: frozen.caught_fade,
+        };
+        frozen.pos = (frozen.pos + 1) % frozen.len;
+        if !self.freeze.is_on() {
+            // Releasing: the live path moves the heads on from here.
+            frozen.moved += 1;
+            if !self.freeze.is_active() {
+                self.frozen = None;
+            }
+        }
+        Some(tap)
+    }
+
+    /// The delay time for this sample, in milliseconds, with `time_fine`
+    /// the fine offset on it: the coarse time's glide stepped on by one,
+    /// plus the offset — or in Tape mode, where the tape head has run
+    /// to, chasing that — with the time modulation on top.
+    #[inline]
+    fn next_delay_ms(&mut self, time_fine: f32) -> f32 {
+        let delay_ms = effective_delay_ms(self.delay_time.next(), time_fine, self.max_delay_ms);
+        let delay_ms = match &mut self.tape_head {
+            Some(head) => head.next(delay_ms, self.scrub),
+            None => delay_ms,
+        };
+        delay_ms + self.next_time_modulation()
+    }
+
+    /// The delay time the last sample was read at, in milliseconds.
+    fn current_delay_ms(&self) -> f32 {
+        let delay_ms = match &self.tape_head {
+            Some(head) => head.delay_ms(),
+            None => effective_delay_ms(
+                self.delay_time.current(),
+                self.time_fine.current(),
+                self.max_delay_ms,
+            ),
+        };
+        delay_ms + self.time_modulation_ms
+    }
+
+    /// Whether the time modulation is moving the delay time, or is on
+    /// its way in or out.
+    fn modulating(&self) -> bool {
+        self.mod_depth.current() > 0.0 || self.mod_depth.is_smoothing()
+    }
+
+    /// How far the time modulation moves the delay time on this sample,
+    /// in milliseconds, stepping the depth's glide and the LFO on by
+    /// one. At zero depth the LFO stands still and this is exactly 0.0.
+    #[inline]
+    fn next_time_modulation(&mut self) -> f32 {
+        let depth = self.mod_depth.next();
+        self.time_modulation_ms = if depth > 0.0 {
+            depth * self.next_lfo()
+        } else {
+            0.0
+        };
+        self.time_modulation_ms
+    }
+
+    /// The time LFO's next value, faded over from the old waveform's
+    /// while a change of waveform runs.
+    #[inline]
+    fn next_lfo(&mut self) -> f32 {
+        let value = self.time_lfo.next();
+        if !self.lfo_fade.is_active() {
+            return value;
+        }
+        let (old_gain, new_gain) = self.lfo_fade.next_gains();
+        old_gain * self.faded_lfo.next() + new_gain * value
+    }
+
+    /// Run the time LFO on over `num_samples` samples that aren't
+    /// processed (the engine is asleep), so it's where it would have
+    /// been when they are again, however the blocks fall.
+    fn skip_time_modulation(&mut self, num_samples: usize) {
+        let depth = self.mod_depth.current();
+        if depth > 0.0 && num_samples > 0 {
+            let mut value = 0.0;
+            for _ in 0..num_samples {
+                value = self.time_lfo.next();
+            }
+            self.time_modulation_ms = depth * value;
+        }
+    }
+
+    /// Start the time LFO over at the top of its cycle, with no change
+    /// of waveform fading.
+    fn restart_time_lfo(&mut self) {
+        self.time_lfo.reset(0.0);
+        self.faded_lfo.reset(0.0);
+        self.lfo_fade.start(0);
+        self.time_modulation_ms = 0.0;
+    }
+
+    /// Whether the tape head is being scrubbed, or is still running to
+    /// its delay time.
+    fn tape_moving(&self) -> bool {
+        self.tape_head
+            .as_ref()
+            .is_some_and(|head| !head.is_locked(self.target_delay_ms(), self.scrub))
+    }
+
+    /// The old head's read for this sample while the coarse delay time
+    /// crossfades, moving the fade on by one, with `time_fine` the fine
+    /// offset on this sample, and the time modulation on top, as
+    /// [`next_delay_ms()`](Self::next_delay_ms) left it. `None` outside
+    /// a crossfade. A change that waited for the fade starts as soon as
+    /// it's done.
+    #[inline]
+    fn next_time_tap(&mut self, time_fine: f32) -> Option<TimeTap> {
+        let time_fade = self.time_fade.as_mut()?;
+        let (old_gain, new_gain) = time_fade.fade.next_gains();
+        let tap = TimeTap {
+            delay_samps: calculate_delay_samples(
+                effective_delay_ms(time_fade.from_ms, time_fine, self.max_delay_ms)
+                    + self.time_modulation_ms,
+                self.sample_rate,
+            ),
+            old_gain,
+            new_gain,
+        };
+        if !time_fade.fade.is_active() {
+            self.time_fade = None;
+            if let Some(frozen) = &mut self.frozen {
+                frozen.caught_fade = false;
+            }
+            if let Some(delay_ms) = self.queued_delay.take() {
+                self.retarget_delay(delay_ms, TimeChange::Crossfade);
+            }
+        }
+        Some(tap)
+    }
+
+    /// The feedback matrix for this sample, moving its glide along.
+    fn next_feedback_matrix(&mut self) -> FeedbackMatrix {
+        let [ll, lr, rl, rr] = self.feedback_matrix.each_mut().map(Smoother::next);
+        FeedbackMatrix { ll, lr, rl, rr }
+    }
+
+    /// The delay time the engine is heading for, in milliseconds.
+    fn target_delay_ms(&self) -> f32 {
+        effective_delay_ms(
+            self.delay_time.target(),
+            self.time_fine.target(),
+            self.max_delay_ms,
+        )
+    }
+
+    /// Start a panic clear: fade the loop and the wet output out over
+    /// `fade_ms`, then wipe the lines. Triggering it again mid-fade
+    /// keeps the running fade unless the new one is quicker (a Clear
+    /// press during a stop fade, say); triggering it mid-wipe starts the
+    /// wipe over. Both are harmless.
+    fn start_clear(&mut self, fade_ms: f32) {
+        let fading = self.tail_clear == TailClear::Fading;
+        self.tail_clear = TailClear::Fading;
+        if fading && fade_ms >= self.clear_fade_ms {
+            return;
+        }
+        self.clear_fade_ms = fade_ms;
+        self.clear_fade = Smoother::new(fade_ms, self.clear_fade.current());
+        self.clear_fade.set_target(self.sample_rate, 0.0);
+    }
+
+    /// The panic clear's gain for this sample, moving the clear along by
+    /// one sample. 1.0 while no clear is running.
+    ///
+    /// # Clearing without a spike
+    ///
+    /// [`DelayLine::clear()`] zeroes seconds of buffer at once — fine
+    /// for `reset()`, far too much for one sample of `process()`. So
+    /// the clear runs in stages:
+    ///
+    /// ```text
+    /// trigger ──► fade 30 ms ──► wipe ~10 ms ──► gain back to 1
+    ///             loop and wet    `chunk` samples
+    ///             go to silence   per line per sample
+    /// ```
+    ///
+    /// The fade scales what's read from the line (so the loop stops
+    /// feeding itself) and the wet output (so it doesn't click). Once it
+    /// reaches zero nothing stale can be heard, and the wipe silences
+    /// the old content a slice at a time with
+    /// [`DelayLine::clear_range()`]. Input keeps going into the lines
+    /// throughout; whatever arrived after the fade is new playing, and
+    /// the wipe works behind it. That new playing fades in over the wipe
+    /// (see [`wipe_fade_in()`](Self::wipe_fade_in)): written at full
+    /// level, it would meet the wiped silence in a step, for the read
+    /// head to play a delay later. When the wipe reaches the end of the
+    /// buffer, the filters are reset and the gain jumps back to 1 — the
+    /// whole wet path is silent, so the jump can't be heard.
+    ///
+    /// A frozen line's write head stands still, so with
+    /// `heads_moving` false nothing new is counted as written: the
+    /// next slice starts right after the last, rather than a sample
+    /// further on, which would leave one stale sample per slice for
+    /// the frozen loop to click on.
+    ///
+    /// A stop fade ([`StopBehavior::Fade`]) runs the same stages, with a
+    /// [`STOP_FADE_MS`] fade.
+    #[inline]
+    fn next_clear_gain(&mut self, heads_moving: bool) -> f32 {
+        match self.tail_clear {
+            TailClear::Idle => 1.0,
+            TailClear::Fading => {
+                let gain = self.clear_fade.next();
+                if !self.clear_fade.is_smoothing() {
+                    let capacity = self.line_capacity();
+                    let wipe_samples = (CLEAR_WIPE_MS / 1000.0 * self.sample_rate).max(1.0);
+                    self.tail_clear = TailClear::Wiping {
+                        since: 0,
+                        wiped: 0,
+                        chunk: capacity.div_ceil(wipe_samples as usize),
+                    };
+                }
+                gain
+            }
+            TailClear::Wiping {
+                since,
+                wiped,
+                chunk,
+            } => {
+                // Delay 1 is the newest sample; the first `since` are
+                // the fresh input written after the fade.
+                let start = since + 1 + wiped;
+                for state in &mut self.channel_states {
+                    state.delay_line.clear_range(start, start + chunk);
+                    state.pre_delay_line.clear_range(start, start + chunk);
+                }
+                self.mono_input.clear_range(start, start + chunk);
+
+                if start + chunk < self.line_capacity() {
+                    self.tail_clear = TailClear::Wiping {
+                        since: since + usize::from(heads_moving),
+                        wiped: wiped + chunk,
+                        chunk,
+                    };
+                    return 0.0;
+                }
+
+                for state in &mut self.channel_states {
+                    state.feedback_chain.reset();
+                    state.crossover.reset();
+                    state.damping.reset();
+                    state.output_filter.reset();
+                }
+                self.clear_fade.reset(1.0);
+                self.tail_clear = TailClear::Idle;
+                1.0
+            }
+        }
+    }
+
+    /// How far back anything still reads from the lines, in samples:
+    /// the farthest of the delay times being heard, headed for, faded
+    /// from or waiting on a beat, plus the pre-delay and any frozen loop.
+    /// A tail capture copies no further (see
+    /// [`capture_tail()`](Self::capture_tail)).
+    fn live_span(&self) -> usize {
+        let waiting = [
+            self.pending_delay.map(|pending| pending.delay_ms),
+            self.time_fade.as_ref().map(|fade| fade.from_ms),
+            self.queued_delay,
+        ];
+        // The coarse times waiting may still have the fine offset on top,
+        // and any of them the time modulation's full swing, wherever the
+        // LFO is in its cycle now.
+        let delay_ms = waiting
+            .into_iter()
+            .flatten()
+            .map(|delay_ms| delay_ms + MAX_TIME_FINE_MS)
+            .fold(
+                (self.current_delay_ms() - self.time_modulation_ms).max(self.target_delay_ms()),
+                f32::max,
+            )
+            + self.mod_depth.current().max(self.mod_depth.target());
+        let pre_delay_ms = self.pre_delay.current().max(self.pre_delay.target());
+        let frozen = self.frozen.map_or(0, |frozen| frozen.len + frozen.moved);
+        // A couple of samples more for the interpolated reads.
+        let reach = calculate_delay_samples(delay_ms + pre_delay_ms, self.sample_rate).ceil();
+        (reach as usize + frozen + 2).min(self.line_capacity())
+    }
+
+    /// The delay lines' length in samples (they're all the same).
+    fn line_capacity(&self) -> usize {
+        self.channel_states
+            .first()
+            .map_or(0, |state| state.delay_line.capacity())
+    }
+
+    /// Count a quantized delay change down by one sample, starting its
+    /// ramp once its beat arrives.
+    #[inline]
+    fn advance_pending_delay(&mut self) {
+        if let Some(pending) = &mut self.pending_delay {
+            if pending.samples_left == 0 {
+                let PendingDelay {
+                    delay_ms, change, ..
+                } = *pending;
+                self.pending_delay = None;
+                self.retarget_delay(delay_ms, change);
+            } else {
+                pending.samples_left -= 1;
+            }
+        }
+    }
+
+    /// The character macro's next offsets, the knobs' next feedback
+    /// cutoffs, and the next drive with its offset applied on top of the
+    /// knob's smoothed value. The cutoffs come without theirs: applying
+    /// it costs an `exp2()`, so the per-sample paths only do it on a
+    /// control tick.
+    ///
+    /// This is the modulation layer: the macro never touches the
+    /// `filter_cutoffs` or `drive` smoothers themselves, only what
+    /// reaches the DSP.
+    #[inline]
+    fn next_shaping_values(&mut self) -> (CharacterOffsets, [f32; 2], f32) {
+        let offsets = CharacterOffsets::at(self.character.next());
+        let filter_cutoffs = self.filter_cutoffs.each_mut().map(Smoother::next);
+        (offsets, filter_cutoffs, offsets.drive(self.drive.next()))
+    }
+
+    /// Stand in for a wet-path stage with `samples` of latency, which
+    /// nothing in the engine has yet, so the dry compensation can be
+    /// tested.
+    #[cfg(test)]
+    fn set_wet_latency(&mut self, samples: usize) {
+        self.wet_latency = samples;
+        for state in &mut self.channel_states {
+            state.dry_compensation = FixedDelay::new(samples);
+        }
+    }
+
+    /// Stand in for a feedback-chain stage with `samples` of latency,
+    /// which nothing in the engine has yet, so the loop's compensation
+    /// can be tested.
+    #[cfg(test)]
+    fn set_feedback_latency(&mut self, samples: usize) {
+        for state in &mut self.channel_states {
+            state.chain_latency = samples;
+            state.chain_stage = FixedDelay::new(samples);
+        }
+    }
+
+    /// Whether mono-safe mode links the first two channels of a block
+    /// with `num_channels` channels. Linking needs both channels each
+    /// sample, so it always takes the per-sample path.
+    fn links_channels(&self, num_channels: usize) -> bool {
+        self.mono_safe.on_path_needed() && self.has_pair(num_channels)
+    }
+
+    /// Whether stereo motion moves the repeats of a block with
+    /// `num_channels` channels between the first two, or is fading from
+    /// one setting to another.
+    fn moves_channels(&self, num_channels: usize) -> bool {
+        (self.motion != StereoMotion::Off || self.motion_fade.is_active())
+            && self.has_pair(num_channels)
+    }
+
+    /// Whether the feedback matrix mixes the two sides' repeats in a
+    /// block with `num_channels` channels, or is gliding.
+    fn mixes_feedback(&self, num_channels: usize) -> bool {
+        let straight = matrix_entries(FeedbackMatrix::STRAIGHT);
+        let gliding = self.feedback_matrix.iter().any(Smoother::is_smoothing);
+        let current = self.feedback_matrix.each_ref().map(Smoother::current);
+        (gliding || current != straight) && self.has_pair(num_channels)
+    }
+
+    /// Whether the dry pan moves the dry signal of a block with
+    /// `num_channels` channels off centre, or is on its way there.
+    fn pans_dry(&self, num_channels: usize) -> bool {
+        (self.dry_pan.current() != 0.0 || self.dry_pan.is_smoothing())
+            && self.has_pair(num_channels)
+    }
+
+    /// Whether Spread pans the repeats of a block with `num_channels`
+    /// channels apart, or is on its way to or from 0%.
+    fn spreads_repeats(&self, num_channels: usize) -> bool {
+        (self.spread.current() > 0.0 || self.spread.is_smoothing()) && self.has_pair(num_channels)
+    }
+
+    /// Whether side decay narrows the feedback of a block with
+    /// `num_channels` channels, or is on its way to or from 0 dB.
+    fn narrows_feedback(&self, num_channels: usize) -> bool {
+        (self.side_decay.current() != 1.0 || self.side_decay.is_smoothing())
+            && self.has_pair(num_channels)
+    }
+
+    /// Whether the wet sides of a block with `num_channels` channels are
+    /// swapped, or on their way to or from it.
+    fn swaps_wet(&self, num_channels: usize) -> bool {
+        (self.swap_channels.is_on() || self.swap_channels.is_active())
+            && self.has_pair(num_channels)
+    }
+
+    /// Whether the wet sides of a block with `num_channels` channels
+    /// alternate repeat by repeat, or are on their way to or from it.
+    fn alternates_wet(&self, num_channels: usize) -> bool {
+        (self.alternate.is_on() || self.alternate.is_active()) && self.has_pair(num_channels)
+    }
+
+    /// Whether the key gate has a say in this block: it's listening to
+    /// `key`, or still moving after being switched or losing its key.
+    /// Either way each sample needs its own gain (the smoothing path).
+    fn gates_input(&self, key: Option<&[&mut [f32]]>) -> bool {
+        !self.key_gate.is_bypassed() || (self.key_gate.is_enabled() && key.is_some())
+    }
+
+    /// Whether anything couples the first two channels, so both have to
+    /// be read before either is written (always the per-sample path).
+    fn couples_channels(&self, num_channels: usize) -> bool {
+        self.links_channels(num_channels)
+            || self.moves_channels(num_channels)
+            || self.mixes_feedback(num_channels)
+            || self.pans_dry(num_channels)
+            || self.spreads_repeats(num_channels)
+            || self.swaps_wet(num_channels)
+            || self.alternates_wet(num_channels)
+            || self.narrows_feedback(num_channels)
+    }
+
+    /// Whether the smoothing path can take a block with `num_channels`
+    /// channels a channel at a time (see
+    /// [Processing Order](self#processing-order)): nothing couples the
+    /// channels, neither the watchdog nor phase alignment is listening
+    /// to their wet signals, the loop isn't frozen, and no panic clear is
+    /// wiping the lines under the channels' feet.
+    fn runs_channel_major(&self, num_channels: usize) -> bool {
+        self.processing_order == ProcessingOrder::ChannelMajor
+            && !self.couples_channels(num_channels)
+            && !self.watchdog.is_enabled()
+            && !self.aligning
+            && !self.freeze.is_on()
+            && self.tail_clear == TailClear::Idle
+    }
+
+    /// Whether a block with `num_channels` channels has a left and right
+    /// for the engine to pair up.
+    fn has_pair(&self, num_channels: usize) -> bool {
+        num_channels >= 2 && self.channel_states.len() >= 2
+    }
+
+    /// Whether a stereo engine can run this block on the first
+    /// channel's state alone (see [Shared Lines](self#shared-lines)):
+    /// the lines are shared already, the two inputs are the same to the
+    /// bit, and nothing would treat the sides differently.
+    fn can_share_lines(&self, channels: &[&mut [f32]], key: Option<&[&mut [f32]]>) -> bool {
+        let [left, right] = channels else {
+            return false;
+        };
+        let [left_cutoff, right_cutoff] = &self.filter_cutoffs;
+        self.lines_shared
+            && self.channel_states.len() == 2
+            && left.len() == right.len()
+            && left
+                .iter()
+                .zip(right.iter())
+                .all(|(l, r)| l.to_bits() == r.to_bits())
+            && !self.couples_channels(2)
+            && !self.gates_input(key)
+            && left_cutoff.current() == right_cutoff.current()
+            && left_cutoff.target() == right_cutoff.target()
+            && self.decorrelate.current() == 0.0
+            && !self.decorrelate.is_smoothing()
+            && self.input_balance.current() == 0.0
+            && !self.input_balance.is_smoothing()
+            && !self.freeze.is_on()
+            && !self.freeze.is_active()
+            && self.frozen.is_none()
+            && self.tail_clear == TailClear::Idle
+    }
+
+    /// Take on `other`'s repeats in flight — the newest `span` samples
+    /// of the lines, the filters, and the state that shapes what's heard
+    /// of them — in place, without allocating. `other` should be built
+    /// alike (see [`tail_snapshot()`](Self::tail_snapshot)). Settings
+    /// aren't copied: they come with `set_params()`.
+    fn copy_tail_from(&mut self, other: &Self, span: usize) {
+        for (ours, theirs) in self.channel_states.iter_mut().zip(&other.channel_states) {
+            ours.copy_from(theirs, span);
+        }
+        for (ours, theirs) in self.limiters.iter_mut().zip(&other.limiters) {
+            ours.clone_from(theirs);
+        }
+        self.lines_shared = other.lines_shared;
+        self.mono_input.copy_recent_from(&other.mono_input, span);
+        self.bloom_envelope.clone_from(&other.bloom_envelope);
+        self.sequencer.clone_from(&other.sequencer);
+        self.pan_rotor.clone_from(&other.pan_rotor);
+        self.phase_aligner.clone_from(&other.phase_aligner);
+        self.frozen = other.frozen;
+        if let (Some(ours), Some(theirs)) = (&mut self.tape_head, &other.tape_head) {
+            ours.clone_from(theirs);
+        }
+        self.time_lfo.clone_from(&other.time_lfo);
+        self.faded_lfo.clone_from(&other.faded_lfo);
+        self.lfo_fade.clone_from(&other.lfo_fade);
+        self.time_modulation_ms = other.time_modulation_ms;
+        let sample_rate = self.sample_rate;
+        for (ours, theirs) in [
+            (&mut self.engage, &other.engage),
+            (&mut self.wet_polarity, &other.wet_polarity),
+        ] {
+            ours.reset(theirs.current());
+            ours.set_target(sample_rate, theirs.target());
+        }
+    }
+
+    /// Give the second channel a state of its own again, a copy of the
+    /// first's, if they've been sharing one (see
+    /// [Shared Lines](self#shared-lines)). From the next sample on, each
+    /// goes its own way from where the shared state left off, so the
+    /// split is seamless. Copies in place: safe on the audio thread.
+    fn split_lines(&mut self) {
+        if !self.lines_shared {
+            return;
+        }
+        self.lines_shared = false;
+        let span = self.line_capacity();
+        if let [left, right, ..] = &mut self.channel_states[..] {
+            right.copy_from(left, span);
+        }
+        if let [left, right, ..] = &mut self.limiters[..] {
+            right.clone_from(left);
+        }
+    }
+}
+
+/// Everything one channel needs to run the delay: its ring buffer, the
+/// stages its repeats pass through on the way back in, and the pre-delay
+/// and filter they pass through on the way out.
+struct ChannelState {
+    delay_line: DelayLine,
+
+    /// A short second ring buffer on the wet output, outside the loop.
+    /// Delaying the wet signal *after* the feedback tap shifts every
+    /// echo by the same amount without changing the repeat spacing.
+    pre_delay_line: DelayLine,
+
+    feedback_chain: FeedbackChain,
+
+    /// Lowpass half of the mono-safe crossover on the feedback signal.
+    crossover: OnePoleFilter,
+
+    /// Follows the loop level and lowers the feedback cutoff with it.
+    damping: LoopDamping,
+
+    output_filter: OutputFilter,
+
+    /// The panic clear's gain on this sample, applied to what's read
+    /// from the line and to the wet output. 1.0 unless a clear is
+    /// running (see [`LovelessDelayEngine::next_clear_gain()`]).
+    clear_gain: f32,
+
+    /// The dry signal's level on this sample: 1.0, or fading to 0.0
+    /// while wet solo is on.
+    dry_gain: f32,
+
+    /// Pulls this side's wet signal away from the other's, by
+    /// `decorrelate` (0.0–1.0) on this sample. The right side (and any
+    /// beyond it) takes the inverted half of the pair.
+    decorrelator: Decorrelator,
+    decorrelate: f32,
+
+    /// The wet signal's polarity on this sample (see
+    /// [`DelayParams::phase_align`]), and while phase alignment is in
+    /// circuit, what this channel has measured since the last control
+    /// tick.
+    wet_polarity: f32,
+    phase_meter: Option<PhaseMeter>,
+
+    /// While the dry pan is off centre, this channel's dry sample on
+    /// this sample, panned together with the other side's. `None` mixes
+    /// the channel's own input.
+    panned_dry: Option<f32>,
+
+    /// The key gate's gain on this sample, applied to the input on its
+    /// way into the line (never to the dry signal). 1.0 while the gate
+    /// is out of circuit.
+    key_gain: f32,
+
+    /// The bloom's gain on this sample, applied to the wet output (see
+    /// [`DelayParams::bloom_db`]). 1.0 while bloom is off.
+    bloom_gain: f32,
+
+    /// The input balance's gain for this side (see [`balance_gains()`]),
+    /// applied with `key_gain`. 1.0 while the balance is centred, and
+    /// always beyond the first two channels.
+    balance: f32,
+
+    /// The feedback compensation's boost for this side's filter, worked
+    /// out with its cutoff (see [`compensation_boost()`]). 1.0 while
+    /// compensation is off.
+    compensation: f32,
+
+    /// How much of the feedback chain's source is the dry input: the
+    /// chain hears `delayed · (1 − dry_feed) + input · dry_feed`. 0.0
+    /// from the wet tap; `1 − mix` post-mix (see [`FeedbackSource`]).
+    dry_feed: f32,
+
+    /// While a freeze is releasing, the frozen loop's read on this
+    /// sample, faded against the live one.
+    frozen_tap: Option<FrozenTap>,
+
+    /// While the delay time crossfades, the old head's read on this
+    /// sample, faded against the new one.
+    time_tap: Option<TimeTap>,
+
+    /// Holds the dry signal back by the wet path's latency, so both
+    /// reach the mix together. `None` when the wet path has no latency.
+    dry_compensation: Option<FixedDelay>,
+
+    /// How many samples late the feedback chain hands back its signal.
+    /// The loop tap reads that much earlier, so a trip round the loop
+    /// still takes the delay time. See [`FEEDBACK_PATH_LATENCY`].
+    chain_latency: usize,
+
+    /// The stand-in for a late feedback-chain stage, from
+    /// [`LovelessDelayEngine::set_feedback_latency()`].
+    #[cfg(test)]
+    chain_stage: Option<FixedDelay>,
+}
+
+impl ChannelState {
+    /// Take on `other`'s audio state — the newest `span` samples of its
+    /// lines, its filters and decorrelator — in place, without
+    /// allocating. The per-sample values are set afresh on every sample,
+    /// so they're left alone, and so is the decorrelator's side. See
+    /// [`LovelessDelayEngine::split_lines()`].
+    fn copy_from(&mut self, other: &Self, span: usize) {
+        self.delay_line.copy_recent_from(&other.delay_line, span);
+        self.pre_delay_line
+            .copy_recent_from(&other.pre_delay_line, span);
+        self.feedback_chain.clone_from(&other.feedback_chain);
+        self.crossover.clone_from(&other.crossover);
+        self.damping.clone_from(&other.damping);
+        self.output_filter.clone_from(&other.output_filter);
+        self.decorrelator.copy_from(&other.decorrelator);
+        self.phase_meter = other.phase_meter;
+        if let (Some(ours), Some(theirs)) = (&mut self.dry_compensation, &other.dry_compensation) {
+            ours.line.copy_from(&theirs.line);
+        }
+        #[cfg(test)]
+        if let (Some(ours), Some(theirs)) = (&mut self.chain_stage, &other.chain_stage) {
+            ours.line.copy_from(&theirs.line);
+        }
+    }
+}
+
+/// A fixed delay of a whole number of samples: on the dry signal, to
+/// match the wet path's latency, and in tests, standing in for a
+/// feedback-chain stage that hands its signal back late.
+struct FixedDelay {
+    line: DelayLine,
+    latency: usize,
+}
+
+impl FixedDelay {
+    /// A delay of `latency` samples, or `None` for no latency, where
+    /// there's nothing to delay.
+    fn new(latency: usize) -> Option<Self> {
+        // One slot longer than the latency: the line is read before the
+        // current sample is written.
+        let length = NonZeroUsize::new(latency.checked_add(1)?)?;
+        (latency > 0).then(|| Self {
+            line: DelayLine::new(length),
+            latency,
+        })
+    }
+
+    /// How many bytes [`new()`](Self::new) allocates for `latency`.
+    fn memory_bytes_for(latency: usize) -> usize {
+        if latency > 0 {
+            (latency + 1) * std::mem::size_of::<f32>()
+        } else {
+            0
+        }
+    }
+
+    /// Delay one sample.
+    #[inline]
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.line.read_int(self.latency);
+        self.line.write(input);
+        self.line.advance();
+        output
+    }
+}
+
+/// Hand one channel the values it shares with the others on this
+/// sample, and return the feedback it applies with the gain round its
+/// loop. Shared by both processing orders of the smoothing path.
+#[inline]
+fn prepare_channel(
+    state: &mut ChannelState,
+    values: &SharedValues,
+    channel_idx: usize,
+    sample_rate: f32,
+) -> (f32, f32) {
+    // Update the filters' cutoff frequencies. We do this within the
+    // block (not once per buffer) because the cutoff parameters might be
+    // smoothing toward new values, and we want the filters to track that
+    // smoothly — but only on control ticks, gliding in between. Each
+    // side follows its own feedback cutoff, lowered by damping as its
+    // own loop gets quieter.
+    if let Some(steps) = values.control_steps {
+        let cutoff = update_filters(
+            state,
+            values
+                .offsets
+                .cutoff(values.filter_cutoffs[side(channel_idx)]),
+            values.damping,
+            [values.out_lowcut, values.out_highcut],
+            sample_rate,
+            steps,
+        );
+        state.compensation = compensation_boost(values.compensation, values.feedback, cutoff);
+    }
+    state.feedback_chain.saturator.set_drive(values.drive);
+    state.feedback_chain.set_shelf_gains(values.shelf_gains);
+    state.clear_gain = values.clear_gain;
+    state.dry_gain = values.dry_gain;
+    state.decorrelate = values.decorrelate;
+    state.wet_polarity = values.wet_polarity;
+    state.panned_dry = values
+        .dry_pair
+        .and_then(|pair| pair.get(channel_idx).copied());
+    state.key_gain = values.key_gain;
+    state.bloom_gain = values.bloom_gain;
+    state.balance = values.balance.get(channel_idx).copied().unwrap_or(1.0);
+    state.dry_feed = values.post_mix * (1.0 - values.mix);
+    state.frozen_tap = values.frozen_tap;
+    state.time_tap = values.time_tap;
+
+    // Ease the feedback back if this channel's stages would push the
+    // loop toward unity gain.
+    let stage_gain = loop_stage_gain(state);
+    let feedback = limit_feedback(values.feedback * state.compensation, stage_gain);
+    (feedback, feedback * stage_gain)
+}
+
+/// Run one sample of one channel through the delay and return the
+/// output sample along with the delayed (wet) sample it was built from.
+/// Shared by both processing paths so they can't drift apart.
+///
+/// `integer_delay` is `Some` when the delay is a settled whole number of
+/// samples and the non-interpolating read can be used.
+/// `pre_delay_samps` is the extra delay applied to the wet signal only.
+#[inline]
+fn process_sample(
+    state: &mut ChannelState,
+    input_sample: f32,
+    delay_samps: f32,
+    integer_delay: Option<usize>,
+    pre_delay_samps: f32,
+    feedback: f32,
+    mix: f32,
+) -> (f32, f32) {
+    // ═══════════════════════════════════════════════════════
+    // THE DELAY ALGORITHM — 6 steps per sample
+    // ═══════════════════════════════════════════════════════
+    //
+    // The steps are split across helpers so mono-safe mode and
+    // stereo motion can couple the channels between them (see
+    // `link_low_bands()` and `StereoMotion`).
+    let (delayed_sample, shaped) = read_and_shape(state, input_sample, delay_samps, integer_delay);
+    let output = write_and_mix(
+        state,
+        input_sample,
+        delayed_sample,
+        shaped,
+        pre_delay_samps,
+        feedback,
+        mix,
+    );
+    (output, delayed_sample)
+}
+
+/// Steps 1–2 of the delay algorithm: read the delayed sample and shape
+/// it through the feedback chain. Returns `(delayed, shaped)`.
+///
+/// `input_sample` is this channel's dry input, which the chain hears
+/// too while the feedback comes from the post-mix signal (see
+/// [`FeedbackSource`]).
+#[inline]
+fn read_and_shape(
+    state: &mut ChannelState,
+    input_sample: f32,
+    delay_samps: f32,
+    integer_delay: Option<usize>,
+) -> (f32, f32) {
+    // Step 1: READ the delayed sample from the ring buffer.
+    let delayed_sample = read_line(state, delay_samps, integer_delay, 0);
+
+    // A feedback chain that hands its signal back late would make every
+    // trip round the loop that much longer than the delay time, so the
+    // loop tap reads that much earlier, and the repeats land where the
+    // knob says. The heard echo still comes from the full delay.
+    let looped = match state.chain_latency {
+        0 => delayed_sample,
+        latency => read_line(state, delay_samps, integer_delay, latency),
+    };
+
+    // The damping follows the loop level whether it's in use or not, so
+    // turning it up mid-tail starts from the level the loop is really at.
+    state.damping.follow(looped);
+
+    // Step 2: SHAPE the delayed sample through the feedback chain.
+    //
+    // The lowpass simulates the high-frequency loss that occurs
+    // in analog delay circuits. Each time the signal passes
+    // through the feedback loop, it goes through this filter
+    // again, so the repeats get progressively darker.
+    //
+    // First repeat: filtered once (slightly darker)
+    // Second repeat: filtered twice (noticeably darker)
+    // Third repeat: filtered three times (quite dark)
+    // ...and so on.
+    //
+    // The saturator (when drive is up) rounds off loud peaks
+    // the same way on every pass, like tape compression.
+    //
+    // Fed from the post-mix signal, the chain hears the mix of the
+    // delayed sample and the dry input rather than the delayed sample
+    // alone. The key gate and the balance scale the dry part like any
+    // other input.
+    let source = if state.dry_feed > 0.0 {
+        let input = input_sample * state.balance * state.key_gain;
+        looped * (1.0 - state.dry_feed) + input * state.dry_feed
+    } else {
+        looped
+    };
+    let filtered = state.feedback_chain.process(source);
+    #[cfg(test)]
+    let filtered = match &mut state.chain_stage {
+        Some(stage) => stage.process(filtered),
+        None => filtered,
+    };
+
+    (delayed_sample, filtered)
+}
+
+/// Read the line `delay_samps` back (exactly `integer_delay` when it's
+/// whole), through any time crossfade, freeze release and panic clear,
+/// with every head `earlier` samples closer to the write head, down to
+/// a sample behind it: the shortest loop that can still play.
+#[inline]
+fn read_line(
+    state: &ChannelState,
+    delay_samps: f32,
+    integer_delay: Option<usize>,
+    earlier: usize,
+) -> f32 {
+    // We look backward in time by `delay_samples` samples.
+    // If the delay is 500ms at 44100 Hz, we're reading the
+    // sample that was written 22050 samples ago. Linear
+    // interpolation handles fractional positions.
+    let delay_line = &state.delay_line;
+    let closer = |delay: f32| (delay - earlier as f32).max(1.0);
+    let closer_int = |delay: usize| delay.saturating_sub(earlier).max(1);
+    let delayed_sample = match integer_delay {
+        Some(delay) => delay_line.read_int(closer_int(delay)),
+        None => delay_line.read(closer(delay_samps)),
+    };
+
+    // Mid crossfade to a new delay time, the old time's head fades out
+    // as the new one fades in.
+    let delayed_sample = match state.time_tap {
+        Some(tap) => TimeTap {
+            delay_samps: closer(tap.delay_samps),
+            ..tap
+        }
+        .blend(delay_line, delayed_sample),
+        None => delayed_sample,
+    };
+
+    // Releasing a freeze fades from the frozen loop to the live read.
+    // The loop keeps feeding the line while it fades, so it carries on
+    // as repeats afterwards.
+    let delayed_sample = match state.frozen_tap {
+        Some(tap) => {
+            tap.live_gain * delayed_sample
+                + tap.frozen_gain * delay_line.read_int(closer_int(tap.delay))
+        }
+        None => delayed_sample,
+    };
+
+    // A panic clear fades out everything the line hands back, so the
+    // loop stops feeding itself (the gain is 1.0 the rest of the time).
+    delayed_sample * state.clear_gain
+}
+
+/// Steps 3–6 of the delay algorithm: scale the shaped sample, write it
+/// back with the input, mix the output, and advance.
+#[inline]
+fn write_and_mix(
+    state: &mut ChannelState,
+    input_sample: f32,
+    delayed_sample: f32,
+    filtered: f32,
+    pre_delay_samps: f32,
+    feedback: f32,
+    mix: f32,
+) -> f32 {
+    write_feedback(state, input_sample * state.balance, filtered, feedback);
+    mix_and_advance(state, input_sample, delayed_sample, pre_delay_samps, mix)
+}
+
+/// The loop gain for an overdub decay of `decay_db` per pass, within
+/// [`OVERDUB_DECAY_RANGE_DB`].
+fn overdub_decay_gain(decay_db: f32) -> f32 {
+    let (min, max) = OVERDUB_DECAY_RANGE_DB;
+    db_to_gain(decay_db.clamp(min, max))
+}
+
+/// The gains overdubbing rewrites a frozen loop's sample with, faded in
+/// by `amount` (0.0–1.0): `(keep, inject)` for `old * keep + input *
+/// inject`. `None` while overdub is off, when the loop is left exactly
+/// as it was.
+///
+/// At full amount the old loop is kept at `decay` (a gain, per pass)
+/// and the input goes in at `√(1 − decay²)`, the power the decay takes
+/// out each pass. So playing on and on over the loop settles it near
+/// the input's own level instead of piling layer on layer, however
+/// little the loop decays:
+///
+/// ```text
+/// decay −6 dB:   keep 0.50   inject 0.87 (−1.2 dB)
+/// decay −1 dB:   keep 0.89   inject 0.45 (−6.9 dB)
+/// ```
+///
+/// A part repeated in time with the loop adds up in amplitude rather
+/// than power and settles louder, at `inject / (1 − keep)`: +4.8 dB at
+/// −6 dB, more the less the loop decays.
+fn overdub_gains(amount: f32, decay: f32) -> Option<(f32, f32)> {
+    if amount == 0.0 {
+        return None;
+    }
+    let keep = 1.0 - amount * (1.0 - decay);
+    let inject = amount * (1.0 - decay * decay).max(0.0).sqrt();
+    Some((keep, inject))
+}
+
+/// The `post_mix` smoother's target for `source`.
+fn post_mix_target(source: FeedbackSource) -> f32 {
+    match source {
+        FeedbackSource::WetTap => 0.0,
+        FeedbackSource::PostMix => 1.0,
+    }
+}
+
+/// The peak gain a channel's loop applies on top of the feedback: the
+/// feedback chain's, times the share of the delayed signal its source
+/// passes on (all of it from the wet tap, `mix` of it post-mix). The
+/// loop-gain ceiling is worked out on this.
+#[inline]
+fn loop_stage_gain(state: &ChannelState) -> f32 {
+    state.feedback_chain.max_gain() * (1.0 - state.dry_feed)
+}
+
+/// A feedback matrix's entries in the order the engine smooths them:
+/// `[ll, lr, rl, rr]`.
+fn matrix_entries(matrix: FeedbackMatrix) -> [f32; 4] {
+    [matrix.ll, matrix.lr, matrix.rl, matrix.rr]
+}
+
+/// A control tick's filter update for one channel: its feedback cutoff,
+/// lowered by loop damping, and its output filter corners, each reached
+/// in a straight line over the next `steps` samples. Returns the
+/// feedback cutoff it's heading for.
+fn update_filters(
+    state: &mut ChannelState,
+    filter_cutoff: f32,
+    damping: f32,
+    [out_lowcut, out_highcut]: [f32; 2],
+    sample_rate: f32,
+    steps: usize,
+) -> f32 {
+    let cutoff = state.damping.cutoff(filter_cutoff, damping);
+    state
+        .feedback_chain
+        .filter
+        .ramp_to_cutoff(cutoff, sample_rate, steps);
+    state
+        .output_filter
+        .ramp_cutoffs(out_lowcut, out_highcut, sample_rate, steps);
+    cutoff
+}
+
+/// The gain on a channel's feedback for a feedback filter at `cutoff`:
+/// the compensation boost for `feedback` (see [`compensation_gain()`]),
+/// faded in by `amount` (0.0–1.0). Exactly 1.0 while compensation is
+/// off. The loop-gain ceiling applies to the boosted feedback, as to
+/// any other.
+fn compensation_boost(amount: f32, feedback: f32, cutoff: f32) -> f32 {
+    if amount == 0.0 {
+        return 1.0;
+    }
+    1.0 + amount * (compensation_gain(feedback, cutoff) - 1.0)
+}
+
+/// Steps 3–4: scale the shaped sample `filtered` by `feedback` and
+/// write it into the line along with `line_input`.
+///
+/// The line's input is usually the channel's own input, but stereo
+/// motion feeds it something else (see [`StereoMotion`]).
+#[inline]
+fn write_feedback(state: &mut ChannelState, line_input: f32, filtered: f32, feedback: f32) {
+    // Step 3: SCALE by the feedback amount.
+    //
+    // This controls how loud each repeat is relative to
+    // the one before it. With feedback = 0.5:
+    //   1st repeat: 50% of original volume
+    //   2nd repeat: 25% (50% of 50%)
+    //   3rd repeat: 12.5% (50% of 25%)
+    //
+    // The signal decays geometrically. Higher feedback =
+    // slower decay = more audible repeats.
+    let feedback_sample = filtered * feedback;
+
+    // Step 4: WRITE (input + feedback) into the ring buffer.
+    //
+    // The current input sample enters the delay line, along
+    // with the feedback signal from the previous iteration
+    // of the loop. This is what creates the recursion:
+    // output feeds back into input, producing echoes of echoes.
+    //
+    // The key gate scales only the input, so a closed gate stops new
+    // material getting in while the repeats carry on.
+    state
+        .delay_line
+        .write(line_input * state.key_gain + feedback_sample);
+}
+
+/// Steps 5–6: mix the dry `input_sample` with the wet `delayed_sample`,
+/// and advance the lines.
+#[inline]
+fn mix_and_advance(
+    state: &mut ChannelState,
+    input_sample: f32,
+    delayed_sample: f32,
+    pre_delay_samps: f32,
+    mix: f32,
+) -> f32 {
+    let output = mix_wet(state, input_sample, delayed_sample, pre_delay_samps, mix);
+
+    // Step 6: ADVANCE the ring buffer's write position.
+    //
+    // Move the "write head" forward by one sample, ready for
+    // the next sample. The delay line handles the wrapping
+    // internally (position resets to 0 at the end of the buffer).
+    state.delay_line.advance();
+
+    output
+}
+
+/// Step 5 on its own: mix the dry and wet signals, moving only the
+/// pre-delay line on. A frozen line stands still, so freeze uses this
+/// directly.
+#[inline]
+fn mix_wet(
+    state: &mut ChannelState,
+    input_sample: f32,
+    delayed_sample: f32,
+    pre_delay_samps: f32,
+    mix: f32,
+) -> f32 {
+    // Step 5: MIX dry (original) and wet (delayed) signals.
+    //
+    // The wet signal first goes through the pre-delay line: written
+    // now, read back `pre_delay_samps` later (at 0 it comes straight
+    // back out). Then it passes through the output low-cut and
+    // high-cut. Both sit outside the loop, so they change when and how
+    // we hear the echoes without changing how the repeats are spaced
+    // or how they decay.
+    //
+    // Then a simple linear crossfade:
+    //   output = dry * (1 - mix) + wet * mix
+    //
+    //   mix = 0.0 → output = input (no delay audible)
+    //   mix = 0.5 → output = 50% input + 50% delayed
+    //   mix = 1.0 → output = delayed only (input silent)
+    //
+    // Wet solo scales the dry half down to nothing, leaving only
+    // the repeats at the level the mix gives them.
+    state.pre_delay_line.write(delayed_sample);
+    let pre_delayed = state.pre_delay_line.read(pre_delay_samps);
+    let wet = state.output_filter.process(pre_delayed) * state.clear_gain;
+    let wet = state.decorrelator.process(wet, state.decorrelate) * state.wet_polarity;
+    let wet = wet * state.bloom_gain;
+    let dry = state.panned_dry.unwrap_or(input_sample);
+    let dry = match &mut state.dry_compensation {
+        Some(compensation) => compensation.process(dry),
+        None => dry,
+    };
+    if let Some(meter) = &mut state.phase_meter {
+        meter.add(dry, wet);
+    }
+    let output = dry * (1.0 - mix) * state.dry_gain + wet * mix;
+    state.pre_delay_line.advance();
+
+    output
+}
+
+/// Move the first repeat, `first`, out of the line it came in on and
+/// into the middle of `heard`, for each motion setting in
+/// `centered_lines` with a centered start: (the line, that setting's
+/// share of a fade).
+#[inline]
+fn center_first_repeat(
+    mut heard: [f32; 2],
+    first: f32,
+    centered_lines: [(Option<usize>, f32); 2],
+) -> [f32; 2] {
+    for (line, gain) in centered_lines {
+        let Some(line) = line else { continue };
+        let first = gain * first;
+        heard[line] -= first;
+        for (heard, gain) in heard.iter_mut().zip(constant_power_gains(0.0)) {
+            *heard += gain * first;
+        }
+    }
+    heard
+}
+
+/// Mono-safe mode: give the left and right feedback signals the same
+/// low band, and return the linked pair.
+///
+/// Each side is split at [`MONO_SAFE_CROSSOVER_HZ`] with a
+/// complementary one-pole pair — a lowpass, and the input minus that
+/// lowpass for the highs, so the bands always sum back to the input.
+/// The highs stay per-side; the lows are replaced by their average.
+/// Both loops then recirculate identical bass, so whatever makes the
+/// sides differ (different cutoffs today) can only act above the
+/// crossover, and the low end can't cancel when the mix is folded to
+/// mono.
+#[inline]
+fn link_low_bands(left: &mut ChannelState, right: &mut ChannelState, shaped: [f32; 2]) -> [f32; 2] {
+    let low_left = left.crossover.process(shaped[0]);
+    let low_right = right.crossover.process(shaped[1]);
+    let low = 0.5 * (low_left + low_right);
+    [shaped[0] - low_left + low, shaped[1] - low_right + low]
+}
+
+/// How long a feedback cut takes to land, in milliseconds. Rises keep
+/// the gentler 20ms ramp.
+const FEEDBACK_FALL_MS: f32 = 5.0;
+
+/// How long a per-repeat step takes to glide to its new value, in
+/// milliseconds: just long enough not to click.
+const STEP_GLIDE_MS: f32 = 5.0;
+
+/// Lowest high-cut corner a step can reach (a step value of 0.0).
+const STEP_MIN_CUTOFF_HZ: f32 = 200.0;
+
+/// Map a step value (0.0–1.0) to a high-cut corner, evenly in octaves
+/// from [`STEP_MIN_CUTOFF_HZ`] up to [`HIGHCUT_OFF_HZ`] (fully open).
+fn step_to_cutoff(value: f32) -> f32 {
+    // Exactly "off" at the top, so a fully open step bypasses the
+    // high-cut instead of landing a rounding error below it.
+    if value >= 1.0 {
+        return HIGHCUT_OFF_HZ;
+    }
+    STEP_MIN_CUTOFF_HZ * (HIGHCUT_OFF_HZ / STEP_MIN_CUTOFF_HZ).powf(value.clamp(0.0, 1.0))
+}
+
+/// Largest magnitude allowed into the delay buffers: +12 dBFS.
+///
+/// Far above anything a sane mix produces, so it never colors normal
+/// material — it only catches runaway values before they get stuck
+/// circulating in the feedback loop.
+const WRITE_CLIP_LEVEL: f32 = 4.0;
+
+/// Level below which the input and the delayed signal count as silence
+/// for tail detection. -90 dBFS is well under the noise floor of any
+/// real playback chain.
+const TAIL_SILENCE_DB: f32 = -90.0;
+
+/// Level below which the input and the delayed signal count as silence
+/// for sleeping (see [`LovelessDelayEngine::process()`]). Far under the
+/// tail threshold: a skipped block is left unprocessed, so whatever the
+/// delay would have made of it must be beyond hearing at any gain.
+const SLEEP_SILENCE_DB: f32 = -120.0;
+
+/// How long switching the limiter or mono-safe mode crossfades between
+/// the two signal paths. 10ms is too short to hear as a fade, but long
+/// enough that the switch doesn't click.
+const SWITCH_FADE_MS: f32 = 10.0;
+
+/// How long a change of the time modulation's waveform crossfades from
+/// the old shape to the new. The fade moves the read head itself, so
+/// [`SWITCH_FADE_MS`] would bend the repeats by a jolt; over a quarter
+/// second they glide.
+const MOD_SWITCH_FADE_MS: f32 = 250.0;
+
+/// How long phase alignment takes to flip the wet signal's polarity.
+/// The wet passes through silence on the way, for a moment too short to
+/// hear as a gap.
+const POLARITY_FADE_MS: f32 = 10.0;
+
+/// How long a crossfaded change of delay time takes, in milliseconds:
+/// as long as the glide, so switching modes changes how a move sounds
+/// but not how long it takes.
+const TIME_FADE_MS: f32 = 50.0;
+
+/// Switching sync on or off always crossfades the delay time, except
+/// when the new time is within this many milliseconds of the old one's
+/// target, when it glides. A bend that small can't be heard, and a
+/// crossfade between two heads a few samples apart would comb-filter
+/// the repeats while it ran.
+const SYNC_GLIDE_MS: f32 = 1.0;
+
+/// How long the effect fades in after the engine is created or reset,
+/// in milliseconds. The mix ramps up from 0 (the input passing straight
+/// through) to its setting.
+const ENGAGE_FADE_MS: f32 = 10.0;
+
+/// How long a panic clear spends wiping the lines once the fade is
+/// done, in milliseconds. Spreading the wipe out keeps each sample's
+/// share of it small.
+const CLEAR_WIPE_MS: f32 = 10.0;
+
+/// How close to a whole number of samples the delay must be, either
+/// side of it, before `process()` switches to the non-interpolating
+/// `read_int()` at the nearest one. A ten-thousandth of a sample is far
+/// below anything audible.
+const INTEGER_DELAY_EPSILON: f32 = 1e-4;
+
+/// How many samples long [`new_delay_line()`] makes a line for
+/// `max_delay_ms` at `sample_rate`.
+fn delay_line_len(max_delay_ms: f32, sample_rate: f32) -> usize {
+    // Calculate the buffer size in samples from the longest read.
+    //
+    // Formula: time_seconds * sample_rate = samples
+    //   2.0 seconds * 44100 Hz = 88200 samples
+    //   2.0 seconds * 48000 Hz = 96000 samples
+    //
+    // `DelayLine::read()` can reach `buffer_len - 1` samples back, so
+    // the line needs one slot more than the longest delay, rounded up
+    // for a fractional one. Nothing more is needed: every read is
+    // clamped to the parameter ranges first (smoothing never overshoots
+    // them), and the lines are read and written one sample at a time,
+    // so a block never has to fit in the buffer on top of the delay.
+    //
+    // Each sample is an f32 (4 bytes), so at 48 kHz this buffer
+    // uses about 380 KB per channel — very modest.
+    (calculate_delay_samples(max_delay_ms, sample_rate).ceil() as usize).saturating_add(1)
+}
+
+/// Allocate a ring buffer long enough for a `max_delay_ms` delay at
+/// `sample_rate`.
+fn new_delay_line(max_delay_ms: f32, sample_rate: f32) -> DelayLine {
+    // `NonZeroUsize` guarantees the delay line can't be zero-length,
+    // which would cause division-by-zero in ring buffer arithmetic.
+    let max_delay_len = NonZeroUsize::new(delay_line_len(max_delay_ms, sample_rate))
+        .expect("max delay samples must be > 0");
+
+    // Writes are clamped to ±WRITE_CLIP_LEVEL (and NaN/inf replaced
+    // with silence) so a transient blow-up in the feedback loop can
+    // never permanently poison the buffer.
+    let mut delay_line = DelayLine::new(max_delay_len);
+    delay_line.set_clip_level(Some(WRITE_CLIP_LEVEL));
+    delay_line
+}
+
+/// How many samples late the wet path delivers its signal, summed over
+/// its stages. Nothing in the wet path adds latency yet; a stage that
+/// does (oversampling, lookahead) adds its share here, and the dry
+/// compensation and the reported latency follow automatically.
+const WET_PATH_LATENCY: usize = 0;
+
+/// How many samples late the feedback chain hands back its signal,
+/// summed over its stages. Every trip round the loop would take that
+/// much longer than the delay time, detuning synced repeats, so the loop
+/// tap reads that much earlier (see [`read_and_shape()`]). Nothing in
+/// the chain adds latency yet; a stage that does (oversampling,
+/// lookahead limiting) adds its share here.
+const FEEDBACK_PATH_LATENCY: usize = 0;
+
+/// Where mono-safe mode splits the feedback signal: below this, both
+/// sides recirculate the same (averaged) low band.
+const MONO_SAFE_CROSSOVER_HZ: f32 = 200.0;
+
+/// The lowpass half of the mono-safe crossover.
+fn new_crossover(sample_rate: f32) -> OnePoleFilter {
+    let mut crossover = OnePoleFilter::new();
+    crossover.set_cutoff(MONO_SAFE_CROSSOVER_HZ, sample_rate);
+    crossover
+}
+
+/// Convert decibels to a linear amplitude: `10^(dB / 20)`.
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+/// A side decay in dB per pass as the side gain it's smoothed in,
+/// clamped to 0–[`MAX_SIDE_DECAY_DB`]. 0 dB is exactly 1.0.
+fn side_decay_gain(db: f32) -> f32 {
+    db_to_gain(-db.clamp(0.0, MAX_SIDE_DECAY_DB))
+}
+
+/// A feedback shelf's setting in dB as the linear gain it's smoothed
+/// in, clamped to ±[`MAX_SHELF_DB`]. 0 dB is exactly 1.0, so a flat
+/// shelf bypasses.
+fn shelf_gain(db: f32) -> f32 {
+    db_to_gain(db.clamp(-MAX_SHELF_DB, MAX_SHELF_DB))
+}
+
+/// Which side's per-side settings (`[left, right]`) a channel uses:
+/// channel 0 is left, everything after it follows the right side.
+const fn side(channel_idx: usize) -> usize {
+    if channel_idx == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+/// The control interval for `sample_rate`: the samples in a millisecond,
+/// within 1..=[`MAX_CONTROL_INTERVAL`].
+fn control_interval_for(sample_rate: f32) -> usize {
+    ((sample_rate / 1000.0) as usize).clamp(1, MAX_CONTROL_INTERVAL)
+}
+
+/// How many samples a block holds: the first channel's length, or none
+/// without channels.
+fn block_len(channels: &[&mut [f32]]) -> usize {
+    channels.first().map_or(0, |c| c.len())
+}
+
+/// The delay time heard, in milliseconds: the coarse time plus the fine
+/// offset, clamped to [`MIN_DELAY_MS`]..=`max_delay_ms` (the engine's
+/// [`max_delay_ms()`](LovelessDelayEngine::max_delay_ms)) only once
+/// they're combined.
+fn effective_delay_ms(coarse_ms: f32, fine_ms: f32, max_delay_ms: f32) -> f32 {
+    (coarse_ms + fine_ms).clamp(MIN_DELAY_MS, max_delay_ms)
+}
+
+/// The longest delay any read head can ask of lines built for
+/// `max_delay_ms`, in milliseconds (see [`MAX_READ_DELAY_MS`]).
+fn read_delay_ms(max_delay_ms: f32) -> f32 {
+    max_delay_ms + MAX_TIME_MODULATION_MS + MAX_STEREO_OFFSET_MS + FREEZE_ROOM_MS
+}
+
+/// Sample `i` of whichever channel of `key` is loudest there, sign and
+/// all, so the key gate's highpass hears a waveform rather than a level.
+/// `None` without a key.
+#[inline]
+fn loudest_key_sample(key: Option<&[&mut [f32]]>, i: usize) -> Option<f32> {
+    key.map(|key| {
+        key.iter()
+            .filter_map(|samples| samples.get(i))
+            .fold(0.0_f32, |loudest, &sample| {
+                if sample.abs() > loudest.abs() {
+                    sample
+                } else {
+                    loudest
+                }
+            })
+    })
+}
+
+const fn calculate_delay_samples(delay_ms: f32, sample_rate: f32) -> f32 {
+    delay_ms * sample_rate / 1000.0
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::smoother::MIN_RAMP_MS;
+    use crate::testing::MockTransport;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    const BLOCK: usize = 2048;
+
+    /// A stereo engine with the delay shortened to 100ms so the first
+    /// echo shows up within a few blocks.
+    fn test_engine() -> LovelessDelayEngine {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(&test_params());
+        skip_engage_fade(&mut engine);
+        engine
+    }
+
+    /// Finish the engage fade-in at once, for tests that check exact
+    /// output from the very first sample. Without this the mix starts
+    /// at 0 and ramps up over [`ENGAGE_FADE_MS`], so an impulse at
+    /// sample 0 comes out dry (see `test_engage_fades_in`). Echoes
+    /// arrive 100ms or more later, long after the fade, so their levels
+    /// don't depend on it.
+    fn skip_engage_fade(engine: &mut LovelessDelayEngine) {
+        engine.engage.reset(1.0);
+    }
+
+    fn test_params() -> DelayParams {
+        DelayParams {
+            delay_ms: 100.0,
+            ..DelayParams::default()
+        }
+    }
+
+    /// A test tone with a different phase per channel.
+    fn input_block(block_idx: usize, channel: usize) -> Vec<f32> {
+        (0..BLOCK)
+            .map(|i| {
+                let n = (block_idx * BLOCK + i) as f32;
+                (n * 0.031 + channel as f32).sin() * 0.5
+            })
+            .collect()
+    }
+
+    /// The first `set_params()` jumps; later ones ramp.
+    #[test]
+    fn test_first_set_params_jumps() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            mix: 0.8,
+            ..DelayParams::default()
+        });
+        assert!(!engine.mix.is_smoothing());
+        assert_eq!(engine.mix.current(), 0.8);
+
+        engine.set_params(&DelayParams {
+            mix: 0.2,
+            ..DelayParams::default()
+        });
+        assert!(engine.mix.is_smoothing());
+    }
+
+    /// A state loaded mid-render lands at once: from the first sample
+    /// after it, every smoothed value is the loaded one, and stays there
+    /// — no sweep from the old settings — while `set_params()` with the
+    /// same values would glide. The wet signal fades back in.
+    #[test]
+    fn test_loaded_state_never_sweeps() {
+        let before = DelayParams {
+            delay_ms: 150.0,
+            feedback: 0.8,
+            mix: 0.3,
+            filter_cutoff: 18000.0,
+            filter_cutoff_right: 18000.0,
+            damping: 0.0,
+            out_highcut: 12000.0,
+            ..DelayParams::default()
+        };
+        let loaded = DelayParams {
+            delay_ms: 1400.0,
+            feedback: 0.2,
+            mix: 1.0,
+            filter_cutoff: 400.0,
+            filter_cutoff_right: 900.0,
+            damping: 1.0,
+            dry_pan: -0.7,
+            out_highcut: 3000.0,
+            output_trim_db: -6.0,
+            motion: StereoMotion::PingPong,
+            ..DelayParams::default()
+        };
+        let render = |load: fn(&mut LovelessDelayEngine, &DelayParams)| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&before);
+            skip_engage_fade(&mut engine);
+            for block_idx in 0..4 {
+                let mut left = input_block(block_idx, 0);
+                let mut right = input_block(block_idx, 1);
+                engine.process(&mut [&mut left, &mut right]);
+            }
+            load(&mut engine, &loaded);
+            let landed = engine.current_values();
+            let mut swept = false;
+            for i in 0..BLOCK {
+                let n = (4 * BLOCK + i) as f32;
+                engine.process(&mut [&mut [(n * 0.031).sin() * 0.5], &mut [n.cos() * 0.5]]);
+                swept |= engine.current_values() != landed
+                    || engine
+                        .channel_states
+                        .iter()
+                        .any(|state| state.feedback_chain.filter.is_fading());
+            }
+            (engine, landed, swept)
+        };
+
+        let (engine, landed, swept) = render(LovelessDelayEngine::load_params);
+        assert!(!swept);
+        assert_eq!(landed.delay_ms, 1400.0);
+        assert_eq!(landed.mix, 1.0);
+        assert_eq!(landed.feedback, 0.2);
+        assert_eq!(landed.dry_pan, -0.7);
+        assert_eq!(landed.output_trim_gain, db_to_gain(-6.0));
+        assert!(engine.time_fade.is_none());
+        assert!(!engine.motion_fade.is_active());
+        for cutoff in &engine.filter_cutoffs {
+            assert!(!cutoff.is_smoothing());
+        }
+
+        let (_, _, swept) = render(LovelessDelayEngine::set_params);
+        assert!(swept);
+    }
+
+    /// A loaded state mutes the wet signal and fades it back in, so the
+    /// read heads' jump to the new delay time is never heard as a step.
+    #[test]
+    fn test_loaded_state_fades_the_wet_back_in() {
+        let mut engine = test_engine();
+        engine.load_params(&DelayParams {
+            delay_ms: 700.0,
+            ..test_params()
+        });
+        assert_eq!(engine.engage.current(), 0.0);
+        let fade = (ENGAGE_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        engine.process(&mut [&mut vec![0.0; fade], &mut vec![0.0; fade]]);
+        assert_eq!(engine.engage.current(), 1.0);
+    }
+
+    /// A cutoff sweep from 20 kHz to 200 Hz glides geometrically: the
+    /// same ratio every sample, so it's at 2 kHz (the halfway point in
+    /// octaves) halfway through its 50ms.
+    #[test]
+    fn test_cutoff_glides_geometrically() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            filter_cutoff: 20000.0,
+            filter_cutoff_right: 20000.0,
+            ..DelayParams::default()
+        });
+        engine.set_params(&DelayParams {
+            filter_cutoff: 200.0,
+            filter_cutoff_right: 200.0,
+            ..DelayParams::default()
+        });
+
+        let mut trajectory = vec![20000.0];
+        for _ in 0..2400 {
+            engine.process(&mut [&mut [0.0]]);
+            trajectory.push(engine.filter_cutoffs[0].current());
+        }
+        let ratio = trajectory[1] / trajectory[0];
+        for w in trajectory.windows(2) {
+            assert!((w[1] / w[0] - ratio).abs() < 1e-4, "{w:?}");
+        }
+        assert!(
+            (trajectory[1200] - 2000.0).abs() < 2.0,
+            "{}",
+            trajectory[1200]
+        );
+        assert_eq!(trajectory[2400], 200.0);
+    }
+
+    /// Asking for the tail, the meter and the current values between
+    /// blocks steps nothing: an engine asked after every block renders
+    /// the same samples, and its smoothers follow the same trajectories,
+    /// as one never asked at all.
+    #[test]
+    fn test_status_reads_do_not_step_smoothers() {
+        let params = DelayParams {
+            feedback: 0.6,
+            hold_level: 0.9,
+            damping: 0.5,
+            dry_pan: 0.3,
+            spread: 0.5,
+            output_trim_db: -3.0,
+            ..test_params()
+        };
+        let moved = DelayParams {
+            delay_ms: 180.0,
+            pre_delay_ms: 20.0,
+            feedback: 0.2,
+            hold: true,
+            mix: 0.3,
+            damping: 0.1,
+            dry_pan: -0.4,
+            spread: 0.0,
+            side_decay_db: 6.0,
+            output_trim_db: 2.0,
+            ..params
+        };
+        let mut quiet = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let mut asked = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        for engine in [&mut quiet, &mut asked] {
+            engine.set_params(&params);
+        }
+
+        for block_idx in 0..8 {
+            if block_idx == 2 {
+                quiet.set_params(&moved);
+                asked.set_params(&moved);
+            }
+            let mut quiet_left = input_block(block_idx, 0);
+            let mut quiet_right = input_block(block_idx, 1);
+            quiet.process(&mut [&mut quiet_left, &mut quiet_right]);
+
+            let mut asked_left = input_block(block_idx, 0);
+            let mut asked_right = input_block(block_idx, 1);
+            for _ in 0..3 {
+                asked.tail_samples();
+                asked.loop_reading();
+                asked.current_values();
+            }
+            asked.process(&mut [&mut asked_left, &mut asked_right]);
+            asked.tail_samples();
+
+            assert_eq!(quiet_left, asked_left, "block {block_idx}");
+            assert_eq!(quiet_right, asked_right, "block {block_idx}");
+            assert_eq!(quiet.current_values(), asked.current_values());
+        }
+    }
+
+    /// The current values are the ones the audio last used: partway
+    /// through a ramp they're partway there, and reading them twice
+    /// gives the same answer.
+    #[test]
+    fn test_current_values_follow_the_audio() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            mix: 0.0,
+            ..test_params()
+        });
+        engine.set_params(&DelayParams {
+            mix: 1.0,
+            ..test_params()
+        });
+        assert_eq!(engine.current_values().mix, 0.0);
+
+        // The mix ramps over 20 ms: 960 samples at 48 kHz.
+        engine.process(&mut [&mut [0.0; 480]]);
+        let halfway = engine.current_values();
+        assert!((halfway.mix - 0.5).abs() < 1e-4, "{}", halfway.mix);
+        assert_eq!(engine.current_values(), halfway);
+
+        engine.process(&mut [&mut [0.0; 480]]);
+        assert_eq!(engine.current_values().mix, 1.0);
+    }
+
+    /// Pulling feedback from 95% to 0% lands within 10ms; turning it
+    /// back up still takes the gentle 20ms.
+    #[test]
+    fn test_feedback_cuts_fast() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            feedback: MAX_FEEDBACK,
+            ..DelayParams::default()
+        });
+        let ten_ms = (SAMPLE_RATE / 100.0) as usize;
+
+        engine.set_params(&DelayParams {
+            feedback: 0.0,
+            ..DelayParams::default()
+        });
+        engine.process(&mut [&mut vec![0.0; ten_ms]]);
+        assert_eq!(engine.feedback.current(), 0.0);
+
+        engine.set_params(&DelayParams {
+            feedback: MAX_FEEDBACK,
+            ..DelayParams::default()
+        });
+        engine.process(&mut [&mut vec![0.0; ten_ms]]);
+        assert!(engine.feedback.current() < MAX_FEEDBACK * 0.6);
+    }
+
+    /// Holding swells the loop gain to the hold level over
+    /// [`HOLD_FADE_MS`]; releasing glides back over the same time, to
+    /// wherever the knob was moved meanwhile. At 100% the loop-gain
+    /// ceiling keeps it under unity.
+    #[test]
+    fn test_hold_slews_loop_gain() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.3,
+            hold_level: 0.8,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        let ten_ms = (SAMPLE_RATE / 100.0) as usize;
+        let mut block_idx = 0;
+        let mut run_ms = |engine: &mut LovelessDelayEngine, ms: usize| {
+            for _ in 0..ms / 10 {
+                let mut block = input_block(block_idx, 0)[..ten_ms].to_vec();
+                engine.process(&mut [&mut block]);
+                block_idx += 1;
+            }
+            engine.loop_gain
+        };
+        assert!((run_ms(&mut engine, 50) - 0.3).abs() < 1e-6);
+
+        params.hold = true;
+        engine.set_params(&params);
+        let halfway = run_ms(&mut engine, 50);
+        assert!((halfway - 0.55).abs() < 0.01, "{halfway}");
+        let held = run_ms(&mut engine, 60);
+        assert!((held - 0.8).abs() < 1e-5, "{held}");
+
+        // The knob moves while held: nothing changes until the release,
+        // which glides to the new setting.
+        params.feedback = 0.5;
+        engine.set_params(&params);
+        assert!((run_ms(&mut engine, 50) - 0.8).abs() < 1e-5);
+        params.hold = false;
+        engine.set_params(&params);
+        let halfway = run_ms(&mut engine, 50);
+        assert!((halfway - 0.65).abs() < 0.01, "{halfway}");
+        let released = run_ms(&mut engine, 60);
+        assert!((released - 0.5).abs() < 1e-5, "{released}");
+
+        params.hold = true;
+        params.hold_level = 1.0;
+        engine.set_params(&params);
+        let full = run_ms(&mut engine, 200);
+        assert!(full > MAX_FEEDBACK && full < LOOP_GAIN_CEILING, "{full}");
+    }
+
+    /// Out-of-range values are clamped to the documented ranges.
+    #[test]
+    fn test_set_params_clamps() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            delay_ms: 10_000.0,
+            feedback: 2.0,
+            mix: -1.0,
+            ..DelayParams::default()
+        });
+        assert_eq!(engine.target_delay_ms(), MAX_DELAY_MS);
+        assert_eq!(engine.feedback.target(), MAX_FEEDBACK);
+        assert_eq!(engine.mix.target(), 0.0);
+    }
+
+    /// Filter updates on control ticks sound like updates on every
+    /// sample: under a cutoff sweep drawn in block by block, the two
+    /// renders stay within a hair of each other, and the ticks add no
+    /// steps of their own.
+    #[test]
+    fn test_control_ticks_track_per_sample_updates() {
+        let render = |interval: usize| {
+            let mut engine = test_engine();
+            engine.set_control_interval(interval);
+            let mut out = Vec::new();
+            for block_idx in 0..60 {
+                let sweep = (block_idx as f32 * 0.2).sin() * 0.5 + 0.5;
+                let cutoff = 300.0 * 50.0_f32.powf(sweep);
+                engine.set_params(&DelayParams {
+                    filter_cutoff: cutoff,
+                    filter_cutoff_right: cutoff,
+                    feedback: 0.8,
+                    ..test_params()
+                });
+                let mut left = input_block(block_idx, 0);
+                let mut right = input_block(block_idx, 1);
+                engine.process(&mut [&mut left, &mut right]);
+                out.extend(left);
+            }
+            out
+        };
+        let largest_step = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f32::max)
+        };
+
+        let per_sample = render(1);
+        let ticked = render(LovelessDelayEngine::new(SAMPLE_RATE, 2).control_interval());
+        let largest_difference = ticked
+            .iter()
+            .zip(&per_sample)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(
+            largest_difference < 0.002,
+            "largest difference {largest_difference}"
+        );
+        assert!(largest_step(&ticked) <= largest_step(&per_sample) * 1.01);
+    }
+
+    /// Economy spreads the ticks out and back again, keeps a benchmark's
+    /// override, and under the same cutoff sweep — switched on and off
+    /// partway — stays close to the full engine, with no steps of its
+    /// own.
+    #[test]
+    fn test_economy_spreads_control_ticks() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let normal = engine.control_interval();
+        engine.set_economy(true);
+        assert!(engine.economy());
+        assert_eq!(engine.control_interval(), normal * ECONOMY_CONTROL_FACTOR);
+        engine.set_control_interval(8);
+        assert_eq!(engine.control_interval(), 8 * ECONOMY_CONTROL_FACTOR);
+        engine.set_economy(false);
+        assert_eq!(engine.control_interval(), 8);
+
+        let render = |economy: fn(usize) -> bool| {
+            let mut engine = test_engine();
+            let mut out = Vec::new();
+            for block_idx in 0..60 {
+                engine.set_economy(economy(block_idx));
+                let sweep = (block_idx as f32 * 0.2).sin() * 0.5 + 0.5;
+                let cutoff = 300.0 * 50.0_f32.powf(sweep);
+                engine.set_params(&DelayParams {
+                    filter_cutoff: cutoff,
+                    filter_cutoff_right: cutoff,
+                    feedback: 0.8,
+                    ..test_params()
+                });
+                let mut left = input_block(block_idx, 0);
+                let mut right = input_block(block_idx, 1);
+                engine.process(&mut [&mut left, &mut right]);
+                out.extend(left);
+            }
+            out
+        };
+        let largest_step = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f32::max)
+        };
+
+        let full = render(|_| false);
+        let economy = render(|block_idx| (20..40).contains(&block_idx));
+        let largest_difference = economy
+            .iter()
+            .zip(&full)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(
+            largest_difference < 0.01,
+            "largest difference {largest_difference}"
+        );
+        assert!(largest_step(&economy) <= largest_step(&full) * 1.05);
+    }
+
+    /// An output trim automated from 0 to −12 dB is heard from the very
+    /// next sample, and lands after the full ramp, a quarter of it, or
+    /// the safety floor, as the automation smoothing says.
+    #[test]
+    fn test_automation_smoothing_latency() {
+        for (smoothing, ramp_ms) in [
+            (AutomationSmoothing::Full, 50.0),
+            (AutomationSmoothing::Reduced, 12.5),
+            (AutomationSmoothing::Off, MIN_RAMP_MS),
+        ] {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_automation_smoothing(smoothing);
+            assert_eq!(engine.automation_smoothing(), smoothing);
+            engine.set_params(&test_params());
+            skip_engage_fade(&mut engine);
+
+            // A steady input, and no echo yet: the output is the dry
+            // signal under the trim.
+            let mut before = vec![0.5; 256];
+            engine.process(&mut [&mut before]);
+            engine.set_params(&DelayParams {
+                output_trim_db: -12.0,
+                ..test_params()
+            });
+            let mut out = vec![0.5; 3072];
+            engine.process(&mut [&mut out]);
+
+            assert!(out[0] < before[255], "{smoothing:?}: not heard at once");
+            let settled = out[out.len() - 1];
+            let landed = out.iter().position(|s| (s - settled).abs() < 1e-6);
+            let expected = (ramp_ms / 1000.0 * SAMPLE_RATE).round() as usize - 1;
+            assert_eq!(landed, Some(expected), "{smoothing:?}");
+        }
+    }
+
+    /// With automation smoothing off, a jump of the mix from all dry to
+    /// all wet still ramps over the safety floor: through it the output
+    /// never moves much further in a sample than the signals on either
+    /// side of it do.
+    #[test]
+    fn test_automation_smoothing_off_is_click_free() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_automation_smoothing(AutomationSmoothing::Off);
+        let params = DelayParams {
+            mix: 0.0,
+            feedback: 0.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        skip_engage_fade(&mut engine);
+
+        let mut out = Vec::new();
+        for block_idx in 0..6 {
+            if block_idx == 4 {
+                engine.set_params(&DelayParams { mix: 1.0, ..params });
+            }
+            let mut samples = input_block(block_idx, 0);
+            engine.process(&mut [&mut samples]);
+            out.extend(samples);
+        }
+
+        let largest_step = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f32::max)
+        };
+        let jump = 4 * BLOCK;
+        let ramp = (MIN_RAMP_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let before = largest_step(&out[jump - 4 * ramp..jump]);
+        let after = largest_step(&out[jump + ramp..jump + 5 * ramp]);
+        assert!(after > 0.5 * before, "no echo to mix in: {after}");
+        let across = largest_step(&out[jump - 1..jump + ramp + 1]);
+        assert!(
+            across < 2.0 * before.max(after),
+            "{across} vs {before} and {after}"
+        );
+    }
+
+    /// Dual mono runs on one line, and splits into two the moment the
+    /// inputs part. Through the split, and the smoothing around it, the
+    /// output is to the bit what an engine with its lines split from the
+    /// start plays, so the split can't click; identical inputs after it
+    /// don't share again while the lines still ring.
+    #[test]
+    fn test_shared_lines_split_seamlessly() {
+        let mut shared = test_engine();
+        let mut separate = test_engine();
+        separate.split_lines();
+        let mut outputs = [[Vec::new(), Vec::new()], [Vec::new(), Vec::new()]];
+        for block_idx in 0..30 {
+            // A feedback change early on, so the smoothing path shares too.
+            let params = DelayParams {
+                feedback: if block_idx < 5 { 0.6 } else { 0.8 },
+                ..test_params()
+            };
+            let right_channel = if (10..20).contains(&block_idx) { 1 } else { 0 };
+            for (engine, output) in [&mut shared, &mut separate].into_iter().zip(&mut outputs) {
+                engine.set_params(&params);
+                let mut left = input_block(block_idx, 0);
+                let mut right = input_block(block_idx, right_channel);
+                engine.process(&mut [&mut left, &mut right]);
+                output[0].extend(left);
+                output[1].extend(right);
+            }
+            assert_eq!(shared.lines_shared, block_idx < 10, "block {block_idx}");
+        }
+        let [shared_out, separate_out] = outputs;
+        assert_eq!(shared_out, separate_out);
+        assert_ne!(shared_out[0], shared_out[1]);
+    }
+
+    /// Ping-pong sends identical inputs down different lines, so it
+    /// splits them, seamlessly; once everything has died away and the
+    /// engine sleeps, they're shared again.
+    #[test]
+    fn test_cross_feedback_splits_shared_lines() {
+        let mut shared = test_engine();
+        let mut separate = test_engine();
+        separate.split_lines();
+        let render = |engines: [&mut LovelessDelayEngine; 2],
+                      block_idx: usize,
+                      motion: StereoMotion,
+                      loud: bool| {
+            let params = DelayParams {
+                feedback: 0.5,
+                motion,
+                ..test_params()
+            };
+            let mut outputs = Vec::new();
+            for engine in engines {
+                engine.set_params(&params);
+                let input = input_block(block_idx, 0).into_iter();
+                let mut left: Vec<f32> = input.map(|s| if loud { s } else { 0.0 }).collect();
+                let mut right = left.clone();
+                engine.process(&mut [&mut left, &mut right]);
+                outputs.push([left, right]);
+            }
+            assert_eq!(outputs[0], outputs[1], "block {block_idx}");
+            outputs.remove(0)
+        };
+
+        for block_idx in 0..6 {
+            render(
+                [&mut shared, &mut separate],
+                block_idx,
+                StereoMotion::Off,
+                true,
+            );
+        }
+        assert!(shared.lines_shared);
+        for block_idx in 6..12 {
+            let [left, right] = render(
+                [&mut shared, &mut separate],
+                block_idx,
+                StereoMotion::PingPong,
+                true,
+            );
+            assert!(!shared.lines_shared);
+            if block_idx == 11 {
+                assert_ne!(left, right);
+            }
+        }
+
+        let line_len = shared.mono_input.capacity();
+        let mut silent_blocks = 0;
+        while !shared.is_asleep() {
+            render(
+                [&mut shared, &mut separate],
+                12 + silent_blocks,
+                StereoMotion::PingPong,
+                false,
+            );
+            silent_blocks += 1;
+            assert!(silent_blocks < 20 * line_len / BLOCK, "never fell asleep");
+        }
+        assert!(shared.lines_shared);
+    }
+
+    /// Phase Align acts only under [`phase_align::MAX_DELAY_MS`], so
+    /// even the shortest delay here is an echo it leaves alone: a 101 ms
+    /// repeat of a 500 Hz tone, half a period out from the dry one,
+    /// cancels at 50% mix with the setting on just as it does with it
+    /// off. The flip itself is tested on the [`PhaseAligner`].
+    #[test]
+    fn test_phase_align_leaves_echoes_alone() {
+        const TONE_HZ: f32 = 500.0;
+        let render = |phase_align: bool| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                delay_ms: 101.0,
+                feedback: 0.0,
+                phase_align,
+                ..DelayParams::default()
+            });
+            skip_engage_fade(&mut engine);
+            let mut out = Vec::new();
+            for block_idx in 0..24 {
+                let mut block: Vec<f32> = (0..BLOCK)
+                    .map(|i| {
+                        let n = (block_idx * BLOCK + i) as f32;
+                        (std::f32::consts::TAU * TONE_HZ * n / SAMPLE_RATE).sin() * 0.5
+                    })
+                    .collect();
+                engine.process(&mut [&mut block]);
+                assert!(!engine.phase_flipped());
+                out.extend(block);
+            }
+            (engine, out)
+        };
+
+        let (_, plain) = render(false);
+        let (engine, aligned) = render(true);
+        assert!(!engine.aligning);
+        assert_eq!(engine.wet_polarity.target(), 1.0);
+        assert_eq!(aligned, plain);
+    }
+
+    /// Channel-major order changes only the memory traffic. Under
+    /// automation of everything the channels share — delay time, a
+    /// freeze and its release, a panic clear, the key gate, a mono-safe
+    /// spell that sends blocks back to sample-major order — and with
+    /// block sizes that split the shared stretches, both orders give the
+    /// same output to the bit, and the same loop gain.
+    #[test]
+    fn test_channel_major_matches_sample_major() {
+        let render = |order: ProcessingOrder| {
+            let mut engine = test_engine();
+            engine.set_processing_order(order);
+            let mut out = Vec::new();
+            let mut channel_major_blocks = 0;
+            for block_idx in 0..48 {
+                let sweep = (block_idx as f32 * 0.3).sin() * 0.5 + 0.5;
+                engine.set_params(&DelayParams {
+                    delay_ms: if block_idx < 16 { 120.0 } else { 180.0 },
+                    feedback: 0.5 + 0.4 * sweep,
+                    mix: 0.3 + 0.5 * sweep,
+                    filter_cutoff: 300.0 * 50.0_f32.powf(sweep),
+                    filter_cutoff_right: 300.0 * 50.0_f32.powf(1.0 - sweep),
+                    feedback_compensation: block_idx % 12 < 6,
+                    feedback_source: if block_idx % 8 < 4 {
+                        FeedbackSource::WetTap
+                    } else {
+                        FeedbackSource::PostMix
+                    },
+                    drive: sweep,
+                    damping: 1.0 - sweep,
+                    out_lowcut: 40.0 + 200.0 * sweep,
+                    decorrelate: sweep,
+                    freeze: (24..28).contains(&block_idx),
+                    clear: (30..32).contains(&block_idx),
+                    mono_safe: (34..38).contains(&block_idx),
+                    key_gate: block_idx >= 40,
+                    ..test_params()
+                });
+                let len = 37 + block_idx * 29 % BLOCK;
+                let mut left = input_block(block_idx, 0)[..len].to_vec();
+                let mut right = input_block(block_idx, 1)[..len].to_vec();
+                let mut key: Vec<f32> = input_block(block_idx, 2)[..len]
+                    .iter()
+                    .map(|sample| sample * sweep)
+                    .collect();
+                channel_major_blocks += usize::from(engine.runs_channel_major(2));
+                engine.process_with_key(&mut [&mut left, &mut right], Some(&[&mut key[..]]));
+                out.push((left, right, engine.loop_gain()));
+            }
+            (out, channel_major_blocks)
+        };
+
+        let (sample_major, none) = render(ProcessingOrder::SampleMajor);
+        let (channel_major, taken) = render(ProcessingOrder::ChannelMajor);
+        assert_eq!(none, 0);
+        assert!(taken > 30, "only {taken} blocks went channel-major");
+        for (block_idx, (a, b)) in channel_major.iter().zip(&sample_major).enumerate() {
+            assert!(a == b, "block {block_idx} differs");
+        }
+    }
+
+    /// With only the filters moving — both feedback cutoffs, damping,
+    /// compensation and the output corners — channel-major order takes
+    /// the control-rate path, which must still match sample-major order
+    /// to the bit, loop gain included.
+    #[test]
+    fn test_control_rate_path_matches_sample_major() {
+        let render = |order: ProcessingOrder| {
+            let mut engine = test_engine();
+            engine.set_processing_order(order);
+            let mut out = Vec::new();
+            for block_idx in 0..24 {
+                let sweep = (block_idx as f32 * 0.3).sin() * 0.5 + 0.5;
+                engine.set_params(&DelayParams {
+                    feedback: 0.7,
+                    filter_cutoff: 300.0 * 50.0_f32.powf(sweep),
+                    filter_cutoff_right: 300.0 * 50.0_f32.powf(1.0 - sweep),
+                    feedback_compensation: block_idx % 12 < 6,
+                    damping: 1.0 - sweep,
+                    out_lowcut: 40.0 + 200.0 * sweep,
+                    ..test_params()
+                });
+                let len = 37 + block_idx * 29 % BLOCK;
+                let mut left = input_block(block_idx, 0)[..len].to_vec();
+                let mut right = input_block(block_idx, 1)[..len].to_vec();
+                engine.process(&mut [&mut left, &mut right]);
+                out.push((left, right, engine.loop_gain()));
+            }
+            out
+        };
+
+        let sample_major = render(ProcessingOrder::SampleMajor);
+        let channel_major = render(ProcessingOrder::ChannelMajor);
+        for (block_idx, (a, b)) in channel_major.iter().zip(&sample_major).enumerate() {
+            assert!(a == b, "block {block_idx} differs");
+        }
+    }
+
+    /// Switching between the idle and smoothing paths from one block to
+    /// the next must not change the output: the idle path has to pick up
+    /// exactly where the per-sample path left off (and vice versa).
+    #[test]
+    fn test_idle_and_smoothing_paths_are_seamless() {
+        let mut switching = test_engine();
+        let mut reference = test_engine();
+
+        let mut out_switching = Vec::new();
+        let mut out_reference = Vec::new();
+        let mut regimes = Vec::new();
+
+        for block_idx in 0..7 {
+            // Move the mix knob before block 2 (a 20ms ramp, done within
+            // the block) and the cutoff before block 4 (a 50ms ramp that
+            // runs into block 5).
+            let mut params = test_params();
+            if block_idx >= 2 {
+                params.mix = 0.9;
+            }
+            if block_idx >= 4 {
+                params.filter_cutoff = 1000.0;
+                params.filter_cutoff_right = 1000.0;
+            }
+            switching.set_params(&params);
+            reference.set_params(&params);
+
+            regimes
+                .push(switching.mix.is_smoothing() || switching.filter_cutoffs[0].is_smoothing());
+
+            let mut left = input_block(block_idx, 0);
+            let mut right = input_block(block_idx, 1);
+            switching.process(&mut [&mut left, &mut right]);
+            out_switching.push((left, right));
+
+            let mut left = input_block(block_idx, 0);
+            let mut right = input_block(block_idx, 1);
+            reference.process_smoothing(&mut [&mut left, &mut right], None);
+            out_reference.push((left, right));
+        }
+
+        assert_eq!(
+            regimes,
+            [false, false, true, false, true, true, false],
+            "test should exercise both paths"
+        );
+
+        for (block_idx, (a, b)) in out_switching.iter().zip(&out_reference).enumerate() {
+            for (x, y) in a.0.iter().zip(&b.0).chain(a.1.iter().zip(&b.1)) {
+                assert!(
+                    (x - y).abs() < 1e-6,
+                    "block {block_idx}: switching path {x} vs per-sample path {y}"
+                );
+            }
+        }
+
+        // No step at the seams: the jump across each block boundary is
+        // no bigger than the largest jump inside the blocks.
+        let left: Vec<f32> = out_switching.iter().flat_map(|b| b.0.clone()).collect();
+        let max_jump_within = left
+            .windows(2)
+            .enumerate()
+            .filter(|(i, _)| (i + 1) % BLOCK != 0)
+            .map(|(_, w)| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max);
+        for seam in (BLOCK..left.len()).step_by(BLOCK) {
+            let jump = (left[seam] - left[seam - 1]).abs();
+            assert!(
+                jump <= max_jump_within * 1.01,
+                "discontinuity at seam {seam}: {jump} > {max_jump_within}"
+            );
+        }
+    }
+
+    /// With the two sides set to different cutoffs, the left channel's
+    /// repeats should be much darker than the right's. The very first
+    /// echo hasn't been through the feedback filter yet, so we compare
+    /// the second — the first filtered repeat.
+    #[test]
+    fn test_per_side_filter_cutoffs() {
+        const DELAY_SAMPLES: usize = 4800; // 100ms at 48 kHz
+        const WINDOW: usize = 512;
+
+        /// Energy in the sample-to-sample differences: a crude but
+        /// monotonic measure of high-frequency content.
+        fn brightness(samples: &[f32]) -> f32 {
+            samples.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum()
+        }
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(&DelayParams {
+            filter_cutoff: 500.0,
+            filter_cutoff_right: 8000.0,
+            ..test_params()
+        });
+
+        let mut left = vec![0.0; 2 * DELAY_SAMPLES + WINDOW];
+        let mut right = vec![0.0; 2 * DELAY_SAMPLES + WINDOW];
+        left[0] = 1.0;
+        right[0] = 1.0;
+        engine.process(&mut [&mut left, &mut right]);
+
+        // The first echoes are identical on both sides...
+        let first = DELAY_SAMPLES..DELAY_SAMPLES + WINDOW;
+        assert_eq!(left[first.clone()], right[first]);
+
+        // ...but the second has been through each side's own filter.
+        let dark = brightness(&left[2 * DELAY_SAMPLES..]);
+        let bright = brightness(&right[2 * DELAY_SAMPLES..]);
+        assert!(
+            bright > dark * 4.0,
+            "right (8 kHz) should be brighter than left (500 Hz): {bright} vs {dark}"
+        );
+    }
+
+    /// The output low-cut and high-cut put their corners where they're
+    /// set. With no feedback and a fully wet mix, the first echo of an
+    /// impulse *is* the output filter's impulse response, so its spectrum
+    /// is the filter's frequency response.
+    #[test]
+    fn test_output_filter_corners() {
+        const DELAY_SAMPLES: usize = 4800; // 100ms at 48 kHz
+        const WINDOW: usize = 4096;
+
+        /// The first echo's level at `freq`, in dB, from a single DFT bin.
+        fn first_echo_gain_db(params: DelayParams, freq: f32) -> f32 {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                feedback: 0.0,
+                mix: 1.0,
+                ..params
+            });
+            let mut samples = vec![0.0; DELAY_SAMPLES + WINDOW];
+            samples[0] = 1.0;
+            engine.process(&mut [&mut samples]);
+
+            let step = 2.0 * std::f32::consts::PI * freq / SAMPLE_RATE;
+            let (re, im) =
+                samples[DELAY_SAMPLES..]
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (n, s)| {
+                        let phase = n as f32 * step;
+                        (re + s * phase.cos(), im - s * phase.sin())
+                    });
+            10.0 * (re * re + im * im).log10()
+        }
+
+        let lowcut = DelayParams {
+            out_lowcut: 500.0,
+            ..test_params()
+        };
+        let at = |freq| first_echo_gain_db(lowcut, freq);
+        assert!(at(5000.0) > -0.5, "low-cut passband: {} dB", at(5000.0));
+        assert!(
+            (-4.5..-2.0).contains(&at(500.0)),
+            "low-cut corner: {} dB",
+            at(500.0)
+        );
+        assert!(at(50.0) < -15.0, "low-cut stopband: {} dB", at(50.0));
+
+        let highcut = DelayParams {
+            out_highcut: 2000.0,
+            ..test_params()
+        };
+        let at = |freq| first_echo_gain_db(highcut, freq);
+        assert!(at(200.0) > -0.5, "high-cut passband: {} dB", at(200.0));
+        assert!(
+            (-4.5..-2.0).contains(&at(2000.0)),
+            "high-cut corner: {} dB",
+            at(2000.0)
+        );
+        assert!(at(16000.0) < -12.0, "high-cut stopband: {} dB", at(16000.0));
+    }
+
+    /// The output filter only colors what we hear: the signal going back
+    /// into the loop is the same with or without it.
+    #[test]
+    fn test_output_filter_leaves_feedback_alone() {
+        let mut plain = test_engine();
+        let mut filtered = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        filtered.set_params(&DelayParams {
+            out_lowcut: 300.0,
+            out_highcut: 3000.0,
+            ..test_params()
+        });
+
+        for block_idx in 0..6 {
+            let (mut l1, mut r1) = (input_block(block_idx, 0), input_block(block_idx, 1));
+            plain.process(&mut [&mut l1, &mut r1]);
+            let (mut l2, mut r2) = (input_block(block_idx, 0), input_block(block_idx, 1));
+            filtered.process(&mut [&mut l2, &mut r2]);
+        }
+
+        for (a, b) in plain.channel_states.iter().zip(&filtered.channel_states) {
+            for delay in [1, 1000, 4800, 9000] {
+                assert_eq!(a.delay_line.read_int(delay), b.delay_line.read_int(delay));
+            }
+        }
+    }
+
+    /// The pre-delay moves every echo later by the same amount: the
+    /// first lands at `pre_delay + delay`, the second at
+    /// `pre_delay + 2 * delay`.
+    #[test]
+    fn test_pre_delay_shifts_echoes() {
+        const DELAY_SAMPLES: usize = 4800; // 100ms at 48 kHz
+        const PRE_DELAY_SAMPLES: usize = 2400; // 50ms at 48 kHz
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            pre_delay_ms: 50.0,
+            mix: 1.0,
+            feedback: 0.5,
+            filter_cutoff: 20000.0,
+            ..test_params()
+        });
+        skip_engage_fade(&mut engine);
+
+        let mut samples = vec![0.0; PRE_DELAY_SAMPLES + 3 * DELAY_SAMPLES];
+        samples[0] = 1.0;
+        engine.process(&mut [&mut samples]);
+
+        let loudest = |range: std::ops::Range<usize>| {
+            let start = range.start;
+            samples[range]
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                .map(|(i, _)| start + i)
+                .unwrap()
+        };
+
+        assert!(samples[..PRE_DELAY_SAMPLES + DELAY_SAMPLES]
+            .iter()
+            .all(|s| *s == 0.0));
+        assert_eq!(
+            loudest(0..PRE_DELAY_SAMPLES + 2 * DELAY_SAMPLES - 1),
+            PRE_DELAY_SAMPLES + DELAY_SAMPLES
+        );
+        assert_eq!(
+            loudest(PRE_DELAY_SAMPLES + 2 * DELAY_SAMPLES - 1..samples.len()),
+            PRE_DELAY_SAMPLES + 2 * DELAY_SAMPLES
+        );
+    }
+
+    /// The tail estimate grows by exactly the pre-delay.
+    #[test]
+    fn test_tail_includes_pre_delay() {
+        let mut engine = test_engine();
+        let without = engine.tail_samples().unwrap();
+        engine.set_params(&DelayParams {
+            pre_delay_ms: 50.0,
+            ..test_params()
+        });
+        engine.reset();
+        assert_eq!(engine.tail_samples().unwrap(), without + 2400);
+    }
+
+    /// A tail capture copies only as far back as the delay and the
+    /// pre-delay read, not the whole line, and the seeded engine plays
+    /// on from it just as the live one does.
+    #[test]
+    fn test_capture_copies_only_the_live_span() {
+        let params = DelayParams {
+            pre_delay_ms: 50.0,
+            feedback: 0.5,
+            ..test_params()
+        };
+        let mut engine = test_engine();
+        engine.set_params(&params);
+        let mut left: Vec<f32> = (0..BLOCK).map(|i| (i as f32 * 0.05).sin()).collect();
+        let mut right = left.clone();
+        engine.process(&mut [&mut left, &mut right]);
+
+        let mut snapshot = engine.tail_snapshot();
+        engine.capture_tail(&mut snapshot, &params);
+        // 150ms of delay and pre-delay at 48 kHz, and the margin.
+        assert!(
+            (7200..7300).contains(&snapshot.span),
+            "copied {} samples",
+            snapshot.span
+        );
+        assert!(snapshot.span < engine.line_capacity() / 10);
+
+        let mut seeded = snapshot.seeded_engine();
+        let mut peak = 0.0_f32;
+        for _ in 0..4 {
+            let mut live = [vec![0.0; BLOCK], vec![0.0; BLOCK]];
+            let mut printed = live.clone();
+            let [a, b] = &mut live;
+            engine.process(&mut [a, b]);
+            let [a, b] = &mut printed;
+            seeded.process(&mut [a, b]);
+            for (live, printed) in live.iter().zip(&printed) {
+                for (l, p) in live.iter().zip(printed) {
+                    peak = peak.max(l.abs());
+                    assert!((l - p).abs() < 1e-3, "live {l}, printed {p}");
+                }
+            }
+        }
+        assert!(peak > 0.1, "nothing was ringing: {peak}");
+    }
+
+    /// While synced, the delay time comes from the tempo and division,
+    /// clamped to the delay range; without a tempo it falls back to the
+    /// knob's milliseconds.
+    #[test]
+    fn test_sync_follows_tempo() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        let synced = DelayParams {
+            sync: true,
+            division: NoteDivision::DottedEighth,
+            ..test_params()
+        };
+
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            pos_beats: None,
+            ..Transport::default()
+        });
+        engine.set_params(&synced);
+        assert_eq!(engine.delay_time.target(), 375.0);
+
+        engine.set_transport(Transport {
+            tempo: Some(30.0),
+            pos_beats: None,
+            ..Transport::default()
+        });
+        engine.set_params(&DelayParams {
+            division: NoteDivision::Whole,
+            ..synced
+        });
+        assert_eq!(engine.target_delay_ms(), MAX_DELAY_MS);
+
+        // No tempo from the host: Manual BPM stands in.
+        engine.set_transport(Transport::default());
+        engine.set_params(&DelayParams {
+            manual_bpm: 100.0,
+            ..synced
+        });
+        assert_eq!(engine.delay_time.target(), 450.0);
+    }
+
+    /// Switching sync on 1.3 beats into a bar holds the new time back
+    /// until beat 2, starts the crossfade on exactly that sample, and
+    /// from then on every echo of an on-beat click lands on a beat.
+    #[test]
+    fn test_quantized_sync_catches_on_beat() {
+        const BEAT: usize = 24000; // 120 BPM at 48 kHz
+        const BLOCK_LEN: usize = 512;
+        const TOTAL: usize = 5 * BEAT;
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        let mut params = DelayParams {
+            delay_ms: 300.0,
+            feedback: 0.0,
+            mix: 1.0,
+            ..DelayParams::default()
+        };
+
+        let mut output = Vec::new();
+        let mut pos = 0;
+        while pos < TOTAL {
+            let len = BLOCK_LEN.min(TOTAL - pos);
+            engine.set_transport(Transport {
+                tempo: Some(120.0),
+                pos_beats: Some(pos as f64 / BEAT as f64),
+                ..Transport::default()
+            });
+            if pos >= BEAT * 13 / 10 {
+                params.sync = true;
+            }
+            engine.set_params(&params);
+
+            // A click on every beat.
+            let mut block: Vec<f32> = (pos..pos + len)
+                .map(|n| if n % BEAT == 0 { 1.0 } else { 0.0 })
+                .collect();
+            engine.process(&mut [&mut block]);
+
+            if pos + len <= 2 * BEAT {
+                assert_eq!(engine.delay_time.target(), 300.0, "changed before the beat");
+            } else if pos < 2 * BEAT {
+                // The block containing beat 2: the time has jumped, and
+                // the 50ms (2400-sample) crossfade has run for exactly
+                // the samples after the beat.
+                assert_eq!(engine.delay_time.current(), 500.0);
+                let faded = (pos + len - 2 * BEAT) as f32;
+                let position = engine.time_fade.as_ref().unwrap().fade.position();
+                assert!(
+                    (position - faded / 2400.0).abs() < 1e-3,
+                    "fade should start on the beat: {position} vs {}",
+                    faded / 2400.0
+                );
+            }
+
+            output.extend(block);
+            pos += len;
+        }
+
+        // Before the catch, the 300ms echoes fall between the beats...
+        assert_eq!(output[BEAT + 14400], 1.0);
+
+        // ...after it, once the ramp has settled, only on them: the
+        // click on beat 3 echoes exactly on beat 4.
+        for (n, sample) in output.iter().enumerate().skip(5 * BEAT / 2) {
+            if n % BEAT != 0 {
+                assert!(sample.abs() < 1e-3, "off-grid echo at {n}: {sample}");
+            }
+        }
+        assert_eq!(output[4 * BEAT], 1.0);
+    }
+
+    /// Switching sync on halfway through a glide crossfades from
+    /// wherever the glide had got to, so the repeats stop bending at
+    /// once instead of bending on towards the synced time.
+    #[test]
+    fn test_sync_on_crossfades_mid_glide() {
+        const TONE_HZ: f32 = 700.0;
+        const BLOCK_LEN: usize = 512;
+        let fade_len = (TIME_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+
+        let mut engine = test_engine();
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            ..Transport::default()
+        });
+        let mut params = DelayParams {
+            delay_ms: 300.0,
+            feedback: 0.0,
+            mix: 1.0,
+            quantize_start: false,
+            ..test_params()
+        };
+        let mut n = 0;
+        let mut render = |engine: &mut LovelessDelayEngine, params: &DelayParams| {
+            engine.set_params(params);
+            let mut left: Vec<f32> = (n..n + BLOCK_LEN)
+                .map(|n| (std::f32::consts::TAU * TONE_HZ * n as f32 / SAMPLE_RATE).sin())
+                .collect();
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+            n += BLOCK_LEN;
+            left
+        };
+        for _ in 0..40 {
+            render(&mut engine, &params);
+        }
+
+        // A glide to 700ms, a block in...
+        params.delay_ms = 700.0;
+        render(&mut engine, &params);
+        let mid_glide = engine.delay_time.current();
+        assert!(mid_glide > 300.0 && mid_glide < 700.0);
+
+        // ...is cut short by sync, which fades to the quarter note.
+        params.sync = true;
+        let mut output = render(&mut engine, &params);
+        let time_fade = engine.time_fade.as_ref().unwrap();
+        assert_eq!(time_fade.from_ms, mid_glide);
+        assert_eq!(engine.delay_time.current(), 500.0);
+        assert!(!engine.delay_time.is_smoothing());
+
+        for _ in 0..fade_len / BLOCK_LEN {
+            output.extend(render(&mut engine, &params));
+        }
+        let crossings = output[..fade_len]
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count() as f32;
+        let expected = 2.0 * TONE_HZ * fade_len as f32 / SAMPLE_RATE;
+        assert!(
+            (crossings - expected).abs() <= 3.0,
+            "{crossings} crossings through the switch, expected {expected}"
+        );
+    }
+
+    /// Switching sync off with the synced time written back to the knob
+    /// (as the editor's handover does, to the knob's 0.1 ms step) leaves
+    /// a glide in progress alone. Without the write-back, the delay
+    /// crossfades to the knob's time.
+    #[test]
+    fn test_sync_off_keeps_the_time_written_back() {
+        use crate::sync_handover::handover_delay_ms;
+
+        let synced_engine = || {
+            let mut engine = test_engine();
+            engine.set_transport(Transport {
+                tempo: Some(120.0),
+                ..Transport::default()
+            });
+            let params = DelayParams {
+                delay_ms: 300.0,
+                sync: true,
+                quantize_start: false,
+                ..test_params()
+            };
+            engine.set_params(&params);
+            let mut block = [vec![0.0; BLOCK], vec![0.0; BLOCK]];
+            let [left, right] = &mut block;
+            for _ in 0..2 {
+                engine.process(&mut [left, right]);
+            }
+            assert!(engine.time_fade.is_none());
+
+            // The tempo drops, and the delay starts gliding to the
+            // longer quarter note.
+            engine.set_transport(Transport {
+                tempo: Some(90.0),
+                ..Transport::default()
+            });
+            engine.set_params(&params);
+            engine.process(&mut [&mut left[..256], &mut right[..256]]);
+            assert!(engine.delay_time.is_smoothing());
+            (engine, params)
+        };
+
+        let (mut engine, params) = synced_engine();
+        let written_back = (handover_delay_ms(NoteDivision::Quarter, 90.0) * 10.0).round() / 10.0;
+        assert_eq!(written_back, 666.7);
+        let current = engine.delay_time.current();
+        engine.set_params(&DelayParams {
+            delay_ms: written_back,
+            sync: false,
+            ..params
+        });
+        assert!(engine.time_fade.is_none());
+        assert!(engine.delay_time.is_smoothing());
+        assert_eq!(engine.delay_time.current(), current);
+        assert_eq!(engine.delay_time.target(), written_back);
+
+        let (mut engine, params) = synced_engine();
+        let current = engine.delay_time.current();
+        engine.set_params(&DelayParams {
+            sync: false,
+            ..params
+        });
+        assert_eq!(engine.time_fade.as_ref().unwrap().from_ms, current);
+        assert_eq!(engine.delay_time.current(), 300.0);
+    }
+
+    /// While synced, a tempo change retargets the delay at the next
+    /// block, and the delay glides to the new length.
+    #[test]
+    fn test_synced_delay_follows_tempo_change() {
+        const BLOCK_LEN: usize = 512;
+        let params = DelayParams {
+            sync: true,
+            division: NoteDivision::Quarter,
+            quantize_start: false,
+            ..test_params()
+        };
+        let run = |engine: &mut LovelessDelayEngine, host: &mut MockTransport, blocks| {
+            for _ in 0..blocks {
+                engine.set_transport(host.next_block(BLOCK_LEN));
+                engine.set_params(&params);
+                let mut left = vec![0.0; BLOCK_LEN];
+                let mut right = vec![0.0; BLOCK_LEN];
+                engine.process(&mut [&mut left, &mut right]);
+            }
+        };
+
+        let mut engine = test_engine();
+        let mut host = MockTransport::new(SAMPLE_RATE, 120.0);
+        host.play();
+        run(&mut engine, &mut host, 10);
+        assert_eq!(engine.delay_time.target(), 500.0);
+        assert!(!engine.delay_time.is_smoothing());
+
+        host.set_tempo(Some(150.0));
+        run(&mut engine, &mut host, 1);
+        assert_eq!(engine.delay_time.target(), 400.0);
+        assert!(engine.delay_time.is_smoothing());
+
+        run(&mut engine, &mut host, 10);
+        assert_eq!(engine.delay_time.current(), 400.0);
+    }
+
+    /// Sync counts at the host's tempo while it reports one, and at
+    /// Manual BPM while it doesn't. A tempo turning up mid-render takes
+    /// over with a crossfade from the manual time, and the engine says
+    /// which tempo is in charge throughout.
+    #[test]
+    fn test_manual_bpm_until_host_reports_tempo() {
+        const BLOCK_LEN: usize = 512;
+        let params = DelayParams {
+            sync: true,
+            manual_bpm: 100.0,
+            division: NoteDivision::Quarter,
+            quantize_start: false,
+            ..test_params()
+        };
+        let run = |engine: &mut LovelessDelayEngine, host: &mut MockTransport, blocks| {
+            for _ in 0..blocks {
+                engine.set_transport(host.next_block(BLOCK_LEN));
+                engine.set_params(&params);
+                let mut left = vec![0.0; BLOCK_LEN];
+                let mut right = vec![0.0; BLOCK_LEN];
+                engine.process(&mut [&mut left, &mut right]);
+            }
+        };
+
+        let mut engine = test_engine();
+        let mut host = MockTransport::new(SAMPLE_RATE, 120.0);
+        host.play();
+        run(&mut engine, &mut host, 4);
+        assert_eq!(engine.sync_tempo(), (120.0, TempoSource::Host));
+        assert_eq!(engine.delay_time.target(), 500.0);
+
+        // A fresh engine, so this is its first load: the manual time
+        // is set outright, not faded to from the test's 100 ms.
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        host.set_tempo(None);
+        run(&mut engine, &mut host, 4);
+        assert_eq!(engine.sync_tempo(), (100.0, TempoSource::Manual));
+        assert_eq!(engine.delay_time.current(), 600.0);
+        assert!(engine.time_fade.is_none());
+
+        host.set_tempo(Some(120.0));
+        run(&mut engine, &mut host, 1);
+        assert_eq!(engine.sync_tempo(), (120.0, TempoSource::Host));
+        assert_eq!(engine.delay_time.current(), 500.0);
+        let time_fade = engine.time_fade.as_ref().expect("glided instead");
+        assert_eq!(time_fade.from_ms, 600.0);
+    }
+
+    /// A quantized change waiting for a beat keeps counting through a
+    /// loop wrap: with the loop's end on a beat, the crossfade starts on
+    /// the sample the playhead jumps back to the loop's start.
+    #[test]
+    fn test_quantized_change_across_loop_wrap() {
+        const BEAT: usize = 24000; // 120 BPM at 48 kHz
+        const BLOCK_LEN: usize = 512;
+        // The playhead starts at beat 5.3 and wraps from 6 back to 4.
+        const WRAP: usize = 7 * BEAT / 10;
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        let mut host = MockTransport::new(SAMPLE_RATE, 120.0);
+        host.set_loop(Some((4.0, 6.0)));
+        host.locate(Some(5.3));
+        host.play();
+        let mut params = DelayParams {
+            delay_ms: 300.0,
+            division: NoteDivision::Quarter,
+            ..DelayParams::default()
+        };
+
+        for pos in (0..2 * WRAP).step_by(BLOCK_LEN) {
+            engine.set_transport(host.next_block(BLOCK_LEN));
+            engine.set_params(&params);
+            // Sync switches on in the second block, mid-beat.
+            params.sync = true;
+            engine.process(&mut [&mut vec![0.0; BLOCK_LEN]]);
+
+            if pos + BLOCK_LEN <= WRAP {
+                assert_eq!(
+                    engine.delay_time.current(),
+                    300.0,
+                    "changed before the wrap"
+                );
+            } else if pos < WRAP {
+                assert_eq!(engine.delay_time.current(), 500.0);
+                let faded = (pos + BLOCK_LEN - WRAP) as f32 / 2400.0;
+                let position = engine.time_fade.as_ref().unwrap().fade.position();
+                assert!(
+                    (position - faded).abs() < 1e-3,
+                    "fade should start on the wrap: {position} vs {faded}"
+                );
+            }
+        }
+        assert_eq!(engine.delay_time.current(), 500.0);
+    }
+
+    /// Without a beat position there's no grid to wait for, and with
+    /// quantizing off there's no reason to: the change is immediate.
+    #[test]
+    fn test_unquantized_sync_changes_immediately() {
+        let synced = DelayParams {
+            sync: true,
+            ..test_params()
+        };
+        let cases = [
+            (None, synced),
+            (
+                Some(1.3),
+                DelayParams {
+                    quantize_start: false,
+                    ..synced
+                },
+            ),
+        ];
+
+        for (pos_beats, params) in cases {
+            let mut engine = test_engine();
+            engine.set_transport(Transport {
+                tempo: Some(120.0),
+                pos_beats,
+                ..Transport::default()
+            });
+            engine.set_params(&params);
+            assert!(engine.pending_delay.is_none());
+            assert_eq!(engine.delay_time.target(), 500.0);
+        }
+    }
+
+    /// The time heard is `base × multiplier + fine`, clamped only once
+    /// it's complete: a fine offset still counts on a coarse time past
+    /// either end of the range.
+    #[test]
+    fn test_time_multiplier_and_fine_combine() {
+        use TimeMultiplier::*;
+        let cases = [
+            (500.0, Single, 0.0, 500.0),
+            (500.0, Dotted, -8.0, 742.0),
+            (1990.0, Single, 20.0, MAX_DELAY_MS),
+            (1010.0, Double, -20.0, MAX_DELAY_MS),
+            (100.0, Half, 20.0, MIN_DELAY_MS),
+            (240.0, Half, 20.0, 140.0),
+            // Out-of-range fine offsets are clamped too.
+            (500.0, Single, -300.0, 480.0),
+        ];
+        for (delay_ms, time_multiplier, time_fine_ms, want) in cases {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                delay_ms,
+                time_multiplier,
+                time_fine_ms,
+                ..DelayParams::default()
+            });
+            assert_eq!(
+                engine.target_delay_ms(),
+                want,
+                "{delay_ms} ms {time_multiplier:?} {time_fine_ms:+} ms"
+            );
+        }
+
+        // Synced, the multiplier scales the division: a dotted eighth.
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            pos_beats: None,
+            ..Transport::default()
+        });
+        engine.set_params(&DelayParams {
+            sync: true,
+            division: NoteDivision::Eighth,
+            time_multiplier: Dotted,
+            ..DelayParams::default()
+        });
+        assert_eq!(engine.target_delay_ms(), 375.0);
+
+        // And the echo arrives at the combined time: 200 × 1.5 + 5.
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            delay_ms: 200.0,
+            time_multiplier: Dotted,
+            time_fine_ms: 5.0,
+            feedback: 0.0,
+            mix: 1.0,
+            ..DelayParams::default()
+        });
+        let mut samples = vec![0.0; 16_000];
+        samples[0] = 1.0;
+        engine.process(&mut [&mut samples]);
+        let echo = calculate_delay_samples(305.0, SAMPLE_RATE) as usize;
+        assert_eq!(samples[echo], 1.0);
+    }
+
+    /// Automation aimed at 333 ms on the 10 ms grid plays exactly
+    /// 330 ms, while the fine offset stays off the grid. Synced, the
+    /// grid doesn't apply.
+    #[test]
+    fn test_time_snap_applies_before_smoothing() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let snapped = DelayParams {
+            delay_ms: 500.0,
+            time_snap: TimeSnap::Ms10,
+            ..DelayParams::default()
+        };
+        engine.set_params(&snapped);
+        engine.set_params(&DelayParams {
+            delay_ms: 333.0,
+            ..snapped
+        });
+        assert_eq!(engine.delay_time.target(), 330.0);
+        // The default Glide mode sweeps there; once it lands, it plays
+        // the snapped time.
+        for _ in 0..32 {
+            engine.process(&mut [&mut vec![0.0; BLOCK], &mut vec![0.0; BLOCK]]);
+        }
+        assert!(!engine.delay_time.is_smoothing());
+        assert_eq!(engine.current_values().delay_ms, 330.0);
+
+        engine.set_params(&DelayParams {
+            delay_ms: 333.0,
+            time_fine_ms: 1.3,
+            ..snapped
+        });
+        assert_eq!(engine.target_delay_ms(), 331.3);
+
+        engine.set_transport(Transport {
+            tempo: Some(100.0),
+            pos_beats: None,
+            ..Transport::default()
+        });
+        engine.set_params(&DelayParams {
+            sync: true,
+            division: NoteDivision::EighthTriplet,
+            ..snapped
+        });
+        assert_eq!(engine.target_delay_ms(), 200.0);
+    }
+
+    /// In crossfade mode, flipping the multiplier jumps the coarse time
+    /// and fades between two heads, so a tone running through the delay
+    /// keeps its pitch. Gliding the same change repitches it. The fine
+    /// offset glides in either mode.
+    #[test]
+    fn test_crossfade_time_change_keeps_pitch() {
+        const TONE_HZ: f32 = 700.0;
+        let fade_len = (TIME_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+
+        // Zero crossings in the first `fade_len` samples after the
+        // multiplier goes from ×1 to ×2.
+        let crossings_through_flip = |time_change| {
+            let mut engine = test_engine();
+            let mut params = DelayParams {
+                delay_ms: 200.0,
+                feedback: 0.0,
+                mix: 1.0,
+                time_change,
+                ..test_params()
+            };
+            let mut n = 0;
+            let mut render = |engine: &mut LovelessDelayEngine, params: &DelayParams| {
+                engine.set_params(params);
+                let mut left: Vec<f32> = (n..n + BLOCK)
+                    .map(|n| (std::f32::consts::TAU * TONE_HZ * n as f32 / SAMPLE_RATE).sin())
+                    .collect();
+                let mut right = left.clone();
+                engine.process(&mut [&mut left, &mut right]);
+                n += BLOCK;
+                left
+            };
+            for _ in 0..20 {
+                render(&mut engine, &params);
+            }
+
+            params.time_multiplier = TimeMultiplier::Double;
+            let mut output = render(&mut engine, &params);
+            output.extend(render(&mut engine, &params));
+            output[..fade_len]
+                .windows(2)
+                .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+                .count()
+        };
+
+        let expected = 2.0 * TONE_HZ * fade_len as f32 / SAMPLE_RATE;
+        let crossfaded = crossings_through_flip(TimeChange::Crossfade) as f32;
+        assert!(
+            (crossfaded - expected).abs() <= 3.0,
+            "crossfade: {crossfaded} crossings, expected {expected}"
+        );
+        let glided = crossings_through_flip(TimeChange::Glide) as f32;
+        assert!(
+            glided > 1.5 * expected,
+            "glide: {glided} crossings, expected a repitch"
+        );
+
+        // The flip itself: the coarse time jumps and a fade starts.
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            time_change: TimeChange::Crossfade,
+            ..test_params()
+        };
+        params.time_multiplier = TimeMultiplier::Double;
+        engine.set_params(&params);
+        assert!(engine.time_fade.is_some());
+        assert!(!engine.delay_time.is_smoothing());
+        assert_eq!(engine.delay_time.current(), 200.0);
+
+        // A change mid-fade waits for it, then fades in turn.
+        let mut block = [vec![0.0; BLOCK], vec![0.0; BLOCK]];
+        let [left, right] = &mut block;
+        engine.process(&mut [left, right]);
+        params.time_multiplier = TimeMultiplier::Single;
+        engine.set_params(&params);
+        assert_eq!(engine.queued_delay, Some(100.0));
+        assert_eq!(engine.delay_time.target(), 200.0);
+        engine.process(&mut [left, right]);
+        assert_eq!(engine.queued_delay, None);
+        assert!(engine.time_fade.is_some());
+        assert_eq!(engine.delay_time.target(), 100.0);
+
+        // The fine offset glides, with no fade.
+        let mut engine = test_engine();
+        engine.set_params(&DelayParams {
+            time_fine_ms: 10.0,
+            ..params
+        });
+        assert!(engine.time_fade.is_none());
+        assert!(engine.time_fine.is_smoothing());
+    }
+
+    /// Double and Half Time crossfade to twice and half the knob's time,
+    /// in Glide mode too, and back on release; held together, the one
+    /// pressed last wins. Automation of the knob meanwhile glides on
+    /// underneath, and release lands where it got to — or if it comes
+    /// mid-fade, waits, with the knob's glide moving it along. Synced,
+    /// the division moves a step, without waiting for the beat.
+    #[test]
+    fn test_double_and_half_time_are_momentary() {
+        let mut block = [vec![0.0; BLOCK], vec![0.0; BLOCK]];
+        let mut play = |engine: &mut LovelessDelayEngine, params: &DelayParams, blocks| {
+            engine.set_params(params);
+            for _ in 0..blocks {
+                let [left, right] = &mut block;
+                engine.process(&mut [left, right]);
+            }
+        };
+        let crossfaded_to = |engine: &LovelessDelayEngine, delay_ms: f32| {
+            engine.time_fade.is_some()
+                && !engine.delay_time.is_smoothing()
+                && engine.delay_time.current() == delay_ms
+        };
+
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            double_time: true,
+            ..test_params()
+        };
+        play(&mut engine, &params, 0);
+        assert!(crossfaded_to(&engine, 200.0));
+        play(&mut engine, &params, 2);
+
+        // The knob moves mid-gesture: a glide, under the doubling.
+        params.delay_ms = 300.0;
+        play(&mut engine, &params, 0);
+        assert!(engine.time_fade.is_none());
+        assert_eq!(engine.delay_time.target(), 600.0);
+        play(&mut engine, &params, 10);
+
+        params.half_time = true;
+        play(&mut engine, &params, 0);
+        assert!(crossfaded_to(&engine, 150.0));
+        play(&mut engine, &params, 2);
+
+        params.half_time = false;
+        play(&mut engine, &params, 0);
+        assert!(crossfaded_to(&engine, 600.0));
+        play(&mut engine, &params, 2);
+
+        params.double_time = false;
+        play(&mut engine, &params, 0);
+        assert!(crossfaded_to(&engine, 300.0));
+
+        // Released before the press's fade is done: the release waits.
+        // The knob moving on meanwhile moves the waiting change, rather
+        // than gliding the whole way from the doubled time.
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            double_time: true,
+            ..test_params()
+        };
+        play(&mut engine, &params, 0);
+        params.double_time = false;
+        play(&mut engine, &params, 0);
+        assert_eq!(engine.queued_delay, Some(100.0));
+        params.delay_ms = 110.0;
+        play(&mut engine, &params, 0);
+        assert_eq!(engine.queued_delay, Some(110.0));
+        assert!(!engine.delay_time.is_smoothing());
+        play(&mut engine, &params, 2);
+        assert!(crossfaded_to(&engine, 110.0));
+
+        // Synced, mid-beat, with Quantize Start on.
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            pos_beats: Some(0.5),
+            playing: false,
+        });
+        let mut params = DelayParams {
+            sync: true,
+            quantize_start: true,
+            ..test_params()
+        };
+        play(&mut engine, &params, 0);
+        assert_eq!(engine.delay_time.target(), 500.0);
+        params.double_time = true;
+        play(&mut engine, &params, 0);
+        assert!(engine.pending_delay.is_none());
+        assert!(crossfaded_to(&engine, 750.0));
+        play(&mut engine, &params, 2);
+
+        params.double_time = false;
+        params.half_time = true;
+        play(&mut engine, &params, 0);
+        assert!(crossfaded_to(&engine, 1000.0 / 3.0));
+    }
+
+    /// In Tape mode, Scrub moves the heard delay — a tape stop stretches
+    /// it — and back at 0 the head runs home and locks, with nothing
+    /// left moving. Switching to Glide mid-scrub crossfades from where
+    /// the head had got to back to the delay time, rather than sweeping
+    /// the repeats home.
+    #[test]
+    fn test_tape_mode_scrubs_and_relocks() {
+        let play = |engine: &mut LovelessDelayEngine, params: &DelayParams, blocks| {
+            engine.set_params(params);
+            for block_idx in 0..blocks {
+                let mut left = input_block(block_idx, 0);
+                let mut right = input_block(block_idx, 1);
+                engine.process(&mut [&mut left, &mut right]);
+            }
+        };
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let mut params = DelayParams {
+            delay_ms: 500.0,
+            time_change: TimeChange::Tape,
+            ..test_params()
+        };
+        play(&mut engine, &params, 2);
+        assert!(!engine.tape_moving());
+        assert_eq!(engine.current_values().delay_ms, 500.0);
+
+        // A quarter of a second with the reel held still.
+        params.scrub = 1.0;
+        play(&mut engine, &params, 6);
+        let stretched = engine.current_values().delay_ms;
+        assert!(stretched > 600.0, "{stretched}");
+
+        params.scrub = 0.0;
+        let mut blocks = 0;
+        while engine.tape_moving() {
+            play(&mut engine, &params, 1);
+            blocks += 1;
+            assert!(blocks < 2 * SAMPLE_RATE as usize / BLOCK, "never re-locked");
+        }
+        assert_eq!(engine.current_values().delay_ms, 500.0);
+
+        params.scrub = 1.0;
+        play(&mut engine, &params, 4);
+        let heard = engine.current_values().delay_ms;
+        assert!(heard > 550.0, "{heard}");
+        params.time_change = TimeChange::Glide;
+        engine.set_params(&params);
+        assert!(engine.tape_head.is_none());
+        assert_eq!(engine.time_fade.as_ref().unwrap().from_ms, heard);
+        assert_eq!(engine.delay_time.current(), 500.0);
+        assert_eq!(engine.delay_time.target(), 500.0);
+    }
+
+    /// The time modulation sways the heard delay both ways, to within
+    /// its depth of the knob and no further, in every waveform it's
+    /// offered in.
+    #[test]
+    fn test_time_modulation_sways_within_depth() {
+        for mod_waveform in [
+            LfoWaveform::Sine,
+            LfoWaveform::Triangle,
+            LfoWaveform::SampleAndHold,
+        ] {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                delay_ms: 500.0,
+                mod_waveform,
+                mod_rate_hz: 4.0,
+                mod_depth_ms: 5.0,
+                ..test_params()
+            });
+            let (mut lowest, mut highest) = (f32::MAX, f32::MIN);
+            for block_idx in 0..SAMPLE_RATE as usize / 64 {
+                let mut left = input_block(block_idx, 0)[..64].to_vec();
+                let mut right = input_block(block_idx, 1)[..64].to_vec();
+                engine.process(&mut [&mut left, &mut right]);
+                let heard = engine.current_values().delay_ms;
+                lowest = lowest.min(heard);
+                highest = highest.max(heard);
+            }
+            assert!(lowest >= 495.0 - 1e-3, "{mod_waveform:?}: {lowest}");
+            assert!(highest <= 505.0 + 1e-3, "{mod_waveform:?}: {highest}");
+            assert!(
+                highest - lowest > 5.0,
+                "{mod_waveform:?}: {lowest}..{highest}"
+            );
+        }
+    }
+
+    /// At zero depth the LFO's settings change nothing: the output is
+    /// bit for bit what it is with the modulation left alone, and once
+    /// a depth glides back out the time is exactly the knob's again.
+    #[test]
+    fn test_zero_depth_modulation_is_exact() {
+        let render = |params: &DelayParams| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(params);
+            let mut output = Vec::new();
+            for block_idx in 0..8 {
+                let mut left = input_block(block_idx, 0);
+                let mut right = input_block(block_idx, 1);
+                engine.process(&mut [&mut left, &mut right]);
+                output.extend(left.into_iter().chain(right));
+            }
+            output
+        };
+        let still = render(&test_params());
+        let idle = render(&DelayParams {
+            mod_waveform: LfoWaveform::SampleAndHold,
+            mod_rate_hz: 7.0,
+            mod_glide: 0.2,
+            ..test_params()
+        });
+        assert_eq!(still, idle);
+
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            mod_depth_ms: 3.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        params.mod_depth_ms = 0.0;
+        engine.set_params(&params);
+        for block_idx in 0..4 {
+            let mut left = input_block(block_idx, 0);
+            let mut right = input_block(block_idx, 1);
+            engine.process(&mut [&mut left, &mut right]);
+        }
+        assert!(!engine.modulating());
+        assert_eq!(engine.current_values().delay_ms, 100.0);
+    }
+
+    /// Changing waveform fades the read head over to the new shape
+    /// rather than jumping it there, so the heard delay moves no faster
+    /// than either shape moves it alone.
+    #[test]
+    fn test_time_modulation_waveform_change_glides() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let mut params = DelayParams {
+            delay_ms: 500.0,
+            mod_rate_hz: 2.0,
+            mod_depth_ms: 5.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        let play = |engine: &mut LovelessDelayEngine, samples: usize| {
+            let mut last = engine.current_values().delay_ms;
+            let mut fastest: f32 = 0.0;
+            for i in 0..samples {
+                let mut left = [(i as f32 * 0.031).sin() * 0.5];
+                let mut right = [(i as f32 * 0.031 + 1.0).sin() * 0.5];
+                engine.process(&mut [&mut left, &mut right]);
+                let heard = engine.current_values().delay_ms;
+                fastest = fastest.max((heard - last).abs());
+                last = heard;
+            }
+            fastest
+        };
+        // An eighth of a cycle in, where the sine is well ahead of the
+        // triangle.
+        play(&mut engine, SAMPLE_RATE as usize / 16);
+        params.mod_waveform = LfoWaveform::Triangle;
+        engine.set_params(&params);
+        let fastest = play(&mut engine, SAMPLE_RATE as usize / 2);
+
+        // The triangle's slope: a full swing each way per cycle.
+        let slope = 4.0 * 5.0 * 2.0 / SAMPLE_RATE;
+        assert!(fastest < 2.0 * slope, "{fastest} ms per sample");
+    }
+
+    /// Render the wet impulse response of a stereo engine whose sides
+    /// are filtered very differently, and return a function giving the
+    /// left and right responses at any frequency.
+    fn stereo_response(mono_safe: bool) -> impl Fn(f64) -> (Complex, Complex) {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(&DelayParams {
+            feedback: 0.7,
+            mix: 1.0,
+            filter_cutoff: 200.0,
+            filter_cutoff_right: 20000.0,
+            mono_safe,
+            ..test_params()
+        });
+        skip_engage_fade(&mut engine);
+
+        // Two seconds is twenty repeats: 0.7^20 is below -60 dB.
+        let mut left = vec![0.0; 2 * SAMPLE_RATE as usize];
+        let mut right = vec![0.0; 2 * SAMPLE_RATE as usize];
+        left[0] = 1.0;
+        right[0] = 1.0;
+        engine.process(&mut [&mut left, &mut right]);
+
+        move |freq| (dft(&left, freq), dft(&right, freq))
+    }
+
+    type Complex = (f64, f64);
+
+    /// A single DFT bin at `freq` Hz.
+    fn dft(samples: &[f32], freq: f64) -> Complex {
+        let step = -2.0 * std::f64::consts::PI * freq / f64::from(SAMPLE_RATE);
+        let (step_re, step_im) = (step.cos(), step.sin());
+        let (mut re, mut im) = (0.0, 0.0);
+        let (mut rot_re, mut rot_im) = (1.0, 0.0);
+        for sample in samples {
+            re += f64::from(*sample) * rot_re;
+            im += f64::from(*sample) * rot_im;
+            (rot_re, rot_im) = (
+                rot_re * step_re - rot_im * step_im,
+                rot_re * step_im + rot_im * step_re,
+            );
+        }
+        (re, im)
+    }
+
+    fn magnitude((re, im): Complex) -> f64 {
+        re.hypot(im)
+    }
+
+    /// Mono-safe mode makes both sides' low end the same: below the
+    /// crossover, the difference between left and right (what folding
+    /// to mono would cancel) shrinks to a small fraction.
+    #[test]
+    fn test_mono_safe_links_low_band() {
+        let side_ratio = |mono_safe| {
+            let response = stereo_response(mono_safe);
+            (20..=100)
+                .map(|freq| {
+                    let (l, r) = response(f64::from(freq));
+                    magnitude((l.0 - r.0, l.1 - r.1)) / (magnitude(l) + magnitude(r))
+                })
+                .fold(0.0, f64::max)
+        };
+
+        let unlinked = side_ratio(false);
+        let linked = side_ratio(true);
+        assert!(unlinked > 0.3, "sides should differ without it: {unlinked}");
+        assert!(linked < 0.15, "sides should match below 100 Hz: {linked}");
+    }
+
+    /// With mono-safe on, folding to mono never loses more than 6 dB
+    /// below 500 Hz compared to the two sides played separately.
+    #[test]
+    fn test_mono_safe_fold_has_no_deep_notches() {
+        let response = stereo_response(true);
+        for freq in (20..500).step_by(2) {
+            let (l, r) = response(f64::from(freq));
+            let folded = magnitude((l.0 + r.0, l.1 + r.1));
+            let notch_db = 20.0 * (folded / (magnitude(l) + magnitude(r))).log10();
+            assert!(notch_db > -6.0, "{notch_db:.1} dB notch at {freq} Hz");
+        }
+    }
+
+    /// With today's stages (which never amplify) the ceiling stays out
+    /// of the way: the reported loop gain is the Feedback setting, on
+    /// the static path, while ramping, and with the channels linked.
+    #[test]
+    fn test_loop_gain_follows_feedback() {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        assert_eq!(engine.loop_gain(), 0.0);
+
+        let process = |engine: &mut LovelessDelayEngine| {
+            let (mut left, mut right) = (input_block(0, 0), input_block(0, 1));
+            engine.process(&mut [&mut left, &mut right]);
+        };
+
+        engine.set_params(&DelayParams {
+            feedback: MAX_FEEDBACK,
+            ..test_params()
+        });
+        process(&mut engine);
+        assert_eq!(engine.loop_gain(), MAX_FEEDBACK);
+
+        engine.set_params(&DelayParams {
+            feedback: 0.6,
+            mono_safe: true,
+            ..test_params()
+        });
+        for _ in 0..4 {
+            process(&mut engine);
+        }
+        assert_eq!(engine.loop_gain(), 0.6);
+    }
+
+    /// An impulse through both feedback sources. From the wet tap, each
+    /// echo is the feedback times the last. Post-mix, the first echo
+    /// carries the dry impulse's own trip round the loop as well, and
+    /// each after it is the feedback times the mix times the last.
+    /// Compared by each echo's area, which the linear filter keeps.
+    #[test]
+    fn test_post_mix_feedback_impulse_response() {
+        const FEEDBACK: f32 = 0.5;
+        const MIX: f32 = 0.5;
+        let period = (0.1 * SAMPLE_RATE) as usize;
+        let echo_areas = |feedback_source: FeedbackSource| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                feedback: FEEDBACK,
+                mix: MIX,
+                feedback_source,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            let mut block = vec![0.0; 5 * period];
+            block[0] = 1.0;
+            engine.process(&mut [&mut block]);
+            (1..5)
+                .map(|k| block[k * period..(k + 1) * period].iter().sum::<f32>())
+                .collect::<Vec<f32>>()
+        };
+
+        let wet_tap = echo_areas(FeedbackSource::WetTap);
+        let post_mix = echo_areas(FeedbackSource::PostMix);
+        for k in 0..4 {
+            let want = MIX * FEEDBACK.powi(k as i32);
+            assert!((wet_tap[k] - want).abs() < 1e-4, "{wet_tap:?}");
+            let want = MIX * (1.0 + FEEDBACK * (1.0 - MIX)) * (FEEDBACK * MIX).powi(k as i32);
+            assert!((post_mix[k] - want).abs() < 1e-4, "{post_mix:?}");
+        }
+    }
+
+    /// Post-mix, the loop gain is the wet tap's times the mix, and the
+    /// ceiling holds it: at full feedback, full drive and a boosting
+    /// compensation, any mix dies away after a burst.
+    #[test]
+    fn test_post_mix_feedback_stays_stable() {
+        const BLOCK_LEN: usize = 512;
+        const BLOCKS: usize = 6 * SAMPLE_RATE as usize / BLOCK_LEN;
+        const SECOND: usize = SAMPLE_RATE as usize / BLOCK_LEN;
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            feedback: 0.6,
+            mix: 0.5,
+            feedback_source: FeedbackSource::PostMix,
+            ..test_params()
+        });
+        engine.process(&mut [&mut input_block(0, 0)]);
+        assert!((engine.loop_gain() - 0.3).abs() < 1e-6);
+
+        for mix in [0.0, 0.3, 0.7, 1.0] {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                feedback: MAX_FEEDBACK,
+                mix,
+                drive: 1.0,
+                filter_cutoff: 200.0,
+                feedback_compensation: true,
+                feedback_source: FeedbackSource::PostMix,
+                ..test_params()
+            });
+            let mut block_peaks = Vec::with_capacity(BLOCKS);
+            for block in 0..BLOCKS {
+                let mut samples = match block {
+                    0..=3 => input_block(block, 0)[..BLOCK_LEN].to_vec(),
+                    _ => vec![0.0; BLOCK_LEN],
+                };
+                engine.process(&mut [&mut samples]);
+                assert!(engine.loop_gain() < LOOP_GAIN_CEILING, "mix {mix}");
+                let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+                assert!(peak.is_finite(), "mix {mix}: not finite in block {block}");
+                block_peaks.push(peak);
+            }
+            let peak = |blocks: &[f32]| blocks.iter().fold(0.0f32, |m, p| m.max(*p));
+            let loudest = peak(&block_peaks);
+            let last_second = peak(&block_peaks[BLOCKS - SECOND..]);
+            assert!(loudest < 2.0, "mix {mix}: built up to {loudest}");
+            assert!(
+                last_second < 0.5 * loudest,
+                "mix {mix}: {last_second} of {loudest}"
+            );
+        }
+    }
+
+    /// A +12 dB shelf in the loop at maximum feedback would put the
+    /// boosted band round at nearly four times unity. The ceiling eases
+    /// the feedback back instead, so a noise burst through either shelf
+    /// or both rings on near the ceiling and then dies away.
+    #[test]
+    fn test_shelf_boosts_stay_stable_at_max_feedback() {
+        const BLOCK_LEN: usize = 512;
+        const BLOCKS: usize = 8 * SAMPLE_RATE as usize / BLOCK_LEN;
+        const SECOND: usize = SAMPLE_RATE as usize / BLOCK_LEN;
+
+        for (low_db, high_db) in [(12.0, 0.0), (0.0, 12.0), (12.0, 12.0)] {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                feedback: MAX_FEEDBACK,
+                filter_cutoff: 20000.0,
+                fb_low_shelf_db: low_db,
+                fb_high_shelf_db: high_db,
+                ..test_params()
+            });
+            let mut noise = crate::dsp::noise::NoiseGenerator::new(7);
+            let mut block_peaks = Vec::with_capacity(BLOCKS);
+            for block in 0..BLOCKS {
+                let mut samples: Vec<f32> = (0..BLOCK_LEN)
+                    .map(|_| if block < 4 { 0.5 * noise.next() } else { 0.0 })
+                    .collect();
+                engine.process(&mut [&mut samples]);
+                let loop_gain = engine.loop_gain();
+                assert!(
+                    loop_gain <= LOOP_GAIN_CEILING && loop_gain > 0.95,
+                    "{low_db}/{high_db} dB: loop gain {loop_gain}"
+                );
+                let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+                assert!(peak.is_finite(), "{low_db}/{high_db} dB: block {block}");
+                block_peaks.push(peak);
+            }
+            let peak = |blocks: &[f32]| blocks.iter().fold(0.0f32, |m, p| m.max(*p));
+            let loudest = peak(&block_peaks);
+            let last_second = peak(&block_peaks[BLOCKS - SECOND..]);
+            assert!(
+                loudest < 4.0,
+                "{low_db}/{high_db} dB: built up to {loudest}"
+            );
+            assert!(
+                last_second < 0.5 * loudest,
+                "{low_db}/{high_db} dB: {last_second} of {loudest}"
+            );
+        }
+    }
+
+    /// The character macro works as offsets on top of the knobs: full
+    /// character on an 8 kHz cutoff with no drive sounds exactly like
+    /// no character on a 1 kHz cutoff (three octaves down) with 0.6
+    /// drive — and the knobs' own values are left where they were.
+    #[test]
+    fn test_character_offsets_the_knobs() {
+        let mut worn = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        worn.set_params(&DelayParams {
+            character: 1.0,
+            filter_cutoff: 8000.0,
+            filter_cutoff_right: 8000.0,
+            ..test_params()
+        });
+        let mut by_hand = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        by_hand.set_params(&DelayParams {
+            filter_cutoff: 1000.0,
+            filter_cutoff_right: 1000.0,
+            drive: 0.6,
+            ..test_params()
+        });
+
+        for block_idx in 0..4 {
+            let (mut l1, mut r1) = (input_block(block_idx, 0), input_block(block_idx, 1));
+            worn.process(&mut [&mut l1, &mut r1]);
+            let (mut l2, mut r2) = (input_block(block_idx, 0), input_block(block_idx, 1));
+            by_hand.process(&mut [&mut l2, &mut r2]);
+            assert_eq!(l1, l2);
+            assert_eq!(r1, r2);
+        }
+
+        assert_eq!(worn.filter_cutoffs[0].target(), 8000.0);
+        assert_eq!(worn.drive.target(), 0.0);
+    }
+
+    /// The magnitude-weighted mean frequency of `samples`, in Hz: where
+    /// the "centre of mass" of the spectrum sits.
+    fn spectral_centroid(samples: &[f32]) -> f64 {
+        let bin_hz = f64::from(SAMPLE_RATE) / samples.len() as f64;
+        let (weighted, total) = (1..samples.len() / 2)
+            .step_by(4)
+            .map(|bin| {
+                let freq = bin as f64 * bin_hz;
+                (freq, magnitude(dft(samples, freq)))
+            })
+            .fold((0.0, 0.0), |(w, t), (freq, m)| (w + freq * m, t + m));
+        weighted / total
+    }
+
+    /// Damping darkens the quiet late repeats faster than the loud
+    /// early ones: from the 2nd repeat to the 6th, the spectral centroid
+    /// falls further with damping up than with the fixed filter alone.
+    #[test]
+    fn test_damping_darkens_late_repeats() {
+        const DELAY: usize = 4800;
+        const BURST: usize = 1024;
+
+        let centroids = |damping| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                delay_ms: 100.0,
+                feedback: 0.7,
+                mix: 1.0,
+                damping,
+                ..DelayParams::default()
+            });
+            let mut noise = crate::dsp::noise::NoiseGenerator::new(3);
+            let mut samples = vec![0.0; DELAY * 7];
+            for sample in &mut samples[..BURST] {
+                *sample = 0.5 * noise.next();
+            }
+            engine.process(&mut [&mut samples]);
+
+            let repeat = |k: usize| spectral_centroid(&samples[k * DELAY..k * DELAY + BURST]);
+            (repeat(2), repeat(6))
+        };
+
+        let (fixed_2nd, fixed_6th) = centroids(0.0);
+        let (damped_2nd, damped_6th) = centroids(1.0);
+        let fixed_fall = fixed_6th / fixed_2nd;
+        let damped_fall = damped_6th / damped_2nd;
+        assert!(damped_2nd < fixed_2nd, "{damped_2nd} vs {fixed_2nd}");
+        assert!(
+            damped_fall < fixed_fall * 0.5,
+            "centroid 2nd → 6th: ×{damped_fall:.3} damped, ×{fixed_fall:.3} fixed"
+        );
+    }
+
+    /// With feedback compensation on, a pink noise burst takes as long
+    /// to fall 60 dB at any cutoff, within ±25% of an open filter's
+    /// time. Without it, the darkest filter cuts the tail by more.
+    #[test]
+    fn test_compensation_keeps_decay_time() {
+        const DELAY: usize = 4800;
+        const BURST: usize = 2400;
+        const REPEATS: usize = 90;
+
+        // How many repeats until one is 60 dB below the burst.
+        let decay_repeats = |cutoff: f32, feedback: f32, compensation: bool| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                delay_ms: 100.0,
+                feedback,
+                mix: 1.0,
+                filter_cutoff: cutoff,
+                filter_cutoff_right: cutoff,
+                feedback_compensation: compensation,
+                ..DelayParams::default()
+            });
+            skip_engage_fade(&mut engine);
+            let mut noise = crate::dsp::noise::NoiseGenerator::new(3);
+            noise.set_color(crate::dsp::noise::NoiseColor::Pink);
+            let mut samples = vec![0.0; DELAY * REPEATS];
+            for sample in &mut samples[..BURST] {
+                *sample = 0.5 * noise.next();
+            }
+            let rms = |window: &[f32]| {
+                (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt()
+            };
+            let burst = rms(&samples[..BURST]);
+            for block in samples.chunks_mut(BLOCK) {
+                engine.process(&mut [block]);
+            }
+            (1..REPEATS)
+                .find(|k| rms(&samples[k * DELAY..k * DELAY + BURST]) < burst * 1e-3)
+                .unwrap_or(REPEATS) as f32
+        };
+
+        for feedback in [0.7, 0.9] {
+            let open = decay_repeats(20000.0, feedback, true);
+            for cutoff in [200.0, 500.0, 1000.0, 2000.0, 5000.0] {
+                let repeats = decay_repeats(cutoff, feedback, true);
+                assert!(
+                    (repeats / open - 1.0).abs() <= 0.25,
+                    "feedback {feedback}, {cutoff} Hz: {repeats} repeats vs {open} open"
+                );
+            }
+        }
+        let open = decay_repeats(20000.0, 0.9, false);
+        let dark = decay_repeats(200.0, 0.9, false);
+        assert!(dark / open < 0.75, "{dark} vs {open} without compensation");
+    }
+
+    /// With the limiter switched off (the default), the output must null
+    /// against an engine that has no limiter stage at all — even for a
+    /// signal hot enough that limiting would have changed it.
+    #[test]
+    fn test_bypassed_limiter_nulls() {
+        let mut bypassed = test_engine();
+        let mut without = test_engine();
+        without.limiters.clear();
+        assert!(!bypassed.limiter.is_on());
+
+        let mut peak = 0.0_f32;
+        for block_idx in 0..4 {
+            // +6 dB over full scale.
+            let hot =
+                |ch| -> Vec<f32> { input_block(block_idx, ch).iter().map(|s| s * 4.0).collect() };
+
+            let (mut l1, mut r1) = (hot(0), hot(1));
+            bypassed.process(&mut [&mut l1, &mut r1]);
+            let (mut l2, mut r2) = (hot(0), hot(1));
+            without.process(&mut [&mut l2, &mut r2]);
+
+            for (a, b) in l1.iter().chain(&r1).zip(l2.iter().chain(&r2)) {
+                assert_eq!(a.to_bits(), b.to_bits());
+                peak = peak.max(a.abs());
+            }
+        }
+        assert!(peak > 1.0, "signal should be hot enough to limit: {peak}");
+    }
+
+    /// With the limiter on, the same hot signal stays under full scale.
+    #[test]
+    fn test_engaged_limiter_holds_ceiling() {
+        let mut engine = test_engine();
+        engine.set_params(&DelayParams {
+            limiter: true,
+            ..test_params()
+        });
+
+        for block_idx in 0..4 {
+            let mut left: Vec<f32> = input_block(block_idx, 0).iter().map(|s| s * 4.0).collect();
+            engine.process(&mut [&mut left]);
+            assert!(left.iter().all(|s| s.abs() <= 1.0));
+        }
+    }
+
+    /// An impulse at 50% mix comes out as one dry spike and then the
+    /// echoes — never a doubled transient — and with wet-path latency
+    /// the dry spike moves by exactly the latency that's reported.
+    #[test]
+    fn test_dry_stays_aligned() {
+        let impulse_response = |wet_latency: usize| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_wet_latency(wet_latency);
+            engine.set_params(&DelayParams {
+                feedback: 0.5,
+                mix: 0.5,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            let mut block = vec![0.0; 4 * 4800];
+            block[0] = 1.0;
+            engine.process(&mut [&mut block]);
+            (engine.latency_samples(), block)
+        };
+
+        let (latency, response) = impulse_response(0);
+        assert_eq!(latency, 0);
+        // The dry spike alone, then nothing until the first echo 100ms
+        // (4800 samples) later.
+        assert_eq!(response[0], 0.5);
+        assert!(response[1..4800].iter().all(|&s| s == 0.0));
+        assert!(response[4800] > 0.1);
+
+        let (latency, delayed) = impulse_response(64);
+        assert_eq!(latency, 64);
+        assert_eq!(delayed[0], 0.0);
+        assert_eq!(delayed[64], 0.5);
+        assert_eq!(delayed[4800], response[4800]);
+    }
+
+    /// Where the loudest sample of each of an impulse's first four
+    /// repeats comes out, with 100ms (4800 samples) on the knob and a
+    /// stand-in stage of `feedback_latency` samples in the loop.
+    fn repeat_times(feedback_latency: usize) -> Vec<usize> {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_feedback_latency(feedback_latency);
+        engine.set_params(&DelayParams {
+            feedback: 0.7,
+            mix: 1.0,
+            ..test_params()
+        });
+        skip_engage_fade(&mut engine);
+        let mut block = vec![0.0; 5 * 4800];
+        block[0] = 1.0;
+        engine.process(&mut [&mut block]);
+        (1..5)
+            .map(|repeat| {
+                let start = repeat * 4800 - 2400;
+                let loudest = block[start..start + 4800]
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                    .map(|(n, _)| n);
+                start + loudest.unwrap()
+            })
+            .collect()
+    }
+
+    /// With a 64-sample stage in the feedback chain, each repeat still
+    /// comes the delay time after the one before, to within a sample:
+    /// the loop tap reads 64 samples earlier to make up for the stage.
+    #[test]
+    fn test_feedback_latency_keeps_repeats_on_time() {
+        let on_time = repeat_times(0);
+        let compensated = repeat_times(64);
+        assert!(on_time[0].abs_diff(4800) <= 1, "{on_time:?}");
+        for (k, pair) in compensated.windows(2).enumerate() {
+            let spacing = pair[1] - pair[0];
+            let expected = on_time[k + 1] - on_time[k];
+            assert!(
+                spacing.abs_diff(expected) <= 1 && spacing.abs_diff(4800) <= 1,
+                "repeats {} and {}: {spacing} apart",
+                k + 1,
+                k + 2
+            );
+        }
+    }
+
+    /// A stage longer than the delay can't be made up for: the loop tap
+    /// stops a sample behind the write head, and each trip round the
+    /// loop takes the stage's latency plus that sample. The first echo,
+    /// read from the full delay, stays on time.
+    #[test]
+    fn test_feedback_latency_clamps_at_the_shortest_loop() {
+        let times = repeat_times(6000);
+        assert_eq!(times[0], 4800);
+        assert!(times[1].abs_diff(4800 + 6001) <= 1, "{times:?}");
+    }
+
+    /// Switching the limiter out mid-signal fades from the limited level
+    /// up to the full one over `SWITCH_FADE_MS`, with no jump.
+    #[test]
+    fn test_limiter_switch_fades() {
+        let dry_only = DelayParams {
+            mix: 0.0,
+            ..test_params()
+        };
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&DelayParams {
+            limiter: true,
+            ..dry_only
+        });
+        let mut block = vec![2.0; 4800];
+        engine.process(&mut [&mut block]);
+        let limited = block[4799];
+        assert!(limited < 1.0);
+
+        engine.set_params(&dry_only);
+        let mut block = vec![2.0; 4800];
+        engine.process(&mut [&mut block]);
+
+        let fade_samples = (SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        assert!(
+            (block[0] - limited).abs() < 1e-3,
+            "{} vs {limited}",
+            block[0]
+        );
+        let max_step = block
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max);
+        assert!(max_step < 0.01, "largest step {max_step}");
+        assert!(block[fade_samples..].iter().all(|&s| s == 2.0));
+    }
+
+    /// A parameter change at sample 37 of a 256-sample buffer must start
+    /// its ramp at sample 37. The host buffer is split at the event the
+    /// same way nih-plug's wrapper does it for the plugin: process up to
+    /// the offset, apply the new value, process the rest.
+    #[test]
+    fn test_parameter_ramp_starts_at_event_offset() {
+        const EVENT_OFFSET: usize = 37;
+
+        let mut engine = test_engine();
+        let mix = test_params().mix;
+
+        // With a 100ms delay the wet signal is still silent this early,
+        // so a constant input of 1.0 comes out as exactly `1 - mix`.
+        let mut left = vec![1.0; 256];
+        let mut right = vec![1.0; 256];
+        let (left_before, left_after) = left.split_at_mut(EVENT_OFFSET);
+        let (right_before, right_after) = right.split_at_mut(EVENT_OFFSET);
+
+        engine.process(&mut [left_before, right_before]);
+        engine.set_params(&DelayParams {
+            mix: 0.9,
+            ..test_params()
+        });
+        engine.process(&mut [left_after, right_after]);
+
+        for (i, sample) in left[..EVENT_OFFSET].iter().enumerate() {
+            assert_eq!(*sample, 1.0 - mix, "sample {i} moved before the event");
+        }
+        assert!(
+            left[EVENT_OFFSET] < 1.0 - mix,
+            "ramp should start at sample {EVENT_OFFSET}"
+        );
+        assert!(
+            left[EVENT_OFFSET..].windows(2).all(|w| w[1] < w[0]),
+            "mix should keep ramping after the event"
+        );
+        assert_eq!(left, right);
+    }
+
+    /// The panic clear takes a 95%-feedback tail below −80 dBFS within
+    /// 50 ms of a trigger that lands mid-block, is re-triggered during
+    /// its own fade without trouble, and never clicks: the output stays
+    /// under the fade envelope applied to the uncleared tail. Playing
+    /// after the clear echoes as usual.
+    #[test]
+    fn test_clear_silences_the_tail() {
+        const BLOCK_LEN: usize = 512;
+        const TRIGGER: usize = 48_000 + 123;
+        const PLAY_AGAIN: usize = 72_000;
+        const TOTAL: usize = 96_000;
+
+        let params = DelayParams {
+            delay_ms: 137.0,
+            feedback: MAX_FEEDBACK,
+            filter_cutoff: 18_000.0,
+            filter_cutoff_right: 18_000.0,
+            mix: 1.0,
+            ..test_params()
+        };
+        let input = |n: usize, channel: usize| match n {
+            0..=11_999 => input_block(n / BLOCK, channel)[n % BLOCK],
+            PLAY_AGAIN => 1.0,
+            _ => 0.0,
+        };
+
+        // Left channel out, with the clear switch flipped at `events`.
+        let render = |events: &[(usize, bool)]| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&params);
+            let mut out = Vec::with_capacity(TOTAL);
+            let mut start = 0;
+            while start < TOTAL {
+                let end = events
+                    .iter()
+                    .map(|(at, _)| *at)
+                    .filter(|at| *at > start)
+                    .fold((start / BLOCK_LEN + 1) * BLOCK_LEN, usize::min)
+                    .min(TOTAL);
+                for (_, clear) in events.iter().filter(|(at, _)| *at == start) {
+                    engine.set_params(&DelayParams {
+                        clear: *clear,
+                        ..params
+                    });
+                }
+                let mut left: Vec<f32> = (start..end).map(|n| input(n, 0)).collect();
+                let mut right: Vec<f32> = (start..end).map(|n| input(n, 1)).collect();
+                engine.process(&mut [&mut left, &mut right]);
+                out.extend(left);
+                start = end;
+            }
+            out
+        };
+
+        let tail = render(&[]);
+        let cleared = render(&[
+            (TRIGGER, true),
+            (TRIGGER + 200, false),
+            (TRIGGER + 400, true),
+        ]);
+
+        assert_eq!(tail[..TRIGGER], cleared[..TRIGGER]);
+        let peak = tail[TRIGGER - 4800..TRIGGER]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.1, "the tail should still be loud: {peak}");
+
+        let fade_len = (CLEAR_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        for n in TRIGGER..TRIGGER + fade_len {
+            let envelope = 1.0 - (n - TRIGGER) as f32 / fade_len as f32;
+            assert!(
+                cleared[n].abs() <= envelope * tail[n].abs() + 1e-6,
+                "sample {n}: {} above the fade of {}",
+                cleared[n],
+                tail[n]
+            );
+        }
+
+        let silent_from = TRIGGER + (0.05 * SAMPLE_RATE) as usize;
+        let residue = cleared[silent_from..PLAY_AGAIN]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(residue < 1e-4, "tail left after 50 ms: {residue}");
+
+        // New playing after the clear echoes again.
+        let echo = PLAY_AGAIN + (0.137 * SAMPLE_RATE) as usize;
+        let echo_peak = cleared[echo - 2..echo + 2]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(echo_peak > 0.5, "no echo after the clear: {echo_peak}");
+    }
+
+    /// A clear under a steady tone wipes what was in the line, and the
+    /// tone written after it fades in: a delay later the echo swells
+    /// back out of the silence rather than stepping in.
+    #[test]
+    fn test_clear_fades_in_the_input_behind_its_wipe() {
+        const BLOCK_LEN: usize = 512;
+        const TRIGGER: usize = 100 * BLOCK_LEN;
+        const TOTAL: usize = 200 * BLOCK_LEN;
+
+        let params = DelayParams {
+            delay_ms: 100.0,
+            feedback: 0.0,
+            filter_cutoff: 18_000.0,
+            filter_cutoff_right: 18_000.0,
+            mix: 1.0,
+            ..test_params()
+        };
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let mut out = Vec::with_capacity(TOTAL);
+        for start in (0..TOTAL).step_by(BLOCK_LEN) {
+            engine.set_params(&DelayParams {
+                clear: start >= TRIGGER,
+                ..params
+            });
+            let tone =
+                |n: usize| 0.5 * (std::f32::consts::TAU * 220.0 * n as f32 / SAMPLE_RATE).sin();
+            let mut left: Vec<f32> = (start..start + BLOCK_LEN).map(tone).collect();
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+            out.extend(left);
+        }
+
+        let delay = (0.1 * SAMPLE_RATE) as usize;
+        let fade_len = (CLEAR_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let quiet = out[TRIGGER + delay..TRIGGER + delay + fade_len]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(quiet < 1e-4, "the clear should leave a gap: {quiet}");
+        let back = out[TOTAL - BLOCK_LEN..]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(back > 0.4, "the echo should come back: {back}");
+        let bend = out[TRIGGER..]
+            .windows(3)
+            .fold(0.0f32, |m, w| m.max((w[2] - 2.0 * w[1] + w[0]).abs()));
+        assert!(bend < 0.005, "the echo stepped back in: {bend}");
+    }
+
+    /// The transport stopping rings the repeats out, fades them over
+    /// [`STOP_FADE_MS`] or clears them, as the stop behavior says, and
+    /// nothing happens without a playing-to-stopped transition.
+    #[test]
+    fn test_stop_behavior_follows_transport() {
+        const BLOCK_LEN: usize = 512;
+        const STOP: usize = 94 * BLOCK_LEN;
+        const TOTAL: usize = 2 * STOP;
+
+        // Left channel out, with the transport playing until `STOP` if
+        // `plays`.
+        fn render(stop_behavior: StopBehavior, plays: bool) -> Vec<f32> {
+            let params = DelayParams {
+                delay_ms: 137.0,
+                feedback: 0.8,
+                mix: 1.0,
+                stop_behavior,
+                ..test_params()
+            };
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            let mut host = MockTransport::new(SAMPLE_RATE, 120.0);
+            if plays {
+                host.play();
+            }
+            let mut out = Vec::with_capacity(TOTAL);
+            for start in (0..TOTAL).step_by(BLOCK_LEN) {
+                if start == STOP {
+                    host.stop();
+                }
+                engine.set_transport(host.next_block(BLOCK_LEN));
+                engine.set_params(&params);
+                let input = |channel| -> Vec<f32> {
+                    (start..start + BLOCK_LEN)
+                        .map(|n| match n {
+                            0..=11_999 => input_block(n / BLOCK, channel)[n % BLOCK],
+                            _ => 0.0,
+                        })
+                        .collect()
+                };
+                let (mut left, mut right) = (input(0), input(1));
+                engine.process(&mut [&mut left, &mut right]);
+                out.extend(left);
+            }
+            out
+        }
+        let peak = |out: &[f32], from_ms: usize, to_ms: usize| {
+            let at = |ms| STOP + ms * SAMPLE_RATE as usize / 1000;
+            out[at(from_ms)..at(to_ms)]
+                .iter()
+                .fold(0.0f32, |m, s| m.max(s.abs()))
+        };
+
+        let ring_out = render(StopBehavior::RingOut, true);
+        assert!(peak(&ring_out, 500, 600) > 0.05, "the tail should ring on");
+        assert_eq!(ring_out, render(StopBehavior::Fade, false));
+        assert_eq!(ring_out, render(StopBehavior::RingOut, false));
+
+        // Fading: barely touched at first, about halfway down midway,
+        // and gone once the fade and the wipe are done.
+        let faded = render(StopBehavior::Fade, true);
+        assert_eq!(faded[..STOP], ring_out[..STOP]);
+        let ratio = |from_ms, to_ms| peak(&faded, from_ms, to_ms) / peak(&ring_out, from_ms, to_ms);
+        assert!(ratio(0, 20) > 0.9, "faded too soon: {}", ratio(0, 20));
+        assert!(
+            (0.2..0.7).contains(&ratio(230, 270)),
+            "not halfway at 250 ms: {}",
+            ratio(230, 270)
+        );
+        assert!(peak(&faded, 520, 1000) < 1e-4);
+
+        let cleared = render(StopBehavior::Clear, true);
+        assert!(peak(&cleared, 50, 1000) < 1e-4);
+    }
+
+    /// A host that pauses for ten seconds without calling `reset()` —
+    /// whether it stops calling `process()` while playing, reports a stop
+    /// and goes quiet, or reports no transport at all and just sends
+    /// silence — gets no stale repeats when playback resumes, under Fade
+    /// or Clear. Under Ring Out they're still there.
+    #[test]
+    fn test_playback_gaps_clear_stale_repeats() {
+        const BLOCK_LEN: usize = 512;
+        const BEFORE: usize = 2 * SAMPLE_RATE as usize / BLOCK_LEN;
+        const PAUSE: usize = 10 * SAMPLE_RATE as usize;
+        const AFTER: usize = SAMPLE_RATE as usize / BLOCK_LEN;
+
+        #[derive(Debug, Clone, Copy)]
+        enum Host {
+            /// Keeps playing, but stops calling `process()`.
+            Skips,
+            /// Reports the stop, then stops calling `process()`.
+            Stops,
+            /// Reports no transport, and sends silence.
+            NoTransport,
+        }
+
+        // Left channel out for a second after the pause. Each stretch of
+        // playing starts with a block of input and goes silent after it.
+        fn resume(host_style: Host, stop_behavior: StopBehavior) -> Vec<f32> {
+            let params = DelayParams {
+                delay_ms: 137.0,
+                feedback: MAX_FEEDBACK,
+                mix: 1.0,
+                stop_behavior,
+                ..test_params()
+            };
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            let mut host = MockTransport::new(SAMPLE_RATE, 120.0);
+            let mut run = |host: &mut MockTransport, loud: bool| -> Vec<f32> {
+                engine.set_transport(host.next_block(BLOCK_LEN));
+                engine.set_params(&params);
+                let input = |channel| match loud {
+                    true => input_block(0, channel)[..BLOCK_LEN].to_vec(),
+                    false => vec![0.0; BLOCK_LEN],
+                };
+                let (mut left, mut right) = (input(0), input(1));
+                engine.process(&mut [&mut left, &mut right]);
+                left
+            };
+
+            match host_style {
+                Host::NoTransport => host.locate(None),
+                Host::Skips | Host::Stops => host.play(),
+            }
+            for block in 0..BEFORE {
+                run(&mut host, block == 0);
+            }
+            match host_style {
+                Host::Skips => {
+                    host.next_block(PAUSE);
+                }
+                Host::Stops => {
+                    host.stop();
+                    run(&mut host, false);
+                    host.play();
+                }
+                Host::NoTransport => {
+                    for _ in 0..PAUSE / BLOCK_LEN {
+                        run(&mut host, false);
+                    }
+                }
+            }
+            (0..AFTER)
+                .flat_map(|block| run(&mut host, block == 0))
+                .collect()
+        }
+        let peak = |out: &[f32], from_ms: usize, to_ms: usize| {
+            let at = |ms| ms * SAMPLE_RATE as usize / 1000;
+            out[at(from_ms)..at(to_ms)]
+                .iter()
+                .fold(0.0f32, |m, s| m.max(s.abs()))
+        };
+
+        for host_style in [Host::Skips, Host::Stops, Host::NoTransport] {
+            let rung_out = resume(host_style, StopBehavior::RingOut);
+            let stale = peak(&rung_out, 50, 130);
+            assert!(
+                stale > 1e-3,
+                "{host_style:?}: nothing stale to clear: {stale}"
+            );
+
+            for stop_behavior in [StopBehavior::Fade, StopBehavior::Clear] {
+                let cleared = resume(host_style, stop_behavior);
+                let leak = peak(&cleared, 50, 130);
+                assert!(
+                    leak < 1e-4,
+                    "{host_style:?}, {stop_behavior:?}: stale {leak}"
+                );
+                // The new playing still echoes.
+                let echo = peak(&cleared, 135, 150);
+                assert!(echo > 0.1, "{host_style:?}, {stop_behavior:?}: echo {echo}");
+            }
+        }
+    }
+
+    /// Feedback matrices with entries anywhere in ±100% — random ones,
+    /// and the lopsided ones that would run away if only their spectral
+    /// radius were normalized — stay stable at full feedback with very
+    /// different filters on the two sides: over ten seconds of decay
+    /// the repeats never get louder than the burst that started them,
+    /// and die away.
+    #[test]
+    fn test_feedback_matrix_stays_stable() {
+        const BLOCK_LEN: usize = 512;
+        const BLOCKS: usize = 10 * SAMPLE_RATE as usize / BLOCK_LEN;
+        const SECOND: usize = SAMPLE_RATE as usize / BLOCK_LEN;
+
+        let mut rng = crate::dsp::noise::Xorshift32::new(5);
+        let mut matrices: Vec<FeedbackMatrix> = (0..12)
+            .map(|_| FeedbackMatrix {
+                ll: rng.next_bipolar(),
+                lr: rng.next_bipolar(),
+                rl: rng.next_bipolar(),
+                rr: rng.next_bipolar(),
+            })
+            .collect();
+        let lopsided = |ll, lr, rl, rr| FeedbackMatrix { ll, lr, rl, rr };
+        matrices.extend([
+            lopsided(1.0, 1.0, -1.0, -1.0),
+            lopsided(1.0, 1.0, 0.0, 1.0),
+            lopsided(-1.0, 1.0, -1.0, 1.0),
+            lopsided(1.0, 1.0, 1.0, 1.0),
+        ]);
+
+        for matrix in matrices {
+            let params = DelayParams {
+                feedback: MAX_FEEDBACK,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                filter_cutoff_right: 300.0,
+                feedback_matrix: matrix,
+                ..test_params()
+            };
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&params);
+
+            let mut block_peaks = Vec::with_capacity(BLOCKS);
+            for block in 0..BLOCKS {
+                let input = |channel| match block {
+                    0 => input_block(0, channel)[..BLOCK_LEN].to_vec(),
+                    _ => vec![0.0; BLOCK_LEN],
+                };
+                let (mut left, mut right) = (input(0), input(1));
+                engine.process(&mut [&mut left, &mut right]);
+                let peak = left
+                    .iter()
+                    .chain(&right)
+                    .fold(0.0f32, |m, s| m.max(s.abs()));
+                assert!(peak.is_finite(), "{matrix:?}: not finite in block {block}");
+                block_peaks.push(peak);
+            }
+
+            let peak = |blocks: &[f32]| blocks.iter().fold(0.0f32, |m, p| m.max(*p));
+            let loudest = peak(&block_peaks);
+            assert!(loudest <= 0.75, "{matrix:?}: built up to {loudest}");
+            let last_second = peak(&block_peaks[BLOCKS - SECOND..]);
+            assert!(
+                last_second < 0.05 * peak(&block_peaks[..SECOND]),
+                "{matrix:?}: still at {last_second} after ten seconds"
+            );
+        }
+    }
+
+    /// Wet solo fades the dry signal out and back in over the switch
+    /// fade, from the sample it's switched at, without a step.
+    #[test]
+    fn test_wet_solo_fades_the_dry_signal() {
+        const SWITCH_AT: usize = 37;
+        let mut engine = test_engine();
+        let dry_level = 1.0 - test_params().mix;
+        let fade_len = (SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+
+        // The echo is still 100 ms away, so a constant 1.0 in comes out
+        // as exactly the dry level.
+        let mut render = |len: usize, wet_solo: bool| {
+            engine.set_params(&DelayParams {
+                wet_solo,
+                ..test_params()
+            });
+            let mut left = vec![1.0; len];
+            let mut right = vec![1.0; len];
+            engine.process(&mut [&mut left, &mut right]);
+            left
+        };
+        let mut out = render(SWITCH_AT, false);
+        out.extend(render(1000, true));
+        out.extend(render(1000, false));
+
+        assert!(out[..SWITCH_AT].iter().all(|s| *s == dry_level));
+        let max_step = dry_level / fade_len as f32 + 1e-6;
+        for (n, pair) in out.windows(2).enumerate() {
+            assert!(
+                (pair[1] - pair[0]).abs() <= max_step,
+                "step of {} at sample {n}",
+                pair[1] - pair[0]
+            );
+        }
+        // The fade starts at full level on the switch's own sample.
+        assert_eq!(out[SWITCH_AT], dry_level);
+        assert!(out[SWITCH_AT + 1] < dry_level);
+        assert_eq!(
+            out[SWITCH_AT + fade_len..SWITCH_AT + 1000],
+            vec![0.0; 1000 - fade_len]
+        );
+        assert_eq!(*out.last().unwrap(), dry_level);
+    }
+
+    /// With maximum feedback, a loud phrase keeps the loop above −20 dBFS
+    /// for seconds. The watchdog lets it ring for its hold time, then
+    /// pulls the feedback down so the tail is gone shortly after, and
+    /// hands the feedback back to the knob once it is.
+    #[test]
+    fn test_watchdog_pulls_down_a_ringing_loop() {
+        const HOLD_SECONDS: f32 = 1.0;
+        let period = (0.1 * SAMPLE_RATE) as usize;
+
+        // The wet signal's peak over each delay period, in dBFS.
+        let ringing = |watchdog: bool| {
+            let mut engine = test_engine();
+            let params = DelayParams {
+                feedback: MAX_FEEDBACK,
+                mix: 1.0,
+                watchdog,
+                watchdog_seconds: HOLD_SECONDS,
+                ..test_params()
+            };
+            engine.set_params(&params);
+            let mut peaks = Vec::new();
+            for n in 0..60 {
+                let mut left: Vec<f32> = (0..period)
+                    .map(|i| if n < 2 { (i as f32 * 0.05).sin() } else { 0.0 })
+                    .collect();
+                let mut right = left.clone();
+                engine.set_params(&params);
+                engine.process(&mut [&mut left, &mut right]);
+                let peak = left.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+                peaks.push(20.0 * peak.max(1e-9).log10());
+            }
+            (peaks, engine.feedback.target())
+        };
+
+        let (unwatched, _) = ringing(false);
+        let (watched, feedback) = ringing(true);
+
+        // Unwatched, the loop is still loud three seconds in.
+        assert!(unwatched[30] > -20.0, "{unwatched:?}");
+
+        // Watched, it rings loud for the hold time...
+        assert!(watched[..10].iter().all(|db| *db > -20.0), "{watched:?}");
+        // ...then falls below the release threshold within a second.
+        let quiet_at = ((HOLD_SECONDS + 1.0) / 0.1) as usize + 2;
+        assert!(
+            watched[quiet_at..].iter().all(|db| *db < -40.0),
+            "{watched:?}"
+        );
+        assert_eq!(feedback, MAX_FEEDBACK);
+    }
+
+    /// Freeze a sine whose cycle doesn't divide the delay period and loop
+    /// it 100 times. Quantized, the loop is a whole number of periods and
+    /// the seam is blended, so no sample steps further than the sine
+    /// itself can, even through the equal-power bump at the seam. A
+    /// plain freeze of the same sine jumps where it wraps.
+    #[test]
+    fn test_quantized_freeze_loops_without_a_seam() {
+        const FREQ_HZ: f32 = 443.9;
+        const AMPLITUDE: f32 = 0.5;
+        let period = (0.1 * SAMPLE_RATE) as usize;
+
+        // The largest step between neighboring output samples while
+        // frozen, and the loop's length.
+        let frozen_steps = |freeze_quantize: bool, loops: usize| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            let mut params = DelayParams {
+                feedback: 0.0,
+                mix: 1.0,
+                freeze_quantize,
+                ..test_params()
+            };
+            engine.set_params(&params);
+
+            // Fill the whole line with the sine.
+            let mut phase = 0.0f32;
+            let mut sine = |len: usize| -> Vec<f32> {
+                (0..len)
+                    .map(|_| {
+                        phase += std::f32::consts::TAU * FREQ_HZ / SAMPLE_RATE;
+                        AMPLITUDE * phase.sin()
+                    })
+                    .collect()
+            };
+            for _ in 0..25 {
+                engine.process(&mut [&mut sine(period)]);
+            }
+
+            params.freeze = true;
+            engine.set_params(&params);
+            let len = engine.frozen.unwrap().len;
+
+            let mut max_step: f32 = 0.0;
+            let mut last: Option<f32> = None;
+            for _ in 0..loops {
+                // The input keeps playing, and isn't heard.
+                let mut block = sine(len);
+                engine.process(&mut [&mut block]);
+                for sample in block {
+                    if let Some(last) = last {
+                        max_step = max_step.max((sample - last).abs());
+                    }
+                    last = Some(sample);
+                }
+            }
+            (max_step, len)
+        };
+
+        let sine_step = std::f32::consts::TAU * FREQ_HZ / SAMPLE_RATE * AMPLITUDE;
+        let (quantized_step, len) = frozen_steps(true, 100);
+        assert_eq!(len % period, 0, "{len}");
+        assert!(
+            quantized_step < sine_step * std::f32::consts::SQRT_2 * 1.05,
+            "{quantized_step} vs {sine_step}"
+        );
+
+        let (plain_step, len) = frozen_steps(false, 2);
+        assert_ne!(len % period, 0, "{len}");
+        assert!(plain_step > 2.0 * sine_step, "{plain_step} vs {sine_step}");
+    }
+
+    /// A frozen loop holds its level; releasing it fades back to the
+    /// live delay without a jump, and the loop decays from there.
+    #[test]
+    fn test_freeze_release_fades_back() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.5,
+            mix: 1.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        let mut render = |params: &DelayParams, input: f32| {
+            engine.set_params(params);
+            let mut left = vec![input; BLOCK];
+            let mut right = vec![input; BLOCK];
+            engine.process(&mut [&mut left, &mut right]);
+            left
+        };
+
+        // Steady input, so the loop settles at a constant level.
+        let mut out = Vec::new();
+        for _ in 0..150 {
+            out = render(&params, 0.25);
+        }
+        let settled = *out.last().unwrap();
+
+        params.freeze = true;
+        let frozen: Vec<f32> = (0..10).flat_map(|_| render(&params, 0.0)).collect();
+        assert!(
+            frozen.iter().all(|s| (s - settled).abs() < 1e-4),
+            "the frozen loop should hold the level"
+        );
+
+        // Until a period after the release, the live read finds the
+        // loop as it was before the freeze.
+        params.freeze = false;
+        let period = (0.1 * SAMPLE_RATE) as usize;
+        let released: Vec<f32> = (0..5).flat_map(|_| render(&params, 0.0)).collect();
+        let max_step = frozen
+            .last()
+            .into_iter()
+            .chain(&released[..period - 1])
+            .collect::<Vec<_>>()
+            .windows(2)
+            .fold(0.0f32, |m, pair| m.max((pair[1] - pair[0]).abs()));
+        // Loop and live read are the same here, so the equal-power fade
+        // swells by 3 dB and back over its 10 ms; nothing steps faster.
+        let fade_len = SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE;
+        let swell_slope = settled * std::f32::consts::FRAC_PI_2 / fade_len;
+        assert!(max_step < swell_slope * 1.05, "step of {max_step}");
+        // Then, with the input gone, it decays as ordinary repeats.
+        assert!((released[period + BLOCK] - settled * 0.5).abs() < 0.01);
+    }
+
+    /// A release fade that ends exactly on a block boundary leaves
+    /// nothing of the loop behind for the (static-path) blocks after it.
+    #[test]
+    fn test_freeze_release_ending_on_a_block_boundary() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.0,
+            mix: 1.0,
+            ..test_params()
+        };
+        let mut render = |params: &DelayParams, len: usize, input: f32| {
+            engine.set_params(params);
+            let mut left = vec![input; len];
+            let mut right = vec![input; len];
+            engine.process(&mut [&mut left, &mut right]);
+            left
+        };
+        for _ in 0..60 {
+            render(&params, BLOCK, 0.25);
+        }
+        params.freeze = true;
+        render(&params, BLOCK, 0.0);
+
+        params.freeze = false;
+        let fade_len = (SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        render(&params, fade_len, 0.0);
+        // For a period the live read still finds the input from before
+        // the freeze, exactly.
+        let after = render(&params, 1024, 0.0);
+        assert!(after.iter().all(|s| *s == 0.25), "{:?}", &after[..4]);
+    }
+
+    /// A clear under a freeze wipes the whole frozen loop: the lines
+    /// stand still, so the wipe's slices must meet without a sample
+    /// between them, or the loop goes round clicking on what's left.
+    #[test]
+    fn test_clear_while_frozen_wipes_the_whole_loop() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.5,
+            mix: 1.0,
+            ..test_params()
+        };
+        let mut render = |params: &DelayParams, len: usize| {
+            engine.set_params(params);
+            let mut left = vec![0.25; len];
+            let mut right = vec![0.25; len];
+            engine.process(&mut [&mut left, &mut right]);
+            left
+        };
+        for _ in 0..60 {
+            render(&params, BLOCK);
+        }
+        params.freeze = true;
+        render(&params, BLOCK);
+
+        params.clear = true;
+        render(&params, (0.1 * SAMPLE_RATE) as usize);
+        let cleared = render(&params, (2.0 * SAMPLE_RATE) as usize);
+        let left = cleared.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert_eq!(left, 0.0, "the wipe left some of the loop");
+    }
+
+    /// A freeze in the same update as a time crossfade starts the loop
+    /// on what was being heard, the old time, and the fade carries on
+    /// in the loop: the sine out never steps.
+    #[test]
+    fn test_freeze_catches_a_time_crossfade() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.0,
+            mix: 1.0,
+            time_change: TimeChange::Crossfade,
+            ..test_params()
+        };
+        let mut phase = 0.0f32;
+        let mut render = |params: &DelayParams| {
+            engine.set_params(params);
+            let mut left: Vec<f32> = (0..BLOCK)
+                .map(|_| {
+                    phase += std::f32::consts::TAU * 220.0 / SAMPLE_RATE;
+                    0.5 * phase.sin()
+                })
+                .collect();
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+            left
+        };
+        let mut out = Vec::new();
+        for _ in 0..20 {
+            out = render(&params);
+        }
+
+        params.delay_ms = 230.0;
+        params.freeze = true;
+        for _ in 0..4 {
+            out.extend(render(&params));
+        }
+        let bend = out
+            .windows(3)
+            .fold(0.0f32, |m, w| m.max((w[2] - 2.0 * w[1] + w[0]).abs()));
+        assert!(bend < 0.005, "the freeze stepped out of the fade: {bend}");
+    }
+
+    /// A time crossfade queued behind the one a freeze caught moves only
+    /// the live read: when it starts, the loop plays on where it was.
+    #[test]
+    fn test_freeze_ignores_a_queued_time_crossfade() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.0,
+            mix: 1.0,
+            time_change: TimeChange::Crossfade,
+            ..test_params()
+        };
+        let mut phase = 0.0f32;
+        let mut render = |params: &DelayParams, len: usize| {
+            engine.set_params(params);
+            let mut left: Vec<f32> = (0..len)
+                .map(|_| {
+                    phase += std::f32::consts::TAU * 220.0 / SAMPLE_RATE;
+                    0.5 * phase.sin()
+                })
+                .collect();
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+            left
+        };
+        let mut out = Vec::new();
+        for _ in 0..20 {
+            out = render(&params, BLOCK);
+        }
+
+        params.delay_ms = 230.0;
+        params.freeze = true;
+        out.extend(render(&params, 256));
+        params.delay_ms = 300.0;
+        for _ in 0..4 {
+            out.extend(render(&params, BLOCK));
+        }
+        let bend = out
+            .windows(3)
+            .fold(0.0f32, |m, w| m.max((w[2] - 2.0 * w[1] + w[0]).abs()));
+        assert!(bend < 0.005, "the loop jumped to the queued time: {bend}");
+    }
+
+    /// Circular motion keeps shaping the wet once it's frozen: engaging
+    /// a freeze mid-rotation steps neither side.
+    #[test]
+    fn test_freeze_keeps_the_motion_gains() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            feedback: 0.5,
+            mix: 1.0,
+            motion: StereoMotion::Circular,
+            ..test_params()
+        };
+        let mut phase = 0.0f32;
+        let mut render = |params: &DelayParams| {
+            engine.set_params(params);
+            let mut left: Vec<f32> = (0..BLOCK)
+                .map(|_| {
+                    phase += std::f32::consts::TAU * 220.0 / SAMPLE_RATE;
+                    0.5 * phase.sin()
+                })
+                .collect();
+            let mut right = vec![0.0; BLOCK];
+            engine.process(&mut [&mut left, &mut right]);
+            [left, right]
+        };
+        let mut out = [Vec::new(), Vec::new()];
+        for _ in 0..60 {
+            out = render(&params);
+        }
+
+        params.freeze = true;
+        let [left, right] = render(&params);
+        out[0].extend(left);
+        out[1].extend(right);
+        for side in out {
+            let bend = side
+                .windows(3)
+                .fold(0.0f32, |m, w| m.max((w[2] - 2.0 * w[1] + w[0]).abs()));
+            assert!(bend < 0.005, "the freeze dropped the motion: {bend}");
+        }
+    }
+
+    /// A plain freeze of dual mono, the two lines sharing one, loops
+    /// everything the line holds, just as a mono freeze does.
+    #[test]
+    fn test_dual_mono_freeze_loops_the_whole_line() {
+        let frozen_len = |channels: usize| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, channels);
+            let mut params = DelayParams {
+                feedback: 0.0,
+                mix: 1.0,
+                ..test_params()
+            };
+            for _ in 0..60 {
+                engine.set_params(&params);
+                let mut blocks = vec![vec![0.25; BLOCK]; channels];
+                let mut refs: Vec<&mut [f32]> = blocks.iter_mut().map(|b| &mut b[..]).collect();
+                engine.process(&mut refs);
+            }
+            params.freeze = true;
+            engine.set_params(&params);
+            engine.frozen.unwrap().len
+        };
+        let mono = frozen_len(1);
+        assert!(mono > (2.0 * SAMPLE_RATE) as usize, "{mono}");
+        assert_eq!(frozen_len(2), mono);
+    }
+
+    /// A freeze at the longest delay loops on from what was being heard,
+    /// quantized or not, with the lines full or not yet: the wave out
+    /// never steps. It starts at its peak, so a loop starting on the
+    /// recording's first sample instead of the silence being heard
+    /// would jump.
+    #[test]
+    fn test_freeze_at_the_longest_time_picks_up_the_read() {
+        for (seconds, freeze_quantize) in [(1.5, false), (1.5, true), (2.5, false), (2.5, true)] {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            let mut params = DelayParams {
+                delay_ms: MAX_DELAY_MS,
+                feedback: 0.0,
+                mix: 1.0,
+                freeze_quantize,
+                ..test_params()
+            };
+            let mut phase = std::f32::consts::FRAC_PI_2;
+            let mut render = |params: &DelayParams| {
+                engine.set_params(params);
+                let mut left: Vec<f32> = (0..BLOCK)
+                    .map(|_| {
+                        phase += std::f32::consts::TAU * 220.0 / SAMPLE_RATE;
+                        0.5 * phase.sin()
+                    })
+                    .collect();
+                engine.process(&mut [&mut left]);
+                left
+            };
+            let mut out = Vec::new();
+            for _ in 0..(seconds * SAMPLE_RATE) as usize / BLOCK {
+                out = render(&params);
+            }
+
+            params.freeze = true;
+            for _ in 0..4 {
+                out.extend(render(&params));
+            }
+            let bend = out
+                .windows(3)
+                .fold(0.0f32, |m, w| m.max((w[2] - 2.0 * w[1] + w[0]).abs()));
+            assert!(
+                bend < 0.005,
+                "{seconds} s in, quantize {freeze_quantize}: the freeze jumped by {bend}"
+            );
+        }
+    }
+
+    /// Overdubbing a frozen loop: the loop fades by the decay on each
+    /// pass, and a burst played over it goes in at the inject gain and
+    /// fades from there, going round at the loop's length.
+    #[test]
+    fn test_overdub_layers_a_second_burst() {
+        const BURST: usize = 480;
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        let mut params = DelayParams {
+            feedback: 0.0,
+            mix: 1.0,
+            freeze_quantize: true,
+            overdub: true,
+            overdub_decay_db: -6.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        skip_engage_fade(&mut engine);
+        let (keep, inject) = overdub_gains(1.0, overdub_decay_gain(-6.0)).unwrap();
+
+        // The first layer: a burst, with two periods of room around it.
+        let mut block = vec![0.0; 10_480];
+        block[2000..2000 + BURST].fill(0.5);
+        engine.process(&mut [&mut block]);
+        params.freeze = true;
+        engine.set_params(&params);
+        let len = engine.frozen.unwrap().len;
+        assert_eq!(len, 9600);
+
+        // Four passes, the second burst played halfway round the loop
+        // from the first during the second pass.
+        let mut first = vec![0.0; len];
+        engine.process(&mut [&mut first]);
+        let first_at = first.iter().position(|s| *s > 0.1).unwrap();
+        let second_at = (first_at + len / 2) % len;
+        let mut rest = vec![0.0; 3 * len];
+        rest[second_at..second_at + BURST].fill(0.5);
+        engine.process(&mut [&mut rest]);
+        let passes = [first, rest].concat();
+
+        let level = |pass: usize, at: usize| {
+            let start = pass * len + at;
+            passes[start + BURST / 4..start + BURST * 3 / 4]
+                .iter()
+                .fold(0.0f32, |m, s| m.max(s.abs()))
+        };
+        for pass in 0..4 {
+            let want = 0.5 * keep.powi(pass as i32);
+            let got = level(pass, first_at);
+            assert!((got - want).abs() < 1e-4, "pass {pass}: {got} vs {want}");
+        }
+        assert!(
+            level(1, second_at) < 1e-6,
+            "not heard on the pass it's played"
+        );
+        for pass in 2..4 {
+            let want = 0.5 * inject * keep.powi(pass as i32 - 2);
+            let got = level(pass, second_at);
+            assert!((got - want).abs() < 1e-4, "pass {pass}: {got} vs {want}");
+        }
+
+        // Nothing lands between the two layers.
+        let gap = (first_at + BURST + 100) % len;
+        assert!(level(3, gap) < 1e-6);
+    }
+
+    /// After an impulse, the engine reports a tail while echoes are still
+    /// audible and reports it over once the measured wet signal has
+    /// stayed below -90 dBFS for a full delay period.
+    #[test]
+    fn test_tail_follows_measured_decay() {
+        const BLOCK_LEN: usize = 512;
+        const DELAY_SAMPLES: usize = 4800; // 100ms at 48 kHz
+
+        let mut engine = test_engine();
+        let threshold = engine.silence.threshold();
+        let mix = test_params().mix;
+
+        let mut output = Vec::new();
+        let mut tails = Vec::new();
+        for block_idx in 0..200 {
+            let mut left = vec![0.0; BLOCK_LEN];
+            let mut right = vec![0.0; BLOCK_LEN];
+            if block_idx == 0 {
+                left[0] = 1.0;
+                right[0] = 1.0;
+            }
+            engine.process(&mut [&mut left, &mut right]);
+            tails.push(engine.tail_samples());
+            output.extend(left);
+        }
+
+        assert!(tails[0].is_some());
+
+        let first_over = tails
+            .iter()
+            .position(Option::is_none)
+            .expect("tail should eventually end");
+        assert!(
+            tails[first_over..].iter().all(Option::is_none),
+            "tail must stay over once it has ended"
+        );
+
+        // The last echo whose wet level was above the threshold (the
+        // output carries the wet signal scaled by `mix`).
+        let last_echo = output
+            .iter()
+            .rposition(|s| s.abs() >= threshold * mix)
+            .unwrap();
+        let end_of_block = (first_over + 1) * BLOCK_LEN;
+
+        assert!(
+            end_of_block >= last_echo + DELAY_SAMPLES,
+            "tail ended at {end_of_block}, before a full silent delay period after {last_echo}"
+        );
+        assert!(
+            end_of_block < last_echo + DELAY_SAMPLES + 2 * BLOCK_LEN,
+            "tail ended at {end_of_block}, long after the echoes died at {last_echo}"
+        );
+    }
+
+    /// Once the tail has died and the lines have held silence for their
+    /// whole length, silent blocks are skipped. The first loud sample
+    /// wakes the engine, and its echoes come out just as they do from a
+    /// fresh engine, on the same samples.
+    #[test]
+    fn test_sleeps_when_idle_and_wakes_cleanly() {
+        const BLOCK_LEN: usize = 512;
+        const HIT_AT: usize = 100;
+        const DELAY_SAMPLES: usize = 4800; // 100ms at 48 kHz
+
+        /// `blocks` blocks of silence, with an impulse part-way into the
+        /// first if `hit` is set.
+        fn render(engine: &mut LovelessDelayEngine, blocks: usize, hit: bool) -> Vec<f32> {
+            let mut output = Vec::new();
+            for block_idx in 0..blocks {
+                let mut left = vec![0.0; BLOCK_LEN];
+                let mut right = vec![0.0; BLOCK_LEN];
+                if hit && block_idx == 0 {
+                    left[HIT_AT] = 1.0;
+                    right[HIT_AT] = 0.5;
+                }
+                engine.process(&mut [&mut left, &mut right]);
+                output.extend(left);
+            }
+            output
+        }
+
+        let mut engine = test_engine();
+        let first = render(&mut engine, 40, true);
+        let echo = first[HIT_AT + DELAY_SAMPLES..][..100]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(echo > 0.01, "no echo to compare: {echo}");
+
+        // The tail takes a couple of seconds to fall below -120 dBFS,
+        // and the lines then have to stay silent for their whole length.
+        let line_len = engine.mono_input.capacity();
+        let mut silent_blocks = 0;
+        while !engine.is_asleep() {
+            render(&mut engine, 1, false);
+            silent_blocks += 1;
+            assert!(
+                silent_blocks < 20 * line_len / BLOCK_LEN,
+                "never fell asleep"
+            );
+        }
+        assert!(silent_blocks * BLOCK_LEN >= line_len);
+        assert!(engine.idle.silent_samples() >= line_len);
+        assert_eq!(engine.tail_samples(), None);
+
+        let idle = render(&mut engine, 10, false);
+        assert!(engine.is_asleep());
+        assert!(idle.iter().all(|s| *s == 0.0));
+
+        let second = render(&mut engine, 40, true);
+        assert!(!engine.is_asleep());
+        for (n, (a, b)) in first.iter().zip(&second).enumerate() {
+            assert!(
+                (a - b).abs() < 1e-5,
+                "sample {n}: {a} after waking, {b} fresh"
+            );
+        }
+    }
+
+    /// Stereo → mono → stereo with audio in between: nothing panics,
+    /// the left channel's echoes survive, and the old right channel's
+    /// audio never leaks into the new right channel.
+    #[test]
+    fn test_channel_count_changes() {
+        const DELAY_SAMPLES: usize = 4800; // 100ms at 48 kHz
+
+        let mut engine = test_engine();
+        let mut left = vec![0.0; 1024];
+        let mut right = vec![0.0; 1024];
+        left[0] = 1.0;
+        right[0] = 1.0;
+        engine.process(&mut [&mut left, &mut right]);
+
+        // Mono: the host hands us one channel. Extra slices from a
+        // confused host are left alone rather than indexing past the end.
+        engine.set_channels(1);
+        assert_eq!(engine.channels(), 1);
+        let mut mono = vec![0.0; 1024];
+        let mut stray = vec![0.5; 1024];
+        engine.process(&mut [&mut mono, &mut stray]);
+        assert!(stray.iter().all(|s| *s == 0.5));
+
+        // Back to stereo, then run past the first echo.
+        engine.set_channels(2);
+        assert_eq!(engine.channels(), 2);
+        let mut left = vec![0.0; DELAY_SAMPLES];
+        let mut right = vec![0.0; DELAY_SAMPLES];
+        engine.process(&mut [&mut left, &mut right]);
+
+        assert!(
+            left.iter().any(|s| s.abs() > 0.1),
+            "left echo should survive the layout changes"
+        );
+        assert!(
+            right.iter().all(|s| *s == 0.0),
+            "stale right-channel audio leaked into the new layout"
+        );
+    }
+
+    /// A delay far shorter than the block: the echo still arrives
+    /// exactly on time, and the feedback builds up just as it does when
+    /// the same audio is fed through one sample at a time. At the lowest
+    /// rate, 8 kHz, the shortest delay (100ms) is 800 samples, against a
+    /// 4096-sample block.
+    #[test]
+    fn test_delay_shorter_than_block() {
+        const RATE: f32 = SAMPLE_RATE_RANGE.0;
+        const DELAY: usize = 800;
+        const BUFFER: usize = 4096;
+        let params = DelayParams {
+            delay_ms: MIN_DELAY_MS,
+            feedback: 0.5,
+            mix: 1.0,
+            ..DelayParams::default()
+        };
+        let engine = || {
+            let mut engine = LovelessDelayEngine::new(RATE, 1);
+            engine.set_params(&params);
+            skip_engage_fade(&mut engine);
+            engine
+        };
+
+        let mut block = vec![0.0; BUFFER];
+        block[0] = 1.0;
+        let impulse = block.clone();
+        engine().process(&mut [&mut block]);
+
+        assert!(block[..DELAY].iter().all(|&s| s == 0.0), "echo came early");
+        assert!(block[DELAY] > 0.1, "no echo at {DELAY}: {}", block[DELAY]);
+
+        let mut per_sample = engine();
+        for (i, &input) in impulse.iter().enumerate() {
+            let mut sample = [input];
+            per_sample.process(&mut [&mut sample]);
+            assert_eq!(sample[0], block[i], "sample {i}");
+        }
+
+        // Several repeats fit in the block, each quieter than the last.
+        let repeat_energy = |k: usize| {
+            block[k * DELAY..(k + 1) * DELAY]
+                .iter()
+                .map(|s| s * s)
+                .sum::<f32>()
+        };
+        for k in 1..BUFFER / DELAY - 1 {
+            assert!(repeat_energy(k + 1) < repeat_energy(k), "repeat {k}");
+        }
+        assert!(repeat_energy(4) > 0.0);
+    }
+
+    /// With the send wired straight back to the return one buffer
+    /// later — as a host does — the external loop sounds the same as
+    /// the internal one: every repeat on time, shaped the same way.
+    #[test]
+    fn test_external_loop_through_a_wire() {
+        const BUFFER: usize = 512;
+        let params = DelayParams {
+            feedback: 0.6,
+            mix: 1.0,
+            filter_cutoff: 3000.0,
+            filter_cutoff_right: 3000.0,
+            ..test_params()
+        };
+        let mut internal = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        internal.set_params(&params);
+        let mut external = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        external.set_params(&DelayParams {
+            external_loop: true,
+            ..params
+        });
+        external.set_external_loop_latency(BUFFER);
+
+        // The "cable": what was sent last block comes back this block.
+        let mut cable = [vec![0.0; BUFFER], vec![0.0; BUFFER]];
+        for block in 0..40 {
+            let input = |channel: usize| {
+                let mut samples = vec![0.0; BUFFER];
+                if block == 0 {
+                    samples[channel * 7] = 1.0;
+                }
+                samples
+            };
+            let (mut a_left, mut a_right) = (input(0), input(1));
+            internal.process(&mut [&mut a_left, &mut a_right]);
+
+            let (mut b_left, mut b_right) = (input(0), input(1));
+            let [mut return_left, mut return_right] = cable.clone();
+            let [send_left, send_right] = &mut cable;
+            external.process_with_external_loop(
+                &mut [&mut b_left, &mut b_right],
+                &mut [send_left, send_right],
+                &[&mut return_left, &mut return_right],
+                None,
+            );
+
+            for (a, b) in a_left
+                .iter()
+                .chain(&a_right)
+                .zip(b_left.iter().chain(&b_right))
+            {
+                assert!((a - b).abs() < 1e-5, "block {block}: {a} vs {b}");
+            }
+        }
+    }
+
+    /// Without a send and return for every channel, or with the loop
+    /// switched off, the internal loop runs and the send stays silent.
+    #[test]
+    fn test_external_loop_fallback() {
+        let render = |external_loop: bool, connected: bool| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.6,
+                external_loop,
+                ..test_params()
+            });
+            engine.set_external_loop_latency(BLOCK);
+            let (mut send, mut returned) = (vec![1.0; BLOCK], vec![0.5; BLOCK]);
+            let mut output = Vec::new();
+            for block in 0..20 {
+                let (mut left, mut right) = (input_block(block, 0), input_block(block, 1));
+                let mut sends: Vec<&mut [f32]> = vec![&mut send];
+                let returns: Vec<&mut [f32]> = vec![&mut returned];
+                let (sends, returns) = if connected {
+                    (&mut sends[..], &returns[..])
+                } else {
+                    (&mut sends[..0], &returns[..0])
+                };
+                engine.process_with_external_loop(
+                    &mut [&mut left, &mut right],
+                    sends,
+                    returns,
+                    None,
+                );
+                output.extend(left);
+                output.extend(right);
+            }
+            (output, send)
+        };
+
+        let internal = {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.6,
+                ..test_params()
+            });
+            let mut output = Vec::new();
+            for block in 0..20 {
+                let (mut left, mut right) = (input_block(block, 0), input_block(block, 1));
+                engine.process(&mut [&mut left, &mut right]);
+                output.extend(left);
+                output.extend(right);
+            }
+            output
+        };
+
+        // Only one of two channels connected: not enough to loop.
+        let (output, _) = render(true, true);
+        assert_eq!(output, internal);
+        let (output, _) = render(true, false);
+        assert_eq!(output, internal);
+        let (output, send) = render(false, true);
+        assert_eq!(output, internal);
+        assert!(send.iter().all(|&s| s == 0.0));
+    }
+
+    /// Per-repeat cutoff steps: each of the first four repeats sounds
+    /// just like it would through a fixed high-cut at its step's corner,
+    /// and the fifth comes round to the first step again.
+    #[test]
+    fn test_steps_per_repeat() {
+        const DELAY: usize = 4800; // 100ms
+        const ONSET: usize = 1000;
+        let steps = [0.2, 0.9, 0.4, 0.6, 0.0, 0.0, 0.0, 0.0];
+        let params = DelayParams {
+            feedback: 0.8,
+            mix: 1.0,
+            step_length: 4,
+            steps,
+            ..test_params()
+        };
+        let render = |params: &DelayParams| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(params);
+            let mut output = vec![0.0; ONSET + DELAY * 6];
+            output[ONSET] = 1.0;
+            engine.process(&mut [&mut output]);
+            output
+        };
+
+        // How much of a signal's energy is in its sample-to-sample
+        // differences: higher for a brighter sound.
+        let brightness = |output: &[f32], k: usize| {
+            let samples = &output[ONSET + DELAY * k..ONSET + DELAY * (k + 1)];
+            let energy: f32 = samples.iter().map(|s| s * s).sum();
+            let diffs: f32 = samples.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+            diffs / energy
+        };
+
+        let stepped = render(&DelayParams {
+            step_target: StepTarget::Cutoff,
+            ..params
+        });
+
+        // The sequencer starts with the engine, so the first repeat lands
+        // on step 1 (the second value), and the fifth on step 1 again.
+        for k in 1..=5 {
+            let fixed = render(&DelayParams {
+                out_highcut: step_to_cutoff(steps[k % 4]),
+                ..params
+            });
+            let got = brightness(&stepped, k);
+            let expected = brightness(&fixed, k);
+            assert!(
+                (got / expected - 1.0).abs() < 0.01,
+                "repeat {k}: brightness {got}, expected {expected}"
+            );
+        }
+
+        // And the steps really differ: the darkest step is far darker
+        // than the brightest.
+        assert!(brightness(&stepped, 4) < brightness(&stepped, 1) * 0.1);
+    }
+
+    /// A synced step pattern follows the song position when sync starts,
+    /// and lines up again after the transport stops and restarts.
+    #[test]
+    fn test_steps_follow_the_song() {
+        let params = DelayParams {
+            sync: true,
+            division: NoteDivision::Quarter,
+            step_target: StepTarget::Cutoff,
+            ..test_params()
+        };
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            pos_beats: Some(6.5),
+            ..Transport::default()
+        });
+        engine.set_params(&params);
+        assert_eq!(engine.sequencer.step(), 2);
+
+        // Half a beat (12000 samples at 120 BPM) to the next step.
+        let mut samples = vec![0.0; 12000];
+        engine.process(&mut [&mut samples]);
+        assert_eq!(engine.sequencer.step(), 3);
+
+        engine.reset();
+        assert_eq!(engine.sequencer.step(), 0);
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            pos_beats: Some(9.0),
+            ..Transport::default()
+        });
+        engine.set_params(&params);
+        assert_eq!(engine.sequencer.step(), 1);
+    }
+
+    /// The mono fold: the two cutoffs meet at their geometric mean,
+    /// mono-safe and motion switch off, and nothing else changes.
+    #[test]
+    fn test_folded_to_mono() {
+        let stereo = DelayParams {
+            filter_cutoff: 1000.0,
+            filter_cutoff_right: 4000.0,
+            mono_safe: true,
+            motion: StereoMotion::Circular,
+            ..DelayParams::default()
+        };
+        let mono = stereo.folded_to_mono();
+        assert!((mono.filter_cutoff - 2000.0).abs() < 1e-3);
+        assert_eq!(mono.filter_cutoff_right, mono.filter_cutoff);
+        assert!(!mono.mono_safe);
+        assert_eq!(mono.motion, StereoMotion::Off);
+        assert_eq!(
+            DelayParams {
+                filter_cutoff: stereo.filter_cutoff,
+                filter_cutoff_right: stereo.filter_cutoff_right,
+                mono_safe: stereo.mono_safe,
+                motion: stereo.motion,
+                ..mono
+            },
+            stereo
+        );
+
+        // Linked cutoffs and an already-mono setup fold to themselves.
+        assert_eq!(
+            DelayParams::default().folded_to_mono(),
+            DelayParams::default()
+        );
+    }
+
+    /// A mono engine sounds exactly like one given the folded settings,
+    /// so an unlinked Filter R, Mono-Safe and Motion are audible in a
+    /// defined way rather than silently dropped.
+    #[test]
+    fn test_mono_engine_runs_the_fold() {
+        let stereo = DelayParams {
+            feedback: 0.7,
+            filter_cutoff: 500.0,
+            filter_cutoff_right: 12000.0,
+            mono_safe: true,
+            motion: StereoMotion::Circular,
+            ..test_params()
+        };
+        let render = |params: &DelayParams| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(params);
+            let mut output = Vec::new();
+            for block in 0..40 {
+                let mut samples = input_block(block, 0);
+                engine.process(&mut [&mut samples]);
+                output.extend(samples);
+            }
+            output
+        };
+
+        let mono = render(&stereo);
+        assert_eq!(mono, render(&stereo.folded_to_mono()));
+
+        // Filter R is heard: the fold isn't just the left filter.
+        let left_only = render(&DelayParams {
+            filter_cutoff_right: 500.0,
+            ..stereo
+        });
+        assert_ne!(mono, left_only);
+    }
+
+    /// An impulse on the left: each echo's stereo position follows the
+    /// motion pattern. Ping-pong alternates sides (reverse starts on
+    /// the right), and circular follows the rotor one rotation per
+    /// repeat.
+    #[test]
+    fn test_stereo_motion_patterns() {
+        const DELAY: usize = 4800;
+        const ECHOES: usize = 6;
+
+        // Where each echo sits, from −1 (left) to 1 (right): the energy
+        // balance between the sides over that echo's period.
+        let positions = |motion, rotation| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                delay_ms: DELAY as f32 * 1000.0 / SAMPLE_RATE,
+                feedback: 0.6,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                filter_cutoff_right: 20000.0,
+                motion,
+                motion_rotation: rotation,
+                ..DelayParams::default()
+            });
+            let mut left = vec![0.0; DELAY * (ECHOES + 1)];
+            let mut right = vec![0.0; left.len()];
+            left[0] = 1.0;
+            engine.process(&mut [&mut left, &mut right]);
+
+            let energy = |samples: &[f32], k: usize| {
+                samples[k * DELAY..(k + 1) * DELAY]
+                    .iter()
+                    .map(|s| s * s)
+                    .sum::<f32>()
+            };
+            (1..=ECHOES)
+                .map(|k| {
+                    let (l, r) = (energy(&left, k), energy(&right, k));
+                    assert!(l + r > 1e-4, "{motion:?}: echo {k} is missing");
+                    (r - l) / (r + l)
+                })
+                .collect::<Vec<f32>>()
+        };
+        let check = |motion, rotation, expected: [f32; ECHOES]| {
+            let got = positions(motion, rotation);
+            for (k, (got, want)) in got.iter().zip(expected).enumerate() {
+                assert!(
+                    (got - want).abs() < 0.05,
+                    "{motion:?} {rotation}°, echo {}: at {got}, expected {want}",
+                    k + 1
+                );
+            }
+        };
+
+        check(StereoMotion::Off, 90.0, [-1.0; ECHOES]);
+        check(
+            StereoMotion::PingPong,
+            90.0,
+            [-1.0, 1.0, -1.0, 1.0, -1.0, 1.0],
+        );
+        check(
+            StereoMotion::ReversePingPong,
+            90.0,
+            [1.0, -1.0, 1.0, -1.0, 1.0, -1.0],
+        );
+
+        // A rotor at pan p puts sin(p·π/2) of the balance to the right.
+        for rotation in [90.0_f32, 45.0] {
+            let expected = std::array::from_fn(|k| {
+                let pan = ((k + 1) as f32 * rotation).to_radians().sin();
+                (pan * std::f32::consts::FRAC_PI_2).sin()
+            });
+            check(StereoMotion::Circular, rotation, expected);
+        }
+    }
+
+    /// An impulse through ping-pong: the first three repeats' levels on
+    /// each side, for every start side, both ways round. A centered
+    /// start hears the first repeat equally on both sides at constant
+    /// power, then alternates exactly like the side it entered on.
+    #[test]
+    fn test_ping_pong_start_sides() {
+        const DELAY: usize = 4800;
+
+        // The level (root of the energy over the echo's period) of the
+        // first three repeats, `[left, right]` each.
+        let repeats = |motion, start_side| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                delay_ms: DELAY as f32 * 1000.0 / SAMPLE_RATE,
+                feedback: 0.6,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                filter_cutoff_right: 20000.0,
+                motion,
+                start_side,
+                ..DelayParams::default()
+            });
+            let mut left = vec![0.0; DELAY * 4];
+            let mut right = vec![0.0; left.len()];
+            left[0] = 1.0;
+            engine.process(&mut [&mut left, &mut right]);
+
+            let level = |samples: &[f32], k: usize| {
+                let period = &samples[k * DELAY..(k + 1) * DELAY];
+                period.iter().map(|s| s * s).sum::<f32>().sqrt()
+            };
+            [1, 2, 3].map(|k| [level(&left, k), level(&right, k)])
+        };
+
+        // The shaped repeats' levels, from a plain left start.
+        let sided = repeats(StereoMotion::PingPong, StartSide::Left);
+        let (one, two, three) = (sided[0][0], sided[1][1], sided[2][0]);
+        // The impulse's mono sum is 0.5, and the first repeat is
+        // unshaped.
+        assert!((one - 0.5).abs() < 1e-6, "{one}");
+        let mid = one * std::f32::consts::FRAC_1_SQRT_2;
+
+        use StartSide::{Center, Left, Right};
+        let (ping_pong, reverse) = (StereoMotion::PingPong, StereoMotion::ReversePingPong);
+        let cases = [
+            (ping_pong, Left, [[one, 0.0], [0.0, two], [three, 0.0]]),
+            (ping_pong, Right, [[0.0, one], [two, 0.0], [0.0, three]]),
+            (ping_pong, Center, [[mid, mid], [0.0, two], [three, 0.0]]),
+            (reverse, Left, [[0.0, one], [two, 0.0], [0.0, three]]),
+            (reverse, Right, [[one, 0.0], [0.0, two], [three, 0.0]]),
+            (reverse, Center, [[mid, mid], [two, 0.0], [0.0, three]]),
+        ];
+        for (motion, start_side, expected) in cases {
+            let got = repeats(motion, start_side);
+            for (k, (got, want)) in got.iter().zip(expected).enumerate() {
+                for side in 0..2 {
+                    assert!(
+                        (got[side] - want[side]).abs() < 1e-4,
+                        "{motion:?} from {start_side:?}, repeat {}: {got:?}, expected {want:?}",
+                        k + 1
+                    );
+                }
+            }
+        }
+    }
+
+    /// Dry Pan hard left moves the whole dry signal to the left: the
+    /// right channel carries only its own repeats, and the left gets
+    /// both dry channels on top of its repeats.
+    #[test]
+    fn test_dry_pan_hard_left_leaves_right_wet_only() {
+        const MIX: f32 = 0.5;
+        const BLOCKS: usize = 5;
+        let input = |channel| -> Vec<f32> {
+            (0..BLOCKS)
+                .flat_map(|block_idx| input_block(block_idx, channel))
+                .collect()
+        };
+        let run = |dry_pan: f32| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.3,
+                mix: MIX,
+                dry_pan,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            let (mut left, mut right) = (input(0), input(1));
+            for (left, right) in left.chunks_mut(BLOCK).zip(right.chunks_mut(BLOCK)) {
+                engine.process(&mut [left, right]);
+            }
+            (left, right)
+        };
+
+        let (dry_left, dry_right) = (input(0), input(1));
+        let (centre_left, centre_right) = run(0.0);
+        let (panned_left, panned_right) = run(-1.0);
+
+        // Nothing on the right until the first echo, 100 ms in.
+        assert!(panned_right[..4800].iter().all(|s| *s == 0.0));
+        assert!(panned_right[4800..].iter().any(|s| s.abs() > 0.1));
+        for i in 0..BLOCKS * BLOCK {
+            let wet_right = centre_right[i] - dry_right[i] * (1.0 - MIX);
+            assert!((panned_right[i] - wet_right).abs() < 1e-6, "sample {i}");
+            let wet_left = centre_left[i] - dry_left[i] * (1.0 - MIX);
+            let want_left = wet_left + (dry_left[i] + dry_right[i]) * (1.0 - MIX);
+            assert!((panned_left[i] - want_left).abs() < 1e-6, "sample {i}");
+        }
+    }
+
+    /// The input balance scales each side's repeats by its balance gain,
+    /// keeping the total power, and leaves the dry signal alone.
+    #[test]
+    fn test_input_balance_trims_only_the_repeats() {
+        const MIX: f32 = 0.5;
+        const BLOCKS: usize = 5;
+        let input = |channel| -> Vec<f32> {
+            (0..BLOCKS)
+                .flat_map(|block_idx| input_block(block_idx, channel))
+                .collect()
+        };
+        let run = |input_balance: f32| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.3,
+                mix: MIX,
+                input_balance,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            let (mut left, mut right) = (input(0), input(1));
+            for (left, right) in left.chunks_mut(BLOCK).zip(right.chunks_mut(BLOCK)) {
+                engine.process(&mut [left, right]);
+            }
+            (left, right)
+        };
+
+        let dry = [input(0), input(1)];
+        let centre = run(0.0);
+        let balanced = run(0.5);
+        let gains = balance_gains(0.5);
+        assert!((gains[0].powi(2) + gains[1].powi(2) - 2.0).abs() < 1e-6);
+
+        // Nothing but dry until the first echo, 100 ms in.
+        assert_eq!(balanced.0[..4800], centre.0[..4800]);
+        assert_eq!(balanced.1[..4800], centre.1[..4800]);
+        for (side, (centre, balanced)) in [(&centre.0, &balanced.0), (&centre.1, &balanced.1)]
+            .into_iter()
+            .enumerate()
+        {
+            for i in 4800..BLOCKS * BLOCK {
+                let dry = dry[side][i] * (1.0 - MIX);
+                let want = (centre[i] - dry) * gains[side];
+                assert!(
+                    (balanced[i] - dry - want).abs() < 1e-5,
+                    "side {side}, sample {i}"
+                );
+            }
+        }
+    }
+
+    /// Swapping the channels mid-tail moves the repeats to the other
+    /// side without a click: through the crossfade the output never
+    /// jumps further from one sample to the next than the tone itself
+    /// does, and afterwards the left repeats play on the right.
+    #[test]
+    fn test_swap_channels_mid_tail_is_click_free() {
+        const BLOCKS: usize = 6;
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let params = DelayParams {
+            feedback: 0.7,
+            mix: 1.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        skip_engage_fade(&mut engine);
+
+        // A tone on the left only, whose echoes build up block by block.
+        let mut out = [Vec::new(), Vec::new()];
+        for block_idx in 0..BLOCKS {
+            if block_idx == 4 {
+                engine.set_params(&DelayParams {
+                    swap_channels: true,
+                    ..params
+                });
+            }
+            let mut left = input_block(block_idx, 0);
+            let mut right = vec![0.0; BLOCK];
+            engine.process(&mut [&mut left, &mut right]);
+            out[0].extend(left);
+            out[1].extend(right);
+        }
+
+        let largest_step = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f32::max)
+        };
+        let swap = 4 * BLOCK;
+        let fade = (SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let before = largest_step(&out[0][swap - fade..swap]);
+        assert!(before > 0.01, "{before}");
+        for side in &out {
+            let across = largest_step(&side[swap - 1..swap + fade + 1]);
+            assert!(across < 1.2 * before, "{across} vs {before}");
+        }
+
+        // Before the swap the repeats are all on the left; after it,
+        // all on the right.
+        assert!(out[1][..swap].iter().all(|s| *s == 0.0));
+        assert!(out[0][swap + fade..].iter().all(|s| *s == 0.0));
+        assert!(largest_step(&out[1][swap + fade..]) > 0.5 * before);
+    }
+
+    /// Turning stereo motion on mid-tail fades the pair's routing over
+    /// rather than rewiring it between two samples: through the fade
+    /// the output never jumps further than the tone itself does.
+    #[test]
+    fn test_motion_change_mid_tail_is_click_free() {
+        const BLOCKS: usize = 6;
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let params = DelayParams {
+            feedback: 0.7,
+            mix: 1.0,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        skip_engage_fade(&mut engine);
+
+        let mut out = Vec::new();
+        for block_idx in 0..BLOCKS {
+            if block_idx == 4 {
+                engine.set_params(&DelayParams {
+                    motion: StereoMotion::Circular,
+                    ..params
+                });
+            }
+            let mut left = input_block(block_idx, 0);
+            let mut right = vec![0.0; BLOCK];
+            engine.process(&mut [&mut left, &mut right]);
+            out.extend(left);
+        }
+
+        let largest_step = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f32::max)
+        };
+        let switch = 4 * BLOCK;
+        let fade = (SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let before = largest_step(&out[switch - fade..switch]);
+        assert!(before > 0.01, "{before}");
+        let across = largest_step(&out[switch - 1..switch + fade + 1]);
+        assert!(across < 1.2 * before, "{across} vs {before}");
+    }
+
+    /// Side decay takes its setting off the side (against the mid) on
+    /// every pass: at 6 dB the fifth repeat is 24 dB narrower than the
+    /// first, whether each side repeats its own input or ping-pong
+    /// crosses them. At 0 dB the width doesn't change.
+    #[test]
+    fn test_side_decay_narrows_each_repeat() {
+        let period = (100.0 / 1000.0 * SAMPLE_RATE) as usize;
+        let burst = period / 5;
+        let widths_db = |motion: StereoMotion, side_decay_db: f32| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.8,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                filter_cutoff_right: 20000.0,
+                motion,
+                side_decay_db,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+
+            // A burst much wider on the left than on the right.
+            let mut noise = crate::dsp::noise::NoiseGenerator::new(5);
+            let mut left: Vec<f32> = (0..6 * period)
+                .map(|n| if n < burst { 0.5 * noise.next() } else { 0.0 })
+                .collect();
+            let mut right: Vec<f32> = left.iter().map(|s| 0.3 * s).collect();
+            for (left, right) in left.chunks_mut(BLOCK).zip(right.chunks_mut(BLOCK)) {
+                engine.process(&mut [left, right]);
+            }
+
+            // Each repeat's side against its mid, in dB.
+            (1..=5)
+                .map(|repeat| {
+                    let window = repeat * period..repeat * period + 2 * burst;
+                    let (mut mid, mut side) = (0.0, 0.0);
+                    for (l, r) in left[window.clone()].iter().zip(&right[window]) {
+                        mid += (0.5 * (l + r)).powi(2);
+                        side += (0.5 * (l - r)).powi(2);
+                    }
+                    10.0 * (side / mid).log10()
+                })
+                .collect::<Vec<f32>>()
+        };
+
+        for motion in [StereoMotion::Off, StereoMotion::PingPong] {
+            for side_decay_db in [0.0, 6.0] {
+                let widths = widths_db(motion, side_decay_db);
+                let narrowed = widths[0] - widths[4];
+                assert!(
+                    (narrowed - 4.0 * side_decay_db).abs() < 0.25,
+                    "{motion:?}, {side_decay_db} dB: {widths:?}"
+                );
+            }
+        }
+    }
+
+    /// At full spread, a centred impulse's first four repeats alternate
+    /// sides and widen: each one's left/right ratio is that of its pan,
+    /// −0.25, +0.5, −0.75 and +1.0.
+    #[test]
+    fn test_spread_pans_first_four_repeats() {
+        const PERIOD: usize = 4800;
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(&DelayParams {
+            feedback: 0.5,
+            mix: 1.0,
+            spread: 1.0,
+            ..test_params()
+        });
+        skip_engage_fade(&mut engine);
+        let mut left = vec![0.0; 5 * PERIOD];
+        left[0] = 1.0;
+        let mut right = left.clone();
+        for (left, right) in left.chunks_mut(BLOCK).zip(right.chunks_mut(BLOCK)) {
+            engine.process(&mut [left, right]);
+        }
+
+        // Each repeat's energy, smeared a little by the feedback filter.
+        let level = |samples: &[f32], repeat: usize| -> f32 {
+            let start = repeat * PERIOD - 100;
+            samples[start..start + 600].iter().map(|s| s.abs()).sum()
+        };
+        // A centred pair panned by `pan` (see `pan_stereo_pair()`).
+        let ratio = |pan: f32| {
+            let [l, r] = pan_stereo_pair(pan, [1.0, 1.0]);
+            l / r
+        };
+        for (repeat, pan) in [(1, -0.25), (2, 0.5), (3, -0.75)] {
+            let heard = level(&left, repeat) / level(&right, repeat);
+            assert!(
+                (heard - ratio(pan)).abs() < 1e-3 * ratio(pan),
+                "repeat {repeat}: L/R {heard}, want {}",
+                ratio(pan)
+            );
+        }
+        // Hard right: nothing left on the left at all.
+        assert!(level(&right, 4) > 0.01);
+        assert!(level(&left, 4) < 1e-6 * level(&right, 4));
+        assert!((ratio(-0.25) - 1.4966).abs() < 1e-3);
+    }
+
+    /// At 0% the repeats come out bit for bit as they do without the
+    /// knob, and turning it up later leaves the echoes already playing
+    /// where they were.
+    #[test]
+    fn test_spread_off_is_untouched() {
+        const BLOCKS: usize = 6;
+        let run = |spread_from: Option<usize>| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.6,
+                ..test_params()
+            });
+            let mut out = Vec::new();
+            for block_idx in 0..BLOCKS {
+                if spread_from == Some(block_idx) {
+                    engine.set_params(&DelayParams {
+                        feedback: 0.6,
+                        spread: 1.0,
+                        ..test_params()
+                    });
+                }
+                let (mut left, mut right) = (input_block(block_idx, 0), input_block(block_idx, 1));
+                if block_idx >= 3 {
+                    left.fill(0.0);
+                    right.fill(0.0);
+                }
+                engine.process(&mut [&mut left, &mut right]);
+                out.push((left, right));
+            }
+            out
+        };
+
+        let plain = run(None);
+        assert_eq!(run(Some(BLOCKS)), plain);
+
+        // Spread switched on once the input has stopped: everything in
+        // the lines was written untracked, so it plays on unpanned.
+        let late = run(Some(3));
+        for (block_idx, (late, plain)) in late.iter().zip(&plain).enumerate() {
+            for (late, plain) in [(&late.0, &plain.0), (&late.1, &plain.1)] {
+                for (i, (late, plain)) in late.iter().zip(plain.iter()).enumerate() {
+                    assert!((late - plain).abs() < 1e-5, "block {block_idx}, sample {i}");
+                }
+            }
+        }
+    }
+
+    /// With Alternate on, an impulse's odd repeats come out where they
+    /// would without it and its even repeats with the sides exchanged,
+    /// sample for sample; each repeat is as loud in all as without it,
+    /// so the loop decays exactly as it did.
+    #[test]
+    fn test_alternate_swaps_every_other_repeat() {
+        const PERIOD: usize = 4800;
+        let run = |alternate: bool| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.5,
+                mix: 1.0,
+                alternate,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            // Different on each side, so a swap can't hide.
+            let mut left = vec![0.0; 7 * PERIOD];
+            let mut right = left.clone();
+            left[0] = 1.0;
+            right[0] = -0.5;
+            for (left, right) in left.chunks_mut(BLOCK).zip(right.chunks_mut(BLOCK)) {
+                engine.process(&mut [left, right]);
+            }
+            [left, right]
+        };
+        let plain = run(false);
+        let alternating = run(true);
+
+        let energy = |samples: &[f32]| samples.iter().map(|s| s * s).sum::<f32>();
+        for repeat in 1..=6 {
+            // Each repeat, smeared a little by the feedback filter.
+            let window = repeat * PERIOD - 100..repeat * PERIOD + 500;
+            let heard = [0, 1].map(|side| &alternating[side][window.clone()]);
+            let want = if repeat % 2 == 1 { [0, 1] } else { [1, 0] }
+                .map(|side| &plain[side][window.clone()]);
+            for side in 0..2 {
+                for (i, (heard, want)) in heard[side].iter().zip(want[side]).enumerate() {
+                    assert!(
+                        (heard - want).abs() < 1e-6,
+                        "repeat {repeat}, side {side}, sample {i}: {heard} vs {want}"
+                    );
+                }
+            }
+            let total = |pair: [&[f32]; 2]| energy(pair[0]) + energy(pair[1]);
+            assert!(total(want) > 1e-6, "repeat {repeat}");
+            assert!((total(heard) - total(want)).abs() < 1e-4 * total(want));
+        }
+    }
+
+    /// Repeat boundaries landing mid-note don't click: with a tone held
+    /// across its own first repeat, the alternating output never jumps
+    /// much further from one sample to the next than the plain one does,
+    /// and the right side, silent without Alternate, hears the repeats.
+    #[test]
+    fn test_alternate_mid_note_is_click_free() {
+        const BLOCKS: usize = 8;
+        let run = |alternate: bool| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                feedback: 0.7,
+                mix: 1.0,
+                alternate,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            // A note on the left, longer than the delay.
+            let mut out = [Vec::new(), Vec::new()];
+            for block_idx in 0..BLOCKS {
+                let mut left = input_block(block_idx, 0);
+                if block_idx >= 3 {
+                    left.fill(0.0);
+                }
+                let mut right = vec![0.0; BLOCK];
+                engine.process(&mut [&mut left, &mut right]);
+                out[0].extend(left);
+                out[1].extend(right);
+            }
+            out
+        };
+        let plain = run(false);
+        let alternating = run(true);
+
+        let largest_step = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0, f32::max)
+        };
+        let plain_step = largest_step(&plain[0]);
+        assert!(plain[1].iter().all(|s| *s == 0.0));
+        for side in &alternating {
+            let step = largest_step(side);
+            assert!(step < 1.2 * plain_step, "{step} vs {plain_step}");
+        }
+        assert!(largest_step(&alternating[1]) > 0.1 * plain_step);
+    }
+
+    /// With the key gate on, only the burst played while the key is loud
+    /// gets into the delay: the other bursts' echo windows are silent,
+    /// while the keyed burst echoes as usual. The dry signal isn't gated.
+    #[test]
+    fn test_key_gate_echoes_only_the_keyed_burst() {
+        const LENGTH: usize = SAMPLE_RATE as usize;
+        let ms = |ms: usize| ms * SAMPLE_RATE as usize / 1000;
+        let tone = |n: usize| 0.5 * (std::f32::consts::TAU * 440.0 * n as f32 / SAMPLE_RATE).sin();
+        // Bursts at 0, 150 and 400 ms; the key sounds with the middle one.
+        let bursts = [ms(0)..ms(50), ms(150)..ms(200), ms(400)..ms(450)];
+        let input: Vec<f32> = (0..LENGTH)
+            .map(|n| {
+                if bursts.iter().any(|burst| burst.contains(&n)) {
+                    tone(n)
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        // The key is a snare-like 1 kHz, above the detector's highpass.
+        let key: Vec<f32> = (0..LENGTH)
+            .map(|n| {
+                if bursts[1].contains(&n) {
+                    0.8 * (std::f32::consts::TAU * 1000.0 * n as f32 / SAMPLE_RATE).sin()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        let run = |key_gate: bool, mix: f32| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            engine.set_params(&DelayParams {
+                delay_ms: 500.0,
+                feedback: 0.0,
+                mix,
+                key_gate,
+                key_release_ms: 10.0,
+                ..DelayParams::default()
+            });
+            skip_engage_fade(&mut engine);
+            let (mut left, mut right) = (input.clone(), input.clone());
+            let (mut key_left, mut key_right) = (key.clone(), key.clone());
+            for (((left, right), key_left), key_right) in left
+                .chunks_mut(BLOCK)
+                .zip(right.chunks_mut(BLOCK))
+                .zip(key_left.chunks_mut(BLOCK))
+                .zip(key_right.chunks_mut(BLOCK))
+            {
+                engine.process_with_key(&mut [left, right], Some(&[key_left, key_right]));
+            }
+            left
+        };
+
+        let loud = |samples: &[f32]| samples.iter().any(|s| s.abs() > 0.1);
+        let ungated = run(false, 1.0);
+        let gated = run(true, 1.0);
+        for burst in &bursts {
+            assert!(loud(&ungated[burst.start + ms(500)..burst.end + ms(500)]));
+        }
+        // Only the middle burst's echo, 650–700 ms, comes back.
+        let keyed_echo = bursts[1].start + ms(500)..bursts[1].end + ms(500);
+        assert!(loud(&gated[keyed_echo.clone()]));
+        for (n, sample) in gated.iter().enumerate() {
+            assert!(
+                keyed_echo.contains(&n) || *sample == 0.0,
+                "sample {n} leaked through: {sample}"
+            );
+        }
+
+        // Fully dry, the gate has nothing to act on.
+        assert_eq!(run(true, 0.0), input);
+    }
+
+    /// Key Listen puts the key, through the detector's highpass, on
+    /// every channel in place of the output, gate or no gate. Switched
+    /// off again, the output is what it would have been had it never
+    /// been on: the delay ran on underneath.
+    #[test]
+    fn test_duck_listen_replaces_the_output() {
+        const BLOCKS: usize = 30;
+        let fade = (SWITCH_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+        let listening = 10 * BLOCK..20 * BLOCK;
+        let input: Vec<f32> = (0..BLOCKS * BLOCK)
+            .map(|n| 0.5 * (std::f32::consts::TAU * 440.0 * n as f32 / SAMPLE_RATE).sin())
+            .collect();
+        // A snare-like 1 kHz over a kick-like 40 Hz.
+        let key: Vec<f32> = (0..BLOCKS * BLOCK)
+            .map(|n| {
+                let t = std::f32::consts::TAU * n as f32 / SAMPLE_RATE;
+                0.4 * (1000.0 * t).sin() + 0.4 * (40.0 * t).sin()
+            })
+            .collect();
+
+        let run = |listen: bool| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+            skip_engage_fade(&mut engine);
+            let (mut left, mut right) = (input.clone(), input.clone());
+            let (mut key_left, mut key_right) = (key.clone(), key.clone());
+            for (block_idx, (((left, right), key_left), key_right)) in left
+                .chunks_mut(BLOCK)
+                .zip(right.chunks_mut(BLOCK))
+                .zip(key_left.chunks_mut(BLOCK))
+                .zip(key_right.chunks_mut(BLOCK))
+                .enumerate()
+            {
+                engine.set_params(&DelayParams {
+                    duck_listen: listen && (10..20).contains(&block_idx),
+                    ..test_params()
+                });
+                engine.process_with_key(&mut [left, right], Some(&[key_left, key_right]));
+            }
+            (left, right)
+        };
+        let (left, right) = run(true);
+        let (unheard, _) = run(false);
+
+        let mut detector = KeyGate::new(SAMPLE_RATE);
+        let heard: Vec<f32> = key[listening.clone()]
+            .iter()
+            .map(|&key| detector.listen(key))
+            .collect();
+        for n in listening.start + fade..listening.end {
+            let want = heard[n - listening.start];
+            assert!((left[n] - want).abs() < 1e-6, "left {n}: {}", left[n]);
+            assert!((right[n] - want).abs() < 1e-6, "right {n}: {}", right[n]);
+        }
+        // The kick's 40 Hz is mostly gone from what's heard.
+        let peak = heard[fade..]
+            .iter()
+            .fold(0.0_f32, |peak, s| peak.max(s.abs()));
+        assert!(peak < 0.55, "heard peak {peak}");
+
+        assert_eq!(left[..listening.start], unheard[..listening.start]);
+        assert_eq!(
+            left[listening.end + fade..],
+            unheard[listening.end + fade..]
+        );
+    }
+
+    /// Bloom holds an impulse's first repeat down and lets the third
+    /// swell back: next to the first, the third comes out the bloom
+    /// amount louder than without bloom — with the feedback high, louder
+    /// outright. From the third repeat on the two renders match exactly:
+    /// the loop itself never heard the bloom.
+    #[test]
+    fn test_bloom_swells_the_third_repeat() {
+        const PERIOD: usize = 4800;
+        let render = |bloom_db: f32| {
+            let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+            engine.set_params(&DelayParams {
+                feedback: 0.9,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                bloom_db,
+                ..test_params()
+            });
+            skip_engage_fade(&mut engine);
+            let mut samples = vec![0.0; 5 * PERIOD];
+            samples[0] = 1.0;
+            engine.process(&mut [&mut samples]);
+            samples
+        };
+        let plain = render(0.0);
+        let bloomed = render(12.0);
+
+        let db = |sample: f32| 20.0 * sample.abs().log10();
+        let rise = |out: &[f32]| db(out[3 * PERIOD]) - db(out[PERIOD]);
+        let bloom = rise(&bloomed) - rise(&plain);
+        assert!((bloom - 12.0).abs() < 0.01, "{bloom} dB");
+        assert!(bloomed[3 * PERIOD].abs() > bloomed[PERIOD].abs());
+        assert_eq!(bloomed[3 * PERIOD..], plain[3 * PERIOD..]);
+    }
+
+    /// A mono input's repeats come out identical on both sides at 0%,
+    /// and less alike the further Decorrelate is turned up, following
+    /// `(1 − a²) / (1 + a²)` down to uncorrelated at 100%.
+    #[test]
+    fn test_decorrelate_widens_mono_repeats() {
+        use crate::dsp::noise::NoiseGenerator;
+
+        let correlation = |decorrelate: f32| {
+            let mut engine = test_engine();
+            engine.set_params(&DelayParams {
+                feedback: 0.3,
+                mix: 1.0,
+                decorrelate,
+                ..test_params()
+            });
+            let mut noise = NoiseGenerator::new(11);
+            let mut left: Vec<f32> = (0..SAMPLE_RATE as usize).map(|_| noise.next()).collect();
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+
+            // Skip the silence before the first echo.
+            let (left, right) = (&left[4800..], &right[4800..]);
+            let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+            dot(left, right) / (dot(left, left) * dot(right, right)).sqrt()
+        };
+
+        let amounts = [0.0, 0.25, 0.5, 0.75, 1.0];
+        let correlations = amounts.map(correlation);
+        assert!(correlations[0] > 0.9999, "{correlations:?}");
+        for (a, got) in amounts.iter().zip(correlations) {
+            let want = (1.0 - a * a) / (1.0 + a * a);
+            assert!((got - want).abs() < 0.05, "{a}: {got}, expected {want}");
+        }
+        assert!(
+            correlations.windows(2).all(|pair| pair[1] < pair[0]),
+            "{correlations:?}"
+        );
+    }
+
+    /// After `reset()` (and on a new engine) the effect fades in: the
+    /// output starts as the input passing straight through and reaches
+    /// the steady mix within [`ENGAGE_FADE_MS`], never further from it
+    /// than the fade allows.
+    #[test]
+    fn test_engage_fades_in() {
+        let params = DelayParams {
+            mix: 0.8,
+            ..test_params()
+        };
+        let fade_len = (ENGAGE_FADE_MS / 1000.0 * SAMPLE_RATE) as usize;
+
+        // A loud tone into an engine that's been playing, then reset.
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        engine.set_params(&params);
+        engine.process(&mut [&mut input_block(0, 0)]);
+        engine.reset();
+        let input = input_block(1, 0);
+        let mut engaged = input.clone();
+        engine.process(&mut [&mut engaged]);
+
+        // The same block through an engine already at its steady mix.
+        let mut steady = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        steady.set_params(&params);
+        skip_engage_fade(&mut steady);
+        let mut settled = input.clone();
+        steady.process(&mut [&mut settled]);
+
+        for (n, ((engaged, settled), input)) in engaged.iter().zip(&settled).zip(&input).enumerate()
+        {
+            let remaining = 1.0 - (n as f32 / fade_len as f32).min(1.0);
+            let allowed = remaining * params.mix * input.abs() + 1e-6;
+            assert!(
+                (engaged - settled).abs() <= allowed,
+                "sample {n}: {engaged} vs {settled}, allowed {allowed}"
+            );
+        }
+        assert!(
+            (engaged[0] - input[0]).abs() <= params.mix * input[0].abs() / fade_len as f32 + 1e-6
+        );
+        assert_eq!(engaged[fade_len..], settled[fade_len..]);
+
+        // A brand-new engine fades in the same way.
+        let mut fresh = LovelessDelayEngine::new(SAMPLE_RATE, 1);
+        fresh.set_params(&params);
+        let mut first = input.clone();
+        fresh.process(&mut [&mut first]);
+        assert_eq!(first, engaged);
+    }
+
+    /// `reset()` silences the echoes and finishes any ramp in progress.
+    #[test]
+    fn test_reset_clears_audio_and_ramps() {
+        let mut engine = test_engine();
+        let mut block = vec![1.0; 1024];
+        engine.process(&mut [&mut block]);
+        engine.set_params(&DelayParams {
+            mix: 1.0,
+            ..test_params()
+        });
+        engine.reset();
+
+        assert!(!engine.mix.is_smoothing());
+        let mut silence = vec![0.0; 8192];
+        engine.process(&mut [&mut silence]);
+        assert!(silence.iter().all(|s| *s == 0.0));
+    }
+
+    /// A line built for [`MAX_READ_DELAY_MS`] holds the longest read at
+    /// any sample rate, fractional ones included, and the fine offset
+    /// can't push a read past it.
+    #[test]
+    fn test_lines_hold_the_longest_read() {
+        assert_eq!(
+            effective_delay_ms(MAX_DELAY_MS, MAX_TIME_FINE_MS, MAX_DELAY_MS),
+            MAX_DELAY_MS
+        );
+        for sample_rate in [22050.0, 44100.0, 47999.7, 48000.0, 96000.0, 192000.0] {
+            let longest = calculate_delay_samples(MAX_READ_DELAY_MS, sample_rate);
+            let back = longest.ceil() as usize;
+            let mut line = new_delay_line(MAX_READ_DELAY_MS, sample_rate);
+            line.write(1.0);
+            line.advance();
+            for _ in 1..back {
+                line.write(0.0);
+                line.advance();
+            }
+
+            // The impulse is `back` samples back and the sample after it
+            // is silence, so a read `frac` short of it gets `1 - frac`.
+            let frac = back as f32 - longest;
+            let expected = 1.0 - frac;
+            let got = line.read(longest);
+            assert!((got - expected).abs() < 1e-3, "{sample_rate} Hz: {got}");
+        }
+    }
+
+    /// What an engine works out it will take before it's built is what
+    /// it holds once it is, at any sample rate and channel count.
+    #[test]
+    fn test_memory_bytes_match_allocations() {
+        for sample_rate in [22050.0, 44100.0, 47999.7, 48000.0, 192000.0] {
+            for channels in [1, 2, 6] {
+                let engine = LovelessDelayEngine::new(sample_rate, channels);
+                assert_eq!(
+                    engine.memory_bytes(),
+                    LovelessDelayEngine::memory_bytes_for(sample_rate, channels, MAX_DELAY_MS),
+                    "{sample_rate} Hz, {channels} channels"
+                );
+            }
+        }
+
+        // Stereo at 48 kHz is mostly four 2-second lines (two channels,
+        // the mono input and the repeat counts): about 1.6 MB.
+        let stereo = LovelessDelayEngine::new(SAMPLE_RATE, 2).memory_bytes();
+        assert!((1_500_000..1_700_000).contains(&stereo), "{stereo}");
+    }
+
+    /// Short of memory, an engine gives up delay time rather than
+    /// failing: it stays within its budget, plays a longer setting at
+    /// its own longest delay, and with no budget at all still runs at
+    /// the shortest.
+    #[test]
+    fn test_memory_budget_shortens_the_longest_delay() {
+        let full = LovelessDelayEngine::memory_bytes_for(SAMPLE_RATE, 2, MAX_DELAY_MS);
+        let roomy = LovelessDelayEngine::with_memory_budget(SAMPLE_RATE, 2, full);
+        assert_eq!(roomy.max_delay_ms(), MAX_DELAY_MS);
+
+        let mut engine = LovelessDelayEngine::with_memory_budget(SAMPLE_RATE, 2, full / 2);
+        assert!(
+            (MIN_DELAY_MS..MAX_DELAY_MS).contains(&engine.max_delay_ms()),
+            "{}",
+            engine.max_delay_ms()
+        );
+        assert!(engine.memory_bytes() <= full / 2);
+
+        engine.set_params(&DelayParams {
+            delay_ms: MAX_DELAY_MS,
+            feedback: 0.0,
+            mix: 1.0,
+            ..DelayParams::default()
+        });
+        skip_engage_fade(&mut engine);
+        let period = calculate_delay_samples(engine.max_delay_ms(), SAMPLE_RATE) as usize;
+        let mut left = vec![0.0; period + 100];
+        left[0] = 1.0;
+        let mut right = left.clone();
+        engine.process(&mut [&mut left, &mut right]);
+        let loudest = (0..left.len())
+            .max_by(|a, b| left[*a].abs().total_cmp(&left[*b].abs()))
+            .unwrap();
+        assert!(loudest.abs_diff(period) <= 1, "{loudest} vs {period}");
+
+        let starved = LovelessDelayEngine::with_memory_budget(SAMPLE_RATE, 2, 0);
+        assert_eq!(starved.max_delay_ms(), MIN_DELAY_MS);
+        assert_eq!(
+            starved.memory_bytes(),
+            LovelessDelayEngine::memory_bytes_for(SAMPLE_RATE, 2, MIN_DELAY_MS)
+        );
+    }
+
+    /// A channel count that grows past the budget is refused, leaving
+    /// the engine untouched; within it, or shrinking, it's taken, and
+    /// the buffers are what the budget was charged for.
+    #[test]
+    fn test_set_channels_within_budget() {
+        let stereo = LovelessDelayEngine::memory_bytes_for(SAMPLE_RATE, 2, MAX_DELAY_MS);
+        let surround = LovelessDelayEngine::memory_bytes_for(SAMPLE_RATE, 6, MAX_DELAY_MS);
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        assert!(!engine.set_channels_within(6, surround - 1));
+        assert_eq!(engine.channels(), 2);
+        assert_eq!(engine.memory_bytes(), stereo);
+
+        assert!(engine.set_channels_within(6, surround));
+        assert_eq!(engine.channels(), 6);
+        assert_eq!(engine.memory_bytes(), surround);
+
+        assert!(engine.set_channels_within(1, 0));
+        assert_eq!(engine.channels(), 1);
+    }
+
+    /// Across the supported rates, from telephone band to 768 kHz, a
+    /// stereo engine in the default budget gets its full delay, its
+    /// buffers grow with the rate, and an impulse's first echo lands
+    /// on the same time in milliseconds.
+    #[test]
+    fn test_sample_rate_matrix() {
+        const DELAY_MS: f32 = 250.0;
+        let budget = crate::memory_budget::DEFAULT_BUDGET_BYTES;
+        let base_bytes = LovelessDelayEngine::memory_bytes_for(48000.0, 2, MAX_DELAY_MS);
+        for sample_rate in [8000.0, 44100.0, 96000.0, 192000.0, 384000.0, 768000.0] {
+            assert!((SAMPLE_RATE_RANGE.0..=SAMPLE_RATE_RANGE.1).contains(&sample_rate));
+            let mut engine = LovelessDelayEngine::with_memory_budget(sample_rate, 2, budget);
+            assert_eq!(engine.max_delay_ms(), MAX_DELAY_MS, "{sample_rate} Hz");
+            let bytes = engine.memory_bytes();
+            assert!(bytes <= budget, "{sample_rate} Hz: {bytes}");
+            let scale = bytes as f32 / base_bytes as f32;
+            let expected = sample_rate / 48000.0;
+            assert!(
+                (scale / expected - 1.0).abs() < 0.05,
+                "{sample_rate} Hz: {scale} × 48 kHz's buffers, not {expected}"
+            );
+
+            engine.set_params(&DelayParams {
+                delay_ms: DELAY_MS,
+                feedback: 0.0,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                filter_cutoff_right: 20000.0,
+                ..DelayParams::default()
+            });
+            skip_engage_fade(&mut engine);
+            let period = calculate_delay_samples(DELAY_MS, sample_rate) as usize;
+            let mut left = vec![0.0; period + 100];
+            left[0] = 1.0;
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+            assert!(
+                left.iter().chain(&right).all(|s| s.is_finite()),
+                "{sample_rate} Hz"
+            );
+            let loudest = (0..left.len())
+                .max_by(|a, b| left[*a].abs().total_cmp(&left[*b].abs()))
+                .unwrap();
+            assert!(
+                loudest.abs_diff(period) <= 1,
+                "{sample_rate} Hz: {loudest} vs {period}"
+            );
+        }
+    }
+
+    /// At 8 kHz a 20 kHz cutoff is out of the filters' reach: its
+    /// target stops at their ceiling, so a sweep down from it starts
+    /// moving at once.
+    #[test]
+    fn test_cutoff_target_stops_below_nyquist() {
+        let mut engine = LovelessDelayEngine::new(8000.0, 2);
+        engine.set_params(&DelayParams {
+            filter_cutoff: 20000.0,
+            filter_cutoff_right: 20000.0,
+            ..DelayParams::default()
+        });
+        for cutoff in &engine.filter_cutoffs {
+            assert_eq!(cutoff.target(), max_cutoff_hz(8000.0));
+        }
+
+        let mut at_48k = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        at_48k.set_params(&DelayParams {
+            filter_cutoff: 20000.0,
+            ..DelayParams::default()
+        });
+        assert_eq!(at_48k.filter_cutoffs[0].target(), 20000.0);
+    }
+
+    /// Sizing buffers for a rate no plugin host would send reads as
+    /// more memory than there is, rather than overflowing.
+    #[test]
+    fn test_memory_for_absurd_rates_saturates() {
+        assert_eq!(
+            LovelessDelayEngine::memory_bytes_for(f32::MAX, 2, MAX_DELAY_MS),
+            usize::MAX
+        );
+        assert!(
+            LovelessDelayEngine::memory_bytes_for(SAMPLE_RATE_RANGE.1, 2, MAX_DELAY_MS)
+                < crate::memory_budget::DEFAULT_BUDGET_BYTES
+        );
+    }
+
+    /// Blocks no one would play through: an empty block changes
+    /// nothing, not even a ramp in progress; a block with no channels
+    /// is fine; channels past the engine's own come back untouched; and
+    /// a stereo engine given one channel runs it.
+    #[test]
+    fn test_degenerate_block_shapes() {
+        let mut engine = test_engine();
+        let mut params = DelayParams {
+            limiter: true,
+            feedback: 0.9,
+            ..test_params()
+        };
+        engine.set_params(&params);
+        engine.process(&mut [&mut [0.5; 64], &mut [0.5; 64]]);
+
+        // A ramp starts, and an empty block doesn't move it.
+        params.delay_ms = 400.0;
+        engine.set_params(&params);
+        let before = (
+            engine.delay_time.current(),
+            engine.tail_samples(),
+            engine.silence.silent_samples(),
+            engine.expected_pos,
+        );
+        engine.process(&mut [&mut [], &mut []]);
+        engine.process(&mut []);
+        engine.process_with_external_loop(&mut [&mut [], &mut []], &mut [], &[], None);
+        let after = (
+            engine.delay_time.current(),
+            engine.tail_samples(),
+            engine.silence.silent_samples(),
+            engine.expected_pos,
+        );
+        assert_eq!(after, before);
+
+        // A third channel passes through; one channel is processed.
+        let mut extra = [0.25; 64];
+        engine.process(&mut [&mut [0.5; 64], &mut [0.5; 64], &mut extra]);
+        assert_eq!(extra, [0.25; 64]);
+        let mut mono = [0.5; 64];
+        engine.process(&mut [&mut mono]);
+        assert!(mono.iter().all(|s| s.is_finite()));
+
+        // An engine with no channels (the plugin's placeholder before
+        // initialize()) leaves everything alone.
+        let mut empty = LovelessDelayEngine::new(SAMPLE_RATE, 0);
+        let mut left = [0.5; 64];
+        empty.process(&mut [&mut left]);
+        assert_eq!(left, [0.5; 64]);
+    }
+}
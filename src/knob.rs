@@ -0,0 +1,299 @@
+//! # Knob Gestures
+//!
+//! Every knob in the editor plays the same way, because every knob hands
+//! its mouse to a [`KnobGesture`] rather than reading it itself:
+//!
+//! ```text
+//! drag up/down          turn: the whole range in KnobSettings::drag_pixels
+//! shift-drag            turn finely: FINE_FACTOR (10×) the pixels for the same turn
+//! double-click          back to the default
+//! ctrl-click (⌘ on Mac) type a value in (see `param_text`)
+//! scroll wheel          a KnobSettings::scroll_step a notch, a tenth of it with shift,
+//!                       and at least one step of a stepped parameter
+//! ```
+//!
+//! Shift can come and go mid-drag: only the pixels moved while it's held
+//! are fine.
+//!
+//! ## Through the Host
+//!
+//! Each gesture reaches the host as one [`ParamSetter`] gesture, begun
+//! and ended around the values it sets, so the host's undo takes back a
+//! whole drag, not its last pixel, and automation written while dragging
+//! is one touch. A drag begins on the press and ends on the release;
+//! a reset, a scroll notch and a typed-in value are gestures of their
+//! own. A scroll during a drag joins the drag.
+//!
+//! A drag keeps its own normalized value rather than reading the
+//! parameter back: a stepped parameter would round each pixel's move
+//! away, and a fine drag would never leave its step.
+//!
+//! ## Not Wired Up Yet
+//!
+//! There's no editor yet to put a knob in. When there is, each knob
+//! widget owns a [`KnobGesture`], passes it its mouse events with the
+//! toolkit's [`Modifiers`], and opens a text field when a press returns
+//! [`KnobAction::EditText`]. The mapping itself — pixels and notches to
+//! a normalized change — is plain functions ([`drag_delta()`],
+//! [`scroll_delta()`]), tested here without a host.
+
+use nih_plug::prelude::{Param, ParamSetter};
+
+/// How many times finer a shift-drag or shift-scroll turns.
+pub const FINE_FACTOR: f32 = 10.0;
+
+/// How far a knob turns for the mouse, the same for every knob in an
+/// editor. A user's preferences can change it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KnobSettings {
+    /// How far a drag goes to turn a knob through its whole range, in
+    /// pixels, without shift.
+    pub drag_pixels: f32,
+
+    /// How far a scroll-wheel notch turns a knob, as a fraction of its
+    /// range, without shift.
+    pub scroll_step: f32,
+}
+
+impl Default for KnobSettings {
+    fn default() -> Self {
+        Self {
+            drag_pixels: 200.0,
+            scroll_step: 0.05,
+        }
+    }
+}
+
+/// The keys held with a mouse event.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    /// Shift: turn finely.
+    pub shift: bool,
+
+    /// Ctrl, or Cmd on macOS (toolkits tend to call it `command`):
+    /// type a value in.
+    pub command: bool,
+}
+
+/// What a press asks of the knob widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnobAction {
+    /// A drag has begun.
+    Drag,
+
+    /// The parameter is back at its default.
+    Reset,
+
+    /// Open a text field for a value to type in, and hand what's typed
+    /// to [`KnobGesture::submit_text()`].
+    EditText,
+}
+
+/// The normalized change for a drag of `pixels` upward (negative for
+/// downward).
+pub fn drag_delta(pixels: f32, modifiers: Modifiers, settings: &KnobSettings) -> f32 {
+    let fine = if modifiers.shift { FINE_FACTOR } else { 1.0 };
+    pixels / (settings.drag_pixels.max(1.0) * fine)
+}
+
+/// The normalized change for `notches` of the scroll wheel, upward or
+/// away positive, on a parameter with `step_count` steps (see
+/// [`Param::step_count()`]). A notch of a stepped parameter moves it at
+/// least one step, so a notch is never lost to rounding.
+pub fn scroll_delta(
+    notches: f32,
+    modifiers: Modifiers,
+    settings: &KnobSettings,
+    step_count: Option<usize>,
+) -> f32 {
+    let fine = if modifiers.shift { FINE_FACTOR } else { 1.0 };
+    let delta = notches * settings.scroll_step / fine;
+    match step_count {
+        Some(steps) if steps > 0 => {
+            let step = 1.0 / steps as f32;
+            delta.signum() * delta.abs().max(step * notches.abs().min(1.0))
+        }
+        _ => delta,
+    }
+}
+
+/// One knob's mouse handling. See [the module docs](self).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct KnobGesture {
+    /// The normalized value a drag in progress has reached, or `None`
+    /// between drags.
+    dragging: Option<f32>,
+}
+
+impl KnobGesture {
+    /// Whether a drag is in progress.
+    pub fn is_dragging(&self) -> bool {
+        self.dragging.is_some()
+    }
+
+    /// The mouse went down on the knob, the `clicks`th click in a row
+    /// (2 for a double-click). Returns what the widget should do.
+    pub fn press<P: Param>(
+        &mut self,
+        setter: &ParamSetter,
+        param: &P,
+        modifiers: Modifiers,
+        clicks: u32,
+    ) -> KnobAction {
+        // A press can't start a drag over another one (a second button,
+        // say); end that one first so the host never sees two begun.
+        self.release(setter, param);
+        if modifiers.command {
+            KnobAction::EditText
+        } else if clicks >= 2 {
+            setter.begin_set_parameter(param);
+            setter.set_parameter(param, param.default_plain_value());
+            setter.end_set_parameter(param);
+            KnobAction::Reset
+        } else {
+            setter.begin_set_parameter(param);
+            self.dragging = Some(param.unmodulated_normalized_value());
+            KnobAction::Drag
+        }
+    }
+
+    /// The mouse moved `pixels` upward (negative for downward) since the
+    /// last call, with the button down.
+    pub fn drag<P: Param>(
+        &mut self,
+        setter: &ParamSetter,
+        param: &P,
+        pixels: f32,
+        modifiers: Modifiers,
+        settings: &KnobSettings,
+    ) {
+        if let Some(normalized) = &mut self.dragging {
+            *normalized = (*normalized + drag_delta(pixels, modifiers, settings)).clamp(0.0, 1.0);
+            setter.set_parameter_normalized(param, *normalized);
+        }
+    }
+
+    /// The mouse button came up, or the widget lost the mouse: end the
+    /// drag, if there was one. Safe to call any time.
+    pub fn release<P: Param>(&mut self, setter: &ParamSetter, param: &P) {
+        if self.dragging.take().is_some() {
+            setter.end_set_parameter(param);
+        }
+    }
+
+    /// The scroll wheel turned `notches` over the knob.
+    pub fn scroll<P: Param>(
+        &mut self,
+        setter: &ParamSetter,
+        param: &P,
+        notches: f32,
+        modifiers: Modifiers,
+        settings: &KnobSettings,
+    ) {
+        let delta = scroll_delta(notches, modifiers, settings, param.step_count());
+        match &mut self.dragging {
+            Some(normalized) => {
+                *normalized = (*normalized + delta).clamp(0.0, 1.0);
+                setter.set_parameter_normalized(param, *normalized);
+            }
+            None => {
+                let normalized = (param.unmodulated_normalized_value() + delta).clamp(0.0, 1.0);
+                setter.begin_set_parameter(param);
+                setter.set_parameter_normalized(param, normalized);
+                setter.end_set_parameter(param);
+            }
+        }
+    }
+
+    /// Set the parameter to the value typed into the text field, read
+    /// the way the host reads one (see `param_text`). Returns `false`,
+    /// leaving the parameter alone, if it doesn't parse.
+    pub fn submit_text<P: Param>(&mut self, setter: &ParamSetter, param: &P, text: &str) -> bool {
+        let Some(normalized) = param.string_to_normalized_value(text) else {
+            return false;
+        };
+        self.release(setter, param);
+        setter.begin_set_parameter(param);
+        setter.set_parameter_normalized(param, normalized);
+        setter.end_set_parameter(param);
+        true
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: Modifiers = Modifiers {
+        shift: false,
+        command: false,
+    };
+    const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        command: false,
+    };
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual}, not {expected}");
+    }
+
+    /// A drag of the configured distance turns the whole range, up
+    /// positive and down negative, and shift takes ten times as far.
+    #[test]
+    fn test_drag_delta() {
+        let settings = KnobSettings::default();
+        assert_close(drag_delta(200.0, PLAIN, &settings), 1.0);
+        assert_close(drag_delta(-50.0, PLAIN, &settings), -0.25);
+        assert_close(drag_delta(200.0, SHIFT, &settings), 0.1);
+        assert_close(drag_delta(2000.0, SHIFT, &settings), 1.0);
+        assert_close(drag_delta(1.0, SHIFT, &settings), 1.0 / 2000.0);
+        assert_close(drag_delta(0.0, SHIFT, &settings), 0.0);
+
+        let slow = KnobSettings {
+            drag_pixels: 400.0,
+            ..settings
+        };
+        assert_close(drag_delta(100.0, PLAIN, &slow), 0.25);
+        // The command key only matters to a press.
+        let command = Modifiers {
+            command: true,
+            ..PLAIN
+        };
+        assert_close(drag_delta(100.0, command, &settings), 0.5);
+    }
+
+    /// A notch turns the configured step, a tenth of it with shift, and
+    /// in proportion for the fractional notches of a trackpad.
+    #[test]
+    fn test_scroll_delta() {
+        let settings = KnobSettings::default();
+        assert_close(scroll_delta(1.0, PLAIN, &settings, None), 0.05);
+        assert_close(scroll_delta(-2.0, PLAIN, &settings, None), -0.1);
+        assert_close(scroll_delta(1.0, SHIFT, &settings, None), 0.005);
+        assert_close(scroll_delta(0.5, PLAIN, &settings, None), 0.025);
+
+        let coarse = KnobSettings {
+            scroll_step: 0.25,
+            ..settings
+        };
+        assert_close(scroll_delta(1.0, PLAIN, &coarse, None), 0.25);
+    }
+
+    /// On a stepped parameter a notch moves at least one step, fine or
+    /// not, and a big enough turn moves more.
+    #[test]
+    fn test_scroll_delta_stepped() {
+        let settings = KnobSettings::default();
+        // Three steps: a notch's 0.05 would round away to nothing.
+        assert_close(scroll_delta(1.0, PLAIN, &settings, Some(3)), 1.0 / 3.0);
+        assert_close(scroll_delta(-1.0, SHIFT, &settings, Some(3)), -1.0 / 3.0);
+        // A hundred steps: a notch is already five of them.
+        assert_close(scroll_delta(1.0, PLAIN, &settings, Some(100)), 0.05);
+        assert_close(scroll_delta(1.0, SHIFT, &settings, Some(100)), 0.01);
+        assert_close(scroll_delta(0.0, PLAIN, &settings, Some(3)), 0.0);
+    }
+}
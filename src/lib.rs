@@ -23,71 +23,198 @@
 //!              │                    │                              │
 //!              └────────────────────│──────────────────────────────┘
 //!                                   │                              │
-//!                                   └──── × mix ─────────────────►(+)──► Output
+//!                                   └──── × mix ─────────────────►(+)──► [Limiter] ──► Output
 //! ```
-
-mod dsp;
+//!
+//! The limiter at the end is optional (off by default) and only there to
+//! keep runaway feedback from clipping the host.
+//!
+//! ## Using the DSP Without a Host
+//!
+//! The whole signal flow above lives in [`LovelessDelayEngine`], which
+//! works on plain `f32` slices. The nih-plug plugin below is just an
+//! adapter around it, so the same engine can run in any Rust program.
+
+// The DSP primitives are public so they can be reused outside the
+// plugin (and so building blocks the plugin doesn't use yet aren't
+// flagged as dead code).
+mod block_chunks;
+pub mod dsp;
+mod engine;
+// Public so every knob in an editor turns the same way (see its docs).
+pub mod knob;
+// Public so an editor can light an economy lamp from the load monitor.
+pub mod load_monitor;
+// Public so an editor (there isn't one yet) can read the loop meter.
+pub mod loop_meter;
+// Public so an editor's "about" panel can show what an instance holds.
+pub mod memory_budget;
+// Public so an editor's A and B buttons can store snapshots for Morph.
+pub mod morph;
+// Walks every parameter through its range (see its docs).
+#[cfg(test)]
+mod param_contract;
+mod param_text;
 mod params;
-
-use std::num::{NonZeroU32, NonZeroUsize};
+mod plugin_id;
+// Public so preset files (and a browser, once there's an editor) can be
+// built from the factory presets.
+pub mod presets;
+// Public so the render harness can ring a render out the way a host
+// would (see its docs).
+pub mod ring_out;
+// Public so an editor can hand Sync over without a jump in time (see
+// its docs).
+pub mod sync_handover;
+// Public so an editor's Print Tail button can ask for a print.
+pub mod tail_print;
+// Public so the render harness's `--self-test` runs the same checks as
+// `cargo test`, but only with the `testing` feature: the plugin doesn't
+// ship it.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+// Public so an editor can show the delay time being played.
+pub mod time_readout;
+// Public for the same reason: nothing fills it in until nih-plug passes
+// CLAP's track-info through (see its docs).
+pub mod track_info;
+
+use std::num::NonZeroU32;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-
-use dsp::{delay_line::DelayLine, filter::OnePoleFilter};
+use std::time::Instant;
+
+pub use engine::{
+    AutomationSmoothing, CurrentValues, DelayParams, FeedbackSource, LovelessDelayEngine,
+    ProcessingOrder, StopBehavior, TailSnapshot, TempoSource, Transport, MAX_DELAY_MS,
+    MAX_FEEDBACK, MAX_MOD_DEPTH_MS, MAX_PRE_DELAY_MS, MAX_TIME_FINE_MS, MIN_DELAY_MS,
+    MOD_RATE_RANGE_HZ, SAMPLE_RATE_RANGE,
+};
+use load_monitor::{LoadIndicator, LoadMonitor};
+use loop_meter::LoopMeter;
+use memory_budget::{MemoryClaim, MemoryUsage};
 use nih_plug::prelude::*;
+use nih_plug::wrapper::state::ParamValue;
 use params::PluginParams;
+use sync_handover::SyncHandover;
+use tail_print::TailPrint;
+use time_readout::TimeReadout;
 
 /// The main plugin struct.
 ///
-/// This holds all the audio-rate state that persists between calls to
-/// `process()`. The DAW calls `process()` hundreds of times per second,
-/// each time passing a small buffer of audio samples (typically 64-1024
-/// samples). Our state must survive between these calls.
+/// This is a thin adapter: all the audio-rate state (delay lines,
+/// filters, smoothers) lives in the host-independent
+/// [`LovelessDelayEngine`]. The plugin's job is to translate between
+/// nih-plug and the engine — parameter values in, channel slices
+/// through, tail length out.
 ///
 /// ## Why separate state from parameters?
 ///
 /// Parameters (`PluginParams`) are shared with the host via `Arc` and can
 /// be read from any thread (the audio thread, the UI thread, the host's
-/// automation thread). Plugin state (delay lines, filters) is owned
-/// exclusively by the audio thread and only accessed in `process()`.
-/// This separation makes the design thread-safe without locks.
+/// automation thread). The engine is owned exclusively by the audio
+/// thread and only accessed in `process()`. This separation makes the
+/// design thread-safe without locks.
 struct LovelessDelay {
     /// Shared reference to the plugin parameters. The `Arc` (Atomic
     /// Reference Counted pointer) allows both the plugin and the host
     /// to hold references to the same parameter data without copying.
     params: Arc<PluginParams>,
 
-    /// The current sample rate in Hz (e.g., 44100.0 or 48000.0).
-    /// Set during `initialize()` and used to convert delay time from
-    /// milliseconds to samples: `delay_samples = delay_ms * sample_rate / 1000`.
-    sample_rate: f32,
+    /// The DSP itself. Rebuilt in `initialize()` once the sample rate
+    /// and channel count are known.
+    engine: LovelessDelayEngine,
+
+    /// The loop level and repeats remaining, published after every
+    /// block for an editor to show (see [`loop_meter`]).
+    loop_meter: Arc<LoopMeter>,
+
+    /// The delay time the engine is playing, published after every
+    /// block for an editor to show (see [`time_readout`]).
+    time_readout: Arc<TimeReadout>,
+
+    /// The tempo sync follows and whether it's the host's or Manual
+    /// BPM's, published every block for an editor's Sync button (see
+    /// [`sync_handover`]).
+    sync_handover: Arc<SyncHandover>,
+
+    /// This instance's share of the process-wide memory budget, held
+    /// until the engine is rebuilt or the plugin unloads (see
+    /// [`memory_budget`]).
+    memory_claim: Option<MemoryClaim<'static>>,
+
+    /// The engine's buffer memory and longest delay, published from
+    /// `initialize()` for an editor's "about" panel, plus Print Tail's
+    /// snapshot once there is one.
+    memory_usage: Arc<MemoryUsage>,
+
+    /// Whether to time each block: not in an offline render, which has
+    /// no deadline (see [`load_monitor`]).
+    measure_load: bool,
+
+    /// What the blocks' loads say about economy.
+    load_monitor: LoadMonitor,
+
+    /// Economy, the last block's load and the overload count, published
+    /// after every timed block for an editor to show.
+    load_indicator: Arc<LoadIndicator>,
+
+    /// The Print Tail button's request, and the snapshot the audio
+    /// thread captures for the background thread to render (see
+    /// [`tail_print`]).
+    tail_print: Arc<TailPrint>,
+
+    /// The longest block handed to the engine in one go: the host's
+    /// declared maximum, plus a margin. A longer block is split (see
+    /// [`block_chunks`]).
+    max_block_len: usize,
+}
 
-    /// One delay line (ring buffer) per audio channel.
-    ///
-    /// For stereo audio, this will contain 2 independent delay lines.
-    /// Each channel is processed separately so that stereo imaging is
-    /// preserved — if only the left channel has audio, only the left
-    /// delay line produces echoes.
-    delay_lines: Vec<DelayLine>,
-
-    /// One lowpass filter per audio channel, applied to the feedback
-    /// signal before it re-enters the delay line.
-    ///
-    /// Independent per-channel filters ensure that stereo balance is
-    /// maintained even when the filter cutoff changes.
-    filters: Vec<OnePoleFilter>,
+/// Work `process()` hands off to nih-plug's background thread.
+enum Task {
+    /// Render the tail just captured into [`TailPrint`], and write it
+    /// out.
+    PrintTail,
 }
 
 impl Default for LovelessDelay {
     fn default() -> Self {
+        let params = Arc::new(PluginParams::default());
+        let memory_usage = Arc::new(MemoryUsage::default());
         Self {
-            params: Arc::new(PluginParams::default()),
-            // 44100 Hz is a placeholder. The real sample rate is set in
-            // initialize() when the host tells us the actual configuration.
-            sample_rate: 44100.0,
-            // Empty vecs — populated in initialize() when we know the
-            // channel count and sample rate.
-            delay_lines: Vec::new(),
-            filters: Vec::new(),
+            // The same handover the Delay Time field reads its tempo from.
+            sync_handover: params.sync_handover.clone(),
+            params,
+            // A placeholder with no channels (so no buffers). The real
+            // engine is created in initialize() when the host tells us
+            // the actual configuration.
+            engine: LovelessDelayEngine::new(44100.0, 0),
+            loop_meter: Arc::new(LoopMeter::default()),
+            time_readout: Arc::new(TimeReadout::default()),
+            memory_claim: None,
+            memory_usage: memory_usage.clone(),
+            measure_load: false,
+            load_monitor: LoadMonitor::default(),
+            load_indicator: Arc::new(LoadIndicator::default()),
+            tail_print: Arc::new(TailPrint::new(memory_usage)),
+            // No limit until the host declares one.
+            max_block_len: usize::MAX,
+        }
+    }
+}
+
+impl LovelessDelay {
+    /// The parts of the host's transport the engine reads.
+    ///
+    /// Everything that acts on them — tempo sync, quantized starts, the
+    /// stop behavior — lives in the engine and takes these plain values,
+    /// so tests can script a transport without a host (nih-plug's own
+    /// `Transport` can't be built outside nih-plug).
+    fn engine_transport(transport: &nih_plug::prelude::Transport) -> Transport {
+        Transport {
+            tempo: transport.tempo,
+            pos_beats: transport.pos_beats(),
+            playing: transport.playing,
         }
     }
 }
@@ -105,6 +232,33 @@ impl Plugin for LovelessDelay {
     // We support stereo (2 in → 2 out) and mono (1 in → 1 out).
     // Most DAW tracks are stereo, so we list it first.
     const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        // Stereo with a feedback send and return, for the external loop,
+        // and a sidechain key for the key gate. Hosts pick the first
+        // layout they support, so those without aux ports fall through to
+        // plain stereo.
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2), new_nonzero_u32(2)],
+            aux_output_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                aux_inputs: &["Loop Return", "Key"],
+                aux_outputs: &["Loop Send"],
+                ..PortNames::const_default()
+            },
+        },
+        // Stereo with just the sidechain key, for hosts that offer a
+        // sidechain input but no aux outputs.
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+            names: PortNames {
+                aux_inputs: &["Key"],
+                ..PortNames::const_default()
+            },
+        },
         // Stereo layout
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(2),
@@ -129,17 +283,58 @@ impl Plugin for LovelessDelay {
     // Process parameter changes at sample-accurate timing. This means
     // when the host sends an automation point at sample 37 of a buffer,
     // the parameter actually changes at sample 37 (not at the start
-    // of the buffer). More accurate, but we're already doing per-sample
-    // smoothing so this just ensures consistency.
+    // of the buffer).
+    //
+    // nih-plug does the work for us: parameter changes never reach
+    // `process()` as events. Instead the wrapper splits the host's buffer
+    // at each change's offset, calls `process()` for the part before it,
+    // applies the change, then calls `process()` again for the rest. So
+    // every block we see starts exactly where a ramp may begin, and
+    // handing the engine fresh values at the top of `process()` retargets
+    // its smoothers at the exact sample.
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = Task;
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
     }
 
+    /// Runs the tasks `process()` hands off, on nih-plug's background
+    /// thread, where they're free to allocate and take their time.
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let params = self.params.clone();
+        let tail_print = self.tail_print.clone();
+        Box::new(move |task| match task {
+            Task::PrintTail => {
+                let dir = params
+                    .tail_print_dir
+                    .read()
+                    .map(|dir| dir.clone())
+                    .unwrap_or_default();
+                match tail_print.print(dir.as_deref()) {
+                    Ok(path) => nih_log!("Printed the tail to {}", path.display()),
+                    Err(err) => nih_error!("Couldn't print the tail: {err}"),
+                }
+            }
+        })
+    }
+
+    /// Called with a saved state just before it's loaded, to patch it up.
+    ///
+    /// Momentary switches are saved along with everything else, so a
+    /// session saved while Wet Solo was held would reopen with the dry
+    /// signal muted, one saved with Key Listen on would play only the key,
+    /// and one saved mid-Clear with a switch that has to be turned off
+    /// before it works again. None is part of the sound, so they always
+    /// load off.
+    fn filter_state(state: &mut PluginState) {
+        for id in params::MOMENTARY_PARAM_IDS {
+            state.params.insert(id.to_string(), ParamValue::Bool(false));
+        }
+    }
+
     /// Called when the plugin is first loaded, or when the audio
     /// configuration changes (e.g., sample rate change, channel count
     /// change). This is where we allocate our delay buffers.
@@ -159,9 +354,19 @@ impl Plugin for LovelessDelay {
         &mut self,
         audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
-        self.sample_rate = buffer_config.sample_rate;
+        // Every buffer and coefficient is sized from the rate, so one
+        // that's nonsense (or out of what the engine is tested at) is
+        // refused here rather than played badly.
+        let (min_rate, max_rate) = SAMPLE_RATE_RANGE;
+        if !(min_rate..=max_rate).contains(&buffer_config.sample_rate) {
+            nih_error!(
+                "Unsupported sample rate {} Hz; Loveless Delay runs at {min_rate}–{max_rate} Hz",
+                buffer_config.sample_rate
+            );
+            return false;
+        }
 
         // Determine the number of audio channels from the layout.
         let num_channels = audio_io_layout
@@ -169,231 +374,187 @@ impl Plugin for LovelessDelay {
             .map(|c| c.get() as usize)
             .unwrap_or(2);
 
-        // Calculate the maximum buffer size in samples.
-        //
-        // Our maximum delay time parameter is 2000ms. We add 100ms of
-        // headroom (2100ms total) to account for parameter smoothing
-        // overshooting slightly during transitions.
+        // A new sample rate changes every buffer size and coefficient, so
+        // start over. A layout change alone (some hosts swap stereo and
+        // mono on the fly) keeps the channels that still exist.
         //
-        // Formula: time_seconds * sample_rate = samples
-        //   2.1 seconds * 44100 Hz = 92610 samples
-        //   2.1 seconds * 48000 Hz = 100800 samples
-        //
-        // Each sample is an f32 (4 bytes), so at 48 kHz this buffer
-        // uses about 400 KB per channel — very modest.
-        const MAX_DELAY_SECONDS: f32 = 2.1;
-        let max_delay_samples = (MAX_DELAY_SECONDS * self.sample_rate) as usize;
-
-        // Create fresh delay lines and filters for each channel.
-        // We replace any existing ones to handle sample rate changes.
-        // `NonZeroUsize` guarantees the delay line can't be zero-length,
-        // which would cause division-by-zero in ring buffer arithmetic.
-        let max_delay_len =
-            NonZeroUsize::new(max_delay_samples).expect("max delay samples must be > 0");
-        self.delay_lines = (0..num_channels)
-            .map(|_| DelayLine::new(max_delay_len))
-            .collect();
-
-        self.filters = (0..num_channels).map(|_| OnePoleFilter::new()).collect();
+        // Either way the engine gets what's left of the memory budget
+        // shared with every other instance, this one's old share
+        // included. More channels than that holds means a new engine
+        // after all: short of the budget, it plays shorter delays rather
+        // than failing here. Print Tail's snapshot, sized for the old
+        // engine, goes too.
+        self.memory_claim = None;
+        self.tail_print.release();
+        let available = memory_budget::SHARED.available();
+        let kept = self.engine.sample_rate() == buffer_config.sample_rate
+            && self.engine.set_channels_within(num_channels, available);
+        if !kept {
+            self.engine = LovelessDelayEngine::with_memory_budget(
+                buffer_config.sample_rate,
+                num_channels,
+                available,
+            );
+        }
+        let claim = self
+            .memory_claim
+            .insert(memory_budget::SHARED.claim(self.engine.memory_bytes()));
+        let max_delay_ms = self.engine.max_delay_ms();
+        self.memory_usage.publish(claim.bytes(), max_delay_ms);
+        // The next print makes a snapshot to match the new engine.
+        self.tail_print.prepare(&self.engine);
+        nih_log!(
+            "Allocated {} KB of delay buffers; delays up to {max_delay_ms} ms",
+            claim.bytes() / 1024
+        );
+        if max_delay_ms < MAX_DELAY_MS {
+            nih_warn!(
+                "The memory budget is short: delays are limited to {max_delay_ms} ms, not {MAX_DELAY_MS} ms"
+            );
+        }
+
+        // nih-plug calls this again after loading a preset or a project's
+        // state, with every parameter already changed. Land on the new
+        // values at once rather than sweeping to them from the old ones
+        // (see `LovelessDelayEngine::load_params()`); on a fresh engine
+        // it's the same as the first `set_params()`.
+        // Morph's snapshots may have come with it.
+        self.params.morph_table.rebuild(&self.params);
+        self.engine.load_params(&self.params.delay_params());
+
+        // Any latency the wet path adds (the dry signal is held back to
+        // match it), so the host can line us up with other tracks.
+        context.set_latency_samples(self.engine.latency_samples());
+
+        // A bounce has all the time it needs, so it always gets the full
+        // engine; a new configuration gets judged afresh.
+        self.measure_load = buffer_config.process_mode != ProcessMode::Offline;
+        self.load_monitor = LoadMonitor::default();
+        self.engine.set_economy(false);
+
+        // The external loop's return comes back one host buffer late.
+        self.engine
+            .set_external_loop_latency(buffer_config.max_buffer_size as usize);
+
+        // Some hosts send longer blocks than they declared here, after
+        // changing their buffer size mid-stream.
+        self.max_block_len = block_chunks::max_block_len(buffer_config.max_buffer_size);
 
         true // Initialization succeeded
     }
 
-    /// Called when playback stops or the plugin is bypassed.
+    /// Called when the plugin is activated or bypassed, and by some
+    /// hosts when playback stops.
     ///
     /// We clear all delay buffers and filter states so that stale audio
-    /// doesn't bleed into the next playback. Without this, pressing
-    /// "play" after "stop" might produce a burst of old echoes.
+    /// doesn't bleed into the next playback. What a plain stop does to
+    /// the repeats is up to the Stop Behavior parameter instead, which
+    /// the engine applies when it sees the transport stop (see
+    /// [`StopBehavior`]).
     fn reset(&mut self) {
-        for dl in &mut self.delay_lines {
-            dl.clear();
-        }
-        for f in &mut self.filters {
-            f.reset();
-        }
+        self.engine.reset();
+        self.loop_meter.publish(self.engine.loop_reading());
     }
 
-    /// The core audio processing function — this is where all the DSP
-    /// magic happens.
+    /// The core audio processing function.
     ///
     /// The host calls this function repeatedly, passing small buffers
     /// of audio samples. A typical buffer might be 256 samples long at
     /// 44100 Hz, meaning this function is called ~172 times per second.
     ///
-    /// # Arguments
-    ///
-    /// * `buffer` - The audio data. Contains interleaved channels that
-    ///   we iterate over. We read input samples and write output samples
-    ///   back to the same buffer (in-place processing).
-    /// * `_aux` - Auxiliary buffers (sidechain inputs, etc.). Unused.
-    /// * `_context` - Process context with transport info. Unused.
-    ///
-    /// # The Delay Algorithm
+    /// All the DSP lives in [`LovelessDelayEngine::process()`]; here we
+    /// only pass the latest parameter values and the audio through.
     ///
-    /// For each sample, across all channels:
+    /// # Arguments
     ///
-    /// 1. **Read** the delayed sample from the ring buffer
-    /// 2. **Filter** it through the lowpass (darkens the feedback)
-    /// 3. **Scale** by feedback amount (controls decay rate)
-    /// 4. **Write** (input + scaled feedback) into the ring buffer
-    /// 5. **Mix** dry and wet signals for the output
-    /// 6. **Advance** the ring buffer write position
+    /// * `buffer` - The audio data. `as_slice()` gives us one
+    ///   `&mut [f32]` per channel; the engine reads input samples and
+    ///   writes output samples back in place.
+    /// * `aux` - Auxiliary buffers: the external loop's send (output) and
+    ///   return (input), and the key gate's sidechain key (the input after
+    ///   the return, or the only input without a send), when the host
+    ///   gave us a layout that has them.
+    /// * `context` - Process context with transport info. Tempo sync
+    ///   reads the tempo and beat position from it.
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        // Iterate over the buffer one sample at a time, across all channels.
-        //
-        // `iter_samples()` yields a `ChannelSamples` for each time step.
-        // Within each time step, we process all channels. This is the
-        // "per-sample, per-channel" pattern — the clearest (though not
-        // the fastest) way to implement audio processing.
-        for mut channel_samples in buffer.iter_samples() {
-            // ─── Read smoothed parameter values for this sample ───
-            //
-            // `.smoothed.next()` returns the parameter's current value
-            // after applying the smoother. If the user just moved a knob
-            // from 500ms to 1000ms, the smoother gradually ramps from
-            // 500 to 1000 over the smoothing duration (e.g., 50ms),
-            // giving us intermediate values like 501, 502, 503... instead
-            // of an instant jump.
-            let delay_ms = self.params.delay_time.smoothed.next();
-            let feedback = self.params.feedback.smoothed.next();
-            let mix = self.params.mix.smoothed.next();
-            let filter_cutoff = self.params.filter_cutoff.smoothed.next();
-
-            // Convert delay time from milliseconds to samples.
-            //
-            // This is one of the most fundamental DSP conversions:
-            //
-            //   delay_samples = delay_ms * sample_rate / 1000
-            //
-            // At 44100 Hz:
-            //   100ms  =  4410 samples
-            //   500ms  = 22050 samples
-            //   2000ms = 88200 samples
-            //
-            // The result is often fractional (e.g., 441.3 samples for
-            // 10.007ms), which is why our delay line supports fractional
-            // reads via linear interpolation.
-            let delay_samps = calculate_delay_samples(delay_ms, self.sample_rate);
-
-            // Process each audio channel independently.
-            for (channel_idx, sample) in channel_samples.iter_mut().enumerate() {
-                // Get this channel's delay line and filter.
-                // The `let-else` pattern skips channels we don't have
-                // state for (shouldn't happen after initialize()).
-                let Some(delay_line) = self.delay_lines.get_mut(channel_idx) else {
-                    continue;
-                };
-                let Some(filter) = self.filters.get_mut(channel_idx) else {
-                    continue;
-                };
-
-                // Update the filter's cutoff frequency for this sample.
-                // We do this per-sample (not per-buffer) because the
-                // cutoff parameter might be smoothing toward a new value,
-                // and we want the filter to track that smoothly.
-                filter.set_cutoff(filter_cutoff, self.sample_rate);
-
-                // ═══════════════════════════════════════════════════════
-                // THE DELAY ALGORITHM — 6 steps per sample
-                // ═══════════════════════════════════════════════════════
-
-                // Step 1: READ the delayed sample from the ring buffer.
-                //
-                // We look backward in time by `delay_samples` samples.
-                // If the delay is 500ms at 44100 Hz, we're reading the
-                // sample that was written 22050 samples ago. Linear
-                // interpolation handles fractional positions.
-                let delayed_sample = delay_line.read(delay_samps);
-
-                // Step 2: FILTER the delayed sample through the lowpass.
-                //
-                // This simulates the high-frequency loss that occurs in
-                // analog delay circuits. Each time the signal passes
-                // through the feedback loop, it goes through this filter
-                // again, so the repeats get progressively darker.
-                //
-                // First repeat: filtered once (slightly darker)
-                // Second repeat: filtered twice (noticeably darker)
-                // Third repeat: filtered three times (quite dark)
-                // ...and so on.
-                let filtered = filter.process(delayed_sample);
-
-                // Step 3: SCALE by the feedback amount.
-                //
-                // This controls how loud each repeat is relative to
-                // the one before it. With feedback = 0.5:
-                //   1st repeat: 50% of original volume
-                //   2nd repeat: 25% (50% of 50%)
-                //   3rd repeat: 12.5% (50% of 25%)
-                //
-                // The signal decays geometrically. Higher feedback =
-                // slower decay = more audible repeats.
-                let feedback_sample = filtered * feedback;
-
-                // Step 4: WRITE (input + feedback) into the ring buffer.
-                //
-                // The current input sample enters the delay line, along
-                // with the feedback signal from the previous iteration
-                // of the loop. This is what creates the recursion:
-                // output feeds back into input, producing echoes of echoes.
-                let input_sample = *sample;
-                delay_line.write(input_sample + feedback_sample);
-
-                // Step 5: MIX dry (original) and wet (delayed) signals.
-                //
-                // This is a simple linear crossfade:
-                //   output = dry * (1 - mix) + wet * mix
-                //
-                //   mix = 0.0 → output = input (no delay audible)
-                //   mix = 0.5 → output = 50% input + 50% delayed
-                //   mix = 1.0 → output = delayed only (input silent)
-                *sample = input_sample * (1.0 - mix) + delayed_sample * mix;
-
-                // Step 6: ADVANCE the ring buffer's write position.
-                //
-                // Move the "write head" forward by one sample, ready for
-                // the next sample. The delay line handles the wrapping
-                // internally (position resets to 0 at the end of the buffer).
-                delay_line.advance();
-            }
+        // Validators send empty buffers. There's nothing to process, and
+        // handing over the transport and parameters now would act on a
+        // block that never plays (a stop seen here, say, instead of on
+        // the next real block), so leave everything for that block.
+        if buffer.samples() == 0 {
+            return ProcessStatus::Normal;
         }
-
-        // Tell the host how long our effect tail is so it keeps calling
-        // process() after the input goes silent (e.g., when a region ends
-        // or the track is muted). Without this, the delay echoes would be
-        // cut off abruptly.
-        //
-        // The tail length depends on how many repeats it takes for the
-        // feedback loop to decay to -60 dB (inaudible). Each repeat is
-        // attenuated by the feedback factor, so after N repeats the level
-        // is feedback^N. Solving feedback^N = 0.001 (-60 dB):
-        //
-        //   N = log(0.001) / log(feedback)
-        //
-        // Multiply N by the delay time in samples to get the tail length.
-        let delay_ms = self.params.delay_time.smoothed.next();
-        let feedback = self.params.feedback.smoothed.next();
-        let delay_samps = calculate_delay_samples(delay_ms, self.sample_rate);
-
-        let tail_samples = if feedback > 0.001 {
-            let repeats = -3.0 / feedback.log10(); // log10(0.001) = -3
-            (repeats * delay_samps) as u32
-        } else {
-            // With no feedback, just one delay period for the single echo.
-            delay_samps as u32
+        let started = self.measure_load.then(Instant::now);
+        let transport = Self::engine_transport(context.transport());
+        self.engine.set_transport(transport);
+        let params = self.params.delay_params();
+        self.engine
+            .set_automation_smoothing(self.params.automation_smoothing());
+        self.engine.set_params(&params);
+        let (tempo, source) = self.engine.sync_tempo();
+        self.sync_handover.publish_tempo(tempo, source);
+        let (send, returned, key) = match (aux.outputs.first_mut(), &mut *aux.inputs) {
+            (Some(send), [returned, key, ..]) => (Some(send), Some(returned), Some(key)),
+            (Some(send), [returned]) => (Some(send), Some(returned), None),
+            (None, [key, ..]) => (None, None, Some(key)),
+            (_, _) => (None, None, None),
         };
+        // A port the layout doesn't have is a set of no channels.
+        let (send, returned, key) = (
+            send.map_or(Default::default(), |send| send.as_slice()),
+            returned.map_or(Default::default(), |returned| returned.as_slice()),
+            key.map_or(Default::default(), |key| key.as_slice()),
+        );
+        // A block longer than the host declared goes through in pieces
+        // (see `block_chunks`), so nothing sized to it runs short.
+        let engine = &mut self.engine;
+        block_chunks::for_each_chunk(
+            buffer.as_slice(),
+            [send, returned, key],
+            self.max_block_len,
+            |main, [send, returned, key]| {
+                let key = (!key.is_empty()).then_some(&*key);
+                if send.is_empty() || returned.is_empty() {
+                    engine.process_with_key(main, key);
+                } else {
+                    engine.process_with_external_loop(main, send, returned, key);
+                }
+            },
+        );
+        self.loop_meter.publish(self.engine.loop_reading());
+        self.time_readout
+            .publish(self.engine.current_values().delay_ms);
+
+        // Print Tail: copy what's ringing at the end of this block, and
+        // leave the rendering to the background thread.
+        if self.tail_print.capture(&self.engine, &params) {
+            context.execute_background(Task::PrintTail);
+        }
 
-        ProcessStatus::Tail(tail_samples)
-    }
-}
+        // Economy takes effect from the next block, once a run of blocks
+        // has been slow (see `load_monitor`).
+        if let Some(started) = started {
+            let load = load_monitor::block_load(
+                started.elapsed(),
+                buffer.samples(),
+                self.engine.sample_rate(),
+            );
+            let economy = self.load_monitor.record(load);
+            self.engine
+                .set_economy(economy && self.params.auto_economy.load(Ordering::Relaxed));
+            self.load_indicator
+                .publish(load, self.engine.economy(), self.load_monitor.overloads());
+        }
 
-const fn calculate_delay_samples(delay_ms: f32, sample_rate: f32) -> f32 {
-    delay_ms * sample_rate / 1000.0
+        // Tell the host how long our effect tail is so it keeps calling
+        // process() after the input goes silent, and when it's over.
+        ring_out::tail_status(&self.engine)
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────
@@ -403,9 +564,30 @@ const fn calculate_delay_samples(delay_ms: f32, sample_rate: f32) -> f32 {
 // These traits tell nih-plug how to package the plugin for different
 // plugin formats. We support both CLAP and VST3.
 
+/// A reverse-domain-notation ID, unique to this plugin. **Change this
+/// if you fork the plugin** — the VST3 class ID follows it (see
+/// [`plugin_id`]).
+const CLAP_ID: &str = plugin_id::ORIGINAL_CLAP_ID;
+
+/// Bump this when a release can't load sessions saved by the previous
+/// one; the VST3 class ID changes with it.
+const ID_NAMESPACE: &str = plugin_id::ORIGINAL_NAMESPACE;
+
+/// The VST3 class ID, derived from [`CLAP_ID`] and [`ID_NAMESPACE`].
+const VST3_CLASS_ID: [u8; 16] = plugin_id::vst3_class_id(CLAP_ID, ID_NAMESPACE);
+
+// A release build of a renamed plugin must not ship with the original's
+// class ID — the derivation guarantees it unless someone swaps the
+// derived ID for a hand-written one.
+#[cfg(not(debug_assertions))]
+const _: () = assert!(
+    plugin_id::str_eq(CLAP_ID, plugin_id::ORIGINAL_CLAP_ID)
+        || !plugin_id::bytes_eq(&VST3_CLASS_ID, &plugin_id::ORIGINAL_VST3_CLASS_ID),
+    "a renamed plugin is reusing the original VST3 class ID"
+);
+
 impl ClapPlugin for LovelessDelay {
-    // A reverse-domain-notation ID, unique to this plugin.
-    const CLAP_ID: &'static str = "com.loveless-audio.loveless-delay-v1";
+    const CLAP_ID: &'static str = CLAP_ID;
     const CLAP_DESCRIPTION: Option<&'static str> =
         Some("A delay plugin with feedback filtering, built for learning DSP");
     const CLAP_MANUAL_URL: Option<&'static str> = None;
@@ -419,12 +601,11 @@ impl ClapPlugin for LovelessDelay {
 
 impl Vst3Plugin for LovelessDelay {
     // A 16-byte class ID that must be globally unique across all VST3
-    // plugins ever made. For a production plugin, use a proper UUID.
-    // For our learning project, this ASCII-based ID is sufficient.
-    //
-    // The `*b"..."` syntax creates a `[u8; 16]` from a 16-character
-    // ASCII string literal. Each character becomes one byte.
-    const VST3_CLASS_ID: [u8; 16] = *b"LvlssDelay__v001";
+    // plugins ever made. It's derived from the CLAP ID at compile time,
+    // so a fork that changes its CLAP ID gets a new one automatically.
+    // The original plugin keeps the ASCII ID it shipped with,
+    // `*b"LvlssDelay__v001"`, so existing sessions still find it.
+    const VST3_CLASS_ID: [u8; 16] = VST3_CLASS_ID;
 
     // Tell the host this is a delay effect so it appears in the
     // correct category in the plugin browser.
@@ -453,3 +634,160 @@ nih_export_vst3!(LovelessDelay);
 // This generates a `GetPluginFactoryAUV2` entry point that macOS uses
 // to discover the plugin as an Audio Unit component.
 clap_wrapper::export_auv2!();
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+
+    /// The VST3 class ID is 16 bytes derived from the CLAP ID — the
+    /// original one for the original plugin — and tracks it.
+    #[test]
+    fn test_vst3_class_id_follows_clap_id() {
+        assert_eq!(
+            <LovelessDelay as Vst3Plugin>::VST3_CLASS_ID,
+            plugin_id::vst3_class_id(<LovelessDelay as ClapPlugin>::CLAP_ID, ID_NAMESPACE)
+        );
+        assert_eq!(<LovelessDelay as Vst3Plugin>::VST3_CLASS_ID.len(), 16);
+        if CLAP_ID == plugin_id::ORIGINAL_CLAP_ID && ID_NAMESPACE == "v1" {
+            assert_eq!(VST3_CLASS_ID, *b"LvlssDelay__v001");
+        } else {
+            assert_ne!(VST3_CLASS_ID, plugin_id::ORIGINAL_VST3_CLASS_ID);
+        }
+    }
+
+    /// The plugin's parameter defaults and the engine's defaults must
+    /// describe the same starting sound.
+    #[test]
+    fn test_param_defaults_match_engine_defaults() {
+        assert_eq!(
+            PluginParams::default().delay_params(),
+            DelayParams::default()
+        );
+    }
+
+    /// With preset loudness matching on, the engine gets the hidden trim
+    /// for the current settings plus the stored preset trim; off, none.
+    #[test]
+    fn test_preset_loudness_match_sets_output_trim() {
+        let params = PluginParams::default();
+        params
+            .preset_trim
+            .store(1.5_f32.to_bits(), Ordering::Relaxed);
+        assert_eq!(params.delay_params().output_trim_db, 0.0);
+
+        params.preset_loudness_match.store(true, Ordering::Relaxed);
+        let expected = presets::loudness_match_db(&DelayParams::default(), 1.5);
+        assert_ne!(expected, 0.0);
+        assert_eq!(params.delay_params().output_trim_db, expected);
+    }
+
+    /// A session saved with a momentary switch on reopens with it off;
+    /// everything else loads as saved.
+    #[test]
+    fn test_momentary_switches_load_off() {
+        let mut state = PluginState {
+            version: String::new(),
+            params: [
+                ("wet_solo", ParamValue::Bool(true)),
+                ("duck_listen", ParamValue::Bool(true)),
+                ("clear", ParamValue::Bool(true)),
+                ("hold", ParamValue::Bool(true)),
+                ("double_time", ParamValue::Bool(true)),
+                ("half_time", ParamValue::Bool(true)),
+                ("limiter", ParamValue::Bool(true)),
+            ]
+            .into_iter()
+            .map(|(id, value)| (id.to_string(), value))
+            .collect(),
+            fields: Default::default(),
+        };
+        LovelessDelay::filter_state(&mut state);
+
+        assert_eq!(state.params["wet_solo"], ParamValue::Bool(false));
+        assert_eq!(state.params["duck_listen"], ParamValue::Bool(false));
+        assert_eq!(state.params["clear"], ParamValue::Bool(false));
+        assert_eq!(state.params["hold"], ParamValue::Bool(false));
+        assert_eq!(state.params["double_time"], ParamValue::Bool(false));
+        assert_eq!(state.params["half_time"], ParamValue::Bool(false));
+        assert_eq!(state.params["limiter"], ParamValue::Bool(true));
+    }
+
+    /// On the 10 ms grid, automation aimed at 333 ms shows as 330 ms,
+    /// a typed-in 333 lands on 330, and 330 ms is what plays.
+    #[test]
+    fn test_time_snap_is_shown_and_played() {
+        let params = PluginParams::default();
+        params
+            .shown_time_snap
+            .store(params::DelaySnap::Ms10.to_index(), Ordering::Relaxed);
+        let delay_time = &params.delay_time;
+        assert_eq!(
+            delay_time.normalized_value_to_string(delay_time.preview_normalized(333.0), true),
+            "330.0 ms"
+        );
+        assert_eq!(
+            delay_time.string_to_normalized_value("333 ms"),
+            Some(delay_time.preview_normalized(330.0))
+        );
+
+        let mut engine = LovelessDelayEngine::new(48000.0, 2);
+        engine.set_params(&DelayParams {
+            delay_ms: 333.0,
+            time_snap: params::DelaySnap::Ms10.into(),
+            ..params.delay_params()
+        });
+        assert_eq!(engine.current_values().delay_ms, 330.0);
+    }
+
+    /// CLAP modulation as nih-plug applies it: an offset in normalized
+    /// space around the knob, clamped to the range.
+    fn modulate(param: &FloatParam, knob: f32, offset: f32) -> f32 {
+        let range = param.range();
+        range.unnormalize((range.normalize(knob) + offset).clamp(0.0, 1.0))
+    }
+
+    /// However hard the continuous parameters are modulated, and from
+    /// wherever the knob sits, they stay within their ranges — and the
+    /// feedback reaching the loop never exceeds [`MAX_FEEDBACK`].
+    #[test]
+    fn test_modulation_is_clamped() {
+        let params = PluginParams::default();
+        let cases: [(&FloatParam, f32, f32); 4] = [
+            (&params.delay_time, MIN_DELAY_MS, MAX_DELAY_MS),
+            (&params.feedback, 0.0, MAX_FEEDBACK),
+            (&params.mix, 0.0, 1.0),
+            (&params.filter_cutoff, 200.0, 20000.0),
+        ];
+        for (param, min, max) in cases {
+            for knob in [min, param.value(), max] {
+                for offset in [-1.0, -0.3, 0.0, 0.3, 1.0] {
+                    let value = modulate(param, knob, offset);
+                    assert!(
+                        (min..=max).contains(&value),
+                        "{}: knob {knob} + {offset} gave {value}",
+                        param.name()
+                    );
+                }
+            }
+        }
+
+        // Full-depth modulation on a maxed-out knob, and a host that
+        // overshoots the range anyway: the loop stays at the ceiling.
+        let full = modulate(&params.feedback, MAX_FEEDBACK, 1.0);
+        for feedback in [full, 1.5] {
+            let mut engine = LovelessDelayEngine::new(48000.0, 2);
+            engine.set_params(&DelayParams {
+                feedback,
+                ..params.delay_params()
+            });
+            let (mut left, mut right) = ([0.5; 256], [0.5; 256]);
+            engine.process(&mut [&mut left, &mut right]);
+            assert!(engine.loop_gain() <= MAX_FEEDBACK, "{}", engine.loop_gain());
+        }
+    }
+}
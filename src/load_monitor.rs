@@ -0,0 +1,247 @@
+//! # Falling Back to Economy When the Deadline Is Tight
+//!
+//! The host gives each block a deadline: the block's own length in
+//! time. A 256-sample block at 48 kHz has 5.3 ms to be processed —
+//! shared with every other plugin in the session — and a block that
+//! misses it is an *xrun*: a dropout everyone hears. Rather than glitch,
+//! the plugin can do a little less work. The engine's economy mode (see
+//! [`LovelessDelayEngine::set_economy()`](crate::LovelessDelayEngine::set_economy))
+//! spaces its control ticks four times further apart, which is nearly
+//! all the per-sample work that can go without changing the sound.
+//!
+//! ## Timing a Block
+//!
+//! `process()` reads the clock as it starts and again as it ends, and
+//! divides the difference by the block's length in time: its *load*.
+//! One read alone could only time the gap between two calls, which is
+//! the host's pace, not this plugin's cost. Two reads of a monotonic
+//! clock cost a few tens of nanoseconds, nothing beside the block.
+//!
+//! ## Deciding
+//!
+//! A single slow block proves nothing — the OS may have paged, or
+//! another thread got the core — so the [`LoadMonitor`] waits for a run
+//! of them, and once in economy it waits far longer before trusting the
+//! headroom again:
+//!
+//! ```text
+//! load ≥ 0.7 for 4 blocks in a row     → economy on
+//! load < 0.35 for 400 blocks in a row  → economy off
+//! ```
+//!
+//! The gap between the two thresholds is the hysteresis: economy itself
+//! lowers the load, and without the gap that drop alone would switch it
+//! straight back off, over and over. The monitor never reads the clock
+//! itself, so its decisions can be tested with made-up loads.
+//!
+//! ## Offline Renders
+//!
+//! A bounce has no deadline: the host calls `process()` as fast as the
+//! plugin can go, and may give a slow block all the time it needs.
+//! nih-plug says when a render is offline, and the plugin doesn't
+//! measure then, so a bounce always sounds like the full engine.
+//!
+//! ## For the Editor
+//!
+//! Whether the engine is in economy, the last load and the count of
+//! overloaded blocks go into a [`LoadIndicator`], atomics an editor can
+//! read to light an "economy" lamp. There's no editor yet to draw it.
+//!
+//! Economy is an option, off unless the user turns it on (see
+//! `PluginParams::auto_economy`): some would rather hear the odd dropout
+//! than a coarser sound. The monitor runs either way, so the count of
+//! overloaded blocks is there to diagnose dropouts with.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+/// The load at or above which a block counts as overloaded: 70% of its
+/// deadline, leaving the rest for the host and the other plugins.
+pub const OVERLOAD: f32 = 0.7;
+
+/// How many overloaded blocks in a row switch economy on.
+pub const OVERLOADED_BLOCKS: u32 = 4;
+
+/// The load below which a block counts as relaxed, once in economy.
+pub const RELAXED: f32 = 0.35;
+
+/// How many relaxed blocks in a row switch economy off again: a couple
+/// of seconds at common block sizes.
+pub const RELAXED_BLOCKS: u32 = 400;
+
+/// A block's load: the time `elapsed` processing it over the time its
+/// `samples` last at `sample_rate`.
+pub fn block_load(elapsed: Duration, samples: usize, sample_rate: f32) -> f32 {
+    let deadline = samples as f32 / sample_rate;
+    if deadline > 0.0 {
+        elapsed.as_secs_f32() / deadline
+    } else {
+        0.0
+    }
+}
+
+/// Whether the engine should be in economy, from the loads of the
+/// blocks so far. See [the module docs](self#deciding).
+#[derive(Debug, Default)]
+pub struct LoadMonitor {
+    economy: bool,
+
+    /// How many blocks in a row have pointed the other way.
+    streak: u32,
+
+    /// How many overloaded blocks there have been in all.
+    overloads: u32,
+}
+
+impl LoadMonitor {
+    /// Count one block's load, and return whether to run in economy
+    /// from now on.
+    pub fn record(&mut self, load: f32) -> bool {
+        if load >= OVERLOAD {
+            self.overloads = self.overloads.saturating_add(1);
+        }
+        let (against, needed) = if self.economy {
+            (load < RELAXED, RELAXED_BLOCKS)
+        } else {
+            (load >= OVERLOAD, OVERLOADED_BLOCKS)
+        };
+        self.streak = if against { self.streak + 1 } else { 0 };
+        if self.streak >= needed {
+            self.economy = !self.economy;
+            self.streak = 0;
+        }
+        self.economy
+    }
+
+    /// Whether the engine should be in economy.
+    pub fn economy(&self) -> bool {
+        self.economy
+    }
+
+    /// How many overloaded blocks there have been since the monitor was
+    /// made, economy or not.
+    pub fn overloads(&self) -> u32 {
+        self.overloads
+    }
+}
+
+/// Whether the engine is in economy, the last block's load and the
+/// overload count, written by the audio thread and read by the editor.
+#[derive(Debug, Default)]
+pub struct LoadIndicator {
+    economy: AtomicBool,
+
+    /// The load's `f32` bits.
+    load: AtomicU32,
+
+    overloads: AtomicU32,
+}
+
+impl LoadIndicator {
+    /// Store the latest load, whether the engine is in economy, and the
+    /// monitor's count of overloaded blocks. Safe on the audio thread:
+    /// three atomic stores.
+    pub fn publish(&self, load: f32, economy: bool, overloads: u32) {
+        self.load.store(load.to_bits(), Ordering::Relaxed);
+        self.economy.store(economy, Ordering::Relaxed);
+        self.overloads.store(overloads, Ordering::Relaxed);
+    }
+
+    /// Whether the engine is in economy.
+    pub fn economy(&self) -> bool {
+        self.economy.load(Ordering::Relaxed)
+    }
+
+    /// The last block's load (1.0 is the whole deadline).
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.load.load(Ordering::Relaxed))
+    }
+
+    /// How many blocks have come close to missing their deadline, for a
+    /// diagnostics readout.
+    pub fn overloads(&self) -> u32 {
+        self.overloads.load(Ordering::Relaxed)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 256-sample block at 48 kHz has 5.33 ms: half of that is a load
+    /// of 0.5.
+    #[test]
+    fn test_block_load() {
+        let load = block_load(Duration::from_micros(2667), 256, 48000.0);
+        assert!((load - 0.5).abs() < 1e-3, "{load}");
+        assert_eq!(block_load(Duration::from_millis(1), 0, 48000.0), 0.0);
+    }
+
+    /// Economy needs a run of overloaded blocks: isolated spikes, however
+    /// high, don't engage it.
+    #[test]
+    fn test_spikes_do_not_engage() {
+        let mut monitor = LoadMonitor::default();
+        for _ in 0..100 {
+            for _ in 1..OVERLOADED_BLOCKS {
+                assert!(!monitor.record(3.0));
+            }
+            assert!(!monitor.record(0.2));
+        }
+        assert_eq!(monitor.overloads(), 100 * (OVERLOADED_BLOCKS - 1));
+
+        for _ in 1..OVERLOADED_BLOCKS {
+            assert!(!monitor.record(OVERLOAD));
+        }
+        assert!(monitor.record(OVERLOAD));
+    }
+
+    /// Once on, economy stays on through loads between the thresholds,
+    /// and through a relaxed run cut short, and goes off only after a
+    /// full run of relaxed blocks.
+    #[test]
+    fn test_disengages_with_hysteresis() {
+        let mut monitor = LoadMonitor::default();
+        for _ in 0..OVERLOADED_BLOCKS {
+            monitor.record(0.9);
+        }
+        assert!(monitor.economy());
+
+        // Economy took the load down, but not below the relaxed line.
+        for _ in 0..10 * RELAXED_BLOCKS {
+            assert!(monitor.record(0.5));
+        }
+        for _ in 1..RELAXED_BLOCKS {
+            assert!(monitor.record(0.1));
+        }
+        assert!(monitor.record(0.5));
+        for _ in 1..RELAXED_BLOCKS {
+            assert!(monitor.record(0.1));
+        }
+        assert!(!monitor.record(0.1));
+
+        // And back on, given another run.
+        for _ in 1..OVERLOADED_BLOCKS {
+            assert!(!monitor.record(0.8));
+        }
+        assert!(monitor.record(0.8));
+    }
+
+    /// What's published is what's read back.
+    #[test]
+    fn test_indicator_round_trips() {
+        let mut monitor = LoadMonitor::default();
+        let indicator = LoadIndicator::default();
+        for _ in 0..OVERLOADED_BLOCKS {
+            monitor.record(1.2);
+        }
+        indicator.publish(1.2, monitor.economy(), monitor.overloads());
+        assert!(indicator.economy());
+        assert_eq!(indicator.load(), 1.2);
+        assert_eq!(indicator.overloads(), OVERLOADED_BLOCKS);
+    }
+}
@@ -0,0 +1,250 @@
+//! # Metering the Feedback Loop
+//!
+//! Two numbers say more about a delay's state than any knob: how loud
+//! the loop is right now, and how many more repeats it will take to die
+//! away. Set the feedback high, stop playing, and the second one counts
+//! down — or doesn't, if the loop is held or frozen.
+//!
+//! - The **loop level** is the level coming out of the delay lines, in
+//!   dBFS, as the loop damping's envelope follower measures it (the
+//!   louder side, for stereo).
+//! - **Repeats remaining** is how many more trips round the loop it
+//!   takes that level to fall below [`METER_FLOOR_DB`], at the current
+//!   loop gain.
+//!
+//! ## Counting the Repeats
+//!
+//! Every trip multiplies the loop by its gain `g`, which in decibels is
+//! a fixed step down. So the count is a division:
+//!
+//! ```text
+//! step      = 20 · log10(g)                     dB per repeat (negative)
+//! remaining = ⌈(floor − level) / step⌉
+//!
+//! level −12 dBFS, g = 0.5 (−6.02 dB):   ⌈−48 / −6.02⌉ = 8 repeats
+//! ```
+//!
+//! The loop gain is the engine's
+//! [`loop_gain()`](crate::LovelessDelayEngine::loop_gain): the applied
+//! feedback times the *peak* gain of the stages in the loop. Filters
+//! take more than that off most signals, so the count is an upper bound
+//! — it's never lower than what's really left. At a loop gain of 1.0
+//! (a held or frozen loop) the level never falls, and there's no count.
+//!
+//! ## Why Not Output Parameters?
+//!
+//! Some hosts (Bitwig, for one) can show a plugin's read-only "output"
+//! parameters next to its knobs. nih-plug has no such thing: every
+//! parameter is an input the host automates, and the audio thread may
+//! not set one. CLAP's param-indication extension doesn't help either —
+//! it lets a host tell the plugin which knobs a controller is mapped
+//! to, not the other way round. So the readings go where an editor can
+//! find them instead:
+//!
+//! ```text
+//! audio thread                              editor (GUI thread)
+//! process() ─► LoopMeter::publish() ─► atomics ─► LoopMeter::reading()
+//!              two stores per block                once per frame
+//! ```
+//!
+//! A [`LoopMeter`] is a pair of atomics, so publishing costs two stores
+//! and can't allocate or block, and the editor reads them whenever it
+//! redraws. The plugin publishes after every block. That's less often
+//! than the engine's control tick, but more often than any screen
+//! redraws, and the envelope follower behind the level has already
+//! smoothed it over far longer than a block. There's no editor yet to
+//! draw them; this is the half that doesn't depend on one.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The level below which the loop counts as died away, in dBFS. Sixty
+/// decibels down is the usual measure of a reverb's or an echo's tail.
+pub const METER_FLOOR_DB: f32 = -60.0;
+
+/// The lowest level the meter reports, in dBFS: anything quieter reads
+/// as this.
+pub const METER_MIN_DB: f32 = -120.0;
+
+/// A snapshot of the feedback loop, for display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopReading {
+    /// The loop level in dBFS, no lower than [`METER_MIN_DB`].
+    pub level_db: f32,
+
+    /// How many more repeats until the loop falls below
+    /// [`METER_FLOOR_DB`], or `None` while it isn't decaying at all.
+    pub repeats_remaining: Option<u32>,
+}
+
+impl LoopReading {
+    /// The reading for a loop at `level` (a linear amplitude) going
+    /// round at `loop_gain`. See [the module docs](self).
+    pub fn measure(level: f32, loop_gain: f32) -> Self {
+        let level_db = (20.0 * level.abs().log10()).max(METER_MIN_DB);
+        let repeats_remaining = if level_db <= METER_FLOOR_DB {
+            Some(0)
+        } else if loop_gain >= 1.0 {
+            None
+        } else if loop_gain <= 0.0 {
+            // Nothing goes back in: what's in the line is the last repeat.
+            Some(0)
+        } else {
+            let step_db = 20.0 * loop_gain.log10();
+            Some(((METER_FLOOR_DB - level_db) / step_db).ceil() as u32)
+        };
+        Self {
+            level_db,
+            repeats_remaining,
+        }
+    }
+}
+
+impl Default for LoopReading {
+    /// An empty loop.
+    fn default() -> Self {
+        Self {
+            level_db: METER_MIN_DB,
+            repeats_remaining: Some(0),
+        }
+    }
+}
+
+/// The latest [`LoopReading`], written by the audio thread and read by
+/// the editor.
+#[derive(Debug)]
+pub struct LoopMeter {
+    /// The level's `f32` bits.
+    level_db: AtomicU32,
+
+    /// The count, with `u32::MAX` standing for `None`.
+    repeats_remaining: AtomicU32,
+}
+
+impl Default for LoopMeter {
+    fn default() -> Self {
+        let empty = LoopReading::default();
+        Self {
+            level_db: AtomicU32::new(empty.level_db.to_bits()),
+            repeats_remaining: AtomicU32::new(encode_repeats(empty.repeats_remaining)),
+        }
+    }
+}
+
+impl LoopMeter {
+    /// Store a reading. Safe on the audio thread: two atomic stores.
+    ///
+    /// The two values are stored separately, so a reader can now and
+    /// then see a level from one block with the count from the next.
+    /// For a display that redraws a moment later anyway, that's fine.
+    pub fn publish(&self, reading: LoopReading) {
+        self.level_db
+            .store(reading.level_db.to_bits(), Ordering::Relaxed);
+        self.repeats_remaining
+            .store(encode_repeats(reading.repeats_remaining), Ordering::Relaxed);
+    }
+
+    /// The latest reading.
+    pub fn reading(&self) -> LoopReading {
+        let repeats = self.repeats_remaining.load(Ordering::Relaxed);
+        LoopReading {
+            level_db: f32::from_bits(self.level_db.load(Ordering::Relaxed)),
+            repeats_remaining: (repeats != u32::MAX).then_some(repeats),
+        }
+    }
+}
+
+fn encode_repeats(repeats: Option<u32>) -> u32 {
+    repeats.map_or(u32::MAX, |count| count.min(u32::MAX - 1))
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DelayParams, LovelessDelayEngine};
+    use std::sync::Arc;
+
+    /// The count is the level's distance to the floor in whole repeats,
+    /// rounded up; a held loop has none, and an empty one none left.
+    #[test]
+    fn test_repeats_remaining() {
+        let db = |db: f32| 10.0_f32.powf(db / 20.0);
+        let cases = [
+            (db(-12.0), 0.5, Some(8)),
+            (db(0.0), 0.5, Some(10)),
+            // Exactly 6 repeats of −10 dB take 0 dBFS to the floor.
+            (db(0.0), db(-10.0), Some(6)),
+            (db(-59.0), 0.9, Some(2)),
+            (db(-60.0), 0.9, Some(0)),
+            (0.0, 0.9, Some(0)),
+            (db(-20.0), 0.0, Some(0)),
+            (db(-20.0), 1.0, None),
+        ];
+        for (level, loop_gain, want) in cases {
+            let reading = LoopReading::measure(level, loop_gain);
+            assert_eq!(
+                reading.repeats_remaining, want,
+                "level {level}, loop gain {loop_gain}: {reading:?}"
+            );
+        }
+        assert!((LoopReading::measure(db(-12.0), 0.5).level_db + 12.0).abs() < 1e-4);
+        assert_eq!(LoopReading::measure(0.0, 0.5).level_db, METER_MIN_DB);
+    }
+
+    /// What the audio thread publishes, another thread reads back,
+    /// including "no count".
+    #[test]
+    fn test_publish_and_read_across_threads() {
+        let meter = Arc::new(LoopMeter::default());
+        assert_eq!(meter.reading(), LoopReading::default());
+
+        let held = LoopReading {
+            level_db: -6.5,
+            repeats_remaining: None,
+        };
+        let writer = {
+            let meter = Arc::clone(&meter);
+            std::thread::spawn(move || meter.publish(held))
+        };
+        writer.join().unwrap();
+        assert_eq!(meter.reading(), held);
+
+        let decaying = LoopReading::measure(0.25, 0.5);
+        meter.publish(decaying);
+        assert_eq!(meter.reading(), decaying);
+    }
+
+    /// The engine's reading of a ringing loop counts down as it decays,
+    /// and settles on an empty loop.
+    #[test]
+    fn test_engine_reading_counts_down() {
+        const SAMPLE_RATE: f32 = 48000.0;
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(&DelayParams {
+            delay_ms: 100.0,
+            feedback: 0.5,
+            ..DelayParams::default()
+        });
+
+        let mut counts = Vec::new();
+        for block in 0..250 {
+            // A 100 ms burst, then silence while the loop rings out.
+            let gain = if block < 10 { 0.5 } else { 0.0 };
+            let mut left: Vec<f32> = (0..480).map(|n| gain * (n as f32 * 0.05).sin()).collect();
+            let mut right = left.clone();
+            engine.process(&mut [&mut left, &mut right]);
+            counts.push(engine.loop_reading().repeats_remaining.unwrap());
+        }
+        let loudest = *counts.iter().max().unwrap();
+        assert!((5..=12).contains(&loudest), "{counts:?}");
+        let peak_at = counts.iter().position(|c| *c == loudest).unwrap();
+        assert!(
+            counts[peak_at..].windows(2).all(|w| w[1] <= w[0]),
+            "{counts:?}"
+        );
+        assert_eq!(counts.last(), Some(&0), "{counts:?}");
+    }
+}
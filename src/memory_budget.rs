@@ -0,0 +1,212 @@
+//! # Sharing Memory Between Instances
+//!
+//! One engine's buffers are modest — about 1.6 MB for stereo at 48 kHz —
+//! but they grow with the sample rate and the channel count, and a
+//! session can hold dozens of instances: at 192 kHz in a 7.1 layout,
+//! each takes about 17 MB. Every instance lives in the same host process,
+//! so rather than each allocating blindly, they share one budget:
+//!
+//! ```text
+//! initialize()
+//!   give back this instance's claim
+//!   available = budget − what the other instances hold
+//!   build an engine that fits (a shorter longest delay if need be)
+//!   claim what it took
+//! ```
+//!
+//! The engine does the fitting (see
+//! [`LovelessDelayEngine::with_memory_budget()`](crate::LovelessDelayEngine::with_memory_budget)):
+//! short of memory, it gives up delay time rather than refusing to run,
+//! so an instance over budget is a limited plugin, never a failed
+//! `initialize()`. A [`MemoryClaim`] gives its bytes back when it's
+//! dropped, so closing one instance makes room for the next.
+//!
+//! Each instance also publishes what it holds to a [`MemoryUsage`], for
+//! an editor's "about" panel. That includes Print Tail's snapshot, a
+//! second set of buffers made only once a print is asked for (see
+//! [`tail_print`](crate::tail_print)), which claims its share too.
+
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// The memory all instances in a process may share by default: 256 MB,
+/// room for fifteen 7.1 instances at 192 kHz, or over a hundred stereo
+/// ones at 48 kHz.
+pub const DEFAULT_BUDGET_BYTES: usize = 256 * 1024 * 1024;
+
+/// The budget every instance in this process shares.
+pub static SHARED: MemoryBudget = MemoryBudget::new(DEFAULT_BUDGET_BYTES);
+
+/// A limit on the buffer memory a group of engines may hold between
+/// them, and how much of it they do.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    limit: AtomicUsize,
+    claimed: AtomicUsize,
+}
+
+impl MemoryBudget {
+    /// A budget of `limit_bytes` with nothing claimed.
+    pub const fn new(limit_bytes: usize) -> Self {
+        Self {
+            limit: AtomicUsize::new(limit_bytes),
+            claimed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Change the limit. Engines already built keep what they have; the
+    /// next ones to be built see the new limit.
+    pub fn set_limit(&self, limit_bytes: usize) {
+        self.limit.store(limit_bytes, Ordering::Relaxed);
+    }
+
+    /// How many bytes are claimed.
+    pub fn claimed(&self) -> usize {
+        self.claimed.load(Ordering::Relaxed)
+    }
+
+    /// How many bytes are left to claim (none, if the claims are over
+    /// the limit).
+    pub fn available(&self) -> usize {
+        self.limit
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.claimed())
+    }
+
+    /// Record `bytes` as held until the claim is dropped. Always
+    /// succeeds: the memory is already allocated, and the budget only
+    /// steers what's built next.
+    pub fn claim(&self, bytes: usize) -> MemoryClaim<'_> {
+        self.claimed.fetch_add(bytes, Ordering::Relaxed);
+        MemoryClaim {
+            budget: self,
+            bytes,
+        }
+    }
+}
+
+/// Bytes held against a [`MemoryBudget`], given back on drop.
+#[derive(Debug)]
+pub struct MemoryClaim<'a> {
+    budget: &'a MemoryBudget,
+    bytes: usize,
+}
+
+impl MemoryClaim<'_> {
+    /// How many bytes this claim holds.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Drop for MemoryClaim<'_> {
+    fn drop(&mut self) {
+        self.budget.claimed.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// What one instance holds, published from `initialize()` for an editor
+/// to show.
+#[derive(Debug, Default)]
+pub struct MemoryUsage {
+    bytes: AtomicUsize,
+    tail_snapshot_bytes: AtomicUsize,
+    max_delay_ms_bits: AtomicU32,
+}
+
+impl MemoryUsage {
+    /// Store the engine's buffer memory, in bytes, and the longest delay
+    /// that bought.
+    pub fn publish(&self, bytes: usize, max_delay_ms: f32) {
+        self.bytes.store(bytes, Ordering::Relaxed);
+        self.max_delay_ms_bits
+            .store(max_delay_ms.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Store the memory Print Tail's snapshot holds, in bytes: none
+    /// until a print is asked for.
+    pub fn publish_tail_snapshot(&self, bytes: usize) {
+        self.tail_snapshot_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// The instance's buffer memory, in bytes: the engine's and the
+    /// snapshot's.
+    pub fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed) + self.tail_snapshot_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The longest delay the instance can play, in milliseconds. Short
+    /// of [`MAX_DELAY_MS`](crate::MAX_DELAY_MS) only when the budget ran
+    /// out.
+    pub fn max_delay_ms(&self) -> f32 {
+        f32::from_bits(self.max_delay_ms_bits.load(Ordering::Relaxed))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LovelessDelayEngine, MAX_DELAY_MS, MIN_DELAY_MS};
+
+    /// Claims add up, and each gives its bytes back when dropped;
+    /// what's left never goes below nothing.
+    #[test]
+    fn test_claims_are_given_back() {
+        let budget = MemoryBudget::new(1000);
+        let first = budget.claim(600);
+        assert_eq!(budget.available(), 400);
+        {
+            let second = budget.claim(700);
+            assert_eq!(second.bytes(), 700);
+            assert_eq!(budget.claimed(), 1300);
+            assert_eq!(budget.available(), 0);
+        }
+        assert_eq!(budget.available(), 400);
+        drop(first);
+        assert_eq!(budget.claimed(), 0);
+
+        budget.set_limit(50);
+        assert_eq!(budget.available(), 50);
+    }
+
+    /// Instances built one after another against the same budget: the
+    /// first few get the full range, the next a shorter one, and the
+    /// last, with nothing left, the shortest — each a working engine.
+    #[test]
+    fn test_instances_share_the_budget() {
+        let one = LovelessDelayEngine::memory_bytes_for(48000.0, 2, MAX_DELAY_MS);
+        let budget = MemoryBudget::new(one * 5 / 2);
+        let mut claims = Vec::new();
+        let mut max_delays = Vec::new();
+        for _ in 0..4 {
+            let engine = LovelessDelayEngine::with_memory_budget(48000.0, 2, budget.available());
+            claims.push(budget.claim(engine.memory_bytes()));
+            max_delays.push(engine.max_delay_ms());
+        }
+        assert_eq!(max_delays[..2], [MAX_DELAY_MS; 2]);
+        assert!(
+            (MIN_DELAY_MS..MAX_DELAY_MS).contains(&max_delays[2]),
+            "{max_delays:?}"
+        );
+        assert_eq!(max_delays[3], MIN_DELAY_MS);
+
+        // Closing the first two makes room for the full range again.
+        claims.drain(..2);
+        let engine = LovelessDelayEngine::with_memory_budget(48000.0, 2, budget.available());
+        assert_eq!(engine.max_delay_ms(), MAX_DELAY_MS);
+    }
+
+    /// What's published is what's read back.
+    #[test]
+    fn test_usage_round_trips() {
+        let usage = MemoryUsage::default();
+        usage.publish(1_637_144, 1500.0);
+        assert_eq!(usage.bytes(), 1_637_144);
+        assert_eq!(usage.max_delay_ms(), 1500.0);
+        usage.publish_tail_snapshot(1_637_144);
+        assert_eq!(usage.bytes(), 2 * 1_637_144);
+    }
+}
@@ -0,0 +1,453 @@
+//! # Morphing Between A and B
+//!
+//! Store a sound as A — a tight slapback, say — and another as B — an
+//! ambient wash — and the **Morph** knob sweeps between them, every
+//! parameter at once, as slowly or as suddenly as it's automated.
+//!
+//! ## Normalized Space
+//!
+//! Each parameter moves in *normalized* space, the 0–1 of its knob's
+//! travel, not in its own units. Halfway there is halfway round the
+//! knob, so a skewed range morphs the way it turns:
+//!
+//! ```text
+//! Delay Time  A 500 ms   B 2000 ms    halfway: 1110.9 ms (not 1250)
+//! Filter L    A 8 kHz    B 20 kHz     halfway: 12968 Hz  (not 14000)
+//! ```
+//!
+//! A parameter with steps — a switch, a menu, a whole number — can't
+//! be anywhere in between, so it plays A below 50% and B from there on.
+//!
+//! ## Knobs on Top
+//!
+//! Morph never sets a parameter. The engine gets each one at its share
+//! of the way from A to B, worked out from the two snapshots, the way it
+//! gets a host's modulation; wherever the knobs were left, 0% plays A
+//! and 100% plays B. A knob that moves after that — automated on its
+//! own lane, say — moves the sound by as much as it has moved since it
+//! was last stored or recalled, its *home*:
+//!
+//! ```text
+//! played = A + morph × (B − A) + (knob − home)    in normalized space, held to 0–1
+//! ```
+//!
+//! So storing A, dialling in B and storing B sweeps from A to B with the
+//! knobs left where they are, and a knob's automation carries on working
+//! anywhere in the sweep, ends included. Nothing written, nothing for
+//! the host to record but the Morph lane itself. [`recall()`] moves the
+//! knobs onto a snapshot and makes it home; [`clear()`] forgets both
+//! snapshots, and the knobs play as they are again.
+//!
+//! ## Storage
+//!
+//! A snapshot is every parameter's normalized value by its ID, saved
+//! with the plugin's state, so a session reopens with both, and with
+//! the knobs' home (a snapshot too, of the last one stored or
+//! recalled). Morph itself and the momentary switches (see
+//! [`MOMENTARY_PARAM_IDS`]) aren't in them, and a parameter missing from
+//! either snapshot (one added since it was stored) doesn't morph.
+//!
+//! The audio thread can't look parameters up by ID without allocating,
+//! so the snapshots are boiled down to a [`MorphTable`] whenever they
+//! change: each morphing parameter's place in [`PluginParams`] (its
+//! offset from the start, the same wherever the struct is moved) with
+//! its A, B and home, sorted for a binary search. `initialize()`
+//! rebuilds it after a state has loaded.
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use nih_plug::prelude::{GuiContext, Param, ParamPtr, ParamSetter, Params};
+
+use crate::params::{PluginParams, MOMENTARY_PARAM_IDS};
+
+/// The ID of the Morph parameter.
+pub const MORPH_PARAM_ID: &str = "morph";
+
+/// A stored sound: each parameter's normalized value, by ID.
+pub type Snapshot = BTreeMap<String, f32>;
+
+/// One of the two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+}
+
+/// The parameters as they stand, as a [`Snapshot`], leaving out what
+/// doesn't morph.
+pub fn snapshot(params: &PluginParams) -> Snapshot {
+    params
+        .param_map()
+        .into_iter()
+        .filter(|(id, _, _)| morphs(id))
+        // SAFETY: `param_map()` points into `params`, which outlives
+        // the call.
+        .map(|(id, ptr, _)| (id, unsafe { ptr.unmodulated_normalized_value() }))
+        .collect()
+}
+
+/// Store the parameters as they stand as `side`, and as the knobs'
+/// home. For the editor's A and B buttons; allocates.
+pub fn store(params: &PluginParams, side: Side) {
+    let stored = snapshot(params);
+    if let Ok(mut home) = params.morph_home.write() {
+        home.clone_from(&stored);
+    }
+    if let Ok(mut snapshot) = snapshot_lock(params, side).write() {
+        *snapshot = stored;
+    }
+    params.morph_table.rebuild(params);
+}
+
+/// Set every parameter to where `side` stored it, each as a whole
+/// gesture through the host, so it's recorded and can be undone, and
+/// make that the knobs' home. Parameters the snapshot doesn't have are
+/// left alone. Allocates.
+pub fn recall(setter: &ParamSetter, params: &PluginParams, side: Side) {
+    let Ok(snapshot) = snapshot_lock(params, side).read() else {
+        return;
+    };
+    if let Ok(mut home) = params.morph_home.write() {
+        home.extend(snapshot.iter().map(|(id, value)| (id.clone(), *value)));
+    }
+    for (id, ptr, _) in params.param_map() {
+        let Some(&normalized) = snapshot.get(&id) else {
+            continue;
+        };
+        // SAFETY: `param_map()` points into `params`, which the host
+        // keeps alive while its editor is open.
+        unsafe {
+            setter.raw_context.raw_begin_set_parameter(ptr);
+            setter
+                .raw_context
+                .raw_set_parameter_normalized(ptr, normalized);
+            setter.raw_context.raw_end_set_parameter(ptr);
+        }
+    }
+    drop(snapshot);
+    params.morph_table.rebuild(params);
+}
+
+/// Forget both snapshots: nothing morphs until A and B are stored
+/// again. For the editor; allocates.
+pub fn clear(params: &PluginParams) {
+    for lock in [&params.morph_a, &params.morph_b, &params.morph_home] {
+        if let Ok(mut snapshot) = lock.write() {
+            snapshot.clear();
+        }
+    }
+    params.morph_table.rebuild(params);
+}
+
+/// Whether the parameter with `id` is one Morph moves.
+fn morphs(id: &str) -> bool {
+    id != MORPH_PARAM_ID && !MOMENTARY_PARAM_IDS.contains(&id)
+}
+
+fn snapshot_lock(params: &PluginParams, side: Side) -> &RwLock<Snapshot> {
+    match side {
+        Side::A => &params.morph_a,
+        Side::B => &params.morph_b,
+    }
+}
+
+/// What the audio thread needs of the snapshots: each morphing
+/// parameter's A, B and home. See [the module docs](self#storage).
+#[derive(Debug, Default)]
+pub struct MorphTable {
+    /// Sorted by offset, for the parameters that differ between the two.
+    entries: RwLock<Vec<MorphEntry>>,
+}
+
+/// One parameter's row of a [`MorphTable`], in normalized values.
+#[derive(Debug, Clone, Copy)]
+struct MorphEntry {
+    offset: usize,
+    a: f32,
+    b: f32,
+    /// `None` for a state saved before homes were: its knobs move
+    /// nothing.
+    home: Option<f32>,
+}
+
+impl MorphTable {
+    /// Boil `params`' snapshots down afresh. Allocates: for the editor,
+    /// or `initialize()`, never `process()`.
+    pub fn rebuild(&self, params: &PluginParams) {
+        let (Ok(a), Ok(b), Ok(home)) = (
+            params.morph_a.read(),
+            params.morph_b.read(),
+            params.morph_home.read(),
+        ) else {
+            return;
+        };
+        let mut entries: Vec<MorphEntry> = params
+            .param_map()
+            .into_iter()
+            .filter(|(id, _, _)| morphs(id))
+            .filter_map(|(id, ptr, _)| {
+                Some(MorphEntry {
+                    offset: offset(params, ptr),
+                    a: *a.get(&id)?,
+                    b: *b.get(&id)?,
+                    home: home.get(&id).copied(),
+                })
+            })
+            .filter(|entry| entry.a != entry.b)
+            .collect();
+        entries.sort_unstable_by_key(|entry| entry.offset);
+        // Swap the new table in, and drop the old one (in `entries`)
+        // after the lock: the audio thread only ever finds it taken for
+        // the swap.
+        if let Ok(mut current) = self.entries.write() {
+            std::mem::swap(&mut *current, &mut entries);
+        }
+    }
+
+    /// The table for one block's reading of `params`, the parameters it
+    /// belongs to, or `None` while it's being swapped (that block plays
+    /// without the morph).
+    pub fn read<'a>(&'a self, params: &'a PluginParams) -> Option<MorphReader<'a>> {
+        self.entries
+            .try_read()
+            .ok()
+            .map(|entries| MorphReader { entries, params })
+    }
+}
+
+/// A [`MorphTable`] held for one block's reading.
+pub struct MorphReader<'a> {
+    entries: std::sync::RwLockReadGuard<'a, Vec<MorphEntry>>,
+    params: &'a PluginParams,
+}
+
+impl MorphReader<'_> {
+    /// Whether nothing morphs: no snapshots, or two the same.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Where `param` plays with Morph at `morph` (0–1): that far from A
+    /// to B, moved on by as much as its knob (at the normalized value
+    /// `normalized`) has moved from home. `None` if A and B don't differ
+    /// on it.
+    pub fn apply<P: Param>(&self, param: &P, normalized: f32, morph: f32) -> Option<f32> {
+        let index = self
+            .entries
+            .binary_search_by_key(&offset(self.params, param.as_ptr()), |entry| entry.offset)
+            .ok()?;
+        let MorphEntry { a, b, home, .. } = self.entries[index];
+        let swept = match param.step_count() {
+            Some(_) if morph < 0.5 => a,
+            Some(_) => b,
+            // Weighted, not A + morph × (B − A), so the ends land on
+            // the snapshots exactly.
+            None => {
+                let morph = morph.clamp(0.0, 1.0);
+                (1.0 - morph) * a + morph * b
+            }
+        };
+        let moved = home.map_or(0.0, |home| normalized - home);
+        Some((swept + moved).clamp(0.0, 1.0))
+    }
+}
+
+/// Where in `params` a parameter lives, which identifies it without
+/// its ID. Every parameter is a field of [`PluginParams`] itself (none
+/// is nested behind a pointer), so the offset holds wherever it moves.
+fn offset(params: &PluginParams, ptr: ParamPtr) -> usize {
+    let address = match ptr {
+        ParamPtr::FloatParam(param) => param as usize,
+        ParamPtr::IntParam(param) => param as usize,
+        ParamPtr::BoolParam(param) => param as usize,
+        ParamPtr::EnumParam(param) => param as usize,
+    };
+    address.wrapping_sub(params as *const PluginParams as usize)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Defaults stored as A, and as B the defaults with Delay Time,
+    /// Filter L and Sync all the way up.
+    fn morphing_params() -> PluginParams {
+        let params = PluginParams::default();
+        store(&params, Side::A);
+        let mut b = snapshot(&params);
+        for id in ["delay", "filt", "sync"] {
+            b.insert(id.to_string(), 1.0);
+        }
+        *params.morph_b.write().unwrap() = b;
+        params.morph_table.rebuild(&params);
+        params
+    }
+
+    /// What the engine gets with Morph at `morph`.
+    fn at(params: &PluginParams, morph: f32) -> crate::DelayParams {
+        params.delay_params_with(&[(params.morph.as_ptr(), morph)])
+    }
+
+    /// Halfway is halfway round each skewed knob, not halfway between
+    /// the two values.
+    #[test]
+    fn test_halfway_follows_the_skew() {
+        let params = morphing_params();
+        let halfway = at(&params, 0.5);
+        assert!(
+            (halfway.delay_ms - 1110.9).abs() < 0.05,
+            "delay {}",
+            halfway.delay_ms
+        );
+        assert!(
+            (halfway.filter_cutoff - 12968.0).abs() <= 1.0,
+            "cutoff {}",
+            halfway.filter_cutoff
+        );
+        // Filter Link is on, so the right side follows.
+        assert_eq!(halfway.filter_cutoff_right, halfway.filter_cutoff);
+
+        let quarter = at(&params, 0.25);
+        assert!(quarter.delay_ms > 500.0 && quarter.delay_ms < halfway.delay_ms);
+    }
+
+    /// With the knobs at home, on A here, the ends play the snapshots
+    /// exactly.
+    #[test]
+    fn test_ends_play_the_snapshots() {
+        let params = morphing_params();
+        assert_eq!(at(&params, 0.0), params.delay_params());
+        assert_eq!(at(&params, 0.0), crate::DelayParams::default());
+
+        let b = at(&params, 1.0);
+        assert_eq!(b.delay_ms, params.delay_time.preview_plain(1.0));
+        assert_eq!(b.filter_cutoff, 20000.0);
+        assert!(b.sync);
+        assert_eq!(b.mix, crate::DelayParams::default().mix);
+    }
+
+    /// A switch plays A below 50% and B from 50% on.
+    #[test]
+    fn test_steps_switch_at_halfway() {
+        let params = morphing_params();
+        assert!(!at(&params, 0.49).sync);
+        assert!(at(&params, 0.5).sync);
+        assert!(at(&params, 0.75).sync);
+    }
+
+    /// A knob A and B differ on still follows its automation with Morph
+    /// at either end, moved from A at 0% and from B at 100% by as much
+    /// as it's moved from home (A, here).
+    #[test]
+    fn test_knob_automation_plays_at_the_ends() {
+        let params = morphing_params();
+        let a = params.morph_a.read().unwrap()["delay"];
+        let distance = 1.0 - a;
+        let (delay, morph) = (params.delay_time.as_ptr(), params.morph.as_ptr());
+        let played = |knob: f32, share: f32| {
+            params
+                .delay_params_with(&[(delay, knob), (morph, share)])
+                .delay_ms
+        };
+
+        let lane = [0.0, 0.05, 0.1, 0.15];
+        assert!(lane[3] + distance < 1.0, "B − A is {distance}");
+        for pair in lane.windows(2) {
+            for share in [0.0, 1.0] {
+                assert!(
+                    played(pair[1], share) > played(pair[0], share),
+                    "at {share}"
+                );
+            }
+        }
+        for knob in lane {
+            for (share, expected) in [(0.0, knob), (1.0, knob + distance)] {
+                let moved = params.delay_time.preview_plain(expected);
+                assert!(
+                    (played(knob, share) - moved).abs() < 1e-3,
+                    "{knob} at {share}: {} for {moved}",
+                    played(knob, share)
+                );
+            }
+        }
+
+        // Cleared, Morph moves nothing.
+        clear(&params);
+        assert_eq!(
+            params
+                .delay_params_with(&[(delay, 0.1), (morph, 1.0)])
+                .delay_ms,
+            params.delay_time.preview_plain(0.1)
+        );
+    }
+
+    /// Store A, turn a knob and flip a switch, store B: with the knobs
+    /// left on B, 0% plays A and 100% plays B, and the knob's automation
+    /// from there moves the sound from each.
+    #[test]
+    fn test_knobs_left_on_b_sweep_from_a() {
+        let params = PluginParams::default();
+        store(&params, Side::A);
+        // `store()` reads the knobs, and only a host can turn one, so
+        // write what it would have stored with Delay Time and Sync up.
+        let mut b = snapshot(&params);
+        for id in ["delay", "sync"] {
+            b.insert(id.to_string(), 1.0);
+        }
+        *params.morph_b.write().unwrap() = b.clone();
+        *params.morph_home.write().unwrap() = b;
+        params.morph_table.rebuild(&params);
+
+        let (delay, sync, morph) = (
+            params.delay_time.as_ptr(),
+            params.sync.as_ptr(),
+            params.morph.as_ptr(),
+        );
+        let played = |knob: f32, share: f32| {
+            params.delay_params_with(&[(delay, knob), (sync, 1.0), (morph, share)])
+        };
+        assert_eq!(played(1.0, 0.0), crate::DelayParams::default());
+        let b = played(1.0, 1.0);
+        assert_eq!(b.delay_ms, params.delay_time.preview_plain(1.0));
+        assert!(b.sync);
+        assert_eq!(
+            b,
+            crate::DelayParams {
+                delay_ms: b.delay_ms,
+                sync: true,
+                ..crate::DelayParams::default()
+            }
+        );
+
+        let a = params.morph_a.read().unwrap()["delay"];
+        for (share, expected) in [(0.0, a - 0.1), (1.0, 0.9)] {
+            let moved = played(0.9, share).delay_ms;
+            let expected = params.delay_time.preview_plain(expected);
+            assert!(
+                (moved - expected).abs() < 1e-3,
+                "{share}: {moved} for {expected}"
+            );
+        }
+    }
+
+    /// Morph and the momentary switches aren't stored, and with no
+    /// snapshots stored nothing morphs.
+    #[test]
+    fn test_what_morphs() {
+        let params = PluginParams::default();
+        let stored = snapshot(&params);
+        assert!(!stored.contains_key(MORPH_PARAM_ID));
+        for id in MOMENTARY_PARAM_IDS {
+            assert!(!stored.contains_key(id), "{id}");
+        }
+        assert!(stored.contains_key("delay"));
+
+        params.morph_table.rebuild(&params);
+        assert_eq!(at(&params, 1.0), params.delay_params());
+    }
+}
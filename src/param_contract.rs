@@ -0,0 +1,141 @@
+//! # The Parameter Contract
+//!
+//! Every parameter added to [`PluginParams`] inherits these checks
+//! without anyone writing a test for it: they walk `param_map()`, the
+//! same list the host sees, rather than a list of their own.
+//!
+//! - **The engine survives every end of every range.** Each parameter
+//!   at its minimum and at its maximum, everything else at its default,
+//!   plays a second of noise: nothing panics, no NaN or infinity comes
+//!   out, and the output stays below [`PEAK_CEILING`] (+12 dBFS) for
+//!   input peaking at [`INPUT_PEAK`]. The defaults all together get the
+//!   same run.
+//! - **Each `FloatParam`'s default lies in its range.** A default
+//!   outside it loads somewhere the knob can't get back to.
+//! - **Each `FloatParam`'s text round-trips** at its minimum, default
+//!   and maximum: typing in what the host shows lands on a value the
+//!   host shows the same way.
+//!
+//! A parameter is set at one end of its range through
+//! [`PluginParams::delay_params_with()`], since only the host can set
+//! one for real.
+
+use nih_plug::prelude::*;
+
+use crate::dsp::noise::NoiseGenerator;
+use crate::params::PluginParams;
+use crate::{DelayParams, LovelessDelayEngine};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const LENGTH: usize = SAMPLE_RATE as usize;
+const BLOCK_SIZE: usize = 512;
+
+/// The loudest the input gets: −6 dBFS.
+const INPUT_PEAK: f32 = 0.5;
+
+/// The loudest the output may get: +12 dBFS, as in the automation fuzz.
+const PEAK_CEILING: f32 = 4.0;
+
+/// Noise on both channels, different on each side.
+fn noise() -> [Vec<f32>; 2] {
+    [1, 2].map(|seed| {
+        let mut noise = NoiseGenerator::new(seed);
+        (0..LENGTH).map(|_| noise.next() * INPUT_PEAK).collect()
+    })
+}
+
+/// Play `input` through a fresh engine set to `params`, and return the
+/// output's peak, or `None` if anything that came out wasn't finite.
+fn render_peak(params: &DelayParams, input: &[Vec<f32>; 2]) -> Option<f32> {
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    engine.set_params(params);
+    let [mut left, mut right] = input.clone();
+    for (left, right) in left
+        .chunks_mut(BLOCK_SIZE)
+        .zip(right.chunks_mut(BLOCK_SIZE))
+    {
+        engine.process(&mut [left, right]);
+    }
+    left.iter().chain(&right).try_fold(0.0_f32, |peak, s| {
+        if s.is_finite() {
+            Some(peak.max(s.abs()))
+        } else {
+            None
+        }
+    })
+}
+
+/// Every `FloatParam` in `params`, by ID.
+fn float_params(params: &PluginParams) -> Vec<(String, &FloatParam)> {
+    params
+        .param_map()
+        .into_iter()
+        .filter_map(|(id, ptr, _)| match ptr {
+            // SAFETY: `param_map()` points into `params`, which the
+            // returned references borrow.
+            ParamPtr::FloatParam(param) => Some((id, unsafe { &*param })),
+            _ => None,
+        })
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+/// Every parameter at its minimum and maximum, the rest at their
+/// defaults, plays finite output under the ceiling, as do the defaults.
+#[test]
+fn test_engine_survives_every_range_end() {
+    let params = PluginParams::default();
+    let input = noise();
+
+    let peak = render_peak(&params.delay_params(), &input);
+    assert!(
+        peak.is_some_and(|peak| peak < PEAK_CEILING),
+        "defaults: peak {peak:?}"
+    );
+
+    let param_map = params.param_map();
+    assert!(!param_map.is_empty());
+    for (id, ptr, _) in param_map {
+        for (end, normalized) in [("min", 0.0), ("max", 1.0)] {
+            let delay_params = params.delay_params_with(&[(ptr, normalized)]);
+            let peak = render_peak(&delay_params, &input);
+            assert!(
+                peak.is_some_and(|peak| peak < PEAK_CEILING),
+                "{id} at {end}: peak {peak:?}"
+            );
+        }
+    }
+}
+
+/// Each `FloatParam`'s default lies within its range.
+#[test]
+fn test_float_defaults_in_range() {
+    let params = PluginParams::default();
+    for (id, param) in float_params(&params) {
+        let ends = [param.preview_plain(0.0), param.preview_plain(1.0)];
+        let (min, max) = (ends[0].min(ends[1]), ends[0].max(ends[1]));
+        let default = param.default_plain_value();
+        assert!(
+            (min..=max).contains(&default),
+            "{id}: default {default} outside {min}..={max}"
+        );
+    }
+}
+
+/// Each `FloatParam`'s text at its minimum, default and maximum parses
+/// back to a value shown with the same text.
+#[test]
+fn test_float_text_round_trips() {
+    let params = PluginParams::default();
+    for (id, param) in float_params(&params) {
+        for normalized in [0.0, param.default_normalized_value(), 1.0] {
+            let text = param.normalized_value_to_string(normalized, true);
+            let parsed = param.string_to_normalized_value(&text);
+            let shown = parsed.map(|parsed| param.normalized_value_to_string(parsed, true));
+            assert_eq!(shown.as_deref(), Some(text.as_str()), "{id}: {text:?}");
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! # Typed-In Values
+//!
+//! Hosts let you type a value into a parameter's field instead of
+//! turning its knob. The formatters in `params` hand what's typed to the
+//! parsers here, which take the shorthand musicians write as well as
+//! plain numbers:
+//!
+//! ```text
+//! Delay Time   350   350 ms   350,5           milliseconds
+//!              1/8.  1/4T  1/4 dotted         a note at the tempo sync follows
+//!              120bpm 1/4                     a note at a tempo of its own: 500 ms
+//! Filters      800   800 Hz   3.5k   3,5 kHz  hertz
+//! ```
+//!
+//! ## Notes
+//!
+//! `n/d` is `n` `d`th notes: `1/4` a quarter note (a beat), `3/16`
+//! three sixteenths. A trailing `.`, `d` or ` dotted` dots it (×1.5), a
+//! trailing `t` or ` triplet` makes it a triplet (×2/3), the way the
+//! Division menu names them. Case doesn't matter anywhere.
+//!
+//! A note on its own needs a tempo: the one the plugin last published
+//! (see [`sync_handover`](crate::sync_handover)), the host's or Manual
+//! BPM. Before the first block there isn't one, and the note doesn't
+//! parse. A tempo typed in front of the note always wins.
+//!
+//! ## Decimal Commas
+//!
+//! Much of the world writes a decimal comma, and some hosts show one. A
+//! number with a single comma and no point reads the comma as the point:
+//! `3,5k` is 3500 Hz. There are no thousands separators, so `1,000` is
+//! one, and `1,000.5` doesn't parse.
+//!
+//! ## Garbage
+//!
+//! Anything else — an empty field, a stray letter, a zero or negative
+//! value, an infinity, a NaN — parses to `None`, and the host keeps the
+//! value it had. Nothing here can panic, whatever it's given. A value
+//! that parses but lies outside a parameter's range is the range's
+//! business: nih-plug clamps it.
+
+use std::borrow::Cow;
+
+/// Parse a delay time in milliseconds: a number, with or without "ms";
+/// a note at `tempo_bpm`; or a note after a tempo of its own ("120bpm
+/// 1/4"). See [the module docs](self).
+pub fn parse_delay_ms(text: &str, tempo_bpm: Option<f64>) -> Option<f32> {
+    let text = text.trim();
+    // Lowercasing ASCII leaves every byte where it was, so `at` is an
+    // index into `text` too.
+    let ms = if let Some(at) = text.to_ascii_lowercase().find("bpm") {
+        let tempo_bpm = parse_number(text.get(..at)?).filter(|bpm| *bpm > 0.0)?;
+        note_ms(parse_note(text.get(at + 3..)?)?, tempo_bpm)
+    } else if text.contains('/') {
+        note_ms(parse_note(text)?, tempo_bpm.filter(|bpm| *bpm > 0.0)?)
+    } else {
+        parse_number(strip_suffix_ignore_case(text, "ms").unwrap_or(text))?
+    };
+    positive(ms)
+}
+
+/// Parse a frequency in hertz, with or without "Hz", where a "k" scales
+/// it by a thousand: "3.5k" and "3,5 kHz" are both 3500.
+pub fn parse_hz(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let text = strip_suffix_ignore_case(text, "hz")
+        .unwrap_or(text)
+        .trim_end();
+    let hz = match strip_suffix_ignore_case(text, "k") {
+        Some(kilo) => parse_number(kilo)? * 1000.0,
+        None => parse_number(text)?,
+    };
+    positive(hz)
+}
+
+/// Parse a note ("1/4", "1/8.", "1/4T", "1/4 dotted") as its length in
+/// beats (quarter notes).
+pub fn parse_note(text: &str) -> Option<f64> {
+    let text = text.trim();
+    // The long names first: "triplet" and "dotted" end in "t" and "d".
+    let (text, feel) = if let Some(head) =
+        strip_suffix_ignore_case(text, "triplet").or_else(|| strip_suffix_ignore_case(text, "t"))
+    {
+        (head, 2.0 / 3.0)
+    } else if let Some(head) = strip_suffix_ignore_case(text, "dotted")
+        .or_else(|| strip_suffix_ignore_case(text, "d"))
+        .or_else(|| text.strip_suffix('.'))
+    {
+        (head, 1.5)
+    } else {
+        (text, 1.0)
+    };
+    let (count, value) = text.split_once('/')?;
+    let count: u32 = count.trim().parse().ok()?;
+    let value: u32 = value.trim().parse().ok()?;
+    if count == 0 || value == 0 {
+        return None;
+    }
+    Some(4.0 * f64::from(count) / f64::from(value) * feel)
+}
+
+/// Parse a plain, finite number, where a lone comma is the decimal
+/// point.
+pub fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let text: Cow<str> = match (text.matches(',').count(), text.contains('.')) {
+        (0, _) => text.into(),
+        (1, false) => text.replace(',', ".").into(),
+        _ => return None,
+    };
+    // `f64`'s parser takes "inf" and "NaN", which no field wants.
+    text.parse().ok().filter(|number: &f64| number.is_finite())
+}
+
+/// How long `beats` last at `tempo_bpm`, in milliseconds.
+fn note_ms(beats: f64, tempo_bpm: f64) -> f64 {
+    beats * 60_000.0 / tempo_bpm
+}
+
+/// `value` as a parameter value, if it's one a field can take: finite,
+/// and above zero, even once it's narrowed to an `f32`.
+fn positive(value: f64) -> Option<f32> {
+    let value = value as f32;
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+/// `text` without `suffix` on the end, in any case, or `None` if it
+/// doesn't end that way.
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = text.len().checked_sub(suffix.len())?;
+    // `get()` rather than indexing: `split` may fall inside a character.
+    let (head, tail) = (text.get(..split)?, text.get(split..)?);
+    tail.eq_ignore_ascii_case(suffix).then_some(head)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_parses(parsed: Option<f32>, expected: f32, text: &str) {
+        let parsed = parsed.unwrap_or_else(|| panic!("{text:?} didn't parse"));
+        assert!(
+            (parsed - expected).abs() < 1e-3,
+            "{text:?}: {parsed}, not {expected}"
+        );
+    }
+
+    /// Milliseconds parse with or without their unit, in any case, with
+    /// a decimal point or a decimal comma.
+    #[test]
+    fn test_delay_ms_numbers() {
+        for (text, ms) in [
+            ("350", 350.0),
+            ("350ms", 350.0),
+            (" 350 ms ", 350.0),
+            ("350.5 MS", 350.5),
+            ("350,5", 350.5),
+            ("350,5 ms", 350.5),
+            ("1e3", 1000.0),
+        ] {
+            assert_parses(parse_delay_ms(text, None), ms, text);
+        }
+    }
+
+    /// A note on its own is timed at the tempo given; one with a tempo
+    /// of its own at that tempo, whatever tempo is given.
+    #[test]
+    fn test_delay_ms_notes() {
+        for (text, ms) in [
+            ("1/4", 500.0),
+            ("1/8.", 375.0),
+            ("1/8d", 375.0),
+            ("1/4 dotted", 750.0),
+            ("1/4T", 1000.0 / 3.0),
+            ("1/4 triplet", 1000.0 / 3.0),
+            ("3/16", 375.0),
+            ("1 / 2", 1000.0),
+        ] {
+            assert_parses(parse_delay_ms(text, Some(120.0)), ms, text);
+        }
+        for (text, ms) in [
+            ("120bpm 1/4", 500.0),
+            ("120 BPM 1/8.", 375.0),
+            ("90bpm 1/4T", 4000.0 / 9.0),
+            ("60,5 bpm 1/4", 60_000.0 / 60.5),
+        ] {
+            assert_parses(parse_delay_ms(text, None), ms, text);
+            assert_parses(parse_delay_ms(text, Some(100.0)), ms, text);
+        }
+    }
+
+    /// Frequencies parse with or without "Hz" and "k", in any case, with
+    /// a decimal point or a decimal comma.
+    #[test]
+    fn test_hz() {
+        for (text, hz) in [
+            ("800", 800.0),
+            ("800 Hz", 800.0),
+            ("800hz", 800.0),
+            ("3.5k", 3500.0),
+            ("3,5k", 3500.0),
+            ("3.5 kHz", 3500.0),
+            ("3.5K", 3500.0),
+            ("20 KHZ", 20000.0),
+        ] {
+            assert_parses(parse_hz(text), hz, text);
+        }
+    }
+
+    /// Garbage, zeros, negatives, infinities and NaNs all give `None`,
+    /// as does a note with no tempo to time it at.
+    #[test]
+    fn test_rejects_garbage() {
+        for text in [
+            "",
+            " ",
+            "ms",
+            "abc",
+            "-5",
+            "0",
+            "0 ms",
+            "inf",
+            "NaN",
+            "1e400",
+            "1,2,3",
+            "1.2,3",
+            "1,000.5",
+            "350 m",
+            "1/4",
+            "120bpm",
+            "bpm 1/4",
+            "0bpm 1/4",
+            "-120bpm 1/4",
+        ] {
+            assert_eq!(parse_delay_ms(text, None), None, "{text:?}");
+        }
+        for text in [
+            "1/0",
+            "0/4",
+            "1/4x",
+            "1//4",
+            "/4",
+            "1/",
+            "1/4.5",
+            "1/-4",
+            "1/4 dotted.",
+        ] {
+            assert_eq!(parse_delay_ms(text, Some(120.0)), None, "{text:?}");
+        }
+        assert_eq!(parse_delay_ms("1/4", Some(0.0)), None);
+        for text in [
+            "", "k", "Hz", "kHz", "3.5m", "-1k", "0 Hz", "1,000.5", "3.5 k k", "∞",
+        ] {
+            assert_eq!(parse_hz(text), None, "{text:?}");
+        }
+    }
+
+    /// Every piece of some awkward input, cut at every character, parses
+    /// to something or nothing, without panicking.
+    #[test]
+    fn test_never_panics() {
+        for text in [
+            "120 bpm 1/4 dotted",
+            "3,5 kHz",
+            "½ ms ✓",
+            "1/8.bpm",
+            "ｋHz 1/4Ｔ",
+        ] {
+            let cuts: Vec<usize> = text
+                .char_indices()
+                .map(|(at, _)| at)
+                .chain([text.len()])
+                .collect();
+            for &start in &cuts {
+                for &end in &cuts {
+                    let Some(piece) = text.get(start..end) else {
+                        continue;
+                    };
+                    let _ = parse_delay_ms(piece, Some(120.0));
+                    let _ = parse_delay_ms(piece, None);
+                    let _ = parse_hz(piece);
+                }
+            }
+        }
+    }
+}
@@ -9,7 +9,6 @@
 //! - A **human-readable name** shown in the DAW's UI.
 //! - A **range** (min, max, and optional skew).
 //! - A **default value**.
-//! - Optional **smoothing** to prevent audible clicks when values change.
 //!
 //! ## Parameter Smoothing
 //!
@@ -17,11 +16,78 @@
 //! audio, instant value changes create discontinuities that sound like
 //! clicks or "zipper noise." Smoothing gradually ramps the value from
 //! old to new over a short time window (e.g., 20ms), eliminating these
-//! artifacts. The `SmoothingStyle::Linear(ms)` option ramps linearly
-//! over the given duration.
+//! artifacts.
+//!
+//! These parameters don't smooth themselves: the plugin hands their raw
+//! values to the [`LovelessDelayEngine`](crate::LovelessDelayEngine)
+//! via [`PluginParams::delay_params()`], and the engine's own smoothers
+//! do the ramping. That way the engine behaves the same with or without
+//! a plugin host.
+//!
+//! ## Modulation
+//!
+//! CLAP hosts such as Bitwig can *modulate* a parameter as well as
+//! automate it: an LFO or macro adds an offset on top of wherever the
+//! user left the knob, and the knob itself doesn't move. nih-plug offers
+//! this for every automatable parameter, so Delay Time, Feedback, Mix
+//! and the filter cutoffs are modulatable simply by not being marked
+//! `non_automatable()`.
+//!
+//! The offset is applied in *normalized* space (0.0–1.0 across the
+//! knob's travel) and the sum is clamped to the range before `value()`
+//! returns it. Two things follow:
+//!
+//! - A modulator can never push a parameter past its own range. Feedback
+//!   tops out at [`MAX_FEEDBACK`] however hard it's modulated, and the
+//!   engine clamps it to that again in case a host misbehaves.
+//! - On skewed ranges the offset follows the skew: ±10% covers far fewer
+//!   hertz at the bottom of a cutoff knob than at the top, much as the
+//!   ear expects.
+//!
+//! Modulated values arrive through [`PluginParams::delay_params()`] like
+//! any other change, so the engine's smoothers ramp them too.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
 
 use nih_plug::prelude::*;
 
+use crate::dsp::bloom::MAX_BLOOM_DB;
+use crate::dsp::feedback::{ChainOrder, MAX_SHELF_DB};
+use crate::dsp::feedback_matrix::FeedbackMatrix;
+use crate::dsp::lfo::{LfoWaveform, MIN_GLIDE};
+use crate::dsp::output_filter::{HIGHCUT_OFF_HZ, LOWCUT_OFF_HZ};
+use crate::dsp::repeat_sequencer::{StepTarget, MAX_STEPS};
+use crate::dsp::stereo_motion::{StartSide, StereoMotion, MAX_SIDE_DECAY_DB};
+use crate::dsp::tape_head::MAX_SCRUB;
+use crate::dsp::tempo::NoteDivision;
+use crate::dsp::time_change::{TimeChange, TimeMultiplier, TimeSnap};
+use crate::engine::{
+    AutomationSmoothing, DelayParams, FeedbackSource, StopBehavior, MANUAL_BPM_RANGE, MAX_DELAY_MS,
+    MAX_FEEDBACK, MAX_MOD_DEPTH_MS, MAX_PRE_DELAY_MS, MAX_TIME_FINE_MS, MIN_DELAY_MS,
+    MOD_RATE_RANGE_HZ, OVERDUB_DECAY_RANGE_DB,
+};
+use crate::morph::{MorphReader, MorphTable, Snapshot};
+use crate::param_text;
+use crate::presets;
+use crate::sync_handover::SyncHandover;
+
+/// The IDs of the momentary switches: things you hold or tap while
+/// playing, not settings of the sound. A saved session always reopens
+/// with them off (see `LovelessDelay::filter_state()`).
+pub const MOMENTARY_PARAM_IDS: [&str; 6] = [
+    "clear",
+    "wet_solo",
+    "duck_listen",
+    "hold",
+    "double_time",
+    "half_time",
+];
+
+/// The unit Delay Time shows after its value.
+pub const DELAY_TIME_UNIT: &str = " ms";
+
 /// All user-facing parameters for the Loveless Delay plugin.
 ///
 /// The `#[derive(Params)]` macro automatically generates the code that
@@ -47,6 +113,170 @@ pub struct PluginParams {
     #[id = "delay"]
     pub delay_time: FloatParam,
 
+    /// **Sync** — lock the delay time to the song's tempo.
+    ///
+    /// When on, the echoes are spaced by **Division** at the host's
+    /// tempo and the Delay Time knob is ignored. If the host doesn't
+    /// report a tempo, the delay follows **Manual BPM** instead. Switching
+    /// it crossfades between the two times rather than bending the
+    /// repeats.
+    #[id = "sync"]
+    pub sync: BoolParam,
+
+    /// **Manual BPM** — the tempo Sync follows when the host has none.
+    ///
+    /// Standalone, or in a host that doesn't report its tempo, Sync
+    /// counts Division at this tempo. Once the host does report one, the
+    /// host's wins, crossfading over from this one; the editor shows
+    /// which is in use.
+    #[id = "manual_bpm"]
+    pub manual_bpm: FloatParam,
+
+    /// **Division** — the note length between echoes while synced.
+    ///
+    /// Dotted eighths give the classic "galloping" rhythm; triplets swing
+    /// against straight playing. At slow tempos, long divisions are
+    /// capped at the 2-second maximum delay.
+    #[id = "division"]
+    pub division: EnumParam<SyncDivision>,
+
+    /// **Quantize Start** — wait for the beat before changing time.
+    ///
+    /// Turning Sync on (or picking a new Division) mid-beat would start
+    /// the new spacing off the grid. With this on, the change waits for
+    /// the next beat so the echoes lock straight to it. Hosts that don't
+    /// report a beat position get the change immediately.
+    #[id = "quantize_start"]
+    pub quantize_start: BoolParam,
+
+    /// **Time Multiplier** — scale the delay time in musical steps.
+    ///
+    /// ×0.5, ×1, ×1.5 (dotted) or ×2 the Delay Time, or the Division
+    /// while synced. Being stepped, it jumps between values rather than
+    /// sweeping through them, so automating it is a rhythmic gesture.
+    #[id = "time_mult"]
+    pub time_multiplier: EnumParam<DelayMultiplier>,
+
+    /// **Double Time** — a looper's Multiply: twice the time, while held.
+    ///
+    /// Doubles the Delay Time for as long as it's on — or, synced, moves
+    /// the Division one step longer — and gives it back on release. Both
+    /// ways it crossfades, so the repeats never bend, and it doesn't wait
+    /// for the beat. Map it to a footswitch or a pad. Held with Half
+    /// Time, the one pressed last wins. Moving the Delay Time meanwhile
+    /// still counts: release lands on the new time. A session always
+    /// reopens with it off.
+    #[id = "double_time"]
+    pub double_time: BoolParam,
+
+    /// **Half Time** — a looper's Divide: half the time, while held.
+    ///
+    /// Halves the Delay Time, or moves the Division one step shorter,
+    /// just as Double Time doubles it.
+    #[id = "half_time"]
+    pub half_time: BoolParam,
+
+    /// **Time Fine** — nudge the echoes ahead of or behind the beat.
+    ///
+    /// Adds up to ±20 ms to the (multiplied) delay time, and always
+    /// glides. The total is kept within the delay's 100–2000 ms range.
+    #[id = "time_fine"]
+    pub time_fine: FloatParam,
+
+    /// **Time Snap** — keep the Delay Time knob on a grid.
+    ///
+    /// Rounds the knob's time (and any automation of it) to whole
+    /// milliseconds, to 5 or 10 ms steps, or to the nearest note length
+    /// at a common tempo, and shows the knob at the rounded time. Time
+    /// Fine stays continuous, and a synced delay ignores the grid.
+    #[id = "time_snap"]
+    pub time_snap: EnumParam<DelaySnap>,
+
+    /// Time Snap's current setting, as its index, kept up to date by its
+    /// callback. The Delay Time display reads it: a formatter can't ask
+    /// another parameter for its value.
+    pub shown_time_snap: Arc<AtomicUsize>,
+
+    /// The tempo sync follows, published by the plugin every block. A
+    /// note typed into Delay Time is timed at it (see
+    /// [`param_text`](crate::param_text)): a formatter can't ask the
+    /// audio thread.
+    pub sync_handover: Arc<SyncHandover>,
+
+    /// **Time Change** — how a new delay time is reached.
+    ///
+    /// *Glide* ramps to it like a tape echo, bending the pitch of the
+    /// repeats on the way. *Crossfade* fades from the old time to the
+    /// new one without any pitch change, like a digital delay. *Tape*
+    /// gives the read head a motor: it runs to the new time with a
+    /// little inertia, and Scrub can slow, stop or reverse it. Time Fine
+    /// glides in Glide and Crossfade.
+    #[id = "time_change"]
+    pub time_change: EnumParam<TimeChangeMode>,
+
+    /// **Scrub** — lean on the tape reel (Tape mode only).
+    ///
+    /// Slows the read head as if a hand were dragging the reel: at +100%
+    /// the tape stops and the repeats fall away in pitch to nothing, at
+    /// +200% it runs backwards, and below 0% it races ahead, pitching
+    /// the repeats up. The head has some weight, so sweeps bend smoothly.
+    /// Back at 0% it runs back to the delay time and locks on. Automate
+    /// it for tape stops and spin-backs.
+    ///
+    /// Range: −200% to +200%.
+    #[id = "scrub"]
+    pub scrub: FloatParam,
+
+    /// **Mod Waveform** — the shape that sways the delay time.
+    ///
+    /// *Sine* and *Triangle* drift it evenly back and forth, for chorus
+    /// and vibrato. *S&H* picks a new random time every cycle and glides
+    /// over to it, for the warble of a worn tape machine. Nothing moves
+    /// until Mod Depth is turned up.
+    #[id = "mod_waveform"]
+    pub mod_waveform: EnumParam<ModWaveform>,
+
+    /// **Mod Rate** — how fast the delay time sways.
+    ///
+    /// One cycle of the waveform, or one new level of S&H, per period.
+    ///
+    /// Range: 0.05 Hz to 10 Hz (skewed toward slow rates).
+    #[id = "mod_rate"]
+    pub mod_rate: FloatParam,
+
+    /// **Mod Depth** — how far the delay time sways either side of its
+    /// setting.
+    ///
+    /// A few milliseconds bend the repeats' pitch gently; more makes them
+    /// seasick. 0 ms leaves the delay time exactly where it is.
+    ///
+    /// Range: 0 ms (off) to 10 ms.
+    #[id = "mod_depth"]
+    pub mod_depth: FloatParam,
+
+    /// **Mod Glide** — how smoothly S&H moves between its levels (S&H
+    /// only).
+    ///
+    /// The fraction of each cycle spent gliding to the next level: at 5%
+    /// it steps and then holds, at 100% it never stops moving. Either
+    /// way it glides, so the delay time never jumps.
+    ///
+    /// Range: 5% to 100%.
+    #[id = "mod_glide"]
+    pub mod_glide: FloatParam,
+
+    /// **Pre-Delay** — holds the echoes back without changing their
+    /// rhythm.
+    ///
+    /// Delays only the wet signal, after the feedback loop, so the first
+    /// echo arrives at Pre-Delay + Delay Time while the repeats stay
+    /// Delay Time apart. Use it to push the echoes behind the beat, or to
+    /// leave a little space after a vocal phrase before they come in.
+    ///
+    /// Range: 0ms (off) to 250ms.
+    #[id = "pre_delay"]
+    pub pre_delay: FloatParam,
+
     /// **Feedback** — how many times the echo repeats.
     ///
     /// Controls how much of the delayed output is fed back into the delay
@@ -63,6 +293,24 @@ pub struct PluginParams {
     #[id = "fdbk"]
     pub feedback: FloatParam,
 
+    /// **Hold** — the dub player's trick: hold the repeats up.
+    ///
+    /// While it's on, the feedback swells to Hold Level over 100 ms,
+    /// whatever the Feedback knob says; on release it settles back to
+    /// the knob over another 100 ms. Unlike Freeze the loop stays open,
+    /// so anything you play meanwhile piles onto the held repeats. Map
+    /// it to a footswitch or a pad. A session always reopens with it off.
+    #[id = "hold"]
+    pub hold: BoolParam,
+
+    /// **Hold Level** — the feedback while Hold is on.
+    ///
+    /// It can go all the way to 100%, past the Feedback knob's 95% cap:
+    /// the loop-gain ceiling still keeps the loop just under unity, so
+    /// at 100% the repeats hang in the air without running away.
+    #[id = "hold_level"]
+    pub hold_level: FloatParam,
+
     /// **Mix** — the balance between dry (original) and wet (delayed) signal.
     ///
     /// - 0% = fully dry (you hear only the original, no delay at all)
@@ -88,19 +336,570 @@ pub struct PluginParams {
     ///
     /// The skewed range gives more knob resolution to lower frequencies,
     /// where the sonic differences are more dramatic.
+    ///
+    /// This is the left channel's cutoff (and the only one for mono).
+    /// While **Filter Link** is on it sets both sides.
     #[id = "filt"]
     pub filter_cutoff: FloatParam,
+
+    /// **Filter Cutoff (Right)** — the right channel's feedback filter.
+    ///
+    /// Only used while **Filter Link** is off, so the two sides can
+    /// darken at different rates — handy when the left and right repeats
+    /// should sit in different parts of the mix.
+    #[id = "filt_r"]
+    pub filter_cutoff_right: FloatParam,
+
+    /// **Filter Link** — one cutoff for both channels.
+    ///
+    /// On by default. Presets saved before the right-hand cutoff existed
+    /// only contain `filt`; since the link is on when they load, both
+    /// sides follow that one value and they sound exactly as before.
+    #[id = "filt_link"]
+    pub filter_link: BoolParam,
+
+    /// **Feedback Comp** — keep the repeats as long at any
+    /// cutoff.
+    ///
+    /// A low Filter setting takes so much from each pass that the
+    /// repeats die away long before the Feedback knob says they should.
+    /// With this on, the feedback is boosted by just enough to make up
+    /// for it (a few percent at most), so Filter sets the tone and
+    /// Feedback the length. Off by default, so older sessions sound the
+    /// same.
+    #[id = "fb_comp"]
+    pub feedback_compensation: BoolParam,
+
+    /// **Drive** — how hard the repeats are squashed on each pass.
+    ///
+    /// A soft saturator in the feedback loop rounds off loud repeats the
+    /// way tape does, adding warmth and grit. Quiet repeats are barely
+    /// touched, and it can never make the loop louder.
+    ///
+    /// - 0% = off (the feedback path is exactly as without it)
+    /// - 30% = gentle tape-style compression of loud repeats
+    /// - 100% = heavily saturated, gritty repeats
+    #[id = "drive"]
+    pub drive: FloatParam,
+
+    /// **Feedback Order** — whether repeats are filtered before or after
+    /// they're driven.
+    ///
+    /// - Filter → Drive: the saturator adds harmonics after the filter,
+    ///   so driven repeats keep some bite.
+    /// - Drive → Filter: the filter smooths the saturator's harmonics
+    ///   away for a darker, warmer drive.
+    ///
+    /// Makes no difference while Drive is at 0%.
+    #[id = "fb_chain_order"]
+    pub fb_chain_order: EnumParam<FeedbackOrder>,
+
+    /// **Low Shelf** — turn the lows of each repeat up or down, ±12 dB
+    /// below 250 Hz.
+    ///
+    /// Cut to thin the repeats out pass by pass, so they stay out of
+    /// the bass line's way; boost for boomier, dub-style repeats. A
+    /// boost is applied on every pass, so the feedback is eased back to
+    /// keep the loop stable — with a big boost the lows ring on while
+    /// the rest fades.
+    ///
+    /// 0 dB = exactly as without it.
+    #[id = "fb_low_shelf"]
+    pub fb_low_shelf: FloatParam,
+
+    /// **High Shelf** — turn the highs of each repeat up or down,
+    /// ±12 dB above 4 kHz. Cut for duller repeats without moving the
+    /// Filter; boost for repeats that get brighter and fizzier. Boosts
+    /// ease the feedback back like the Low Shelf's.
+    ///
+    /// 0 dB = exactly as without it.
+    #[id = "fb_high_shelf"]
+    pub fb_high_shelf: FloatParam,
+
+    /// **Feedback Source** — what goes back round the loop.
+    ///
+    /// - Wet Tap: the echoes alone. Mix only sets how loud you hear
+    ///   them.
+    /// - Post-Mix: the mixed output, the way some classic delays are
+    ///   wired. Mix becomes part of the sound: towards dry, the repeats
+    ///   die away sooner, and the dry signal goes round again under
+    ///   them, through the filter and drive.
+    #[id = "fb_source"]
+    pub fb_source: EnumParam<FeedbackTap>,
+
+    /// **Character** — one knob from pristine digital to worn tape.
+    ///
+    /// Turning it up darkens the repeats (up to three octaves below the
+    /// Filter setting) and adds drive, the way an old tape echo sounds
+    /// duller and grittier than a new one. It works *on top of* the
+    /// Filter and Drive knobs rather than moving them, so you can still
+    /// fine-tune both, and at 0% they sound exactly as set.
+    #[id = "character"]
+    pub character: FloatParam,
+
+    /// **Damping** — late, quiet repeats lose their top end faster.
+    ///
+    /// The Filter darkens every pass by the same amount. Damping lowers
+    /// it further as the echoes fade, by up to four octaves once they're
+    /// 48 dB down, so the tail dulls away the way worn tape does while
+    /// the first loud repeats stay bright. At 0% the Filter works alone.
+    #[id = "damping"]
+    pub damping: FloatParam,
+
+    /// **Low Cut** — thins out the low end of the echoes.
+    ///
+    /// A highpass on the wet signal only, applied once on the way out —
+    /// not inside the feedback loop — so it changes where the echoes sit
+    /// in the mix without changing how the repeats decay. Handy for
+    /// keeping echoes of a bass-heavy source from muddying the low end.
+    ///
+    /// Range: Off (20 Hz) to 500 Hz. "Off" bypasses the filter entirely.
+    #[id = "out_lowcut"]
+    pub out_lowcut: FloatParam,
+
+    /// **High Cut** — softens the top end of the echoes.
+    ///
+    /// A lowpass on the wet signal, also outside the loop. Unlike the
+    /// feedback filter, it darkens every repeat by the same amount, so
+    /// it pushes the echoes behind the dry signal without making later
+    /// repeats fade faster.
+    ///
+    /// Range: 1 kHz to Off (20 kHz). "Off" bypasses the filter entirely.
+    #[id = "out_highcut"]
+    pub out_highcut: FloatParam,
+
+    /// **Mono Safe** — keep the echoes' low end mono-compatible.
+    ///
+    /// When the two sides are set up differently (e.g. different filter
+    /// cutoffs), their repeats drift apart in phase, and folding the mix
+    /// to mono — a phone speaker, a club system — can thin out the
+    /// bass. Mono Safe makes both sides recirculate the same content
+    /// below ~200 Hz, so the low end always sums cleanly, while the
+    /// stereo differences carry on above it.
+    #[id = "mono_safe"]
+    pub mono_safe: BoolParam,
+
+    /// **Limiter** — a safety net on the output.
+    ///
+    /// When on, a soft-knee limiter holds the output below -0.1 dBFS, so
+    /// even runaway feedback settings can't send clipped samples to the
+    /// host. It reacts instantly to peaks and recovers over ~50ms.
+    ///
+    /// Off by default: at normal levels it's transparent anyway, and
+    /// leaving it off keeps the signal path exactly as designed.
+    #[id = "limiter"]
+    pub limiter: BoolParam,
+
+    /// **External Loop** — patch outboard gear into the feedback path.
+    ///
+    /// When on, each repeat leaves through the plugin's "Loop Send" aux
+    /// output and comes back in through "Loop Return", so whatever the
+    /// host routes in between colors every pass, like the FX loop on a
+    /// hardware delay. The repeats stay on time; the internal loop takes
+    /// over again if the host hasn't connected the ports.
+    #[id = "external_loop"]
+    pub external_loop: BoolParam,
+
+    /// **Step Target** — what changes from one repeat to the next.
+    ///
+    /// Instead of sweeping a setting continuously, the step pattern
+    /// gives each repeat its own value: with "Cutoff", the first echo
+    /// might be dark, the next bright, and so on, cycling through the
+    /// pattern. The steps act on the echoes on their way to the mix, so
+    /// every repeat sounds exactly as its step says, however many times
+    /// it has been round the loop.
+    #[id = "step_target"]
+    pub step_target: EnumParam<StepDestination>,
+
+    /// **Steps** — how many steps the pattern cycles through (1–8).
+    #[id = "step_length"]
+    pub step_length: IntParam,
+
+    /// **Step 1–8** — the pattern itself. For the cutoff target, 0% is a
+    /// 200 Hz high-cut and 100% leaves the repeat fully open.
+    #[id = "step_1"]
+    pub step_1: FloatParam,
+    #[id = "step_2"]
+    pub step_2: FloatParam,
+    #[id = "step_3"]
+    pub step_3: FloatParam,
+    #[id = "step_4"]
+    pub step_4: FloatParam,
+    #[id = "step_5"]
+    pub step_5: FloatParam,
+    #[id = "step_6"]
+    pub step_6: FloatParam,
+    #[id = "step_7"]
+    pub step_7: FloatParam,
+    #[id = "step_8"]
+    pub step_8: FloatParam,
+
+    /// **Motion** — how the repeats move around the stereo field.
+    ///
+    /// "Ping-Pong" bounces them left, right, left; "Reverse Ping-Pong"
+    /// starts on the right. "Circular" pans each repeat a little further
+    /// round a circle, by the Rotation angle. Both ping-pongs start from
+    /// the mono sum of the input. Mono tracks have no field to move
+    /// around, so there it does nothing, and it's set aside while the
+    /// External Loop is in use.
+    #[id = "motion"]
+    pub motion: EnumParam<MotionMode>,
+
+    /// **Rotation** — how far Circular motion turns between repeats.
+    ///
+    /// 90° visits right, centre, left and centre again over four
+    /// repeats; small angles drift slowly across the field; 180° swings
+    /// from one side to the other.
+    #[id = "motion_rotation"]
+    pub motion_rotation: FloatParam,
+
+    /// **Start Side** — where ping-pong's first repeat is heard.
+    ///
+    /// "Center" plays the first repeat in the middle, then bounces from
+    /// right to left. Reverse Ping-Pong mirrors the choice, so "Left"
+    /// there starts on the right.
+    #[id = "start_side"]
+    pub start_side: EnumParam<PingPongStart>,
+
+    /// **Self Feedback** — how much of each side's repeats goes back
+    /// into its own side.
+    ///
+    /// 100% (the default) with Cross Feedback at 0% is a plain stereo
+    /// delay. These two set the feedback matrix while **Matrix Expert**
+    /// is off, as a fraction of the Feedback knob.
+    #[id = "fb_self"]
+    pub feedback_self: FloatParam,
+
+    /// **Cross Feedback** — how much of each side's repeats goes into
+    /// the other side.
+    ///
+    /// Turn it up against Self Feedback and each side's echoes smear
+    /// into the other; at 100% with Self at 0% the repeats alternate
+    /// sides like ping-pong, but from a stereo input. When the two add
+    /// up to more than 100% the pair is scaled back, so the repeats can
+    /// never build up.
+    #[id = "fb_cross"]
+    pub feedback_cross: FloatParam,
+
+    /// **Matrix Expert** — set all four feedback routes separately.
+    ///
+    /// Off by default, so presets saved before the matrix existed load
+    /// as a plain stereo delay.
+    #[id = "fb_matrix"]
+    pub feedback_matrix_expert: BoolParam,
+
+    /// **L→L**, **L→R**, **R→L**, **R→R** — the four routes of the
+    /// feedback matrix, used while **Matrix Expert** is on: how much of
+    /// one side's repeats goes into each side, from −100% to 100%.
+    ///
+    /// Unequal crossings make the repeats drift one way across the
+    /// field; opposite signs (L→R at 30%, R→L at −30%) make them swirl.
+    /// Like Self and Cross, the matrix is scaled back whenever it could
+    /// make the repeats grow. Mono tracks and ping-pong motion don't use
+    /// it.
+    #[id = "fb_ll"]
+    pub feedback_ll: FloatParam,
+    #[id = "fb_lr"]
+    pub feedback_lr: FloatParam,
+    #[id = "fb_rl"]
+    pub feedback_rl: FloatParam,
+    #[id = "fb_rr"]
+    pub feedback_rr: FloatParam,
+
+    /// **Decorrelate** — widen the repeats of a dual-mono input.
+    ///
+    /// When both sides are fed the same signal, the echoes sit as a
+    /// point in the middle. Turning this up smears the two sides' wet
+    /// signals apart with short, complementary allpass filters (under
+    /// 15 ms), for width without a doubled, slapback sound; folded to
+    /// mono, the smearing cancels. At 0% the wet signal is untouched.
+    #[id = "decorrelate"]
+    pub decorrelate: FloatParam,
+
+    /// **Phase Align** — keep very short delays from hollowing out the
+    /// sound.
+    ///
+    /// Under 20 ms the repeat blurs into the dry signal, and with the
+    /// mix near half-way the two can partly cancel, thinning some notes
+    /// and not others. With this on, the plugin listens for that and
+    /// flips the repeats' polarity (with a quick fade) when they're
+    /// working against the dry signal, so Haas widening and doubling
+    /// sound as full on every note. Longer delays are left alone.
+    #[id = "phase_align"]
+    pub phase_align: BoolParam,
+
+    /// **Dry Pan** — move the dry signal in the stereo field, leaving
+    /// the repeats where they are.
+    ///
+    /// Turning it left slides the right channel of the dry signal over
+    /// to the left at constant power, until both sit hard left; turning
+    /// it right does the opposite. Useful for nudging the source aside
+    /// while the echoes stay wide. At C the dry signal is untouched.
+    #[id = "dry_pan"]
+    pub dry_pan: FloatParam,
+
+    /// **Input Balance** — feed more of one side of the input into the
+    /// delay than the other.
+    ///
+    /// Turning it left trims the right input on its way into the delay
+    /// and lifts the left, keeping the total power the same, until only
+    /// the left side is echoed (3 dB up); turning it right does the
+    /// opposite. The dry signal is untouched. At C both sides go in as
+    /// they came. Stereo only.
+    #[id = "input_balance"]
+    pub input_balance: FloatParam,
+
+    /// **Swap Channels** — exchange the left and right repeats.
+    ///
+    /// Handy when ping-pong lands its first repeat on the wrong side for
+    /// the arrangement: the repeats that would come from the left come
+    /// from the right, and vice versa, while the dry signal stays put.
+    /// Switching crossfades over 10ms, so it's safe mid-tail. Stereo
+    /// only.
+    #[id = "swap_channels"]
+    pub swap_channels: BoolParam,
+
+    /// **Side Decay** — let the repeats fold in towards the middle as
+    /// they fade.
+    ///
+    /// On every pass round the loop, the difference between left and
+    /// right loses this much more than what they share, so a wide first
+    /// echo narrows step by step towards mono, the way echoes in a room
+    /// do. 3 dB takes the fifth repeat 12 dB narrower than the first.
+    /// Nothing gets louder, so it never affects stability. Stereo only.
+    ///
+    /// 0 dB = exactly as without it.
+    #[id = "side_decay"]
+    pub side_decay: FloatParam,
+
+    /// **Spread** — pan each repeat a little further out than the last,
+    /// alternating sides.
+    ///
+    /// The first repeat lands slightly left, the second slightly right,
+    /// the third further left, until the fourth reaches the full Spread
+    /// width and the rest bounce between its edges. Each side keeps its
+    /// own repeats (unlike ping-pong), so a stereo source keeps its
+    /// image as it drifts. At 0% the repeats are untouched.
+    #[id = "spread"]
+    pub spread: FloatParam,
+
+    /// **Alternate** — bounce the repeats from side to side.
+    ///
+    /// Every other repeat comes out with left and right exchanged, so a
+    /// note played on the left answers on the right, then the left
+    /// again. Unlike ping-pong, each side keeps its own time, tone and
+    /// feedback: only what's heard changes sides, so the tail lasts
+    /// exactly as long. Each swap glides in over 10ms just before its
+    /// repeat, so a repeat arriving mid-note doesn't click. Stereo only.
+    #[id = "alternate"]
+    pub alternate: BoolParam,
+
+    /// **Bloom** — let the repeats swell before they fade.
+    ///
+    /// After fresh input, the first repeat comes back this much quieter,
+    /// and the wet level rises to full by the third, so a note blooms
+    /// out of the delay instead of starting loud and dying away. Only
+    /// the heard repeats are shaped, never the loop, so it can't change
+    /// how long they last. Playing again starts the swell over.
+    ///
+    /// 0 dB = exactly as without it.
+    #[id = "bloom"]
+    pub bloom: FloatParam,
+
+    /// **Key Gate** — only echo what plays while the sidechain key is
+    /// loud.
+    ///
+    /// Route a track into the plugin's Key input and only the moments
+    /// when it's above Key Threshold get into the delay: key a drum bus
+    /// delay from the snare, and only the snare echoes. The dry signal
+    /// and the repeats already playing are left alone. With nothing on
+    /// the Key input, the gate stays open.
+    #[id = "key_gate"]
+    pub key_gate: BoolParam,
+
+    /// **Key Threshold** — how loud the key must be to open the gate.
+    #[id = "key_threshold"]
+    pub key_threshold: FloatParam,
+
+    /// **Key Release** — how long the gate takes to close once the key
+    /// drops below the threshold, letting the tail of each keyed sound
+    /// in too.
+    #[id = "key_release"]
+    pub key_release: FloatParam,
+
+    /// **Key Listen** — hear the key the way the gate does.
+    ///
+    /// Replaces the plugin's output with the Key input, after the
+    /// highpass that keeps low end from opening the gate, so you can
+    /// hear exactly what's crossing the threshold. It works with Key
+    /// Gate on or off, and it's a monitoring aid, not part of the sound:
+    /// a session always reopens with it off.
+    #[id = "duck_listen"]
+    pub duck_listen: BoolParam,
+
+    /// **Clear** — the panic button for a runaway tail.
+    ///
+    /// Switching it on fades the echoes out over 30 ms and empties the
+    /// delay lines, without touching the Feedback knob; what you play
+    /// next echoes as usual. It acts when it turns on, so map it to a
+    /// momentary controller button or automate a short pulse. Leaving it
+    /// on does nothing more — switch it off and on again to clear again.
+    #[id = "clear"]
+    pub clear: BoolParam,
+
+    /// **Wet Solo** — hear only the repeats while it's held.
+    ///
+    /// Mutes the dry signal (with a short fade) without touching Mix or
+    /// its automation, so you can dial in the filter and drive on the
+    /// echoes alone. It's a monitoring aid, not part of the sound: a
+    /// session always reopens with it off.
+    #[id = "wet_solo"]
+    pub wet_solo: BoolParam,
+
+    /// **Stop Behavior** — what the repeats do when you press stop.
+    ///
+    /// *Ring Out* leaves them alone, *Fade* fades them out over half a
+    /// second, and *Clear* cuts them (with a click-free 30 ms fade). It
+    /// follows the host's transport, so it works the same in every
+    /// host — though a host that resets the plugin on stop still clears
+    /// the repeats whatever this says. Fade and Clear also get rid of
+    /// repeats left over from a pause the host never reported, so they
+    /// don't come back when playback resumes.
+    #[id = "stop_behavior"]
+    pub stop_behavior: EnumParam<StopMode>,
+
+    /// **Watchdog** — turn the feedback down if nobody else does.
+    ///
+    /// For installations left running unattended. When the repeats have
+    /// stayed loud (above −20 dBFS) for longer than Watchdog Time, the
+    /// feedback ramps down to 50% until the tail has died away (below
+    /// −40 dBFS), then goes back to the Feedback knob's setting. Off by
+    /// default.
+    #[id = "watchdog_enable"]
+    pub watchdog_enable: BoolParam,
+
+    /// **Watchdog Time** — how long the repeats may stay loud before the
+    /// watchdog steps in, in seconds.
+    #[id = "watchdog_time"]
+    pub watchdog_time: FloatParam,
+
+    /// **Freeze** — hold the delay's contents and loop them.
+    ///
+    /// Nothing new goes in and nothing decays: what's in the delay
+    /// (about two seconds of it) plays round and round until you switch
+    /// it off, when it fades back to the live delay and the loop carries
+    /// on as ordinary repeats.
+    #[id = "freeze"]
+    pub freeze: BoolParam,
+
+    /// **Freeze Quantize** — make the frozen loop a whole number of
+    /// delay times (or note divisions, while synced) and smooth over its
+    /// seam, for a loop that stays in time and doesn't click where it
+    /// wraps.
+    #[id = "freeze_quantize"]
+    pub freeze_quantize: BoolParam,
+
+    /// **Overdub** — play into the frozen loop, like a simple looper.
+    ///
+    /// While Freeze is on, what you play is layered onto the loop, and
+    /// each time round the older layers fade by Overdub Decay, so the
+    /// loop keeps changing without piling up. Switch Freeze off and the
+    /// whole loop carries on as ordinary repeats. Off, Freeze holds the
+    /// loop exactly as it was.
+    #[id = "overdub"]
+    pub overdub: BoolParam,
+
+    /// **Overdub Decay** — how much the loop fades each time round
+    /// while overdubbing. Near 0 dB the layers build up over many
+    /// passes, and new playing goes in quieter to make room; lower,
+    /// each layer is gone after a few.
+    #[id = "overdub_decay"]
+    pub overdub_decay: FloatParam,
+
+    /// **Morph** — sweep every parameter from the sound stored as A to
+    /// the one stored as B.
+    ///
+    /// Once both are stored, 0% plays A and 100% plays B, wherever the
+    /// knobs are; in between each parameter is its share of the way
+    /// round its knob, so a skewed range sweeps the way it turns.
+    /// Switches and menus jump at 50%. Nothing is written to the knobs
+    /// themselves, and a knob moved since it was stored moves the sound
+    /// on top, so its automation still plays, at either end too (see
+    /// [`morph`](crate::morph)).
+    #[id = "morph"]
+    pub morph: FloatParam,
+
+    /// **Preset loudness match** — an option, not a parameter: hosts
+    /// can't automate it. While it's on, a hidden output trim brings
+    /// every preset to about its input's level, so flipping through
+    /// presets compares sounds rather than loudness (see
+    /// [`presets`](crate::presets)). Saved with the plugin's state.
+    #[persist = "preset_loudness_match"]
+    pub preset_loudness_match: AtomicBool,
+
+    /// **Auto Economy** — an option, like loudness matching: when the
+    /// plugin keeps coming close to missing the host's deadline, run the
+    /// engine in economy until the load has stayed low for a while (see
+    /// [`load_monitor`](crate::load_monitor)). Off by default. Saved
+    /// with the plugin's state.
+    #[persist = "auto_economy"]
+    pub auto_economy: AtomicBool,
+
+    /// **Automation Smoothing** — an option too: Full, Reduced or Off,
+    /// for hosts whose automation arrives smooth already (see
+    /// [`AutomationSmoothing`]). Stored as its place in
+    /// [`AutomationSmoothing::ALL`]; read it with
+    /// [`automation_smoothing()`](Self::automation_smoothing). Saved
+    /// with the plugin's state.
+    #[persist = "automation_smoothing"]
+    pub automation_smoothing: AtomicU8,
+
+    /// The loaded preset's own loudness trim, in dB, stored as `f32`
+    /// bits (see [`FactoryPreset::trim_db`](presets::FactoryPreset)).
+    /// It's part of the state, so a preset file carries its trim.
+    #[persist = "preset_trim"]
+    pub preset_trim: AtomicU32,
+
+    /// Where Print Tail writes its files, or `None` for the host's
+    /// working directory (see [`tail_print`](crate::tail_print)). Chosen
+    /// in the editor and saved with the plugin's state.
+    #[persist = "tail_print_dir"]
+    pub tail_print_dir: RwLock<Option<PathBuf>>,
+
+    /// The A and B sounds **Morph** sweeps between, stored from the
+    /// editor (see [`morph`](crate::morph)). Saved with the plugin's
+    /// state.
+    #[persist = "morph_a"]
+    pub morph_a: RwLock<Snapshot>,
+    #[persist = "morph_b"]
+    pub morph_b: RwLock<Snapshot>,
+    /// Where the knobs were when A or B was last stored or recalled:
+    /// Morph adds how far each has moved since (see
+    /// [Knobs on Top](crate::morph#knobs-on-top)).
+    #[persist = "morph_home"]
+    pub morph_home: RwLock<Snapshot>,
+
+    /// The snapshots boiled down for the audio thread, rebuilt whenever
+    /// they change.
+    pub morph_table: MorphTable,
 }
 
 impl Default for PluginParams {
     fn default() -> Self {
+        // The step pattern's defaults come from the engine rather than
+        // being spelled out eight times over.
+        let defaults = DelayParams::default();
+
+        let shown_time_snap = Arc::new(AtomicUsize::new(DelaySnap::Off.to_index()));
+        let sync_handover = Arc::new(SyncHandover::default());
+
         Self {
             delay_time: FloatParam::new(
                 "Delay Time",
                 500.0, // Default: 500ms
                 FloatRange::Skewed {
-                    min: 100.0,
-                    max: 2000.0,
+                    min: MIN_DELAY_MS,
+                    max: MAX_DELAY_MS,
                     // `skew_factor(-1.0)` biases the knob toward lower values.
                     // Negative = more resolution at the low end.
                     // Positive = more resolution at the high end.
@@ -108,12 +907,108 @@ impl Default for PluginParams {
                     factor: FloatRange::skew_factor(-1.0),
                 },
             )
-            .with_unit(" ms")
-            // Smooth delay time changes over 50ms to avoid clicks when
-            // the read position in the ring buffer jumps.
-            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit(DELAY_TIME_UNIT)
             // Snap to 0.1ms increments in the DAW UI. Sub-millisecond
             // precision isn't perceptually meaningful for delay time.
+            .with_step_size(0.1)
+            // Time Snap's grid goes further, for the time shown as well
+            // as the time played.
+            .with_value_to_string(snapped_ms(shown_time_snap.clone()))
+            .with_string_to_value(snapped_input(
+                shown_time_snap.clone(),
+                sync_handover.clone(),
+            )),
+
+            sync: BoolParam::new("Sync", false),
+            manual_bpm: FloatParam::new(
+                "Manual BPM",
+                defaults.manual_bpm,
+                FloatRange::Linear {
+                    min: MANUAL_BPM_RANGE.0,
+                    max: MANUAL_BPM_RANGE.1,
+                },
+            )
+            .with_unit(" BPM")
+            .with_step_size(0.1),
+            division: EnumParam::new("Division", SyncDivision::Quarter),
+            quantize_start: BoolParam::new("Quantize Start", true),
+            time_multiplier: EnumParam::new("Time Multiplier", DelayMultiplier::Single),
+            double_time: BoolParam::new("Double Time", false),
+            half_time: BoolParam::new("Half Time", false),
+            time_fine: FloatParam::new(
+                "Time Fine",
+                defaults.time_fine_ms,
+                FloatRange::Linear {
+                    min: -MAX_TIME_FINE_MS,
+                    max: MAX_TIME_FINE_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1),
+            time_snap: EnumParam::new("Time Snap", DelaySnap::Off).with_callback({
+                let shown_time_snap = shown_time_snap.clone();
+                Arc::new(move |snap: DelaySnap| {
+                    shown_time_snap.store(snap.to_index(), Ordering::Relaxed);
+                })
+            }),
+            shown_time_snap,
+            sync_handover,
+            time_change: EnumParam::new("Time Change", TimeChangeMode::Glide),
+            scrub: FloatParam::new(
+                "Scrub",
+                defaults.scrub,
+                FloatRange::Linear {
+                    min: -MAX_SCRUB,
+                    max: MAX_SCRUB,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            mod_waveform: EnumParam::new("Mod Waveform", ModWaveform::Sine),
+            mod_rate: FloatParam::new(
+                "Mod Rate",
+                defaults.mod_rate_hz,
+                FloatRange::Skewed {
+                    min: MOD_RATE_RANGE_HZ.0,
+                    max: MOD_RATE_RANGE_HZ.1,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(2))
+            .with_string_to_value(Arc::new(param_text::parse_hz)),
+            mod_depth: FloatParam::new(
+                "Mod Depth",
+                defaults.mod_depth_ms,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_MOD_DEPTH_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.01),
+            mod_glide: FloatParam::new(
+                "Mod Glide",
+                defaults.mod_glide,
+                FloatRange::Linear {
+                    min: MIN_GLIDE,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            pre_delay: FloatParam::new(
+                "Pre-Delay",
+                0.0, // Default: off
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_PRE_DELAY_MS,
+                },
+            )
+            .with_unit(" ms")
             .with_step_size(0.1),
 
             feedback: FloatParam::new(
@@ -121,14 +1016,22 @@ impl Default for PluginParams {
                 0.40, // Default: 40% — a moderate number of repeats
                 FloatRange::Linear {
                     min: 0.0,
-                    max: 0.95, // Capped below 1.0 for stability
+                    max: MAX_FEEDBACK, // Capped below 1.0 for stability
                 },
             )
             .with_unit("%")
-            .with_smoother(SmoothingStyle::Linear(20.0))
             // Display as percentage: 0.40 → "40.0%"
             .with_value_to_string(formatters::v2s_f32_percentage(1))
             .with_string_to_value(formatters::s2v_f32_percentage()),
+            hold: BoolParam::new("Hold", false),
+            hold_level: FloatParam::new(
+                "Hold Level",
+                defaults.hold_level,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
 
             mix: FloatParam::new(
                 "Mix",
@@ -136,27 +1039,884 @@ impl Default for PluginParams {
                 FloatRange::Linear { min: 0.0, max: 1.0 },
             )
             .with_unit("%")
-            .with_smoother(SmoothingStyle::Linear(20.0))
             .with_value_to_string(formatters::v2s_f32_percentage(1))
             .with_string_to_value(formatters::s2v_f32_percentage()),
 
-            filter_cutoff: FloatParam::new(
-                "Filter",
-                8000.0, // Default: 8 kHz — gentle high-end rolloff
+            filter_cutoff: cutoff_param("Filter L"),
+            filter_cutoff_right: cutoff_param("Filter R"),
+            filter_link: BoolParam::new("Filter Link", true),
+            feedback_compensation: BoolParam::new("Feedback Comp", defaults.feedback_compensation),
+
+            drive: FloatParam::new(
+                "Drive",
+                0.0, // Default: off
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(1))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            fb_chain_order: EnumParam::new("Feedback Order", FeedbackOrder::FilterThenDrive),
+            fb_low_shelf: shelf_param("Low Shelf", defaults.fb_low_shelf_db),
+            fb_high_shelf: shelf_param("High Shelf", defaults.fb_high_shelf_db),
+            fb_source: EnumParam::new("Feedback Source", FeedbackTap::WetTap),
+
+            character: FloatParam::new(
+                "Character",
+                0.0, // Default: pristine
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(1))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            damping: FloatParam::new(
+                "Damping",
+                defaults.damping,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            out_lowcut: FloatParam::new(
+                "Low Cut",
+                LOWCUT_OFF_HZ, // Default: off
+                FloatRange::Skewed {
+                    min: LOWCUT_OFF_HZ,
+                    max: 500.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(1.0)
+            // No `with_unit()`: the formatter adds " Hz" itself, so the
+            // off position reads "Off" rather than "Off Hz".
+            .with_value_to_string(off_or_hz(|hz| hz <= LOWCUT_OFF_HZ))
+            .with_string_to_value(hz_or_off(LOWCUT_OFF_HZ)),
+
+            out_highcut: FloatParam::new(
+                "High Cut",
+                HIGHCUT_OFF_HZ, // Default: off
                 FloatRange::Skewed {
-                    min: 200.0,
-                    max: 20000.0,
-                    // Stronger skew (-2.0) for frequency because human
-                    // frequency perception is roughly logarithmic.
-                    // The difference between 200 Hz and 400 Hz is huge;
-                    // the difference between 19800 Hz and 20000 Hz is
-                    // imperceptible. This skew matches perception.
+                    min: 1000.0,
+                    max: HIGHCUT_OFF_HZ,
                     factor: FloatRange::skew_factor(-2.0),
                 },
             )
-            .with_unit(" Hz")
-            .with_smoother(SmoothingStyle::Linear(50.0))
-            .with_step_size(1.0), // Whole Hz steps are fine
+            .with_step_size(1.0)
+            .with_value_to_string(off_or_hz(|hz| hz >= HIGHCUT_OFF_HZ))
+            .with_string_to_value(hz_or_off(HIGHCUT_OFF_HZ)),
+
+            mono_safe: BoolParam::new("Mono Safe", false),
+
+            limiter: BoolParam::new("Limiter", false),
+            external_loop: BoolParam::new("External Loop", false),
+
+            step_target: EnumParam::new("Step Target", StepDestination::Off),
+            step_length: IntParam::new(
+                "Steps",
+                defaults.step_length as i32,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_STEPS as i32,
+                },
+            ),
+            step_1: step_param("Step 1", defaults.steps[0]),
+            step_2: step_param("Step 2", defaults.steps[1]),
+            step_3: step_param("Step 3", defaults.steps[2]),
+            step_4: step_param("Step 4", defaults.steps[3]),
+            step_5: step_param("Step 5", defaults.steps[4]),
+            step_6: step_param("Step 6", defaults.steps[5]),
+            step_7: step_param("Step 7", defaults.steps[6]),
+            step_8: step_param("Step 8", defaults.steps[7]),
+
+            motion: EnumParam::new("Motion", MotionMode::Off),
+            motion_rotation: FloatParam::new(
+                "Rotation",
+                defaults.motion_rotation,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 180.0,
+                },
+            )
+            .with_step_size(1.0)
+            .with_unit("°"),
+            start_side: EnumParam::new("Start Side", PingPongStart::Left),
+            feedback_self: route_param("Self Feedback", 0.0, 1.0),
+            feedback_cross: route_param("Cross Feedback", 0.0, 0.0),
+            feedback_matrix_expert: BoolParam::new("Matrix Expert", false),
+            feedback_ll: route_param("L→L", -1.0, defaults.feedback_matrix.ll),
+            feedback_lr: route_param("L→R", -1.0, defaults.feedback_matrix.lr),
+            feedback_rl: route_param("R→L", -1.0, defaults.feedback_matrix.rl),
+            feedback_rr: route_param("R→R", -1.0, defaults.feedback_matrix.rr),
+            decorrelate: FloatParam::new(
+                "Decorrelate",
+                defaults.decorrelate,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            phase_align: BoolParam::new("Phase Align", defaults.phase_align),
+            dry_pan: FloatParam::new(
+                "Dry Pan",
+                defaults.dry_pan,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_panning())
+            .with_string_to_value(formatters::s2v_f32_panning()),
+            input_balance: FloatParam::new(
+                "Input Balance",
+                defaults.input_balance,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_panning())
+            .with_string_to_value(formatters::s2v_f32_panning()),
+            swap_channels: BoolParam::new("Swap Channels", defaults.swap_channels),
+            side_decay: FloatParam::new(
+                "Side Decay",
+                defaults.side_decay_db,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_SIDE_DECAY_DB,
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" dB"),
+            spread: FloatParam::new(
+                "Spread",
+                defaults.spread,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            alternate: BoolParam::new("Alternate", defaults.alternate),
+            bloom: FloatParam::new(
+                "Bloom",
+                defaults.bloom_db,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_BLOOM_DB,
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" dB"),
+            key_gate: BoolParam::new("Key Gate", defaults.key_gate),
+            key_threshold: FloatParam::new(
+                "Key Threshold",
+                defaults.key_threshold_db,
+                FloatRange::Linear {
+                    min: -60.0,
+                    max: 0.0,
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" dB"),
+            key_release: FloatParam::new(
+                "Key Release",
+                defaults.key_release_ms,
+                FloatRange::Skewed {
+                    min: 10.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_step_size(1.0)
+            .with_unit(" ms"),
+            duck_listen: BoolParam::new("Key Listen", false),
+
+            clear: BoolParam::new("Clear", false),
+            wet_solo: BoolParam::new("Wet Solo", false),
+            stop_behavior: EnumParam::new("Stop Behavior", StopMode::RingOut),
+
+            watchdog_enable: BoolParam::new("Watchdog", defaults.watchdog),
+            watchdog_time: FloatParam::new(
+                "Watchdog Time",
+                defaults.watchdog_seconds,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 120.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" s"),
+
+            freeze: BoolParam::new("Freeze", defaults.freeze),
+            freeze_quantize: BoolParam::new("Freeze Quantize", defaults.freeze_quantize),
+            overdub: BoolParam::new("Overdub", defaults.overdub),
+            overdub_decay: FloatParam::new(
+                "Overdub Decay",
+                defaults.overdub_decay_db,
+                FloatRange::Linear {
+                    min: OVERDUB_DECAY_RANGE_DB.0,
+                    max: OVERDUB_DECAY_RANGE_DB.1,
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" dB"),
+
+            morph: FloatParam::new("Morph", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage()),
+
+            preset_loudness_match: AtomicBool::new(false),
+            auto_economy: AtomicBool::new(false),
+            automation_smoothing: AtomicU8::new(0),
+            preset_trim: AtomicU32::new(0.0_f32.to_bits()),
+            tail_print_dir: RwLock::new(None),
+            morph_a: RwLock::new(Snapshot::new()),
+            morph_b: RwLock::new(Snapshot::new()),
+            morph_home: RwLock::new(Snapshot::new()),
+            morph_table: MorphTable::default(),
+        }
+    }
+}
+
+impl PluginParams {
+    /// Snapshot the current (unsmoothed) values for the engine.
+    pub fn delay_params(&self) -> DelayParams {
+        self.delay_params_with(&[])
+    }
+
+    /// Like [`delay_params()`](Self::delay_params), but with each
+    /// parameter in `overridden` read as if it were set to the
+    /// normalized value paired with it. Only the host can set a
+    /// parameter, so this is how a test walks one through its range, or
+    /// automates it under Morph, and hears what the engine does with it.
+    pub fn delay_params_with(&self, overridden: &[(ParamPtr, f32)]) -> DelayParams {
+        let morph_table = self.morph_table.read(self);
+        let read = ParamReader {
+            overridden,
+            morph: None,
+        };
+        let morph = read.value(&self.morph);
+        let read = ParamReader {
+            overridden,
+            morph: morph_table
+                .as_ref()
+                .filter(|table| !table.is_empty())
+                .map(|table| (table, morph)),
+        };
+        let params = DelayParams {
+            delay_ms: read.value(&self.delay_time),
+            sync: read.value(&self.sync),
+            manual_bpm: read.value(&self.manual_bpm),
+            division: read.value(&self.division).into(),
+            quantize_start: read.value(&self.quantize_start),
+            time_multiplier: read.value(&self.time_multiplier).into(),
+            double_time: read.value(&self.double_time),
+            half_time: read.value(&self.half_time),
+            time_fine_ms: read.value(&self.time_fine),
+            time_change: read.value(&self.time_change).into(),
+            scrub: read.value(&self.scrub),
+            mod_waveform: read.value(&self.mod_waveform).into(),
+            mod_rate_hz: read.value(&self.mod_rate),
+            mod_depth_ms: read.value(&self.mod_depth),
+            mod_glide: read.value(&self.mod_glide),
+            time_snap: read.value(&self.time_snap).into(),
+            feedback: read.value(&self.feedback),
+            hold: read.value(&self.hold),
+            hold_level: read.value(&self.hold_level),
+            pre_delay_ms: read.value(&self.pre_delay),
+            mix: read.value(&self.mix),
+            filter_cutoff: read.value(&self.filter_cutoff),
+            // The link lives here, at the parameter layer: the engine
+            // always runs two independent filters.
+            filter_cutoff_right: if read.value(&self.filter_link) {
+                read.value(&self.filter_cutoff)
+            } else {
+                read.value(&self.filter_cutoff_right)
+            },
+            feedback_compensation: read.value(&self.feedback_compensation),
+            feedback_source: read.value(&self.fb_source).into(),
+            drive: read.value(&self.drive),
+            chain_order: read.value(&self.fb_chain_order).into(),
+            fb_low_shelf_db: read.value(&self.fb_low_shelf),
+            fb_high_shelf_db: read.value(&self.fb_high_shelf),
+            character: read.value(&self.character),
+            damping: read.value(&self.damping),
+            out_lowcut: read.value(&self.out_lowcut),
+            out_highcut: read.value(&self.out_highcut),
+            mono_safe: read.value(&self.mono_safe),
+            limiter: read.value(&self.limiter),
+            external_loop: read.value(&self.external_loop),
+            step_target: read.value(&self.step_target).into(),
+            step_length: read.value(&self.step_length) as usize,
+            steps: [
+                &self.step_1,
+                &self.step_2,
+                &self.step_3,
+                &self.step_4,
+                &self.step_5,
+                &self.step_6,
+                &self.step_7,
+                &self.step_8,
+            ]
+            .map(|step| read.value(step)),
+            motion: read.value(&self.motion).into(),
+            motion_rotation: read.value(&self.motion_rotation),
+            start_side: read.value(&self.start_side).into(),
+            // Like the filter link, the simple view is a parameter-layer
+            // idea: the engine only ever sees the full matrix.
+            feedback_matrix: if read.value(&self.feedback_matrix_expert) {
+                FeedbackMatrix {
+                    ll: read.value(&self.feedback_ll),
+                    lr: read.value(&self.feedback_lr),
+                    rl: read.value(&self.feedback_rl),
+                    rr: read.value(&self.feedback_rr),
+                }
+            } else {
+                FeedbackMatrix::from_self_cross(
+                    read.value(&self.feedback_self),
+                    read.value(&self.feedback_cross),
+                )
+            },
+            decorrelate: read.value(&self.decorrelate),
+            phase_align: read.value(&self.phase_align),
+            dry_pan: read.value(&self.dry_pan),
+            input_balance: read.value(&self.input_balance),
+            swap_channels: read.value(&self.swap_channels),
+            side_decay_db: read.value(&self.side_decay),
+            spread: read.value(&self.spread),
+            alternate: read.value(&self.alternate),
+            bloom_db: read.value(&self.bloom),
+            key_gate: read.value(&self.key_gate),
+            key_threshold_db: read.value(&self.key_threshold),
+            key_release_ms: read.value(&self.key_release),
+            duck_listen: read.value(&self.duck_listen),
+            clear: read.value(&self.clear),
+            wet_solo: read.value(&self.wet_solo),
+            stop_behavior: read.value(&self.stop_behavior).into(),
+            watchdog: read.value(&self.watchdog_enable),
+            watchdog_seconds: read.value(&self.watchdog_time),
+            freeze: read.value(&self.freeze),
+            freeze_quantize: read.value(&self.freeze_quantize),
+            overdub: read.value(&self.overdub),
+            overdub_decay_db: read.value(&self.overdub_decay),
+            output_trim_db: 0.0,
+        };
+        // The loudness match follows the knobs as they move, so it keeps
+        // matching while you tweak a preset you're auditioning.
+        let output_trim_db = if self.preset_loudness_match.load(Ordering::Relaxed) {
+            presets::loudness_match_db(&params, self.preset_trim_db())
+        } else {
+            0.0
+        };
+        DelayParams {
+            output_trim_db,
+            ..params
+        }
+    }
+
+    /// The loaded preset's loudness trim, in dB.
+    pub fn preset_trim_db(&self) -> f32 {
+        f32::from_bits(self.preset_trim.load(Ordering::Relaxed))
+    }
+
+    /// The automation smoothing option. A stored value from a newer
+    /// version that this one doesn't know reads as Full.
+    pub fn automation_smoothing(&self) -> AutomationSmoothing {
+        let index = self.automation_smoothing.load(Ordering::Relaxed);
+        AutomationSmoothing::ALL
+            .get(usize::from(index))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Reads parameters for [`PluginParams::delay_params_with()`]: each at
+/// its current value, modulation included, or where it's overridden,
+/// and swept by Morph once A and B are stored.
+struct ParamReader<'a> {
+    overridden: &'a [(ParamPtr, f32)],
+    morph: Option<(&'a MorphReader<'a>, f32)>,
+}
+
+impl ParamReader<'_> {
+    fn value<P: Param>(&self, param: &P) -> P::Plain {
+        let overridden = self
+            .overridden
+            .iter()
+            .find(|(ptr, _)| *ptr == param.as_ptr())
+            .map(|&(_, normalized)| normalized);
+        let morphed = self.morph.and_then(|(table, morph)| {
+            let normalized = overridden.unwrap_or_else(|| param.modulated_normalized_value());
+            table.apply(param, normalized, morph)
+        });
+        match morphed.or(overridden) {
+            Some(normalized) => param.preview_plain(normalized),
+            None => param.modulated_plain_value(),
+        }
+    }
+}
+
+/// The feedback chain orders offered to the user.
+///
+/// This mirrors [`ChainOrder`] with the display names and stable IDs
+/// nih-plug needs; the DSP side stays free of plugin-framework types.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedbackOrder {
+    #[id = "filter-drive"]
+    #[name = "Filter → Drive"]
+    FilterThenDrive,
+
+    #[id = "drive-filter"]
+    #[name = "Drive → Filter"]
+    DriveThenFilter,
+}
+
+impl From<FeedbackOrder> for ChainOrder {
+    fn from(order: FeedbackOrder) -> Self {
+        match order {
+            FeedbackOrder::FilterThenDrive => ChainOrder::FilterThenDrive,
+            FeedbackOrder::DriveThenFilter => ChainOrder::DriveThenFilter,
+        }
+    }
+}
+
+/// The feedback sources offered to the user, mirroring
+/// [`FeedbackSource`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedbackTap {
+    #[id = "wet-tap"]
+    #[name = "Wet Tap"]
+    WetTap,
+
+    #[id = "post-mix"]
+    #[name = "Post-Mix"]
+    PostMix,
+}
+
+impl From<FeedbackTap> for FeedbackSource {
+    fn from(tap: FeedbackTap) -> Self {
+        match tap {
+            FeedbackTap::WetTap => FeedbackSource::WetTap,
+            FeedbackTap::PostMix => FeedbackSource::PostMix,
+        }
+    }
+}
+
+/// The synced note divisions offered to the user, mirroring
+/// [`NoteDivision`] the same way [`FeedbackOrder`] mirrors
+/// [`ChainOrder`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDivision {
+    #[id = "1/1"]
+    #[name = "1/1"]
+    Whole,
+
+    #[id = "1/2"]
+    #[name = "1/2"]
+    Half,
+
+    #[id = "1/4d"]
+    #[name = "1/4 dotted"]
+    DottedQuarter,
+
+    #[id = "1/4"]
+    #[name = "1/4"]
+    Quarter,
+
+    #[id = "1/4t"]
+    #[name = "1/4 triplet"]
+    QuarterTriplet,
+
+    #[id = "1/8d"]
+    #[name = "1/8 dotted"]
+    DottedEighth,
+
+    #[id = "1/8"]
+    #[name = "1/8"]
+    Eighth,
+
+    #[id = "1/8t"]
+    #[name = "1/8 triplet"]
+    EighthTriplet,
+
+    #[id = "1/16"]
+    #[name = "1/16"]
+    Sixteenth,
+}
+
+impl From<SyncDivision> for NoteDivision {
+    fn from(division: SyncDivision) -> Self {
+        match division {
+            SyncDivision::Whole => NoteDivision::Whole,
+            SyncDivision::Half => NoteDivision::Half,
+            SyncDivision::DottedQuarter => NoteDivision::DottedQuarter,
+            SyncDivision::Quarter => NoteDivision::Quarter,
+            SyncDivision::QuarterTriplet => NoteDivision::QuarterTriplet,
+            SyncDivision::DottedEighth => NoteDivision::DottedEighth,
+            SyncDivision::Eighth => NoteDivision::Eighth,
+            SyncDivision::EighthTriplet => NoteDivision::EighthTriplet,
+            SyncDivision::Sixteenth => NoteDivision::Sixteenth,
+        }
+    }
+}
+
+impl From<NoteDivision> for SyncDivision {
+    fn from(division: NoteDivision) -> Self {
+        match division {
+            NoteDivision::Whole => Self::Whole,
+            NoteDivision::Half => Self::Half,
+            NoteDivision::DottedQuarter => Self::DottedQuarter,
+            NoteDivision::Quarter => Self::Quarter,
+            NoteDivision::QuarterTriplet => Self::QuarterTriplet,
+            NoteDivision::DottedEighth => Self::DottedEighth,
+            NoteDivision::Eighth => Self::Eighth,
+            NoteDivision::EighthTriplet => Self::EighthTriplet,
+            NoteDivision::Sixteenth => Self::Sixteenth,
+        }
+    }
+}
+
+/// The delay time multipliers offered to the user, mirroring
+/// [`TimeMultiplier`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayMultiplier {
+    #[id = "x0.5"]
+    #[name = "×0.5"]
+    Half,
+
+    #[id = "x1"]
+    #[name = "×1"]
+    Single,
+
+    #[id = "x1.5"]
+    #[name = "×1.5"]
+    Dotted,
+
+    #[id = "x2"]
+    #[name = "×2"]
+    Double,
+}
+
+impl From<DelayMultiplier> for TimeMultiplier {
+    fn from(multiplier: DelayMultiplier) -> Self {
+        match multiplier {
+            DelayMultiplier::Half => TimeMultiplier::Half,
+            DelayMultiplier::Single => TimeMultiplier::Single,
+            DelayMultiplier::Dotted => TimeMultiplier::Dotted,
+            DelayMultiplier::Double => TimeMultiplier::Double,
+        }
+    }
+}
+
+/// How a new delay time is reached, mirroring [`TimeChange`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeChangeMode {
+    #[id = "glide"]
+    #[name = "Glide"]
+    Glide,
+
+    #[id = "crossfade"]
+    #[name = "Crossfade"]
+    Crossfade,
+
+    #[id = "tape"]
+    #[name = "Tape"]
+    Tape,
+}
+
+impl From<TimeChangeMode> for TimeChange {
+    fn from(mode: TimeChangeMode) -> Self {
+        match mode {
+            TimeChangeMode::Glide => TimeChange::Glide,
+            TimeChangeMode::Crossfade => TimeChange::Crossfade,
+            TimeChangeMode::Tape => TimeChange::Tape,
+        }
+    }
+}
+
+/// The time modulation's waveforms offered to the user, from the
+/// [`LfoWaveform`]s that glide: saw and square jump the delay time every
+/// cycle, which clicks.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModWaveform {
+    #[id = "sine"]
+    #[name = "Sine"]
+    Sine,
+
+    #[id = "triangle"]
+    #[name = "Triangle"]
+    Triangle,
+
+    #[id = "s&h"]
+    #[name = "S&H"]
+    SampleAndHold,
+}
+
+impl From<ModWaveform> for LfoWaveform {
+    fn from(waveform: ModWaveform) -> Self {
+        match waveform {
+            ModWaveform::Sine => LfoWaveform::Sine,
+            ModWaveform::Triangle => LfoWaveform::Triangle,
+            ModWaveform::SampleAndHold => LfoWaveform::SampleAndHold,
+        }
+    }
+}
+
+/// The delay time grids offered to the user, mirroring [`TimeSnap`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelaySnap {
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+
+    #[id = "1ms"]
+    #[name = "1 ms"]
+    Ms1,
+
+    #[id = "5ms"]
+    #[name = "5 ms"]
+    Ms5,
+
+    #[id = "10ms"]
+    #[name = "10 ms"]
+    Ms10,
+
+    #[id = "musical"]
+    #[name = "Musical"]
+    Musical,
+}
+
+impl From<DelaySnap> for TimeSnap {
+    fn from(snap: DelaySnap) -> Self {
+        match snap {
+            DelaySnap::Off => TimeSnap::Off,
+            DelaySnap::Ms1 => TimeSnap::Ms1,
+            DelaySnap::Ms5 => TimeSnap::Ms5,
+            DelaySnap::Ms10 => TimeSnap::Ms10,
+            DelaySnap::Musical => TimeSnap::Musical,
+        }
+    }
+}
+
+/// What the per-repeat steps can modulate, mirroring [`StepTarget`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepDestination {
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+
+    #[id = "cutoff"]
+    #[name = "Cutoff"]
+    Cutoff,
+}
+
+impl From<StepDestination> for StepTarget {
+    fn from(destination: StepDestination) -> Self {
+        match destination {
+            StepDestination::Off => StepTarget::Off,
+            StepDestination::Cutoff => StepTarget::Cutoff,
+        }
+    }
+}
+
+/// How the repeats move around the stereo field, mirroring
+/// [`StereoMotion`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionMode {
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+
+    #[id = "ping_pong"]
+    #[name = "Ping-Pong"]
+    PingPong,
+
+    #[id = "reverse_ping_pong"]
+    #[name = "Reverse Ping-Pong"]
+    ReversePingPong,
+
+    #[id = "circular"]
+    #[name = "Circular"]
+    Circular,
+}
+
+impl From<MotionMode> for StereoMotion {
+    fn from(mode: MotionMode) -> Self {
+        match mode {
+            MotionMode::Off => StereoMotion::Off,
+            MotionMode::PingPong => StereoMotion::PingPong,
+            MotionMode::ReversePingPong => StereoMotion::ReversePingPong,
+            MotionMode::Circular => StereoMotion::Circular,
+        }
+    }
+}
+
+/// Where ping-pong starts, mirroring [`StartSide`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingPongStart {
+    #[id = "left"]
+    #[name = "Left"]
+    Left,
+
+    #[id = "right"]
+    #[name = "Right"]
+    Right,
+
+    #[id = "center"]
+    #[name = "Center"]
+    Center,
+}
+
+impl From<PingPongStart> for StartSide {
+    fn from(start: PingPongStart) -> Self {
+        match start {
+            PingPongStart::Left => StartSide::Left,
+            PingPongStart::Right => StartSide::Right,
+            PingPongStart::Center => StartSide::Center,
+        }
+    }
+}
+
+/// What the transport stopping does, mirroring [`StopBehavior`].
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMode {
+    #[id = "ring_out"]
+    #[name = "Ring Out"]
+    RingOut,
+
+    #[id = "fade"]
+    #[name = "Fade 500 ms"]
+    Fade,
+
+    #[id = "clear"]
+    #[name = "Immediate Clear"]
+    Clear,
+}
+
+impl From<StopMode> for StopBehavior {
+    fn from(mode: StopMode) -> Self {
+        match mode {
+            StopMode::RingOut => StopBehavior::RingOut,
+            StopMode::Fade => StopBehavior::Fade,
+            StopMode::Clear => StopBehavior::Clear,
         }
     }
 }
+
+/// One step of the per-repeat pattern, shown as a percentage.
+fn step_param(name: &str, default: f32) -> FloatParam {
+    FloatParam::new(name, default, FloatRange::Linear { min: 0.0, max: 1.0 })
+        .with_unit("%")
+        .with_value_to_string(formatters::v2s_f32_percentage(0))
+        .with_string_to_value(formatters::s2v_f32_percentage())
+}
+
+/// One route of the feedback matrix, from `min` (0% or −100%) to 100%.
+fn route_param(name: &str, min: f32, default: f32) -> FloatParam {
+    FloatParam::new(name, default, FloatRange::Linear { min, max: 1.0 })
+        .with_unit("%")
+        .with_value_to_string(formatters::v2s_f32_percentage(0))
+        .with_string_to_value(formatters::s2v_f32_percentage())
+}
+
+/// A feedback filter cutoff knob. Both sides share the same range, so
+/// they're built by the same function.
+fn cutoff_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        8000.0, // Default: 8 kHz — gentle high-end rolloff
+        FloatRange::Skewed {
+            min: 200.0,
+            max: 20000.0,
+            // Stronger skew (-2.0) for frequency because human
+            // frequency perception is roughly logarithmic.
+            // The difference between 200 Hz and 400 Hz is huge;
+            // the difference between 19800 Hz and 20000 Hz is
+            // imperceptible. This skew matches perception.
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    .with_unit(" Hz")
+    .with_step_size(1.0) // Whole Hz steps are fine
+    .with_string_to_value(Arc::new(param_text::parse_hz))
+}
+
+/// A feedback shelf's gain knob, ±[`MAX_SHELF_DB`] in 0.1 dB steps, so
+/// the centre lands on 0 dB exactly and bypasses.
+fn shelf_param(name: &str, default_db: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default_db,
+        FloatRange::Linear {
+            min: -MAX_SHELF_DB,
+            max: MAX_SHELF_DB,
+        },
+    )
+    .with_step_size(0.1)
+    .with_unit(" dB")
+}
+
+/// The formatter closures nih-plug takes for a `FloatParam`.
+type ValueToString = Arc<dyn Fn(f32) -> String + Send + Sync>;
+type StringToValue = Arc<dyn Fn(&str) -> Option<f32> + Send + Sync>;
+
+/// Show an output filter's corner in Hz, or "Off" at its bypass end.
+fn off_or_hz(is_off: fn(f32) -> bool) -> ValueToString {
+    Arc::new(move |hz| {
+        if is_off(hz) {
+            String::from("Off")
+        } else {
+            format!("{hz:.0} Hz")
+        }
+    })
+}
+
+/// Parse a typed-in corner frequency (see [`param_text::parse_hz()`]),
+/// where "off" means `off_hz`.
+fn hz_or_off(off_hz: f32) -> StringToValue {
+    Arc::new(move |text| {
+        if text.trim().eq_ignore_ascii_case("off") {
+            Some(off_hz)
+        } else {
+            param_text::parse_hz(text)
+        }
+    })
+}
+
+/// `delay_ms` on the Time Snap grid whose index is in `snap`.
+fn snap_delay_ms(snap: &AtomicUsize, delay_ms: f32) -> f32 {
+    TimeSnap::from(DelaySnap::from_index(snap.load(Ordering::Relaxed))).apply(
+        delay_ms,
+        MIN_DELAY_MS,
+        MAX_DELAY_MS,
+    )
+}
+
+/// Show the delay time as it plays: on the Time Snap grid.
+fn snapped_ms(snap: Arc<AtomicUsize>) -> ValueToString {
+    Arc::new(move |ms| delay_ms_text(snap_delay_ms(&snap, ms)))
+}
+
+/// A delay time as Delay Time shows it, without its unit (see
+/// [`DELAY_TIME_UNIT`]). The editor's time readout shows it the same way.
+pub fn delay_ms_text(delay_ms: f32) -> String {
+    format!("{delay_ms:.1}")
+}
+
+/// A note division as Division names it.
+pub fn division_name(division: NoteDivision) -> &'static str {
+    SyncDivision::variants()[SyncDivision::from(division).to_index()]
+}
+
+/// Parse a typed-in delay time onto the Time Snap grid: milliseconds,
+/// or a note at the tempo `sync_handover` last heard of, or at one typed
+/// with it (see [`param_text::parse_delay_ms()`]).
+fn snapped_input(snap: Arc<AtomicUsize>, sync_handover: Arc<SyncHandover>) -> StringToValue {
+    Arc::new(move |text| {
+        let ms = param_text::parse_delay_ms(text, sync_handover.tempo())?;
+        Some(snap_delay_ms(&snap, ms))
+    })
+}
@@ -0,0 +1,175 @@
+//! # Plugin IDs That Follow Each Other
+//!
+//! Every plugin format identifies a plugin by an ID of its own: CLAP by a
+//! reverse-domain string (`com.loveless-audio.loveless-delay-v1`), VST3 by
+//! a 16-byte class ID. Hosts use them to tell plugins apart and to find
+//! the right plugin again when a session is reopened, so two different
+//! plugins must never share one.
+//!
+//! That's easy to get wrong in a fork. Someone copies this repo, changes
+//! the CLAP ID to their own domain, and forgets the VST3 ID — and now two
+//! different plugins claim the same class ID, and a host will load
+//! whichever it found first.
+//!
+//! So the VST3 class ID isn't written out by hand. [`vst3_class_id()`]
+//! derives it from the CLAP ID (plus a version namespace) at compile
+//! time: change the CLAP ID and the VST3 ID changes with it.
+//!
+//! ## Hashing at Compile Time
+//!
+//! The derivation is a `const fn`, so the ID is computed by the compiler
+//! and baked into the binary like any literal. It runs FNV-1a — a tiny
+//! hash: XOR each byte in, multiply by a prime — twice over the input
+//! with different starting values, for 2 × 64 bits. FNV alone lets a
+//! change in the last byte reach only some of the output bits, so each
+//! half then goes through MurmurHash3's 64-bit finalizer, which mixes
+//! every input bit into every output bit.
+//!
+//! ## The Original ID
+//!
+//! This plugin shipped with the hand-written ID `*b"LvlssDelay__v001"`,
+//! and hosts have saved it into existing sessions. Changing it would
+//! make those sessions lose the plugin, so the original CLAP ID (in its
+//! original namespace) keeps its original VST3 ID. Any other CLAP ID gets
+//! a hashed one.
+
+/// The CLAP ID the plugin shipped with.
+pub const ORIGINAL_CLAP_ID: &str = "com.loveless-audio.loveless-delay-v1";
+
+/// The VST3 class ID the plugin shipped with, kept for
+/// [`ORIGINAL_CLAP_ID`] so saved sessions still find it.
+pub const ORIGINAL_VST3_CLASS_ID: [u8; 16] = *b"LvlssDelay__v001";
+
+/// The namespace the original IDs belong to. Bump it (and the hashed
+/// VST3 ID changes) when a release is deliberately incompatible with
+/// sessions saved by the previous one.
+pub const ORIGINAL_NAMESPACE: &str = "v1";
+
+/// FNV-1a's 64-bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a's standard 64-bit starting value.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// A second starting value, so the two halves of the ID are independent
+/// hashes of the same input.
+const FNV_OFFSET_SECOND: u64 = 0x6c62_272e_07bb_0142;
+
+/// The VST3 class ID for a plugin with `clap_id`, in `namespace`.
+///
+/// The original CLAP ID in the original namespace gets
+/// [`ORIGINAL_VST3_CLASS_ID`]; everything else gets 16 bytes hashed from
+/// the namespace and the CLAP ID.
+pub const fn vst3_class_id(clap_id: &str, namespace: &str) -> [u8; 16] {
+    if str_eq(clap_id, ORIGINAL_CLAP_ID) && str_eq(namespace, ORIGINAL_NAMESPACE) {
+        return ORIGINAL_VST3_CLASS_ID;
+    }
+
+    let high = hash(clap_id, namespace, FNV_OFFSET).to_be_bytes();
+    let low = hash(clap_id, namespace, FNV_OFFSET_SECOND).to_be_bytes();
+
+    let mut id = [0; 16];
+    let mut i = 0;
+    while i < 8 {
+        id[i] = high[i];
+        id[i + 8] = low[i];
+        i += 1;
+    }
+    id
+}
+
+/// FNV-1a over `namespace`, a zero byte, and `clap_id`. The separator
+/// keeps `("ab", "c")` and `("a", "bc")` apart.
+const fn hash(clap_id: &str, namespace: &str, offset: u64) -> u64 {
+    let hash = fnv1a(namespace.as_bytes(), offset);
+    let hash = fnv1a(&[0], hash);
+    finalize(fnv1a(clap_id.as_bytes(), hash))
+}
+
+/// MurmurHash3's `fmix64`: shifts and multiplies that spread every bit
+/// of `hash` across the whole word.
+const fn finalize(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+/// Feed `bytes` into an FNV-1a hash that has reached `hash`.
+const fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
+
+/// String equality usable in a `const fn` (`==` on `&str` isn't yet).
+pub const fn str_eq(a: &str, b: &str) -> bool {
+    bytes_eq(a.as_bytes(), b.as_bytes())
+}
+
+/// Byte-slice equality usable in a `const fn`.
+pub const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The shipped plugin keeps the ID saved sessions know it by.
+    #[test]
+    fn test_original_id_is_kept() {
+        assert_eq!(
+            vst3_class_id(ORIGINAL_CLAP_ID, ORIGINAL_NAMESPACE),
+            ORIGINAL_VST3_CLASS_ID
+        );
+    }
+
+    /// A fork's CLAP ID, or a new namespace, gets its own ID: not the
+    /// original, and not all zeros.
+    #[test]
+    fn test_forks_get_their_own_id() {
+        let fork = vst3_class_id("com.example.my-delay", ORIGINAL_NAMESPACE);
+        let bumped = vst3_class_id(ORIGINAL_CLAP_ID, "v2");
+        for id in [fork, bumped] {
+            assert_ne!(id, ORIGINAL_VST3_CLASS_ID);
+            assert_ne!(id, [0; 16]);
+        }
+        assert_ne!(fork, bumped);
+    }
+
+    /// The same inputs always give the same ID, and nearby inputs give
+    /// unrelated ones.
+    #[test]
+    fn test_hash_is_stable_and_spread() {
+        let a = vst3_class_id("com.example.delay", "v1");
+        assert_eq!(a, vst3_class_id("com.example.delay", "v1"));
+
+        let b = vst3_class_id("com.example.delaz", "v1");
+        let differing = a.iter().zip(&b).filter(|(x, y)| x != y).count();
+        assert!(differing >= 8, "only {differing} bytes differ");
+
+        // The separator keeps the namespace and the ID apart.
+        assert_ne!(vst3_class_id("bc", "a"), vst3_class_id("c", "ab"));
+    }
+}
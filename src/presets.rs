@@ -0,0 +1,254 @@
+//! # Factory Presets and Loudness-Matched Auditioning
+//!
+//! Flip through presets and the loudest one always seems best: a couple
+//! of dB more reads as "fuller" or "more exciting", whatever the sound.
+//! A delay is especially prone to it. High feedback piles repeat on
+//! repeat, and a wet mix near 100% can come out several dB hotter (or,
+//! with a dark filter, quieter) than the dry track.
+//!
+//! So while *preset loudness matching* is on, the plugin adds a hidden
+//! output trim that brings every preset to about the level of its
+//! input, and comparisons come down to the sound.
+//!
+//! ## Estimating the Level
+//!
+//! Most of the difference can be worked out from two knobs. For a
+//! signal like music or noise, each repeat is unrelated to the dry
+//! signal and to the other repeats, so their *powers* add. Each repeat
+//! has `feedback²` the power of the one before, which makes a geometric
+//! series:
+//!
+//! ```text
+//! dry power  = (1 − mix)²
+//! wet power  = mix² · (1 + fb² + fb⁴ + …) = mix² / (1 − fb²)
+//! level (dB) = 10 · log10(dry power + wet power)
+//! ```
+//!
+//! That's [`estimated_level_db()`]. It leaves out what the repeats lose
+//! on the way round — each pass through a dark filter takes more off
+//! than the series assumes — and what drive squashes. Those are
+//! measured once per factory preset and stored with it as
+//! [`FactoryPreset::trim_db`], so the match is
+//!
+//! ```text
+//! trim = −estimated_level_db + preset trim
+//! ```
+//!
+//! ([`loudness_match_db()`]). The plugin stores the trim in its own
+//! state, next to the parameters, so it travels with a preset file and
+//! with any session saved from one.
+//!
+//! ## Where the Presets Come From
+//!
+//! nih-plug has no API for shipping factory presets inside the plugin,
+//! so hosts can't list these yet. They're the data model: the values,
+//! their trims, and the estimate, for a preset browser or for preset
+//! files generated from them.
+
+use crate::dsp::stereo_motion::StereoMotion;
+use crate::engine::{DelayParams, MAX_FEEDBACK};
+
+/// The furthest the loudness match moves the output either way, in dB.
+/// The estimate can run away near full feedback; a match that large
+/// would be a mistake, not a correction.
+pub const MAX_MATCH_DB: f32 = 18.0;
+
+/// A preset that ships with the plugin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FactoryPreset {
+    pub name: &'static str,
+    pub params: DelayParams,
+
+    /// The correction, in dB, for what [`estimated_level_db()`] misses
+    /// on this preset (filter losses, drive). Added on top of the
+    /// estimate's own compensation.
+    pub trim_db: f32,
+}
+
+/// The factory presets, in the order a browser lists them.
+pub fn factory_presets() -> [FactoryPreset; 6] {
+    let defaults = DelayParams::default();
+    [
+        FactoryPreset {
+            name: "Slapback",
+            params: DelayParams {
+                delay_ms: 120.0,
+                feedback: 0.1,
+                mix: 0.35,
+                filter_cutoff: 6000.0,
+                filter_cutoff_right: 6000.0,
+                ..defaults
+            },
+            trim_db: 0.0,
+        },
+        FactoryPreset {
+            name: "Quarter Echo",
+            params: DelayParams {
+                delay_ms: 500.0,
+                feedback: 0.4,
+                mix: 0.4,
+                ..defaults
+            },
+            trim_db: 0.1,
+        },
+        FactoryPreset {
+            name: "Dub Tape",
+            params: DelayParams {
+                delay_ms: 375.0,
+                feedback: 0.7,
+                mix: 0.5,
+                filter_cutoff: 2000.0,
+                filter_cutoff_right: 2000.0,
+                drive: 0.4,
+                ..defaults
+            },
+            trim_db: 1.1,
+        },
+        FactoryPreset {
+            name: "Ambient Wash",
+            params: DelayParams {
+                delay_ms: 800.0,
+                feedback: 0.85,
+                mix: 0.6,
+                filter_cutoff: 4000.0,
+                filter_cutoff_right: 4000.0,
+                decorrelate: 0.6,
+                ..defaults
+            },
+            trim_db: 0.6,
+        },
+        FactoryPreset {
+            name: "Ping-Pong",
+            params: DelayParams {
+                delay_ms: 300.0,
+                feedback: 0.55,
+                mix: 0.45,
+                motion: StereoMotion::PingPong,
+                ..defaults
+            },
+            // Ping-pong feeds the loop the mono sum, which for a wide
+            // input has less power than the two sides apart.
+            trim_db: 2.1,
+        },
+        FactoryPreset {
+            name: "Dark Space",
+            params: DelayParams {
+                delay_ms: 1200.0,
+                feedback: 0.75,
+                mix: 0.7,
+                filter_cutoff: 800.0,
+                filter_cutoff_right: 800.0,
+                ..defaults
+            },
+            trim_db: 1.1,
+        },
+    ]
+}
+
+/// The output level of `params` relative to its input, in dB, from the
+/// feedback and mix alone. See the [module docs](self#estimating-the-level).
+pub fn estimated_level_db(params: &DelayParams) -> f32 {
+    let feedback = params.feedback.clamp(0.0, MAX_FEEDBACK);
+    let mix = params.mix.clamp(0.0, 1.0);
+    let dry = (1.0 - mix) * (1.0 - mix);
+    let wet = mix * mix / (1.0 - feedback * feedback);
+    10.0 * (dry + wet).log10()
+}
+
+/// The output trim, in dB, that brings `params` to about its input's
+/// level: the estimate's compensation plus the preset's own `trim_db`,
+/// within ±[`MAX_MATCH_DB`].
+pub fn loudness_match_db(params: &DelayParams, trim_db: f32) -> f32 {
+    (trim_db - estimated_level_db(params)).clamp(-MAX_MATCH_DB, MAX_MATCH_DB)
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::noise::{NoiseColor, NoiseGenerator};
+    use crate::LovelessDelayEngine;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// The RMS level, in dB, of stereo pink noise through `params`, after
+    /// the longest preset's repeats have built up.
+    fn render_level_db(params: &DelayParams) -> f32 {
+        const BLOCK: usize = 1024;
+        const SETTLE: usize = 8 * SAMPLE_RATE as usize;
+        const MEASURE: usize = 4 * SAMPLE_RATE as usize;
+
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(params);
+        let mut noise = [NoiseGenerator::new(3), NoiseGenerator::new(7)];
+        for generator in &mut noise {
+            generator.set_color(NoiseColor::Pink);
+        }
+
+        let mut energy = 0.0_f64;
+        for start in (0..SETTLE + MEASURE).step_by(BLOCK) {
+            let [mut left, mut right] =
+                [0, 1].map(|side| (0..BLOCK).map(|_| noise[side].next()).collect::<Vec<_>>());
+            engine.process(&mut [&mut left, &mut right]);
+            if start >= SETTLE {
+                energy += left
+                    .iter()
+                    .chain(&right)
+                    .map(|s| f64::from(*s) * f64::from(*s))
+                    .sum::<f64>();
+            }
+        }
+        let mean = energy / (2 * MEASURE) as f64;
+        10.0 * mean.log10() as f32
+    }
+
+    /// Dry only, and a fully wet single echo, are both at the input's
+    /// level; full feedback is the series' sum.
+    #[test]
+    fn test_estimate_edges() {
+        let dry = DelayParams {
+            mix: 0.0,
+            ..DelayParams::default()
+        };
+        assert_eq!(estimated_level_db(&dry), 0.0);
+        let echo = DelayParams {
+            mix: 1.0,
+            feedback: 0.0,
+            ..DelayParams::default()
+        };
+        assert_eq!(estimated_level_db(&echo), 0.0);
+        let loud = DelayParams {
+            mix: 1.0,
+            feedback: MAX_FEEDBACK,
+            ..DelayParams::default()
+        };
+        // 10 · log10(1 / (1 − 0.95²)) ≈ 10.11 dB too loud.
+        assert!((loudness_match_db(&loud, 0.0) + 10.11).abs() < 0.01);
+    }
+
+    /// With matching on, pink noise through every factory preset comes
+    /// out within ±1.5 dB of the same level.
+    #[test]
+    fn test_factory_presets_match_within_1_5_db() {
+        let levels: Vec<(&str, f32)> = factory_presets()
+            .iter()
+            .map(|preset| {
+                let params = DelayParams {
+                    output_trim_db: loudness_match_db(&preset.params, preset.trim_db),
+                    ..preset.params
+                };
+                (preset.name, render_level_db(&params))
+            })
+            .collect();
+        let mean = levels.iter().map(|(_, level)| level).sum::<f32>() / levels.len() as f32;
+        for (name, level) in &levels {
+            assert!(
+                (level - mean).abs() <= 1.5,
+                "{name}: {level:.2} dB against a mean of {mean:.2} dB ({levels:?})"
+            );
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! # Ringing Out an Offline Render
+//!
+//! An offline render that stops when its input does cuts the last
+//! echoes off. With `--ring-out`, the render harness
+//! (`examples/render_delay.rs`, and the standalone app once there is
+//! one) keeps feeding the engine silence after the input ends, until
+//! the tail is over:
+//!
+//! ```text
+//! input   ██████▇▅▃
+//! output  ██████▇▅▃ ▅ ▄ ▃ ▂ ▁ · ·      ◄ rings out, then stops
+//!                  └── silence in ──┘
+//! ```
+//!
+//! ## The Stop Condition
+//!
+//! The harness goes by the same [`ProcessStatus`] the plugin hands the
+//! host after every block ([`tail_status()`]), so a render ends where a
+//! host would stop calling `process()`:
+//!
+//! ```text
+//! Normal      the tail is measured to be over      stop now
+//! Tail(n)     n more samples at most               stop by then, sooner if a
+//!                                                  later block reports less
+//! KeepAlive   no end in sight                      go on to the limit
+//! ```
+//!
+//! A tail only ever gets shorter: a later `Tail(n)` that reaches past
+//! the end already promised doesn't move it. And nothing goes on past
+//! [`MAX_RING_OUT_SECONDS`], for a held or frozen loop that never dies
+//! away.
+
+use nih_plug::prelude::ProcessStatus;
+
+use crate::engine::LovelessDelayEngine;
+
+/// The longest a ring-out goes on, in seconds.
+pub const MAX_RING_OUT_SECONDS: f32 = 30.0;
+
+/// What the plugin tells the host after a block: how much tail `engine`
+/// has left, or [`ProcessStatus::Normal`] once it's over.
+pub fn tail_status(engine: &LovelessDelayEngine) -> ProcessStatus {
+    match engine.tail_samples() {
+        Some(samples) => ProcessStatus::Tail(samples),
+        None => ProcessStatus::Normal,
+    }
+}
+
+/// Counts a ring-out down by what each block reports. See
+/// [the module docs](self#the-stop-condition).
+#[derive(Debug, Clone, Copy)]
+pub struct RingOut {
+    /// Samples played since the input ended.
+    rendered: usize,
+
+    /// How many samples in the ring-out ends.
+    end: usize,
+}
+
+impl RingOut {
+    /// A ring-out after a last block of input that reported `status`,
+    /// going on for `max_samples` at most.
+    pub fn new(status: ProcessStatus, max_samples: usize) -> Self {
+        let mut ring_out = Self {
+            rendered: 0,
+            end: max_samples,
+        };
+        ring_out.report(status);
+        ring_out
+    }
+
+    /// How long the next block of silence should be, at most
+    /// `block_size`, or `None` once the ring-out is over.
+    pub fn next_block(&self, block_size: usize) -> Option<usize> {
+        let left = self.end.saturating_sub(self.rendered);
+        (left > 0).then(|| left.min(block_size.max(1)))
+    }
+
+    /// Count a block of `len` samples, after which the plugin reported
+    /// `status`.
+    pub fn advance(&mut self, len: usize, status: ProcessStatus) {
+        self.rendered += len;
+        self.report(status);
+    }
+
+    /// Samples played since the input ended.
+    pub fn rendered(&self) -> usize {
+        self.rendered
+    }
+
+    fn report(&mut self, status: ProcessStatus) {
+        match status {
+            ProcessStatus::Tail(samples) => {
+                self.end = self.end.min(self.rendered.saturating_add(samples as usize));
+            }
+            ProcessStatus::KeepAlive => {}
+            // Over, or gone wrong: either way, nothing more to play.
+            _ => self.end = self.rendered,
+        }
+    }
+}
+
+/// Feed `engine` silence after its input ends, in blocks of up to
+/// `block_size`, for as long as [`RingOut`] says, and return what it
+/// played, one `Vec` per channel.
+///
+/// Allocates as it goes: for offline renders, not the audio thread.
+pub fn ring_out(engine: &mut LovelessDelayEngine, block_size: usize) -> Vec<Vec<f32>> {
+    let max_samples = (MAX_RING_OUT_SECONDS * engine.sample_rate()) as usize;
+    let mut ring_out = RingOut::new(tail_status(engine), max_samples);
+    let channels = engine.channels();
+    let mut tail = vec![Vec::new(); channels];
+    let mut block = vec![vec![0.0; block_size.max(1)]; channels];
+    while let Some(len) = ring_out.next_block(block_size) {
+        let mut slices: Vec<&mut [f32]> = block
+            .iter_mut()
+            .map(|samples| {
+                samples.fill(0.0);
+                &mut samples[..len]
+            })
+            .collect();
+        engine.process(&mut slices);
+        for (samples, played) in tail.iter_mut().zip(&block) {
+            samples.extend_from_slice(&played[..len]);
+        }
+        ring_out.advance(len, tail_status(engine));
+    }
+    tail
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::DelayParams;
+    use crate::tail_print::write_wav;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    const BLOCK: usize = 512;
+
+    /// Where the impulse goes: after the engine's engage fade, so it's
+    /// heard in full.
+    const IMPULSE_AT: usize = BLOCK;
+
+    /// A fresh stereo engine at `params` after `len` samples of input
+    /// that's silence but for a full-scale impulse, and what they
+    /// played.
+    fn after_impulse(params: &DelayParams, len: usize) -> (LovelessDelayEngine, [Vec<f32>; 2]) {
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        engine.set_params(params);
+        let mut input = [vec![0.0; len], vec![0.0; len]];
+        input[0][IMPULSE_AT] = 1.0;
+        input[1][IMPULSE_AT] = 1.0;
+        let [left, right] = &mut input;
+        engine.process(&mut [left, right]);
+        (engine, input)
+    }
+
+    /// An impulse into long feedback renders to a file as long as the
+    /// input plus the tail reported when it ended, within one buffer,
+    /// with the echoes in it.
+    #[test]
+    fn test_file_lasts_the_reported_tail() {
+        let (mut engine, input) = after_impulse(
+            &DelayParams {
+                delay_ms: 100.0,
+                feedback: 0.9,
+                mix: 1.0,
+                filter_cutoff: 20000.0,
+                filter_cutoff_right: 20000.0,
+                ..DelayParams::default()
+            },
+            2 * BLOCK,
+        );
+        let reported = engine.tail_samples().expect("no tail reported") as usize;
+
+        let tail = ring_out(&mut engine, BLOCK);
+        let rendered: Vec<Vec<f32>> = input
+            .iter()
+            .zip(&tail)
+            .map(|(input, tail)| [input.as_slice(), tail].concat())
+            .collect();
+        let mut file = Vec::new();
+        write_wav(&mut file, SAMPLE_RATE as u32, &rendered).unwrap();
+
+        // A 44-byte header, then 8 bytes a stereo frame.
+        let frames = (file.len() - 44) / 8;
+        assert!(
+            frames.abs_diff(2 * BLOCK + reported) <= BLOCK,
+            "{frames} frames for a {reported}-sample tail"
+        );
+        let first_echo = IMPULSE_AT + 4800;
+        let peak = rendered[0][first_echo..first_echo + BLOCK]
+            .iter()
+            .fold(0.0_f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.5, "no first echo: {peak}");
+    }
+
+    /// Once the tail is measured to be over, the ring-out stops there,
+    /// short of the estimate it started with. Here the input runs on
+    /// until the last few echoes, so there's little left to measure.
+    #[test]
+    fn test_stops_when_measured_over() {
+        let params = DelayParams {
+            delay_ms: 100.0,
+            feedback: 0.5,
+            ..DelayParams::default()
+        };
+        let (mut engine, _) = after_impulse(&params, (1.2 * SAMPLE_RATE) as usize);
+        let reported = engine.tail_samples().expect("no tail reported") as usize;
+
+        let tail = ring_out(&mut engine, BLOCK);
+        assert_eq!(engine.tail_samples(), None);
+        assert!(tail[0].len() < reported, "{} of {reported}", tail[0].len());
+    }
+
+    /// `KeepAlive` runs to the limit, a shorter `Tail` pulls the end in
+    /// and a longer one doesn't push it out, and `Normal` or an error
+    /// stops it at once.
+    #[test]
+    fn test_counts_down_by_status() {
+        let max = (MAX_RING_OUT_SECONDS * SAMPLE_RATE) as usize;
+        let mut kept_alive = RingOut::new(ProcessStatus::KeepAlive, max);
+        while let Some(len) = kept_alive.next_block(BLOCK) {
+            kept_alive.advance(len, ProcessStatus::KeepAlive);
+        }
+        assert_eq!(kept_alive.rendered(), max);
+
+        let mut tail = RingOut::new(ProcessStatus::Tail(10 * BLOCK as u32), max);
+        tail.advance(BLOCK, ProcessStatus::Tail(2 * BLOCK as u32));
+        tail.advance(BLOCK, ProcessStatus::Tail(100 * BLOCK as u32));
+        assert_eq!(tail.next_block(BLOCK), Some(BLOCK));
+        tail.advance(BLOCK, ProcessStatus::KeepAlive);
+        assert_eq!(tail.next_block(BLOCK), None);
+        assert_eq!(tail.rendered(), 3 * BLOCK);
+
+        for status in [ProcessStatus::Normal, ProcessStatus::Error("broken")] {
+            let mut stopped = RingOut::new(ProcessStatus::Tail(u32::MAX), max);
+            stopped.advance(BLOCK, status);
+            assert_eq!(stopped.next_block(BLOCK), None);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! # Handing Over Between Free and Synced Time
+//!
+//! Sync swaps the source of the delay time — the Delay Time knob, or a
+//! note division at the song's tempo — and the two rarely agree. The
+//! engine crossfades between them, so the switch never bends the
+//! repeats (see `set_delay_target()` in the engine), but the echoes
+//! still jump to a new spacing. Handing over *seamlessly* means making
+//! the two agree before the switch:
+//!
+//! ```text
+//! Sync on:   Division   ← the division nearest the knob's time
+//!            Sync       ← on      (the time moves to the nearest note)
+//!
+//! Sync off:  Delay Time ← the division's length at the current tempo
+//!            Sync       ← off     (the time stays exactly where it was)
+//! ```
+//!
+//! Switching on, the echoes move only as far as the nearest division
+//! (see [`NoteDivision::nearest()`]) and crossfade there. Switching off,
+//! nothing moves at all: the knob already holds the time being heard.
+//! Either way the other parameter is written *first*, so by the time
+//! the engine sees the switch, its two sources already agree.
+//!
+//! ## Through the Host
+//!
+//! The writes are parameter changes like any other — a gesture begun,
+//! a value set, the gesture ended — so the host records them in its
+//! automation and its undo history, and the session reopens the way it
+//! sounded. That's [`ParamSetter`]'s job, which is why this lives here
+//! and not in the engine: nih-plug lets only an editor set parameters,
+//! never the audio thread. Automating Sync directly skips the handover
+//! and gets the engine's crossfade alone.
+//!
+//! The editor needs the tempo, which only the audio thread hears from
+//! the host, so the plugin publishes it here every block: the tempo sync
+//! follows, the host's or Manual BPM standing in for it, and which of
+//! the two it is, for the editor to show beside the Sync button.
+//!
+//! ## Not Wired Up Yet
+//!
+//! There's no editor yet to put a Sync button on. When there is, it
+//! calls [`SyncHandover::set_sync()`] instead of setting the Sync
+//! parameter itself.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use nih_plug::prelude::{Param, ParamSetter};
+
+use crate::dsp::tempo::NoteDivision;
+use crate::engine::{TempoSource, MAX_DELAY_MS, MIN_DELAY_MS};
+use crate::params::PluginParams;
+
+/// The Delay Time that keeps `division` at `tempo_bpm` where it is,
+/// within the knob's range.
+pub fn handover_delay_ms(division: NoteDivision, tempo_bpm: f64) -> f32 {
+    (division.to_ms(tempo_bpm) as f32).clamp(MIN_DELAY_MS, MAX_DELAY_MS)
+}
+
+/// The tempo sync follows and where it comes from, shared from the
+/// audio thread with the editor, and the handover that needs it.
+#[derive(Debug, Default)]
+pub struct SyncHandover {
+    /// The tempo's `f64` bits, with 0 (0.0 BPM) standing for "not
+    /// published yet".
+    tempo: AtomicU64,
+
+    /// Whether that tempo is Manual BPM's, the host reporting none.
+    manual: AtomicBool,
+}
+
+impl SyncHandover {
+    /// Store the tempo the engine settled on for this block (see
+    /// [`LovelessDelayEngine::sync_tempo()`](crate::LovelessDelayEngine::sync_tempo)).
+    /// Safe on the audio thread: two atomic stores.
+    pub fn publish_tempo(&self, tempo_bpm: f64, source: TempoSource) {
+        let tempo = Some(tempo_bpm).filter(|bpm| *bpm > 0.0).unwrap_or(0.0);
+        self.tempo.store(tempo.to_bits(), Ordering::Relaxed);
+        self.manual
+            .store(source == TempoSource::Manual, Ordering::Relaxed);
+    }
+
+    /// The last tempo published, if there's been one.
+    pub fn tempo(&self) -> Option<f64> {
+        Some(f64::from_bits(self.tempo.load(Ordering::Relaxed))).filter(|bpm| *bpm > 0.0)
+    }
+
+    /// Whether the last tempo published was the host's or Manual BPM's.
+    pub fn tempo_source(&self) -> TempoSource {
+        if self.manual.load(Ordering::Relaxed) {
+            TempoSource::Manual
+        } else {
+            TempoSource::Host
+        }
+    }
+
+    /// Switch Sync on or off from the editor, first setting Division or
+    /// Delay Time so the delay time doesn't jump (see
+    /// [the module docs](self)). Without a tempo there's nothing to
+    /// match, and only Sync is set.
+    pub fn set_sync(&self, setter: &ParamSetter, params: &PluginParams, sync: bool) {
+        if params.sync.value() == sync {
+            return;
+        }
+        if let Some(bpm) = self.tempo() {
+            if sync {
+                let division = NoteDivision::nearest(params.delay_time.value(), bpm);
+                set(setter, &params.division, division.into());
+            } else {
+                let division = NoteDivision::from(params.division.value());
+                set(setter, &params.delay_time, handover_delay_ms(division, bpm));
+            }
+        }
+        set(setter, &params.sync, sync);
+    }
+}
+
+/// One whole parameter gesture, as a host expects to see it.
+fn set<P: Param>(setter: &ParamSetter, param: &P, value: P::Plain) {
+    setter.begin_set_parameter(param);
+    setter.set_parameter(param, value);
+    setter.end_set_parameter(param);
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tempo and its source come back as published, and a tempo
+    /// before the first block, or a nonsense one, as none.
+    #[test]
+    fn test_tempo_round_trip() {
+        let handover = SyncHandover::default();
+        assert_eq!(handover.tempo(), None);
+        handover.publish_tempo(132.5, TempoSource::Host);
+        assert_eq!(handover.tempo(), Some(132.5));
+        assert_eq!(handover.tempo_source(), TempoSource::Host);
+        handover.publish_tempo(90.0, TempoSource::Manual);
+        assert_eq!(handover.tempo(), Some(90.0));
+        assert_eq!(handover.tempo_source(), TempoSource::Manual);
+        handover.publish_tempo(0.0, TempoSource::Host);
+        assert_eq!(handover.tempo(), None);
+    }
+
+    /// Switching off writes back the synced time, held to the knob's
+    /// range at extreme tempos.
+    #[test]
+    fn test_handover_delay_ms() {
+        assert_eq!(handover_delay_ms(NoteDivision::DottedEighth, 120.0), 375.0);
+        assert_eq!(handover_delay_ms(NoteDivision::Whole, 60.0), MAX_DELAY_MS);
+        assert_eq!(
+            handover_delay_ms(NoteDivision::Sixteenth, 200.0),
+            MIN_DELAY_MS
+        );
+    }
+}
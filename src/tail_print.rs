@@ -0,0 +1,449 @@
+//! # Printing the Tail
+//!
+//! Bouncing a region stops at the region's end, and unless the bounce
+//! is stretched by hand, the echoes still ringing there are cut off. The
+//! Print Tail button is the way round it: it renders whatever is ringing
+//! at the moment it's pressed, to the end, and writes it to a WAV file
+//! to drag in after the bounce.
+//!
+//! ```text
+//! editor              request()          makes the snapshot, the first
+//!                                        time; sets a flag
+//! audio thread        capture()          copies the repeats in flight into
+//!                                        a snapshot, then hands off
+//! background thread   print()            feeds a copy of the engine silence
+//!                                        until the tail is over; writes it
+//! ```
+//!
+//! ## Real-Time Safety
+//!
+//! Only the capture happens on the audio thread, and it copies in place
+//! into a [`TailSnapshot`] made beforehand (see
+//! [`LovelessDelayEngine::capture_tail()`]). The snapshot is behind a
+//! lock the audio thread only ever *tries*: if the background thread
+//! is still seeding an engine from the last capture, the request waits
+//! for the next block. Everything that allocates — the rendering engine,
+//! the rendered audio, the file — happens on the background thread.
+//!
+//! Nor does it copy whole lines: only each line's live span, the
+//! newest samples back as far as the delay, the pre-delay and any
+//! crossfade still read, so a 500 ms delay copies about a quarter of
+//! what a 2 s one does. A frozen loop is the exception: it can reach
+//! back the whole line, and all of it is copied.
+//!
+//! ## Memory
+//!
+//! The snapshot is an engine's worth of buffers, and the render needs a
+//! third set for as long as it runs. Neither is made until a print is
+//! asked for, so an instance that never prints holds only its engine.
+//! Both claim their bytes from the budget every instance shares (see
+//! [`memory_budget`](crate::memory_budget)), and the snapshot's are
+//! published with the engine's. The snapshot is made by
+//! [`request()`](TailPrint::request), on the editor's thread, then kept
+//! for the next print until the engine is rebuilt.
+//!
+//! ## Where It Goes
+//!
+//! Into the folder chosen in the editor (`PluginParams::tail_print_dir`,
+//! saved with the plugin's state), or, before one is chosen, the host's
+//! working directory, which many hosts set to the project's folder.
+//! nih-plug doesn't pass on where the project is. Each print is named
+//! for the moment it was made, so none overwrites another.
+//!
+//! ## Record Arm
+//!
+//! Printing automatically whenever the track is armed to record would
+//! save pressing the button, but hosts don't tell a plugin its track's
+//! record-arm state through nih-plug (or, in most formats, at all). So
+//! printing is on request only.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::engine::{DelayParams, LovelessDelayEngine, TailSnapshot};
+use crate::memory_budget::{self, MemoryClaim, MemoryUsage};
+
+/// The longest tail a print renders, in seconds. A held or frozen loop
+/// never dies away, so it gets this much and no more.
+pub const MAX_TAIL_SECONDS: f32 = 60.0;
+
+/// How many samples the render hands the engine at once.
+const RENDER_BLOCK: usize = 1024;
+
+/// What a snapshot has to be built to match: the engine prints are
+/// captured from.
+#[derive(Debug, Clone, Copy)]
+struct EngineShape {
+    sample_rate: f32,
+    channels: usize,
+    max_delay_ms: f32,
+}
+
+/// A print asked for, the snapshot it's captured into, and whether one
+/// is being rendered, shared between the editor, the audio thread and
+/// the background thread.
+pub struct TailPrint {
+    requested: AtomicBool,
+    printing: AtomicBool,
+
+    /// The engine to make a snapshot for, or `None` while it's being
+    /// rebuilt.
+    shape: Mutex<Option<EngineShape>>,
+
+    /// `None` until a print is asked for, and again once the engine is
+    /// rebuilt; held with its share of the memory budget.
+    snapshot: Mutex<Option<(TailSnapshot, MemoryClaim<'static>)>>,
+
+    /// Where the snapshot's memory is published, with the engine's.
+    memory_usage: Arc<MemoryUsage>,
+
+    /// Where the last print went, for the editor to show.
+    last_path: Mutex<Option<PathBuf>>,
+}
+
+impl TailPrint {
+    /// Nothing to print yet, publishing the snapshot's memory, once
+    /// there is one, to `memory_usage`.
+    pub fn new(memory_usage: Arc<MemoryUsage>) -> Self {
+        Self {
+            requested: AtomicBool::new(false),
+            printing: AtomicBool::new(false),
+            shape: Mutex::new(None),
+            snapshot: Mutex::new(None),
+            memory_usage,
+            last_path: Mutex::new(None),
+        }
+    }
+
+    /// Ask for the tail ringing on the next block to be printed: the
+    /// Print Tail button.
+    ///
+    /// The first request after the engine is built makes the snapshot
+    /// to capture into, so it allocates: call it from the editor, never
+    /// the audio thread.
+    pub fn request(&self) {
+        let Ok(shape) = self.shape.lock() else {
+            return;
+        };
+        self.requested.store(true, Ordering::Relaxed);
+        if let Some(shape) = *shape {
+            self.make_snapshot(shape);
+        }
+    }
+
+    /// Whether a print has been asked for and not yet written.
+    pub fn is_printing(&self) -> bool {
+        self.requested.load(Ordering::Relaxed) || self.printing.load(Ordering::Relaxed)
+    }
+
+    /// Where the last print was written, if there's been one.
+    pub fn last_path(&self) -> Option<PathBuf> {
+        self.last_path.lock().ok()?.clone()
+    }
+
+    /// Drop the snapshot and give its memory back, before the engine
+    /// it was made for is rebuilt. Call [`prepare()`](Self::prepare)
+    /// with the new one.
+    pub fn release(&self) {
+        if let Ok(mut shape) = self.shape.lock() {
+            *shape = None;
+        }
+        if let Ok(mut snapshot) = self.snapshot.lock() {
+            *snapshot = None;
+        }
+        self.memory_usage.publish_tail_snapshot(0);
+    }
+
+    /// Capture from `engine` from now on. Makes its snapshot only if a
+    /// print is already waiting; otherwise the next
+    /// [`request()`](Self::request) does. Call it from `initialize()`.
+    pub fn prepare(&self, engine: &LovelessDelayEngine) {
+        let Ok(mut shape) = self.shape.lock() else {
+            return;
+        };
+        let new_shape = EngineShape {
+            sample_rate: engine.sample_rate(),
+            channels: engine.channels(),
+            max_delay_ms: engine.max_delay_ms(),
+        };
+        *shape = Some(new_shape);
+        if self.requested.load(Ordering::Relaxed) {
+            self.make_snapshot(new_shape);
+        }
+    }
+
+    /// Make a snapshot for `shape` and claim its memory, unless there's
+    /// one already. Called with `shape`'s lock held, so the engine
+    /// can't be rebuilt meanwhile.
+    fn make_snapshot(&self, shape: EngineShape) {
+        let Ok(mut snapshot) = self.snapshot.lock() else {
+            return;
+        };
+        if snapshot.is_none() {
+            let made = TailSnapshot::new(shape.sample_rate, shape.channels, shape.max_delay_ms);
+            let claim = memory_budget::SHARED.claim(made.memory_bytes());
+            self.memory_usage.publish_tail_snapshot(claim.bytes());
+            *snapshot = Some((made, claim));
+        }
+    }
+
+    /// If a print has been asked for, capture `engine`'s repeats as they
+    /// stand, under `params`, and return `true`: the caller hands the
+    /// print to the background thread. Safe on the audio thread (see
+    /// [Real-Time Safety](self#real-time-safety)).
+    pub fn capture(&self, engine: &LovelessDelayEngine, params: &DelayParams) -> bool {
+        if !self.requested.load(Ordering::Relaxed) {
+            return false;
+        }
+        let Ok(mut snapshot) = self.snapshot.try_lock() else {
+            return false;
+        };
+        let Some((snapshot, _)) = snapshot.as_mut() else {
+            return false;
+        };
+        engine.capture_tail(snapshot, params);
+        self.printing.store(true, Ordering::Relaxed);
+        self.requested.store(false, Ordering::Relaxed);
+        true
+    }
+
+    /// Render the captured tail and write it into `dir` (or the working
+    /// directory), returning the file's path. For the background thread.
+    pub fn print(&self, dir: Option<&Path>) -> io::Result<PathBuf> {
+        let result = self.render_and_write(dir);
+        if let (Ok(path), Ok(mut last_path)) = (&result, self.last_path.lock()) {
+            *last_path = Some(path.clone());
+        }
+        self.printing.store(false, Ordering::Relaxed);
+        result
+    }
+
+    fn render_and_write(&self, dir: Option<&Path>) -> io::Result<PathBuf> {
+        // Hold the lock only while seeding: the render is long, and the
+        // audio thread may want the snapshot for the next print.
+        let engine = self
+            .snapshot
+            .lock()
+            .map_err(|_| io::Error::other("the tail snapshot was poisoned"))?
+            .as_ref()
+            .map(|(snapshot, _)| snapshot.seeded_engine())
+            .ok_or_else(|| io::Error::other("nothing captured yet"))?;
+        // The rendering engine is a third set of buffers; count it for
+        // as long as it's alive.
+        let _rendering = memory_budget::SHARED.claim(engine.memory_bytes());
+        let sample_rate = engine.sample_rate();
+        let tail = render_tail(engine, (MAX_TAIL_SECONDS * sample_rate) as usize);
+
+        let dir = match dir {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir()?,
+        };
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        let path = dir.join(format!("Loveless Delay tail {stamp}.wav"));
+        let mut file = BufWriter::new(File::create(&path)?);
+        write_wav(&mut file, sample_rate as u32, &tail)?;
+        file.flush()?;
+        Ok(path)
+    }
+}
+
+/// Feed `engine` silence until its tail is measured to be over (see
+/// [`LovelessDelayEngine::tail_samples()`]), or for `max_samples`,
+/// whichever comes first. Returns what it played, one `Vec` per channel.
+pub fn render_tail(mut engine: LovelessDelayEngine, max_samples: usize) -> Vec<Vec<f32>> {
+    let channels = engine.channels();
+    let mut tail = vec![Vec::new(); channels];
+    let mut block = vec![vec![0.0; RENDER_BLOCK]; channels];
+    let mut rendered = 0;
+    while rendered < max_samples {
+        let len = RENDER_BLOCK.min(max_samples - rendered);
+        let mut slices: Vec<&mut [f32]> = block
+            .iter_mut()
+            .map(|samples| {
+                samples.fill(0.0);
+                &mut samples[..len]
+            })
+            .collect();
+        engine.process(&mut slices);
+        for (samples, played) in tail.iter_mut().zip(&block) {
+            samples.extend_from_slice(&played[..len]);
+        }
+        rendered += len;
+        if engine.tail_samples().is_none() {
+            break;
+        }
+    }
+    tail
+}
+
+/// Write `channels` (all the same length) as a 32-bit float WAV file.
+pub fn write_wav(out: &mut impl Write, sample_rate: u32, channels: &[Vec<f32>]) -> io::Result<()> {
+    const FORMAT_FLOAT: u16 = 3;
+    let num_channels = channels.len() as u16;
+    let frames = channels.first().map_or(0, Vec::len);
+    let block_align = u32::from(num_channels) * 4;
+    let data_len = frames as u32 * block_align;
+
+    out.write_all(b"RIFF")?;
+    out.write_all(&(4 + 8 + 16 + 8 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVE")?;
+    out.write_all(b"fmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&FORMAT_FLOAT.to_le_bytes())?;
+    out.write_all(&num_channels.to_le_bytes())?;
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * block_align).to_le_bytes())?;
+    out.write_all(&(block_align as u16).to_le_bytes())?;
+    out.write_all(&32u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for frame in 0..frames {
+        for samples in channels {
+            out.write_all(&samples[frame].to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    const BLOCK: usize = 512;
+
+    /// A print captured mid-tail renders what the live engine goes on to
+    /// play, sample for sample within a hair, and stops once the tail is
+    /// over.
+    #[test]
+    fn test_printed_tail_matches_live_engine() {
+        let params = DelayParams {
+            delay_ms: 180.0,
+            feedback: 0.6,
+            bloom_db: 6.0,
+            ..DelayParams::default()
+        };
+        let mut live = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        live.set_params(&params);
+        let process = |engine: &mut LovelessDelayEngine, block_idx: usize, loud: bool| {
+            let mut channels: Vec<Vec<f32>> = (0..2)
+                .map(|channel| {
+                    (0..BLOCK)
+                        .map(|i| {
+                            let n = (block_idx * BLOCK + i) as f32;
+                            if loud {
+                                (n * 0.031 + channel as f32).sin() * 0.5
+                            } else {
+                                0.0
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let mut slices: Vec<&mut [f32]> = channels.iter_mut().map(|c| &mut c[..]).collect();
+            engine.set_params(&params);
+            engine.process(&mut slices);
+            channels
+        };
+        for block_idx in 0..40 {
+            process(&mut live, block_idx, block_idx < 20);
+        }
+
+        let tail_print = TailPrint::new(Arc::default());
+        tail_print.prepare(&live);
+        assert!(!tail_print.capture(&live, &params), "captured unasked");
+        tail_print.request();
+        assert!(tail_print.capture(&live, &params));
+        assert!(tail_print.is_printing());
+        let seeded = tail_print
+            .snapshot
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .0
+            .seeded_engine();
+        let printed = render_tail(seeded, (MAX_TAIL_SECONDS * SAMPLE_RATE) as usize);
+        assert!(
+            printed[0].len() < (10.0 * SAMPLE_RATE) as usize,
+            "never ended"
+        );
+
+        let mut peak = 0.0_f32;
+        for block_idx in 40.. {
+            let start = (block_idx - 40) * BLOCK;
+            if start >= printed[0].len() {
+                break;
+            }
+            let played = process(&mut live, block_idx, false);
+            for (channel, samples) in played.iter().enumerate() {
+                for (i, sample) in samples.iter().enumerate() {
+                    let Some(printed) = printed[channel].get(start + i) else {
+                        break;
+                    };
+                    peak = peak.max(sample.abs());
+                    assert!(
+                        (sample - printed).abs() < 1e-3,
+                        "channel {channel}, sample {}: live {sample}, printed {printed}",
+                        start + i
+                    );
+                }
+            }
+        }
+        assert!(peak > 0.05, "nothing was ringing: {peak}");
+    }
+
+    /// No snapshot is made until a print is asked for; once it is, its
+    /// memory is published with the engine's, and it's given back when
+    /// the engine is rebuilt.
+    #[test]
+    fn test_snapshot_waits_for_a_request() {
+        let engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let usage = Arc::new(MemoryUsage::default());
+        let tail_print = TailPrint::new(usage.clone());
+        tail_print.prepare(&engine);
+        assert!(tail_print.snapshot.lock().unwrap().is_none());
+        assert_eq!(usage.bytes(), 0);
+
+        tail_print.request();
+        assert_eq!(usage.bytes(), engine.memory_bytes());
+        assert!(tail_print.capture(&engine, &DelayParams::default()));
+
+        tail_print.release();
+        assert!(tail_print.snapshot.lock().unwrap().is_none());
+        assert_eq!(usage.bytes(), 0);
+    }
+
+    /// The WAV header describes the data that follows: 32-bit float,
+    /// interleaved.
+    #[test]
+    fn test_wav_layout() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, 48000, &[vec![0.25, -0.5], vec![1.0, 0.0]]).unwrap();
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let f32_at = |at: usize| f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        assert_eq!(bytes.len(), 44 + 16);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32_at(4), bytes.len() as u32 - 8);
+        assert_eq!(u16_at(20), 3);
+        assert_eq!(u16_at(22), 2);
+        assert_eq!(u32_at(24), 48000);
+        assert_eq!(u32_at(28), 48000 * 8);
+        assert_eq!(u16_at(32), 8);
+        assert_eq!(u16_at(34), 32);
+        assert_eq!(u32_at(40), 16);
+        let samples: Vec<f32> = (0..4).map(|i| f32_at(44 + 4 * i)).collect();
+        assert_eq!(samples, [0.25, 1.0, -0.5, 0.0]);
+    }
+}
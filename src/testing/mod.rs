@@ -0,0 +1,164 @@
+//! # Test Support
+//!
+//! What the tests drive the engine with, built for the unit tests and,
+//! behind the `testing` feature, for the integration tests and the
+//! render harness (never for the plugin itself):
+//!
+//! - [`MockTransport`], a host's transport played block by block (below).
+//! - [`self_test`], runtime checks of the compiled DSP, run by
+//!   `cargo test` and by the render harness's `--self-test`.
+//!
+//! ## A Scriptable Host Transport
+//!
+//! Tempo sync, quantized starts and the stop behavior all read the
+//! host's transport, and a test needs to play the host's part: set a
+//! tempo, press play, move the playhead block by block, loop, stop.
+//!
+//! ### Why Not a Mock `ProcessContext`?
+//!
+//! The obvious seam would be nih-plug's `ProcessContext`, whose
+//! `transport()` hands the plugin a `nih_plug::prelude::Transport`. But
+//! only nih-plug itself can build one of those: its constructor and its
+//! position fields are private to the crate. A mock context would have
+//! nothing to return.
+//!
+//! So the seam sits one step further in. The plugin copies the few
+//! values it needs into the engine's plain [`Transport`] (see
+//! `LovelessDelay::engine_transport()`), and everything that acts on
+//! them lives in [`LovelessDelayEngine::set_transport()`] and
+//! `set_params()`. A [`MockTransport`] produces those plain values the
+//! way a host would, so a test can drive the engine exactly as
+//! `process()` does:
+//!
+//! ```text
+//! let mut host = MockTransport::new(48000.0, 120.0);
+//! host.play();
+//! for block in blocks {
+//!     engine.set_transport(host.next_block(block.len()));
+//!     engine.set_params(&params);
+//!     engine.process(block);
+//! }
+//! ```
+//!
+//! [`LovelessDelayEngine::set_transport()`]: crate::LovelessDelayEngine::set_transport
+
+pub mod self_test;
+
+use crate::Transport;
+
+/// A host's transport, played one block at a time.
+///
+/// It starts stopped at beat 0. While playing, each block moves the
+/// playhead on by the block's length at the current tempo; a stopped
+/// playhead stays put, as it does in a host.
+#[derive(Debug, Clone)]
+pub struct MockTransport {
+    sample_rate: f64,
+    tempo: Option<f64>,
+    playing: bool,
+
+    /// The playhead, in beats, or `None` for a host that doesn't report
+    /// one.
+    pos_beats: Option<f64>,
+
+    /// The loop's start and end, in beats, while looping.
+    loop_beats: Option<(f64, f64)>,
+}
+
+impl MockTransport {
+    /// A stopped transport at beat 0, running at `tempo` BPM.
+    pub fn new(sample_rate: f32, tempo: f64) -> Self {
+        Self {
+            sample_rate: f64::from(sample_rate),
+            tempo: Some(tempo),
+            playing: false,
+            pos_beats: Some(0.0),
+            loop_beats: None,
+        }
+    }
+
+    /// Change the tempo, or stop reporting one with `None`.
+    pub fn set_tempo(&mut self, tempo: Option<f64>) {
+        self.tempo = tempo;
+    }
+
+    /// Press play: the playhead moves from the next block on.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Press stop: the playhead stays where it is.
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    /// Move the playhead to `beats`, or stop reporting it with `None`.
+    pub fn locate(&mut self, beats: Option<f64>) {
+        self.pos_beats = beats;
+    }
+
+    /// Loop between `start` and `end` beats, or stop looping with
+    /// `None`. A playhead that reaches `end` jumps back to `start`,
+    /// keeping whatever it overshot by.
+    pub fn set_loop(&mut self, range: Option<(f64, f64)>) {
+        self.loop_beats = range;
+    }
+
+    /// The transport for a block of `len` samples starting now, as
+    /// `process()` would pass it to the engine. Then, if playing, move
+    /// the playhead past the block.
+    pub fn next_block(&mut self, len: usize) -> Transport {
+        let transport = Transport {
+            tempo: self.tempo,
+            pos_beats: self.pos_beats,
+            playing: self.playing,
+        };
+
+        if let (true, Some(tempo), Some(pos)) = (self.playing, self.tempo, self.pos_beats) {
+            let mut next = pos + len as f64 / self.sample_rate * tempo / 60.0;
+            if let Some((start, end)) = self.loop_beats {
+                if pos < end && next >= end {
+                    next = start + (next - end);
+                }
+            }
+            self.pos_beats = Some(next);
+        }
+
+        transport
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The playhead moves only while playing, at the current tempo, and
+    /// wraps at the loop's end keeping the overshoot.
+    #[test]
+    fn test_playhead_advances_and_wraps() {
+        let mut host = MockTransport::new(48000.0, 120.0);
+        assert_eq!(host.next_block(24000).pos_beats, Some(0.0));
+        assert_eq!(host.next_block(24000).pos_beats, Some(0.0));
+
+        host.play();
+        host.set_loop(Some((4.0, 6.0)));
+        host.locate(Some(5.0));
+        assert_eq!(host.next_block(12000).pos_beats, Some(5.0));
+        assert_eq!(host.next_block(24000).pos_beats, Some(5.5));
+        let wrapped = host.next_block(0);
+        assert_eq!(wrapped.pos_beats, Some(4.5));
+        assert!(wrapped.playing);
+
+        host.set_tempo(Some(60.0));
+        host.next_block(24000);
+        assert_eq!(host.next_block(0).pos_beats, Some(5.0));
+
+        host.stop();
+        assert!(!host.next_block(48000).playing);
+        assert_eq!(host.next_block(0).pos_beats, Some(5.0));
+    }
+}
@@ -0,0 +1,624 @@
+//! # Self-Test
+//!
+//! The tests check the engine as it's written; these checks run it as
+//! it's *built* — in whatever profile, on whatever CPU — and say in
+//! plain words whether it still behaves. They're for anyone changing
+//! the DSP: build, run, and see at once if a change has moved the
+//! echoes, bent the filter or left the tail ringing.
+//!
+//! ```text
+//! cargo run --release --example render_delay -- --self-test
+//!
+//! PASS  impulse timing  first echo on the sample at 44100, 48000 and 96000 Hz
+//! PASS  filter corner   -3.01 dB at 1000 Hz, -0.04 dB at 100 Hz
+//! PASS  tail decay      -6.0 dB a repeat at 50% feedback (-6.0 expected, worst -6.1)
+//! PASS  denormals       no subnormal samples; silent and asleep after 4.4 s
+//! PASS  allocations     none in 400 blocks of automation
+//! 5 passed, 0 failed, 0 skipped
+//! ```
+//!
+//! The harness exits non-zero if any check fails. `cargo test` runs the
+//! same [`CHECKS`] (`tests/self_test.rs`), so a check is never out of
+//! step with the tests.
+//!
+//! ## Denormals
+//!
+//! A decaying filter heads for zero through the *subnormal* floats
+//! (below about 1e-38), which many CPUs work on dozens of times slower.
+//! The engine doesn't guard against them itself: nih-plug's wrappers
+//! switch the CPU to flush-to-zero around every `process()`. So the
+//! check does the same (`FlushToZero`), makes sure it took, and plays
+//! an echo tail into silence with it, requiring no subnormal sample on
+//! the way and exact silence (the engine asleep) at the end.
+//!
+//! ## Allocations
+//!
+//! `process()` must never allocate (see `assert_process_allocs`). The
+//! check counts the allocations made while the engine plays through
+//! automation, which needs [`CountingAllocator`] as the program's global
+//! allocator. A debug build already has one — nih-plug's, which aborts
+//! on an allocation inside the plugin's `process()` — so there the check
+//! is skipped; run it with `--release`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt::Write;
+use std::hint::black_box;
+
+use crate::dsp::filter::OnePoleFilter;
+use crate::{DelayParams, LovelessDelayEngine};
+
+/// The sample rate the checks run at, unless they say otherwise.
+const SAMPLE_RATE: f32 = 48000.0;
+
+/// The block size the checks play in, as a host would.
+const BLOCK_SIZE: usize = 512;
+
+/// How a check came out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail,
+
+    /// It couldn't run here (a build or a CPU without what it needs).
+    /// Doesn't count as a failure.
+    Skip,
+}
+
+/// One runtime check.
+#[derive(Debug, Clone, Copy)]
+pub struct Check {
+    /// What's checked, for the report.
+    pub name: &'static str,
+
+    /// Run the check: its verdict, and what it measured.
+    pub run: fn() -> (Verdict, String),
+}
+
+/// A check that has run.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub verdict: Verdict,
+
+    /// What was measured, in a line.
+    pub detail: String,
+}
+
+/// Every check, in the order they run and are reported.
+pub const CHECKS: [Check; 5] = [
+    Check {
+        name: "impulse timing",
+        run: impulse_timing,
+    },
+    Check {
+        name: "filter corner",
+        run: filter_corner,
+    },
+    Check {
+        name: "tail decay",
+        run: tail_decay,
+    },
+    Check {
+        name: "denormals",
+        run: denormals,
+    },
+    Check {
+        name: "allocations",
+        run: allocations,
+    },
+];
+
+/// Run every check.
+pub fn run() -> Vec<CheckResult> {
+    CHECKS
+        .iter()
+        .map(|check| {
+            let (verdict, detail) = (check.run)();
+            CheckResult {
+                name: check.name,
+                verdict,
+                detail,
+            }
+        })
+        .collect()
+}
+
+/// Whether none of `results` failed.
+pub fn passed(results: &[CheckResult]) -> bool {
+    results.iter().all(|result| result.verdict != Verdict::Fail)
+}
+
+/// `results` as the harness prints them: a line a check, then the
+/// totals.
+pub fn report(results: &[CheckResult]) -> String {
+    let mut report = String::new();
+    let width = results
+        .iter()
+        .map(|result| result.name.len())
+        .max()
+        .unwrap_or(0);
+    for result in results {
+        let verdict = match result.verdict {
+            Verdict::Pass => "PASS",
+            Verdict::Fail => "FAIL",
+            Verdict::Skip => "SKIP",
+        };
+        let _ = writeln!(
+            report,
+            "{verdict}  {:<width$}  {}",
+            result.name, result.detail
+        );
+    }
+    let count = |verdict| results.iter().filter(|r| r.verdict == verdict).count();
+    let _ = writeln!(
+        report,
+        "{} passed, {} failed, {} skipped",
+        count(Verdict::Pass),
+        count(Verdict::Fail),
+        count(Verdict::Skip)
+    );
+    report
+}
+
+/// The gain of a [`OnePoleFilter`] set to `cutoff` for a sine at
+/// `freq`, in dB, measured the way you'd measure hardware: play the
+/// sine, let the filter settle, and compare the output's peak to the
+/// input's.
+pub fn filter_gain_db(cutoff: f32, freq: f32, sample_rate: f32) -> f32 {
+    let mut filter: OnePoleFilter = OnePoleFilter::new();
+    filter.set_cutoff(cutoff, sample_rate);
+
+    // A tenth of a second to settle, then measure over the next tenth.
+    let settle = sample_rate as usize / 10;
+    let phase_step = std::f32::consts::TAU * freq / sample_rate;
+
+    let mut peak = 0.0_f32;
+    for n in 0..settle * 2 {
+        let output = filter.process((n as f32 * phase_step).sin());
+        if n >= settle {
+            peak = peak.max(output.abs());
+        }
+    }
+
+    20.0 * peak.log10()
+}
+
+/// A pass if `passed`, a failure if not.
+fn verdict(passed: bool, detail: String) -> (Verdict, String) {
+    let verdict = if passed { Verdict::Pass } else { Verdict::Fail };
+    (verdict, detail)
+}
+
+/// Fully wet, one echo at `delay_ms` and then as many as `feedback`
+/// allows, through an open filter.
+fn echo_params(delay_ms: f32, feedback: f32) -> DelayParams {
+    DelayParams {
+        delay_ms,
+        feedback,
+        mix: 1.0,
+        filter_cutoff: 20000.0,
+        filter_cutoff_right: 20000.0,
+        ..DelayParams::default()
+    }
+}
+
+/// Play `input` into both sides of `engine` in host-sized blocks, and
+/// return the left output.
+fn render(engine: &mut LovelessDelayEngine, input: &[f32]) -> Vec<f32> {
+    let mut left = input.to_vec();
+    let mut right = input.to_vec();
+    for (left, right) in left
+        .chunks_mut(BLOCK_SIZE)
+        .zip(right.chunks_mut(BLOCK_SIZE))
+    {
+        engine.process(&mut [left, right]);
+    }
+    left
+}
+
+/// The index of the loudest sample in `samples`.
+fn loudest(samples: &[f32]) -> usize {
+    (0..samples.len())
+        .max_by(|a, b| samples[*a].abs().total_cmp(&samples[*b].abs()))
+        .unwrap_or(0)
+}
+
+/// The first echo of an impulse lands on the sample the delay time
+/// puts it, at the common sample rates.
+fn impulse_timing() -> (Verdict, String) {
+    const DELAY_MS: f32 = 250.0;
+    const RATES: [f32; 3] = [44100.0, 48000.0, 96000.0];
+
+    let mut misses = Vec::new();
+    for sample_rate in RATES {
+        // A tenth of a second in, well after the engage fade.
+        let hit = sample_rate as usize / 10;
+        let period = (DELAY_MS * sample_rate / 1000.0).round() as usize;
+        let mut input = vec![0.0; hit + period * 3 / 2];
+        input[hit] = 1.0;
+
+        let mut engine = LovelessDelayEngine::new(sample_rate, 2);
+        engine.set_params(&echo_params(DELAY_MS, 0.0));
+        let output = render(&mut engine, &input);
+        let from = hit + period / 2;
+        let echo = from + loudest(&output[from..]);
+        let off = echo as i64 - (hit + period) as i64;
+        if off.abs() > 1 {
+            misses.push(format!("{off:+} samples at {sample_rate} Hz"));
+        }
+    }
+
+    let rates = RATES.map(|rate| rate.to_string());
+    if misses.is_empty() {
+        verdict(
+            true,
+            format!(
+                "first echo on the sample at {} and {} Hz",
+                rates[..rates.len() - 1].join(", "),
+                rates[rates.len() - 1]
+            ),
+        )
+    } else {
+        verdict(false, format!("first echo off by {}", misses.join(", ")))
+    }
+}
+
+/// The feedback filter is 3 dB down at its cutoff and flat a decade
+/// below it.
+fn filter_corner() -> (Verdict, String) {
+    const CUTOFF: f32 = 1000.0;
+    let at_corner = filter_gain_db(CUTOFF, CUTOFF, SAMPLE_RATE);
+    let passband = filter_gain_db(CUTOFF, CUTOFF / 10.0, SAMPLE_RATE);
+    verdict(
+        (at_corner + 3.01).abs() <= 0.5 && passband.abs() <= 0.1,
+        format!(
+            "{at_corner:.2} dB at {CUTOFF} Hz, {passband:.2} dB at {} Hz",
+            CUTOFF / 10.0
+        ),
+    )
+}
+
+/// Each repeat is the feedback's share of the one before: at 50%,
+/// 6 dB down, repeat after repeat.
+fn tail_decay() -> (Verdict, String) {
+    const DELAY_MS: f32 = 100.0;
+    const FEEDBACK: f32 = 0.5;
+    const REPEATS: usize = 6;
+
+    let period = (DELAY_MS * SAMPLE_RATE / 1000.0) as usize;
+    let hit = period;
+    // A 20 ms, 200 Hz burst: low enough that the open filter leaves
+    // each repeat's level alone, which a single-sample click's isn't.
+    let burst = SAMPLE_RATE as usize / 50;
+    let mut input = vec![0.0; hit + period * (REPEATS + 1)];
+    for (n, sample) in input[hit..hit + burst].iter_mut().enumerate() {
+        let window = (std::f32::consts::PI * n as f32 / burst as f32)
+            .sin()
+            .powi(2);
+        *sample = 0.5 * window * (std::f32::consts::TAU * 200.0 * n as f32 / SAMPLE_RATE).sin();
+    }
+
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    engine.set_params(&echo_params(DELAY_MS, FEEDBACK));
+    let output = render(&mut engine, &input);
+    let echo_db = |repeat: usize| {
+        let start = hit + repeat * period - period / 4;
+        let window = &output[start..start + burst + period / 2];
+        20.0 * window[loudest(window)].abs().max(1e-9).log10()
+    };
+    let steps: Vec<f32> = (1..REPEATS)
+        .map(|repeat| echo_db(repeat + 1) - echo_db(repeat))
+        .collect();
+
+    let expected = 20.0 * FEEDBACK.log10();
+    let worst = steps
+        .iter()
+        .copied()
+        .max_by(|a, b| (a - expected).abs().total_cmp(&(b - expected).abs()))
+        .unwrap_or(0.0);
+    let average = steps.iter().sum::<f32>() / steps.len() as f32;
+    verdict(
+        (worst - expected).abs() <= 1.0,
+        format!(
+            "{average:.1} dB a repeat at {:.0}% feedback ({expected:.1} expected, worst {worst:.1})",
+            FEEDBACK * 100.0
+        ),
+    )
+}
+
+/// Under flush-to-zero, as the plugin runs, an echo tail dies away
+/// without a subnormal sample and ends in exact silence.
+fn denormals() -> (Verdict, String) {
+    const DELAY_MS: f32 = 100.0;
+    const MAX_SECONDS: usize = 20;
+
+    let _ftz = FlushToZero::enable();
+    if black_box(f32::MIN_POSITIVE) * black_box(0.5) != 0.0 {
+        return (
+            Verdict::Skip,
+            "flush-to-zero can't be switched on for this CPU here".to_string(),
+        );
+    }
+
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    engine.set_params(&DelayParams {
+        delay_ms: DELAY_MS,
+        feedback: 0.5,
+        mix: 1.0,
+        ..DelayParams::default()
+    });
+    let mut left = vec![0.0; BLOCK_SIZE];
+    let mut right = vec![0.0; BLOCK_SIZE];
+    let mut subnormal = 0;
+    let mut blocks = 0;
+    while !engine.is_asleep() && blocks * BLOCK_SIZE < MAX_SECONDS * SAMPLE_RATE as usize {
+        left.fill(0.0);
+        right.fill(0.0);
+        if blocks == 0 {
+            left[0] = 1.0;
+            right[0] = 1.0;
+        }
+        engine.process(&mut [&mut left, &mut right]);
+        subnormal += left
+            .iter()
+            .chain(&right)
+            .filter(|s| s.is_subnormal())
+            .count();
+        blocks += 1;
+    }
+
+    let seconds = (blocks * BLOCK_SIZE) as f32 / SAMPLE_RATE;
+    let asleep = engine.is_asleep();
+    verdict(
+        subnormal == 0 && asleep,
+        match (subnormal, asleep) {
+            (0, true) => format!("no subnormal samples; silent and asleep after {seconds:.1} s"),
+            (_, true) => format!("{subnormal} subnormal samples before falling silent"),
+            _ => {
+                format!("{subnormal} subnormal samples, and still not silent after {seconds:.1} s")
+            }
+        },
+    )
+}
+
+/// Playing through automation — every block with knobs on the move —
+/// allocates nothing.
+fn allocations() -> (Verdict, String) {
+    const BLOCKS: usize = 400;
+
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    let mut params = DelayParams::default();
+    engine.set_params(&params);
+    let input: Vec<f32> = (0..BLOCK_SIZE)
+        .map(|n| 0.5 * (std::f32::consts::TAU * n as f32 / 64.0).sin())
+        .collect();
+    let mut left = input.clone();
+    let mut right = input.clone();
+
+    let counted = count_allocations(|| {
+        for block in 0..BLOCKS {
+            let sweep = (block as f32 / BLOCKS as f32 * std::f32::consts::TAU).sin();
+            params.delay_ms = 300.0 + 200.0 * sweep;
+            params.feedback = 0.5 + 0.3 * sweep;
+            params.filter_cutoff = 4000.0 + 3000.0 * sweep;
+            params.drive = 0.5 + 0.5 * sweep;
+            params.character = 0.5 - 0.5 * sweep;
+            params.limiter = block % 100 < 50;
+            engine.set_params(&params);
+
+            left.copy_from_slice(&input);
+            right.copy_from_slice(&input);
+            engine.process(&mut [&mut left, &mut right]);
+        }
+    });
+
+    match counted {
+        Some(0) => verdict(true, format!("none in {BLOCKS} blocks of automation")),
+        Some(count) => verdict(
+            false,
+            format!("{count} allocations in {BLOCKS} blocks of automation"),
+        ),
+        None => (
+            Verdict::Skip,
+            "the counting allocator isn't installed (debug builds use nih-plug's); run with --release"
+                .to_string(),
+        ),
+    }
+}
+
+thread_local! {
+    /// This thread's allocations while they're being counted, or
+    /// `None` while they aren't.
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// A global allocator that counts each thread's allocations while
+/// [`count_allocations()`] asks it to, and otherwise passes straight
+/// through to the system's. Install it in the program running the
+/// checks:
+///
+/// ```text
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// ```
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn count() {
+        // `try_with`: a thread being torn down still frees memory
+        // after its counter has gone.
+        let _ = ALLOCATIONS.try_with(|count| {
+            if let Some(n) = count.get() {
+                count.set(Some(n + 1));
+            }
+        });
+    }
+}
+
+// SAFETY: every call is passed on to `System` unchanged; counting only
+// touches a thread-local `Cell`, which never allocates.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+/// How many allocations `f` makes on this thread, or `None` if
+/// [`CountingAllocator`] isn't the global allocator.
+pub fn count_allocations(f: impl FnOnce()) -> Option<usize> {
+    // An allocation that has to be counted, to see if anything counts.
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    drop(black_box(Box::new(0_u8)));
+    let installed = ALLOCATIONS.with(|count| count.get()) != Some(0);
+
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    f();
+    let allocations = ALLOCATIONS.with(|count| count.replace(None));
+    allocations.filter(|_| installed)
+}
+
+/// The CPU's flush-to-zero mode, on for as long as this is held, the
+/// way nih-plug's wrappers run `process()`: any result that would be
+/// subnormal comes out as zero. A no-op on CPUs other than x86-64 and
+/// AArch64.
+struct FlushToZero {
+    /// The control register as it was, to put back.
+    previous: u64,
+}
+
+impl FlushToZero {
+    /// MXCSR's flush-to-zero bit.
+    #[cfg(target_arch = "x86_64")]
+    const FTZ: u64 = 1 << 15;
+
+    /// FPCR's flush-to-zero bit.
+    #[cfg(target_arch = "aarch64")]
+    const FTZ: u64 = 1 << 24;
+
+    fn enable() -> Self {
+        let previous = Self::read();
+        Self::write(previous | Self::ftz());
+        Self { previous }
+    }
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn ftz() -> u64 {
+        Self::FTZ
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn ftz() -> u64 {
+        0
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[allow(deprecated)]
+    fn read() -> u64 {
+        // SAFETY: SSE is part of x86-64; reading MXCSR has no effect.
+        u64::from(unsafe { std::arch::x86_64::_mm_getcsr() })
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[allow(deprecated)]
+    fn write(value: u64) {
+        // SAFETY: only ever a value read from MXCSR, with at most the
+        // flush-to-zero bit added.
+        unsafe { std::arch::x86_64::_mm_setcsr(value as u32) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn read() -> u64 {
+        let value: u64;
+        // SAFETY: reading FPCR has no effect.
+        unsafe { std::arch::asm!("mrs {}, fpcr", out(reg) value) };
+        value
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn write(value: u64) {
+        // SAFETY: only ever a value read from FPCR, with at most the
+        // flush-to-zero bit added.
+        unsafe { std::arch::asm!("msr fpcr, {}", in(reg) value) };
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn read() -> u64 {
+        0
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn write(_value: u64) {}
+}
+
+impl Drop for FlushToZero {
+    fn drop(&mut self) {
+        Self::write(self.previous);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The report lines the verdicts up, totals them, and only a
+    /// failure fails the run: a skip doesn't.
+    #[test]
+    fn test_report() {
+        let result = |name, verdict, detail: &str| CheckResult {
+            name,
+            verdict,
+            detail: detail.to_string(),
+        };
+        let mut results = vec![
+            result("impulse timing", Verdict::Pass, "on time"),
+            result("allocations", Verdict::Skip, "not counted"),
+        ];
+        assert!(passed(&results));
+        assert_eq!(
+            report(&results),
+            "PASS  impulse timing  on time\n\
+             SKIP  allocations     not counted\n\
+             1 passed, 0 failed, 1 skipped\n"
+        );
+
+        results.push(result("tail decay", Verdict::Fail, "too slow"));
+        assert!(!passed(&results));
+        assert!(report(&results).ends_with("1 passed, 1 failed, 1 skipped\n"));
+    }
+
+    /// Flush-to-zero is on while held and off again after, and a sine
+    /// at the cutoff comes through 3 dB down.
+    #[test]
+    fn test_flush_to_zero_and_filter_gain() {
+        let tiny = || black_box(f32::MIN_POSITIVE) * black_box(0.5);
+        assert!(tiny().is_subnormal());
+        if cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+            let ftz = FlushToZero::enable();
+            assert_eq!(tiny(), 0.0);
+            drop(ftz);
+        }
+        assert!(tiny().is_subnormal());
+
+        assert!((filter_gain_db(1000.0, 1000.0, SAMPLE_RATE) + 3.01).abs() < 0.1);
+    }
+}
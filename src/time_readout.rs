@@ -0,0 +1,182 @@
+//! # The Time Being Played
+//!
+//! The Delay Time knob says what you asked for, not what you hear. Sync
+//! swaps it for a division at the tempo, the multiplier scales it, Fine
+//! nudges it, Multiply and Divide double and halve it, and every change
+//! glides, crossfades or runs a tape head there. The readout shows the
+//! one time all that lands on — the time the engine last read its lines
+//! at — in milliseconds and as the nearest note:
+//!
+//! ```text
+//! 375.0 ms   1/8 dotted         synced at 120 BPM, settled
+//! 412.3 ms   ≈ 1/8 dotted       gliding between two times
+//! ```
+//!
+//! Both halves are formatted by the code the parameters use
+//! (`delay_ms_text()` and `division_name()` in `params`), so a settled
+//! readout reads exactly as the Delay Time field or the Division menu.
+//! The note is marked "≈" when the time is more than
+//! [`NOTE_TOLERANCE`] off it, and left out while there's no tempo.
+//!
+//! ## Publishing
+//!
+//! As with the [loop meter](crate::loop_meter), the audio thread
+//! publishes and the editor reads:
+//!
+//! ```text
+//! process() ─► TimeReadout::publish() ─► AtomicU32 ─► TimeReadout::delay_ms()
+//!              one store per block                      once per frame
+//! ```
+//!
+//! The time is one `f32`, stored as its bits in an `AtomicU32`, so it
+//! can't tear, allocate or block. The tempo for the note comes from the
+//! [`SyncHandover`](crate::sync_handover::SyncHandover) the plugin
+//! already publishes to. There's no editor yet to draw it.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::dsp::tempo::NoteDivision;
+use crate::params::{delay_ms_text, division_name, DELAY_TIME_UNIT};
+
+/// How far from a note's length a time can be and still read as that
+/// note, as a fraction of it: half a percent, 1.9 ms at a dotted
+/// eighth at 120 BPM.
+pub const NOTE_TOLERANCE: f64 = 0.005;
+
+/// The delay time last played, written by the audio thread and read by
+/// the editor.
+#[derive(Debug, Default)]
+pub struct TimeReadout {
+    /// The time's `f32` bits, with 0 (0.0 ms) standing for "not
+    /// published yet".
+    delay_ms: AtomicU32,
+}
+
+impl TimeReadout {
+    /// Store the delay time the engine last read at (its
+    /// [`current_values()`](crate::LovelessDelayEngine::current_values)).
+    /// Safe on the audio thread: one atomic store.
+    pub fn publish(&self, delay_ms: f32) {
+        let delay_ms = Some(delay_ms)
+            .filter(|ms| ms.is_finite() && *ms > 0.0)
+            .unwrap_or(0.0);
+        self.delay_ms.store(delay_ms.to_bits(), Ordering::Relaxed);
+    }
+
+    /// The last time published, if there's been one.
+    pub fn delay_ms(&self) -> Option<f32> {
+        Some(f32::from_bits(self.delay_ms.load(Ordering::Relaxed))).filter(|ms| *ms > 0.0)
+    }
+}
+
+/// `delay_ms` as the readout shows it: as Delay Time shows it, with its
+/// unit.
+pub fn ms_text(delay_ms: f32) -> String {
+    format!("{}{DELAY_TIME_UNIT}", delay_ms_text(delay_ms))
+}
+
+/// `delay_ms` as the nearest note at `tempo_bpm`, marked "≈" unless
+/// it's within [`NOTE_TOLERANCE`] of it, or `None` without a tempo.
+pub fn note_text(delay_ms: f32, tempo_bpm: Option<f64>) -> Option<String> {
+    let tempo_bpm = tempo_bpm.filter(|bpm| *bpm > 0.0)?;
+    let division = NoteDivision::nearest(delay_ms, tempo_bpm);
+    let off = (f64::from(delay_ms) / division.to_ms(tempo_bpm) - 1.0).abs();
+    let name = division_name(division);
+    Some(if off <= NOTE_TOLERANCE {
+        name.to_string()
+    } else {
+        format!("≈ {name}")
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DelayParams, LovelessDelayEngine, Transport};
+    use std::sync::Arc;
+
+    /// The milliseconds read as Delay Time shows them, and the note as
+    /// Division names it, marked when it's only the nearest.
+    #[test]
+    fn test_formatting() {
+        assert_eq!(ms_text(375.0), "375.0 ms");
+        assert_eq!(ms_text(412.34), "412.3 ms");
+        assert_eq!(
+            ms_text(500.0),
+            format!("{}{DELAY_TIME_UNIT}", delay_ms_text(500.0))
+        );
+
+        assert_eq!(note_text(375.0, Some(120.0)).as_deref(), Some("1/8 dotted"));
+        assert_eq!(note_text(376.0, Some(120.0)).as_deref(), Some("1/8 dotted"));
+        assert_eq!(note_text(1000.0, Some(120.0)).as_deref(), Some("1/2"));
+        assert_eq!(
+            note_text(412.3, Some(120.0)).as_deref(),
+            Some("≈ 1/8 dotted")
+        );
+        assert_eq!(note_text(375.0, None), None);
+        assert_eq!(note_text(375.0, Some(0.0)), None);
+    }
+
+    /// What the audio thread publishes, another thread reads back, and
+    /// nothing reads back before the first block or after nonsense.
+    #[test]
+    fn test_publish_and_read_across_threads() {
+        let readout = Arc::new(TimeReadout::default());
+        assert_eq!(readout.delay_ms(), None);
+
+        let writer = {
+            let readout = Arc::clone(&readout);
+            std::thread::spawn(move || readout.publish(412.3))
+        };
+        writer.join().unwrap();
+        assert_eq!(readout.delay_ms(), Some(412.3));
+
+        readout.publish(f32::NAN);
+        assert_eq!(readout.delay_ms(), None);
+    }
+
+    /// Synced to a dotted eighth at 120 BPM, the engine's time glides
+    /// from the knob's to 375 ms, and the readout follows it there.
+    #[test]
+    fn test_engine_time_published() {
+        const SAMPLE_RATE: f32 = 48000.0;
+        let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+        let readout = TimeReadout::default();
+        engine.set_transport(Transport {
+            tempo: Some(120.0),
+            ..Transport::default()
+        });
+        engine.set_params(&DelayParams {
+            delay_ms: 500.0,
+            ..DelayParams::default()
+        });
+        let play = |engine: &mut LovelessDelayEngine, blocks: usize| {
+            for _ in 0..blocks {
+                let mut left = vec![0.0; 480];
+                let mut right = vec![0.0; 480];
+                engine.process(&mut [&mut left, &mut right]);
+                readout.publish(engine.current_values().delay_ms);
+            }
+            readout.delay_ms().unwrap()
+        };
+        assert!((play(&mut engine, 10) - 500.0).abs() < 0.05);
+
+        engine.set_params(&DelayParams {
+            delay_ms: 500.0,
+            sync: true,
+            division: NoteDivision::DottedEighth,
+            ..DelayParams::default()
+        });
+        let settled = play(&mut engine, 200);
+        assert!((settled - 375.0).abs() < 0.05, "{settled}");
+        assert_eq!(ms_text(settled), "375.0 ms");
+        assert_eq!(
+            note_text(settled, Some(120.0)).as_deref(),
+            Some("1/8 dotted")
+        );
+    }
+}
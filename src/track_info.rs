@@ -0,0 +1,142 @@
+//! # The Host's Track Name and Color
+//!
+//! A plugin window that shows which track it sits on — "Vox Dub L" in
+//! the track's own color — is a small thing that makes a busy session
+//! easier to find your way around. CLAP hosts offer exactly that through
+//! the *track-info* extension (`clap.track-info`):
+//!
+//! ```text
+//! host                                   plugin
+//!  │  track renamed or recolored            │
+//!  ├── plugin_track_info.changed() ────────►│  (main thread)
+//!  │◄──────────── host_track_info.get() ────┤  name, color, flags
+//!  │                                        │
+//!  │                               store it here; the editor
+//!  │                               picks it up on its next redraw
+//! ```
+//!
+//! Both calls happen on the main thread, and the editor reads from its
+//! own GUI thread, so the information crosses threads once, through a
+//! [`SharedTrackInfo`]. The audio thread never touches it: nothing about
+//! the sound depends on a track's name.
+//!
+//! ## Not Wired Up Yet
+//!
+//! nih-plug's CLAP wrapper doesn't advertise the track-info extension,
+//! and [`ClapPlugin`](nih_plug::prelude::ClapPlugin) has no callback for
+//! it, so there's nothing for the plugin to hook into without patching
+//! nih-plug itself. There's no editor to show the result yet either.
+//! This module is the half that doesn't depend on either: when nih-plug
+//! grows a `track_info_changed()` hook, the plugin stores what the host
+//! reports with [`SharedTrackInfo::set()`], and the editor reads it back
+//! with [`SharedTrackInfo::get()`] whenever
+//! [`SharedTrackInfo::generation()`] has moved on.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// An RGBA color as the host reports it, 8 bits per channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// What the host has told us about the track the plugin sits on. Every
+/// field is optional: hosts may leave any of them out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackInfo {
+    pub name: Option<String>,
+    pub color: Option<TrackColor>,
+}
+
+/// The latest [`TrackInfo`], shared between the main thread (which
+/// writes it) and the editor (which reads it).
+///
+/// A plain lock is fine here: both sides are UI threads, and a rename
+/// is rare. The generation counter lets the editor check for a change
+/// every frame without taking the lock.
+#[derive(Debug, Default)]
+pub struct SharedTrackInfo {
+    info: Mutex<TrackInfo>,
+    generation: AtomicU64,
+}
+
+impl SharedTrackInfo {
+    /// Store what the host just reported. Call it from the main thread,
+    /// never the audio thread.
+    pub fn set(&self, info: TrackInfo) {
+        let mut stored = self
+            .info
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *stored != info {
+            *stored = info;
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// A copy of the latest information.
+    pub fn get(&self) -> TrackInfo {
+        self.info
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// How many times the information has changed. An editor keeps the
+    /// last value it drew and calls [`get()`](Self::get) only when this
+    /// has moved on.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// What one thread stores, another reads back; the generation moves
+    /// on for a change and stays put for a repeat of the same report.
+    #[test]
+    fn test_set_and_get_across_threads() {
+        let shared = Arc::new(SharedTrackInfo::default());
+        assert_eq!(shared.get(), TrackInfo::default());
+        assert_eq!(shared.generation(), 0);
+
+        let info = TrackInfo {
+            name: Some("Vox Dub L".to_string()),
+            color: Some(TrackColor {
+                red: 200,
+                green: 40,
+                blue: 90,
+                alpha: 255,
+            }),
+        };
+        let writer = {
+            let shared = Arc::clone(&shared);
+            let info = info.clone();
+            std::thread::spawn(move || shared.set(info))
+        };
+        writer.join().unwrap();
+        assert_eq!(shared.generation(), 1);
+        assert_eq!(shared.get(), info);
+
+        shared.set(info.clone());
+        assert_eq!(shared.generation(), 1);
+
+        shared.set(TrackInfo {
+            color: None,
+            ..info
+        });
+        assert_eq!(shared.generation(), 2);
+        assert_eq!(shared.get().color, None);
+    }
+}
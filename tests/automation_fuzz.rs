@@ -0,0 +1,779 @@
+//! # Automation Fuzzing
+//!
+//! The unit tests each turn one knob at a time. A session turns all of
+//! them, at once, in orders nobody planned: Freeze engaged mid-glide,
+//! the feedback source flipped while the matrix crosses the sides, the
+//! shelves boosted with the drive up and the external loop patched in.
+//! This test plays that session. For each seed it draws random — but
+//! bounded, every value inside its knob's range — automation for every
+//! field of [`DelayParams`], renders ten seconds of varied program
+//! material through the headless engine, and checks what must hold
+//! whatever the knobs do:
+//!
+//! - **No NaN or infinity** ever reaches the output.
+//! - **The output stays below [`PEAK_CEILING`]** (+12 dBFS) for input
+//!   that peaks at [`INPUT_PEAK`]. The loop-gain ceiling, not the
+//!   settings, decides how far the repeats can pile up.
+//! - **No clicks.** Every switch in the engine is faded or smoothed, so
+//!   no sample may jump further from the curve its neighbours trace than
+//!   smooth audio at that level can (see [`find_click()`]).
+//! - **The tail is finite** — within what the loop-gain ceiling allows
+//!   at the longest delay — whenever neither Freeze nor Hold is engaged.
+//!   And once the knobs settle on something ordinary and the input goes
+//!   quiet, the engine reports the tail over.
+//!
+//! The seeds are fixed, so a run is repeatable: a failure names its seed,
+//! and `FUZZ_SEED=<seed> cargo test --test automation_fuzz` plays just
+//! that one again.
+//!
+//! ## Keeping the Fuzz Fair
+//!
+//! A few moves are sharp on purpose, and the automation stays clear of
+//! them rather than loosening the checks for everything else:
+//!
+//! - **Delay time glides.** In Glide mode a change of delay time
+//!   repitches the repeats as the read head sweeps to the new time — a
+//!   jump from 100 ms to 2 s is a chirp, by design. So the delay time,
+//!   fine offset and pre-delay are ramped, never jumped, and no faster
+//!   than a gentle repitch; the musical time (sync, division, multiplier)
+//!   only switches in Crossfade mode, which fades rather than sweeps.
+//!   Double and Half Time always crossfade, so they're free to switch.
+//!   Tape mode's head bends at most seven semitones as it re-locks, but
+//!   Scrub below 0% races it up an octave or more, so Scrub stays at or
+//!   above 0%: slowing, stopping and reversing never raise the pitch.
+//!   Time Snap's musical grid is left out: its points sit up to a couple
+//!   of hundred milliseconds apart, so a ramp across one jumps.
+//! - **Time modulation sways gently.** The LFO moves the read head as a
+//!   glide does, so it's held to [`MAX_MOD_RATE_HZ`] and a glide of at
+//!   least half a cycle between sample-and-hold levels, which bends
+//!   the repeats by three semitones at most. Saw and square jump the
+//!   head every cycle, so they're left out.
+//! - **The tempo holds steady** through a render, for the same reason:
+//!   a synced delay follows it.
+//! - **The external loop is a path of its own.** It sets mono-safe
+//!   linking, stereo motion, the matrix, spread, Alternate, the swap,
+//!   side decay and freeze aside (see the engine's docs), so patching it in or out
+//!   with any of those engaged changes the sound on the spot. The loop
+//!   only switches while they all rest.
+//!
+//! Everything else — every switch, every other knob, jumps as well as
+//! ramps — is fair game.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use loveless_delay_v1::dsp::bloom::MAX_BLOOM_DB;
+use loveless_delay_v1::dsp::feedback::{ChainOrder, MAX_SHELF_DB};
+use loveless_delay_v1::dsp::feedback_matrix::FeedbackMatrix;
+use loveless_delay_v1::dsp::lfo::LfoWaveform;
+use loveless_delay_v1::dsp::noise::Xorshift32;
+use loveless_delay_v1::dsp::output_filter::{HIGHCUT_OFF_HZ, LOWCUT_OFF_HZ};
+use loveless_delay_v1::dsp::repeat_sequencer::{StepTarget, MAX_STEPS};
+use loveless_delay_v1::dsp::stereo_motion::{StartSide, StereoMotion, MAX_SIDE_DECAY_DB};
+use loveless_delay_v1::dsp::tape_head::MAX_SCRUB;
+use loveless_delay_v1::dsp::tempo::NoteDivision;
+use loveless_delay_v1::dsp::time_change::{TimeChange, TimeMultiplier, TimeSnap};
+use loveless_delay_v1::{
+    DelayParams, FeedbackSource, LovelessDelayEngine, StopBehavior, Transport, MAX_DELAY_MS,
+    MAX_FEEDBACK, MAX_MOD_DEPTH_MS, MAX_PRE_DELAY_MS, MAX_TIME_FINE_MS, MIN_DELAY_MS,
+    MOD_RATE_RANGE_HZ,
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const SECONDS: f32 = 10.0;
+const LENGTH: usize = (SECONDS * SAMPLE_RATE) as usize;
+
+/// The host's buffer size, and so the external loop's latency: what
+/// goes out of the send in one block comes back in the next.
+const BLOCK_SIZE: usize = 256;
+
+/// The seeds every run plays.
+const SEEDS: [u32; 4] = [0x5EED_0001, 0x0BAD_CAFE, 0x1234_5678, 0xDE1A_7E57];
+
+/// The loudest the program material gets: −6 dBFS.
+const INPUT_PEAK: f32 = 0.5;
+
+/// The loudest the output may get: +12 dBFS.
+const PEAK_CEILING: f32 = 4.0;
+
+/// How far a sample may bend away from its neighbours, as a fraction of
+/// the level around it. See [`find_click()`].
+const CLICK_RATIO: f32 = 0.25;
+
+/// Below this, a bend counts as no bend at all, however quiet the
+/// surroundings: about −66 dBFS.
+const CLICK_FLOOR: f32 = 5e-4;
+
+/// How fast the delay time and pre-delay may be ramped, in ms of delay
+/// per ms of time. Even doubled by the time multiplier, that repitches
+/// the repeats by half an octave at most.
+const MAX_TIME_SLOPE: f32 = 0.2;
+
+/// The fastest the time modulation runs, in Hz. At the full depth a
+/// sine there sways the read head at a third of [`MAX_TIME_SLOPE`], and
+/// sample-and-hold, gliding over half a cycle or more, at most at four
+/// fifths of it.
+const MAX_MOD_RATE_HZ: f32 = 1.0;
+
+/// How long the engine gets, with ordinary settings and a silent input,
+/// to report its tail over.
+const COOL_DOWN_SECONDS: f32 = 6.0;
+
+#[test]
+fn random_automation_keeps_the_output_sane() {
+    let seeds = match std::env::var("FUZZ_SEED") {
+        Ok(seed) => vec![parse_seed(&seed)],
+        Err(_) => SEEDS.to_vec(),
+    };
+    for seed in seeds {
+        if let Err(panic) = catch_unwind(AssertUnwindSafe(|| fuzz(seed))) {
+            let message = panic
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| panic.downcast_ref::<&str>().copied())
+                .unwrap_or("(no message)");
+            panic!(
+                "seed {seed:#010x} failed: {message}\n\
+                 replay it with FUZZ_SEED={seed:#010x} cargo test --test automation_fuzz"
+            );
+        }
+    }
+}
+
+/// A seed from the environment, in decimal or `0x` hex.
+fn parse_seed(seed: &str) -> u32 {
+    let seed = seed.trim();
+    let parsed = match seed.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(&hex.replace('_', ""), 16),
+        None => seed.parse(),
+    };
+    parsed.unwrap_or_else(|_| panic!("FUZZ_SEED={seed:?} isn't a number"))
+}
+
+/// Render one seed's session and check it. Panics on the first broken
+/// invariant.
+fn fuzz(seed: u32) {
+    let mut dice = Dice::new(seed);
+    let tempo = dice.range(70.0, 170.0) as f64;
+    let automation = automation(&mut dice);
+    let [mut left, mut right] = program_material(&mut dice);
+    let key = key_material(&mut dice);
+    assert!(
+        find_click(&left).is_none() && find_click(&right).is_none(),
+        "the program material clicks by itself"
+    );
+
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    engine.set_external_loop_latency(BLOCK_SIZE);
+    let mut cable = [vec![0.0; BLOCK_SIZE], vec![0.0; BLOCK_SIZE]];
+    let tail_bound = tail_bound();
+
+    for (block, ((left, right), key)) in left
+        .chunks_mut(BLOCK_SIZE)
+        .zip(right.chunks_mut(BLOCK_SIZE))
+        .zip(key.chunks(BLOCK_SIZE))
+        .enumerate()
+    {
+        let start = block * BLOCK_SIZE;
+        let params = params_at(&automation, start);
+        engine.set_transport(Transport {
+            tempo: Some(tempo),
+            pos_beats: Some(start as f64 / f64::from(SAMPLE_RATE) * tempo / 60.0),
+            playing: automation_at(&automation, start).playing,
+        });
+        engine.set_params(&params);
+
+        // A wire from the send back to the return, one block late.
+        let [mut return_left, mut return_right] = cable.clone();
+        let [send_left, send_right] = &mut cable;
+        let mut key = key.to_vec();
+        engine.process_with_external_loop(
+            &mut [left, right],
+            &mut [send_left, send_right],
+            &[&mut return_left, &mut return_right],
+            Some(&[&mut key]),
+        );
+
+        if !params.freeze && !params.hold {
+            if let Some(tail) = engine.tail_samples() {
+                assert!(
+                    tail <= tail_bound,
+                    "a tail of {tail} samples at {:.2} s, past the {tail_bound} the \
+                     loop-gain ceiling allows",
+                    start as f32 / SAMPLE_RATE
+                );
+            }
+        }
+    }
+
+    for (side, output) in [("left", &left), ("right", &right)] {
+        if let Some(n) = output.iter().position(|s| !s.is_finite()) {
+            panic!(
+                "the {side} output went {} at {:.4} s",
+                output[n],
+                seconds(n)
+            );
+        }
+        let (n, peak) = output
+            .iter()
+            .enumerate()
+            .fold((0, 0.0f32), |(at, peak), (n, s)| {
+                if s.abs() > peak {
+                    (n, s.abs())
+                } else {
+                    (at, peak)
+                }
+            });
+        assert!(
+            peak < PEAK_CEILING,
+            "the {side} output peaked at {:.1} dBFS at {:.4} s",
+            20.0 * peak.log10(),
+            seconds(n)
+        );
+        if let Some((n, bend, level)) = find_click(output) {
+            panic!(
+                "a click in the {side} output at {:.4} s: a bend of {bend:.4} in audio \
+                 around {level:.4}",
+                seconds(n)
+            );
+        }
+    }
+
+    cool_down(&mut engine, params_at(&automation, LENGTH - 1), tempo);
+}
+
+/// Settle the knobs on something ordinary, play silence, and require
+/// the engine to report the tail over within [`COOL_DOWN_SECONDS`].
+fn cool_down(engine: &mut LovelessDelayEngine, last: DelayParams, tempo: f64) {
+    let settled = DelayParams {
+        delay_ms: 250.0,
+        sync: false,
+        time_multiplier: TimeMultiplier::Single,
+        // A tape head left at the far end of the knob takes seconds to
+        // run home, by design; a crossfade gets there at once.
+        time_change: TimeChange::Crossfade,
+        scrub: 0.0,
+        double_time: false,
+        half_time: false,
+        time_fine_ms: 0.0,
+        pre_delay_ms: 0.0,
+        feedback: last.feedback.min(0.3),
+        hold: false,
+        freeze: false,
+        overdub: false,
+        clear: false,
+        key_gate: false,
+        external_loop: false,
+        ..last
+    };
+    engine.set_params(&settled);
+    engine.set_transport(Transport {
+        tempo: Some(tempo),
+        pos_beats: None,
+        playing: true,
+    });
+    let mut left = vec![0.0; BLOCK_SIZE];
+    let mut right = vec![0.0; BLOCK_SIZE];
+    let blocks = (COOL_DOWN_SECONDS * SAMPLE_RATE) as usize / BLOCK_SIZE;
+    for _ in 0..blocks {
+        left.fill(0.0);
+        right.fill(0.0);
+        engine.process(&mut [&mut left, &mut right]);
+        if engine.tail_samples().is_none() {
+            return;
+        }
+    }
+    panic!(
+        "the tail was still {:?} samples after {COOL_DOWN_SECONDS} s of silence at \
+         {:.0}% feedback",
+        engine.tail_samples(),
+        100.0 * settled.feedback
+    );
+}
+
+/// The longest tail the engine could honestly report: repeats at the
+/// loop-gain ceiling (0.98) falling 60 dB, at the longest delay the
+/// knobs reach, after the longest pre-delay.
+fn tail_bound() -> u32 {
+    let repeats = -3.0 / 0.98f32.log10();
+    let delay_ms = 2.0 * MAX_DELAY_MS + MAX_TIME_FINE_MS;
+    let ms = repeats * delay_ms + MAX_PRE_DELAY_MS;
+    (ms / 1000.0 * SAMPLE_RATE) as u32
+}
+
+fn seconds(n: usize) -> f32 {
+    n as f32 / SAMPLE_RATE
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Clicks
+// ─────────────────────────────────────────────────────────────────────
+
+/// The first click in `output`, if there is one: where, how far the
+/// sample bent, and the level around it.
+///
+/// The *bend* at a sample is how far it lands from the straight line
+/// through the two before it — the second difference,
+/// `x[n] − 2·x[n−1] + x[n−2]`. A sine of amplitude `A` and frequency
+/// `f` bends by at most `A·(2πf/fs)²`: a quarter of `A` only above
+/// 3.8 kHz, and the program material is far below that. A step, though,
+/// bends by its full height, whatever it steps between. So a bend of more
+/// than [`CLICK_RATIO`] of the loudest sample within a block either side
+/// is a click.
+fn find_click(output: &[f32]) -> Option<(usize, f32, f32)> {
+    let peaks: Vec<f32> = output
+        .chunks(BLOCK_SIZE)
+        .map(|chunk| chunk.iter().fold(0.0f32, |m, s| m.max(s.abs())))
+        .collect();
+    (2..output.len()).find_map(|n| {
+        let bend = (output[n] - 2.0 * output[n - 1] + output[n - 2]).abs();
+        let chunk = n / BLOCK_SIZE;
+        let level = peaks[chunk.saturating_sub(1)..(chunk + 2).min(peaks.len())]
+            .iter()
+            .fold(0.0f32, |m, p| m.max(*p));
+        (bend > CLICK_FLOOR + CLICK_RATIO * level).then_some((n, bend, level))
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Automation
+// ─────────────────────────────────────────────────────────────────────
+
+/// One point of the automation: the settings reached at sample `at`,
+/// and how the way there goes.
+struct Keyframe {
+    at: usize,
+    params: DelayParams,
+    /// Whether the knobs ramp to these settings from the last keyframe's
+    /// (the switches still flip here), or jump to them on arrival.
+    ramp: bool,
+    /// Whether the host's transport plays from here on.
+    playing: bool,
+}
+
+/// A random session: keyframes from a quarter of a second to a second
+/// and a half apart, from the start to past the end of the render.
+fn automation(dice: &mut Dice) -> Vec<Keyframe> {
+    let mut keyframes = vec![Keyframe {
+        at: 0,
+        params: random_params(dice),
+        ramp: false,
+        playing: true,
+    }];
+    while let Some(last) = keyframes.last().filter(|k| k.at < LENGTH) {
+        let at = last.at + (dice.range(0.25, 1.5) * SAMPLE_RATE) as usize;
+        let ramp = dice.chance(0.5);
+        let gap_ms = (at - last.at) as f32 / SAMPLE_RATE * 1000.0;
+        let params = fair(&last.params, random_params(dice), gap_ms, ramp);
+        keyframes.push(Keyframe {
+            at,
+            params,
+            ramp,
+            playing: dice.chance(0.9),
+        });
+    }
+    keyframes
+}
+
+/// `next`, made fair to follow `last` (see
+/// [Keeping the Fuzz Fair](self#keeping-the-fuzz-fair)).
+fn fair(last: &DelayParams, next: DelayParams, gap_ms: f32, ramp: bool) -> DelayParams {
+    // A jump holds the times still; a ramp moves them no faster than
+    // MAX_TIME_SLOPE.
+    let reach = if ramp { MAX_TIME_SLOPE * gap_ms } else { 0.0 };
+    let toward = |from: f32, to: f32| to.clamp(from - reach, from + reach);
+    let mut fair = DelayParams {
+        delay_ms: toward(last.delay_ms, next.delay_ms),
+        time_fine_ms: toward(last.time_fine_ms, next.time_fine_ms),
+        pre_delay_ms: toward(last.pre_delay_ms, next.pre_delay_ms),
+        ..next
+    };
+    if fair.time_change != TimeChange::Crossfade {
+        fair.sync = last.sync;
+        fair.division = last.division;
+        fair.time_multiplier = last.time_multiplier;
+    }
+    if fair.external_loop != last.external_loop {
+        if at_rest_outside_the_loop(last) {
+            fair = DelayParams {
+                mono_safe: false,
+                motion: StereoMotion::Off,
+                feedback_matrix: FeedbackMatrix::STRAIGHT,
+                spread: 0.0,
+                alternate: false,
+                swap_channels: false,
+                side_decay_db: 0.0,
+                freeze: false,
+                ..fair
+            };
+        } else {
+            fair.external_loop = last.external_loop;
+        }
+    }
+    fair
+}
+
+/// Whether everything the external loop sets aside is at rest.
+fn at_rest_outside_the_loop(params: &DelayParams) -> bool {
+    !params.mono_safe
+        && params.motion == StereoMotion::Off
+        && params.feedback_matrix == FeedbackMatrix::STRAIGHT
+        && params.spread == 0.0
+        && !params.alternate
+        && !params.swap_channels
+        && params.side_decay_db == 0.0
+        && !params.freeze
+}
+
+/// The keyframe in force at sample `at`.
+fn automation_at(automation: &[Keyframe], at: usize) -> &Keyframe {
+    let next = automation.partition_point(|k| k.at <= at);
+    &automation[next - 1]
+}
+
+/// The settings at sample `at`: the last keyframe's, or on a ramp,
+/// partway to the next one's.
+fn params_at(automation: &[Keyframe], at: usize) -> DelayParams {
+    let next = automation.partition_point(|k| k.at <= at);
+    let from = &automation[next - 1];
+    match automation.get(next) {
+        Some(to) if to.ramp => {
+            let t = (at - from.at) as f32 / (to.at - from.at) as f32;
+            blend(&from.params, &to.params, t)
+        }
+        _ => from.params,
+    }
+}
+
+/// `from`'s settings with every continuous knob `t` of the way to
+/// `to`'s. The switches stay at `from`'s until the keyframe.
+fn blend(from: &DelayParams, to: &DelayParams, t: f32) -> DelayParams {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    let mut steps = from.steps;
+    for (step, target) in steps.iter_mut().zip(to.steps) {
+        *step = lerp(*step, target);
+    }
+    let (m, n) = (from.feedback_matrix, to.feedback_matrix);
+    DelayParams {
+        delay_ms: lerp(from.delay_ms, to.delay_ms),
+        manual_bpm: lerp(from.manual_bpm, to.manual_bpm),
+        time_fine_ms: lerp(from.time_fine_ms, to.time_fine_ms),
+        mod_rate_hz: lerp(from.mod_rate_hz, to.mod_rate_hz),
+        mod_depth_ms: lerp(from.mod_depth_ms, to.mod_depth_ms),
+        mod_glide: lerp(from.mod_glide, to.mod_glide),
+        feedback: lerp(from.feedback, to.feedback),
+        hold_level: lerp(from.hold_level, to.hold_level),
+        pre_delay_ms: lerp(from.pre_delay_ms, to.pre_delay_ms),
+        mix: lerp(from.mix, to.mix),
+        filter_cutoff: lerp(from.filter_cutoff, to.filter_cutoff),
+        filter_cutoff_right: lerp(from.filter_cutoff_right, to.filter_cutoff_right),
+        drive: lerp(from.drive, to.drive),
+        fb_low_shelf_db: lerp(from.fb_low_shelf_db, to.fb_low_shelf_db),
+        fb_high_shelf_db: lerp(from.fb_high_shelf_db, to.fb_high_shelf_db),
+        character: lerp(from.character, to.character),
+        damping: lerp(from.damping, to.damping),
+        out_lowcut: lerp(from.out_lowcut, to.out_lowcut),
+        out_highcut: lerp(from.out_highcut, to.out_highcut),
+        steps,
+        motion_rotation: lerp(from.motion_rotation, to.motion_rotation),
+        feedback_matrix: FeedbackMatrix {
+            ll: lerp(m.ll, n.ll),
+            lr: lerp(m.lr, n.lr),
+            rl: lerp(m.rl, n.rl),
+            rr: lerp(m.rr, n.rr),
+        },
+        watchdog_seconds: lerp(from.watchdog_seconds, to.watchdog_seconds),
+        decorrelate: lerp(from.decorrelate, to.decorrelate),
+        dry_pan: lerp(from.dry_pan, to.dry_pan),
+        input_balance: lerp(from.input_balance, to.input_balance),
+        side_decay_db: lerp(from.side_decay_db, to.side_decay_db),
+        spread: lerp(from.spread, to.spread),
+        bloom_db: lerp(from.bloom_db, to.bloom_db),
+        key_threshold_db: lerp(from.key_threshold_db, to.key_threshold_db),
+        key_release_ms: lerp(from.key_release_ms, to.key_release_ms),
+        overdub_decay_db: lerp(from.overdub_decay_db, to.overdub_decay_db),
+        output_trim_db: lerp(from.output_trim_db, to.output_trim_db),
+        ..*from
+    }
+}
+
+/// Every field of [`DelayParams`], drawn from within its knob's range.
+/// The one-shot moves — Clear, Freeze — come up rarely, as they would in
+/// a session.
+fn random_params(dice: &mut Dice) -> DelayParams {
+    let mut steps = [0.0; MAX_STEPS];
+    for step in &mut steps {
+        *step = dice.unit();
+    }
+    let feedback_matrix = if dice.chance(0.5) {
+        FeedbackMatrix::STRAIGHT
+    } else {
+        FeedbackMatrix {
+            ll: dice.range(-1.0, 1.0),
+            lr: dice.range(-1.0, 1.0),
+            rl: dice.range(-1.0, 1.0),
+            rr: dice.range(-1.0, 1.0),
+        }
+    };
+    DelayParams {
+        delay_ms: dice.log_range(MIN_DELAY_MS.max(100.0), MAX_DELAY_MS),
+        sync: dice.chance(0.3),
+        // Inert: the host always reports a tempo here.
+        manual_bpm: dice.range(30.0, 300.0),
+        division: dice.pick(&NoteDivision::ALL),
+        quantize_start: dice.chance(0.2),
+        time_multiplier: dice.pick(&[
+            TimeMultiplier::Half,
+            TimeMultiplier::Single,
+            TimeMultiplier::Dotted,
+            TimeMultiplier::Double,
+        ]),
+        // Always crossfaded, so fair to throw in whenever.
+        double_time: dice.chance(0.1),
+        half_time: dice.chance(0.1),
+        time_fine_ms: dice.range(-MAX_TIME_FINE_MS, MAX_TIME_FINE_MS),
+        time_change: dice.pick(&[TimeChange::Glide, TimeChange::Crossfade, TimeChange::Tape]),
+        // The musical grid's points are far apart, so a ramp across one
+        // is a jump (see `Keeping the Fuzz Fair`).
+        time_snap: dice.pick(&[TimeSnap::Off, TimeSnap::Ms1, TimeSnap::Ms5, TimeSnap::Ms10]),
+        scrub: if dice.chance(0.5) {
+            0.0
+        } else {
+            dice.range(0.0, MAX_SCRUB)
+        },
+        // Swaying gently (see `Keeping the Fuzz Fair`).
+        mod_waveform: dice.pick(&[
+            LfoWaveform::Sine,
+            LfoWaveform::Triangle,
+            LfoWaveform::SampleAndHold,
+        ]),
+        mod_rate_hz: dice.range(MOD_RATE_RANGE_HZ.0, MAX_MOD_RATE_HZ),
+        mod_depth_ms: if dice.chance(0.5) {
+            0.0
+        } else {
+            dice.range(0.0, MAX_MOD_DEPTH_MS)
+        },
+        mod_glide: dice.range(0.5, 1.0),
+        feedback: dice.range(0.0, MAX_FEEDBACK),
+        hold: dice.chance(0.1),
+        hold_level: dice.unit(),
+        pre_delay_ms: if dice.chance(0.5) {
+            0.0
+        } else {
+            dice.range(0.0, MAX_PRE_DELAY_MS)
+        },
+        mix: dice.unit(),
+        filter_cutoff: dice.log_range(200.0, 20000.0),
+        filter_cutoff_right: dice.log_range(200.0, 20000.0),
+        feedback_compensation: dice.chance(0.5),
+        feedback_source: dice.pick(&[FeedbackSource::WetTap, FeedbackSource::PostMix]),
+        drive: dice.unit(),
+        chain_order: dice.pick(&[ChainOrder::FilterThenDrive, ChainOrder::DriveThenFilter]),
+        fb_low_shelf_db: dice.range(-MAX_SHELF_DB, MAX_SHELF_DB),
+        fb_high_shelf_db: dice.range(-MAX_SHELF_DB, MAX_SHELF_DB),
+        character: dice.unit(),
+        damping: dice.unit(),
+        out_lowcut: if dice.chance(0.5) {
+            LOWCUT_OFF_HZ
+        } else {
+            dice.log_range(LOWCUT_OFF_HZ, 500.0)
+        },
+        out_highcut: if dice.chance(0.5) {
+            HIGHCUT_OFF_HZ
+        } else {
+            dice.log_range(1000.0, HIGHCUT_OFF_HZ)
+        },
+        mono_safe: dice.chance(0.3),
+        limiter: dice.chance(0.5),
+        external_loop: dice.chance(0.3),
+        step_target: dice.pick(&[StepTarget::Off, StepTarget::Cutoff]),
+        step_length: 1 + dice.below(MAX_STEPS),
+        steps,
+        motion: dice.pick(&[
+            StereoMotion::Off,
+            StereoMotion::PingPong,
+            StereoMotion::ReversePingPong,
+            StereoMotion::Circular,
+        ]),
+        motion_rotation: dice.range(0.0, 180.0),
+        start_side: dice.pick(&[StartSide::Left, StartSide::Right, StartSide::Center]),
+        feedback_matrix,
+        clear: dice.chance(0.03),
+        wet_solo: dice.chance(0.1),
+        watchdog: dice.chance(0.3),
+        watchdog_seconds: dice.range(1.0, 120.0),
+        stop_behavior: dice.pick(&[
+            StopBehavior::RingOut,
+            StopBehavior::Fade,
+            StopBehavior::Clear,
+        ]),
+        decorrelate: dice.unit(),
+        phase_align: dice.chance(0.3),
+        dry_pan: dice.range(-1.0, 1.0),
+        input_balance: dice.range(-1.0, 1.0),
+        swap_channels: dice.chance(0.2),
+        side_decay_db: dice.range(0.0, MAX_SIDE_DECAY_DB),
+        spread: dice.unit(),
+        alternate: dice.chance(0.2),
+        bloom_db: dice.range(0.0, MAX_BLOOM_DB),
+        key_gate: dice.chance(0.2),
+        key_threshold_db: dice.range(-60.0, 0.0),
+        key_release_ms: dice.log_range(10.0, 1000.0),
+        // Listening swaps the output for the key, leaving nothing of the
+        // delay to check.
+        duck_listen: false,
+        freeze: dice.chance(0.05),
+        freeze_quantize: dice.chance(0.3),
+        overdub: dice.chance(0.1),
+        overdub_decay_db: dice.range(-24.0, -0.5),
+        output_trim_db: dice.range(-6.0, 6.0),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Program material
+// ─────────────────────────────────────────────────────────────────────
+
+/// Ten seconds of something to play, a stretch at a time: plucked
+/// notes, a sustained chord, a swell of band-limited noise, or silence.
+/// Everything is smooth (below about 1 kHz, no steps), so any click in
+/// the output is the engine's, and nothing goes over [`INPUT_PEAK`].
+fn program_material(dice: &mut Dice) -> [Vec<f32>; 2] {
+    let mut left = vec![0.0; LENGTH];
+    let mut right = vec![0.0; LENGTH];
+    let notes = [
+        196.0, 220.0, 262.0, 294.0, 330.0, 392.0, 440.0, 523.0, 587.0, 659.0,
+    ];
+    let mut start = 0;
+    while start < LENGTH {
+        let len = ((dice.range(0.5, 2.0) * SAMPLE_RATE) as usize).min(LENGTH - start);
+        let (left, right) = (
+            &mut left[start..start + len],
+            &mut right[start..start + len],
+        );
+        let width = dice.range(0.5, 1.0);
+        match dice.below(4) {
+            0 => {
+                // Plucks, every 100 to 400 ms.
+                let spacing = (dice.range(0.1, 0.4) * SAMPLE_RATE) as usize;
+                let decay = dice.range(0.03, 0.3);
+                let mut freq = dice.pick(&notes);
+                for n in 0..len {
+                    if n % spacing == 0 {
+                        freq = dice.pick(&notes);
+                    }
+                    let since = (n % spacing) as f32 / SAMPLE_RATE;
+                    let s = INPUT_PEAK
+                        * fade_envelope(n, len, 0.05)
+                        * fade_envelope(n % spacing, spacing, 0.005)
+                        * (-since / decay).exp()
+                        * (std::f32::consts::TAU * freq * since).sin();
+                    left[n] = s;
+                    right[n] = width * s;
+                }
+            }
+            1 => {
+                // A three-note chord, faded in and out.
+                let chord = [dice.pick(&notes), dice.pick(&notes), dice.pick(&notes)];
+                for n in 0..len {
+                    let t = n as f32 / SAMPLE_RATE;
+                    let envelope = fade_envelope(n, len, 0.05);
+                    let [a, b, c] = chord.map(|f| (std::f32::consts::TAU * f * t).sin());
+                    left[n] = INPUT_PEAK / 3.0 * envelope * (a + b + c);
+                    right[n] = INPUT_PEAK / 3.0 * envelope * (a + width * b - c);
+                }
+            }
+            2 => {
+                // Noise through two one-pole lowpasses near 500 Hz, swelling
+                // in and out.
+                let mut noise = Xorshift32::new(dice.next_u32() | 1);
+                let coeff = 1.0 - (-std::f32::consts::TAU * 500.0 / SAMPLE_RATE).exp();
+                let mut state = [[0.0f32; 2]; 2];
+                for n in 0..len {
+                    let envelope = fade_envelope(n, len, 0.05);
+                    for (side, state) in state.iter_mut().enumerate() {
+                        state[0] += coeff * (noise.next_bipolar() - state[0]);
+                        state[1] += coeff * (state[0] - state[1]);
+                        let s =
+                            (3.0 * INPUT_PEAK * envelope * state[1]).clamp(-INPUT_PEAK, INPUT_PEAK);
+                        if side == 0 {
+                            left[n] = s;
+                        } else {
+                            right[n] = s;
+                        }
+                    }
+                }
+            }
+            _ => {} // Silence.
+        }
+        start += len;
+    }
+    [left, right]
+}
+
+/// The sidechain: a kick-like thump every beat or so, sometimes resting.
+fn key_material(dice: &mut Dice) -> Vec<f32> {
+    let spacing = (dice.range(0.3, 0.8) * SAMPLE_RATE) as usize;
+    let rest = dice.chance(0.5);
+    (0..LENGTH)
+        .map(|n| {
+            let bar = n / (4 * spacing);
+            if rest && bar % 2 == 1 {
+                return 0.0;
+            }
+            let since = (n % spacing) as f32 / SAMPLE_RATE;
+            0.8 * (-since / 0.08).exp() * (std::f32::consts::TAU * 60.0 * since).sin()
+        })
+        .collect()
+}
+
+/// 0.0 to 1.0 over the first `fade` seconds of `len` samples, and back
+/// over the last, along a raised cosine.
+fn fade_envelope(n: usize, len: usize, fade: f32) -> f32 {
+    let edge = (n.min(len - 1 - n) as f32 / (fade * SAMPLE_RATE)).min(1.0);
+    0.5 - 0.5 * (std::f32::consts::PI * edge).cos()
+}
+
+// ─────────────────────────────────────────────────────────────────────
+// Dice
+// ─────────────────────────────────────────────────────────────────────
+
+/// The seeded source of every random choice, so a seed replays exactly.
+struct Dice(Xorshift32);
+
+impl Dice {
+    fn new(seed: u32) -> Self {
+        Self(Xorshift32::new(seed))
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    /// 0.0 to 1.0.
+    fn unit(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.unit()
+    }
+
+    /// Like [`range()`](Self::range), evenly spread in octaves rather
+    /// than in Hz or ms.
+    fn log_range(&mut self, low: f32, high: f32) -> f32 {
+        low * (high / low).powf(self.unit())
+    }
+
+    fn chance(&mut self, probability: f32) -> bool {
+        self.unit() < probability
+    }
+
+    /// 0 to `n − 1`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u32() as usize) % n
+    }
+
+    fn pick<T: Copy>(&mut self, choices: &[T]) -> T {
+        choices[self.below(choices.len())]
+    }
+}
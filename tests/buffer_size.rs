@@ -0,0 +1,229 @@
+//! # Buffer-Size Independence
+//!
+//! Hosts hand a plugin its audio in blocks of whatever size suits them —
+//! 16 samples for live monitoring, 4096 for a mixdown — and some change
+//! the size from one call to the next. The same session must sound the
+//! same either way: how the stream is chopped up is an accident of the
+//! host, not part of the music.
+//!
+//! These tests render the same two seconds of program material, with
+//! the same automation, at several buffer sizes, and require the results
+//! to match sample for sample. Anything in the engine that does work
+//! "once per block" — choosing a processing path, following the host's
+//! transport, counting down to a beat, measuring the tail — has to land
+//! on the same samples however the blocks fall.
+
+use loveless_delay_v1::dsp::noise::NoiseGenerator;
+use loveless_delay_v1::dsp::repeat_sequencer::StepTarget;
+use loveless_delay_v1::dsp::stereo_motion::{StartSide, StereoMotion};
+use loveless_delay_v1::dsp::tempo::NoteDivision;
+use loveless_delay_v1::{DelayParams, LovelessDelayEngine, Transport};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const TEMPO: f64 = 120.0;
+const LENGTH: usize = 2 * SAMPLE_RATE as usize;
+
+/// The buffer sizes to compare: odd, small, not a power of two, typical,
+/// and large.
+const BUFFER_SIZES: [usize; 5] = [17, 64, 100, 512, 4096];
+
+/// How far two renders may drift apart. The idle and smoothing paths
+/// agree to within an ulp or so, and a block boundary decides which one
+/// a stretch of samples takes.
+const TOLERANCE: f32 = 1e-6;
+
+/// Plucked tones and noise bursts on both channels, a little different
+/// on each side.
+fn program_material() -> [Vec<f32>; 2] {
+    let mut noise = NoiseGenerator::new(42);
+    let mut left = vec![0.0; LENGTH];
+    let mut right = vec![0.0; LENGTH];
+    for (n, (left, right)) in left.iter_mut().zip(&mut right).enumerate() {
+        let t = n as f32 / SAMPLE_RATE;
+        // A new note every 250 ms, decaying over about 60 ms.
+        let note = n / 12000;
+        let since = (n % 12000) as f32 / SAMPLE_RATE;
+        let freq = [220.0, 330.0, 277.0, 440.0][note % 4];
+        let pluck = 0.5 * (-since / 0.06).exp() * (std::f32::consts::TAU * freq * t).sin();
+        // A short noise burst every 700 ms.
+        let burst = if n % 33600 < 480 {
+            0.3 * noise.next()
+        } else {
+            0.0
+        };
+        *left = pluck + burst;
+        *right = 0.8 * pluck - burst;
+    }
+    [left, right]
+}
+
+/// The automation: parameter snapshots, each taking effect at a sample.
+fn automation() -> Vec<(usize, DelayParams)> {
+    let start = DelayParams {
+        sync: true,
+        division: NoteDivision::Eighth,
+        feedback: 0.6,
+        pre_delay_ms: 20.0,
+        drive: 0.3,
+        damping: 0.5,
+        mono_safe: true,
+        limiter: true,
+        step_target: StepTarget::Cutoff,
+        motion: StereoMotion::Circular,
+        ..DelayParams::default()
+    };
+    let unsynced = DelayParams {
+        sync: false,
+        delay_ms: 230.0,
+        ..start
+    };
+    let ping_pong = DelayParams {
+        motion: StereoMotion::PingPong,
+        start_side: StartSide::Center,
+        mix: 0.7,
+        ..unsynced
+    };
+    // A panic clear part-way through the ping-pong, released again at
+    // the next point.
+    let cleared = DelayParams {
+        clear: true,
+        ..ping_pong
+    };
+    let darker = DelayParams {
+        limiter: false,
+        mono_safe: false,
+        filter_cutoff: 3000.0,
+        filter_cutoff_right: 3000.0,
+        ..ping_pong
+    };
+    let resynced = DelayParams {
+        sync: true,
+        division: NoteDivision::DottedEighth,
+        step_target: StepTarget::Off,
+        motion: StereoMotion::Off,
+        damping: 0.0,
+        feedback: 0.9,
+        ..darker
+    };
+    vec![
+        (0, start),
+        (20_011, unsynced),
+        (45_000, ping_pong),
+        (52_345, cleared),
+        (60_123, darker),
+        (75_000, resynced),
+    ]
+}
+
+/// What one render produced: the audio, and the tail the engine
+/// reported at each automation point and at the end.
+struct Render {
+    channels: [Vec<f32>; 2],
+    tails: Vec<Option<u32>>,
+}
+
+/// Render the program through a fresh engine the way a host would:
+/// blocks of at most `buffer_sizes` (cycling through them, for a host
+/// that changes its size between calls — a size of 0 is an empty call),
+/// split at each automation point (as nih-plug does for sample-accurate
+/// automation), with the transport and the current parameters handed
+/// over before every block.
+fn render(buffer_sizes: &[usize]) -> Render {
+    let mut engine = LovelessDelayEngine::new(SAMPLE_RATE, 2);
+    let mut channels = program_material();
+    let automation = automation();
+    let mut params = automation[0].1;
+    let mut next_point = 0;
+    let mut tails = Vec::new();
+
+    let samples_per_beat = f64::from(SAMPLE_RATE) * 60.0 / TEMPO;
+    let mut buffer_sizes = buffer_sizes.iter().cycle();
+    let mut pos = 0;
+    while pos < LENGTH {
+        if let Some((_, new_params)) = automation.get(next_point).filter(|(at, _)| *at == pos) {
+            tails.push(engine.tail_samples());
+            params = *new_params;
+            next_point += 1;
+        }
+        let next_event = automation
+            .iter()
+            .map(|(at, _)| *at)
+            .find(|at| *at > pos)
+            .unwrap_or(LENGTH);
+        let len = (*buffer_sizes.next().unwrap()).min(next_event - pos);
+
+        engine.set_transport(Transport {
+            tempo: Some(TEMPO),
+            pos_beats: Some(pos as f64 / samples_per_beat),
+            ..Transport::default()
+        });
+        engine.set_params(&params);
+        let [left, right] = &mut channels;
+        engine.process(&mut [&mut left[pos..pos + len], &mut right[pos..pos + len]]);
+        pos += len;
+    }
+    tails.push(engine.tail_samples());
+
+    Render { channels, tails }
+}
+
+/// Panic at the first sample where `render` strays from `reference`,
+/// or if the reported tails differ. `label` names the render.
+fn assert_matches(render: &Render, reference: &Render, label: &str) {
+    for (channel, (got, want)) in render.channels.iter().zip(&reference.channels).enumerate() {
+        if let Some((n, (g, w))) = got
+            .iter()
+            .zip(want)
+            .enumerate()
+            .find(|(_, (g, w))| (*g - *w).abs() > TOLERANCE)
+        {
+            panic!("{label}, channel {channel}: sample {n} is {g}, expected {w}");
+        }
+    }
+    assert_eq!(render.tails, reference.tails, "{label}: tails");
+}
+
+/// Every buffer size renders the same audio, and reports the same tail,
+/// as the smallest.
+#[test]
+fn test_buffer_size_independence() {
+    let reference = render(&BUFFER_SIZES[..1]);
+    assert!(
+        reference.channels[0].iter().any(|s| s.abs() > 0.1),
+        "the program should be audible"
+    );
+
+    for &buffer_size in &BUFFER_SIZES[1..] {
+        let render = render(&[buffer_size]);
+        assert_matches(&render, &reference, &format!("buffer {buffer_size}"));
+    }
+}
+
+/// A host that changes its buffer size from call to call — down to
+/// single samples — gets the same audio too.
+#[test]
+fn test_changing_buffer_sizes() {
+    let reference = render(&BUFFER_SIZES[..1]);
+    let ragged = render(&[1, 300, 17, 4096, 64, 5, 999]);
+    assert_matches(&ragged, &reference, "changing sizes");
+}
+
+/// A single sample at a time — the smallest buffer a host can send —
+/// gets the same audio as any other size.
+#[test]
+fn test_single_sample_buffers() {
+    let reference = render(&BUFFER_SIZES[3..4]);
+    let single = render(&[1]);
+    assert_matches(&single, &reference, "single samples");
+}
+
+/// Empty calls between the real ones, as plugin validators make (and
+/// some hosts, around transport changes), change nothing at all: the
+/// transport and parameters handed over with them, then no audio, leave
+/// the engine exactly where it was.
+#[test]
+fn test_empty_buffers_change_nothing() {
+    let reference = render(&BUFFER_SIZES[..1]);
+    let with_empty = render(&[0, 17, 0, 0, 17, 1, 0]);
+    assert_matches(&with_empty, &reference, "empty buffers");
+}
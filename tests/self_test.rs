@@ -0,0 +1,47 @@
+//! # The Self-Test Under `cargo test`
+//!
+//! The render harness's `--self-test` runs runtime checks of the
+//! compiled DSP — impulse timing, the filter's corner, the tail's decay,
+//! denormals, allocations — for anyone who has changed the engine and
+//! wants to know it still behaves. The checks live in the library
+//! (`testing::self_test`, behind the `testing` feature, which this test
+//! needs) so that this test runs exactly the same ones: whatever the
+//! harness reports, `cargo test --features testing` has required.
+//!
+//! The allocation check counts with the self-test's own allocator,
+//! which a debug build can't have (nih-plug's takes its place), so it's
+//! skipped there and runs under `cargo test --release --features testing`.
+
+use loveless_delay_v1::testing::self_test::{self, Verdict};
+
+#[cfg(not(debug_assertions))]
+#[global_allocator]
+static ALLOCATOR: self_test::CountingAllocator = self_test::CountingAllocator;
+
+/// Every check passes, or is skipped for want of what it needs here.
+#[test]
+fn test_every_check_passes() {
+    for check in self_test::CHECKS {
+        let (verdict, detail) = (check.run)();
+        assert_ne!(verdict, Verdict::Fail, "{}: {detail}", check.name);
+    }
+}
+
+/// A release build counts allocations, so nothing is skipped there.
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_nothing_skipped_in_release() {
+    let results = self_test::run();
+    assert!(
+        self_test::passed(&results),
+        "{}",
+        self_test::report(&results)
+    );
+    if cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+        assert!(
+            results.iter().all(|result| result.verdict == Verdict::Pass),
+            "{}",
+            self_test::report(&results)
+        );
+    }
+}
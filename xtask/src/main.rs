@@ -1,10 +1,23 @@
-/// The xtask binary delegates entirely to nih_plug_xtask, which provides
-/// the `bundle` subcommand. Usage:
+mod nulltest;
+
+/// The xtask binary handles `nulltest` itself and delegates everything
+/// else to nih_plug_xtask, which provides the `bundle` subcommand. Usage:
 ///
 ///   cargo xtask bundle loveless-delay-v1 --release
+///   cargo xtask nulltest --baseline main
 ///
-/// This compiles the plugin as a cdylib and packages it into a .vst3
-/// bundle at `target/bundled/Loveless Delay.vst3`.
+/// `bundle` compiles the plugin as a cdylib and packages it into a .vst3
+/// bundle at `target/bundled/Loveless Delay.vst3`. `nulltest` checks
+/// whether the working tree still sounds like a git ref (see
+/// `nulltest.rs`).
 fn main() -> nih_plug_xtask::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("nulltest") {
+        if let Err(err) = nulltest::run(&args[1..]) {
+            eprintln!("nulltest: {err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     nih_plug_xtask::main()
 }
@@ -0,0 +1,249 @@
+//! # `nulltest`: Did My Change Change the Sound?
+//!
+//! ```text
+//! cargo xtask nulltest --baseline <git ref> [--threshold-db <dB>] [--expect-change]
+//! ```
+//!
+//! Renders the standard test program (`examples/null_render.rs`) from
+//! the working tree and from `<git ref>`, subtracts one from the other
+//! sample by sample, and reports the largest difference in each render,
+//! in dBFS. A refactor or an optimization that leaves the sound alone
+//! *nulls*: the difference stays below the threshold (−120 dBFS by
+//! default, well under anything audible but above the last-bit noise of
+//! reordered float math), and the check passes.
+//!
+//! A difference above the threshold fails the check, unless
+//! `--expect-change` says the sound was meant to change — then the
+//! report is just for reading.
+//!
+//! ## How the Baseline Is Built
+//!
+//! The ref is checked out into a git worktree under
+//! `target/nulltest/baseline`, and the working tree's render program is
+//! copied into it, so both sides play exactly the same program through
+//! their own engine. Each side builds in release mode with its own
+//! target directory, so neither throws away the other's build cache.
+//! The worktree stays for the next run; `git worktree prune` after a
+//! `cargo clean` tidies up.
+//!
+//! The baseline has to be able to build the render program: anything
+//! from the factory presets on.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The largest difference, in dBFS, that still counts as a null.
+const DEFAULT_THRESHOLD_DB: f64 = -120.0;
+
+/// The render program both sides run.
+const RENDER_EXAMPLE: &str = "null_render";
+
+/// What the command line asked for.
+struct Options {
+    baseline: String,
+    threshold_db: f64,
+    expect_change: bool,
+}
+
+/// Run the check. `args` are the ones after `nulltest`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_args(args)?;
+    let root = project_root();
+    let work = root.join("target").join("nulltest");
+
+    // Checked out first, so a mistyped ref fails before any building.
+    let checkout = work.join("baseline");
+    check_out(&root, &checkout, &options.baseline)?;
+
+    println!("Rendering the working tree...");
+    let current = work.join("current");
+    render(&root, &root.join("target"), &current)?;
+
+    println!("Rendering {}...", options.baseline);
+    let example = Path::new("examples").join(format!("{RENDER_EXAMPLE}.rs"));
+    fs::create_dir_all(checkout.join("examples")).map_err(|e| e.to_string())?;
+    fs::copy(root.join(&example), checkout.join(&example))
+        .map_err(|e| format!("can't copy the render program: {e}"))?;
+    let baseline = work.join("baseline-render");
+    render(&checkout, &work.join("baseline-target"), &baseline)?;
+
+    let worst = compare(&current, &baseline)?;
+    println!();
+    if worst <= options.threshold_db {
+        println!(
+            "Null: every render is within {} dBFS of {}.",
+            options.threshold_db, options.baseline
+        );
+        Ok(())
+    } else if options.expect_change {
+        println!(
+            "Changed from {} (expected): up to {}.",
+            options.baseline,
+            format_db(worst)
+        );
+        Ok(())
+    } else {
+        Err(format!(
+            "the sound changed from {}: up to {}, above the {} dBFS threshold \
+             (pass --expect-change if that's intended)",
+            options.baseline,
+            format_db(worst),
+            options.threshold_db
+        ))
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut baseline = None;
+    let mut threshold_db = DEFAULT_THRESHOLD_DB;
+    let mut expect_change = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baseline" => baseline = args.next().cloned(),
+            "--threshold-db" => {
+                threshold_db = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or("--threshold-db needs a number of dB")?;
+            }
+            "--expect-change" => expect_change = true,
+            other => return Err(format!("unknown argument {other:?}")),
+        }
+    }
+    let baseline = baseline
+        .ok_or("usage: nulltest --baseline <git ref> [--threshold-db <dB>] [--expect-change]")?;
+    Ok(Options {
+        baseline,
+        threshold_db,
+        expect_change,
+    })
+}
+
+/// The plugin's root directory, where the xtask lives.
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("the xtask sits inside the project")
+        .to_path_buf()
+}
+
+/// Run `command`, failing with what it was doing if it fails.
+fn run_command(command: &mut Command, doing: &str) -> Result<(), String> {
+    let status = command
+        .status()
+        .map_err(|e| format!("can't start {doing}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{doing} failed ({status})"))
+    }
+}
+
+/// Check `git_ref` out into a worktree at `checkout`, reusing the one a
+/// previous run left there.
+///
+/// The ref is resolved to a commit in the project first: inside the
+/// worktree, `HEAD` (and `HEAD~1`, and so on) would mean the worktree's
+/// own, left over from the last run.
+fn check_out(root: &Path, checkout: &Path, git_ref: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{git_ref}^{{commit}}"))
+        .output()
+        .map_err(|e| format!("can't start git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("{git_ref:?} isn't a commit"));
+    }
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let mut git = Command::new("git");
+    if checkout.join(".git").exists() {
+        git.current_dir(checkout)
+            .args(["checkout", "--quiet", "--force", "--detach"])
+            .arg(&commit);
+    } else {
+        git.current_dir(root)
+            .args(["worktree", "add", "--quiet", "--force", "--detach"])
+            .arg(checkout)
+            .arg(&commit);
+    }
+    run_command(&mut git, &format!("checking out {git_ref}"))
+}
+
+/// Render the program from the crate at `crate_dir` into `out_dir`.
+fn render(crate_dir: &Path, target_dir: &Path, out_dir: &Path) -> Result<(), String> {
+    if out_dir.exists() {
+        fs::remove_dir_all(out_dir).map_err(|e| e.to_string())?;
+    }
+    let mut cargo = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    cargo
+        .current_dir(crate_dir)
+        .args(["run", "--release", "--quiet", "--example", RENDER_EXAMPLE])
+        .arg("--target-dir")
+        .arg(target_dir)
+        .arg("--")
+        .arg(out_dir);
+    run_command(&mut cargo, &format!("rendering {}", crate_dir.display()))
+}
+
+/// Compare every render in `current` with the baseline's, print a line
+/// for each, and return the largest difference in dBFS. A render the
+/// baseline doesn't have, or of a different length, counts as a 0 dBFS
+/// difference, so it fails any sensible threshold.
+fn compare(current: &Path, baseline: &Path) -> Result<f64, String> {
+    let mut names: Vec<String> = fs::read_dir(current)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+
+    println!();
+    println!("{:<28}  max difference", "render");
+    let mut worst = f64::NEG_INFINITY;
+    for name in names {
+        let ours = read_samples(&current.join(&name))?;
+        let diff_db = match read_samples(&baseline.join(&name)) {
+            Ok(theirs) if theirs.len() == ours.len() => {
+                let peak = ours
+                    .iter()
+                    .zip(&theirs)
+                    .fold(0.0f64, |m, (a, b)| m.max(f64::from(a - b).abs()));
+                20.0 * peak.log10()
+            }
+            Ok(_) => {
+                println!("{name:<28}  different length");
+                worst = worst.max(0.0);
+                continue;
+            }
+            Err(_) => {
+                println!("{name:<28}  missing from the baseline");
+                worst = worst.max(0.0);
+                continue;
+            }
+        };
+        println!("{name:<28}  {}", format_db(diff_db));
+        worst = worst.max(diff_db);
+    }
+    Ok(worst)
+}
+
+/// A render's samples, from interleaved little-endian `f32`.
+fn read_samples(path: &Path) -> Result<Vec<f32>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// A difference in dBFS, with silence spelled out.
+fn format_db(db: f64) -> String {
+    if db == f64::NEG_INFINITY {
+        "identical".to_string()
+    } else {
+        format!("{db:.1} dBFS")
+    }
+}